@@ -0,0 +1,33 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Encryption of transaction payloads at rest.
+
+use crate::error::InternalError;
+
+/// Encrypts transaction payloads before a store writes them, and decrypts
+/// them when the store reads them back
+///
+/// A store configured with a cipher encrypts the payloads of the
+/// transactions it adds and records that they are encrypted, so payloads
+/// written before the cipher was configured are still read as is. Only the
+/// transaction payloads are encrypted; the serialized batch, which is
+/// submitted as is, is stored unchanged.
+pub trait PayloadCipher: Send + Sync {
+    /// Returns the encrypted form of a transaction payload
+    fn encrypt(&self, payload: &[u8]) -> Result<Vec<u8>, InternalError>;
+
+    /// Returns the transaction payload from its encrypted form
+    fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>, InternalError>;
+}
@@ -14,35 +14,143 @@
 
 pub mod models;
 mod operations;
+mod recent_writes;
 pub(crate) mod schema;
 
-use diesel::connection::AnsiTransactionManager;
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Write};
+use std::sync::Arc;
+use std::time::Duration;
+
+use diesel::connection::{AnsiTransactionManager, TransactionManager};
 use diesel::r2d2::{ConnectionManager, Pool};
+use transact::protocol::batch::Batch;
 
 use super::{
-    BatchStatus, BatchStatusName, BatchTrackingStore, BatchTrackingStoreError, InvalidTransaction,
-    SubmissionError, TrackingBatch, TrackingBatchList, TrackingTransaction, TransactionReceipt,
-    ValidTransaction,
+    BatchProjection, BatchStatus, BatchStatusEvent, BatchStatusName, BatchSubmissionInfo,
+    BatchTrackingStore, BatchTrackingStoreError, FailedBatchDetail, FailureSummary, HealthSnapshot,
+    InvalidTransaction, OutboxEvent, PayloadCipher, PoolState, SubmissionError, TimestampPrecision,
+    TrackingBatch, TrackingBatchList, TrackingBatchPage, TrackingTransaction, TransactionReceipt,
+    TryAddBatchesOutcomes, UnsubmittedBatchReceiver, UnsubmittedWatchers, ValidTransaction,
+    WatchBackpressure, NON_SPLINTER_SERVICE_ID_DEFAULT,
 };
 
-use crate::error::ResourceTemporarilyUnavailableError;
+use crate::error::{InternalError, InvalidArgumentError, ResourceTemporarilyUnavailableError};
 
 use models::{NewBatchStatusModel, NewSubmissionModel, TransactionReceiptModel};
+use operations::ack_outbox::BatchTrackingStoreAckOutboxOperation as _;
 use operations::add_batches::BatchTrackingStoreAddBatchesOperation as _;
+use operations::add_transact_batches::BatchTrackingStoreAddTransactBatchesOperation as _;
+use operations::all_statuses_for_service::BatchTrackingStoreAllStatusesForServiceOperation as _;
+use operations::average_submission_latency::BatchTrackingStoreAverageSubmissionLatencyOperation as _;
+use operations::backup_service::BatchTrackingStoreBackupServiceOperation as _;
 use operations::change_batch_to_submitted::BatchTrackingStoreChangeBatchToSubmittedOperation as _;
+use operations::claim_unsubmitted_batches::BatchTrackingStoreClaimUnsubmittedBatchesOperation as _;
 use operations::clean_stale_records::BatchTrackingCleanStaleRecordsOperation as _;
+use operations::commit_batch::BatchTrackingStoreCommitBatchOperation as _;
+use operations::compact::BatchTrackingStoreCompactOperation as _;
+use operations::content_digest::BatchTrackingStoreContentDigestOperation as _;
+use operations::count_batches_by_origin::BatchTrackingStoreCountBatchesByOriginOperation as _;
+use operations::count_transactions::BatchTrackingStoreCountTransactionsOperation as _;
+use operations::created_at_bounds::BatchTrackingStoreCreatedAtBoundsOperation as _;
+use operations::dead_letter_batch::BatchTrackingStoreDeadLetterBatchOperation as _;
+use operations::dead_letter_batches::BatchTrackingStoreDeadLetterBatchesOperation as _;
+use operations::dedupe_receipts::BatchTrackingStoreDedupeReceiptsOperation as _;
+use operations::failed_batches_cursor::BatchTrackingStoreFailedBatchesCursorOperation as _;
+use operations::fetch_outbox::BatchTrackingStoreFetchOutboxOperation as _;
+use operations::find_batches_by_transaction_prefix::BatchTrackingStoreFindBatchesByTransactionPrefixOperation as _;
+use operations::find_batches_with_excess_receipts::BatchTrackingStoreFindBatchesWithExcessReceiptsOperation as _;
+use operations::find_batches_without_transactions::BatchTrackingStoreFindBatchesWithoutTransactionsOperation as _;
+use operations::find_committed_batches_missing_receipts::BatchTrackingStoreFindCommittedBatchesMissingReceiptsOperation as _;
+use operations::find_duplicate_dcids::BatchTrackingStoreFindDuplicateDcidsOperation as _;
+use operations::find_flapping_batches::BatchTrackingStoreFindFlappingBatchesOperation as _;
 use operations::get_batch::BatchTrackingStoreGetBatchOperation as _;
+use operations::get_batch_by_alias::BatchTrackingStoreGetBatchByAliasOperation as _;
+use operations::get_batch_by_transaction_id::BatchTrackingStoreGetBatchByTransactionIdOperation as _;
+use operations::get_batch_projected::BatchTrackingStoreGetBatchProjectedOperation as _;
 use operations::get_batch_status::BatchTrackingStoreGetBatchStatusOperation as _;
+use operations::get_batch_status_at::BatchTrackingStoreGetBatchStatusAtOperation as _;
+use operations::get_batch_submission_info::BatchTrackingStoreGetBatchSubmissionInfoOperation as _;
+use operations::get_batches_awaiting_receipts::BatchTrackingStoreGetBatchesAwaitingReceiptsOperation as _;
+use operations::get_batches_by_data_change_ids::BatchTrackingStoreGetBatchesByDataChangeIdsOperation as _;
+use operations::get_dead_lettered_batches::BatchTrackingStoreGetDeadLetteredBatchesOperation as _;
 use operations::get_failed_batches::BatchTrackingStoreGetFailedBatchesOperation as _;
+use operations::get_latest_submission_error::BatchTrackingStoreGetLatestSubmissionErrorOperation as _;
+use operations::get_recent_failures::BatchTrackingStoreGetRecentFailuresOperation as _;
 use operations::get_unsubmitted_batches::BatchTrackingStoreGetUnsubmittedBatchesOperation as _;
+use operations::get_unsubmitted_batches_limited::BatchTrackingStoreGetUnsubmittedBatchesLimitedOperation as _;
+use operations::has_unsubmitted_batches::BatchTrackingStoreHasUnsubmittedBatchesOperation as _;
+use operations::health_snapshot::BatchTrackingStoreHealthSnapshotOperation as _;
+use operations::hourly_batch_counts::BatchTrackingStoreHourlyBatchCountsOperation as _;
+use operations::id_status_page::BatchTrackingStoreIdStatusPageOperation as _;
+use operations::list_batch_status_events::BatchTrackingStoreListBatchStatusEventsOperation as _;
+use operations::list_batches::BatchTrackingStoreListBatchesOperation as _;
+use operations::list_batches_by_attempts::BatchTrackingStoreListBatchesByAttemptsOperation as _;
+use operations::list_batches_by_family::BatchTrackingStoreListBatchesByFamilyOperation as _;
+use operations::list_batches_by_kind::BatchTrackingStoreListBatchesByKindOperation as _;
+use operations::list_batches_by_labels::BatchTrackingStoreListBatchesByLabelsOperation as _;
+use operations::list_batches_by_network::BatchTrackingStoreListBatchesByNetworkOperation as _;
+use operations::list_batches_by_round::BatchTrackingStoreListBatchesByRoundOperation as _;
+use operations::list_batches_by_state_address::BatchTrackingStoreListBatchesByStateAddressOperation as _;
 use operations::list_batches_by_status::BatchTrackingStoreListBatchesByStatusOperation as _;
+use operations::list_batches_by_status_with_total::BatchTrackingStoreListBatchesByStatusWithTotalOperation as _;
+use operations::list_batches_by_submit_url::BatchTrackingStoreListBatchesBySubmitUrlOperation as _;
+use operations::list_batches_depending_on::BatchTrackingStoreListBatchesDependingOnOperation as _;
+use operations::list_batches_status_changed_between::BatchTrackingStoreListBatchesStatusChangedBetweenOperation as _;
+use operations::list_failed_batches_recent::BatchTrackingStoreListFailedBatchesRecentOperation as _;
+use operations::list_failure_summaries::BatchTrackingStoreListFailureSummariesOperation as _;
+use operations::metrics_text::BatchTrackingStoreMetricsTextOperation as _;
+use operations::normalize_status_values::BatchTrackingStoreNormalizeStatusValuesOperation as _;
+use operations::record_submission_attempt::BatchTrackingStoreRecordSubmissionAttemptOperation as _;
+use operations::remap_data_change_ids::BatchTrackingStoreRemapDataChangeIdsOperation as _;
+use operations::repair_missing_statuses::BatchTrackingStoreRepairMissingStatusesOperation as _;
+use operations::resolve_service_id::BatchTrackingStoreResolveServiceIdOperation as _;
+use operations::restore_service::BatchTrackingStoreRestoreServiceOperation as _;
+use operations::scrub_receipts::BatchTrackingStoreScrubReceiptsOperation as _;
+use operations::set_alias::BatchTrackingStoreSetAliasOperation as _;
+use operations::set_batch_notes::BatchTrackingStoreSetBatchNotesOperation as _;
+use operations::status_distribution_between::BatchTrackingStoreStatusDistributionBetweenOperation as _;
+use operations::store_receipts_only::BatchTrackingStoreStoreReceiptsOnlyOperation as _;
+use operations::submission_success_rate::BatchTrackingStoreSubmissionSuccessRateOperation as _;
+use operations::swap_batch_status::BatchTrackingStoreSwapBatchStatusOperation as _;
+use operations::sync_since::BatchTrackingStoreSyncSinceOperation as _;
+use operations::tombstone_batch::BatchTrackingStoreTombstoneBatchOperation as _;
+use operations::tombstone_batches::BatchTrackingStoreTombstoneBatchesOperation as _;
+use operations::total_bytes_by_service::BatchTrackingStoreTotalBytesByServiceOperation as _;
+use operations::transition_batches::BatchTrackingStoreTransitionBatchesOperation as _;
+use operations::try_add_batches::BatchTrackingStoreTryAddBatchesOperation as _;
 use operations::update_batch_status::BatchTrackingStoreUpdateBatchStatusOperation as _;
 use operations::BatchTrackingStoreOperations;
+use recent_writes::{written_batch_ids, RecentWrites};
+
+/// The number of times a bulk write is retried after a serialization failure
+/// unless the store is configured otherwise
+#[cfg(feature = "postgres")]
+const DEFAULT_SERIALIZATION_RETRIES: u32 = 3;
+
+/// Checks the format of a batch ID, returning why it is malformed if it is
+pub type BatchIdValidator = fn(&str) -> Result<(), String>;
 
 /// Manages batches in the database
 #[derive(Clone)]
 pub struct DieselBatchTrackingStore<C: diesel::Connection + 'static> {
     connection_pool: Pool<ConnectionManager<C>>,
+    read_pool: Pool<ConnectionManager<C>>,
+    timestamp_precision: TimestampPrecision,
+    ignore_duplicate_batches: bool,
+    case_insensitive_service_ids: bool,
+    allowed_batch_kinds: Option<Vec<String>>,
+    allowed_service_ids: Option<HashSet<String>>,
+    batch_id_validator: Option<BatchIdValidator>,
+    status_event_debounce: Duration,
+    recent_writes: Option<Arc<RecentWrites>>,
+    unsubmitted_watchers: Arc<UnsubmittedWatchers>,
+    #[cfg(feature = "postgres")]
+    schema: Option<String>,
+    #[cfg(feature = "postgres")]
+    serialization_retries: u32,
+    correlation_id: Option<String>,
+    payload_cipher: Option<Arc<dyn PayloadCipher>>,
 }
 
 impl<C: diesel::Connection> DieselBatchTrackingStore<C> {
@@ -51,9 +159,402 @@ impl<C: diesel::Connection> DieselBatchTrackingStore<C> {
     /// # Arguments
     ///
     ///  * `connection_pool`: connection pool to the database
-    #[allow(dead_code)]
     pub fn new(connection_pool: Pool<ConnectionManager<C>>) -> Self {
-        DieselBatchTrackingStore { connection_pool }
+        DieselBatchTrackingStore {
+            read_pool: connection_pool.clone(),
+            connection_pool,
+            timestamp_precision: TimestampPrecision::Seconds,
+            ignore_duplicate_batches: false,
+            case_insensitive_service_ids: false,
+            allowed_batch_kinds: None,
+            allowed_service_ids: None,
+            batch_id_validator: None,
+            status_event_debounce: Duration::from_secs(0),
+            recent_writes: None,
+            unsubmitted_watchers: Arc::new(UnsubmittedWatchers::default()),
+            #[cfg(feature = "postgres")]
+            schema: None,
+            #[cfg(feature = "postgres")]
+            serialization_retries: DEFAULT_SERIALIZATION_RETRIES,
+            correlation_id: None,
+            payload_cipher: None,
+        }
+    }
+
+    /// Creates a new DieselBatchTrackingStore that reads from a separate
+    /// connection pool, such as one connected to a replica
+    ///
+    /// Methods that only read from the database use the read pool, all other
+    /// methods use the write pool.
+    ///
+    /// # Arguments
+    ///
+    ///  * `write_pool`: connection pool to the primary database
+    ///  * `read_pool`: connection pool used for reads
+    pub fn with_read_pool(
+        write_pool: Pool<ConnectionManager<C>>,
+        read_pool: Pool<ConnectionManager<C>>,
+    ) -> Self {
+        DieselBatchTrackingStore {
+            connection_pool: write_pool,
+            read_pool,
+            timestamp_precision: TimestampPrecision::Seconds,
+            ignore_duplicate_batches: false,
+            case_insensitive_service_ids: false,
+            allowed_batch_kinds: None,
+            allowed_service_ids: None,
+            batch_id_validator: None,
+            status_event_debounce: Duration::from_secs(0),
+            recent_writes: None,
+            unsubmitted_watchers: Arc::new(UnsubmittedWatchers::default()),
+            #[cfg(feature = "postgres")]
+            schema: None,
+            #[cfg(feature = "postgres")]
+            serialization_retries: DEFAULT_SERIALIZATION_RETRIES,
+            correlation_id: None,
+            payload_cipher: None,
+        }
+    }
+
+    /// Sets the precision of the timestamps recorded by the store
+    ///
+    /// Timestamps are recorded in seconds by default. Millisecond precision
+    /// allows batches created within the same second to be distinguished.
+    ///
+    /// # Arguments
+    ///
+    ///  * `timestamp_precision`: the unit timestamps are recorded in
+    pub fn with_timestamp_precision(mut self, timestamp_precision: TimestampPrecision) -> Self {
+        self.timestamp_precision = timestamp_precision;
+        self
+    }
+
+    /// Sets whether `add_batches` skips batches that are already present
+    ///
+    /// By default, adding a batch that is already present fails the whole
+    /// call with a `ConstraintViolationError` and none of the batches are
+    /// added. When enabled, batches that are already present, including
+    /// those added concurrently by another caller, are skipped and the
+    /// remaining batches are added.
+    ///
+    /// # Arguments
+    ///
+    ///  * `ignore_duplicate_batches`: whether to skip batches already present
+    pub fn with_ignore_duplicate_batches(mut self, ignore_duplicate_batches: bool) -> Self {
+        self.ignore_duplicate_batches = ignore_duplicate_batches;
+        self
+    }
+
+    /// Sets whether service IDs are matched case-insensitively
+    ///
+    /// By default, service IDs are matched exactly. When enabled, methods
+    /// that take a service ID operate on the stored service ID that matches
+    /// it when compared case-insensitively. If several stored service IDs
+    /// differ only by case, the first in sort order is used.
+    ///
+    /// # Arguments
+    ///
+    ///  * `case_insensitive_service_ids`: whether to ignore case in service IDs
+    pub fn with_case_insensitive_service_ids(mut self, case_insensitive_service_ids: bool) -> Self {
+        self.case_insensitive_service_ids = case_insensitive_service_ids;
+        self
+    }
+
+    /// Sets the batch kinds that `add_batches` accepts
+    ///
+    /// By default, batches of any kind are accepted. When set, adding a batch
+    /// whose kind is not in `allowed_batch_kinds` fails the whole call with an
+    /// `InvalidArgumentError`. Batches without a kind are always accepted.
+    ///
+    /// # Arguments
+    ///
+    ///  * `allowed_batch_kinds`: the kinds of batch that can be added
+    pub fn with_allowed_batch_kinds(mut self, allowed_batch_kinds: Vec<String>) -> Self {
+        self.allowed_batch_kinds = Some(allowed_batch_kinds);
+        self
+    }
+
+    /// Sets the service IDs that the store accepts writes for
+    ///
+    /// By default, writes for any service are accepted. When set, a write to a
+    /// service that is not in `allowed_service_ids` fails with
+    /// `ServiceNotAllowed`, and adding batches fails the whole call if any
+    /// batch is for a service that is not allowed. Reads and maintenance
+    /// operations that are not scoped to a service are unaffected.
+    ///
+    /// # Arguments
+    ///
+    ///  * `allowed_service_ids`: the service IDs that can be written to
+    pub fn with_allowed_service_ids(mut self, allowed_service_ids: HashSet<String>) -> Self {
+        self.allowed_service_ids = Some(allowed_service_ids);
+        self
+    }
+
+    /// Sets the function that checks the format of the IDs of added batches
+    ///
+    /// By default, any batch ID is accepted. When set, adding batches fails
+    /// the whole call with `InvalidBatchId` if the validator rejects the ID
+    /// of any batch, and `try_add_batches` reports the error for each
+    /// rejected batch.
+    ///
+    /// # Arguments
+    ///
+    ///  * `batch_id_validator`: returns an error describing why a batch ID is
+    ///    malformed
+    pub fn with_batch_id_validator(mut self, batch_id_validator: BatchIdValidator) -> Self {
+        self.batch_id_validator = Some(batch_id_validator);
+        self
+    }
+
+    /// Sets how long repeated status events are collapsed for
+    ///
+    /// By default, an event is recorded each time a batch's status is set.
+    /// When set, setting a batch to the status of its last event within
+    /// `status_event_debounce` of that event records no new event, so a
+    /// batch whose status is set repeatedly does not flood the event table.
+    /// Changes to a different status are always recorded.
+    ///
+    /// # Arguments
+    ///
+    ///  * `status_event_debounce`: how long after an event identical events
+    ///    are skipped
+    pub fn with_status_event_debounce(mut self, status_event_debounce: Duration) -> Self {
+        self.status_event_debounce = status_event_debounce;
+        self
+    }
+
+    /// Sets the cipher used to encrypt transaction payloads at rest
+    ///
+    /// By default, payloads are stored as is. When set, the payloads of
+    /// added transactions are encrypted before they are stored, and
+    /// encrypted payloads are decrypted when batches are read. Payloads
+    /// stored before the cipher was set are still read as is.
+    ///
+    /// # Arguments
+    ///
+    ///  * `payload_cipher`: the cipher to encrypt and decrypt payloads with
+    pub fn with_payload_cipher(mut self, payload_cipher: Arc<dyn PayloadCipher>) -> Self {
+        self.payload_cipher = Some(payload_cipher);
+        self
+    }
+
+    /// Sets how long reads of a batch go to the write pool after the batch
+    /// is written
+    ///
+    /// By default, reads always use the read pool, so a batch read from a
+    /// lagging replica right after it is written may be missing or out of
+    /// date. When set, `get_batch`, `get_batch_status` and
+    /// `get_batch_projected` read a batch from the write pool for
+    /// `read_your_writes_window` after it was added or had its status set
+    /// through this store or a copy of it. Status changes are tracked by the
+    /// ID they were made with.
+    ///
+    /// # Arguments
+    ///
+    ///  * `read_your_writes_window`: how long after a write reads of the
+    ///    batch use the write pool
+    pub fn with_read_your_writes(mut self, read_your_writes_window: Duration) -> Self {
+        self.recent_writes = Some(Arc::new(RecentWrites::new(read_your_writes_window)));
+        self
+    }
+
+    /// Sets how many batches each `watch_unsubmitted` subscriber can hold and
+    /// what happens when a subscriber's channel is full
+    ///
+    /// By default, subscribers hold 64 batches and the oldest batch is
+    /// dropped when a new one arrives. With `WatchBackpressure::Block`,
+    /// `add_batches` waits until the subscriber has made room.
+    ///
+    /// # Arguments
+    ///
+    ///  * `capacity`: the number of batches each subscriber can hold
+    ///  * `backpressure`: what to do when a subscriber is full
+    pub fn with_watch_capacity(mut self, capacity: usize, backpressure: WatchBackpressure) -> Self {
+        self.unsubmitted_watchers = Arc::new(UnsubmittedWatchers::new(capacity, backpressure));
+        self
+    }
+
+    /// Sets the watchers the store sends unsubmitted batches to, so that
+    /// subscribers receive the batches added through any store sharing them
+    ///
+    /// # Arguments
+    ///
+    ///  * `unsubmitted_watchers`: the watchers to share
+    pub(crate) fn with_unsubmitted_watchers(
+        mut self,
+        unsubmitted_watchers: Arc<UnsubmittedWatchers>,
+    ) -> Self {
+        self.unsubmitted_watchers = unsubmitted_watchers;
+        self
+    }
+
+    /// Returns a copy of the store that tags what it does with a correlation
+    /// ID
+    ///
+    /// The correlation ID is included in the store's log messages and in the
+    /// status events recorded by the returned store. The copy shares this
+    /// store's connection pools and watchers.
+    ///
+    /// # Arguments
+    ///
+    ///  * `correlation_id`: the ID used to correlate the store's work with the
+    ///    caller's request
+    pub fn with_correlation_id(&self, correlation_id: &str) -> Self {
+        DieselBatchTrackingStore {
+            connection_pool: self.connection_pool.clone(),
+            read_pool: self.read_pool.clone(),
+            timestamp_precision: self.timestamp_precision,
+            ignore_duplicate_batches: self.ignore_duplicate_batches,
+            case_insensitive_service_ids: self.case_insensitive_service_ids,
+            allowed_batch_kinds: self.allowed_batch_kinds.clone(),
+            allowed_service_ids: self.allowed_service_ids.clone(),
+            batch_id_validator: self.batch_id_validator,
+            status_event_debounce: self.status_event_debounce,
+            recent_writes: self.recent_writes.clone(),
+            unsubmitted_watchers: Arc::clone(&self.unsubmitted_watchers),
+            #[cfg(feature = "postgres")]
+            schema: self.schema.clone(),
+            #[cfg(feature = "postgres")]
+            serialization_retries: self.serialization_retries,
+            correlation_id: Some(correlation_id.to_string()),
+            payload_cipher: self.payload_cipher.clone(),
+        }
+    }
+
+    /// Returns a store with this store's settings that runs its operations
+    /// on a connection provided by the caller
+    ///
+    /// Operations on the returned store run on `connection` instead of
+    /// checking out a connection from the pool, so a sequence of operations
+    /// can be run in a transaction managed by the caller and committed or
+    /// rolled back together. The returned store shares this store's
+    /// watchers.
+    ///
+    /// # Arguments
+    ///
+    ///  * `connection`: the connection to run the store's operations on
+    pub fn with_connection<'a>(
+        &self,
+        connection: &'a C,
+    ) -> DieselConnectionBatchTrackingStore<'a, C>
+    where
+        C: diesel::Connection<TransactionManager = AnsiTransactionManager> + 'static,
+        C::Backend: diesel::backend::UsesAnsiSavepointSyntax,
+    {
+        DieselConnectionBatchTrackingStore {
+            connection,
+            timestamp_precision: self.timestamp_precision,
+            ignore_duplicate_batches: self.ignore_duplicate_batches,
+            case_insensitive_service_ids: self.case_insensitive_service_ids,
+            allowed_batch_kinds: self.allowed_batch_kinds.clone(),
+            allowed_service_ids: self.allowed_service_ids.clone(),
+            batch_id_validator: self.batch_id_validator,
+            status_event_debounce: self.status_event_debounce,
+            unsubmitted_watchers: Arc::clone(&self.unsubmitted_watchers),
+            #[cfg(feature = "postgres")]
+            schema: self.schema.clone(),
+            #[cfg(feature = "postgres")]
+            serialization_retries: self.serialization_retries,
+            correlation_id: self.correlation_id.clone(),
+            payload_cipher: self.payload_cipher.clone(),
+        }
+    }
+}
+
+impl<C: diesel::Connection> DieselBatchTrackingStore<C> {
+    /// Records that a batch was written, if reads of recently written
+    /// batches go to the write pool
+    ///
+    /// Writes are recorded before they are made, so that a read racing with
+    /// a write is sent to the write pool too.
+    fn record_write(&self, service_id: &str, id: &str) {
+        if let Some(recent_writes) = &self.recent_writes {
+            recent_writes.record(vec![(service_id.to_string(), id.to_string())]);
+        }
+    }
+
+    /// Returns the pool to read a batch from
+    fn read_pool_for(&self, service_id: &str, id: &str) -> &Pool<ConnectionManager<C>> {
+        match &self.recent_writes {
+            Some(recent_writes) if recent_writes.contains(service_id, id) => &self.connection_pool,
+            _ => &self.read_pool,
+        }
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl DieselBatchTrackingStore<diesel::pg::PgConnection> {
+    /// Sets the postgres schema the batch tracking tables are in
+    ///
+    /// By default, the tables are found through the connection's search
+    /// path. When set, the schema is put on the search path for each
+    /// operation the store runs, so the tables can be kept in a schema other
+    /// than `public`.
+    ///
+    /// # Arguments
+    ///
+    ///  * `schema`: the name of the schema the tables are in
+    pub fn with_schema(mut self, schema: &str) -> Self {
+        self.schema = Some(schema.to_string());
+        self
+    }
+
+    /// Sets how many times a bulk write is retried after postgres fails to
+    /// serialize it with a concurrent transaction
+    ///
+    /// Under the `SERIALIZABLE` isolation level, a transaction that conflicts
+    /// with a concurrent one is aborted with a serialization failure and
+    /// succeeds if run again. The bulk writes (`add_batches`,
+    /// `add_transact_batches`, `remap_data_change_ids`,
+    /// `normalize_status_values`, `repair_missing_statuses`,
+    /// `transition_batches`, `dead_letter_batches`, `tombstone_batches`,
+    /// `claim_unsubmitted_batches`, `clean_stale_records` and `ack_outbox`)
+    /// are retried up to `serialization_retries` times when that happens,
+    /// unless they are run inside a transaction opened by the caller. Any
+    /// other error is returned without retrying. Defaults to 3.
+    ///
+    /// # Arguments
+    ///
+    ///  * `serialization_retries`: the number of times to retry a bulk write
+    pub fn with_serialization_retries(mut self, serialization_retries: u32) -> Self {
+        self.serialization_retries = serialization_retries;
+        self
+    }
+
+    /// Returns the service ID the store should use for the given service ID
+    fn resolve_service_id(&self, service_id: &str) -> Result<String, BatchTrackingStoreError> {
+        if !self.case_insensitive_service_ids {
+            return Ok(service_id.to_string());
+        }
+
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .resolve_service_id(service_id)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl DieselBatchTrackingStore<diesel::sqlite::SqliteConnection> {
+    /// Returns the service ID the store should use for the given service ID
+    fn resolve_service_id(&self, service_id: &str) -> Result<String, BatchTrackingStoreError> {
+        if !self.case_insensitive_service_ids {
+            return Ok(service_id.to_string());
+        }
+
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .resolve_service_id(service_id)
     }
 }
 
@@ -64,11 +565,17 @@ impl BatchTrackingStore for DieselBatchTrackingStore<diesel::pg::PgConnection> {
         id: &str,
         service_id: &str,
     ) -> Result<Option<BatchStatus>, BatchTrackingStoreError> {
-        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
-            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
-                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
-            )
-        })?)
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool_for(service_id, id).get().map_err(
+            |err| {
+                BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                    ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+                )
+            },
+        )?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
         .get_batch_status(id, service_id)
     }
 
@@ -80,6 +587,9 @@ impl BatchTrackingStore for DieselBatchTrackingStore<diesel::pg::PgConnection> {
         transaction_receipts: Vec<TransactionReceipt>,
         submission_error: Option<SubmissionError>,
     ) -> Result<(), BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        self.record_write(service_id, id);
         let rcpts: Vec<TransactionReceiptModel> = transaction_receipts
             .iter()
             .map(|t| TransactionReceiptModel::from((t, service_id)))
@@ -94,16 +604,40 @@ impl BatchTrackingStore for DieselBatchTrackingStore<diesel::pg::PgConnection> {
                 ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
             )
         })?)
+        .with_timestamp_precision(self.timestamp_precision)
+        .with_status_event_debounce(self.status_event_debounce)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
         .update_batch_status(id, service_id, batch_status, rcpts, submission_error)
     }
 
     fn add_batches(&self, batches: Vec<TrackingBatch>) -> Result<(), BatchTrackingStoreError> {
+        validate_batch_kinds(self.allowed_batch_kinds.as_deref(), &batches)?;
+        validate_batch_ids(self.batch_id_validator, &batches)?;
+        validate_batch_service_ids(self.allowed_service_ids.as_ref(), &batches)?;
+
+        if let Some(recent_writes) = &self.recent_writes {
+            recent_writes.record(written_batch_ids(&batches));
+        }
+
+        let watched = self.unsubmitted_watchers.watched(&batches);
+
         BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
             BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
                 ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
             )
         })?)
-        .add_batches(batches)
+        .with_timestamp_precision(self.timestamp_precision)
+        .with_schema(self.schema.as_deref())
+        .with_serialization_retries(self.serialization_retries)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .add_batches(batches, self.ignore_duplicate_batches)?;
+
+        self.unsubmitted_watchers.send(watched);
+
+        Ok(())
     }
 
     fn change_batch_to_submitted(
@@ -113,7 +647,15 @@ impl BatchTrackingStore for DieselBatchTrackingStore<diesel::pg::PgConnection> {
         transaction_receipts: Vec<TransactionReceipt>,
         dlt_status: Option<&str>,
         submission_error: Option<SubmissionError>,
+        submitter_response: Option<&[u8]>,
+        submit_headers: Option<&serde_json::Value>,
+        submit_url: Option<&str>,
+        submission_round: Option<i64>,
+        network_id: Option<&str>,
     ) -> Result<(), BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        self.record_write(service_id, batch_id);
         let mut batch_status = None;
 
         if let Some(ds) = dlt_status {
@@ -145,6 +687,11 @@ impl BatchTrackingStore for DieselBatchTrackingStore<diesel::pg::PgConnection> {
                 ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
             )
         })?)
+        .with_timestamp_precision(self.timestamp_precision)
+        .with_status_event_debounce(self.status_event_debounce)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
         .change_batch_to_submitted(
             batch_id,
             service_id,
@@ -154,6 +701,11 @@ impl BatchTrackingStore for DieselBatchTrackingStore<diesel::pg::PgConnection> {
                 .collect(),
             batch_status,
             submission,
+            submitter_response,
+            submit_headers,
+            submit_url,
+            submission_round,
+            network_id,
         )
     }
 
@@ -162,11 +714,17 @@ impl BatchTrackingStore for DieselBatchTrackingStore<diesel::pg::PgConnection> {
         id: &str,
         service_id: &str,
     ) -> Result<Option<TrackingBatch>, BatchTrackingStoreError> {
-        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
-            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
-                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
-            )
-        })?)
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool_for(service_id, id).get().map_err(
+            |err| {
+                BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                    ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+                )
+            },
+        )?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
         .get_batch(id, service_id)
     }
 
@@ -174,11 +732,14 @@ impl BatchTrackingStore for DieselBatchTrackingStore<diesel::pg::PgConnection> {
         &self,
         status: BatchStatus,
     ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
-        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
             BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
                 ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
             )
         })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
         .list_batches_by_status(&status.to_string())
     }
 
@@ -188,1142 +749,13467 @@ impl BatchTrackingStore for DieselBatchTrackingStore<diesel::pg::PgConnection> {
                 ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
             )
         })?)
+        .with_schema(self.schema.as_deref())
+        .with_serialization_retries(self.serialization_retries)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
         .clean_stale_records(submitted_by)
     }
 
     fn get_unsubmitted_batches(&self) -> Result<TrackingBatchList, BatchTrackingStoreError> {
-        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
             BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
                 ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
             )
         })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
         .get_unsubmitted_batches()
     }
 
     fn get_failed_batches(&self) -> Result<TrackingBatchList, BatchTrackingStoreError> {
-        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
             BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
                 ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
             )
         })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
         .get_failed_batches()
     }
-}
 
-#[cfg(feature = "sqlite")]
-impl BatchTrackingStore for DieselBatchTrackingStore<diesel::sqlite::SqliteConnection> {
-    fn get_batch_status(
-        &self,
-        id: &str,
-        service_id: &str,
-    ) -> Result<Option<BatchStatus>, BatchTrackingStoreError> {
+    fn tombstone_batch(&self, id: &str, service_id: &str) -> Result<(), BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
         BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
             BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
                 ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
             )
         })?)
-        .get_batch_status(id, service_id)
+        .with_timestamp_precision(self.timestamp_precision)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .tombstone_batch(id, service_id)
     }
 
-    fn update_batch_status(
+    fn get_recent_failures(
         &self,
-        id: &str,
         service_id: &str,
-        status: Option<BatchStatus>,
-        transaction_receipts: Vec<TransactionReceipt>,
-        submission_error: Option<SubmissionError>,
-    ) -> Result<(), BatchTrackingStoreError> {
-        let rcpts: Vec<TransactionReceiptModel> = transaction_receipts
-            .iter()
-            .map(|t| TransactionReceiptModel::from((t, service_id)))
-            .collect::<Vec<TransactionReceiptModel>>();
-
-        let stat = status.map(|s| s.to_string());
-
-        let batch_status: Option<&str> = stat.as_deref();
-
-        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+        limit: i64,
+    ) -> Result<Vec<FailedBatchDetail>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
             BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
                 ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
             )
         })?)
-        .update_batch_status(id, service_id, batch_status, rcpts, submission_error)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .get_recent_failures(service_id, limit)
     }
 
-    fn add_batches(&self, batches: Vec<TrackingBatch>) -> Result<(), BatchTrackingStoreError> {
+    fn compact(&self) -> Result<(), BatchTrackingStoreError> {
         BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
             BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
                 ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
             )
         })?)
-        .add_batches(batches)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .compact()
     }
 
-    fn change_batch_to_submitted(
+    fn status_distribution_between(
         &self,
-        batch_id: &str,
         service_id: &str,
-        transaction_receipts: Vec<TransactionReceipt>,
-        dlt_status: Option<&str>,
-        submission_error: Option<SubmissionError>,
-    ) -> Result<(), BatchTrackingStoreError> {
-        let mut batch_status = None;
-
-        if let Some(ds) = dlt_status {
-            batch_status = Some(NewBatchStatusModel {
-                batch_id: batch_id.to_string(),
-                service_id: service_id.to_string(),
-                dlt_status: ds.to_string(),
-            });
-        }
-
-        let mut submission = NewSubmissionModel {
-            batch_id: batch_id.to_string(),
-            service_id: service_id.to_string(),
-            error_type: None,
-            error_message: None,
-        };
-
-        if let Some(s) = submission_error {
-            submission = NewSubmissionModel {
-                batch_id: batch_id.to_string(),
-                service_id: service_id.to_string(),
-                error_type: Some(s.error_type().to_string()),
-                error_message: Some(s.error_message().to_string()),
-            };
-        }
-
-        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+        start: i64,
+        end: i64,
+    ) -> Result<HashMap<BatchStatusName, i64>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
             BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
                 ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
             )
         })?)
-        .change_batch_to_submitted(
-            batch_id,
-            service_id,
-            transaction_receipts
-                .iter()
-                .map(|r| TransactionReceiptModel::from((r, service_id)))
-                .collect(),
-            batch_status,
-            submission,
-        )
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .status_distribution_between(service_id, start, end)
     }
 
-    fn get_batch(
+    fn get_batch_submission_info(
         &self,
         id: &str,
         service_id: &str,
-    ) -> Result<Option<TrackingBatch>, BatchTrackingStoreError> {
-        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+    ) -> Result<Option<BatchSubmissionInfo>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
             BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
                 ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
             )
         })?)
-        .get_batch(id, service_id)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .get_batch_submission_info(id, service_id)
     }
 
-    fn list_batches_by_status(
+    fn store_receipts_only(
         &self,
-        status: BatchStatus,
-    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        id: &str,
+        service_id: &str,
+        transaction_receipts: Vec<TransactionReceipt>,
+    ) -> Result<(), BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        let rcpts: Vec<TransactionReceiptModel> = transaction_receipts
+            .iter()
+            .map(|t| TransactionReceiptModel::from((t, service_id)))
+            .collect();
+
         BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
             BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
                 ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
             )
         })?)
-        .list_batches_by_status(&status.to_string())
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .store_receipts_only(id, service_id, rcpts)
     }
 
-    fn clean_stale_records(&self, submitted_by: i64) -> Result<(), BatchTrackingStoreError> {
-        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+    fn find_committed_batches_missing_receipts(
+        &self,
+        service_id: &str,
+    ) -> Result<Vec<String>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
             BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
                 ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
             )
         })?)
-        .clean_stale_records(submitted_by)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .find_committed_batches_missing_receipts(service_id)
     }
 
-    fn get_unsubmitted_batches(&self) -> Result<TrackingBatchList, BatchTrackingStoreError> {
-        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+    fn list_batches_by_state_address(
+        &self,
+        address: &str,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
             BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
                 ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
             )
         })?)
-        .get_unsubmitted_batches()
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .list_batches_by_state_address(address, service_id)
     }
 
-    fn get_failed_batches(&self) -> Result<TrackingBatchList, BatchTrackingStoreError> {
-        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+    fn average_submission_latency(
+        &self,
+        service_id: &str,
+        since: i64,
+    ) -> Result<Option<i64>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
             BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
                 ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
             )
         })?)
-        .get_failed_batches()
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .average_submission_latency(service_id, since)
     }
-}
 
-pub struct DieselConnectionBatchTrackingStore<'a, C>
-where
-    C: diesel::Connection<TransactionManager = AnsiTransactionManager> + 'static,
-    C::Backend: diesel::backend::UsesAnsiSavepointSyntax,
-{
-    connection: &'a C,
-}
+    fn add_transact_batches(
+        &self,
+        batches: Vec<Batch>,
+        service_id: &str,
+    ) -> Result<(), BatchTrackingStoreError> {
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_timestamp_precision(self.timestamp_precision)
+        .with_schema(self.schema.as_deref())
+        .with_serialization_retries(self.serialization_retries)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .add_transact_batches(batches, service_id, self.ignore_duplicate_batches)
+    }
 
-impl<'a, C> DieselConnectionBatchTrackingStore<'a, C>
-where
-    C: diesel::Connection<TransactionManager = AnsiTransactionManager> + 'static,
-    C::Backend: diesel::backend::UsesAnsiSavepointSyntax,
-{
-    #[allow(dead_code)]
-    pub fn new(connection: &'a C) -> Self {
-        DieselConnectionBatchTrackingStore { connection }
+    fn watch_unsubmitted(&self, service_id: &str) -> UnsubmittedBatchReceiver {
+        self.unsubmitted_watchers.subscribe(service_id)
     }
-}
 
-#[cfg(feature = "postgres")]
-impl<'a> BatchTrackingStore for DieselConnectionBatchTrackingStore<'a, diesel::pg::PgConnection> {
-    fn get_batch_status(
+    fn record_submission_attempt(
         &self,
         id: &str,
         service_id: &str,
-    ) -> Result<Option<BatchStatus>, BatchTrackingStoreError> {
-        BatchTrackingStoreOperations::new(self.connection).get_batch_status(id, service_id)
+    ) -> Result<i64, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_timestamp_precision(self.timestamp_precision)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .record_submission_attempt(id, service_id)
     }
 
-    fn update_batch_status(
+    fn list_batches_by_status_with_total(
+        &self,
+        status: BatchStatus,
+        offset: i64,
+        limit: i64,
+    ) -> Result<TrackingBatchPage, BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .list_batches_by_status_with_total(&status.to_string(), offset, limit)
+    }
+
+    fn scrub_receipts(&self, id: &str, service_id: &str) -> Result<usize, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .scrub_receipts(id, service_id)
+    }
+
+    fn list_batches(&self, service_id: &str) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .list_batches(service_id)
+    }
+
+    fn pool_state(&self) -> PoolState {
+        let state = self.connection_pool.state();
+
+        PoolState::new(
+            state.connections,
+            state.idle_connections,
+            self.connection_pool.max_size(),
+        )
+    }
+
+    fn set_batch_notes(
         &self,
         id: &str,
         service_id: &str,
-        status: Option<BatchStatus>,
-        transaction_receipts: Vec<TransactionReceipt>,
-        submission_error: Option<SubmissionError>,
+        notes: Option<&str>,
     ) -> Result<(), BatchTrackingStoreError> {
-        let rcpts: Vec<TransactionReceiptModel> = transaction_receipts
-            .iter()
-            .map(|t| TransactionReceiptModel::from((t, service_id)))
-            .collect::<Vec<TransactionReceiptModel>>();
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
 
-        let stat = status.map(|s| s.to_string());
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .set_batch_notes(id, service_id, notes)
+    }
 
-        let batch_status: Option<&str> = stat.as_deref();
+    fn get_batch_by_transaction_id(
+        &self,
+        transaction_id: &str,
+        service_id: &str,
+    ) -> Result<Vec<TrackingBatch>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
 
-        BatchTrackingStoreOperations::new(self.connection).update_batch_status(
-            id,
-            service_id,
-            batch_status,
-            rcpts,
-            submission_error,
-        )
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .get_batch_by_transaction_id(transaction_id, service_id)
     }
 
-    fn add_batches(&self, batches: Vec<TrackingBatch>) -> Result<(), BatchTrackingStoreError> {
-        BatchTrackingStoreOperations::new(self.connection).add_batches(batches)
+    fn metrics_text(&self) -> Result<String, BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .metrics_text()
     }
 
-    fn change_batch_to_submitted(
+    fn list_batches_by_round(
         &self,
-        batch_id: &str,
+        round: i64,
         service_id: &str,
-        transaction_receipts: Vec<TransactionReceipt>,
-        dlt_status: Option<&str>,
-        submission_error: Option<SubmissionError>,
-    ) -> Result<(), BatchTrackingStoreError> {
-        let mut batch_status = None;
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
 
-        if let Some(ds) = dlt_status {
-            batch_status = Some(NewBatchStatusModel {
-                batch_id: batch_id.to_string(),
-                service_id: service_id.to_string(),
-                dlt_status: ds.to_string(),
-            });
-        }
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .list_batches_by_round(round, service_id)
+    }
 
-        let mut submission = NewSubmissionModel {
-            batch_id: batch_id.to_string(),
-            service_id: service_id.to_string(),
-            error_type: None,
-            error_message: None,
-        };
+    fn has_unsubmitted_batches(
+        &self,
+        service_id: Option<&str>,
+    ) -> Result<bool, BatchTrackingStoreError> {
+        let service_id = service_id
+            .map(|service_id| self.resolve_service_id(service_id))
+            .transpose()?;
 
-        if let Some(s) = submission_error {
-            submission = NewSubmissionModel {
-                batch_id: batch_id.to_string(),
-                service_id: service_id.to_string(),
-                error_type: Some(s.error_type().to_string()),
-                error_message: Some(s.error_message().to_string()),
-            };
-        }
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .has_unsubmitted_batches(service_id.as_deref())
+    }
 
-        BatchTrackingStoreOperations::new(self.connection).change_batch_to_submitted(
-            batch_id,
-            service_id,
-            transaction_receipts
-                .iter()
-                .map(|r| TransactionReceiptModel::from((r, service_id)))
-                .collect(),
-            batch_status,
-            submission,
-        )
+    fn normalize_status_values(&self) -> Result<usize, BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_serialization_retries(self.serialization_retries)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .normalize_status_values()
     }
 
-    fn get_batch(
+    fn list_batch_status_events(
         &self,
         id: &str,
         service_id: &str,
-    ) -> Result<Option<TrackingBatch>, BatchTrackingStoreError> {
-        BatchTrackingStoreOperations::new(self.connection).get_batch(id, service_id)
+    ) -> Result<Vec<BatchStatusEvent>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .list_batch_status_events(id, service_id)
     }
 
-    fn list_batches_by_status(
+    fn get_batches_by_data_change_ids(
         &self,
-        status: BatchStatus,
+        dcids: &[&str],
+        service_id: &str,
     ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
-        BatchTrackingStoreOperations::new(self.connection)
-            .list_batches_by_status(&status.to_string())
-    }
+        let service_id: &str = &self.resolve_service_id(service_id)?;
 
-    fn clean_stale_records(&self, submitted_by: i64) -> Result<(), BatchTrackingStoreError> {
-        BatchTrackingStoreOperations::new(self.connection).clean_stale_records(submitted_by)
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .get_batches_by_data_change_ids(dcids, service_id)
     }
 
-    fn get_unsubmitted_batches(&self) -> Result<TrackingBatchList, BatchTrackingStoreError> {
-        BatchTrackingStoreOperations::new(self.connection).get_unsubmitted_batches()
-    }
+    fn total_bytes_by_service(&self, service_id: &str) -> Result<i64, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
 
-    fn get_failed_batches(&self) -> Result<TrackingBatchList, BatchTrackingStoreError> {
-        BatchTrackingStoreOperations::new(self.connection).get_failed_batches()
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .total_bytes_by_service(service_id)
     }
-}
 
-#[cfg(feature = "sqlite")]
-impl<'a> BatchTrackingStore
-    for DieselConnectionBatchTrackingStore<'a, diesel::sqlite::SqliteConnection>
-{
-    fn get_batch_status(
+    fn list_batches_status_changed_between(
         &self,
-        id: &str,
         service_id: &str,
-    ) -> Result<Option<BatchStatus>, BatchTrackingStoreError> {
-        BatchTrackingStoreOperations::new(self.connection).get_batch_status(id, service_id)
+        start: i64,
+        end: i64,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .list_batches_status_changed_between(service_id, start, end)
     }
 
-    fn update_batch_status(
+    fn commit_batch(
         &self,
         id: &str,
         service_id: &str,
-        status: Option<BatchStatus>,
         transaction_receipts: Vec<TransactionReceipt>,
-        submission_error: Option<SubmissionError>,
     ) -> Result<(), BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
         let rcpts: Vec<TransactionReceiptModel> = transaction_receipts
             .iter()
-            .map(|t| TransactionReceiptModel::from((t, service_id)))
-            .collect::<Vec<TransactionReceiptModel>>();
-
-        let stat = status.map(|s| s.to_string());
-
-        let batch_status: Option<&str> = stat.as_deref();
-
-        BatchTrackingStoreOperations::new(self.connection).update_batch_status(
-            id,
-            service_id,
-            batch_status,
-            rcpts,
-            submission_error,
-        )
-    }
+            .map(|r| TransactionReceiptModel::from((r, service_id)))
+            .collect();
 
-    fn add_batches(&self, batches: Vec<TrackingBatch>) -> Result<(), BatchTrackingStoreError> {
-        BatchTrackingStoreOperations::new(self.connection).add_batches(batches)
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_timestamp_precision(self.timestamp_precision)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .commit_batch(id, service_id, rcpts)
     }
 
-    fn change_batch_to_submitted(
+    fn created_at_bounds(
         &self,
-        batch_id: &str,
         service_id: &str,
-        transaction_receipts: Vec<TransactionReceipt>,
-        dlt_status: Option<&str>,
-        submission_error: Option<SubmissionError>,
-    ) -> Result<(), BatchTrackingStoreError> {
-        let mut batch_status = None;
-
-        if let Some(ds) = dlt_status {
-            batch_status = Some(NewBatchStatusModel {
-                batch_id: batch_id.to_string(),
-                service_id: service_id.to_string(),
-                dlt_status: ds.to_string(),
-            });
-        }
-
-        let mut submission = NewSubmissionModel {
-            batch_id: batch_id.to_string(),
-            service_id: service_id.to_string(),
-            error_type: None,
-            error_message: None,
-        };
-
-        if let Some(s) = submission_error {
-            submission = NewSubmissionModel {
-                batch_id: batch_id.to_string(),
-                service_id: service_id.to_string(),
-                error_type: Some(s.error_type().to_string()),
-                error_message: Some(s.error_message().to_string()),
-            };
-        }
-
-        BatchTrackingStoreOperations::new(self.connection).change_batch_to_submitted(
-            batch_id,
-            service_id,
-            transaction_receipts
-                .iter()
-                .map(|r| TransactionReceiptModel::from((r, service_id)))
-                .collect(),
-            batch_status,
-            submission,
-        )
+    ) -> Result<Option<(i64, i64)>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .created_at_bounds(service_id)
     }
 
-    fn get_batch(
+    fn list_batches_by_network(
         &self,
-        id: &str,
+        network_id: &str,
         service_id: &str,
-    ) -> Result<Option<TrackingBatch>, BatchTrackingStoreError> {
-        BatchTrackingStoreOperations::new(self.connection).get_batch(id, service_id)
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .list_batches_by_network(network_id, service_id)
     }
 
-    fn list_batches_by_status(
+    fn find_flapping_batches(
         &self,
+        min_transitions: i64,
+        since: i64,
+    ) -> Result<Vec<String>, BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .find_flapping_batches(min_transitions, since)
+    }
+
+    fn sync_since(
+        &self,
+        service_id: &str,
+        checkpoint: Option<i64>,
+        limit: i64,
+    ) -> Result<(Vec<TrackingBatch>, Option<i64>), BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .sync_since(service_id, checkpoint, limit)
+    }
+
+    fn set_alias(
+        &self,
+        id: &str,
+        service_id: &str,
+        alias: Option<&str>,
+    ) -> Result<(), BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .set_alias(id, service_id, alias)
+    }
+
+    fn get_batch_by_alias(
+        &self,
+        alias: &str,
+        service_id: &str,
+    ) -> Result<Option<TrackingBatch>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .get_batch_by_alias(alias, service_id)
+    }
+
+    fn all_statuses_for_service(
+        &self,
+        service_id: &str,
+    ) -> Result<HashMap<String, BatchStatusName>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .all_statuses_for_service(service_id)
+    }
+
+    fn repair_missing_statuses(&self) -> Result<usize, BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_timestamp_precision(self.timestamp_precision)
+        .with_status_event_debounce(self.status_event_debounce)
+        .with_schema(self.schema.as_deref())
+        .with_serialization_retries(self.serialization_retries)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .repair_missing_statuses()
+    }
+
+    fn list_batches_by_attempts(
+        &self,
+        service_id: &str,
+        limit: i64,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .list_batches_by_attempts(service_id, limit)
+    }
+
+    fn count_transactions(&self, service_id: &str) -> Result<i64, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .count_transactions(service_id)
+    }
+
+    fn list_failed_batches_recent(
+        &self,
+        service_id: &str,
+        offset: i64,
+        limit: i64,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .list_failed_batches_recent(service_id, offset, limit)
+    }
+
+    fn list_batches_by_kind(
+        &self,
+        kind: &str,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .list_batches_by_kind(kind, service_id)
+    }
+
+    fn get_batch_projected(
+        &self,
+        id: &str,
+        service_id: &str,
+        projection: BatchProjection,
+    ) -> Result<Option<TrackingBatch>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool_for(service_id, id).get().map_err(
+            |err| {
+                BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                    ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+                )
+            },
+        )?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .get_batch_projected(id, service_id, projection)
+    }
+
+    fn find_batches_by_transaction_prefix(
+        &self,
+        prefix: &str,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .find_batches_by_transaction_prefix(prefix, service_id)
+    }
+
+    fn list_failure_summaries(
+        &self,
+        service_id: &str,
+        limit: i64,
+    ) -> Result<Vec<FailureSummary>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .list_failure_summaries(service_id, limit)
+    }
+
+    fn swap_batch_status(
+        &self,
+        id: &str,
+        service_id: &str,
         status: BatchStatus,
+    ) -> Result<Option<BatchStatus>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        self.record_write(service_id, id);
+        let status = status.to_string();
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_timestamp_precision(self.timestamp_precision)
+        .with_status_event_debounce(self.status_event_debounce)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .swap_batch_status(id, service_id, &status)
+    }
+
+    fn get_unsubmitted_batches_limited(
+        &self,
+        service_id: Option<&str>,
+        limit: i64,
     ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
-        BatchTrackingStoreOperations::new(self.connection)
-            .list_batches_by_status(&status.to_string())
+        let service_id = service_id
+            .map(|service_id| self.resolve_service_id(service_id))
+            .transpose()?;
+
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .get_unsubmitted_batches_limited(service_id.as_deref(), limit)
+    }
+
+    fn dead_letter_batch(
+        &self,
+        id: &str,
+        service_id: &str,
+        reason: &str,
+    ) -> Result<(), BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        self.record_write(service_id, id);
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_timestamp_precision(self.timestamp_precision)
+        .with_status_event_debounce(self.status_event_debounce)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .dead_letter_batch(id, service_id, reason)
+    }
+
+    fn get_dead_lettered_batches(
+        &self,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .get_dead_lettered_batches(service_id)
+    }
+
+    fn content_digest(&self, service_id: &str) -> Result<String, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .content_digest(service_id)
+    }
+
+    fn get_batches_awaiting_receipts(
+        &self,
+        older_than: i64,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .get_batches_awaiting_receipts(older_than, service_id)
+    }
+
+    fn failed_batches_cursor(
+        &self,
+        service_id: &str,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .failed_batches_cursor(service_id, after, limit)
+    }
+
+    fn remap_data_change_ids(
+        &self,
+        mapping: HashMap<String, String>,
+        service_id: &str,
+    ) -> Result<usize, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_serialization_retries(self.serialization_retries)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .remap_data_change_ids(&mapping, service_id)
+    }
+
+    fn list_batches_by_family(
+        &self,
+        family_name: &str,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .list_batches_by_family(family_name, service_id)
+    }
+
+    fn find_batches_with_excess_receipts(
+        &self,
+        service_id: &str,
+    ) -> Result<Vec<String>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .find_batches_with_excess_receipts(service_id)
+    }
+
+    fn dedupe_receipts(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<usize, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .dedupe_receipts(id, service_id)
+    }
+
+    fn get_batch_status_at(
+        &self,
+        id: &str,
+        service_id: &str,
+        at: i64,
+    ) -> Result<Option<BatchStatusName>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .get_batch_status_at(id, service_id, at)
+    }
+
+    fn try_add_batches(
+        &self,
+        batches: Vec<TrackingBatch>,
+    ) -> Result<TryAddBatchesOutcomes, BatchTrackingStoreError> {
+        let mut watched = self.unsubmitted_watchers.watched(&batches);
+
+        let outcomes = try_add_checked(
+            self.allowed_batch_kinds.as_deref(),
+            self.allowed_service_ids.as_ref(),
+            self.batch_id_validator,
+            batches,
+            |batches| {
+                if let Some(recent_writes) = &self.recent_writes {
+                    recent_writes.record(written_batch_ids(&batches));
+                }
+
+                BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+                    BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                        ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+                    )
+                })?)
+                .with_timestamp_precision(self.timestamp_precision)
+                .with_schema(self.schema.as_deref())
+                .with_correlation_id(self.correlation_id.as_deref())
+                .with_payload_cipher(self.payload_cipher.as_deref())
+                .try_add_batches(batches, self.ignore_duplicate_batches)
+            },
+        )?;
+
+        watched.retain(|batch| is_added(&outcomes, batch));
+        self.unsubmitted_watchers.send(watched);
+
+        Ok(outcomes)
+    }
+
+    fn list_batches_by_submit_url(
+        &self,
+        submit_url: &str,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .list_batches_by_submit_url(submit_url, service_id)
+    }
+
+    fn find_duplicate_dcids(
+        &self,
+        service_id: &str,
+    ) -> Result<Vec<(String, Vec<String>)>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .find_duplicate_dcids(service_id)
+    }
+
+    fn count_batches_by_origin(
+        &self,
+        service_id: &str,
+        since: i64,
+    ) -> Result<HashMap<String, i64>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .count_batches_by_origin(service_id, since)
+    }
+
+    fn id_status_page(
+        &self,
+        service_id: &str,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<(String, BatchStatusName)>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .id_status_page(service_id, after, limit)
     }
 
-    fn clean_stale_records(&self, submitted_by: i64) -> Result<(), BatchTrackingStoreError> {
-        BatchTrackingStoreOperations::new(self.connection).clean_stale_records(submitted_by)
+    fn list_batches_depending_on(
+        &self,
+        transaction_id: &str,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .list_batches_depending_on(transaction_id, service_id)
+    }
+
+    fn tombstone_batches(
+        &self,
+        ids: &[&str],
+        service_id: &str,
+    ) -> Result<usize, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_serialization_retries(self.serialization_retries)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .tombstone_batches(ids, service_id)
+    }
+
+    fn submission_success_rate(
+        &self,
+        service_id: &str,
+        start: i64,
+        end: i64,
+    ) -> Result<f64, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .submission_success_rate(service_id, start, end)
+    }
+
+    fn find_batches_without_transactions(
+        &self,
+        service_id: &str,
+    ) -> Result<Vec<String>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .find_batches_without_transactions(service_id)
+    }
+
+    fn claim_unsubmitted_batches(
+        &self,
+        service_id: &str,
+        claimant: &str,
+        n: i64,
+        lease_secs: u64,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_serialization_retries(self.serialization_retries)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .claim_unsubmitted_batches(service_id, claimant, n, lease_secs)
+    }
+
+    fn health_snapshot(
+        &self,
+        service_id: &str,
+        stuck_threshold: i64,
+    ) -> Result<HealthSnapshot, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .health_snapshot(service_id, stuck_threshold)
+    }
+
+    fn hourly_batch_counts(
+        &self,
+        service_id: &str,
+        start: i64,
+        end: i64,
+    ) -> Result<Vec<(i64, i64)>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_timestamp_precision(self.timestamp_precision)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .hourly_batch_counts(service_id, start, end)
+    }
+
+    fn fetch_outbox(&self, limit: i64) -> Result<Vec<OutboxEvent>, BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .fetch_outbox(limit)
+    }
+
+    fn ack_outbox(&self, ids: &[i64]) -> Result<usize, BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_timestamp_precision(self.timestamp_precision)
+        .with_schema(self.schema.as_deref())
+        .with_serialization_retries(self.serialization_retries)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .ack_outbox(ids)
+    }
+
+    fn dead_letter_batches(
+        &self,
+        ids: &[&str],
+        service_id: &str,
+        reason: &str,
+    ) -> Result<usize, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        for id in ids {
+            self.record_write(service_id, id);
+        }
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_timestamp_precision(self.timestamp_precision)
+        .with_status_event_debounce(self.status_event_debounce)
+        .with_schema(self.schema.as_deref())
+        .with_serialization_retries(self.serialization_retries)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .dead_letter_batches(ids, service_id, reason)
+    }
+
+    fn list_batches_by_labels(
+        &self,
+        predicates: &[(String, String)],
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .list_batches_by_labels(predicates, service_id)
+    }
+
+    fn get_latest_submission_error(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<Option<SubmissionError>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .get_latest_submission_error(id, service_id)
+    }
+
+    fn transition_batches(
+        &self,
+        transitions: Vec<(String, BatchStatus)>,
+        service_id: &str,
+    ) -> Result<(), BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        for (id, _) in &transitions {
+            self.record_write(service_id, id);
+        }
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_timestamp_precision(self.timestamp_precision)
+        .with_status_event_debounce(self.status_event_debounce)
+        .with_schema(self.schema.as_deref())
+        .with_serialization_retries(self.serialization_retries)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .transition_batches(transitions, service_id)
+    }
+
+    fn write_service_backup(
+        &self,
+        service_id: &str,
+        writer: &mut dyn Write,
+    ) -> Result<(), BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .backup_service(service_id, writer)
+    }
+
+    fn read_service_backup(&self, reader: &mut dyn Read) -> Result<(), BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_timestamp_precision(self.timestamp_precision)
+        .with_schema(self.schema.as_deref())
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .restore_service(reader, self.allowed_service_ids.as_ref())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl BatchTrackingStore for DieselBatchTrackingStore<diesel::sqlite::SqliteConnection> {
+    fn get_batch_status(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<Option<BatchStatus>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool_for(service_id, id).get().map_err(
+            |err| {
+                BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                    ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+                )
+            },
+        )?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .get_batch_status(id, service_id)
+    }
+
+    fn update_batch_status(
+        &self,
+        id: &str,
+        service_id: &str,
+        status: Option<BatchStatus>,
+        transaction_receipts: Vec<TransactionReceipt>,
+        submission_error: Option<SubmissionError>,
+    ) -> Result<(), BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        self.record_write(service_id, id);
+        let rcpts: Vec<TransactionReceiptModel> = transaction_receipts
+            .iter()
+            .map(|t| TransactionReceiptModel::from((t, service_id)))
+            .collect::<Vec<TransactionReceiptModel>>();
+
+        let stat = status.map(|s| s.to_string());
+
+        let batch_status: Option<&str> = stat.as_deref();
+
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_timestamp_precision(self.timestamp_precision)
+        .with_status_event_debounce(self.status_event_debounce)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .update_batch_status(id, service_id, batch_status, rcpts, submission_error)
+    }
+
+    fn add_batches(&self, batches: Vec<TrackingBatch>) -> Result<(), BatchTrackingStoreError> {
+        validate_batch_kinds(self.allowed_batch_kinds.as_deref(), &batches)?;
+        validate_batch_ids(self.batch_id_validator, &batches)?;
+        validate_batch_service_ids(self.allowed_service_ids.as_ref(), &batches)?;
+
+        if let Some(recent_writes) = &self.recent_writes {
+            recent_writes.record(written_batch_ids(&batches));
+        }
+
+        let watched = self.unsubmitted_watchers.watched(&batches);
+
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_timestamp_precision(self.timestamp_precision)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .add_batches(batches, self.ignore_duplicate_batches)?;
+
+        self.unsubmitted_watchers.send(watched);
+
+        Ok(())
+    }
+
+    fn change_batch_to_submitted(
+        &self,
+        batch_id: &str,
+        service_id: &str,
+        transaction_receipts: Vec<TransactionReceipt>,
+        dlt_status: Option<&str>,
+        submission_error: Option<SubmissionError>,
+        submitter_response: Option<&[u8]>,
+        submit_headers: Option<&serde_json::Value>,
+        submit_url: Option<&str>,
+        submission_round: Option<i64>,
+        network_id: Option<&str>,
+    ) -> Result<(), BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        self.record_write(service_id, batch_id);
+        let mut batch_status = None;
+
+        if let Some(ds) = dlt_status {
+            batch_status = Some(NewBatchStatusModel {
+                batch_id: batch_id.to_string(),
+                service_id: service_id.to_string(),
+                dlt_status: ds.to_string(),
+            });
+        }
+
+        let mut submission = NewSubmissionModel {
+            batch_id: batch_id.to_string(),
+            service_id: service_id.to_string(),
+            error_type: None,
+            error_message: None,
+        };
+
+        if let Some(s) = submission_error {
+            submission = NewSubmissionModel {
+                batch_id: batch_id.to_string(),
+                service_id: service_id.to_string(),
+                error_type: Some(s.error_type().to_string()),
+                error_message: Some(s.error_message().to_string()),
+            };
+        }
+
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_timestamp_precision(self.timestamp_precision)
+        .with_status_event_debounce(self.status_event_debounce)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .change_batch_to_submitted(
+            batch_id,
+            service_id,
+            transaction_receipts
+                .iter()
+                .map(|r| TransactionReceiptModel::from((r, service_id)))
+                .collect(),
+            batch_status,
+            submission,
+            submitter_response,
+            submit_headers,
+            submit_url,
+            submission_round,
+            network_id,
+        )
+    }
+
+    fn get_batch(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<Option<TrackingBatch>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool_for(service_id, id).get().map_err(
+            |err| {
+                BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                    ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+                )
+            },
+        )?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .get_batch(id, service_id)
+    }
+
+    fn list_batches_by_status(
+        &self,
+        status: BatchStatus,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .list_batches_by_status(&status.to_string())
+    }
+
+    fn clean_stale_records(&self, submitted_by: i64) -> Result<(), BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .clean_stale_records(submitted_by)
+    }
+
+    fn get_unsubmitted_batches(&self) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .get_unsubmitted_batches()
+    }
+
+    fn get_failed_batches(&self) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .get_failed_batches()
+    }
+
+    fn tombstone_batch(&self, id: &str, service_id: &str) -> Result<(), BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_timestamp_precision(self.timestamp_precision)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .tombstone_batch(id, service_id)
+    }
+
+    fn get_recent_failures(
+        &self,
+        service_id: &str,
+        limit: i64,
+    ) -> Result<Vec<FailedBatchDetail>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .get_recent_failures(service_id, limit)
+    }
+
+    fn compact(&self) -> Result<(), BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .compact()
+    }
+
+    fn status_distribution_between(
+        &self,
+        service_id: &str,
+        start: i64,
+        end: i64,
+    ) -> Result<HashMap<BatchStatusName, i64>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .status_distribution_between(service_id, start, end)
+    }
+
+    fn get_batch_submission_info(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<Option<BatchSubmissionInfo>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .get_batch_submission_info(id, service_id)
+    }
+
+    fn store_receipts_only(
+        &self,
+        id: &str,
+        service_id: &str,
+        transaction_receipts: Vec<TransactionReceipt>,
+    ) -> Result<(), BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        let rcpts: Vec<TransactionReceiptModel> = transaction_receipts
+            .iter()
+            .map(|t| TransactionReceiptModel::from((t, service_id)))
+            .collect();
+
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .store_receipts_only(id, service_id, rcpts)
+    }
+
+    fn find_committed_batches_missing_receipts(
+        &self,
+        service_id: &str,
+    ) -> Result<Vec<String>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .find_committed_batches_missing_receipts(service_id)
+    }
+
+    fn list_batches_by_state_address(
+        &self,
+        address: &str,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .list_batches_by_state_address(address, service_id)
+    }
+
+    fn average_submission_latency(
+        &self,
+        service_id: &str,
+        since: i64,
+    ) -> Result<Option<i64>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .average_submission_latency(service_id, since)
+    }
+
+    fn add_transact_batches(
+        &self,
+        batches: Vec<Batch>,
+        service_id: &str,
+    ) -> Result<(), BatchTrackingStoreError> {
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_timestamp_precision(self.timestamp_precision)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .add_transact_batches(batches, service_id, self.ignore_duplicate_batches)
+    }
+
+    fn watch_unsubmitted(&self, service_id: &str) -> UnsubmittedBatchReceiver {
+        self.unsubmitted_watchers.subscribe(service_id)
+    }
+
+    fn record_submission_attempt(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<i64, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_timestamp_precision(self.timestamp_precision)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .record_submission_attempt(id, service_id)
+    }
+
+    fn list_batches_by_status_with_total(
+        &self,
+        status: BatchStatus,
+        offset: i64,
+        limit: i64,
+    ) -> Result<TrackingBatchPage, BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .list_batches_by_status_with_total(&status.to_string(), offset, limit)
+    }
+
+    fn scrub_receipts(&self, id: &str, service_id: &str) -> Result<usize, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .scrub_receipts(id, service_id)
+    }
+
+    fn list_batches(&self, service_id: &str) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .list_batches(service_id)
+    }
+
+    fn pool_state(&self) -> PoolState {
+        let state = self.connection_pool.state();
+
+        PoolState::new(
+            state.connections,
+            state.idle_connections,
+            self.connection_pool.max_size(),
+        )
+    }
+
+    fn set_batch_notes(
+        &self,
+        id: &str,
+        service_id: &str,
+        notes: Option<&str>,
+    ) -> Result<(), BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .set_batch_notes(id, service_id, notes)
+    }
+
+    fn get_batch_by_transaction_id(
+        &self,
+        transaction_id: &str,
+        service_id: &str,
+    ) -> Result<Vec<TrackingBatch>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .get_batch_by_transaction_id(transaction_id, service_id)
+    }
+
+    fn metrics_text(&self) -> Result<String, BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .metrics_text()
+    }
+
+    fn list_batches_by_round(
+        &self,
+        round: i64,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .list_batches_by_round(round, service_id)
+    }
+
+    fn has_unsubmitted_batches(
+        &self,
+        service_id: Option<&str>,
+    ) -> Result<bool, BatchTrackingStoreError> {
+        let service_id = service_id
+            .map(|service_id| self.resolve_service_id(service_id))
+            .transpose()?;
+
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .has_unsubmitted_batches(service_id.as_deref())
+    }
+
+    fn normalize_status_values(&self) -> Result<usize, BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .normalize_status_values()
+    }
+
+    fn list_batch_status_events(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<Vec<BatchStatusEvent>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .list_batch_status_events(id, service_id)
+    }
+
+    fn get_batches_by_data_change_ids(
+        &self,
+        dcids: &[&str],
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .get_batches_by_data_change_ids(dcids, service_id)
+    }
+
+    fn total_bytes_by_service(&self, service_id: &str) -> Result<i64, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .total_bytes_by_service(service_id)
+    }
+
+    fn list_batches_status_changed_between(
+        &self,
+        service_id: &str,
+        start: i64,
+        end: i64,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .list_batches_status_changed_between(service_id, start, end)
+    }
+
+    fn commit_batch(
+        &self,
+        id: &str,
+        service_id: &str,
+        transaction_receipts: Vec<TransactionReceipt>,
+    ) -> Result<(), BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        let rcpts: Vec<TransactionReceiptModel> = transaction_receipts
+            .iter()
+            .map(|r| TransactionReceiptModel::from((r, service_id)))
+            .collect();
+
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_timestamp_precision(self.timestamp_precision)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .commit_batch(id, service_id, rcpts)
+    }
+
+    fn created_at_bounds(
+        &self,
+        service_id: &str,
+    ) -> Result<Option<(i64, i64)>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .created_at_bounds(service_id)
+    }
+
+    fn list_batches_by_network(
+        &self,
+        network_id: &str,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .list_batches_by_network(network_id, service_id)
+    }
+
+    fn find_flapping_batches(
+        &self,
+        min_transitions: i64,
+        since: i64,
+    ) -> Result<Vec<String>, BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .find_flapping_batches(min_transitions, since)
+    }
+
+    fn sync_since(
+        &self,
+        service_id: &str,
+        checkpoint: Option<i64>,
+        limit: i64,
+    ) -> Result<(Vec<TrackingBatch>, Option<i64>), BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .sync_since(service_id, checkpoint, limit)
+    }
+
+    fn set_alias(
+        &self,
+        id: &str,
+        service_id: &str,
+        alias: Option<&str>,
+    ) -> Result<(), BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .set_alias(id, service_id, alias)
+    }
+
+    fn get_batch_by_alias(
+        &self,
+        alias: &str,
+        service_id: &str,
+    ) -> Result<Option<TrackingBatch>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .get_batch_by_alias(alias, service_id)
+    }
+
+    fn all_statuses_for_service(
+        &self,
+        service_id: &str,
+    ) -> Result<HashMap<String, BatchStatusName>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .all_statuses_for_service(service_id)
+    }
+
+    fn repair_missing_statuses(&self) -> Result<usize, BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_timestamp_precision(self.timestamp_precision)
+        .with_status_event_debounce(self.status_event_debounce)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .repair_missing_statuses()
+    }
+
+    fn list_batches_by_attempts(
+        &self,
+        service_id: &str,
+        limit: i64,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .list_batches_by_attempts(service_id, limit)
+    }
+
+    fn count_transactions(&self, service_id: &str) -> Result<i64, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .count_transactions(service_id)
+    }
+
+    fn list_failed_batches_recent(
+        &self,
+        service_id: &str,
+        offset: i64,
+        limit: i64,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .list_failed_batches_recent(service_id, offset, limit)
+    }
+
+    fn list_batches_by_kind(
+        &self,
+        kind: &str,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .list_batches_by_kind(kind, service_id)
+    }
+
+    fn get_batch_projected(
+        &self,
+        id: &str,
+        service_id: &str,
+        projection: BatchProjection,
+    ) -> Result<Option<TrackingBatch>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool_for(service_id, id).get().map_err(
+            |err| {
+                BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                    ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+                )
+            },
+        )?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .get_batch_projected(id, service_id, projection)
+    }
+
+    fn find_batches_by_transaction_prefix(
+        &self,
+        prefix: &str,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .find_batches_by_transaction_prefix(prefix, service_id)
+    }
+
+    fn list_failure_summaries(
+        &self,
+        service_id: &str,
+        limit: i64,
+    ) -> Result<Vec<FailureSummary>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .list_failure_summaries(service_id, limit)
+    }
+
+    fn swap_batch_status(
+        &self,
+        id: &str,
+        service_id: &str,
+        status: BatchStatus,
+    ) -> Result<Option<BatchStatus>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        self.record_write(service_id, id);
+        let status = status.to_string();
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_timestamp_precision(self.timestamp_precision)
+        .with_status_event_debounce(self.status_event_debounce)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .swap_batch_status(id, service_id, &status)
+    }
+
+    fn get_unsubmitted_batches_limited(
+        &self,
+        service_id: Option<&str>,
+        limit: i64,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id = service_id
+            .map(|service_id| self.resolve_service_id(service_id))
+            .transpose()?;
+
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .get_unsubmitted_batches_limited(service_id.as_deref(), limit)
+    }
+
+    fn dead_letter_batch(
+        &self,
+        id: &str,
+        service_id: &str,
+        reason: &str,
+    ) -> Result<(), BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        self.record_write(service_id, id);
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_timestamp_precision(self.timestamp_precision)
+        .with_status_event_debounce(self.status_event_debounce)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .dead_letter_batch(id, service_id, reason)
+    }
+
+    fn get_dead_lettered_batches(
+        &self,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .get_dead_lettered_batches(service_id)
+    }
+
+    fn content_digest(&self, service_id: &str) -> Result<String, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .content_digest(service_id)
+    }
+
+    fn get_batches_awaiting_receipts(
+        &self,
+        older_than: i64,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .get_batches_awaiting_receipts(older_than, service_id)
+    }
+
+    fn failed_batches_cursor(
+        &self,
+        service_id: &str,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .failed_batches_cursor(service_id, after, limit)
+    }
+
+    fn remap_data_change_ids(
+        &self,
+        mapping: HashMap<String, String>,
+        service_id: &str,
+    ) -> Result<usize, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .remap_data_change_ids(&mapping, service_id)
+    }
+
+    fn list_batches_by_family(
+        &self,
+        family_name: &str,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .list_batches_by_family(family_name, service_id)
+    }
+
+    fn find_batches_with_excess_receipts(
+        &self,
+        service_id: &str,
+    ) -> Result<Vec<String>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .find_batches_with_excess_receipts(service_id)
+    }
+
+    fn dedupe_receipts(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<usize, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .dedupe_receipts(id, service_id)
+    }
+
+    fn get_batch_status_at(
+        &self,
+        id: &str,
+        service_id: &str,
+        at: i64,
+    ) -> Result<Option<BatchStatusName>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .get_batch_status_at(id, service_id, at)
+    }
+
+    fn try_add_batches(
+        &self,
+        batches: Vec<TrackingBatch>,
+    ) -> Result<TryAddBatchesOutcomes, BatchTrackingStoreError> {
+        let mut watched = self.unsubmitted_watchers.watched(&batches);
+
+        let outcomes = try_add_checked(
+            self.allowed_batch_kinds.as_deref(),
+            self.allowed_service_ids.as_ref(),
+            self.batch_id_validator,
+            batches,
+            |batches| {
+                if let Some(recent_writes) = &self.recent_writes {
+                    recent_writes.record(written_batch_ids(&batches));
+                }
+
+                BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+                    BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                        ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+                    )
+                })?)
+                .with_timestamp_precision(self.timestamp_precision)
+                .with_correlation_id(self.correlation_id.as_deref())
+                .with_payload_cipher(self.payload_cipher.as_deref())
+                .try_add_batches(batches, self.ignore_duplicate_batches)
+            },
+        )?;
+
+        watched.retain(|batch| is_added(&outcomes, batch));
+        self.unsubmitted_watchers.send(watched);
+
+        Ok(outcomes)
+    }
+
+    fn list_batches_by_submit_url(
+        &self,
+        submit_url: &str,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .list_batches_by_submit_url(submit_url, service_id)
+    }
+
+    fn find_duplicate_dcids(
+        &self,
+        service_id: &str,
+    ) -> Result<Vec<(String, Vec<String>)>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .find_duplicate_dcids(service_id)
+    }
+
+    fn count_batches_by_origin(
+        &self,
+        service_id: &str,
+        since: i64,
+    ) -> Result<HashMap<String, i64>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .count_batches_by_origin(service_id, since)
+    }
+
+    fn id_status_page(
+        &self,
+        service_id: &str,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<(String, BatchStatusName)>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .id_status_page(service_id, after, limit)
+    }
+
+    fn list_batches_depending_on(
+        &self,
+        transaction_id: &str,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .list_batches_depending_on(transaction_id, service_id)
+    }
+
+    fn tombstone_batches(
+        &self,
+        ids: &[&str],
+        service_id: &str,
+    ) -> Result<usize, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .tombstone_batches(ids, service_id)
+    }
+
+    fn submission_success_rate(
+        &self,
+        service_id: &str,
+        start: i64,
+        end: i64,
+    ) -> Result<f64, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .submission_success_rate(service_id, start, end)
+    }
+
+    fn find_batches_without_transactions(
+        &self,
+        service_id: &str,
+    ) -> Result<Vec<String>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .find_batches_without_transactions(service_id)
+    }
+
+    fn claim_unsubmitted_batches(
+        &self,
+        service_id: &str,
+        claimant: &str,
+        n: i64,
+        lease_secs: u64,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .claim_unsubmitted_batches(service_id, claimant, n, lease_secs)
+    }
+
+    fn health_snapshot(
+        &self,
+        service_id: &str,
+        stuck_threshold: i64,
+    ) -> Result<HealthSnapshot, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .health_snapshot(service_id, stuck_threshold)
+    }
+
+    fn hourly_batch_counts(
+        &self,
+        service_id: &str,
+        start: i64,
+        end: i64,
+    ) -> Result<Vec<(i64, i64)>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_timestamp_precision(self.timestamp_precision)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .hourly_batch_counts(service_id, start, end)
+    }
+
+    fn fetch_outbox(&self, limit: i64) -> Result<Vec<OutboxEvent>, BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .fetch_outbox(limit)
+    }
+
+    fn ack_outbox(&self, ids: &[i64]) -> Result<usize, BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_timestamp_precision(self.timestamp_precision)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .ack_outbox(ids)
+    }
+
+    fn dead_letter_batches(
+        &self,
+        ids: &[&str],
+        service_id: &str,
+        reason: &str,
+    ) -> Result<usize, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        for id in ids {
+            self.record_write(service_id, id);
+        }
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_timestamp_precision(self.timestamp_precision)
+        .with_status_event_debounce(self.status_event_debounce)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .dead_letter_batches(ids, service_id, reason)
+    }
+
+    fn list_batches_by_labels(
+        &self,
+        predicates: &[(String, String)],
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .list_batches_by_labels(predicates, service_id)
+    }
+
+    fn get_latest_submission_error(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<Option<SubmissionError>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .get_latest_submission_error(id, service_id)
+    }
+
+    fn transition_batches(
+        &self,
+        transitions: Vec<(String, BatchStatus)>,
+        service_id: &str,
+    ) -> Result<(), BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        for (id, _) in &transitions {
+            self.record_write(service_id, id);
+        }
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_timestamp_precision(self.timestamp_precision)
+        .with_status_event_debounce(self.status_event_debounce)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .transition_batches(transitions, service_id)
+    }
+
+    fn write_service_backup(
+        &self,
+        service_id: &str,
+        writer: &mut dyn Write,
+    ) -> Result<(), BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(&*self.read_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .backup_service(service_id, writer)
+    }
+
+    fn read_service_backup(&self, reader: &mut dyn Read) -> Result<(), BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(&*self.connection_pool.get().map_err(|err| {
+            BatchTrackingStoreError::ResourceTemporarilyUnavailableError(
+                ResourceTemporarilyUnavailableError::from_source(Box::new(err)),
+            )
+        })?)
+        .with_timestamp_precision(self.timestamp_precision)
+        .with_correlation_id(self.correlation_id.as_deref())
+        .with_payload_cipher(self.payload_cipher.as_deref())
+        .restore_service(reader, self.allowed_service_ids.as_ref())
+    }
+}
+
+pub struct DieselConnectionBatchTrackingStore<'a, C>
+where
+    C: diesel::Connection<TransactionManager = AnsiTransactionManager> + 'static,
+    C::Backend: diesel::backend::UsesAnsiSavepointSyntax,
+{
+    connection: &'a C,
+    timestamp_precision: TimestampPrecision,
+    ignore_duplicate_batches: bool,
+    case_insensitive_service_ids: bool,
+    allowed_batch_kinds: Option<Vec<String>>,
+    allowed_service_ids: Option<HashSet<String>>,
+    batch_id_validator: Option<BatchIdValidator>,
+    status_event_debounce: Duration,
+    unsubmitted_watchers: Arc<UnsubmittedWatchers>,
+    #[cfg(feature = "postgres")]
+    schema: Option<String>,
+    #[cfg(feature = "postgres")]
+    serialization_retries: u32,
+    correlation_id: Option<String>,
+    payload_cipher: Option<Arc<dyn PayloadCipher>>,
+}
+
+impl<'a, C> DieselConnectionBatchTrackingStore<'a, C>
+where
+    C: diesel::Connection<TransactionManager = AnsiTransactionManager> + 'static,
+    C::Backend: diesel::backend::UsesAnsiSavepointSyntax,
+{
+    pub fn new(connection: &'a C) -> Self {
+        DieselConnectionBatchTrackingStore {
+            connection,
+            timestamp_precision: TimestampPrecision::Seconds,
+            ignore_duplicate_batches: false,
+            case_insensitive_service_ids: false,
+            allowed_batch_kinds: None,
+            allowed_service_ids: None,
+            batch_id_validator: None,
+            status_event_debounce: Duration::from_secs(0),
+            unsubmitted_watchers: Arc::new(UnsubmittedWatchers::default()),
+            #[cfg(feature = "postgres")]
+            schema: None,
+            #[cfg(feature = "postgres")]
+            serialization_retries: DEFAULT_SERIALIZATION_RETRIES,
+            correlation_id: None,
+            payload_cipher: None,
+        }
+    }
+
+    /// Sets the precision of the timestamps recorded by the store
+    ///
+    /// # Arguments
+    ///
+    ///  * `timestamp_precision`: the unit timestamps are recorded in
+    pub fn with_timestamp_precision(mut self, timestamp_precision: TimestampPrecision) -> Self {
+        self.timestamp_precision = timestamp_precision;
+        self
+    }
+
+    /// Sets whether `add_batches` skips batches that are already present
+    ///
+    /// # Arguments
+    ///
+    ///  * `ignore_duplicate_batches`: whether to skip batches already present
+    pub fn with_ignore_duplicate_batches(mut self, ignore_duplicate_batches: bool) -> Self {
+        self.ignore_duplicate_batches = ignore_duplicate_batches;
+        self
+    }
+
+    /// Sets whether service IDs are matched case-insensitively
+    ///
+    /// # Arguments
+    ///
+    ///  * `case_insensitive_service_ids`: whether to ignore case in service IDs
+    pub fn with_case_insensitive_service_ids(mut self, case_insensitive_service_ids: bool) -> Self {
+        self.case_insensitive_service_ids = case_insensitive_service_ids;
+        self
+    }
+
+    /// Sets the batch kinds that `add_batches` accepts
+    ///
+    /// # Arguments
+    ///
+    ///  * `allowed_batch_kinds`: the kinds of batch that can be added
+    pub fn with_allowed_batch_kinds(mut self, allowed_batch_kinds: Vec<String>) -> Self {
+        self.allowed_batch_kinds = Some(allowed_batch_kinds);
+        self
+    }
+
+    /// Sets the service IDs that the store accepts writes for
+    ///
+    /// # Arguments
+    ///
+    ///  * `allowed_service_ids`: the service IDs that can be written to
+    pub fn with_allowed_service_ids(mut self, allowed_service_ids: HashSet<String>) -> Self {
+        self.allowed_service_ids = Some(allowed_service_ids);
+        self
+    }
+
+    /// Sets the function that checks the format of the IDs of added batches
+    ///
+    /// # Arguments
+    ///
+    ///  * `batch_id_validator`: returns an error describing why a batch ID is
+    ///    malformed
+    pub fn with_batch_id_validator(mut self, batch_id_validator: BatchIdValidator) -> Self {
+        self.batch_id_validator = Some(batch_id_validator);
+        self
+    }
+
+    /// Sets how long repeated status events are collapsed for
+    ///
+    /// # Arguments
+    ///
+    ///  * `status_event_debounce`: how long after an event identical events
+    ///    are skipped
+    pub fn with_status_event_debounce(mut self, status_event_debounce: Duration) -> Self {
+        self.status_event_debounce = status_event_debounce;
+        self
+    }
+
+    /// Sets the cipher used to encrypt transaction payloads at rest
+    ///
+    /// # Arguments
+    ///
+    ///  * `payload_cipher`: the cipher to encrypt and decrypt payloads with
+    pub fn with_payload_cipher(mut self, payload_cipher: Arc<dyn PayloadCipher>) -> Self {
+        self.payload_cipher = Some(payload_cipher);
+        self
+    }
+
+    /// Sets how many batches each `watch_unsubmitted` subscriber can hold and
+    /// what happens when a subscriber's channel is full
+    ///
+    /// Batches added while the connection is in a transaction opened by the
+    /// caller are not sent to subscribers, since the store can't tell whether
+    /// that transaction will be committed.
+    ///
+    /// # Arguments
+    ///
+    ///  * `capacity`: the number of batches each subscriber can hold
+    ///  * `backpressure`: what to do when a subscriber is full
+    pub fn with_watch_capacity(mut self, capacity: usize, backpressure: WatchBackpressure) -> Self {
+        self.unsubmitted_watchers = Arc::new(UnsubmittedWatchers::new(capacity, backpressure));
+        self
+    }
+
+    /// Sets the watchers the store sends unsubmitted batches to, so that
+    /// subscribers receive the batches added through any store sharing them
+    ///
+    /// # Arguments
+    ///
+    ///  * `unsubmitted_watchers`: the watchers to share
+    pub(crate) fn with_unsubmitted_watchers(
+        mut self,
+        unsubmitted_watchers: Arc<UnsubmittedWatchers>,
+    ) -> Self {
+        self.unsubmitted_watchers = unsubmitted_watchers;
+        self
+    }
+
+    /// Returns a copy of the store that tags what it does with a correlation
+    /// ID
+    ///
+    /// # Arguments
+    ///
+    ///  * `correlation_id`: the ID used to correlate the store's work with the
+    ///    caller's request
+    pub fn with_correlation_id(&self, correlation_id: &str) -> Self {
+        DieselConnectionBatchTrackingStore {
+            connection: self.connection,
+            timestamp_precision: self.timestamp_precision,
+            ignore_duplicate_batches: self.ignore_duplicate_batches,
+            case_insensitive_service_ids: self.case_insensitive_service_ids,
+            allowed_batch_kinds: self.allowed_batch_kinds.clone(),
+            allowed_service_ids: self.allowed_service_ids.clone(),
+            batch_id_validator: self.batch_id_validator,
+            status_event_debounce: self.status_event_debounce,
+            unsubmitted_watchers: Arc::clone(&self.unsubmitted_watchers),
+            #[cfg(feature = "postgres")]
+            schema: self.schema.clone(),
+            #[cfg(feature = "postgres")]
+            serialization_retries: self.serialization_retries,
+            correlation_id: Some(correlation_id.to_string()),
+            payload_cipher: self.payload_cipher.clone(),
+        }
+    }
+
+    /// Sends the added batches to their subscribers, unless the connection is
+    /// still in a transaction opened by the caller that may be rolled back
+    fn send_watched(&self, watched: Vec<TrackingBatch>) {
+        let transaction_manager = self.connection.transaction_manager();
+        if TransactionManager::<C>::get_transaction_depth(transaction_manager) == 0 {
+            self.unsubmitted_watchers.send(watched);
+        }
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> DieselConnectionBatchTrackingStore<'a, diesel::pg::PgConnection> {
+    /// Sets the postgres schema the batch tracking tables are in
+    ///
+    /// # Arguments
+    ///
+    ///  * `schema`: the name of the schema the tables are in
+    pub fn with_schema(mut self, schema: &str) -> Self {
+        self.schema = Some(schema.to_string());
+        self
+    }
+
+    /// Sets how many times a bulk write is retried after postgres fails to
+    /// serialize it with a concurrent transaction
+    ///
+    /// # Arguments
+    ///
+    ///  * `serialization_retries`: the number of times to retry a bulk write
+    pub fn with_serialization_retries(mut self, serialization_retries: u32) -> Self {
+        self.serialization_retries = serialization_retries;
+        self
+    }
+
+    /// Returns the service ID the store should use for the given service ID
+    fn resolve_service_id(&self, service_id: &str) -> Result<String, BatchTrackingStoreError> {
+        if !self.case_insensitive_service_ids {
+            return Ok(service_id.to_string());
+        }
+
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .resolve_service_id(service_id)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> DieselConnectionBatchTrackingStore<'a, diesel::sqlite::SqliteConnection> {
+    /// Returns the service ID the store should use for the given service ID
+    fn resolve_service_id(&self, service_id: &str) -> Result<String, BatchTrackingStoreError> {
+        if !self.case_insensitive_service_ids {
+            return Ok(service_id.to_string());
+        }
+
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .resolve_service_id(service_id)
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStore for DieselConnectionBatchTrackingStore<'a, diesel::pg::PgConnection> {
+    fn get_batch_status(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<Option<BatchStatus>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .get_batch_status(id, service_id)
+    }
+
+    fn update_batch_status(
+        &self,
+        id: &str,
+        service_id: &str,
+        status: Option<BatchStatus>,
+        transaction_receipts: Vec<TransactionReceipt>,
+        submission_error: Option<SubmissionError>,
+    ) -> Result<(), BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        let rcpts: Vec<TransactionReceiptModel> = transaction_receipts
+            .iter()
+            .map(|t| TransactionReceiptModel::from((t, service_id)))
+            .collect::<Vec<TransactionReceiptModel>>();
+
+        let stat = status.map(|s| s.to_string());
+
+        let batch_status: Option<&str> = stat.as_deref();
+
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_timestamp_precision(self.timestamp_precision)
+            .with_status_event_debounce(self.status_event_debounce)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .update_batch_status(id, service_id, batch_status, rcpts, submission_error)
+    }
+
+    fn add_batches(&self, batches: Vec<TrackingBatch>) -> Result<(), BatchTrackingStoreError> {
+        validate_batch_kinds(self.allowed_batch_kinds.as_deref(), &batches)?;
+        validate_batch_ids(self.batch_id_validator, &batches)?;
+        validate_batch_service_ids(self.allowed_service_ids.as_ref(), &batches)?;
+
+        let watched = self.unsubmitted_watchers.watched(&batches);
+
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_timestamp_precision(self.timestamp_precision)
+            .with_schema(self.schema.as_deref())
+            .with_serialization_retries(self.serialization_retries)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .add_batches(batches, self.ignore_duplicate_batches)?;
+
+        self.send_watched(watched);
+
+        Ok(())
+    }
+
+    fn change_batch_to_submitted(
+        &self,
+        batch_id: &str,
+        service_id: &str,
+        transaction_receipts: Vec<TransactionReceipt>,
+        dlt_status: Option<&str>,
+        submission_error: Option<SubmissionError>,
+        submitter_response: Option<&[u8]>,
+        submit_headers: Option<&serde_json::Value>,
+        submit_url: Option<&str>,
+        submission_round: Option<i64>,
+        network_id: Option<&str>,
+    ) -> Result<(), BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        let mut batch_status = None;
+
+        if let Some(ds) = dlt_status {
+            batch_status = Some(NewBatchStatusModel {
+                batch_id: batch_id.to_string(),
+                service_id: service_id.to_string(),
+                dlt_status: ds.to_string(),
+            });
+        }
+
+        let mut submission = NewSubmissionModel {
+            batch_id: batch_id.to_string(),
+            service_id: service_id.to_string(),
+            error_type: None,
+            error_message: None,
+        };
+
+        if let Some(s) = submission_error {
+            submission = NewSubmissionModel {
+                batch_id: batch_id.to_string(),
+                service_id: service_id.to_string(),
+                error_type: Some(s.error_type().to_string()),
+                error_message: Some(s.error_message().to_string()),
+            };
+        }
+
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_timestamp_precision(self.timestamp_precision)
+            .with_status_event_debounce(self.status_event_debounce)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .change_batch_to_submitted(
+                batch_id,
+                service_id,
+                transaction_receipts
+                    .iter()
+                    .map(|r| TransactionReceiptModel::from((r, service_id)))
+                    .collect(),
+                batch_status,
+                submission,
+                submitter_response,
+                submit_headers,
+                submit_url,
+                submission_round,
+                network_id,
+            )
+    }
+
+    fn get_batch(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<Option<TrackingBatch>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .get_batch(id, service_id)
+    }
+
+    fn list_batches_by_status(
+        &self,
+        status: BatchStatus,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .list_batches_by_status(&status.to_string())
+    }
+
+    fn clean_stale_records(&self, submitted_by: i64) -> Result<(), BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_serialization_retries(self.serialization_retries)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .clean_stale_records(submitted_by)
+    }
+
+    fn get_unsubmitted_batches(&self) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .get_unsubmitted_batches()
+    }
+
+    fn get_failed_batches(&self) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .get_failed_batches()
+    }
+
+    fn tombstone_batch(&self, id: &str, service_id: &str) -> Result<(), BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_timestamp_precision(self.timestamp_precision)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .tombstone_batch(id, service_id)
+    }
+
+    fn get_recent_failures(
+        &self,
+        service_id: &str,
+        limit: i64,
+    ) -> Result<Vec<FailedBatchDetail>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .get_recent_failures(service_id, limit)
+    }
+
+    fn compact(&self) -> Result<(), BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .compact()
+    }
+
+    fn status_distribution_between(
+        &self,
+        service_id: &str,
+        start: i64,
+        end: i64,
+    ) -> Result<HashMap<BatchStatusName, i64>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .status_distribution_between(service_id, start, end)
+    }
+
+    fn get_batch_submission_info(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<Option<BatchSubmissionInfo>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .get_batch_submission_info(id, service_id)
+    }
+
+    fn store_receipts_only(
+        &self,
+        id: &str,
+        service_id: &str,
+        transaction_receipts: Vec<TransactionReceipt>,
+    ) -> Result<(), BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        let rcpts: Vec<TransactionReceiptModel> = transaction_receipts
+            .iter()
+            .map(|t| TransactionReceiptModel::from((t, service_id)))
+            .collect();
+
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .store_receipts_only(id, service_id, rcpts)
+    }
+
+    fn find_committed_batches_missing_receipts(
+        &self,
+        service_id: &str,
+    ) -> Result<Vec<String>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .find_committed_batches_missing_receipts(service_id)
+    }
+
+    fn list_batches_by_state_address(
+        &self,
+        address: &str,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .list_batches_by_state_address(address, service_id)
+    }
+
+    fn average_submission_latency(
+        &self,
+        service_id: &str,
+        since: i64,
+    ) -> Result<Option<i64>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .average_submission_latency(service_id, since)
+    }
+
+    fn add_transact_batches(
+        &self,
+        batches: Vec<Batch>,
+        service_id: &str,
+    ) -> Result<(), BatchTrackingStoreError> {
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_timestamp_precision(self.timestamp_precision)
+            .with_schema(self.schema.as_deref())
+            .with_serialization_retries(self.serialization_retries)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .add_transact_batches(batches, service_id, self.ignore_duplicate_batches)
+    }
+
+    fn watch_unsubmitted(&self, service_id: &str) -> UnsubmittedBatchReceiver {
+        self.unsubmitted_watchers.subscribe(service_id)
+    }
+
+    fn record_submission_attempt(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<i64, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_timestamp_precision(self.timestamp_precision)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .record_submission_attempt(id, service_id)
+    }
+
+    fn list_batches_by_status_with_total(
+        &self,
+        status: BatchStatus,
+        offset: i64,
+        limit: i64,
+    ) -> Result<TrackingBatchPage, BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .list_batches_by_status_with_total(&status.to_string(), offset, limit)
+    }
+
+    fn scrub_receipts(&self, id: &str, service_id: &str) -> Result<usize, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .scrub_receipts(id, service_id)
+    }
+
+    fn list_batches(&self, service_id: &str) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .list_batches(service_id)
+    }
+
+    fn pool_state(&self) -> PoolState {
+        PoolState::new(1, 0, 1)
+    }
+
+    fn set_batch_notes(
+        &self,
+        id: &str,
+        service_id: &str,
+        notes: Option<&str>,
+    ) -> Result<(), BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .set_batch_notes(id, service_id, notes)
+    }
+
+    fn get_batch_by_transaction_id(
+        &self,
+        transaction_id: &str,
+        service_id: &str,
+    ) -> Result<Vec<TrackingBatch>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .get_batch_by_transaction_id(transaction_id, service_id)
+    }
+
+    fn metrics_text(&self) -> Result<String, BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .metrics_text()
+    }
+
+    fn list_batches_by_round(
+        &self,
+        round: i64,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .list_batches_by_round(round, service_id)
+    }
+
+    fn has_unsubmitted_batches(
+        &self,
+        service_id: Option<&str>,
+    ) -> Result<bool, BatchTrackingStoreError> {
+        let service_id = service_id
+            .map(|service_id| self.resolve_service_id(service_id))
+            .transpose()?;
+
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .has_unsubmitted_batches(service_id.as_deref())
+    }
+
+    fn normalize_status_values(&self) -> Result<usize, BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_serialization_retries(self.serialization_retries)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .normalize_status_values()
+    }
+
+    fn list_batch_status_events(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<Vec<BatchStatusEvent>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .list_batch_status_events(id, service_id)
+    }
+
+    fn get_batches_by_data_change_ids(
+        &self,
+        dcids: &[&str],
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .get_batches_by_data_change_ids(dcids, service_id)
+    }
+
+    fn total_bytes_by_service(&self, service_id: &str) -> Result<i64, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .total_bytes_by_service(service_id)
+    }
+
+    fn list_batches_status_changed_between(
+        &self,
+        service_id: &str,
+        start: i64,
+        end: i64,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .list_batches_status_changed_between(service_id, start, end)
+    }
+
+    fn commit_batch(
+        &self,
+        id: &str,
+        service_id: &str,
+        transaction_receipts: Vec<TransactionReceipt>,
+    ) -> Result<(), BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        let rcpts: Vec<TransactionReceiptModel> = transaction_receipts
+            .iter()
+            .map(|r| TransactionReceiptModel::from((r, service_id)))
+            .collect();
+
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_timestamp_precision(self.timestamp_precision)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .commit_batch(id, service_id, rcpts)
+    }
+
+    fn created_at_bounds(
+        &self,
+        service_id: &str,
+    ) -> Result<Option<(i64, i64)>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .created_at_bounds(service_id)
+    }
+
+    fn list_batches_by_network(
+        &self,
+        network_id: &str,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .list_batches_by_network(network_id, service_id)
+    }
+
+    fn find_flapping_batches(
+        &self,
+        min_transitions: i64,
+        since: i64,
+    ) -> Result<Vec<String>, BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .find_flapping_batches(min_transitions, since)
+    }
+
+    fn sync_since(
+        &self,
+        service_id: &str,
+        checkpoint: Option<i64>,
+        limit: i64,
+    ) -> Result<(Vec<TrackingBatch>, Option<i64>), BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .sync_since(service_id, checkpoint, limit)
+    }
+
+    fn set_alias(
+        &self,
+        id: &str,
+        service_id: &str,
+        alias: Option<&str>,
+    ) -> Result<(), BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .set_alias(id, service_id, alias)
+    }
+
+    fn get_batch_by_alias(
+        &self,
+        alias: &str,
+        service_id: &str,
+    ) -> Result<Option<TrackingBatch>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .get_batch_by_alias(alias, service_id)
+    }
+
+    fn all_statuses_for_service(
+        &self,
+        service_id: &str,
+    ) -> Result<HashMap<String, BatchStatusName>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .all_statuses_for_service(service_id)
+    }
+
+    fn repair_missing_statuses(&self) -> Result<usize, BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_timestamp_precision(self.timestamp_precision)
+            .with_status_event_debounce(self.status_event_debounce)
+            .with_schema(self.schema.as_deref())
+            .with_serialization_retries(self.serialization_retries)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .repair_missing_statuses()
+    }
+
+    fn list_batches_by_attempts(
+        &self,
+        service_id: &str,
+        limit: i64,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .list_batches_by_attempts(service_id, limit)
+    }
+
+    fn count_transactions(&self, service_id: &str) -> Result<i64, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .count_transactions(service_id)
+    }
+
+    fn list_failed_batches_recent(
+        &self,
+        service_id: &str,
+        offset: i64,
+        limit: i64,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .list_failed_batches_recent(service_id, offset, limit)
+    }
+
+    fn list_batches_by_kind(
+        &self,
+        kind: &str,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .list_batches_by_kind(kind, service_id)
+    }
+
+    fn get_batch_projected(
+        &self,
+        id: &str,
+        service_id: &str,
+        projection: BatchProjection,
+    ) -> Result<Option<TrackingBatch>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .get_batch_projected(id, service_id, projection)
+    }
+
+    fn find_batches_by_transaction_prefix(
+        &self,
+        prefix: &str,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .find_batches_by_transaction_prefix(prefix, service_id)
+    }
+
+    fn list_failure_summaries(
+        &self,
+        service_id: &str,
+        limit: i64,
+    ) -> Result<Vec<FailureSummary>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .list_failure_summaries(service_id, limit)
+    }
+
+    fn swap_batch_status(
+        &self,
+        id: &str,
+        service_id: &str,
+        status: BatchStatus,
+    ) -> Result<Option<BatchStatus>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        let status = status.to_string();
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_timestamp_precision(self.timestamp_precision)
+            .with_status_event_debounce(self.status_event_debounce)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .swap_batch_status(id, service_id, &status)
+    }
+
+    fn get_unsubmitted_batches_limited(
+        &self,
+        service_id: Option<&str>,
+        limit: i64,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id = service_id
+            .map(|service_id| self.resolve_service_id(service_id))
+            .transpose()?;
+
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .get_unsubmitted_batches_limited(service_id.as_deref(), limit)
+    }
+
+    fn dead_letter_batch(
+        &self,
+        id: &str,
+        service_id: &str,
+        reason: &str,
+    ) -> Result<(), BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_timestamp_precision(self.timestamp_precision)
+            .with_status_event_debounce(self.status_event_debounce)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .dead_letter_batch(id, service_id, reason)
+    }
+
+    fn get_dead_lettered_batches(
+        &self,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .get_dead_lettered_batches(service_id)
+    }
+
+    fn content_digest(&self, service_id: &str) -> Result<String, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .content_digest(service_id)
+    }
+
+    fn get_batches_awaiting_receipts(
+        &self,
+        older_than: i64,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .get_batches_awaiting_receipts(older_than, service_id)
+    }
+
+    fn failed_batches_cursor(
+        &self,
+        service_id: &str,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .failed_batches_cursor(service_id, after, limit)
+    }
+
+    fn remap_data_change_ids(
+        &self,
+        mapping: HashMap<String, String>,
+        service_id: &str,
+    ) -> Result<usize, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_serialization_retries(self.serialization_retries)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .remap_data_change_ids(&mapping, service_id)
+    }
+
+    fn list_batches_by_family(
+        &self,
+        family_name: &str,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .list_batches_by_family(family_name, service_id)
+    }
+
+    fn find_batches_with_excess_receipts(
+        &self,
+        service_id: &str,
+    ) -> Result<Vec<String>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .find_batches_with_excess_receipts(service_id)
+    }
+
+    fn dedupe_receipts(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<usize, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .dedupe_receipts(id, service_id)
+    }
+
+    fn get_batch_status_at(
+        &self,
+        id: &str,
+        service_id: &str,
+        at: i64,
+    ) -> Result<Option<BatchStatusName>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .get_batch_status_at(id, service_id, at)
+    }
+
+    fn try_add_batches(
+        &self,
+        batches: Vec<TrackingBatch>,
+    ) -> Result<TryAddBatchesOutcomes, BatchTrackingStoreError> {
+        let mut watched = self.unsubmitted_watchers.watched(&batches);
+
+        let outcomes = try_add_checked(
+            self.allowed_batch_kinds.as_deref(),
+            self.allowed_service_ids.as_ref(),
+            self.batch_id_validator,
+            batches,
+            |batches| {
+                BatchTrackingStoreOperations::new(self.connection)
+                    .with_timestamp_precision(self.timestamp_precision)
+                    .with_schema(self.schema.as_deref())
+                    .with_correlation_id(self.correlation_id.as_deref())
+                    .with_payload_cipher(self.payload_cipher.as_deref())
+                    .try_add_batches(batches, self.ignore_duplicate_batches)
+            },
+        )?;
+
+        watched.retain(|batch| is_added(&outcomes, batch));
+        self.send_watched(watched);
+
+        Ok(outcomes)
+    }
+
+    fn list_batches_by_submit_url(
+        &self,
+        submit_url: &str,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .list_batches_by_submit_url(submit_url, service_id)
+    }
+
+    fn find_duplicate_dcids(
+        &self,
+        service_id: &str,
+    ) -> Result<Vec<(String, Vec<String>)>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .find_duplicate_dcids(service_id)
+    }
+
+    fn count_batches_by_origin(
+        &self,
+        service_id: &str,
+        since: i64,
+    ) -> Result<HashMap<String, i64>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .count_batches_by_origin(service_id, since)
+    }
+
+    fn id_status_page(
+        &self,
+        service_id: &str,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<(String, BatchStatusName)>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .id_status_page(service_id, after, limit)
+    }
+
+    fn list_batches_depending_on(
+        &self,
+        transaction_id: &str,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .list_batches_depending_on(transaction_id, service_id)
+    }
+
+    fn tombstone_batches(
+        &self,
+        ids: &[&str],
+        service_id: &str,
+    ) -> Result<usize, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_serialization_retries(self.serialization_retries)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .tombstone_batches(ids, service_id)
+    }
+
+    fn submission_success_rate(
+        &self,
+        service_id: &str,
+        start: i64,
+        end: i64,
+    ) -> Result<f64, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .submission_success_rate(service_id, start, end)
+    }
+
+    fn find_batches_without_transactions(
+        &self,
+        service_id: &str,
+    ) -> Result<Vec<String>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .find_batches_without_transactions(service_id)
+    }
+
+    fn claim_unsubmitted_batches(
+        &self,
+        service_id: &str,
+        claimant: &str,
+        n: i64,
+        lease_secs: u64,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_serialization_retries(self.serialization_retries)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .claim_unsubmitted_batches(service_id, claimant, n, lease_secs)
+    }
+
+    fn health_snapshot(
+        &self,
+        service_id: &str,
+        stuck_threshold: i64,
+    ) -> Result<HealthSnapshot, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .health_snapshot(service_id, stuck_threshold)
+    }
+
+    fn hourly_batch_counts(
+        &self,
+        service_id: &str,
+        start: i64,
+        end: i64,
+    ) -> Result<Vec<(i64, i64)>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_timestamp_precision(self.timestamp_precision)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .hourly_batch_counts(service_id, start, end)
+    }
+
+    fn fetch_outbox(&self, limit: i64) -> Result<Vec<OutboxEvent>, BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .fetch_outbox(limit)
+    }
+
+    fn ack_outbox(&self, ids: &[i64]) -> Result<usize, BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_timestamp_precision(self.timestamp_precision)
+            .with_schema(self.schema.as_deref())
+            .with_serialization_retries(self.serialization_retries)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .ack_outbox(ids)
+    }
+
+    fn dead_letter_batches(
+        &self,
+        ids: &[&str],
+        service_id: &str,
+        reason: &str,
+    ) -> Result<usize, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_timestamp_precision(self.timestamp_precision)
+            .with_status_event_debounce(self.status_event_debounce)
+            .with_schema(self.schema.as_deref())
+            .with_serialization_retries(self.serialization_retries)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .dead_letter_batches(ids, service_id, reason)
+    }
+
+    fn list_batches_by_labels(
+        &self,
+        predicates: &[(String, String)],
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .list_batches_by_labels(predicates, service_id)
+    }
+
+    fn get_latest_submission_error(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<Option<SubmissionError>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .get_latest_submission_error(id, service_id)
+    }
+
+    fn transition_batches(
+        &self,
+        transitions: Vec<(String, BatchStatus)>,
+        service_id: &str,
+    ) -> Result<(), BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_timestamp_precision(self.timestamp_precision)
+            .with_status_event_debounce(self.status_event_debounce)
+            .with_schema(self.schema.as_deref())
+            .with_serialization_retries(self.serialization_retries)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .transition_batches(transitions, service_id)
+    }
+
+    fn write_service_backup(
+        &self,
+        service_id: &str,
+        writer: &mut dyn Write,
+    ) -> Result<(), BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .backup_service(service_id, writer)
+    }
+
+    fn read_service_backup(&self, reader: &mut dyn Read) -> Result<(), BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_timestamp_precision(self.timestamp_precision)
+            .with_schema(self.schema.as_deref())
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .restore_service(reader, self.allowed_service_ids.as_ref())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStore
+    for DieselConnectionBatchTrackingStore<'a, diesel::sqlite::SqliteConnection>
+{
+    fn get_batch_status(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<Option<BatchStatus>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .get_batch_status(id, service_id)
+    }
+
+    fn update_batch_status(
+        &self,
+        id: &str,
+        service_id: &str,
+        status: Option<BatchStatus>,
+        transaction_receipts: Vec<TransactionReceipt>,
+        submission_error: Option<SubmissionError>,
+    ) -> Result<(), BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        let rcpts: Vec<TransactionReceiptModel> = transaction_receipts
+            .iter()
+            .map(|t| TransactionReceiptModel::from((t, service_id)))
+            .collect::<Vec<TransactionReceiptModel>>();
+
+        let stat = status.map(|s| s.to_string());
+
+        let batch_status: Option<&str> = stat.as_deref();
+
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_timestamp_precision(self.timestamp_precision)
+            .with_status_event_debounce(self.status_event_debounce)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .update_batch_status(id, service_id, batch_status, rcpts, submission_error)
+    }
+
+    fn add_batches(&self, batches: Vec<TrackingBatch>) -> Result<(), BatchTrackingStoreError> {
+        validate_batch_kinds(self.allowed_batch_kinds.as_deref(), &batches)?;
+        validate_batch_ids(self.batch_id_validator, &batches)?;
+        validate_batch_service_ids(self.allowed_service_ids.as_ref(), &batches)?;
+
+        let watched = self.unsubmitted_watchers.watched(&batches);
+
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_timestamp_precision(self.timestamp_precision)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .add_batches(batches, self.ignore_duplicate_batches)?;
+
+        self.send_watched(watched);
+
+        Ok(())
+    }
+
+    fn change_batch_to_submitted(
+        &self,
+        batch_id: &str,
+        service_id: &str,
+        transaction_receipts: Vec<TransactionReceipt>,
+        dlt_status: Option<&str>,
+        submission_error: Option<SubmissionError>,
+        submitter_response: Option<&[u8]>,
+        submit_headers: Option<&serde_json::Value>,
+        submit_url: Option<&str>,
+        submission_round: Option<i64>,
+        network_id: Option<&str>,
+    ) -> Result<(), BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        let mut batch_status = None;
+
+        if let Some(ds) = dlt_status {
+            batch_status = Some(NewBatchStatusModel {
+                batch_id: batch_id.to_string(),
+                service_id: service_id.to_string(),
+                dlt_status: ds.to_string(),
+            });
+        }
+
+        let mut submission = NewSubmissionModel {
+            batch_id: batch_id.to_string(),
+            service_id: service_id.to_string(),
+            error_type: None,
+            error_message: None,
+        };
+
+        if let Some(s) = submission_error {
+            submission = NewSubmissionModel {
+                batch_id: batch_id.to_string(),
+                service_id: service_id.to_string(),
+                error_type: Some(s.error_type().to_string()),
+                error_message: Some(s.error_message().to_string()),
+            };
+        }
+
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_timestamp_precision(self.timestamp_precision)
+            .with_status_event_debounce(self.status_event_debounce)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .change_batch_to_submitted(
+                batch_id,
+                service_id,
+                transaction_receipts
+                    .iter()
+                    .map(|r| TransactionReceiptModel::from((r, service_id)))
+                    .collect(),
+                batch_status,
+                submission,
+                submitter_response,
+                submit_headers,
+                submit_url,
+                submission_round,
+                network_id,
+            )
+    }
+
+    fn get_batch(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<Option<TrackingBatch>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .get_batch(id, service_id)
+    }
+
+    fn list_batches_by_status(
+        &self,
+        status: BatchStatus,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .list_batches_by_status(&status.to_string())
+    }
+
+    fn clean_stale_records(&self, submitted_by: i64) -> Result<(), BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .clean_stale_records(submitted_by)
+    }
+
+    fn get_unsubmitted_batches(&self) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .get_unsubmitted_batches()
+    }
+
+    fn get_failed_batches(&self) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .get_failed_batches()
+    }
+
+    fn tombstone_batch(&self, id: &str, service_id: &str) -> Result<(), BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_timestamp_precision(self.timestamp_precision)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .tombstone_batch(id, service_id)
+    }
+
+    fn get_recent_failures(
+        &self,
+        service_id: &str,
+        limit: i64,
+    ) -> Result<Vec<FailedBatchDetail>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .get_recent_failures(service_id, limit)
+    }
+
+    fn compact(&self) -> Result<(), BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .compact()
+    }
+
+    fn status_distribution_between(
+        &self,
+        service_id: &str,
+        start: i64,
+        end: i64,
+    ) -> Result<HashMap<BatchStatusName, i64>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .status_distribution_between(service_id, start, end)
+    }
+
+    fn get_batch_submission_info(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<Option<BatchSubmissionInfo>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .get_batch_submission_info(id, service_id)
+    }
+
+    fn store_receipts_only(
+        &self,
+        id: &str,
+        service_id: &str,
+        transaction_receipts: Vec<TransactionReceipt>,
+    ) -> Result<(), BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        let rcpts: Vec<TransactionReceiptModel> = transaction_receipts
+            .iter()
+            .map(|t| TransactionReceiptModel::from((t, service_id)))
+            .collect();
+
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .store_receipts_only(id, service_id, rcpts)
+    }
+
+    fn find_committed_batches_missing_receipts(
+        &self,
+        service_id: &str,
+    ) -> Result<Vec<String>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .find_committed_batches_missing_receipts(service_id)
+    }
+
+    fn list_batches_by_state_address(
+        &self,
+        address: &str,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .list_batches_by_state_address(address, service_id)
+    }
+
+    fn average_submission_latency(
+        &self,
+        service_id: &str,
+        since: i64,
+    ) -> Result<Option<i64>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .average_submission_latency(service_id, since)
+    }
+
+    fn add_transact_batches(
+        &self,
+        batches: Vec<Batch>,
+        service_id: &str,
+    ) -> Result<(), BatchTrackingStoreError> {
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_timestamp_precision(self.timestamp_precision)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .add_transact_batches(batches, service_id, self.ignore_duplicate_batches)
+    }
+
+    fn watch_unsubmitted(&self, service_id: &str) -> UnsubmittedBatchReceiver {
+        self.unsubmitted_watchers.subscribe(service_id)
+    }
+
+    fn record_submission_attempt(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<i64, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_timestamp_precision(self.timestamp_precision)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .record_submission_attempt(id, service_id)
+    }
+
+    fn list_batches_by_status_with_total(
+        &self,
+        status: BatchStatus,
+        offset: i64,
+        limit: i64,
+    ) -> Result<TrackingBatchPage, BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .list_batches_by_status_with_total(&status.to_string(), offset, limit)
+    }
+
+    fn scrub_receipts(&self, id: &str, service_id: &str) -> Result<usize, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .scrub_receipts(id, service_id)
+    }
+
+    fn list_batches(&self, service_id: &str) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .list_batches(service_id)
+    }
+
+    fn pool_state(&self) -> PoolState {
+        PoolState::new(1, 0, 1)
+    }
+
+    fn set_batch_notes(
+        &self,
+        id: &str,
+        service_id: &str,
+        notes: Option<&str>,
+    ) -> Result<(), BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .set_batch_notes(id, service_id, notes)
+    }
+
+    fn get_batch_by_transaction_id(
+        &self,
+        transaction_id: &str,
+        service_id: &str,
+    ) -> Result<Vec<TrackingBatch>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .get_batch_by_transaction_id(transaction_id, service_id)
+    }
+
+    fn metrics_text(&self) -> Result<String, BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .metrics_text()
+    }
+
+    fn list_batches_by_round(
+        &self,
+        round: i64,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .list_batches_by_round(round, service_id)
+    }
+
+    fn has_unsubmitted_batches(
+        &self,
+        service_id: Option<&str>,
+    ) -> Result<bool, BatchTrackingStoreError> {
+        let service_id = service_id
+            .map(|service_id| self.resolve_service_id(service_id))
+            .transpose()?;
+
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .has_unsubmitted_batches(service_id.as_deref())
+    }
+
+    fn normalize_status_values(&self) -> Result<usize, BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .normalize_status_values()
+    }
+
+    fn list_batch_status_events(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<Vec<BatchStatusEvent>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .list_batch_status_events(id, service_id)
+    }
+
+    fn get_batches_by_data_change_ids(
+        &self,
+        dcids: &[&str],
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .get_batches_by_data_change_ids(dcids, service_id)
+    }
+
+    fn total_bytes_by_service(&self, service_id: &str) -> Result<i64, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .total_bytes_by_service(service_id)
+    }
+
+    fn list_batches_status_changed_between(
+        &self,
+        service_id: &str,
+        start: i64,
+        end: i64,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .list_batches_status_changed_between(service_id, start, end)
+    }
+
+    fn commit_batch(
+        &self,
+        id: &str,
+        service_id: &str,
+        transaction_receipts: Vec<TransactionReceipt>,
+    ) -> Result<(), BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        let rcpts: Vec<TransactionReceiptModel> = transaction_receipts
+            .iter()
+            .map(|r| TransactionReceiptModel::from((r, service_id)))
+            .collect();
+
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_timestamp_precision(self.timestamp_precision)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .commit_batch(id, service_id, rcpts)
+    }
+
+    fn created_at_bounds(
+        &self,
+        service_id: &str,
+    ) -> Result<Option<(i64, i64)>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .created_at_bounds(service_id)
+    }
+
+    fn list_batches_by_network(
+        &self,
+        network_id: &str,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .list_batches_by_network(network_id, service_id)
+    }
+
+    fn find_flapping_batches(
+        &self,
+        min_transitions: i64,
+        since: i64,
+    ) -> Result<Vec<String>, BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .find_flapping_batches(min_transitions, since)
+    }
+
+    fn sync_since(
+        &self,
+        service_id: &str,
+        checkpoint: Option<i64>,
+        limit: i64,
+    ) -> Result<(Vec<TrackingBatch>, Option<i64>), BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .sync_since(service_id, checkpoint, limit)
+    }
+
+    fn set_alias(
+        &self,
+        id: &str,
+        service_id: &str,
+        alias: Option<&str>,
+    ) -> Result<(), BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .set_alias(id, service_id, alias)
+    }
+
+    fn get_batch_by_alias(
+        &self,
+        alias: &str,
+        service_id: &str,
+    ) -> Result<Option<TrackingBatch>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .get_batch_by_alias(alias, service_id)
+    }
+
+    fn all_statuses_for_service(
+        &self,
+        service_id: &str,
+    ) -> Result<HashMap<String, BatchStatusName>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .all_statuses_for_service(service_id)
+    }
+
+    fn repair_missing_statuses(&self) -> Result<usize, BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_timestamp_precision(self.timestamp_precision)
+            .with_status_event_debounce(self.status_event_debounce)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .repair_missing_statuses()
+    }
+
+    fn list_batches_by_attempts(
+        &self,
+        service_id: &str,
+        limit: i64,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .list_batches_by_attempts(service_id, limit)
+    }
+
+    fn count_transactions(&self, service_id: &str) -> Result<i64, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .count_transactions(service_id)
+    }
+
+    fn list_failed_batches_recent(
+        &self,
+        service_id: &str,
+        offset: i64,
+        limit: i64,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .list_failed_batches_recent(service_id, offset, limit)
+    }
+
+    fn list_batches_by_kind(
+        &self,
+        kind: &str,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .list_batches_by_kind(kind, service_id)
+    }
+
+    fn get_batch_projected(
+        &self,
+        id: &str,
+        service_id: &str,
+        projection: BatchProjection,
+    ) -> Result<Option<TrackingBatch>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .get_batch_projected(id, service_id, projection)
+    }
+
+    fn find_batches_by_transaction_prefix(
+        &self,
+        prefix: &str,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .find_batches_by_transaction_prefix(prefix, service_id)
+    }
+
+    fn list_failure_summaries(
+        &self,
+        service_id: &str,
+        limit: i64,
+    ) -> Result<Vec<FailureSummary>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .list_failure_summaries(service_id, limit)
+    }
+
+    fn swap_batch_status(
+        &self,
+        id: &str,
+        service_id: &str,
+        status: BatchStatus,
+    ) -> Result<Option<BatchStatus>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        let status = status.to_string();
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_timestamp_precision(self.timestamp_precision)
+            .with_status_event_debounce(self.status_event_debounce)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .swap_batch_status(id, service_id, &status)
+    }
+
+    fn get_unsubmitted_batches_limited(
+        &self,
+        service_id: Option<&str>,
+        limit: i64,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id = service_id
+            .map(|service_id| self.resolve_service_id(service_id))
+            .transpose()?;
+
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .get_unsubmitted_batches_limited(service_id.as_deref(), limit)
+    }
+
+    fn dead_letter_batch(
+        &self,
+        id: &str,
+        service_id: &str,
+        reason: &str,
+    ) -> Result<(), BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_timestamp_precision(self.timestamp_precision)
+            .with_status_event_debounce(self.status_event_debounce)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .dead_letter_batch(id, service_id, reason)
+    }
+
+    fn get_dead_lettered_batches(
+        &self,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .get_dead_lettered_batches(service_id)
+    }
+
+    fn content_digest(&self, service_id: &str) -> Result<String, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .content_digest(service_id)
+    }
+
+    fn get_batches_awaiting_receipts(
+        &self,
+        older_than: i64,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .get_batches_awaiting_receipts(older_than, service_id)
+    }
+
+    fn failed_batches_cursor(
+        &self,
+        service_id: &str,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .failed_batches_cursor(service_id, after, limit)
+    }
+
+    fn remap_data_change_ids(
+        &self,
+        mapping: HashMap<String, String>,
+        service_id: &str,
+    ) -> Result<usize, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .remap_data_change_ids(&mapping, service_id)
+    }
+
+    fn list_batches_by_family(
+        &self,
+        family_name: &str,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .list_batches_by_family(family_name, service_id)
+    }
+
+    fn find_batches_with_excess_receipts(
+        &self,
+        service_id: &str,
+    ) -> Result<Vec<String>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .find_batches_with_excess_receipts(service_id)
+    }
+
+    fn dedupe_receipts(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<usize, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .dedupe_receipts(id, service_id)
+    }
+
+    fn get_batch_status_at(
+        &self,
+        id: &str,
+        service_id: &str,
+        at: i64,
+    ) -> Result<Option<BatchStatusName>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .get_batch_status_at(id, service_id, at)
+    }
+
+    fn try_add_batches(
+        &self,
+        batches: Vec<TrackingBatch>,
+    ) -> Result<TryAddBatchesOutcomes, BatchTrackingStoreError> {
+        let mut watched = self.unsubmitted_watchers.watched(&batches);
+
+        let outcomes = try_add_checked(
+            self.allowed_batch_kinds.as_deref(),
+            self.allowed_service_ids.as_ref(),
+            self.batch_id_validator,
+            batches,
+            |batches| {
+                BatchTrackingStoreOperations::new(self.connection)
+                    .with_timestamp_precision(self.timestamp_precision)
+                    .with_correlation_id(self.correlation_id.as_deref())
+                    .with_payload_cipher(self.payload_cipher.as_deref())
+                    .try_add_batches(batches, self.ignore_duplicate_batches)
+            },
+        )?;
+
+        watched.retain(|batch| is_added(&outcomes, batch));
+        self.send_watched(watched);
+
+        Ok(outcomes)
+    }
+
+    fn list_batches_by_submit_url(
+        &self,
+        submit_url: &str,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .list_batches_by_submit_url(submit_url, service_id)
+    }
+
+    fn find_duplicate_dcids(
+        &self,
+        service_id: &str,
+    ) -> Result<Vec<(String, Vec<String>)>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .find_duplicate_dcids(service_id)
+    }
+
+    fn count_batches_by_origin(
+        &self,
+        service_id: &str,
+        since: i64,
+    ) -> Result<HashMap<String, i64>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .count_batches_by_origin(service_id, since)
+    }
+
+    fn id_status_page(
+        &self,
+        service_id: &str,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<(String, BatchStatusName)>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .id_status_page(service_id, after, limit)
+    }
+
+    fn list_batches_depending_on(
+        &self,
+        transaction_id: &str,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .list_batches_depending_on(transaction_id, service_id)
+    }
+
+    fn tombstone_batches(
+        &self,
+        ids: &[&str],
+        service_id: &str,
+    ) -> Result<usize, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .tombstone_batches(ids, service_id)
+    }
+
+    fn submission_success_rate(
+        &self,
+        service_id: &str,
+        start: i64,
+        end: i64,
+    ) -> Result<f64, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .submission_success_rate(service_id, start, end)
+    }
+
+    fn find_batches_without_transactions(
+        &self,
+        service_id: &str,
+    ) -> Result<Vec<String>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .find_batches_without_transactions(service_id)
+    }
+
+    fn claim_unsubmitted_batches(
+        &self,
+        service_id: &str,
+        claimant: &str,
+        n: i64,
+        lease_secs: u64,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .claim_unsubmitted_batches(service_id, claimant, n, lease_secs)
+    }
+
+    fn health_snapshot(
+        &self,
+        service_id: &str,
+        stuck_threshold: i64,
+    ) -> Result<HealthSnapshot, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .health_snapshot(service_id, stuck_threshold)
+    }
+
+    fn hourly_batch_counts(
+        &self,
+        service_id: &str,
+        start: i64,
+        end: i64,
+    ) -> Result<Vec<(i64, i64)>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_timestamp_precision(self.timestamp_precision)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .hourly_batch_counts(service_id, start, end)
+    }
+
+    fn fetch_outbox(&self, limit: i64) -> Result<Vec<OutboxEvent>, BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .fetch_outbox(limit)
+    }
+
+    fn ack_outbox(&self, ids: &[i64]) -> Result<usize, BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_timestamp_precision(self.timestamp_precision)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .ack_outbox(ids)
+    }
+
+    fn dead_letter_batches(
+        &self,
+        ids: &[&str],
+        service_id: &str,
+        reason: &str,
+    ) -> Result<usize, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_timestamp_precision(self.timestamp_precision)
+            .with_status_event_debounce(self.status_event_debounce)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .dead_letter_batches(ids, service_id, reason)
+    }
+
+    fn list_batches_by_labels(
+        &self,
+        predicates: &[(String, String)],
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .list_batches_by_labels(predicates, service_id)
+    }
+
+    fn get_latest_submission_error(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<Option<SubmissionError>, BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .get_latest_submission_error(id, service_id)
+    }
+
+    fn transition_batches(
+        &self,
+        transitions: Vec<(String, BatchStatus)>,
+        service_id: &str,
+    ) -> Result<(), BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        check_service_allowed(self.allowed_service_ids.as_ref(), service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_timestamp_precision(self.timestamp_precision)
+            .with_status_event_debounce(self.status_event_debounce)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .transition_batches(transitions, service_id)
+    }
+
+    fn write_service_backup(
+        &self,
+        service_id: &str,
+        writer: &mut dyn Write,
+    ) -> Result<(), BatchTrackingStoreError> {
+        let service_id: &str = &self.resolve_service_id(service_id)?;
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .backup_service(service_id, writer)
+    }
+
+    fn read_service_backup(&self, reader: &mut dyn Read) -> Result<(), BatchTrackingStoreError> {
+        BatchTrackingStoreOperations::new(self.connection)
+            .with_timestamp_precision(self.timestamp_precision)
+            .with_correlation_id(self.correlation_id.as_deref())
+            .with_payload_cipher(self.payload_cipher.as_deref())
+            .restore_service(reader, self.allowed_service_ids.as_ref())
+    }
+}
+
+/// Adds the batches that pass the store's checks with `add`, returning the
+/// outcome for every batch in the order the batches were given
+fn try_add_checked<F>(
+    allowed_batch_kinds: Option<&[String]>,
+    allowed_service_ids: Option<&HashSet<String>>,
+    batch_id_validator: Option<BatchIdValidator>,
+    batches: Vec<TrackingBatch>,
+    add: F,
+) -> Result<TryAddBatchesOutcomes, BatchTrackingStoreError>
+where
+    F: FnOnce(Vec<TrackingBatch>) -> Result<TryAddBatchesOutcomes, BatchTrackingStoreError>,
+{
+    let mut checks = Vec::with_capacity(batches.len());
+    let mut accepted = Vec::new();
+    for batch in batches {
+        let batch_id = batch.batch_header().to_string();
+        let check = validate_batch_kinds(allowed_batch_kinds, std::slice::from_ref(&batch))
+            .and_then(|_| {
+                validate_batch_service_ids(allowed_service_ids, std::slice::from_ref(&batch))
+            })
+            .and_then(|_| validate_batch_ids(batch_id_validator, std::slice::from_ref(&batch)));
+        if check.is_ok() {
+            accepted.push(batch);
+        }
+        checks.push((batch_id, check));
+    }
+
+    let mut added = add(accepted)?.into_iter();
+
+    let mut outcomes = Vec::with_capacity(checks.len());
+    for (batch_id, check) in checks {
+        let outcome = match check {
+            Ok(()) => {
+                added
+                    .next()
+                    .ok_or_else(|| {
+                        BatchTrackingStoreError::InternalError(InternalError::with_message(
+                            format!("No outcome was reported for batch {}", batch_id),
+                        ))
+                    })?
+                    .1
+            }
+            Err(err) => Err(err),
+        };
+        outcomes.push((batch_id, outcome));
+    }
+
+    Ok(outcomes)
+}
+
+/// Returns whether a batch was added according to the outcomes of
+/// `try_add_batches`
+fn is_added(
+    outcomes: &[(String, Result<(), BatchTrackingStoreError>)],
+    batch: &TrackingBatch,
+) -> bool {
+    outcomes
+        .iter()
+        .any(|(batch_id, outcome)| batch_id == batch.batch_header() && outcome.is_ok())
+}
+
+/// Checks that writes to a service are allowed by the store's allowed service
+/// IDs, if it has any
+fn check_service_allowed(
+    allowed_service_ids: Option<&HashSet<String>>,
+    service_id: &str,
+) -> Result<(), BatchTrackingStoreError> {
+    match allowed_service_ids {
+        Some(allowed_service_ids) if !allowed_service_ids.contains(service_id) => {
+            Err(BatchTrackingStoreError::ServiceNotAllowed {
+                service_id: service_id.to_string(),
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Checks that each batch is for a service that writes are allowed for
+fn validate_batch_service_ids(
+    allowed_service_ids: Option<&HashSet<String>>,
+    batches: &[TrackingBatch],
+) -> Result<(), BatchTrackingStoreError> {
+    for batch in batches {
+        check_service_allowed(
+            allowed_service_ids,
+            batch
+                .service_id()
+                .unwrap_or(NON_SPLINTER_SERVICE_ID_DEFAULT),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Checks that each batch's ID is accepted by the store's batch ID validator,
+/// if it has one
+fn validate_batch_ids(
+    batch_id_validator: Option<BatchIdValidator>,
+    batches: &[TrackingBatch],
+) -> Result<(), BatchTrackingStoreError> {
+    if let Some(batch_id_validator) = batch_id_validator {
+        for batch in batches {
+            batch_id_validator(batch.batch_header()).map_err(|reason| {
+                BatchTrackingStoreError::InvalidBatchId {
+                    batch_id: batch.batch_header().to_string(),
+                    reason,
+                }
+            })?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that each batch's kind, if it has one, is one of the allowed kinds
+fn validate_batch_kinds(
+    allowed_batch_kinds: Option<&[String]>,
+    batches: &[TrackingBatch],
+) -> Result<(), BatchTrackingStoreError> {
+    let allowed_batch_kinds = match allowed_batch_kinds {
+        Some(allowed_batch_kinds) => allowed_batch_kinds,
+        None => return Ok(()),
+    };
+
+    for batch in batches {
+        if let Some(kind) = batch.batch_kind() {
+            if !allowed_batch_kinds.iter().any(|allowed| allowed == kind) {
+                return Err(BatchTrackingStoreError::InvalidArgumentError(
+                    InvalidArgumentError::new(
+                        "batch_kind".to_string(),
+                        format!(
+                            "Batch {} has kind {}, which is not allowed",
+                            batch.batch_header(),
+                            kind
+                        ),
+                    ),
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use cylinder::{secp256k1::Secp256k1Context, Context, Signer};
+    use diesel::prelude::*;
+    use diesel::r2d2::{ConnectionManager, Pool};
+    use diesel::sqlite::SqliteConnection;
+    use transact::protocol::{
+        batch::{Batch, BatchBuilder},
+        transaction::{HashMethod, Transaction, TransactionBuilder},
+    };
+
+    use crate::batch_tracking::store::{
+        spawn_retention_task, validate_against_batch, verify_migration, BatchBuilderError,
+        BatchMismatch, InvalidTransactionBuilder, RetentionPolicy, SubmissionErrorBuilder,
+        TrackingBatchBuilder, TransactionReceiptBuilder, ID_STATUS_PAGE_SIZE,
+    };
+    use crate::hex;
+    use crate::migrations::run_sqlite_migrations;
+    use crate::paging::Paging;
+
+    static FAMILY_NAME: &str = "test_family";
+    static FAMILY_VERSION: &str = "0.1";
+    static KEY1: &str = "111111111111111111111111111111111111111111111111111111111111111111";
+    static KEY2: &str = "222222222222222222222222222222222222222222222222222222222222222222";
+    static KEY3: &str = "333333333333333333333333333333333333333333333333333333333333333333";
+    static KEY4: &str = "444444444444444444444444444444444444444444444444444444444444444444";
+    static KEY5: &str = "555555555555555555555555555555555555555555555555555555555555555555";
+    static KEY6: &str = "666666666666666666666666666666666666666666666666666666666666666666";
+    static KEY7: &str = "777777777777777777777777777777777777777777777777777777777777777777";
+    static NONCE: &str = "f9kdzz";
+    static NONCE2: &str = "dzzf9k";
+    static BYTES2: [u8; 4] = [0x05, 0x06, 0x07, 0x08];
+
+    #[test]
+    fn add_and_fetch_batch() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let signer = new_signer();
+
+        let pair = get_transact_transaction(&*signer, NONCE);
+
+        let batch_1 = get_transact_batch(&*signer, vec![pair]);
+
+        let add_tracking_batch = get_tracking_batch(batch_1.clone(), false)
+            .build()
+            .expect("Failed to build batch");
+
+        let id = add_tracking_batch.batch_header();
+
+        store
+            .add_batches(vec![add_tracking_batch.clone()])
+            .expect("Failed to add batch");
+
+        let batch_result = store
+            .get_batch(&id, "TEST")
+            .expect("Failed to get batch")
+            .unwrap();
+        let batch_timestamp = batch_result.created_at();
+
+        let expected = get_tracking_batch(batch_1.clone(), false)
+            .with_created_at(batch_timestamp)
+            .build()
+            .expect("Failed to build batch");
+
+        assert_eq!(batch_result, expected);
+    }
+
+    #[test]
+    fn add_and_fetch_batch_with_dcid() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let signer = new_signer();
+
+        let pair = get_transact_transaction(&*signer, NONCE);
+
+        let batch_1 = get_transact_batch(&*signer, vec![pair]);
+
+        let dcid = "dcid:data_change".to_string();
+
+        let add_tracking_batch = get_tracking_batch(batch_1.clone(), false)
+            .with_data_change_id(dcid.clone())
+            .build()
+            .expect("Failed to build batch");
+
+        store
+            .add_batches(vec![add_tracking_batch.clone()])
+            .expect("Failed to add batch");
+
+        let batch_result = store
+            .get_batch(&dcid, "TEST")
+            .expect("Failed to get batch")
+            .unwrap();
+        let batch_timestamp = batch_result.created_at();
+        let expected = get_tracking_batch(batch_1.clone(), false)
+            .with_created_at(batch_timestamp)
+            .with_data_change_id(dcid)
+            .build()
+            .expect("Failed to build batch");
+
+        assert_eq!(batch_result, expected);
+    }
+
+    #[test]
+    fn test_invalid_dcid() {
+        let signer = new_signer();
+
+        let pair = get_transact_transaction(&*signer, NONCE);
+
+        let batch_1 = get_transact_batch(&*signer, vec![pair]);
+
+        let dcid = "data_change".to_string();
+
+        let add_tracking_batch = TrackingBatchBuilder::default()
+            .with_batch(batch_1.clone())
+            .with_service_id("TEST".to_string())
+            .with_data_change_id(dcid.clone())
+            .with_signer_public_key(KEY1.to_string())
+            .with_submitted(false)
+            .build();
+
+        assert!(add_tracking_batch.is_err());
+
+        let expected = BatchBuilderError::MissingRequiredField(
+            "data change IDs must be formatted as 'dcid:<id>'".to_string(),
+        );
+
+        assert_eq!(
+            add_tracking_batch.unwrap_err().to_string(),
+            expected.to_string()
+        );
+    }
+
+    #[test]
+    fn update_batch_status() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let signer = new_signer();
+
+        let pair = get_transact_transaction(&*signer, NONCE);
+
+        let transaction_id = pair.header_signature().to_string();
+
+        let batch_1 = get_transact_batch(&*signer, vec![pair]);
+
+        let tracking_batch = get_tracking_batch(batch_1.clone(), false)
+            .build()
+            .expect("Failed to build batch");
+
+        let id = tracking_batch.batch_header();
+
+        store
+            .add_batches(vec![tracking_batch.clone()])
+            .expect("Failed to add batch");
+
+        store
+            .update_batch_status(
+                &tracking_batch.batch_header(),
+                "TEST",
+                Some(BatchStatus::Pending),
+                Vec::new(),
+                None,
+            )
+            .expect("Failed to update batch");
+
+        let batch_result_1 = store
+            .get_batch(&id, "TEST")
+            .expect("Failed to get batch")
+            .unwrap();
+        let batch_result_1_timestamp = batch_result_1.created_at();
+
+        let expected_1 = get_tracking_batch(batch_1.clone(), true)
+            .with_created_at(batch_result_1_timestamp)
+            .with_batch_status(BatchStatus::Pending)
+            .build()
+            .expect("Failed to build batch");
+
+        assert_eq!(batch_result_1, expected_1);
+
+        let submission_error = SubmissionErrorBuilder::default()
+            .with_error_type("test".to_string())
+            .with_error_message("test message".to_string())
+            .build()
+            .expect("Failed to build error");
+
+        let receipt_1 = TransactionReceiptBuilder::default()
+            .with_transaction_id(transaction_id.to_string())
+            .with_result_valid(false)
+            .with_error_message("test".to_string())
+            .with_error_data(BYTES2.to_vec())
+            .with_serialized_receipt(
+                std::str::from_utf8(&BYTES2)
+                    .expect("Failed to build string")
+                    .to_string(),
+            )
+            .build()
+            .expect("Failed to build receipt");
+
+        let invalid_transactions = vec![InvalidTransactionBuilder::default()
+            .with_transaction_id(transaction_id.to_string())
+            .with_error_message("test".to_string())
+            .with_error_data(BYTES2.to_vec())
+            .build()
+            .expect("Failed to build invalid transaction")];
+
+        store
+            .update_batch_status(
+                &tracking_batch.batch_header(),
+                "TEST",
+                Some(BatchStatus::Invalid(invalid_transactions.to_vec())),
+                vec![receipt_1],
+                Some(submission_error.clone()),
+            )
+            .expect("Failed to update batch");
+
+        let batch_result_2 = store
+            .get_batch(&id, "TEST")
+            .expect("Failed to get batch")
+            .unwrap();
+        let batch_result_2_timestamp = batch_result_2.created_at();
+        let batch_result_2_latency = batch_result_2
+            .submission_latency_ms()
+            .expect("Latency not recorded");
+
+        let expected_2 = get_tracking_batch(batch_1.clone(), true)
+            .with_created_at(batch_result_2_timestamp)
+            .with_submission_latency_ms(batch_result_2_latency)
+            .with_batch_status(BatchStatus::Invalid(invalid_transactions))
+            .with_submission_error(submission_error)
+            .build()
+            .expect("Failed to build batch");
+
+        assert_eq!(batch_result_2, expected_2);
+    }
+
+    #[test]
+    fn update_batch_status_dcid() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let signer = new_signer();
+
+        let pair = get_transact_transaction(&*signer, NONCE);
+
+        let transaction_id = pair.header_signature().to_string();
+
+        let batch_1 = get_transact_batch(&*signer, vec![pair]);
+
+        let dcid = "dcid:data_change".to_string();
+
+        let tracking_batch = get_tracking_batch(batch_1.clone(), false)
+            .with_data_change_id(dcid.clone())
+            .build()
+            .expect("Failed to build batch");
+
+        store
+            .add_batches(vec![tracking_batch.clone()])
+            .expect("Failed to add batch");
+
+        store
+            .update_batch_status(&dcid, "TEST", Some(BatchStatus::Pending), Vec::new(), None)
+            .expect("Failed to update batch");
+
+        let batch_result_1 = store
+            .get_batch(&dcid, "TEST")
+            .expect("Failed to get batch")
+            .unwrap();
+        let batch_result_1_timestamp = batch_result_1.created_at();
+
+        let expected_1 = get_tracking_batch(batch_1.clone(), true)
+            .with_created_at(batch_result_1_timestamp)
+            .with_batch_status(BatchStatus::Pending)
+            .with_data_change_id(dcid.clone())
+            .build()
+            .expect("Failed to build batch");
+
+        assert_eq!(batch_result_1, expected_1);
+
+        let submission_error = SubmissionErrorBuilder::default()
+            .with_error_type("test".to_string())
+            .with_error_message("test message".to_string())
+            .build()
+            .expect("Failed to build error");
+
+        let receipt_1 = TransactionReceiptBuilder::default()
+            .with_transaction_id(transaction_id.to_string())
+            .with_result_valid(false)
+            .with_error_message("test".to_string())
+            .with_error_data(BYTES2.to_vec())
+            .with_serialized_receipt(
+                std::str::from_utf8(&BYTES2)
+                    .expect("Failed to build string")
+                    .to_string(),
+            )
+            .build()
+            .expect("Failed to build receipt");
+
+        let invalid_transactions = vec![InvalidTransactionBuilder::default()
+            .with_transaction_id(transaction_id.to_string())
+            .with_error_message("test".to_string())
+            .with_error_data(BYTES2.to_vec())
+            .build()
+            .expect("Failed to build invalid transaction")];
+
+        store
+            .update_batch_status(
+                &dcid,
+                "TEST",
+                Some(BatchStatus::Invalid(invalid_transactions.to_vec())),
+                vec![receipt_1],
+                Some(submission_error.clone()),
+            )
+            .expect("Failed to update batch");
+
+        let batch_result_2 = store
+            .get_batch(&dcid, "TEST")
+            .expect("Failed to get batch")
+            .unwrap();
+        let batch_result_2_timestamp = batch_result_2.created_at();
+        let batch_result_2_latency = batch_result_2
+            .submission_latency_ms()
+            .expect("Latency not recorded");
+
+        let expected_2 = get_tracking_batch(batch_1.clone(), true)
+            .with_created_at(batch_result_2_timestamp)
+            .with_submission_latency_ms(batch_result_2_latency)
+            .with_batch_status(BatchStatus::Invalid(invalid_transactions))
+            .with_submission_error(submission_error)
+            .with_data_change_id(dcid.clone())
+            .build()
+            .expect("Failed to build batch");
+
+        assert_eq!(batch_result_2, expected_2);
+    }
+
+    #[test]
+    fn change_batch_to_submitted() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let signer = new_signer();
+
+        let pair = get_transact_transaction(&*signer, NONCE);
+
+        let transaction_header = pair.header_signature().to_string();
+
+        let batch_1 = get_transact_batch(&*signer, vec![pair]);
+
+        let tracking_batch = get_tracking_batch(batch_1.clone(), false)
+            .build()
+            .expect("Failed to build batch");
+
+        let id = tracking_batch.batch_header();
+
+        let txn_receipts = vec![TransactionReceiptBuilder::default()
+            .with_transaction_id(transaction_header)
+            .with_result_valid(true)
+            .with_serialized_receipt(
+                std::str::from_utf8(&BYTES2)
+                    .expect("Failed to build string")
+                    .to_string(),
+            )
+            .build()
+            .expect("Failed to build receipt")];
+
+        let submission_error = SubmissionErrorBuilder::default()
+            .with_error_type("test".to_string())
+            .with_error_message("test message".to_string())
+            .build()
+            .expect("Failed to build error");
+
+        store
+            .add_batches(vec![tracking_batch.clone()])
+            .expect("Failed to add batch");
+
+        store
+            .change_batch_to_submitted(
+                &id,
+                "TEST",
+                txn_receipts,
+                Some("Pending"),
+                Some(submission_error),
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .expect("Failed to change batch to submitted");
+
+        let batch = store
+            .get_batch(&id, "TEST")
+            .expect("Failed to get batch")
+            .unwrap();
+
+        assert_eq!(batch.submitted(), true)
+    }
+
+    #[test]
+    fn change_batch_to_submitted_dcid() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let signer = new_signer();
+
+        let pair = get_transact_transaction(&*signer, NONCE);
+
+        let transaction_header = pair.header_signature().to_string();
+
+        let batch_1 = get_transact_batch(&*signer, vec![pair]);
+
+        let dcid = "dcid:data_change".to_string();
+
+        let tracking_batch = get_tracking_batch(batch_1.clone(), false)
+            .with_data_change_id(dcid.clone())
+            .build()
+            .expect("Failed to build batch");
+
+        let txn_receipts = vec![TransactionReceiptBuilder::default()
+            .with_transaction_id(transaction_header)
+            .with_result_valid(true)
+            .with_serialized_receipt(
+                std::str::from_utf8(&BYTES2)
+                    .expect("Failed to build string")
+                    .to_string(),
+            )
+            .build()
+            .expect("Failed to build receipt")];
+
+        let submission_error = SubmissionErrorBuilder::default()
+            .with_error_type("test".to_string())
+            .with_error_message("test message".to_string())
+            .build()
+            .expect("Failed to build error");
+
+        store
+            .add_batches(vec![tracking_batch.clone()])
+            .expect("Failed to add batch");
+
+        store
+            .change_batch_to_submitted(
+                &dcid,
+                "TEST",
+                txn_receipts,
+                Some("Pending"),
+                Some(submission_error),
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .expect("Failed to change batch to submitted");
+
+        let batch = store
+            .get_batch(&dcid, "TEST")
+            .expect("Failed to get batch")
+            .unwrap();
+
+        assert_eq!(batch.submitted(), true)
+    }
+
+    #[test]
+    fn change_batch_to_submitted_no_batch() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let res = store
+            .change_batch_to_submitted(
+                "id",
+                "TEST",
+                Vec::new(),
+                Some("Pending"),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .unwrap_err();
+
+        assert_eq!(
+            res.to_string(),
+            BatchTrackingStoreError::NotFoundError("Could not find batch with ID id".to_string())
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn get_unsubmitted_batches() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let signer = new_signer();
+
+        let pair_1 = get_transact_transaction(&*signer, NONCE);
+
+        let pair_2 = get_transact_transaction(&*signer, NONCE2);
+
+        let batch_1 = get_transact_batch(&*signer, vec![pair_1]);
+
+        let batch_2 = get_transact_batch(&*signer, vec![pair_2]);
+
+        let tracking_batch_1 = get_tracking_batch(batch_1.clone(), false)
+            .build()
+            .expect("Failed to build batch");
+
+        let id = tracking_batch_1.batch_header();
+
+        let tracking_batch_2 = get_tracking_batch(batch_2.clone(), true)
+            .build()
+            .expect("Failed to build batch");
+
+        store
+            .add_batches(vec![tracking_batch_1.clone(), tracking_batch_2])
+            .expect("Failed to add batches");
+
+        let batch_result = store
+            .get_batch(&id, "TEST")
+            .expect("Failed to get batch")
+            .unwrap();
+        let batch_result_timestamp = batch_result.created_at();
+
+        let expected_batch = get_tracking_batch(batch_1.clone(), false)
+            .with_created_at(batch_result_timestamp)
+            .build()
+            .expect("Failed to build batch");
+
+        let expected = TrackingBatchList {
+            batches: vec![expected_batch],
+        };
+
+        assert_eq!(
+            store
+                .get_unsubmitted_batches()
+                .expect("Failed to get batch"),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_list_pending_batches() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let signer = new_signer();
+
+        let pair = get_transact_transaction(&*signer, NONCE);
+
+        let batch_1 = get_transact_batch(&*signer, vec![pair]);
+
+        let tracking_batch = get_tracking_batch(batch_1.clone(), false)
+            .build()
+            .expect("Failed to build batch");
+
+        let id = tracking_batch.batch_header();
+
+        store
+            .add_batches(vec![tracking_batch.clone()])
+            .expect("Failed to add batch");
+
+        let batch_result = store
+            .get_batch(&id, "TEST")
+            .expect("Failed to get batch")
+            .unwrap();
+        let batch_result_timestamp = batch_result.created_at();
+
+        store
+            .update_batch_status(&id, "TEST", Some(BatchStatus::Pending), Vec::new(), None)
+            .expect("Failed to update batch");
+
+        let expected = get_tracking_batch(batch_1.clone(), true)
+            .with_created_at(batch_result_timestamp)
+            .with_batch_status(BatchStatus::Pending)
+            .build()
+            .expect("Failed to build batch");
+
+        assert_eq!(
+            store
+                .list_batches_by_status(BatchStatus::Pending)
+                .expect("Failed to get batch"),
+            TrackingBatchList {
+                batches: vec![expected]
+            }
+        );
+
+        store
+            .update_batch_status(&id, "TEST", Some(BatchStatus::Unknown), Vec::new(), None)
+            .expect("Failed to update batch");
+
+        assert_eq!(
+            store
+                .list_batches_by_status(BatchStatus::Pending)
+                .expect("Failed to get batch"),
+            TrackingBatchList {
+                batches: Vec::new()
+            }
+        );
+    }
+
+    #[test]
+    fn test_get_failed_batches() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let signer = new_signer();
+
+        let pair = get_transact_transaction(&*signer, NONCE);
+
+        let transaction_id = pair.header_signature().to_string();
+
+        let batch_1 = get_transact_batch(&*signer, vec![pair]);
+
+        let tracking_batch = get_tracking_batch(batch_1.clone(), false)
+            .build()
+            .expect("Failed to build batch");
+
+        let id = tracking_batch.batch_header();
+
+        store
+            .add_batches(vec![tracking_batch.clone()])
+            .expect("Failed to add batch");
+
+        let batch_result = store
+            .get_batch(&id, "TEST")
+            .expect("Failed to get batch")
+            .unwrap();
+        let batch_result_timestamp = batch_result.created_at();
+
+        let submission_error = SubmissionErrorBuilder::default()
+            .with_error_type("test".to_string())
+            .with_error_message("test message".to_string())
+            .build()
+            .expect("Failed to build error");
+
+        let receipt_1 = TransactionReceiptBuilder::default()
+            .with_transaction_id(transaction_id.to_string())
+            .with_result_valid(false)
+            .with_error_message("test".to_string())
+            .with_error_data(BYTES2.to_vec())
+            .with_serialized_receipt(
+                std::str::from_utf8(&BYTES2)
+                    .expect("Failed to build string")
+                    .to_string(),
+            )
+            .build()
+            .expect("Failed to build receipt");
+
+        let invalid_transactions = vec![InvalidTransactionBuilder::default()
+            .with_transaction_id(transaction_id.to_string())
+            .with_error_message("test".to_string())
+            .with_error_data(BYTES2.to_vec())
+            .build()
+            .expect("Failed to build transaction")];
+
+        store
+            .update_batch_status(
+                &id,
+                "TEST",
+                Some(BatchStatus::Invalid(invalid_transactions.to_vec())),
+                vec![receipt_1],
+                Some(submission_error.clone()),
+            )
+            .expect("Failed to update batch");
+
+        let batch_result_latency = store
+            .get_batch(id, "TEST")
+            .expect("Failed to get batch")
+            .unwrap()
+            .submission_latency_ms()
+            .expect("Latency not recorded");
+
+        let expected = get_tracking_batch(batch_1.clone(), true)
+            .with_created_at(batch_result_timestamp)
+            .with_submission_latency_ms(batch_result_latency)
+            .with_batch_status(BatchStatus::Invalid(invalid_transactions))
+            .with_submission_error(submission_error)
+            .build()
+            .expect("Failed to build batch");
+
+        assert_eq!(
+            store.get_failed_batches().expect("Failed to get batch"),
+            TrackingBatchList {
+                batches: vec![expected]
+            }
+        );
+
+        store
+            .update_batch_status(&id, "TEST", Some(BatchStatus::Pending), Vec::new(), None)
+            .expect("Failed to update batch");
+
+        assert_eq!(
+            store.get_failed_batches().expect("Failed to get batch"),
+            TrackingBatchList {
+                batches: Vec::new()
+            }
+        );
+    }
+
+    #[test]
+    fn test_clean_stale_records() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let signer = new_signer();
+
+        let pair = get_transact_transaction(&*signer, NONCE);
+
+        let batch_1 = get_transact_batch(&*signer, vec![pair]);
+
+        let tracking_batch = get_tracking_batch(batch_1.clone(), false)
+            .build()
+            .expect("Failed to build batch");
+
+        let id = tracking_batch.batch_header();
+
+        store
+            .add_batches(vec![tracking_batch.clone()])
+            .expect("Failed to add batch");
+
+        let batch_result = store
+            .get_batch(&id, "TEST")
+            .expect("Failed to get batch")
+            .unwrap();
+        let batch_timestamp = batch_result.created_at();
+
+        store
+            .clean_stale_records(batch_timestamp + 1)
+            .expect("Failed to clean records");
+
+        assert_eq!(
+            store.get_batch(&id, "TEST").expect("Failed to get batch"),
+            None
+        )
+    }
+
+    #[test]
+    fn test_tombstone_batch() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let signer = new_signer();
+
+        let pair = get_transact_transaction(&*signer, NONCE);
+
+        let batch_1 = get_transact_batch(&*signer, vec![pair]);
+
+        let tracking_batch = get_tracking_batch(batch_1.clone(), false)
+            .build()
+            .expect("Failed to build batch");
+
+        let id = tracking_batch.batch_header();
+
+        store
+            .tombstone_batch(id, "TEST")
+            .expect("Failed to tombstone batch");
+
+        let res = store.add_batches(vec![tracking_batch.clone()]).unwrap_err();
+
+        assert_eq!(
+            res.to_string(),
+            BatchTrackingStoreError::Tombstoned(id.to_string()).to_string()
+        );
+
+        assert_eq!(
+            store.get_batch(id, "TEST").expect("Failed to get batch"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_get_recent_failures() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool.clone());
+
+        let signer = new_signer();
+
+        let set_created_at = |id: &str, created_at: i64| {
+            diesel::update(
+                schema::batches::table.filter(
+                    schema::batches::batch_id
+                        .eq(id)
+                        .and(schema::batches::service_id.eq("TEST")),
+                ),
+            )
+            .set(schema::batches::created_at.eq(created_at))
+            .execute(&*pool.get().expect("Failed to get connection"))
+            .expect("Failed to set created_at");
+        };
+
+        let mut tracking_batches = Vec::new();
+        let mut transaction_ids = Vec::new();
+        for nonce in &[NONCE, NONCE2, "zz9kdf", "kd9fzz"] {
+            let pair = get_transact_transaction(&*signer, nonce);
+            transaction_ids.push(pair.header_signature().to_string());
+            let batch = get_transact_batch(&*signer, vec![pair]);
+            tracking_batches.push(
+                get_tracking_batch(batch, false)
+                    .build()
+                    .expect("Failed to build batch"),
+            );
+        }
+
+        store
+            .add_batches(tracking_batches.clone())
+            .expect("Failed to add batches");
+
+        let ids: Vec<String> = tracking_batches
+            .iter()
+            .map(|b| b.batch_header().to_string())
+            .collect();
+
+        // The first batch is invalid and has a submission error
+        let submission_error = SubmissionErrorBuilder::default()
+            .with_error_type("test".to_string())
+            .with_error_message("test message".to_string())
+            .build()
+            .expect("Failed to build error");
+
+        let receipt = TransactionReceiptBuilder::default()
+            .with_transaction_id(transaction_ids[0].to_string())
+            .with_result_valid(false)
+            .with_error_message("test".to_string())
+            .with_error_data(BYTES2.to_vec())
+            .with_serialized_receipt(
+                std::str::from_utf8(&BYTES2)
+                    .expect("Failed to build string")
+                    .to_string(),
+            )
+            .build()
+            .expect("Failed to build receipt");
+
+        let invalid_transactions = [InvalidTransactionBuilder::default()
+            .with_transaction_id(transaction_ids[0].to_string())
+            .with_error_message("test".to_string())
+            .with_error_data(BYTES2.to_vec())
+            .build()
+            .expect("Failed to build transaction")];
+
+        store
+            .update_batch_status(
+                &ids[0],
+                "TEST",
+                Some(BatchStatus::Invalid(invalid_transactions.to_vec())),
+                vec![receipt],
+                Some(submission_error.clone()),
+            )
+            .expect("Failed to update batch");
+
+        // The second and third batches are unknown, the fourth is still
+        // pending and should never be returned
+        for id in &ids[1..3] {
+            store
+                .update_batch_status(id, "TEST", Some(BatchStatus::Unknown), Vec::new(), None)
+                .expect("Failed to update batch");
+        }
+        store
+            .update_batch_status(
+                &ids[3],
+                "TEST",
+                Some(BatchStatus::Pending),
+                Vec::new(),
+                None,
+            )
+            .expect("Failed to update batch");
+
+        set_created_at(&ids[0], 300);
+        set_created_at(&ids[1], 200);
+        set_created_at(&ids[2], 100);
+        set_created_at(&ids[3], 400);
+
+        let failures = store
+            .get_recent_failures("TEST", 2)
+            .expect("Failed to get recent failures");
+
+        assert_eq!(failures.len(), 2);
+
+        let expected = store
+            .get_batch(&ids[0], "TEST")
+            .expect("Failed to get batch")
+            .expect("Batch not found");
+        assert_eq!(failures[0].batch(), &expected);
+        assert_eq!(failures[0].submission_error(), Some(&submission_error));
+        assert_eq!(
+            failures[0].invalid_transactions(),
+            &invalid_transactions[..]
+        );
+
+        assert_eq!(failures[1].batch().batch_header(), ids[1]);
+        assert_eq!(failures[1].batch().transactions().len(), 1);
+        assert_eq!(failures[1].submission_error(), None);
+        assert!(failures[1].invalid_transactions().is_empty());
+
+        assert!(store
+            .get_recent_failures("OTHER", 2)
+            .expect("Failed to get recent failures")
+            .is_empty());
+    }
+
+    #[test]
+    fn test_compact() {
+        let path = std::env::temp_dir().join(format!(
+            "grid-batch-tracking-compact-{}.db",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+
+        let connection_manager =
+            ConnectionManager::<SqliteConnection>::new(path.to_string_lossy().to_string());
+        let pool = Pool::builder()
+            .max_size(1)
+            .build(connection_manager)
+            .expect("Failed to build connection pool");
+
+        run_sqlite_migrations(&pool.get().expect("Failed to get connection for migrations"))
+            .expect("Failed to run migrations");
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let signer = new_signer();
+
+        let tracking_batches = (0..200)
+            .map(|i| {
+                let pair = get_transact_transaction(&*signer, &format!("nonce{}", i));
+                let batch = get_transact_batch(&*signer, vec![pair]);
+                get_tracking_batch(batch, false)
+                    .build()
+                    .expect("Failed to build batch")
+            })
+            .collect();
+
+        store
+            .add_batches(tracking_batches)
+            .expect("Failed to add batches");
+
+        store
+            .clean_stale_records(i64::MAX)
+            .expect("Failed to clean records");
+
+        let size_before = std::fs::metadata(&path)
+            .expect("Failed to read database file")
+            .len();
+
+        store.compact().expect("Failed to compact database");
+
+        let size_after = std::fs::metadata(&path)
+            .expect("Failed to read database file")
+            .len();
+
+        std::fs::remove_file(&path).expect("Failed to remove database file");
+
+        assert!(size_after < size_before);
+    }
+
+    #[test]
+    fn test_millisecond_timestamp_precision() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool)
+            .with_timestamp_precision(TimestampPrecision::Milliseconds);
+
+        let signer = new_signer();
+
+        let tracking_batch_1 = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE)]),
+            false,
+        )
+        .build()
+        .expect("Failed to build batch");
+
+        let tracking_batch_2 = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE2)]),
+            false,
+        )
+        .build()
+        .expect("Failed to build batch");
+
+        store
+            .add_batches(vec![tracking_batch_1.clone()])
+            .expect("Failed to add batch");
+
+        std::thread::sleep(std::time::Duration::from_millis(2));
+
+        store
+            .add_batches(vec![tracking_batch_2.clone()])
+            .expect("Failed to add batch");
+
+        let created_at_1 = store
+            .get_batch(tracking_batch_1.batch_header(), "TEST")
+            .expect("Failed to get batch")
+            .expect("Batch not found")
+            .created_at();
+        let created_at_2 = store
+            .get_batch(tracking_batch_2.batch_header(), "TEST")
+            .expect("Failed to get batch")
+            .expect("Batch not found")
+            .created_at();
+
+        assert!(created_at_2 > created_at_1);
+
+        // Records created before the second batch are removed when
+        // cleaning by a millisecond timestamp
+        store
+            .clean_stale_records(created_at_2)
+            .expect("Failed to clean records");
+
+        assert_eq!(
+            store
+                .get_batch(tracking_batch_1.batch_header(), "TEST")
+                .expect("Failed to get batch"),
+            None
+        );
+        assert!(store
+            .get_batch(tracking_batch_2.batch_header(), "TEST")
+            .expect("Failed to get batch")
+            .is_some());
+    }
+
+    #[test]
+    fn test_status_distribution_between() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool.clone());
+
+        let signer = new_signer();
+
+        let mut ids = Vec::new();
+        for nonce in &["n1", "n2", "n3", "n4", "n5"] {
+            let batch =
+                get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, nonce)]);
+            let tracking_batch = get_tracking_batch(batch, false)
+                .build()
+                .expect("Failed to build batch");
+            ids.push(tracking_batch.batch_header().to_string());
+            store
+                .add_batches(vec![tracking_batch])
+                .expect("Failed to add batch");
+        }
+
+        let statuses_and_times = [
+            (BatchStatus::Pending, 100),
+            (BatchStatus::Pending, 150),
+            (BatchStatus::Unknown, 199),
+            // Outside of the window
+            (BatchStatus::Pending, 200),
+            (BatchStatus::Unknown, 99),
+        ];
+
+        for (id, (status, created_at)) in ids.iter().zip(statuses_and_times.iter()) {
+            store
+                .update_batch_status(id, "TEST", Some(status.clone()), Vec::new(), None)
+                .expect("Failed to update batch");
+
+            diesel::update(
+                schema::batches::table.filter(
+                    schema::batches::batch_id
+                        .eq(id)
+                        .and(schema::batches::service_id.eq("TEST")),
+                ),
+            )
+            .set(schema::batches::created_at.eq(created_at))
+            .execute(&*pool.get().expect("Failed to get connection"))
+            .expect("Failed to set created_at");
+        }
+
+        let distribution = store
+            .status_distribution_between("TEST", 100, 200)
+            .expect("Failed to get status distribution");
+
+        let mut expected = HashMap::new();
+        expected.insert(BatchStatusName::Pending, 2);
+        expected.insert(BatchStatusName::Unknown, 1);
+
+        assert_eq!(distribution, expected);
+
+        assert!(store
+            .status_distribution_between("OTHER", 0, i64::MAX)
+            .expect("Failed to get status distribution")
+            .is_empty());
+    }
+
+    #[test]
+    fn test_add_batches_concurrently() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = Arc::new(DieselBatchTrackingStore::new(pool));
+
+        let signer = new_signer();
+
+        let tracking_batches: Vec<TrackingBatch> = ["n1", "n2", "n3"]
+            .iter()
+            .map(|nonce| {
+                let batch =
+                    get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, nonce)]);
+                get_tracking_batch(batch, false)
+                    .build()
+                    .expect("Failed to build batch")
+            })
+            .collect();
+
+        let ids: Vec<String> = tracking_batches
+            .iter()
+            .map(|b| b.batch_header().to_string())
+            .collect();
+
+        // Both sets contain the second batch
+        let sets = vec![
+            tracking_batches[0..2].to_vec(),
+            tracking_batches[1..3].to_vec(),
+        ];
+
+        let handles: Vec<_> = sets
+            .into_iter()
+            .map(|set| {
+                let store = Arc::clone(&store);
+                std::thread::spawn(move || {
+                    // Store errors can not be sent between threads, so only
+                    // whether the error was a constraint violation is returned
+                    store.add_batches(set).map_err(|err| {
+                        matches!(err, BatchTrackingStoreError::ConstraintViolationError(_))
+                    })
+                })
+            })
+            .collect();
+
+        let results: Vec<Result<(), bool>> = handles
+            .into_iter()
+            .map(|handle| handle.join().expect("Thread panicked"))
+            .collect();
+
+        let winner = results
+            .iter()
+            .position(|res| res.is_ok())
+            .expect("One call should succeed");
+
+        assert_eq!(results[1 - winner], Err(true));
+
+        // The losing call must not have added its non-overlapping batch
+        let loser_only = if winner == 0 { &ids[2] } else { &ids[0] };
+        assert_eq!(
+            store
+                .get_batch(loser_only, "TEST")
+                .expect("Failed to get batch"),
+            None
+        );
+        assert!(store
+            .get_batch(&ids[1], "TEST")
+            .expect("Failed to get batch")
+            .is_some());
+    }
+
+    #[test]
+    fn test_add_batches_concurrently_ignoring_duplicates() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store =
+            Arc::new(DieselBatchTrackingStore::new(pool).with_ignore_duplicate_batches(true));
+
+        let signer = new_signer();
+
+        let tracking_batches: Vec<TrackingBatch> = ["n1", "n2", "n3"]
+            .iter()
+            .map(|nonce| {
+                let batch =
+                    get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, nonce)]);
+                get_tracking_batch(batch, false)
+                    .build()
+                    .expect("Failed to build batch")
+            })
+            .collect();
+
+        let sets = vec![
+            tracking_batches[0..2].to_vec(),
+            tracking_batches[1..3].to_vec(),
+        ];
+
+        let handles: Vec<_> = sets
+            .into_iter()
+            .map(|set| {
+                let store = Arc::clone(&store);
+                std::thread::spawn(move || store.add_batches(set).map_err(|err| err.to_string()))
+            })
+            .collect();
+
+        for handle in handles {
+            handle
+                .join()
+                .expect("Thread panicked")
+                .expect("Failed to add batches");
+        }
+
+        for batch in tracking_batches {
+            let stored = store
+                .get_batch(batch.batch_header(), "TEST")
+                .expect("Failed to get batch")
+                .expect("Batch not found");
+            assert_eq!(stored.transactions(), batch.transactions());
+        }
+    }
+
+    #[test]
+    fn test_get_batch_submission_info() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let signer = new_signer();
+
+        let tracking_batch_1 = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE)]),
+            false,
+        )
+        .build()
+        .expect("Failed to build batch");
+        let tracking_batch_2 = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE2)]),
+            false,
+        )
+        .build()
+        .expect("Failed to build batch");
+
+        let id_1 = tracking_batch_1.batch_header().to_string();
+        let id_2 = tracking_batch_2.batch_header().to_string();
+
+        store
+            .add_batches(vec![tracking_batch_1, tracking_batch_2])
+            .expect("Failed to add batches");
+
+        assert_eq!(
+            store
+                .get_batch_submission_info(&id_1, "TEST")
+                .expect("Failed to get submission info"),
+            None
+        );
+
+        assert!(matches!(
+            store.change_batch_to_submitted(
+                &id_1,
+                "TEST",
+                Vec::new(),
+                Some("Pending"),
+                None,
+                None,
+                Some(&serde_json::json!(["x-request-id"])),
+                None,
+                None,
+                None
+            ),
+            Err(BatchTrackingStoreError::InvalidArgumentError(_))
+        ));
+
+        let headers =
+            serde_json::json!({"x-request-id": "abc", "content-type": "application/octet-stream"});
+        store
+            .change_batch_to_submitted(
+                &id_1,
+                "TEST",
+                Vec::new(),
+                Some("Pending"),
+                None,
+                Some(&BYTES2),
+                Some(&headers),
+                None,
+                None,
+                None,
+            )
+            .expect("Failed to change batch to submitted");
+
+        store
+            .change_batch_to_submitted(
+                &id_2,
+                "TEST",
+                Vec::new(),
+                Some("Pending"),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .expect("Failed to change batch to submitted");
+
+        let info = store
+            .get_batch_submission_info(&id_1, "TEST")
+            .expect("Failed to get submission info")
+            .expect("Submission info not found");
+        assert_eq!(info.submitter_response(), Some(&BYTES2[..]));
+        assert_eq!(info.submit_headers(), Some(&headers));
+        assert_eq!(info.submission_error(), None);
+
+        let info = store
+            .get_batch_submission_info(&id_2, "TEST")
+            .expect("Failed to get submission info")
+            .expect("Submission info not found");
+        assert_eq!(info.submitter_response(), None);
+        assert_eq!(info.submit_headers(), None);
+    }
+
+    #[test]
+    fn test_update_batch_status_receipts_require_status() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let signer = new_signer();
+
+        let pair = get_transact_transaction(&*signer, NONCE);
+        let transaction_id = pair.header_signature().to_string();
+
+        let tracking_batch = get_tracking_batch(get_transact_batch(&*signer, vec![pair]), false)
+            .build()
+            .expect("Failed to build batch");
+        let id = tracking_batch.batch_header().to_string();
+
+        store
+            .add_batches(vec![tracking_batch])
+            .expect("Failed to add batch");
+
+        let receipt = TransactionReceiptBuilder::default()
+            .with_transaction_id(transaction_id.to_string())
+            .with_result_valid(true)
+            .with_serialized_receipt(
+                std::str::from_utf8(&BYTES2)
+                    .expect("Failed to build string")
+                    .to_string(),
+            )
+            .build()
+            .expect("Failed to build receipt");
+
+        // Receipts without a status are rejected
+        assert!(matches!(
+            store.update_batch_status(&id, "TEST", None, vec![receipt.clone()], None),
+            Err(BatchTrackingStoreError::InvalidArgumentError(_))
+        ));
+
+        // A status may be set without receipts
+        store
+            .update_batch_status(&id, "TEST", Some(BatchStatus::Pending), Vec::new(), None)
+            .expect("Failed to update batch");
+        assert_eq!(
+            store
+                .get_batch_status(&id, "TEST")
+                .expect("Failed to get batch status"),
+            Some(BatchStatus::Pending)
+        );
+
+        // Receipts may be stored explicitly without changing the status
+        store
+            .store_receipts_only(&id, "TEST", vec![receipt.clone()])
+            .expect("Failed to store receipts");
+        assert_eq!(
+            store
+                .get_batch_status(&id, "TEST")
+                .expect("Failed to get batch status"),
+            Some(BatchStatus::Pending)
+        );
+
+        // Receipts for transactions outside of the batch are rejected
+        let other = TransactionReceiptBuilder::default()
+            .with_transaction_id("other".to_string())
+            .with_result_valid(true)
+            .with_serialized_receipt("other".to_string())
+            .build()
+            .expect("Failed to build receipt");
+        assert!(matches!(
+            store.store_receipts_only(&id, "TEST", vec![other]),
+            Err(BatchTrackingStoreError::InvalidArgumentError(_))
+        ));
+
+        // The previously stored receipts are used for the new status
+        store
+            .update_batch_status(
+                &id,
+                "TEST",
+                Some(BatchStatus::Committed(Vec::new())),
+                Vec::new(),
+                None,
+            )
+            .expect("Failed to update batch");
+        match store
+            .get_batch_status(&id, "TEST")
+            .expect("Failed to get batch status")
+        {
+            Some(BatchStatus::Committed(txns)) => {
+                assert_eq!(txns.len(), 1);
+                assert_eq!(txns[0].transaction_id(), transaction_id);
+            }
+            status => panic!("Unexpected batch status {:?}", status),
+        }
+
+        // A status may be set along with receipts
+        store
+            .update_batch_status(
+                &id,
+                "TEST",
+                Some(BatchStatus::Valid(Vec::new())),
+                vec![receipt],
+                None,
+            )
+            .expect("Failed to update batch");
+        match store
+            .get_batch_status(&id, "TEST")
+            .expect("Failed to get batch status")
+        {
+            Some(BatchStatus::Valid(txns)) => {
+                assert_eq!(txns.len(), 1);
+                assert_eq!(txns[0].transaction_id(), transaction_id);
+            }
+            status => panic!("Unexpected batch status {:?}", status),
+        }
+    }
+
+    #[test]
+    fn test_find_committed_batches_missing_receipts() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let signer = new_signer();
+
+        let valid_receipt = |transaction_id: &str| {
+            TransactionReceiptBuilder::default()
+                .with_transaction_id(transaction_id.to_string())
+                .with_result_valid(true)
+                .with_serialized_receipt(
+                    std::str::from_utf8(&BYTES2)
+                        .expect("Failed to build string")
+                        .to_string(),
+                )
+                .build()
+                .expect("Failed to build receipt")
+        };
+
+        // A committed batch with a receipt for only one of its transactions
+        let pair_1 = get_transact_transaction(&*signer, "n1");
+        let pair_2 = get_transact_transaction(&*signer, "n2");
+        let txn_id_1 = pair_1.header_signature().to_string();
+        let missing = get_tracking_batch(get_transact_batch(&*signer, vec![pair_1, pair_2]), false)
+            .build()
+            .expect("Failed to build batch");
+
+        // A committed batch with all of its receipts
+        let pair_3 = get_transact_transaction(&*signer, "n3");
+        let txn_id_3 = pair_3.header_signature().to_string();
+        let complete = get_tracking_batch(get_transact_batch(&*signer, vec![pair_3]), false)
+            .build()
+            .expect("Failed to build batch");
+
+        // A batch that has not been committed
+        let pending = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, "n4")]),
+            false,
+        )
+        .build()
+        .expect("Failed to build batch");
+
+        store
+            .add_batches(vec![missing.clone(), complete.clone(), pending.clone()])
+            .expect("Failed to add batches");
+
+        store
+            .update_batch_status(
+                missing.batch_header(),
+                "TEST",
+                Some(BatchStatus::Committed(Vec::new())),
+                vec![valid_receipt(&txn_id_1)],
+                None,
+            )
+            .expect("Failed to update batch");
+        store
+            .update_batch_status(
+                complete.batch_header(),
+                "TEST",
+                Some(BatchStatus::Committed(Vec::new())),
+                vec![valid_receipt(&txn_id_3)],
+                None,
+            )
+            .expect("Failed to update batch");
+        store
+            .update_batch_status(
+                pending.batch_header(),
+                "TEST",
+                Some(BatchStatus::Pending),
+                Vec::new(),
+                None,
+            )
+            .expect("Failed to update batch");
+
+        assert_eq!(
+            store
+                .find_committed_batches_missing_receipts("TEST")
+                .expect("Failed to find batches"),
+            vec![missing.batch_header().to_string()]
+        );
+
+        assert!(store
+            .find_committed_batches_missing_receipts("OTHER")
+            .expect("Failed to find batches")
+            .is_empty());
+    }
+
+    #[test]
+    fn test_with_read_pool() {
+        // The pools are connected to separate databases so that the database
+        // each method uses can be observed
+        let write_pool = create_connection_pool_and_migrate();
+        let read_pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::with_read_pool(write_pool.clone(), read_pool.clone());
+        let write_store = DieselBatchTrackingStore::new(write_pool);
+        let read_store = DieselBatchTrackingStore::new(read_pool);
+
+        let signer = new_signer();
+
+        let written = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE)]),
+            false,
+        )
+        .build()
+        .expect("Failed to build batch");
+
+        store
+            .add_batches(vec![written.clone()])
+            .expect("Failed to add batch");
+
+        assert!(write_store
+            .get_batch(written.batch_header(), "TEST")
+            .expect("Failed to get batch")
+            .is_some());
+        assert_eq!(
+            store
+                .get_batch(written.batch_header(), "TEST")
+                .expect("Failed to get batch"),
+            None
+        );
+
+        let replicated = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE2)]),
+            false,
+        )
+        .build()
+        .expect("Failed to build batch");
+
+        read_store
+            .add_batches(vec![replicated.clone()])
+            .expect("Failed to add batch");
+
+        assert!(store
+            .get_batch(replicated.batch_header(), "TEST")
+            .expect("Failed to get batch")
+            .is_some());
+        assert_eq!(
+            store
+                .get_unsubmitted_batches()
+                .expect("Failed to get batches")
+                .batches
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_list_batches_by_state_address() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let signer = new_signer();
+
+        // The transaction reads from KEY4 and writes to KEY6
+        let batch = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE)]),
+            false,
+        )
+        .build()
+        .expect("Failed to build batch");
+
+        let other_transaction = TransactionBuilder::new()
+            .with_batcher_public_key(hex::parse_hex(KEY1).unwrap())
+            .with_family_name(FAMILY_NAME.to_string())
+            .with_family_version(FAMILY_VERSION.to_string())
+            .with_inputs(vec![hex::parse_hex(KEY2).unwrap()])
+            .with_nonce(NONCE2.to_string().into_bytes())
+            .with_outputs(vec![hex::parse_hex(KEY2).unwrap()])
+            .with_payload_hash_method(HashMethod::Sha512)
+            .with_payload(BYTES2.to_vec())
+            .build(&*signer)
+            .expect("Failed to build transaction");
+
+        let other_batch =
+            get_tracking_batch(get_transact_batch(&*signer, vec![other_transaction]), false)
+                .build()
+                .expect("Failed to build batch");
+
+        store
+            .add_batches(vec![batch.clone(), other_batch.clone()])
+            .expect("Failed to add batches");
+
+        let found = store
+            .list_batches_by_state_address(KEY4, "TEST")
+            .expect("Failed to list batches")
+            .batches;
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].batch_header(), batch.batch_header());
+        assert_eq!(found[0].transactions(), batch.transactions());
+        let found = store
+            .list_batches_by_state_address(KEY6, "TEST")
+            .expect("Failed to list batches")
+            .batches;
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].batch_header(), batch.batch_header());
+
+        let found = store
+            .list_batches_by_state_address(KEY2, "TEST")
+            .expect("Failed to list batches")
+            .batches;
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].batch_header(), other_batch.batch_header());
+        assert!(store
+            .list_batches_by_state_address(KEY3, "TEST")
+            .expect("Failed to list batches")
+            .batches
+            .is_empty());
+        assert!(store
+            .list_batches_by_state_address(KEY4, "OTHER")
+            .expect("Failed to list batches")
+            .batches
+            .is_empty());
+    }
+
+    #[test]
+    fn test_internal_error_includes_operation_name() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool.clone());
+
+        // Removing a table out from under the store forces a database error
+        diesel::sql_query("DROP TABLE submissions")
+            .execute(&*pool.get().expect("Failed to get connection"))
+            .expect("Failed to drop table");
+
+        match store.get_batch_submission_info("batch", "TEST") {
+            Err(err @ BatchTrackingStoreError::InternalError(_)) => {
+                assert!(err.to_string().starts_with("get_batch_submission_info: "))
+            }
+            res => panic!("Expected an internal error, got {:?}", res),
+        }
+    }
+
+    #[test]
+    fn test_submission_latency() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool.clone());
+
+        let signer = new_signer();
+
+        let set_created_at = |id: &str, created_at: i64| {
+            diesel::update(
+                schema::batches::table.filter(
+                    schema::batches::batch_id
+                        .eq(id)
+                        .and(schema::batches::service_id.eq("TEST")),
+                ),
+            )
+            .set(schema::batches::created_at.eq(created_at))
+            .execute(&*pool.get().expect("Failed to get connection"))
+            .expect("Failed to set created_at");
+        };
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("Failed to get time")
+            .as_secs() as i64;
+
+        let recent = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE)]),
+            false,
+        )
+        .build()
+        .expect("Failed to build batch");
+
+        let older = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE2)]),
+            false,
+        )
+        .build()
+        .expect("Failed to build batch");
+
+        store
+            .add_batches(vec![recent.clone(), older.clone()])
+            .expect("Failed to add batches");
+
+        set_created_at(recent.batch_header(), now - 10);
+        set_created_at(older.batch_header(), now - 30);
+
+        assert_eq!(
+            store
+                .average_submission_latency("TEST", 0)
+                .expect("Failed to get average latency"),
+            None
+        );
+
+        // Pending is not a terminal status, so no latency is recorded
+        store
+            .update_batch_status(
+                recent.batch_header(),
+                "TEST",
+                Some(BatchStatus::Pending),
+                Vec::new(),
+                None,
+            )
+            .expect("Failed to update batch");
+
+        assert_eq!(
+            store
+                .get_batch(recent.batch_header(), "TEST")
+                .expect("Failed to get batch")
+                .expect("Batch not found")
+                .submission_latency_ms(),
+            None
+        );
+
+        for batch in &[&recent, &older] {
+            let receipt = TransactionReceiptBuilder::default()
+                .with_transaction_id(batch.transactions()[0].transaction_header().to_string())
+                .with_result_valid(true)
+                .with_serialized_receipt(
+                    std::str::from_utf8(&BYTES2)
+                        .expect("Failed to build string")
+                        .to_string(),
+                )
+                .build()
+                .expect("Failed to build receipt");
+
+            store
+                .update_batch_status(
+                    batch.batch_header(),
+                    "TEST",
+                    Some(BatchStatus::Committed(Vec::new())),
+                    vec![receipt],
+                    None,
+                )
+                .expect("Failed to update batch");
+        }
+
+        let latency = |id: &str| {
+            store
+                .get_batch(id, "TEST")
+                .expect("Failed to get batch")
+                .expect("Batch not found")
+                .submission_latency_ms()
+                .expect("Latency not recorded")
+        };
+
+        // The clock may have ticked over since `now` was taken
+        let recent_latency = latency(recent.batch_header());
+        assert!((10_000..=11_000).contains(&recent_latency));
+        let older_latency = latency(older.batch_header());
+        assert!((30_000..=31_000).contains(&older_latency));
+
+        assert_eq!(
+            store
+                .average_submission_latency("TEST", 0)
+                .expect("Failed to get average latency"),
+            Some((recent_latency + older_latency) / 2)
+        );
+        assert_eq!(
+            store
+                .average_submission_latency("TEST", now - 20)
+                .expect("Failed to get average latency"),
+            Some(recent_latency)
+        );
+        assert_eq!(
+            store
+                .average_submission_latency("OTHER", 0)
+                .expect("Failed to get average latency"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_add_transact_batches() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let signer = new_signer();
+        let signer_public_key = hex::to_hex(
+            signer
+                .public_key()
+                .expect("Failed to get public key")
+                .as_slice(),
+        );
+
+        let batches: Vec<Batch> = [NONCE, NONCE2, "zz9kdf"]
+            .iter()
+            .map(|nonce| {
+                get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, nonce)])
+            })
+            .collect();
+
+        store
+            .add_transact_batches(batches.clone(), "TEST")
+            .expect("Failed to add batches");
+
+        for batch in batches {
+            let tracking_batch = store
+                .get_batch(batch.header_signature(), "TEST")
+                .expect("Failed to get batch")
+                .expect("Batch not found");
+
+            assert_eq!(tracking_batch.signer_public_key(), signer_public_key);
+            assert!(!tracking_batch.submitted());
+            assert_eq!(
+                tracking_batch.transactions()[0].transaction_header(),
+                batch.transactions()[0].header_signature()
+            );
+        }
+
+        assert_eq!(
+            store
+                .get_unsubmitted_batches()
+                .expect("Failed to get unsubmitted batches")
+                .batches
+                .len(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_case_insensitive_service_ids() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool.clone());
+        let insensitive_store =
+            DieselBatchTrackingStore::new(pool).with_case_insensitive_service_ids(true);
+
+        let signer = new_signer();
+
+        let batch = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE)]),
+            false,
+        )
+        .with_service_id("Alpha".to_string())
+        .build()
+        .expect("Failed to build batch");
+
+        store
+            .add_batches(vec![batch.clone()])
+            .expect("Failed to add batch");
+
+        assert_eq!(
+            store
+                .get_batch(batch.batch_header(), "alpha")
+                .expect("Failed to get batch"),
+            None
+        );
+
+        let found = insensitive_store
+            .get_batch(batch.batch_header(), "alpha")
+            .expect("Failed to get batch")
+            .expect("Batch not found");
+        assert_eq!(found.service_id(), Some("Alpha"));
+
+        // Writes are applied to the stored service ID
+        insensitive_store
+            .update_batch_status(
+                batch.batch_header(),
+                "ALPHA",
+                Some(BatchStatus::Pending),
+                Vec::new(),
+                None,
+            )
+            .expect("Failed to update batch");
+        assert_eq!(
+            store
+                .get_batch_status(batch.batch_header(), "Alpha")
+                .expect("Failed to get batch status"),
+            Some(BatchStatus::Pending)
+        );
+
+        // Service IDs without a stored match are used as given
+        assert_eq!(
+            insensitive_store
+                .get_batch(batch.batch_header(), "beta")
+                .expect("Failed to get batch"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_watch_unsubmitted() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let receiver = store.watch_unsubmitted("TEST");
+        let other_receiver = store.watch_unsubmitted("OTHER");
+
+        let signer = new_signer();
+
+        let unsubmitted_batch = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE)]),
+            false,
+        )
+        .build()
+        .expect("Failed to build batch");
+
+        let submitted_batch = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE2)]),
+            true,
+        )
+        .build()
+        .expect("Failed to build batch");
+
+        store
+            .add_batches(vec![unsubmitted_batch.clone(), submitted_batch])
+            .expect("Failed to add batches");
+
+        let received = receiver
+            .recv_timeout(Duration::from_secs(1))
+            .expect("Failed to receive batch");
+        assert_eq!(received.batch_header(), unsubmitted_batch.batch_header());
+        assert!(!received.submitted());
+
+        assert!(receiver.try_recv().is_none());
+        assert!(other_receiver.try_recv().is_none());
+    }
+
+    #[test]
+    fn test_long_names_and_ids_round_trip() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let service_id = "s".repeat(1024);
+        let data_change_id = format!("dcid:{}", "d".repeat(1019));
+        let family_name = "f".repeat(1024);
+        let family_version = "v".repeat(1024);
+
+        let signer = new_signer();
+
+        let transaction = TransactionBuilder::new()
+            .with_batcher_public_key(hex::parse_hex(KEY1).unwrap())
+            .with_family_name(family_name.clone())
+            .with_family_version(family_version.clone())
+            .with_inputs(vec![hex::parse_hex(KEY4).unwrap()])
+            .with_nonce(NONCE.to_string().into_bytes())
+            .with_outputs(vec![hex::parse_hex(KEY6).unwrap()])
+            .with_payload_hash_method(HashMethod::Sha512)
+            .with_payload(BYTES2.to_vec())
+            .build(&*signer)
+            .expect("Failed to build transaction");
+
+        let batch = TrackingBatchBuilder::default()
+            .with_batch(get_transact_batch(&*signer, vec![transaction]))
+            .with_service_id(service_id.clone())
+            .with_data_change_id(data_change_id.clone())
+            .with_signer_public_key(KEY1.to_string())
+            .with_submitted(false)
+            .build()
+            .expect("Failed to build batch");
+
+        store
+            .add_batches(vec![batch.clone()])
+            .expect("Failed to add batch");
+
+        let stored = store
+            .get_batch(batch.batch_header(), &service_id)
+            .expect("Failed to get batch")
+            .expect("Batch not found");
+
+        assert_eq!(stored.service_id(), Some(service_id.as_str()));
+        assert_eq!(stored.data_change_id(), Some(data_change_id.as_str()));
+        assert_eq!(stored.batch_header(), batch.batch_header());
+
+        let stored_transaction = &stored.transactions()[0];
+        assert_eq!(stored_transaction.family_name(), family_name);
+        assert_eq!(stored_transaction.family_version(), family_version);
+        assert_eq!(stored_transaction.service_id(), service_id);
+        assert_eq!(
+            stored_transaction.transaction_header(),
+            batch.transactions()[0].transaction_header()
+        );
+    }
+
+    #[test]
+    fn test_record_submission_attempt() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = Arc::new(DieselBatchTrackingStore::new(pool));
+
+        let signer = new_signer();
+
+        let tracking_batch = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE)]),
+            false,
+        )
+        .build()
+        .expect("Failed to build batch");
+
+        let id = tracking_batch.batch_header().to_string();
+
+        store
+            .add_batches(vec![tracking_batch])
+            .expect("Failed to add batch");
+
+        match store.record_submission_attempt(&id, "TEST") {
+            Err(BatchTrackingStoreError::NotFoundError(_)) => (),
+            res => panic!("Expected NotFoundError, got {:?}", res),
+        }
+
+        store
+            .change_batch_to_submitted(
+                &id,
+                "TEST",
+                Vec::new(),
+                Some("Pending"),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .expect("Failed to change batch to submitted");
+
+        let handles: Vec<_> = (0..2)
+            .map(|_| {
+                let store = Arc::clone(&store);
+                let id = id.clone();
+                std::thread::spawn(move || {
+                    (0..10)
+                        .map(|_| {
+                            store
+                                .record_submission_attempt(&id, "TEST")
+                                .expect("Failed to record submission attempt")
+                        })
+                        .collect::<Vec<i64>>()
+                })
+            })
+            .collect();
+
+        let mut attempts: Vec<i64> = handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("Thread panicked"))
+            .collect();
+        attempts.sort_unstable();
+
+        // Every attempt must have seen a distinct count
+        assert_eq!(attempts, (1..21).collect::<Vec<i64>>());
+
+        assert_eq!(
+            store
+                .record_submission_attempt(&id, "TEST")
+                .expect("Failed to record submission attempt"),
+            21
+        );
+    }
+
+    #[test]
+    fn test_list_batches_by_delayed_status() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let signer = new_signer();
+
+        let delayed_batch = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE)]),
+            false,
+        )
+        .build()
+        .expect("Failed to build batch");
+        let pending_batch = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE2)]),
+            false,
+        )
+        .build()
+        .expect("Failed to build batch");
+
+        let delayed_id = delayed_batch.batch_header().to_string();
+        let pending_id = pending_batch.batch_header().to_string();
+
+        store
+            .add_batches(vec![delayed_batch, pending_batch])
+            .expect("Failed to add batches");
+
+        store
+            .update_batch_status(
+                &delayed_id,
+                "TEST",
+                Some(BatchStatus::Delayed),
+                Vec::new(),
+                None,
+            )
+            .expect("Failed to update batch status");
+        store
+            .update_batch_status(
+                &pending_id,
+                "TEST",
+                Some(BatchStatus::Pending),
+                Vec::new(),
+                None,
+            )
+            .expect("Failed to update batch status");
+
+        let delayed = store
+            .list_batches_by_status(BatchStatus::Delayed)
+            .expect("Failed to list batches")
+            .batches;
+
+        assert_eq!(delayed.len(), 1);
+        assert_eq!(delayed[0].batch_header(), delayed_id);
+        assert_eq!(delayed[0].batch_status(), Some(&BatchStatus::Delayed));
+
+        assert_eq!(
+            store
+                .get_batch_status(&delayed_id, "TEST")
+                .expect("Failed to get batch status"),
+            Some(BatchStatus::Delayed)
+        );
+    }
+
+    #[test]
+    fn test_list_batches_by_status_with_total() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let signer = new_signer();
+
+        let batches: Vec<TrackingBatch> = [NONCE, NONCE2, "zz9kdf", "qq4tuv"]
+            .iter()
+            .map(|nonce| {
+                get_tracking_batch(
+                    get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, nonce)]),
+                    false,
+                )
+                .build()
+                .expect("Failed to build batch")
+            })
+            .collect();
+
+        store
+            .add_batches(batches.clone())
+            .expect("Failed to add batches");
+
+        // Leave the last batch without a pending status
+        let mut pending_ids: Vec<String> = batches[..3]
+            .iter()
+            .map(|batch| batch.batch_header().to_string())
+            .collect();
+
+        for id in &pending_ids {
+            store
+                .update_batch_status(id, "TEST", Some(BatchStatus::Pending), Vec::new(), None)
+                .expect("Failed to update batch status");
+        }
+
+        // All batches share a creation time, so they are ordered by ID
+        pending_ids.sort();
+
+        let page = store
+            .list_batches_by_status_with_total(BatchStatus::Pending, 1, 1)
+            .expect("Failed to list batches");
+
+        assert_eq!(
+            page.paging.total,
+            store
+                .list_batches_by_status(BatchStatus::Pending)
+                .expect("Failed to list batches")
+                .batches
+                .len() as i64
+        );
+        assert_eq!(page.paging, Paging::new(1, 1, 3));
+        assert_eq!(page.batches.len(), 1);
+        assert_eq!(page.batches[0].batch_header(), pending_ids[1]);
+        assert_eq!(page.batches[0].transactions().len(), 1);
+        assert_eq!(page.batches[0].batch_status(), Some(&BatchStatus::Pending));
+
+        let page = store
+            .list_batches_by_status_with_total(BatchStatus::Pending, 0, 10)
+            .expect("Failed to list batches");
+
+        assert_eq!(page.paging.total, 3);
+        assert_eq!(
+            page.batches
+                .iter()
+                .map(|batch| batch.batch_header().to_string())
+                .collect::<Vec<_>>(),
+            pending_ids
+        );
+    }
+
+    #[test]
+    fn test_scrub_receipts() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool.clone());
+
+        let signer = new_signer();
+
+        let batch = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE)]),
+            false,
+        )
+        .build()
+        .expect("Failed to build batch");
+        let id = batch.batch_header().to_string();
+        let transaction_id = batch.transactions()[0].transaction_header().to_string();
+
+        store.add_batches(vec![batch]).expect("Failed to add batch");
+
+        let receipt = TransactionReceiptBuilder::default()
+            .with_transaction_id(transaction_id.clone())
+            .with_result_valid(true)
+            .with_error_data(BYTES2.to_vec())
+            .with_serialized_receipt(
+                std::str::from_utf8(&BYTES2)
+                    .expect("Failed to build string")
+                    .to_string(),
+            )
+            .build()
+            .expect("Failed to build receipt");
+
+        store
+            .update_batch_status(
+                &id,
+                "TEST",
+                Some(BatchStatus::Committed(Vec::new())),
+                vec![receipt],
+                None,
+            )
+            .expect("Failed to update batch");
+
+        assert_eq!(
+            store
+                .scrub_receipts(&id, "TEST")
+                .expect("Failed to scrub receipts"),
+            1
+        );
+
+        let (serialized_receipt, error_data): (Option<Vec<u8>>, Option<Vec<u8>>) =
+            schema::transaction_receipts::table
+                .select((
+                    schema::transaction_receipts::serialized_receipt,
+                    schema::transaction_receipts::error_data,
+                ))
+                .filter(schema::transaction_receipts::transaction_id.eq(&transaction_id))
+                .first(&*pool.get().expect("Failed to get connection"))
+                .expect("Failed to get receipt");
+        assert_eq!(serialized_receipt, None);
+        assert_eq!(error_data, None);
+
+        let stored = store
+            .get_batch(&id, "TEST")
+            .expect("Failed to get batch")
+            .expect("Batch not found");
+        match stored.batch_status() {
+            Some(BatchStatus::Committed(valid)) => {
+                assert_eq!(valid.len(), 1);
+                assert_eq!(valid[0].transaction_id(), transaction_id);
+            }
+            status => panic!("Expected committed status, got {:?}", status),
+        }
+
+        match store.scrub_receipts("missing", "TEST") {
+            Err(BatchTrackingStoreError::NotFoundError(_)) => (),
+            res => panic!("Expected NotFoundError, got {:?}", res),
+        }
+    }
+
+    #[test]
+    fn test_verify_migration() {
+        let src = DieselBatchTrackingStore::new(create_connection_pool_and_migrate());
+        let dst = DieselBatchTrackingStore::new(create_connection_pool_and_migrate());
+
+        let signer = new_signer();
+
+        let batches: Vec<TrackingBatch> = [NONCE, NONCE2]
+            .iter()
+            .map(|nonce| {
+                get_tracking_batch(
+                    get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, nonce)]),
+                    false,
+                )
+                .build()
+                .expect("Failed to build batch")
+            })
+            .collect();
+
+        src.add_batches(batches).expect("Failed to add batches");
+
+        dst.add_batches(
+            src.list_batches("TEST")
+                .expect("Failed to list batches")
+                .batches,
+        )
+        .expect("Failed to copy batches");
+
+        let report = verify_migration(&src, &dst, "TEST").expect("Failed to verify migration");
+
+        assert!(report.is_complete());
+        assert!(report.missing_from_destination().is_empty());
+        assert!(report.missing_from_source().is_empty());
+        assert!(report.differences().is_empty());
+
+        let extra = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, "zz9kdf")]),
+            false,
+        )
+        .build()
+        .expect("Failed to build batch");
+        let extra_id = extra.batch_header().to_string();
+
+        src.add_batches(vec![extra]).expect("Failed to add batch");
+
+        let report = verify_migration(&src, &dst, "TEST").expect("Failed to verify migration");
+
+        assert!(!report.is_complete());
+        assert_eq!(report.missing_from_destination(), &[extra_id][..]);
+        assert!(report.missing_from_source().is_empty());
+    }
+
+    #[test]
+    fn test_pool_state() {
+        let pool = Pool::builder()
+            .max_size(2)
+            .build(ConnectionManager::<SqliteConnection>::new(":memory:"))
+            .expect("Failed to build connection pool");
+
+        let store = DieselBatchTrackingStore::new(pool.clone());
+
+        let state = store.pool_state();
+        assert_eq!(state.max_size(), 2);
+        assert_eq!(state.connections(), 2);
+        assert_eq!(state.idle_connections(), 2);
+
+        let _conn = pool.get().expect("Failed to get connection");
+
+        let state = store.pool_state();
+        assert_eq!(state.connections(), 2);
+        assert_eq!(state.idle_connections(), 1);
+    }
+
+    #[test]
+    fn test_set_batch_notes() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let signer = new_signer();
+
+        let batch = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE)]),
+            false,
+        )
+        .build()
+        .expect("Failed to build batch");
+        let id = batch.batch_header().to_string();
+
+        store.add_batches(vec![batch]).expect("Failed to add batch");
+
+        let notes = |store: &DieselBatchTrackingStore<SqliteConnection>| {
+            store
+                .get_batch(&id, "TEST")
+                .expect("Failed to get batch")
+                .expect("Batch not found")
+                .notes()
+                .map(String::from)
+        };
+
+        assert_eq!(notes(&store), None);
+
+        store
+            .set_batch_notes(&id, "TEST", Some("Stuck behind a large batch"))
+            .expect("Failed to set notes");
+        assert_eq!(
+            notes(&store),
+            Some("Stuck behind a large batch".to_string())
+        );
+
+        store
+            .set_batch_notes(&id, "TEST", None)
+            .expect("Failed to clear notes");
+        assert_eq!(notes(&store), None);
+
+        match store.set_batch_notes("missing", "TEST", Some("note")) {
+            Err(BatchTrackingStoreError::NotFoundError(_)) => (),
+            res => panic!("Expected NotFoundError, got {:?}", res),
+        }
+    }
+
+    #[test]
+    fn test_get_batch_by_transaction_id() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let signer = new_signer();
+
+        let duplicate = get_transact_transaction(&*signer, NONCE);
+        let transaction_id = duplicate.header_signature().to_string();
+
+        let batch_a = get_transact_batch(&*signer, vec![duplicate.clone()]);
+        let batch_b = get_transact_batch(
+            &*signer,
+            vec![duplicate, get_transact_transaction(&*signer, NONCE2)],
+        );
+        let id_a = batch_a.header_signature().to_string();
+        let id_b = batch_b.header_signature().to_string();
+
+        store
+            .add_batches(vec![get_tracking_batch(batch_a, false)
+                .build()
+                .expect("Failed to build batch")])
+            .expect("Failed to add batch");
+
+        // A transaction ID may only be stored once per service
+        match store.add_batches(vec![get_tracking_batch(batch_b.clone(), false)
+            .build()
+            .expect("Failed to build batch")])
+        {
+            Err(BatchTrackingStoreError::ConstraintViolationError(_)) => (),
+            res => panic!("Expected ConstraintViolationError, got {:?}", res),
+        }
+
+        store
+            .add_batches(vec![get_tracking_batch(batch_b, false)
+                .with_service_id("OTHER".to_string())
+                .build()
+                .expect("Failed to build batch")])
+            .expect("Failed to add batch");
+
+        let found = store
+            .get_batch_by_transaction_id(&transaction_id, "TEST")
+            .expect("Failed to get batches");
+        assert_eq!(
+            found.iter().map(|b| b.batch_header()).collect::<Vec<_>>(),
+            vec![id_a.as_str()]
+        );
+
+        let found = store
+            .get_batch_by_transaction_id(&transaction_id, "OTHER")
+            .expect("Failed to get batches");
+        assert_eq!(
+            found.iter().map(|b| b.batch_header()).collect::<Vec<_>>(),
+            vec![id_b.as_str()]
+        );
+
+        assert!(store
+            .get_batch_by_transaction_id("missing", "TEST")
+            .expect("Failed to get batches")
+            .is_empty());
+    }
+
+    #[test]
+    fn test_metrics_text() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let signer = new_signer();
+
+        let batches: Vec<TrackingBatch> = [NONCE, NONCE2, "zz9kdf"]
+            .iter()
+            .map(|nonce| {
+                get_tracking_batch(
+                    get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, nonce)]),
+                    false,
+                )
+                .build()
+                .expect("Failed to build batch")
+            })
+            .collect();
+
+        store
+            .add_batches(batches.clone())
+            .expect("Failed to add batches");
+
+        // The first batch is left unsubmitted without a status
+        store
+            .update_batch_status(
+                batches[1].batch_header(),
+                "TEST",
+                Some(BatchStatus::Pending),
+                Vec::new(),
+                None,
+            )
+            .expect("Failed to update batch status");
+        store
+            .update_batch_status(
+                batches[2].batch_header(),
+                "TEST",
+                Some(BatchStatus::Unknown),
+                Vec::new(),
+                None,
+            )
+            .expect("Failed to update batch status");
+
+        let text = store.metrics_text().expect("Failed to get metrics");
+        let lines: Vec<&str> = text.lines().collect();
+
+        for expected in &[
+            "# TYPE grid_batch_tracking_batches gauge",
+            "grid_batch_tracking_batches 3",
+            "grid_batch_tracking_batches_by_status{status=\"Unknown\"} 1",
+            "grid_batch_tracking_batches_by_status{status=\"Pending\"} 1",
+            "grid_batch_tracking_batches_by_status{status=\"Delayed\"} 0",
+            "grid_batch_tracking_batches_by_status{status=\"Invalid\"} 0",
+            "grid_batch_tracking_batches_by_status{status=\"Valid\"} 0",
+            "grid_batch_tracking_batches_by_status{status=\"Committed\"} 0",
+            "grid_batch_tracking_unsubmitted_batches 2",
+            "grid_batch_tracking_failed_batches 1",
+        ] {
+            assert!(
+                lines.contains(expected),
+                "Missing line {:?} in:\n{}",
+                expected,
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn test_signer_public_key_validation() {
+        let signer = new_signer();
+
+        let batch = get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE)]);
+
+        let build = |signer_public_key: &str| {
+            TrackingBatchBuilder::default()
+                .with_batch(batch.clone())
+                .with_service_id("TEST".to_string())
+                .with_signer_public_key(signer_public_key.to_string())
+                .with_submitted(false)
+                .build()
+        };
+
+        let built = build(KEY2).expect("Failed to build batch with a valid key");
+        assert_eq!(built.signer_public_key(), KEY2);
+
+        match build(&KEY2[1..]) {
+            Err(BatchBuilderError::InvalidField(msg)) => assert!(msg.contains("odd number")),
+            res => panic!("Expected InvalidField, got {:?}", res),
+        }
+
+        let non_hex = format!("{}zz", &KEY2[2..]);
+        match build(&non_hex) {
+            Err(BatchBuilderError::InvalidField(msg)) => assert!(msg.contains("non-hex")),
+            res => panic!("Expected InvalidField, got {:?}", res),
+        }
+
+        match build(&KEY2[2..]) {
+            Err(BatchBuilderError::InvalidField(_)) => (),
+            res => panic!("Expected InvalidField, got {:?}", res),
+        }
+    }
+
+    #[test]
+    /// Test that batches submitted across two submission rounds can be listed
+    /// by round, and that batches submitted without a round are not listed
+    fn test_list_batches_by_round() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let signer = new_signer();
+
+        let batches: Vec<TrackingBatch> = [NONCE, NONCE2, "k9fzdz"]
+            .iter()
+            .map(|nonce| {
+                get_tracking_batch(
+                    get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, nonce)]),
+                    false,
+                )
+                .build()
+                .expect("Failed to build batch")
+            })
+            .collect();
+        let ids: Vec<String> = batches
+            .iter()
+            .map(|b| b.batch_header().to_string())
+            .collect();
+
+        store.add_batches(batches).expect("Failed to add batches");
+
+        store
+            .change_batch_to_submitted(
+                &ids[0],
+                "TEST",
+                Vec::new(),
+                None,
+                None,
+                None,
+                None,
+                None,
+                Some(1),
+                None,
+            )
+            .expect("Failed to change batch to submitted");
+        store
+            .change_batch_to_submitted(
+                &ids[1],
+                "TEST",
+                Vec::new(),
+                None,
+                None,
+                None,
+                None,
+                None,
+                Some(2),
+                None,
+            )
+            .expect("Failed to change batch to submitted");
+        store
+            .change_batch_to_submitted(
+                &ids[2],
+                "TEST",
+                Vec::new(),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .expect("Failed to change batch to submitted");
+
+        let round_1 = store
+            .list_batches_by_round(1, "TEST")
+            .expect("Failed to list batches")
+            .batches;
+        assert_eq!(round_1.len(), 1);
+        assert_eq!(round_1[0].batch_header(), ids[0]);
+        assert_eq!(round_1[0].submission_round(), Some(1));
+        assert!(round_1[0].submitted());
+
+        let round_2 = store
+            .list_batches_by_round(2, "TEST")
+            .expect("Failed to list batches")
+            .batches;
+        assert_eq!(round_2.len(), 1);
+        assert_eq!(round_2[0].batch_header(), ids[1]);
+        assert_eq!(round_2[0].submission_round(), Some(2));
+
+        assert!(store
+            .list_batches_by_round(3, "TEST")
+            .expect("Failed to list batches")
+            .batches
+            .is_empty());
+
+        let unrounded = store
+            .get_batch(&ids[2], "TEST")
+            .expect("Failed to get batch")
+            .expect("Batch not found");
+        assert_eq!(unrounded.submission_round(), None);
+    }
+
+    #[test]
+    /// Test that has_unsubmitted_batches reports false for an empty store and
+    /// true once an unsubmitted batch is added, both across all services and
+    /// for a single service
+    fn test_has_unsubmitted_batches() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        assert!(!store
+            .has_unsubmitted_batches(None)
+            .expect("Failed to check for unsubmitted batches"));
+        assert!(!store
+            .has_unsubmitted_batches(Some("TEST"))
+            .expect("Failed to check for unsubmitted batches"));
+
+        let signer = new_signer();
+
+        let batch = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE)]),
+            false,
+        )
+        .build()
+        .expect("Failed to build batch");
+        let id = batch.batch_header().to_string();
+
+        store
+            .add_batches(vec![batch])
+            .expect("Failed to add batches");
+
+        assert!(store
+            .has_unsubmitted_batches(None)
+            .expect("Failed to check for unsubmitted batches"));
+        assert!(store
+            .has_unsubmitted_batches(Some("TEST"))
+            .expect("Failed to check for unsubmitted batches"));
+        assert!(!store
+            .has_unsubmitted_batches(Some("OTHER"))
+            .expect("Failed to check for unsubmitted batches"));
+
+        store
+            .change_batch_to_submitted(
+                &id,
+                "TEST",
+                Vec::new(),
+                Some("Pending"),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .expect("Failed to change batch to submitted");
+
+        assert!(!store
+            .has_unsubmitted_batches(None)
+            .expect("Failed to check for unsubmitted batches"));
+        assert!(!store
+            .has_unsubmitted_batches(Some("TEST"))
+            .expect("Failed to check for unsubmitted batches"));
+    }
+
+    #[cfg(feature = "postgres")]
+    #[test]
+    #[ignore]
+    /// Test that a postgres store configured with a schema operates on the
+    /// tables in that schema rather than on those in the search path
+    ///
+    /// Requires a postgres database at the URL in `GRID_TEST_POSTGRES_URL`.
+    fn test_postgres_with_schema() {
+        use diesel::connection::SimpleConnection;
+        use diesel::pg::PgConnection;
+
+        use crate::migrations::run_postgres_migrations;
+
+        let url = std::env::var("GRID_TEST_POSTGRES_URL")
+            .expect("GRID_TEST_POSTGRES_URL must be set to run this test");
+        let pool = Pool::builder()
+            .max_size(1)
+            .build(ConnectionManager::<PgConnection>::new(url))
+            .expect("Failed to build connection pool");
+
+        {
+            let conn = pool.get().expect("Failed to get connection");
+            conn.batch_execute(
+                "DROP SCHEMA IF EXISTS batch_tracking_test CASCADE;
+                CREATE SCHEMA batch_tracking_test;
+                SET search_path TO batch_tracking_test;",
+            )
+            .expect("Failed to create schema");
+            run_postgres_migrations(&conn).expect("Failed to run migrations");
+            conn.batch_execute("RESET search_path;")
+                .expect("Failed to reset search path");
+        }
+
+        let store = DieselBatchTrackingStore::new(pool.clone()).with_schema("batch_tracking_test");
+
+        let signer = new_signer();
+
+        let batch = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE)]),
+            false,
+        )
+        .build()
+        .expect("Failed to build batch");
+        let id = batch.batch_header().to_string();
+
+        store
+            .add_batches(vec![batch])
+            .expect("Failed to add batches");
+        store
+            .change_batch_to_submitted(
+                &id,
+                "TEST",
+                Vec::new(),
+                Some("Pending"),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .expect("Failed to change batch to submitted");
+
+        let fetched = store
+            .get_batch(&id, "TEST")
+            .expect("Failed to get batch")
+            .expect("Batch not found");
+        assert!(fetched.submitted());
+
+        let pending = store
+            .list_batches_by_status(BatchStatus::Pending)
+            .expect("Failed to list batches")
+            .batches;
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].batch_header(), id);
+
+        // Operations run in the caller's transaction leave the caller's
+        // search path as it was once they return
+        {
+            let pooled = pool.get().expect("Failed to get connection");
+            let conn: &PgConnection = &pooled;
+            conn.transaction::<_, BatchTrackingStoreError, _>(|| {
+                let search_path = || {
+                    diesel::select(diesel::dsl::sql::<diesel::sql_types::Text>(
+                        "current_setting('search_path')",
+                    ))
+                    .get_result::<String>(conn)
+                    .expect("Failed to get search path")
+                };
+                let before = search_path();
+
+                let conn_store = DieselConnectionBatchTrackingStore::new(conn)
+                    .with_schema("batch_tracking_test");
+                assert!(conn_store.get_batch(&id, "TEST")?.is_some());
+                assert_eq!(search_path(), before);
+                conn_store.update_batch_status(
+                    &id,
+                    "TEST",
+                    Some(BatchStatus::Pending),
+                    Vec::new(),
+                    None,
+                )?;
+                assert_eq!(search_path(), before);
+
+                Ok(())
+            })
+            .expect("Failed to run operations in transaction");
+        }
+
+        store.compact().expect("Failed to compact");
+
+        pool.get()
+            .expect("Failed to get connection")
+            .batch_execute("DROP SCHEMA batch_tracking_test CASCADE;")
+            .expect("Failed to drop schema");
+    }
+
+    #[cfg(feature = "postgres")]
+    #[test]
+    #[ignore]
+    /// Test that a bulk write that fails with a serialization failure is
+    /// retried up to the configured number of times, and that other errors
+    /// are not retried
+    ///
+    /// Contention is simulated with a trigger that fails the first inserts
+    /// into the batches table, counting attempts with a sequence as its value
+    /// is not rolled back with the failed transactions.
+    ///
+    /// Requires a postgres database at the URL in `GRID_TEST_POSTGRES_URL`.
+    fn test_postgres_serialization_retries() {
+        use diesel::connection::SimpleConnection;
+        use diesel::dsl::sql;
+        use diesel::pg::PgConnection;
+        use diesel::sql_types::BigInt;
+
+        use crate::migrations::run_postgres_migrations;
+
+        let url = std::env::var("GRID_TEST_POSTGRES_URL")
+            .expect("GRID_TEST_POSTGRES_URL must be set to run this test");
+        let pool = Pool::builder()
+            .max_size(1)
+            .build(ConnectionManager::<PgConnection>::new(url))
+            .expect("Failed to build connection pool");
+
+        let fail_inserts = |failures: i64, error_code: &str| {
+            pool.get()
+                .expect("Failed to get connection")
+                .batch_execute(&format!(
+                    "ALTER SEQUENCE batch_tracking_retry_test.insert_attempts RESTART;
+                    CREATE OR REPLACE FUNCTION batch_tracking_retry_test.fail_inserts()
+                    RETURNS trigger AS $$
+                    BEGIN
+                        IF nextval('batch_tracking_retry_test.insert_attempts') <= {} THEN
+                            RAISE EXCEPTION 'simulated failure' USING ERRCODE = '{}';
+                        END IF;
+                        RETURN NEW;
+                    END
+                    $$ LANGUAGE plpgsql;",
+                    failures, error_code
+                ))
+                .expect("Failed to create trigger function");
+        };
+        let attempts = || {
+            diesel::select(sql::<BigInt>(
+                "(SELECT last_value FROM batch_tracking_retry_test.insert_attempts)",
+            ))
+            .get_result::<i64>(&*pool.get().expect("Failed to get connection"))
+            .expect("Failed to count attempts")
+        };
+
+        {
+            let conn = pool.get().expect("Failed to get connection");
+            conn.batch_execute(
+                "DROP SCHEMA IF EXISTS batch_tracking_retry_test CASCADE;
+                CREATE SCHEMA batch_tracking_retry_test;
+                SET search_path TO batch_tracking_retry_test;",
+            )
+            .expect("Failed to create schema");
+            run_postgres_migrations(&conn).expect("Failed to run migrations");
+            conn.batch_execute(
+                "RESET search_path;
+                CREATE SEQUENCE batch_tracking_retry_test.insert_attempts;",
+            )
+            .expect("Failed to create sequence");
+        }
+        fail_inserts(0, "serialization_failure");
+        pool.get()
+            .expect("Failed to get connection")
+            .batch_execute(
+                "CREATE TRIGGER fail_inserts BEFORE INSERT
+                ON batch_tracking_retry_test.batches
+                FOR EACH ROW EXECUTE PROCEDURE batch_tracking_retry_test.fail_inserts();",
+            )
+            .expect("Failed to create trigger");
+
+        let store = DieselBatchTrackingStore::new(pool.clone())
+            .with_schema("batch_tracking_retry_test")
+            .with_serialization_retries(2);
+
+        let signer = new_signer();
+        let batch = |nonce: &str| {
+            get_tracking_batch(
+                get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, nonce)]),
+                false,
+            )
+            .build()
+            .expect("Failed to build batch")
+        };
+
+        // Two failures are retried and the third attempt succeeds
+        fail_inserts(2, "serialization_failure");
+        let retried = batch(NONCE);
+        store
+            .add_batches(vec![retried.clone()])
+            .expect("Failed to add batches");
+        assert_eq!(attempts(), 3);
+        assert!(store
+            .get_batch(retried.batch_header(), "TEST")
+            .expect("Failed to get batch")
+            .is_some());
+
+        // Three failures exhaust the retries
+        fail_inserts(3, "serialization_failure");
+        let exhausted = batch(NONCE2);
+        assert!(store.add_batches(vec![exhausted.clone()]).is_err());
+        assert_eq!(attempts(), 3);
+        assert!(store
+            .get_batch(exhausted.batch_header(), "TEST")
+            .expect("Failed to get batch")
+            .is_none());
+
+        // Other errors are not retried
+        fail_inserts(1, "check_violation");
+        assert!(store.add_batches(vec![exhausted]).is_err());
+        assert_eq!(attempts(), 1);
+
+        // Bulk status updates are retried as well
+        pool.get()
+            .expect("Failed to get connection")
+            .batch_execute(
+                "CREATE TRIGGER fail_updates BEFORE UPDATE
+                ON batch_tracking_retry_test.batches
+                FOR EACH ROW EXECUTE PROCEDURE batch_tracking_retry_test.fail_inserts();",
+            )
+            .expect("Failed to create trigger");
+        fail_inserts(1, "serialization_failure");
+        assert_eq!(
+            store
+                .dead_letter_batches(&[retried.batch_header()], "TEST", "test")
+                .expect("Failed to dead-letter batches"),
+            1
+        );
+        assert_eq!(attempts(), 2);
+        assert!(store
+            .get_dead_lettered_batches("TEST")
+            .expect("Failed to get dead-lettered batches")
+            .batches
+            .iter()
+            .any(|batch| batch.batch_header() == retried.batch_header()));
+
+        pool.get()
+            .expect("Failed to get connection")
+            .batch_execute("DROP SCHEMA batch_tracking_retry_test CASCADE;")
+            .expect("Failed to drop schema");
+    }
+
+    #[test]
+    /// Test that statuses stored with non-canonical casing are rewritten to
+    /// their canonical form and can then be listed by status
+    fn test_normalize_status_values() {
+        use super::models::NewBatchStatusModel;
+        use super::schema::batch_statuses;
+
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool.clone());
+
+        let signer = new_signer();
+
+        let batches: Vec<TrackingBatch> = [NONCE, NONCE2, "k9fzdz"]
+            .iter()
+            .map(|nonce| {
+                get_tracking_batch(
+                    get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, nonce)]),
+                    true,
+                )
+                .build()
+                .expect("Failed to build batch")
+            })
+            .collect();
+        let ids: Vec<String> = batches
+            .iter()
+            .map(|b| b.batch_header().to_string())
+            .collect();
+
+        store.add_batches(batches).expect("Failed to add batches");
+
+        let statuses: Vec<NewBatchStatusModel> = ids
+            .iter()
+            .zip(["pending", "Pending", "cOMMITTED"].iter())
+            .map(|(id, status)| NewBatchStatusModel {
+                service_id: "TEST".to_string(),
+                batch_id: id.to_string(),
+                dlt_status: status.to_string(),
+            })
+            .collect();
+        diesel::insert_into(batch_statuses::table)
+            .values(&statuses)
+            .execute(&*pool.get().expect("Failed to get connection"))
+            .expect("Failed to insert statuses");
+
+        assert_eq!(
+            store
+                .list_batches_by_status(BatchStatus::Pending)
+                .expect("Failed to list batches")
+                .batches
+                .len(),
+            1
+        );
+
+        assert_eq!(
+            store
+                .normalize_status_values()
+                .expect("Failed to normalize statuses"),
+            2
+        );
+
+        let stored: Vec<String> = batch_statuses::table
+            .select(batch_statuses::dlt_status)
+            .order(batch_statuses::dlt_status.asc())
+            .load(&*pool.get().expect("Failed to get connection"))
+            .expect("Failed to load statuses");
+        assert_eq!(stored, vec!["Committed", "Pending", "Pending"]);
+
+        let mut pending: Vec<String> = store
+            .list_batches_by_status(BatchStatus::Pending)
+            .expect("Failed to list batches")
+            .batches
+            .iter()
+            .map(|b| b.batch_header().to_string())
+            .collect();
+        pending.sort();
+        let mut expected = vec![ids[0].clone(), ids[1].clone()];
+        expected.sort();
+        assert_eq!(pending, expected);
+
+        assert_eq!(
+            store
+                .normalize_status_values()
+                .expect("Failed to normalize statuses"),
+            0
+        );
+    }
+
+    #[test]
+    /// Test that the status events recorded by a store bound to a correlation
+    /// ID carry that ID, and that events recorded by the unbound store do not
+    fn test_correlation_id_in_status_events() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let signer = new_signer();
+
+        let batch = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE)]),
+            false,
+        )
+        .build()
+        .expect("Failed to build batch");
+        let id = batch.batch_header().to_string();
+
+        store
+            .add_batches(vec![batch])
+            .expect("Failed to add batches");
+
+        store
+            .with_correlation_id("request-1")
+            .change_batch_to_submitted(
+                &id,
+                "TEST",
+                Vec::new(),
+                Some("Pending"),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .expect("Failed to change batch to submitted");
+        store
+            .update_batch_status(&id, "TEST", Some(BatchStatus::Delayed), Vec::new(), None)
+            .expect("Failed to update batch status");
+
+        let events = store
+            .list_batch_status_events(&id, "TEST")
+            .expect("Failed to list status events");
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].batch_id(), id);
+        assert_eq!(events[0].status(), BatchStatusName::Pending);
+        assert_eq!(events[0].correlation_id(), Some("request-1"));
+        assert_eq!(events[1].status(), BatchStatusName::Delayed);
+        assert_eq!(events[1].correlation_id(), None);
+    }
+
+    #[test]
+    /// Test that batches can be fetched by a subset of their data change IDs,
+    /// and that a data change ID without a batch is ignored
+    fn test_get_batches_by_data_change_ids() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let signer = new_signer();
+
+        let batches: Vec<TrackingBatch> = [
+            (NONCE, "dcid:first"),
+            (NONCE2, "dcid:second"),
+            ("k9fzdz", "dcid:third"),
+        ]
+        .iter()
+        .map(|(nonce, dcid)| {
+            get_tracking_batch(
+                get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, nonce)]),
+                false,
+            )
+            .with_data_change_id(dcid.to_string())
+            .build()
+            .expect("Failed to build batch")
+        })
+        .collect();
+
+        store.add_batches(batches).expect("Failed to add batches");
+
+        let mut fetched: Vec<String> = store
+            .get_batches_by_data_change_ids(&["dcid:first", "dcid:third", "dcid:missing"], "TEST")
+            .expect("Failed to get batches")
+            .batches
+            .iter()
+            .map(|b| {
+                b.data_change_id()
+                    .expect("Batch has no data change ID")
+                    .to_string()
+            })
+            .collect();
+        fetched.sort();
+
+        assert_eq!(fetched, vec!["dcid:first", "dcid:third"]);
+
+        assert!(store
+            .get_batches_by_data_change_ids(&["dcid:first"], "OTHER")
+            .expect("Failed to get batches")
+            .batches
+            .is_empty());
+        assert!(store
+            .get_batches_by_data_change_ids(&[], "TEST")
+            .expect("Failed to get batches")
+            .batches
+            .is_empty());
+    }
+
+    #[test]
+    /// Test that each stored batch records the size of its serialized batch
+    /// and that the sizes are totalled per service
+    fn test_batch_byte_size() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        assert_eq!(
+            store
+                .total_bytes_by_service("TEST")
+                .expect("Failed to total bytes"),
+            0
+        );
+
+        let signer = new_signer();
+
+        let batches: Vec<TrackingBatch> = [NONCE, NONCE2]
+            .iter()
+            .map(|nonce| {
+                get_tracking_batch(
+                    get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, nonce)]),
+                    false,
+                )
+                .build()
+                .expect("Failed to build batch")
+            })
+            .collect();
+        let expected_sizes: Vec<i64> = batches
+            .iter()
+            .map(|b| b.serialized_batch().len() as i64)
+            .collect();
+        let ids: Vec<String> = batches
+            .iter()
+            .map(|b| b.batch_header().to_string())
+            .collect();
+
+        store.add_batches(batches).expect("Failed to add batches");
+
+        for (id, expected_size) in ids.iter().zip(expected_sizes.iter()) {
+            let batch = store
+                .get_batch(id, "TEST")
+                .expect("Failed to get batch")
+                .expect("Batch not found");
+            assert!(batch.byte_size() > 0);
+            assert_eq!(batch.byte_size(), *expected_size);
+        }
+
+        assert_eq!(
+            store
+                .total_bytes_by_service("TEST")
+                .expect("Failed to total bytes"),
+            expected_sizes.iter().sum::<i64>()
+        );
+        assert_eq!(
+            store
+                .total_bytes_by_service("OTHER")
+                .expect("Failed to total bytes"),
+            0
+        );
+    }
+
+    #[test]
+    /// Test that batches are listed once when their status was set within the
+    /// window, and not listed when it was set outside of it
+    fn test_list_batches_status_changed_between() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool.clone());
+
+        let signer = new_signer();
+
+        let batches: Vec<TrackingBatch> = [NONCE, NONCE2, "k9fzdz"]
+            .iter()
+            .map(|nonce| {
+                get_tracking_batch(
+                    get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, nonce)]),
+                    false,
+                )
+                .build()
+                .expect("Failed to build batch")
+            })
+            .collect();
+        let ids: Vec<String> = batches
+            .iter()
+            .map(|b| b.batch_header().to_string())
+            .collect();
+
+        store.add_batches(batches).expect("Failed to add batches");
+
+        // The first batch's status is set at 100, the second's at 200 and 250
+        // and the third's at 300
+        for (id, status, at) in [
+            (&ids[0], BatchStatus::Pending, 100),
+            (&ids[1], BatchStatus::Pending, 200),
+            (&ids[1], BatchStatus::Delayed, 250),
+            (&ids[2], BatchStatus::Pending, 300),
+        ]
+        .iter()
+        {
+            store
+                .update_batch_status(id, "TEST", Some(status.clone()), Vec::new(), None)
+                .expect("Failed to update batch status");
+
+            let latest: i64 = schema::batch_status_events::table
+                .select(diesel::dsl::max(schema::batch_status_events::id))
+                .first::<Option<i64>>(&*pool.get().expect("Failed to get connection"))
+                .expect("Failed to get latest event")
+                .expect("No event recorded");
+            diesel::update(
+                schema::batch_status_events::table
+                    .filter(schema::batch_status_events::id.eq(latest)),
+            )
+            .set(schema::batch_status_events::created_at.eq(at))
+            .execute(&*pool.get().expect("Failed to get connection"))
+            .expect("Failed to set event time");
+        }
+
+        let mut changed: Vec<String> = store
+            .list_batches_status_changed_between("TEST", 100, 300)
+            .expect("Failed to list batches")
+            .batches
+            .iter()
+            .map(|b| b.batch_header().to_string())
+            .collect();
+        changed.sort();
+        let mut expected = vec![ids[0].clone(), ids[1].clone()];
+        expected.sort();
+        assert_eq!(changed, expected);
+
+        let changed = store
+            .list_batches_status_changed_between("TEST", 240, 301)
+            .expect("Failed to list batches")
+            .batches;
+        assert_eq!(changed.len(), 2);
+
+        let changed = store
+            .list_batches_status_changed_between("TEST", 300, 400)
+            .expect("Failed to list batches")
+            .batches;
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].batch_header(), ids[2]);
+
+        assert!(store
+            .list_batches_status_changed_between("TEST", 400, 500)
+            .expect("Failed to list batches")
+            .batches
+            .is_empty());
+        assert!(store
+            .list_batches_status_changed_between("OTHER", 0, 500)
+            .expect("Failed to list batches")
+            .batches
+            .is_empty());
+    }
+
+    #[test]
+    /// Test that committing a batch sets its status, stores its receipts and
+    /// records its submission latency, and that committing a missing batch
+    /// fails
+    fn test_commit_batch() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let signer = new_signer();
+
+        let pair = get_transact_transaction(&*signer, NONCE);
+        let transaction_id = pair.header_signature().to_string();
+
+        let batch = get_tracking_batch(get_transact_batch(&*signer, vec![pair]), true)
+            .build()
+            .expect("Failed to build batch");
+        let id = batch.batch_header().to_string();
+
+        store
+            .add_batches(vec![batch])
+            .expect("Failed to add batches");
+
+        let txn_receipts = vec![TransactionReceiptBuilder::default()
+            .with_transaction_id(transaction_id.clone())
+            .with_result_valid(true)
+            .with_serialized_receipt(
+                std::str::from_utf8(&BYTES2)
+                    .expect("Failed to build string")
+                    .to_string(),
+            )
+            .build()
+            .expect("Failed to build receipt")];
+
+        store
+            .commit_batch(&id, "TEST", txn_receipts)
+            .expect("Failed to commit batch");
+
+        let committed = store
+            .get_batch(&id, "TEST")
+            .expect("Failed to get batch")
+            .expect("Batch not found");
+
+        match committed.batch_status() {
+            Some(BatchStatus::Committed(txns)) => {
+                assert_eq!(txns.len(), 1);
+                assert_eq!(txns[0].transaction_id(), transaction_id);
+            }
+            status => panic!("Expected a committed status, got {:?}", status),
+        }
+        assert!(committed.submission_latency_ms().is_some());
+
+        let events = store
+            .list_batch_status_events(&id, "TEST")
+            .expect("Failed to list status events");
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].status(), BatchStatusName::Committed);
+
+        let res = store.commit_batch("missing", "TEST", Vec::new());
+        assert_eq!(
+            res.unwrap_err().to_string(),
+            BatchTrackingStoreError::NotFoundError(
+                "Could not find batch with ID missing".to_string()
+            )
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn test_spawn_retention_task() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool.clone())
+            .with_timestamp_precision(TimestampPrecision::Milliseconds);
+
+        let signer = new_signer();
+
+        let tracking_batch = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE)]),
+            false,
+        )
+        .build()
+        .expect("Failed to build batch");
+
+        let id = tracking_batch.batch_header().to_string();
+
+        store
+            .add_batches(vec![tracking_batch])
+            .expect("Failed to add batch");
+
+        let errors = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let task_errors = errors.clone();
+
+        let handle = spawn_retention_task(
+            DieselBatchTrackingStore::new(pool)
+                .with_timestamp_precision(TimestampPrecision::Milliseconds),
+            RetentionPolicy::new(Duration::from_millis(0))
+                .with_timestamp_precision(TimestampPrecision::Milliseconds),
+            Duration::from_millis(10),
+            move |err| {
+                task_errors
+                    .lock()
+                    .expect("Failed to lock errors")
+                    .push(err.to_string())
+            },
+        )
+        .expect("Failed to spawn retention task");
+
+        let mut attempts = 0;
+        while store
+            .get_batch(&id, "TEST")
+            .expect("Failed to get batch")
+            .is_some()
+        {
+            attempts += 1;
+            assert!(attempts < 100, "Stale batch was not removed");
+            std::thread::sleep(Duration::from_millis(20));
+        }
+
+        handle.stop().expect("Failed to stop retention task");
+
+        assert!(errors.lock().expect("Failed to lock errors").is_empty());
+    }
+
+    #[test]
+    fn test_created_at_bounds() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool.clone());
+
+        assert_eq!(
+            store
+                .created_at_bounds("TEST")
+                .expect("Failed to get created_at bounds"),
+            None
+        );
+
+        let signer = new_signer();
+
+        let batches: Vec<TrackingBatch> = [NONCE, NONCE2, "k9fzdz"]
+            .iter()
+            .map(|nonce| {
+                get_tracking_batch(
+                    get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, nonce)]),
+                    false,
+                )
+                .build()
+                .expect("Failed to build batch")
+            })
+            .collect();
+        let ids: Vec<String> = batches
+            .iter()
+            .map(|b| b.batch_header().to_string())
+            .collect();
+
+        store.add_batches(batches).expect("Failed to add batches");
+
+        for (id, created_at) in ids.iter().zip([200, 100, 300].iter()) {
+            diesel::update(
+                schema::batches::table.filter(
+                    schema::batches::batch_id
+                        .eq(id)
+                        .and(schema::batches::service_id.eq("TEST")),
+                ),
+            )
+            .set(schema::batches::created_at.eq(created_at))
+            .execute(&*pool.get().expect("Failed to get connection"))
+            .expect("Failed to set created_at");
+        }
+
+        assert_eq!(
+            store
+                .created_at_bounds("TEST")
+                .expect("Failed to get created_at bounds"),
+            Some((100, 300))
+        );
+        assert_eq!(
+            store
+                .created_at_bounds("OTHER")
+                .expect("Failed to get created_at bounds"),
+            None
+        );
+    }
+
+    #[test]
+    /// Test that batches submitted to different networks are listed by the
+    /// network they were submitted to
+    fn test_list_batches_by_network() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let signer = new_signer();
+
+        let batches: Vec<TrackingBatch> = [NONCE, NONCE2, "k9fzdz"]
+            .iter()
+            .map(|nonce| {
+                get_tracking_batch(
+                    get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, nonce)]),
+                    false,
+                )
+                .build()
+                .expect("Failed to build batch")
+            })
+            .collect();
+        let ids: Vec<String> = batches
+            .iter()
+            .map(|b| b.batch_header().to_string())
+            .collect();
+
+        store.add_batches(batches).expect("Failed to add batches");
+
+        for (id, network_id) in ids.iter().zip(["mainnet", "testnet", "mainnet"].iter()) {
+            store
+                .change_batch_to_submitted(
+                    id,
+                    "TEST",
+                    Vec::new(),
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    Some(network_id),
+                )
+                .expect("Failed to change batch to submitted");
+        }
+
+        let mainnet: Vec<String> = store
+            .list_batches_by_network("mainnet", "TEST")
+            .expect("Failed to list batches")
+            .batches
+            .iter()
+            .map(|b| {
+                assert_eq!(b.network_id(), Some("mainnet"));
+                b.batch_header().to_string()
+            })
+            .collect();
+        assert_eq!(mainnet.len(), 2);
+        assert!(mainnet.contains(&ids[0]));
+        assert!(mainnet.contains(&ids[2]));
+
+        let testnet = store
+            .list_batches_by_network("testnet", "TEST")
+            .expect("Failed to list batches")
+            .batches;
+        assert_eq!(testnet.len(), 1);
+        assert_eq!(testnet[0].batch_header(), ids[1]);
+        assert_eq!(testnet[0].network_id(), Some("testnet"));
+
+        assert!(store
+            .list_batches_by_network("mainnet", "OTHER")
+            .expect("Failed to list batches")
+            .batches
+            .is_empty());
+    }
+
+    #[test]
+    /// Test that a batch moving back and forth between pending and delayed is
+    /// reported as flapping, while a batch whose status is set repeatedly to
+    /// the same value is not
+    fn test_find_flapping_batches() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let signer = new_signer();
+
+        let batches: Vec<TrackingBatch> = [NONCE, NONCE2]
+            .iter()
+            .map(|nonce| {
+                get_tracking_batch(
+                    get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, nonce)]),
+                    false,
+                )
+                .build()
+                .expect("Failed to build batch")
+            })
+            .collect();
+        let ids: Vec<String> = batches
+            .iter()
+            .map(|b| b.batch_header().to_string())
+            .collect();
+
+        store.add_batches(batches).expect("Failed to add batches");
+
+        for status in [
+            BatchStatus::Pending,
+            BatchStatus::Delayed,
+            BatchStatus::Pending,
+            BatchStatus::Delayed,
+            BatchStatus::Pending,
+        ]
+        .iter()
+        {
+            store
+                .update_batch_status(&ids[0], "TEST", Some(status.clone()), Vec::new(), None)
+                .expect("Failed to update batch status");
+            store
+                .update_batch_status(
+                    &ids[1],
+                    "TEST",
+                    Some(BatchStatus::Pending),
+                    Vec::new(),
+                    None,
+                )
+                .expect("Failed to update batch status");
+        }
+
+        assert_eq!(
+            store
+                .find_flapping_batches(3, 0)
+                .expect("Failed to find flapping batches"),
+            vec![ids[0].clone()]
+        );
+        assert!(store
+            .find_flapping_batches(4, 0)
+            .expect("Failed to find flapping batches")
+            .is_empty());
+        assert!(store
+            .find_flapping_batches(0, i64::MAX)
+            .expect("Failed to find flapping batches")
+            .is_empty());
+    }
+
+    #[test]
+    /// Test that incremental syncs return each updated batch exactly once,
+    /// including batches updated after a previous sync
+    fn test_sync_since() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool.clone());
+
+        let signer = new_signer();
+
+        let batches: Vec<TrackingBatch> = [NONCE, NONCE2, "k9fzdz"]
+            .iter()
+            .map(|nonce| {
+                get_tracking_batch(
+                    get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, nonce)]),
+                    false,
+                )
+                .build()
+                .expect("Failed to build batch")
+            })
+            .collect();
+        let ids: Vec<String> = batches
+            .iter()
+            .map(|b| b.batch_header().to_string())
+            .collect();
+
+        store.add_batches(batches).expect("Failed to add batches");
+
+        for (id, created_at) in ids.iter().zip([100, 200, 300].iter()) {
+            diesel::update(
+                schema::batches::table.filter(
+                    schema::batches::batch_id
+                        .eq(id)
+                        .and(schema::batches::service_id.eq("TEST")),
+                ),
+            )
+            .set(schema::batches::created_at.eq(created_at))
+            .execute(&*pool.get().expect("Failed to get connection"))
+            .expect("Failed to set created_at");
+        }
+
+        let (first, checkpoint) = store
+            .sync_since("TEST", None, 2)
+            .expect("Failed to sync batches");
+        let first: Vec<&str> = first.iter().map(|b| b.batch_header()).collect();
+        assert_eq!(first, vec![ids[0].as_str(), ids[1].as_str()]);
+        assert_eq!(checkpoint, Some(200));
+
+        let (second, checkpoint) = store
+            .sync_since("TEST", checkpoint, 2)
+            .expect("Failed to sync batches");
+        let second: Vec<&str> = second.iter().map(|b| b.batch_header()).collect();
+        assert_eq!(second, vec![ids[2].as_str()]);
+        assert_eq!(checkpoint, Some(300));
+
+        let (empty, unchanged) = store
+            .sync_since("TEST", checkpoint, 2)
+            .expect("Failed to sync batches");
+        assert!(empty.is_empty());
+        assert_eq!(unchanged, checkpoint);
+
+        // Updating the status of the first batch brings it back into the
+        // next sync
+        store
+            .update_batch_status(
+                &ids[0],
+                "TEST",
+                Some(BatchStatus::Pending),
+                Vec::new(),
+                None,
+            )
+            .expect("Failed to update batch status");
+
+        let (updated, _) = store
+            .sync_since("TEST", checkpoint, 2)
+            .expect("Failed to sync batches");
+        assert_eq!(updated.len(), 1);
+        assert_eq!(updated[0].batch_header(), ids[0]);
+    }
+
+    #[test]
+    /// Test that batches updated at the same time are not split across syncs
+    fn test_sync_since_same_update_time() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool.clone());
+
+        let signer = new_signer();
+
+        let batches: Vec<TrackingBatch> = [NONCE, NONCE2, "k9fzdz"]
+            .iter()
+            .map(|nonce| {
+                get_tracking_batch(
+                    get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, nonce)]),
+                    false,
+                )
+                .build()
+                .expect("Failed to build batch")
+            })
+            .collect();
+        let ids: Vec<String> = batches
+            .iter()
+            .map(|b| b.batch_header().to_string())
+            .collect();
+
+        store.add_batches(batches).expect("Failed to add batches");
+
+        for (id, created_at) in ids.iter().zip([100, 200, 200].iter()) {
+            diesel::update(
+                schema::batches::table.filter(
+                    schema::batches::batch_id
+                        .eq(id)
+                        .and(schema::batches::service_id.eq("TEST")),
+                ),
+            )
+            .set(schema::batches::created_at.eq(created_at))
+            .execute(&*pool.get().expect("Failed to get connection"))
+            .expect("Failed to set created_at");
+        }
+
+        let (first, checkpoint) = store
+            .sync_since("TEST", None, 2)
+            .expect("Failed to sync batches");
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].batch_header(), ids[0]);
+        assert_eq!(checkpoint, Some(100));
+
+        let (second, checkpoint) = store
+            .sync_since("TEST", checkpoint, 1)
+            .expect("Failed to sync batches");
+        let mut second: Vec<String> = second
+            .iter()
+            .map(|b| b.batch_header().to_string())
+            .collect();
+        second.sort();
+        let mut expected = vec![ids[1].clone(), ids[2].clone()];
+        expected.sort();
+        assert_eq!(second, expected);
+        assert_eq!(checkpoint, Some(200));
+    }
+
+    #[test]
+    /// Test that a batch can be fetched by the alias set on it, and that an
+    /// alias can not be used by two batches in the same service
+    fn test_batch_alias() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let signer = new_signer();
+
+        let batches: Vec<TrackingBatch> = [NONCE, NONCE2]
+            .iter()
+            .map(|nonce| {
+                get_tracking_batch(
+                    get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, nonce)]),
+                    false,
+                )
+                .build()
+                .expect("Failed to build batch")
+            })
+            .collect();
+        let ids: Vec<String> = batches
+            .iter()
+            .map(|b| b.batch_header().to_string())
+            .collect();
+
+        store.add_batches(batches).expect("Failed to add batches");
+
+        assert_eq!(
+            store
+                .get_batch_by_alias("nightly-import", "TEST")
+                .expect("Failed to get batch"),
+            None
+        );
+
+        store
+            .set_alias(&ids[0], "TEST", Some("nightly-import"))
+            .expect("Failed to set alias");
+
+        let batch = store
+            .get_batch_by_alias("nightly-import", "TEST")
+            .expect("Failed to get batch")
+            .expect("Batch not found");
+        assert_eq!(batch.batch_header(), ids[0]);
+        assert_eq!(batch.alias(), Some("nightly-import"));
+
+        // Setting the same alias again on the same batch is allowed
+        store
+            .set_alias(&ids[0], "TEST", Some("nightly-import"))
+            .expect("Failed to set alias");
+
+        match store.set_alias(&ids[1], "TEST", Some("nightly-import")) {
+            Err(BatchTrackingStoreError::ConstraintViolationError(_)) => (),
+            res => panic!("Expected ConstraintViolationError, got {:?}", res),
+        }
+
+        match store.set_alias(&ids[1], "TEST", Some("dcid:nightly-import")) {
+            Err(BatchTrackingStoreError::InvalidArgumentError(_)) => (),
+            res => panic!("Expected InvalidArgumentError, got {:?}", res),
+        }
+
+        match store.set_alias("unknown", "TEST", Some("other")) {
+            Err(BatchTrackingStoreError::NotFoundError(_)) => (),
+            res => panic!("Expected NotFoundError, got {:?}", res),
+        }
+
+        assert_eq!(
+            store
+                .get_batch_by_alias("nightly-import", "OTHER")
+                .expect("Failed to get batch"),
+            None
+        );
+
+        // Once cleared, the alias can be given to another batch
+        store
+            .set_alias(&ids[0], "TEST", None)
+            .expect("Failed to clear alias");
+        store
+            .set_alias(&ids[1], "TEST", Some("nightly-import"))
+            .expect("Failed to set alias");
+
+        let batch = store
+            .get_batch_by_alias("nightly-import", "TEST")
+            .expect("Failed to get batch")
+            .expect("Batch not found");
+        assert_eq!(batch.batch_header(), ids[1]);
+    }
+
+    #[test]
+    /// Test that the statuses of a service with more batches than are read at
+    /// a time are all returned, and that batches without a status are left out
+    fn test_all_statuses_for_service() {
+        use super::models::NewBatchStatusModel;
+        use super::schema::batch_statuses;
+
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool.clone());
+
+        assert!(store
+            .all_statuses_for_service("TEST")
+            .expect("Failed to get statuses")
+            .is_empty());
+
+        let signer = new_signer();
+
+        let batches: Vec<TrackingBatch> = (0..1201)
+            .map(|i| {
+                let nonce = format!("nonce{:04}", i);
+                get_tracking_batch(
+                    get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, &nonce)]),
+                    false,
+                )
+                .build()
+                .expect("Failed to build batch")
+            })
+            .collect();
+        let ids: Vec<String> = batches
+            .iter()
+            .map(|b| b.batch_header().to_string())
+            .collect();
+
+        store.add_batches(batches).expect("Failed to add batches");
+
+        // Every batch but the last is given a status
+        let names = [
+            BatchStatusName::Pending,
+            BatchStatusName::Committed,
+            BatchStatusName::Invalid,
+        ];
+        let expected: HashMap<String, BatchStatusName> = ids[..1200]
+            .iter()
+            .enumerate()
+            .map(|(i, id)| (id.clone(), names[i % names.len()]))
+            .collect();
+
+        let statuses: Vec<NewBatchStatusModel> = expected
+            .iter()
+            .map(|(id, status)| NewBatchStatusModel {
+                service_id: "TEST".to_string(),
+                batch_id: id.clone(),
+                dlt_status: status.to_string(),
+            })
+            .collect();
+        diesel::insert_into(batch_statuses::table)
+            .values(&statuses)
+            .execute(&*pool.get().expect("Failed to get connection"))
+            .expect("Failed to insert statuses");
+
+        assert_eq!(
+            store
+                .all_statuses_for_service("TEST")
+                .expect("Failed to get statuses"),
+            expected
+        );
+        assert!(store
+            .all_statuses_for_service("OTHER")
+            .expect("Failed to get statuses")
+            .is_empty());
+    }
+
+    #[test]
+    /// Test that a submitted batch with no status reports no status until it
+    /// is repaired, after which it is Unknown, and that unsubmitted batches
+    /// and batches with a status are left alone
+    fn test_repair_missing_statuses() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool.clone());
+
+        let signer = new_signer();
+
+        let batches: Vec<TrackingBatch> = [(NONCE, true), (NONCE2, true), ("k9fzdz", false)]
+            .iter()
+            .map(|(nonce, submitted)| {
+                get_tracking_batch(
+                    get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, nonce)]),
+                    *submitted,
+                )
+                .build()
+                .expect("Failed to build batch")
+            })
+            .collect();
+        let ids: Vec<String> = batches
+            .iter()
+            .map(|b| b.batch_header().to_string())
+            .collect();
+
+        store.add_batches(batches).expect("Failed to add batches");
+
+        store
+            .update_batch_status(
+                &ids[1],
+                "TEST",
+                Some(BatchStatus::Pending),
+                Vec::new(),
+                None,
+            )
+            .expect("Failed to update batch status");
+
+        // The first batch was submitted, but its status is missing
+        diesel::delete(
+            schema::batch_statuses::table.filter(
+                schema::batch_statuses::batch_id
+                    .eq(&ids[0])
+                    .and(schema::batch_statuses::service_id.eq("TEST")),
+            ),
+        )
+        .execute(&*pool.get().expect("Failed to get connection"))
+        .expect("Failed to delete status");
+
+        assert_eq!(
+            store
+                .get_batch_status(&ids[0], "TEST")
+                .expect("Failed to get status"),
+            None
+        );
+
+        assert_eq!(
+            store
+                .repair_missing_statuses()
+                .expect("Failed to repair statuses"),
+            1
+        );
+
+        assert_eq!(
+            store
+                .get_batch_status(&ids[0], "TEST")
+                .expect("Failed to get status"),
+            Some(BatchStatus::Unknown)
+        );
+        assert_eq!(
+            store
+                .get_batch_status(&ids[1], "TEST")
+                .expect("Failed to get status")
+                .map(|status| status.to_string()),
+            Some("Pending".to_string())
+        );
+        assert_eq!(
+            store
+                .get_batch_status(&ids[2], "TEST")
+                .expect("Failed to get status"),
+            None
+        );
+
+        assert_eq!(
+            store
+                .repair_missing_statuses()
+                .expect("Failed to repair statuses"),
+            0
+        );
+    }
+
+    #[test]
+    /// Test that operations run on a connection provided by the caller share
+    /// its transaction, so they are rolled back or committed together
+    fn test_with_connection() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool.clone());
+
+        let signer = new_signer();
+
+        let tracking_batch = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE)]),
+            false,
+        )
+        .build()
+        .expect("Failed to build batch");
+        let id = tracking_batch.batch_header().to_string();
+
+        {
+            let conn = pool.get().expect("Failed to get connection");
+            let result = conn.transaction::<(), _, _>(|| {
+                let connection_store = store.with_connection(&*conn);
+
+                connection_store
+                    .add_batches(vec![tracking_batch.clone()])
+                    .expect("Failed to add batch");
+                connection_store
+                    .update_batch_status(&id, "TEST", Some(BatchStatus::Pending), Vec::new(), None)
+                    .expect("Failed to update batch status");
+
+                assert!(connection_store
+                    .get_batch(&id, "TEST")
+                    .expect("Failed to get batch")
+                    .is_some());
+
+                Err(diesel::result::Error::RollbackTransaction)
+            });
+            assert_eq!(result, Err(diesel::result::Error::RollbackTransaction));
+        }
+
+        assert_eq!(
+            store.get_batch(&id, "TEST").expect("Failed to get batch"),
+            None
+        );
+
+        {
+            let conn = pool.get().expect("Failed to get connection");
+            conn.transaction::<(), diesel::result::Error, _>(|| {
+                let connection_store = store.with_connection(&*conn);
+
+                connection_store
+                    .add_batches(vec![tracking_batch.clone()])
+                    .expect("Failed to add batch");
+                connection_store
+                    .update_batch_status(&id, "TEST", Some(BatchStatus::Pending), Vec::new(), None)
+                    .expect("Failed to update batch status");
+
+                Ok(())
+            })
+            .expect("Failed to commit transaction");
+        }
+
+        assert_eq!(
+            store
+                .get_batch_status(&id, "TEST")
+                .expect("Failed to get status")
+                .map(|status| status.to_string()),
+            Some("Pending".to_string())
+        );
+    }
+
+    #[test]
+    /// Test that batches are listed by their number of submission attempts,
+    /// most first, up to the limit, and that batches never submitted are not
+    /// listed
+    fn test_list_batches_by_attempts() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let signer = new_signer();
+
+        let batches: Vec<TrackingBatch> = [NONCE, NONCE2, "k9fzdz", "zdz9fk"]
+            .iter()
+            .map(|nonce| {
+                get_tracking_batch(
+                    get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, nonce)]),
+                    false,
+                )
+                .build()
+                .expect("Failed to build batch")
+            })
+            .collect();
+        let ids: Vec<String> = batches
+            .iter()
+            .map(|b| b.batch_header().to_string())
+            .collect();
+
+        store.add_batches(batches).expect("Failed to add batches");
+
+        // The last batch is never submitted
+        for (id, attempts) in ids[..3].iter().zip([2, 5, 0].iter()) {
+            store
+                .change_batch_to_submitted(
+                    id,
+                    "TEST",
+                    Vec::new(),
+                    Some("Pending"),
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                )
+                .expect("Failed to change batch to submitted");
+            for _ in 0..*attempts {
+                store
+                    .record_submission_attempt(id, "TEST")
+                    .expect("Failed to record submission attempt");
+            }
+        }
+
+        let listed: Vec<String> = store
+            .list_batches_by_attempts("TEST", 10)
+            .expect("Failed to list batches")
+            .batches
+            .iter()
+            .map(|b| b.batch_header().to_string())
+            .collect();
+        assert_eq!(listed, vec![ids[1].clone(), ids[0].clone(), ids[2].clone()]);
+
+        let limited: Vec<String> = store
+            .list_batches_by_attempts("TEST", 2)
+            .expect("Failed to list batches")
+            .batches
+            .iter()
+            .map(|b| b.batch_header().to_string())
+            .collect();
+        assert_eq!(limited, vec![ids[1].clone(), ids[0].clone()]);
+
+        assert!(store
+            .list_batches_by_attempts("OTHER", 10)
+            .expect("Failed to list batches")
+            .batches
+            .is_empty());
+    }
+
+    #[test]
+    /// Test that a batch can not be added with a data change ID that is the ID
+    /// of a batch in the same service
+    fn test_add_batches_id_collision() {
+        use super::models::NewBatchModel;
+
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool.clone());
+
+        // Batch IDs are not validated when stored directly, so a batch whose
+        // ID looks like a data change ID can be created
+        diesel::insert_into(schema::batches::table)
+            .values(NewBatchModel {
+                service_id: "TEST".to_string(),
+                batch_id: "dcid:collides".to_string(),
+                data_change_id: None,
+                signer_public_key: KEY1.to_string(),
+                trace: false,
+                serialized_batch: Vec::new(),
+                submitted: false,
+                created_at: 0,
+                notes: None,
+                byte_size: 0,
+                batch_kind: None,
+                origin: None,
+                metadata: None,
+            })
+            .execute(&*pool.get().expect("Failed to get connection"))
+            .expect("Failed to insert batch");
+
+        let signer = new_signer();
+
+        let colliding = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE)]),
+            false,
+        )
+        .with_data_change_id("dcid:collides".to_string())
+        .build()
+        .expect("Failed to build batch");
+
+        match store.add_batches(vec![colliding.clone()]) {
+            Err(BatchTrackingStoreError::IdCollision(id)) => {
+                assert_eq!(id, colliding.batch_header())
+            }
+            res => panic!("Expected IdCollision, got {:?}", res),
+        }
+        assert_eq!(
+            store
+                .get_batch(colliding.batch_header(), "TEST")
+                .expect("Failed to get batch"),
+            None
+        );
+
+        // The same data change ID does not collide in another service
+        let other_service = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE)]),
+            false,
+        )
+        .with_service_id("OTHER".to_string())
+        .with_data_change_id("dcid:collides".to_string())
+        .build()
+        .expect("Failed to build batch");
+
+        store
+            .add_batches(vec![other_service])
+            .expect("Failed to add batch");
+    }
+
+    #[test]
+    /// Test that the transactions in all of a service's batches are counted
+    fn test_count_transactions() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        assert_eq!(
+            store
+                .count_transactions("TEST")
+                .expect("Failed to count transactions"),
+            0
+        );
+
+        let signer = new_signer();
+
+        let batches = vec![
+            get_tracking_batch(
+                get_transact_batch(
+                    &*signer,
+                    vec![
+                        get_transact_transaction(&*signer, NONCE),
+                        get_transact_transaction(&*signer, NONCE2),
+                        get_transact_transaction(&*signer, "k9fzdz"),
+                    ],
+                ),
+                false,
+            )
+            .build()
+            .expect("Failed to build batch"),
+            get_tracking_batch(
+                get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, "zdz9fk")]),
+                false,
+            )
+            .build()
+            .expect("Failed to build batch"),
+        ];
+
+        store.add_batches(batches).expect("Failed to add batches");
+
+        assert_eq!(
+            store
+                .count_transactions("TEST")
+                .expect("Failed to count transactions"),
+            4
+        );
+        assert_eq!(
+            store
+                .count_transactions("OTHER")
+                .expect("Failed to count transactions"),
+            0
+        );
+    }
+
+    #[test]
+    /// Test that failed batches are paged most recently failed first
+    fn test_list_failed_batches_recent() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool.clone());
+
+        let signer = new_signer();
+
+        // Failures are set directly so that they can be given distinct times
+        let set_status = |id: &str, status: &str, updated_at: i64| {
+            diesel::update(
+                schema::batch_statuses::table.filter(
+                    schema::batch_statuses::batch_id
+                        .eq(id)
+                        .and(schema::batch_statuses::service_id.eq("TEST")),
+                ),
+            )
+            .set((
+                schema::batch_statuses::dlt_status.eq(status),
+                schema::batch_statuses::updated_at.eq(updated_at),
+            ))
+            .execute(&*pool.get().expect("Failed to get connection"))
+            .expect("Failed to set status");
+        };
+
+        let batches: Vec<TrackingBatch> = [NONCE, NONCE2, "k9fzdz", "zdz9fk"]
+            .iter()
+            .map(|nonce| {
+                get_tracking_batch(
+                    get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, nonce)]),
+                    false,
+                )
+                .build()
+                .expect("Failed to build batch")
+            })
+            .collect();
+        let ids: Vec<String> = batches
+            .iter()
+            .map(|b| b.batch_header().to_string())
+            .collect();
+
+        store.add_batches(batches).expect("Failed to add batches");
+
+        for id in &ids {
+            store
+                .change_batch_to_submitted(
+                    id,
+                    "TEST",
+                    Vec::new(),
+                    Some("Pending"),
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                )
+                .expect("Failed to change batch to submitted");
+        }
+
+        // The last batch is still pending, so it has not failed
+        set_status(&ids[0], "Unknown", 100);
+        set_status(&ids[1], "Unknown", 300);
+        set_status(&ids[2], "Unknown", 200);
+        set_status(&ids[3], "Pending", 400);
+
+        let list_ids = |offset: i64, limit: i64| -> Vec<String> {
+            store
+                .list_failed_batches_recent("TEST", offset, limit)
+                .expect("Failed to list failed batches")
+                .batches
+                .iter()
+                .map(|b| b.batch_header().to_string())
+                .collect()
+        };
+
+        assert_eq!(list_ids(0, 2), vec![ids[1].clone(), ids[2].clone()]);
+        assert_eq!(list_ids(2, 2), vec![ids[0].clone()]);
+        assert!(list_ids(4, 2).is_empty());
+
+        assert!(store
+            .list_failed_batches_recent("OTHER", 0, 10)
+            .expect("Failed to list failed batches")
+            .batches
+            .is_empty());
+    }
+
+    #[test]
+    /// Test that connections are returned to the pool when operations fail
+    fn test_failing_operations_return_connections() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let signer = new_signer();
+
+        let batch = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE)]),
+            false,
+        )
+        .build()
+        .expect("Failed to build batch");
+
+        store
+            .add_batches(vec![batch.clone()])
+            .expect("Failed to add batch");
+
+        // Each operation fails part way through its transaction
+        for _ in 0..100 {
+            assert!(store.add_batches(vec![batch.clone()]).is_err());
+            assert!(store.set_alias("missing", "TEST", Some("alias")).is_err());
+            assert!(store
+                .change_batch_to_submitted(
+                    batch.batch_header(),
+                    "TEST",
+                    Vec::new(),
+                    Some("Committed"),
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None
+                )
+                .is_err());
+        }
+
+        let state = store.pool_state();
+        assert_eq!(state.connections(), 1);
+        assert_eq!(state.idle_connections(), 1);
+
+        // The failed transactions were rolled back, so the connections can
+        // still be used
+        assert!(store
+            .get_batch(batch.batch_header(), "TEST")
+            .expect("Failed to get batch")
+            .expect("Batch not found")
+            .batch_status()
+            .is_none());
+    }
+
+    #[test]
+    /// Test that batches can be listed by kind, and that a store with allowed
+    /// kinds rejects batches of other kinds
+    fn test_list_batches_by_kind() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool.clone())
+            .with_allowed_batch_kinds(vec!["product".to_string(), "agent".to_string()]);
+
+        let signer = new_signer();
+
+        let batches: Vec<TrackingBatch> = [NONCE, NONCE2, "k9fzdz"]
+            .iter()
+            .zip(["product", "agent", "product"].iter())
+            .map(|(nonce, kind)| {
+                get_tracking_batch(
+                    get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, nonce)]),
+                    false,
+                )
+                .with_batch_kind(kind.to_string())
+                .build()
+                .expect("Failed to build batch")
+            })
+            .collect();
+        let ids: Vec<String> = batches
+            .iter()
+            .map(|b| b.batch_header().to_string())
+            .collect();
+
+        store.add_batches(batches).expect("Failed to add batches");
+
+        let products: Vec<String> = store
+            .list_batches_by_kind("product", "TEST")
+            .expect("Failed to list batches")
+            .batches
+            .iter()
+            .map(|b| {
+                assert_eq!(b.batch_kind(), Some("product"));
+                b.batch_header().to_string()
+            })
+            .collect();
+        assert_eq!(products.len(), 2);
+        assert!(products.contains(&ids[0]));
+        assert!(products.contains(&ids[2]));
+
+        let agents = store
+            .list_batches_by_kind("agent", "TEST")
+            .expect("Failed to list batches")
+            .batches;
+        assert_eq!(agents.len(), 1);
+        assert_eq!(agents[0].batch_header(), ids[1]);
+        assert_eq!(agents[0].batch_kind(), Some("agent"));
+
+        assert!(store
+            .list_batches_by_kind("product", "OTHER")
+            .expect("Failed to list batches")
+            .batches
+            .is_empty());
+
+        let disallowed = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, "zdz9fk")]),
+            false,
+        )
+        .with_batch_kind("location".to_string())
+        .build()
+        .expect("Failed to build batch");
+
+        match store.add_batches(vec![disallowed.clone()]) {
+            Err(BatchTrackingStoreError::InvalidArgumentError(_)) => (),
+            res => panic!("Expected InvalidArgumentError, got {:?}", res),
+        }
+
+        // A store without allowed kinds accepts batches of any kind
+        DieselBatchTrackingStore::new(pool)
+            .add_batches(vec![disallowed])
+            .expect("Failed to add batch");
+    }
+
+    #[test]
+    /// Test that get_batch_projected only loads the parts of a batch selected
+    /// by the projection
+    fn test_get_batch_projected() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let signer = new_signer();
+
+        let transaction = get_transact_transaction(&*signer, NONCE);
+        let transaction_id = transaction.header_signature().to_string();
+        let batch = get_tracking_batch(get_transact_batch(&*signer, vec![transaction]), false)
+            .build()
+            .expect("Failed to build batch");
+        let id = batch.batch_header().to_string();
+
+        store.add_batches(vec![batch]).expect("Failed to add batch");
+
+        let submission_error = SubmissionErrorBuilder::default()
+            .with_error_type("test".to_string())
+            .with_error_message("test message".to_string())
+            .build()
+            .expect("Failed to build error");
+
+        let receipt = TransactionReceiptBuilder::default()
+            .with_transaction_id(transaction_id.to_string())
+            .with_result_valid(false)
+            .with_error_message("test".to_string())
+            .with_error_data(BYTES2.to_vec())
+            .with_serialized_receipt(
+                std::str::from_utf8(&BYTES2)
+                    .expect("Failed to build string")
+                    .to_string(),
+            )
+            .build()
+            .expect("Failed to build receipt");
+
+        let invalid_transactions = vec![InvalidTransactionBuilder::default()
+            .with_transaction_id(transaction_id)
+            .with_error_message("test".to_string())
+            .with_error_data(BYTES2.to_vec())
+            .build()
+            .expect("Failed to build transaction")];
+
+        store
+            .update_batch_status(
+                &id,
+                "TEST",
+                Some(BatchStatus::Invalid(invalid_transactions.clone())),
+                vec![receipt],
+                Some(submission_error.clone()),
+            )
+            .expect("Failed to update batch");
+
+        let get_projected = |projection: BatchProjection| {
+            store
+                .get_batch_projected(&id, "TEST", projection)
+                .expect("Failed to get batch")
+                .expect("Batch not found")
+        };
+
+        let batch = get_projected(BatchProjection::new());
+        assert_eq!(batch.batch_header(), id);
+        assert!(batch.transactions().is_empty());
+        assert!(batch.batch_status().is_none());
+        assert!(batch.submission_error().is_none());
+
+        let batch = get_projected(BatchProjection::new().with_transactions(true));
+        assert_eq!(batch.transactions().len(), 1);
+        assert!(batch.batch_status().is_none());
+        assert!(batch.submission_error().is_none());
+
+        let batch = get_projected(BatchProjection::new().with_status(true));
+        assert!(batch.transactions().is_empty());
+        assert_eq!(
+            batch.batch_status(),
+            Some(&BatchStatus::Invalid(Vec::new()))
+        );
+        assert!(batch.submission_error().is_none());
+
+        // Receipts are returned in the status, so they are not loaded without
+        // it
+        let batch = get_projected(BatchProjection::new().with_receipts(true));
+        assert!(batch.batch_status().is_none());
+
+        let batch = get_projected(BatchProjection::new().with_status(true).with_receipts(true));
+        assert_eq!(
+            batch.batch_status(),
+            Some(&BatchStatus::Invalid(invalid_transactions))
+        );
+
+        let batch = get_projected(BatchProjection::new().with_submission_error(true));
+        assert!(batch.transactions().is_empty());
+        assert!(batch.batch_status().is_none());
+        assert_eq!(batch.submission_error(), Some(&submission_error));
+
+        assert_eq!(
+            Some(get_projected(BatchProjection::all())),
+            store.get_batch(&id, "TEST").expect("Failed to get batch")
+        );
+
+        assert!(store
+            .get_batch_projected("missing", "TEST", BatchProjection::all())
+            .expect("Failed to get batch")
+            .is_none());
+    }
+
+    #[test]
+    /// Test that batches can be found by the start of the ID of one of their
+    /// transactions
+    fn test_find_batches_by_transaction_prefix() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let signer = new_signer();
+
+        // With more batches than hex digits, at least two transaction IDs
+        // start with the same digit
+        let mut batches = Vec::new();
+        let mut transaction_ids = Vec::new();
+        for i in 0..17 {
+            let transaction = get_transact_transaction(&*signer, &format!("nonce{}", i));
+            transaction_ids.push(transaction.header_signature().to_string());
+            batches.push(
+                get_tracking_batch(get_transact_batch(&*signer, vec![transaction]), false)
+                    .build()
+                    .expect("Failed to build batch"),
+            );
+        }
+        let ids: Vec<String> = batches
+            .iter()
+            .map(|b| b.batch_header().to_string())
+            .collect();
+
+        store.add_batches(batches).expect("Failed to add batches");
+
+        let find_ids = |prefix: &str| -> Vec<String> {
+            let mut found: Vec<String> = store
+                .find_batches_by_transaction_prefix(prefix, "TEST")
+                .expect("Failed to find batches")
+                .batches
+                .iter()
+                .map(|b| b.batch_header().to_string())
+                .collect();
+            found.sort();
+            found
+        };
+
+        assert_eq!(find_ids(&transaction_ids[0][..16]), vec![ids[0].clone()]);
+
+        let shared_prefix = transaction_ids
+            .iter()
+            .map(|id| &id[..1])
+            .find(|prefix| {
+                transaction_ids
+                    .iter()
+                    .filter(|id| id.starts_with(*prefix))
+                    .count()
+                    > 1
+            })
+            .expect("No transaction IDs share a prefix");
+        let mut expected: Vec<String> = ids
+            .iter()
+            .zip(transaction_ids.iter())
+            .filter(|(_, transaction_id)| transaction_id.starts_with(shared_prefix))
+            .map(|(id, _)| id.clone())
+            .collect();
+        expected.sort();
+        assert!(expected.len() > 1);
+        assert_eq!(find_ids(shared_prefix), expected);
+
+        assert!(find_ids("zz").is_empty());
+        // Wildcards are matched literally
+        assert!(find_ids("%").is_empty());
+        assert!(find_ids("_").is_empty());
+
+        assert!(store
+            .find_batches_by_transaction_prefix(&transaction_ids[0], "OTHER")
+            .expect("Failed to find batches")
+            .batches
+            .is_empty());
+    }
+
+    #[test]
+    /// Test that a batch's JSON metadata is stored and returned, and that
+    /// metadata that is not a JSON object is rejected
+    fn test_batch_metadata() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let signer = new_signer();
+
+        let metadata = serde_json::json!({
+            "origin": {
+                "system": "erp",
+                "ids": [1, 2, 3],
+            },
+            "priority": 2,
+            "reviewed": true,
+        });
+
+        let batch = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE)]),
+            false,
+        )
+        .with_metadata(metadata.clone())
+        .build()
+        .expect("Failed to build batch");
+        let id = batch.batch_header().to_string();
+
+        store.add_batches(vec![batch]).expect("Failed to add batch");
+
+        let stored = store
+            .get_batch(&id, "TEST")
+            .expect("Failed to get batch")
+            .expect("Batch not found");
+        assert_eq!(stored.metadata(), Some(&metadata));
+
+        for not_object in &[
+            serde_json::json!([1, 2]),
+            serde_json::json!("metadata"),
+            serde_json::json!(null),
+        ] {
+            match get_tracking_batch(
+                get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE2)]),
+                false,
+            )
+            .with_metadata(not_object.clone())
+            .build()
+            {
+                Err(BatchBuilderError::InvalidField(_)) => (),
+                res => panic!("Expected InvalidField, got {:?}", res),
+            }
+        }
+    }
+
+    #[test]
+    /// Test that failure summaries hold the stored submission errors, most
+    /// recently failed first
+    fn test_list_failure_summaries() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool.clone());
+
+        let signer = new_signer();
+
+        // Failures are set directly so that they can be given distinct times
+        let set_status = |id: &str, status: &str, updated_at: i64| {
+            diesel::update(
+                schema::batch_statuses::table.filter(
+                    schema::batch_statuses::batch_id
+                        .eq(id)
+                        .and(schema::batch_statuses::service_id.eq("TEST")),
+                ),
+            )
+            .set((
+                schema::batch_statuses::dlt_status.eq(status),
+                schema::batch_statuses::updated_at.eq(updated_at),
+            ))
+            .execute(&*pool.get().expect("Failed to get connection"))
+            .expect("Failed to set status");
+        };
+
+        let batches: Vec<TrackingBatch> = [NONCE, NONCE2, "k9fzdz", "zdz9fk"]
+            .iter()
+            .map(|nonce| {
+                get_tracking_batch(
+                    get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, nonce)]),
+                    false,
+                )
+                .build()
+                .expect("Failed to build batch")
+            })
+            .collect();
+        let ids: Vec<String> = batches
+            .iter()
+            .map(|b| b.batch_header().to_string())
+            .collect();
+
+        store.add_batches(batches).expect("Failed to add batches");
+
+        let errors: Vec<Option<SubmissionError>> = vec![
+            Some(
+                SubmissionErrorBuilder::default()
+                    .with_error_type("timeout".to_string())
+                    .with_error_message("first error".to_string())
+                    .build()
+                    .expect("Failed to build error"),
+            ),
+            Some(
+                SubmissionErrorBuilder::default()
+                    .with_error_type("rejected".to_string())
+                    .with_error_message("second error".to_string())
+                    .build()
+                    .expect("Failed to build error"),
+            ),
+            None,
+            None,
+        ];
+
+        for (id, error) in ids.iter().zip(errors.iter()) {
+            store
+                .change_batch_to_submitted(
+                    id,
+                    "TEST",
+                    Vec::new(),
+                    Some("Pending"),
+                    error.clone(),
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                )
+                .expect("Failed to change batch to submitted");
+        }
+
+        // The last batch is still pending, so it has not failed
+        set_status(&ids[0], "Unknown", 100);
+        set_status(&ids[1], "Unknown", 300);
+        set_status(&ids[2], "Unknown", 200);
+        set_status(&ids[3], "Pending", 400);
+
+        let summaries = store
+            .list_failure_summaries("TEST", 10)
+            .expect("Failed to list failure summaries");
+        assert_eq!(
+            summaries,
+            vec![
+                FailureSummary::new(
+                    ids[1].clone(),
+                    Some("rejected".to_string()),
+                    Some("second error".to_string()),
+                    300,
+                ),
+                FailureSummary::new(ids[2].clone(), None, None, 200),
+                FailureSummary::new(
+                    ids[0].clone(),
+                    Some("timeout".to_string()),
+                    Some("first error".to_string()),
+                    100,
+                ),
+            ]
+        );
+
+        assert_eq!(
+            store
+                .list_failure_summaries("TEST", 2)
+                .expect("Failed to list failure summaries"),
+            summaries[..2].to_vec()
+        );
+
+        assert!(store
+            .list_failure_summaries("OTHER", 10)
+            .expect("Failed to list failure summaries")
+            .is_empty());
+    }
+
+    #[test]
+    /// Test that repeated status updates to the same status within the
+    /// configured debounce record a single status event, that each distinct
+    /// transition records its own event, and that a store without a debounce
+    /// records every update
+    fn test_status_event_debounce() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool.clone())
+            .with_status_event_debounce(Duration::from_secs(60));
+
+        let signer = new_signer();
+
+        let batch = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE)]),
+            false,
+        )
+        .build()
+        .expect("Failed to build batch");
+        let id = batch.batch_header().to_string();
+
+        store
+            .add_batches(vec![batch])
+            .expect("Failed to add batches");
+
+        for _ in 0..3 {
+            store
+                .update_batch_status(&id, "TEST", Some(BatchStatus::Pending), Vec::new(), None)
+                .expect("Failed to update batch status");
+        }
+
+        let events = store
+            .list_batch_status_events(&id, "TEST")
+            .expect("Failed to list status events");
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].status(), BatchStatusName::Pending);
+
+        store
+            .update_batch_status(&id, "TEST", Some(BatchStatus::Delayed), Vec::new(), None)
+            .expect("Failed to update batch status");
+        store
+            .update_batch_status(&id, "TEST", Some(BatchStatus::Pending), Vec::new(), None)
+            .expect("Failed to update batch status");
+
+        let events = store
+            .list_batch_status_events(&id, "TEST")
+            .expect("Failed to list status events");
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[1].status(), BatchStatusName::Delayed);
+        assert_eq!(events[2].status(), BatchStatusName::Pending);
+
+        let undebounced_store = DieselBatchTrackingStore::new(pool);
+
+        undebounced_store
+            .update_batch_status(&id, "TEST", Some(BatchStatus::Pending), Vec::new(), None)
+            .expect("Failed to update batch status");
+
+        let events = undebounced_store
+            .list_batch_status_events(&id, "TEST")
+            .expect("Failed to list status events");
+        assert_eq!(events.len(), 4);
+    }
+
+    #[test]
+    /// Test that swapping a batch's status returns the status it had before,
+    /// or none if it had no status, and applies the new status
+    fn test_swap_batch_status() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let signer = new_signer();
+
+        let batch = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE)]),
+            false,
+        )
+        .build()
+        .expect("Failed to build batch");
+        let id = batch.batch_header().to_string();
+
+        store
+            .add_batches(vec![batch])
+            .expect("Failed to add batches");
+
+        let previous = store
+            .swap_batch_status(&id, "TEST", BatchStatus::Pending)
+            .expect("Failed to swap batch status");
+        assert_eq!(previous, None);
+        assert_eq!(
+            store
+                .get_batch_status(&id, "TEST")
+                .expect("Failed to get batch status"),
+            Some(BatchStatus::Pending)
+        );
+
+        let previous = store
+            .swap_batch_status(&id, "TEST", BatchStatus::Delayed)
+            .expect("Failed to swap batch status");
+        assert_eq!(previous, Some(BatchStatus::Pending));
+        assert_eq!(
+            store
+                .get_batch_status(&id, "TEST")
+                .expect("Failed to get batch status"),
+            Some(BatchStatus::Delayed)
+        );
+    }
+
+    #[test]
+    /// Test that getting a limited number of unsubmitted batches returns
+    /// exactly that many of the oldest unsubmitted batches
+    fn test_get_unsubmitted_batches_limited() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool.clone());
+
+        let signer = new_signer();
+
+        let batches: Vec<TrackingBatch> = (0..5)
+            .map(|i| {
+                get_tracking_batch(
+                    get_transact_batch(
+                        &*signer,
+                        vec![get_transact_transaction(&*signer, &format!("limited{}", i))],
+                    ),
+                    false,
+                )
+                .build()
+                .expect("Failed to build batch")
+            })
+            .collect();
+        let ids: Vec<String> = batches
+            .iter()
+            .map(|b| b.batch_header().to_string())
+            .collect();
+
+        store.add_batches(batches).expect("Failed to add batches");
+
+        for (id, created_at) in ids.iter().zip([400, 100, 500, 300, 200].iter()) {
+            diesel::update(
+                schema::batches::table.filter(
+                    schema::batches::batch_id
+                        .eq(id)
+                        .and(schema::batches::service_id.eq("TEST")),
+                ),
+            )
+            .set(schema::batches::created_at.eq(created_at))
+            .execute(&*pool.get().expect("Failed to get connection"))
+            .expect("Failed to set created_at");
+        }
+
+        let unsubmitted: Vec<String> = store
+            .get_unsubmitted_batches_limited(Some("TEST"), 3)
+            .expect("Failed to get unsubmitted batches")
+            .batches
+            .iter()
+            .map(|b| b.batch_header().to_string())
+            .collect();
+        assert_eq!(
+            unsubmitted,
+            vec![ids[1].to_string(), ids[4].to_string(), ids[3].to_string()]
+        );
+
+        assert_eq!(
+            store
+                .get_unsubmitted_batches_limited(None, 10)
+                .expect("Failed to get unsubmitted batches")
+                .batches
+                .len(),
+            5
+        );
+        assert!(store
+            .get_unsubmitted_batches_limited(Some("OTHER"), 3)
+            .expect("Failed to get unsubmitted batches")
+            .batches
+            .is_empty());
+    }
+
+    #[test]
+    /// Test that a stored tracking batch built from a transact batch
+    /// validates against that batch
+    fn test_validate_against_batch() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let signer = new_signer();
+        let signer_public_key = hex::to_hex(
+            signer
+                .public_key()
+                .expect("Failed to get public key")
+                .as_slice(),
+        );
+
+        let batch = get_transact_batch(
+            &*signer,
+            vec![
+                get_transact_transaction(&*signer, NONCE),
+                get_transact_transaction(&*signer, NONCE2),
+            ],
+        );
+        let tracking_batch = get_tracking_batch(batch.clone(), false)
+            .with_signer_public_key(signer_public_key)
+            .build()
+            .expect("Failed to build batch");
+        let id = tracking_batch.batch_header().to_string();
+
+        store
+            .add_batches(vec![tracking_batch])
+            .expect("Failed to add batches");
+
+        let stored = store
+            .get_batch(&id, "TEST")
+            .expect("Failed to get batch")
+            .expect("Batch not found");
+
+        assert_eq!(validate_against_batch(&stored, &batch), Ok(()));
+    }
+
+    #[test]
+    /// Test that validating a tracking batch against a transact batch with a
+    /// different number of transactions or a different signer reports each
+    /// mismatch
+    fn test_validate_against_batch_mismatches() {
+        let signer = new_signer();
+        let signer_public_key = hex::to_hex(
+            signer
+                .public_key()
+                .expect("Failed to get public key")
+                .as_slice(),
+        );
+
+        let batch = get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE)]);
+
+        // The test helper uses a signer public key that did not sign the batch
+        let wrong_signer = get_tracking_batch(batch.clone(), false)
+            .build()
+            .expect("Failed to build batch");
+
+        let err = validate_against_batch(&wrong_signer, &batch)
+            .expect_err("Validated a batch with the wrong signer");
+        assert_eq!(
+            err.mismatches(),
+            &[BatchMismatch::SignerPublicKey {
+                expected: signer_public_key.to_string(),
+                found: KEY1.to_string(),
+            }][..]
+        );
+
+        let two_txn_batch = get_transact_batch(
+            &*signer,
+            vec![
+                get_transact_transaction(&*signer, NONCE),
+                get_transact_transaction(&*signer, NONCE2),
+            ],
+        );
+        let wrong_count = get_tracking_batch(batch, false)
+            .with_signer_public_key(signer_public_key)
+            .build()
+            .expect("Failed to build batch");
+
+        let err = validate_against_batch(&wrong_count, &two_txn_batch)
+            .expect_err("Validated a batch with the wrong transaction count");
+        assert!(err.mismatches().contains(&BatchMismatch::TransactionCount {
+            expected: 2,
+            found: 1,
+        }));
+        assert!(err.mismatches().contains(&BatchMismatch::BatchId {
+            expected: two_txn_batch.header_signature().to_string(),
+            found: wrong_count.batch_header().to_string(),
+        }));
+    }
+
+    #[test]
+    /// Test that with read-your-writes enabled, a batch just added or updated
+    /// through the store is read from the write pool even though the read
+    /// pool, standing in for a lagging replica, does not have it
+    fn test_read_your_writes() {
+        let primary = create_connection_pool_and_migrate();
+        let replica = create_connection_pool_and_migrate();
+
+        let lagging_store =
+            DieselBatchTrackingStore::with_read_pool(primary.clone(), replica.clone());
+        let store = DieselBatchTrackingStore::with_read_pool(primary, replica)
+            .with_read_your_writes(Duration::from_secs(60));
+
+        let signer = new_signer();
+
+        let batch = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE)]),
+            false,
+        )
+        .build()
+        .expect("Failed to build batch");
+        let id = batch.batch_header().to_string();
+
+        store
+            .add_batches(vec![batch])
+            .expect("Failed to add batches");
+
+        assert!(lagging_store
+            .get_batch(&id, "TEST")
+            .expect("Failed to get batch")
+            .is_none());
+        assert!(store
+            .get_batch(&id, "TEST")
+            .expect("Failed to get batch")
+            .is_some());
+
+        store
+            .update_batch_status(&id, "TEST", Some(BatchStatus::Pending), Vec::new(), None)
+            .expect("Failed to update batch status");
+
+        assert_eq!(
+            store
+                .get_batch_status(&id, "TEST")
+                .expect("Failed to get batch status"),
+            Some(BatchStatus::Pending)
+        );
+
+        // Batches that were not written through the store are read from the
+        // read pool
+        assert!(store
+            .get_batch(&id, "OTHER")
+            .expect("Failed to get batch")
+            .is_none());
+    }
+
+    #[test]
+    /// Test that dead-lettering a batch sets its status and reason, that the
+    /// batch is no longer returned as unsubmitted, and that it is returned by
+    /// get_dead_lettered_batches
+    fn test_dead_letter_batch() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let signer = new_signer();
+
+        let batches: Vec<TrackingBatch> = [NONCE, NONCE2]
+            .iter()
+            .map(|nonce| {
+                get_tracking_batch(
+                    get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, nonce)]),
+                    false,
+                )
+                .build()
+                .expect("Failed to build batch")
+            })
+            .collect();
+        let dead_id = batches[0].batch_header().to_string();
+        let live_id = batches[1].batch_header().to_string();
+
+        store.add_batches(batches).expect("Failed to add batches");
+
+        store
+            .update_batch_status(
+                &dead_id,
+                "TEST",
+                Some(BatchStatus::Delayed),
+                Vec::new(),
+                None,
+            )
+            .expect("Failed to update batch status");
+
+        store
+            .dead_letter_batch(&dead_id, "TEST", "retries exhausted")
+            .expect("Failed to dead-letter batch");
+
+        assert_eq!(
+            store
+                .get_batch_status(&dead_id, "TEST")
+                .expect("Failed to get batch status"),
+            Some(BatchStatus::DeadLettered)
+        );
+
+        let unsubmitted: Vec<String> = store
+            .get_unsubmitted_batches()
+            .expect("Failed to get unsubmitted batches")
+            .batches
+            .iter()
+            .map(|b| b.batch_header().to_string())
+            .collect();
+        assert_eq!(unsubmitted, vec![live_id.to_string()]);
+
+        assert_eq!(
+            store
+                .get_unsubmitted_batches_limited(Some("TEST"), 10)
+                .expect("Failed to get unsubmitted batches")
+                .batches
+                .len(),
+            1
+        );
+
+        let dead_lettered = store
+            .get_dead_lettered_batches("TEST")
+            .expect("Failed to get dead-lettered batches")
+            .batches;
+        assert_eq!(dead_lettered.len(), 1);
+        assert_eq!(dead_lettered[0].batch_header(), dead_id);
+        assert_eq!(
+            dead_lettered[0].dead_letter_reason(),
+            Some("retries exhausted")
+        );
+        assert_eq!(
+            dead_lettered[0].batch_status(),
+            Some(&BatchStatus::DeadLettered)
+        );
+
+        assert!(store
+            .get_dead_lettered_batches("OTHER")
+            .expect("Failed to get dead-lettered batches")
+            .batches
+            .is_empty());
+
+        match store.dead_letter_batch("missing", "TEST", "retries exhausted") {
+            Err(BatchTrackingStoreError::NotFoundError(_)) => (),
+            res => panic!("Expected NotFoundError, got {:?}", res),
+        }
+    }
+
+    #[test]
+    /// Test that two stores holding the same batches and statuses have the
+    /// same content digest, and that the digests differ once a status changes
+    fn test_content_digest() {
+        let store = DieselBatchTrackingStore::new(create_connection_pool_and_migrate());
+        let other_store = DieselBatchTrackingStore::new(create_connection_pool_and_migrate());
+
+        let signer = new_signer();
+
+        let batches: Vec<TrackingBatch> = [NONCE, NONCE2]
+            .iter()
+            .map(|nonce| {
+                get_tracking_batch(
+                    get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, nonce)]),
+                    false,
+                )
+                .build()
+                .expect("Failed to build batch")
+            })
+            .collect();
+        let id = batches[0].batch_header().to_string();
+
+        // The batches are added in a different order to each store
+        let mut reversed = batches.clone();
+        reversed.reverse();
+        store.add_batches(batches).expect("Failed to add batches");
+        other_store
+            .add_batches(reversed)
+            .expect("Failed to add batches");
+
+        let digest = store
+            .content_digest("TEST")
+            .expect("Failed to get content digest");
+        assert_eq!(
+            digest,
+            other_store
+                .content_digest("TEST")
+                .expect("Failed to get content digest")
+        );
+        assert_ne!(
+            digest,
+            store
+                .content_digest("OTHER")
+                .expect("Failed to get content digest")
+        );
+
+        store
+            .update_batch_status(&id, "TEST", Some(BatchStatus::Pending), Vec::new(), None)
+            .expect("Failed to update batch status");
+
+        assert_ne!(
+            store
+                .content_digest("TEST")
+                .expect("Failed to get content digest"),
+            other_store
+                .content_digest("TEST")
+                .expect("Failed to get content digest")
+        );
+    }
+
+    #[test]
+    fn test_get_batches_awaiting_receipts() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool.clone());
+
+        let signer = new_signer();
+
+        let set_submitted_at = |id: &str, submitted_at: i64| {
+            diesel::update(
+                schema::submissions::table.filter(
+                    schema::submissions::batch_id
+                        .eq(id)
+                        .and(schema::submissions::service_id.eq("TEST")),
+                ),
+            )
+            .set(schema::submissions::created_at.eq(submitted_at))
+            .execute(&*pool.get().expect("Failed to get connection"))
+            .expect("Failed to set submission created_at");
+        };
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("Failed to get time")
+            .as_secs() as i64;
+
+        let recent = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE)]),
+            false,
+        )
+        .build()
+        .expect("Failed to build batch");
+        let waiting = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE2)]),
+            false,
+        )
+        .build()
+        .expect("Failed to build batch");
+        let dead_lettered = get_tracking_batch(
+            get_transact_batch(
+                &*signer,
+                vec![get_transact_transaction(&*signer, "dead_lettered")],
+            ),
+            false,
+        )
+        .build()
+        .expect("Failed to build batch");
+
+        let recent_id = recent.batch_header().to_string();
+        let waiting_id = waiting.batch_header().to_string();
+        let dead_lettered_id = dead_lettered.batch_header().to_string();
+
+        store
+            .add_batches(vec![recent, waiting, dead_lettered])
+            .expect("Failed to add batches");
+
+        store
+            .change_batch_to_submitted(
+                &recent_id,
+                "TEST",
+                Vec::new(),
+                Some("Pending"),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .expect("Failed to change batch to submitted");
+        store
+            .change_batch_to_submitted(
+                &waiting_id,
+                "TEST",
+                Vec::new(),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .expect("Failed to change batch to submitted");
+        store
+            .change_batch_to_submitted(
+                &dead_lettered_id,
+                "TEST",
+                Vec::new(),
+                Some("Pending"),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .expect("Failed to change batch to submitted");
+
+        store
+            .dead_letter_batch(&dead_lettered_id, "TEST", "Rejected by the DLT")
+            .expect("Failed to dead letter batch");
+
+        set_submitted_at(&waiting_id, now - 3600);
+        set_submitted_at(&dead_lettered_id, now - 3600);
+
+        let awaiting = store
+            .get_batches_awaiting_receipts(now - 600, "TEST")
+            .expect("Failed to get batches awaiting receipts")
+            .batches;
+        assert_eq!(awaiting.len(), 1);
+        assert_eq!(awaiting[0].batch_header(), waiting_id);
+
+        assert!(store
+            .get_batches_awaiting_receipts(now - 600, "OTHER")
+            .expect("Failed to get batches awaiting receipts")
+            .batches
+            .is_empty());
+    }
+
+    #[test]
+    fn test_failed_batches_cursor() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool.clone());
+
+        let signer = new_signer();
+
+        let set_created_at_and_status = |id: &str, created_at: i64, status: &str| {
+            let conn = pool.get().expect("Failed to get connection");
+            diesel::update(
+                schema::batches::table.filter(
+                    schema::batches::batch_id
+                        .eq(id)
+                        .and(schema::batches::service_id.eq("TEST")),
+                ),
+            )
+            .set(schema::batches::created_at.eq(created_at))
+            .execute(&*conn)
+            .expect("Failed to set created_at");
+            diesel::update(
+                schema::batch_statuses::table.filter(
+                    schema::batch_statuses::batch_id
+                        .eq(id)
+                        .and(schema::batch_statuses::service_id.eq("TEST")),
+                ),
+            )
+            .set(schema::batch_statuses::dlt_status.eq(status))
+            .execute(&*conn)
+            .expect("Failed to set status");
+        };
+
+        let batches: Vec<TrackingBatch> = [NONCE, NONCE2, "k9fzdz", "zdz9fk", "fk9zdz"]
+            .iter()
+            .map(|nonce| {
+                get_tracking_batch(
+                    get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, nonce)]),
+                    false,
+                )
+                .build()
+                .expect("Failed to build batch")
+            })
+            .collect();
+        let ids: Vec<String> = batches
+            .iter()
+            .map(|b| b.batch_header().to_string())
+            .collect();
+
+        store.add_batches(batches).expect("Failed to add batches");
+
+        for id in &ids {
+            store
+                .change_batch_to_submitted(
+                    id,
+                    "TEST",
+                    Vec::new(),
+                    Some("Pending"),
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                )
+                .expect("Failed to change batch to submitted");
+        }
+
+        // The first two batches share a creation time, so they are ordered by
+        // ID, and the pending batch is skipped
+        set_created_at_and_status(&ids[0], 100, "Unknown");
+        set_created_at_and_status(&ids[1], 100, "Unknown");
+        set_created_at_and_status(&ids[2], 200, "Unknown");
+        set_created_at_and_status(&ids[3], 300, "Pending");
+        set_created_at_and_status(&ids[4], 400, "Unknown");
+
+        let mut first_two = vec![ids[0].clone(), ids[1].clone()];
+        first_two.sort();
+        let mut expected = first_two;
+        expected.push(ids[2].clone());
+        expected.push(ids[4].clone());
+
+        let page = |after: Option<&str>| -> Vec<String> {
+            store
+                .failed_batches_cursor("TEST", after, 2)
+                .expect("Failed to get failed batches")
+                .batches
+                .iter()
+                .map(|b| b.batch_header().to_string())
+                .collect()
+        };
+
+        let mut walked = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let ids = page(cursor.as_deref());
+            if ids.is_empty() {
+                break;
+            }
+            cursor = ids.last().cloned();
+            walked.extend(ids);
+        }
+        assert_eq!(walked, expected);
+
+        // Resuming from a cursor returns only the batches after it
+        assert_eq!(page(Some(&expected[1])), expected[2..].to_vec());
+        assert_eq!(page(Some(&expected[0])), expected[1..3].to_vec());
+        assert!(page(Some(&expected[3])).is_empty());
+
+        assert!(matches!(
+            store.failed_batches_cursor("TEST", Some("unknown"), 2),
+            Err(BatchTrackingStoreError::InvalidArgumentError(_))
+        ));
+    }
+
+    #[test]
+    fn test_remap_data_change_ids() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let signer = new_signer();
+
+        let batches: Vec<TrackingBatch> = [
+            (NONCE, "dcid:a"),
+            (NONCE2, "dcid:b"),
+            ("k9fzdz", "dcid:c"),
+            ("zdz9fk", "dcid:d"),
+        ]
+        .iter()
+        .map(|(nonce, dcid)| {
+            get_tracking_batch(
+                get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, nonce)]),
+                false,
+            )
+            .with_data_change_id(dcid.to_string())
+            .build()
+            .expect("Failed to build batch")
+        })
+        .collect();
+        let ids: Vec<String> = batches
+            .iter()
+            .map(|b| b.batch_header().to_string())
+            .collect();
+
+        store.add_batches(batches).expect("Failed to add batches");
+
+        let remap = |pairs: &[(&str, &str)]| {
+            store.remap_data_change_ids(
+                pairs
+                    .iter()
+                    .map(|(old, new)| (old.to_string(), new.to_string()))
+                    .collect(),
+                "TEST",
+            )
+        };
+
+        // dcid:a and dcid:b are swapped, dcid:c gets a new ID and the
+        // unknown dcid:x is ignored
+        assert_eq!(
+            remap(&[
+                ("dcid:a", "dcid:b"),
+                ("dcid:b", "dcid:a"),
+                ("dcid:c", "dcid:e"),
+                ("dcid:x", "dcid:y"),
+            ])
+            .expect("Failed to remap data change IDs"),
+            3
+        );
+
+        let batch_id_for = |dcid: &str| {
+            store
+                .get_batch(dcid, "TEST")
+                .expect("Failed to get batch")
+                .map(|b| b.batch_header().to_string())
+        };
+
+        assert_eq!(batch_id_for("dcid:b"), Some(ids[0].clone()));
+        assert_eq!(batch_id_for("dcid:a"), Some(ids[1].clone()));
+        assert_eq!(batch_id_for("dcid:e"), Some(ids[2].clone()));
+        assert_eq!(batch_id_for("dcid:c"), None);
+        assert_eq!(batch_id_for("dcid:y"), None);
+
+        // Taking the ID of a batch that is not remapped is rejected and
+        // leaves every batch unchanged
+        assert!(matches!(
+            remap(&[("dcid:e", "dcid:f"), ("dcid:b", "dcid:d")]),
+            Err(BatchTrackingStoreError::ConstraintViolationError(_))
+        ));
+        assert!(matches!(
+            remap(&[("dcid:e", "dcid:f"), ("dcid:b", "dcid:f")]),
+            Err(BatchTrackingStoreError::ConstraintViolationError(_))
+        ));
+        assert!(matches!(
+            remap(&[("dcid:e", "not-a-dcid")]),
+            Err(BatchTrackingStoreError::InvalidArgumentError(_))
+        ));
+        assert_eq!(batch_id_for("dcid:e"), Some(ids[2].clone()));
+        assert_eq!(batch_id_for("dcid:f"), None);
     }
 
-    fn get_unsubmitted_batches(&self) -> Result<TrackingBatchList, BatchTrackingStoreError> {
-        BatchTrackingStoreOperations::new(self.connection).get_unsubmitted_batches()
+    #[test]
+    fn test_list_batches_by_family() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let signer = new_signer();
+
+        let product_transaction = |nonce: &str| {
+            TransactionBuilder::new()
+                .with_batcher_public_key(hex::parse_hex(KEY1).unwrap())
+                .with_family_name("grid_product".to_string())
+                .with_family_version("2".to_string())
+                .with_inputs(vec![hex::parse_hex(KEY4).unwrap()])
+                .with_nonce(nonce.to_string().into_bytes())
+                .with_outputs(vec![hex::parse_hex(KEY6).unwrap()])
+                .with_payload_hash_method(HashMethod::Sha512)
+                .with_payload(BYTES2.to_vec())
+                .build(&*signer)
+                .expect("Failed to build transaction")
+        };
+
+        // Only the first batch has no product transactions, and the last mixes
+        // families
+        let other = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE)]),
+            false,
+        )
+        .build()
+        .expect("Failed to build batch");
+        let product = get_tracking_batch(
+            get_transact_batch(&*signer, vec![product_transaction(NONCE2)]),
+            false,
+        )
+        .build()
+        .expect("Failed to build batch");
+        let mixed = get_tracking_batch(
+            get_transact_batch(
+                &*signer,
+                vec![
+                    get_transact_transaction(&*signer, "k9fzdz"),
+                    product_transaction("zdz9fk"),
+                ],
+            ),
+            false,
+        )
+        .build()
+        .expect("Failed to build batch");
+
+        let other_id = other.batch_header().to_string();
+        let product_id = product.batch_header().to_string();
+        let mixed_id = mixed.batch_header().to_string();
+
+        store
+            .add_batches(vec![other, product, mixed])
+            .expect("Failed to add batches");
+
+        let list_ids = |family_name: &str, service_id: &str| -> Vec<String> {
+            let mut ids: Vec<String> = store
+                .list_batches_by_family(family_name, service_id)
+                .expect("Failed to list batches")
+                .batches
+                .iter()
+                .map(|b| b.batch_header().to_string())
+                .collect();
+            ids.sort();
+            ids
+        };
+
+        let sorted = |mut ids: Vec<String>| {
+            ids.sort();
+            ids
+        };
+
+        assert_eq!(
+            list_ids("grid_product", "TEST"),
+            sorted(vec![product_id, mixed_id.clone()])
+        );
+        assert_eq!(
+            list_ids(FAMILY_NAME, "TEST"),
+            sorted(vec![other_id, mixed_id])
+        );
+        assert!(list_ids("grid_product", "OTHER").is_empty());
+        assert!(list_ids("grid_location", "TEST").is_empty());
     }
 
-    fn get_failed_batches(&self) -> Result<TrackingBatchList, BatchTrackingStoreError> {
-        BatchTrackingStoreOperations::new(self.connection).get_failed_batches()
+    #[test]
+    fn test_export_service_ndjson() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let signer = new_signer();
+
+        let batches: Vec<TrackingBatch> = [NONCE, NONCE2, "k9fzdz"]
+            .iter()
+            .map(|nonce| {
+                get_tracking_batch(
+                    get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, nonce)]),
+                    false,
+                )
+                .build()
+                .expect("Failed to build batch")
+            })
+            .collect();
+
+        store
+            .add_batches(batches.clone())
+            .expect("Failed to add batches");
+
+        let mut output: Vec<u8> = Vec::new();
+        assert_eq!(
+            store
+                .export_service_ndjson("TEST", &mut output)
+                .expect("Failed to export batches"),
+            3
+        );
+
+        let output = String::from_utf8(output).expect("Export is not UTF-8");
+        let mut exported: Vec<TrackingBatch> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("Failed to parse exported batch"))
+            .collect();
+        exported.sort_by(|a, b| a.batch_header().cmp(b.batch_header()));
+
+        let mut expected: Vec<TrackingBatch> = batches
+            .iter()
+            .map(|batch| {
+                store
+                    .get_batch(batch.batch_header(), "TEST")
+                    .expect("Failed to get batch")
+                    .expect("Batch not found")
+            })
+            .collect();
+        expected.sort_by(|a, b| a.batch_header().cmp(b.batch_header()));
+
+        assert_eq!(exported, expected);
+
+        let mut output: Vec<u8> = Vec::new();
+        assert_eq!(
+            store
+                .export_service_ndjson("OTHER", &mut output)
+                .expect("Failed to export batches"),
+            0
+        );
+        assert!(output.is_empty());
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_find_and_dedupe_excess_receipts() {
+        let pool = create_connection_pool_and_migrate();
 
-    use cylinder::{secp256k1::Secp256k1Context, Context, Signer};
-    use diesel::r2d2::{ConnectionManager, Pool};
-    use diesel::sqlite::SqliteConnection;
-    use transact::protocol::{
-        batch::{Batch, BatchBuilder},
-        transaction::{HashMethod, Transaction, TransactionBuilder},
-    };
+        let store = DieselBatchTrackingStore::new(pool.clone());
 
-    use crate::batch_tracking::store::{
-        BatchBuilderError, InvalidTransactionBuilder, SubmissionErrorBuilder, TrackingBatchBuilder,
-        TransactionReceiptBuilder,
-    };
-    use crate::hex;
-    use crate::migrations::run_sqlite_migrations;
+        let signer = new_signer();
 
-    static FAMILY_NAME: &str = "test_family";
-    static FAMILY_VERSION: &str = "0.1";
-    static KEY1: &str = "111111111111111111111111111111111111111111111111111111111111111111";
-    static KEY2: &str = "222222222222222222222222222222222222222222222222222222222222222222";
-    static KEY3: &str = "333333333333333333333333333333333333333333333333333333333333333333";
-    static KEY4: &str = "444444444444444444444444444444444444444444444444444444444444444444";
-    static KEY5: &str = "555555555555555555555555555555555555555555555555555555555555555555";
-    static KEY6: &str = "666666666666666666666666666666666666666666666666666666666666666666";
-    static KEY7: &str = "777777777777777777777777777777777777777777777777777777777777777777";
-    static NONCE: &str = "f9kdzz";
-    static NONCE2: &str = "dzzf9k";
-    static BYTES2: [u8; 4] = [0x05, 0x06, 0x07, 0x08];
+        // Postgres does not enforce a single receipt per transaction, so the
+        // receipts table is rebuilt without its primary key to allow the
+        // duplicates it can hold
+        for statement in &[
+            "CREATE TABLE transaction_receipts_copy AS SELECT * FROM transaction_receipts",
+            "DROP TABLE transaction_receipts",
+            "ALTER TABLE transaction_receipts_copy RENAME TO transaction_receipts",
+        ] {
+            diesel::sql_query(*statement)
+                .execute(&*pool.get().expect("Failed to get connection"))
+                .expect("Failed to rebuild receipts table");
+        }
+
+        let valid_receipt = |transaction_id: &str| {
+            TransactionReceiptBuilder::default()
+                .with_transaction_id(transaction_id.to_string())
+                .with_result_valid(true)
+                .with_serialized_receipt(
+                    std::str::from_utf8(&BYTES2)
+                        .expect("Failed to build string")
+                        .to_string(),
+                )
+                .build()
+                .expect("Failed to build receipt")
+        };
+
+        let pair_1 = get_transact_transaction(&*signer, "n1");
+        let pair_2 = get_transact_transaction(&*signer, "n2");
+        let txn_id_1 = pair_1.header_signature().to_string();
+        let txn_id_2 = pair_2.header_signature().to_string();
+        let excess = get_tracking_batch(get_transact_batch(&*signer, vec![pair_1, pair_2]), false)
+            .build()
+            .expect("Failed to build batch");
+
+        let pair_3 = get_transact_transaction(&*signer, "n3");
+        let txn_id_3 = pair_3.header_signature().to_string();
+        let complete = get_tracking_batch(get_transact_batch(&*signer, vec![pair_3]), false)
+            .build()
+            .expect("Failed to build batch");
+
+        let excess_id = excess.batch_header().to_string();
+
+        store
+            .add_batches(vec![excess, complete.clone()])
+            .expect("Failed to add batches");
+
+        store
+            .update_batch_status(
+                &excess_id,
+                "TEST",
+                Some(BatchStatus::Committed(Vec::new())),
+                vec![valid_receipt(&txn_id_1), valid_receipt(&txn_id_2)],
+                None,
+            )
+            .expect("Failed to update batch");
+        store
+            .update_batch_status(
+                complete.batch_header(),
+                "TEST",
+                Some(BatchStatus::Committed(Vec::new())),
+                vec![valid_receipt(&txn_id_3)],
+                None,
+            )
+            .expect("Failed to update batch");
+
+        assert!(store
+            .find_batches_with_excess_receipts("TEST")
+            .expect("Failed to find batches")
+            .is_empty());
+
+        // Record the first transaction's receipt twice more
+        for _ in 0..2 {
+            diesel::sql_query(
+                "INSERT INTO transaction_receipts
+                SELECT * FROM transaction_receipts WHERE transaction_id = ? LIMIT 1",
+            )
+            .bind::<diesel::sql_types::Text, _>(&txn_id_1)
+            .execute(&*pool.get().expect("Failed to get connection"))
+            .expect("Failed to duplicate receipt");
+        }
+
+        assert_eq!(
+            store
+                .find_batches_with_excess_receipts("TEST")
+                .expect("Failed to find batches"),
+            vec![excess_id.clone()]
+        );
+        assert!(store
+            .find_batches_with_excess_receipts("OTHER")
+            .expect("Failed to find batches")
+            .is_empty());
+
+        assert_eq!(
+            store
+                .dedupe_receipts(&excess_id, "TEST")
+                .expect("Failed to dedupe receipts"),
+            2
+        );
+        assert!(store
+            .find_batches_with_excess_receipts("TEST")
+            .expect("Failed to find batches")
+            .is_empty());
+        assert_eq!(
+            store
+                .dedupe_receipts(&excess_id, "TEST")
+                .expect("Failed to dedupe receipts"),
+            0
+        );
+
+        let receipt_count: i64 = schema::transaction_receipts::table
+            .filter(schema::transaction_receipts::transaction_id.eq_any(vec![txn_id_1, txn_id_2]))
+            .count()
+            .get_result(&*pool.get().expect("Failed to get connection"))
+            .expect("Failed to count receipts");
+        assert_eq!(receipt_count, 2);
+
+        assert!(matches!(
+            store.dedupe_receipts("unknown", "TEST"),
+            Err(BatchTrackingStoreError::NotFoundError(_))
+        ));
+    }
 
     #[test]
-    fn add_and_fetch_batch() {
+    fn test_get_batch_status_at() {
         let pool = create_connection_pool_and_migrate();
 
-        let store = DieselBatchTrackingStore::new(pool);
+        let store = DieselBatchTrackingStore::new(pool.clone());
 
         let signer = new_signer();
 
         let pair = get_transact_transaction(&*signer, NONCE);
+        let txn_id = pair.header_signature().to_string();
+        let batch = get_tracking_batch(get_transact_batch(&*signer, vec![pair]), false)
+            .build()
+            .expect("Failed to build batch");
+        let id = batch.batch_header().to_string();
 
-        let batch_1 = get_transact_batch(&*signer, vec![pair]);
+        store.add_batches(vec![batch]).expect("Failed to add batch");
 
-        let add_tracking_batch = get_tracking_batch(batch_1.clone(), false)
+        let receipt = TransactionReceiptBuilder::default()
+            .with_transaction_id(txn_id)
+            .with_result_valid(true)
+            .with_serialized_receipt(
+                std::str::from_utf8(&BYTES2)
+                    .expect("Failed to build string")
+                    .to_string(),
+            )
             .build()
-            .expect("Failed to build batch");
+            .expect("Failed to build receipt");
 
-        let id = add_tracking_batch.batch_header();
+        store
+            .update_batch_status(&id, "TEST", Some(BatchStatus::Pending), Vec::new(), None)
+            .expect("Failed to update batch status");
+        store
+            .update_batch_status(&id, "TEST", Some(BatchStatus::Unknown), Vec::new(), None)
+            .expect("Failed to update batch status");
+        store
+            .update_batch_status(
+                &id,
+                "TEST",
+                Some(BatchStatus::Committed(Vec::new())),
+                vec![receipt],
+                None,
+            )
+            .expect("Failed to update batch status");
+
+        // The events are moved to known times, in the order they were
+        // recorded
+        let event_ids: Vec<i64> = schema::batch_status_events::table
+            .select(schema::batch_status_events::id)
+            .filter(schema::batch_status_events::batch_id.eq(&id))
+            .order(schema::batch_status_events::id.asc())
+            .load(&*pool.get().expect("Failed to get connection"))
+            .expect("Failed to load status events");
+        assert_eq!(event_ids.len(), 3);
+        for (event_id, created_at) in event_ids.iter().zip(&[100, 200, 300]) {
+            diesel::update(
+                schema::batch_status_events::table
+                    .filter(schema::batch_status_events::id.eq(event_id)),
+            )
+            .set(schema::batch_status_events::created_at.eq(created_at))
+            .execute(&*pool.get().expect("Failed to get connection"))
+            .expect("Failed to set event time");
+        }
+
+        let status_at = |at: i64| {
+            store
+                .get_batch_status_at(&id, "TEST", at)
+                .expect("Failed to get batch status")
+        };
+
+        assert_eq!(status_at(99), None);
+        assert_eq!(status_at(100), Some(BatchStatusName::Pending));
+        assert_eq!(status_at(150), Some(BatchStatusName::Pending));
+        assert_eq!(status_at(200), Some(BatchStatusName::Unknown));
+        assert_eq!(status_at(299), Some(BatchStatusName::Unknown));
+        assert_eq!(status_at(1000), Some(BatchStatusName::Committed));
+
+        assert_eq!(
+            store
+                .get_batch_status_at(&id, "OTHER", 1000)
+                .expect("Failed to get batch status"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_allowed_service_ids() {
+        let pool = create_connection_pool_and_migrate();
+
+        let unrestricted = DieselBatchTrackingStore::new(pool.clone());
+        let store = DieselBatchTrackingStore::new(pool)
+            .with_allowed_service_ids(vec!["TEST".to_string()].into_iter().collect());
+
+        let signer = new_signer();
+
+        let allowed = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE)]),
+            false,
+        )
+        .build()
+        .expect("Failed to build batch");
+        let allowed_id = allowed.batch_header().to_string();
 
         store
-            .add_batches(vec![add_tracking_batch.clone()])
+            .add_batches(vec![allowed])
             .expect("Failed to add batch");
+        store
+            .update_batch_status(
+                &allowed_id,
+                "TEST",
+                Some(BatchStatus::Pending),
+                Vec::new(),
+                None,
+            )
+            .expect("Failed to update batch status");
+
+        let disallowed = TrackingBatchBuilder::default()
+            .with_batch(get_transact_batch(
+                &*signer,
+                vec![get_transact_transaction(&*signer, NONCE2)],
+            ))
+            .with_service_id("OTHER".to_string())
+            .with_signer_public_key(KEY1.to_string())
+            .with_submitted(false)
+            .build()
+            .expect("Failed to build batch");
+        let disallowed_id = disallowed.batch_header().to_string();
 
-        let batch_result = store
-            .get_batch(&id, "TEST")
+        match store.add_batches(vec![disallowed.clone()]) {
+            Err(BatchTrackingStoreError::ServiceNotAllowed { service_id }) => {
+                assert_eq!(service_id, "OTHER")
+            }
+            res => panic!("Expected ServiceNotAllowed, got {:?}", res),
+        }
+        assert_eq!(
+            unrestricted
+                .get_batch(&disallowed_id, "OTHER")
+                .expect("Failed to get batch"),
+            None
+        );
+
+        // Batches written by other stores can still be read, but not updated
+        unrestricted
+            .add_batches(vec![disallowed])
+            .expect("Failed to add batch");
+        assert!(store
+            .get_batch(&disallowed_id, "OTHER")
             .expect("Failed to get batch")
-            .unwrap();
-        let batch_timestamp = batch_result.created_at();
+            .is_some());
+        assert!(matches!(
+            store.update_batch_status(
+                &disallowed_id,
+                "OTHER",
+                Some(BatchStatus::Pending),
+                Vec::new(),
+                None
+            ),
+            Err(BatchTrackingStoreError::ServiceNotAllowed { .. })
+        ));
+        assert_eq!(
+            store
+                .get_batch_status(&disallowed_id, "OTHER")
+                .expect("Failed to get batch status"),
+            None
+        );
+    }
 
-        let expected = get_tracking_batch(batch_1.clone(), false)
-            .with_created_at(batch_timestamp)
+    /// Verify that try_add_batches adds each batch on its own, reporting the
+    /// outcome for each batch in the order given:
+    ///
+    /// 1. Add a batch with add_batches
+    /// 2. Try to add two new batches along with a duplicate of the first
+    /// 3. Validate the new batches were added and the duplicate failed
+    #[test]
+    fn test_try_add_batches() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let signer = new_signer();
+
+        let get_batch = |nonce: &str| {
+            get_tracking_batch(
+                get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, nonce)]),
+                false,
+            )
+            .build()
+            .expect("Failed to build batch")
+        };
+
+        let existing = get_batch("n1");
+        let first = get_batch("n2");
+        let second = get_batch("n3");
+        let existing_id = existing.batch_header().to_string();
+        let first_id = first.batch_header().to_string();
+        let second_id = second.batch_header().to_string();
+
+        store
+            .add_batches(vec![existing.clone()])
+            .expect("Failed to add batch");
+
+        let outcomes = store
+            .try_add_batches(vec![first, existing, second])
+            .expect("Failed to try adding batches");
+
+        let ids: Vec<&str> = outcomes.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(ids, [&first_id, &existing_id, &second_id]);
+        assert!(outcomes[0].1.is_ok());
+        assert!(outcomes[1].1.is_err());
+        assert!(outcomes[2].1.is_ok());
+
+        for id in [&first_id, &existing_id, &second_id].iter() {
+            assert!(store
+                .get_batch(id, "TEST")
+                .expect("Failed to get batch")
+                .is_some());
+        }
+    }
+
+    /// Verify that get_transact_batch reconstructs the transact batch that was
+    /// added to the store:
+    ///
+    /// 1. Add a batch built from a transact batch
+    /// 2. Validate the reconstructed batch matches the original
+    /// 3. Validate a batch that does not exist returns None
+    #[test]
+    fn test_get_transact_batch() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let signer = new_signer();
+
+        let transact_batch = get_transact_batch(
+            &*signer,
+            vec![
+                get_transact_transaction(&*signer, NONCE),
+                get_transact_transaction(&*signer, NONCE2),
+            ],
+        );
+        let batch = get_tracking_batch(transact_batch.clone(), false)
             .build()
             .expect("Failed to build batch");
 
-        assert_eq!(batch_result, expected);
+        store.add_batches(vec![batch]).expect("Failed to add batch");
+
+        let reconstructed = store
+            .get_transact_batch(transact_batch.header_signature(), "TEST")
+            .expect("Failed to get transact batch")
+            .expect("Batch not found");
+
+        assert_eq!(
+            reconstructed.header_signature(),
+            transact_batch.header_signature()
+        );
+        assert_eq!(reconstructed, transact_batch);
+
+        assert_eq!(
+            store
+                .get_transact_batch("not-a-batch", "TEST")
+                .expect("Failed to get transact batch"),
+            None
+        );
     }
 
+    /// Verify that the URL a batch was submitted to is recorded and that
+    /// batches can be listed by it:
+    ///
+    /// 1. Add three batches
+    /// 2. Submit two batches to one URL and the third to another
+    /// 3. Validate the URL is returned in the submission info
+    /// 4. Validate listing by each URL returns only the batches sent to it
     #[test]
-    fn add_and_fetch_batch_with_dcid() {
+    fn test_list_batches_by_submit_url() {
         let pool = create_connection_pool_and_migrate();
 
         let store = DieselBatchTrackingStore::new(pool);
 
         let signer = new_signer();
 
-        let pair = get_transact_transaction(&*signer, NONCE);
-
-        let batch_1 = get_transact_batch(&*signer, vec![pair]);
-
-        let dcid = "dcid:data_change".to_string();
+        let batch_ids: Vec<String> = ["n1", "n2", "n3"]
+            .iter()
+            .map(|nonce| {
+                let batch = get_tracking_batch(
+                    get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, nonce)]),
+                    false,
+                )
+                .build()
+                .expect("Failed to build batch");
+                let batch_id = batch.batch_header().to_string();
+                store.add_batches(vec![batch]).expect("Failed to add batch");
+                batch_id
+            })
+            .collect();
+
+        let first_url = "http://validator-0:8080/batches";
+        let second_url = "http://validator-1:8080/batches";
+
+        for (batch_id, url) in batch_ids
+            .iter()
+            .zip([first_url, first_url, second_url].iter())
+        {
+            store
+                .change_batch_to_submitted(
+                    batch_id,
+                    "TEST",
+                    Vec::new(),
+                    Some("Pending"),
+                    None,
+                    None,
+                    None,
+                    Some(url),
+                    None,
+                    None,
+                )
+                .expect("Failed to change batch to submitted");
+        }
 
-        let add_tracking_batch = get_tracking_batch(batch_1.clone(), false)
-            .with_data_change_id(dcid.clone())
-            .build()
-            .expect("Failed to build batch");
+        assert_eq!(
+            store
+                .get_batch_submission_info(&batch_ids[2], "TEST")
+                .expect("Failed to get submission info")
+                .expect("Submission info not found")
+                .submit_url(),
+            Some(second_url)
+        );
 
-        store
-            .add_batches(vec![add_tracking_batch.clone()])
-            .expect("Failed to add batch");
+        let listed_ids = |url: &str| -> Vec<String> {
+            store
+                .list_batches_by_submit_url(url, "TEST")
+                .expect("Failed to list batches")
+                .batches
+                .iter()
+                .map(|batch| batch.batch_header().to_string())
+                .collect()
+        };
 
-        let batch_result = store
-            .get_batch(&dcid, "TEST")
-            .expect("Failed to get batch")
-            .unwrap();
-        let batch_timestamp = batch_result.created_at();
-        let expected = get_tracking_batch(batch_1.clone(), false)
-            .with_created_at(batch_timestamp)
-            .with_data_change_id(dcid)
-            .build()
-            .expect("Failed to build batch");
+        let mut first_ids = listed_ids(first_url);
+        first_ids.sort();
+        let mut expected_ids = batch_ids[0..2].to_vec();
+        expected_ids.sort();
+        assert_eq!(first_ids, expected_ids);
 
-        assert_eq!(batch_result, expected);
+        assert_eq!(listed_ids(second_url), vec![batch_ids[2].clone()]);
+        assert!(listed_ids("http://validator-2:8080/batches").is_empty());
     }
 
+    /// Verify that data change IDs held by more than one batch are found:
+    ///
+    /// 1. Rebuild the batches table without its unique data change ID
+    ///    constraint, as in a database merged from two others
+    /// 2. Add four batches with distinct data change IDs and one without
+    /// 3. Validate no duplicates are found
+    /// 4. Give three batches the same data change ID and validate they are
+    ///    returned together
     #[test]
-    fn test_invalid_dcid() {
+    fn test_find_duplicate_dcids() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool.clone());
+
         let signer = new_signer();
 
-        let pair = get_transact_transaction(&*signer, NONCE);
+        for statement in &[
+            "CREATE TABLE batches_copy AS SELECT * FROM batches",
+            "DROP TABLE batches",
+            "ALTER TABLE batches_copy RENAME TO batches",
+        ] {
+            diesel::sql_query(*statement)
+                .execute(&*pool.get().expect("Failed to get connection"))
+                .expect("Failed to rebuild batches table");
+        }
 
-        let batch_1 = get_transact_batch(&*signer, vec![pair]);
+        let batches: Vec<TrackingBatch> = [
+            ("n1", Some("dcid:one")),
+            ("n2", Some("dcid:two")),
+            ("n3", Some("dcid:three")),
+            ("n4", Some("dcid:four")),
+            ("n5", None),
+        ]
+        .iter()
+        .map(|(nonce, dcid)| {
+            let builder = get_tracking_batch(
+                get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, nonce)]),
+                false,
+            );
+            match dcid {
+                Some(dcid) => builder.with_data_change_id(dcid.to_string()),
+                None => builder,
+            }
+            .build()
+            .expect("Failed to build batch")
+        })
+        .collect();
+        let batch_ids: Vec<String> = batches
+            .iter()
+            .map(|batch| batch.batch_header().to_string())
+            .collect();
 
-        let dcid = "data_change".to_string();
+        store.add_batches(batches).expect("Failed to add batches");
 
-        let add_tracking_batch = TrackingBatchBuilder::default()
-            .with_batch(batch_1.clone())
-            .with_service_id("TEST".to_string())
-            .with_data_change_id(dcid.clone())
-            .with_signer_public_key(KEY1.to_string())
-            .with_submitted(false)
-            .build();
+        assert!(store
+            .find_duplicate_dcids("TEST")
+            .expect("Failed to find duplicate dcids")
+            .is_empty());
 
-        assert!(add_tracking_batch.is_err());
+        diesel::update(
+            schema::batches::table
+                .filter(schema::batches::batch_id.eq_any(vec![&batch_ids[2], &batch_ids[3]])),
+        )
+        .set(schema::batches::data_change_id.eq("dcid:one"))
+        .execute(&*pool.get().expect("Failed to get connection"))
+        .expect("Failed to update data change IDs");
 
-        let expected = BatchBuilderError::MissingRequiredField(
-            "data change IDs must be formatted as 'dcid:<id>'".to_string(),
-        );
+        let mut expected_ids = vec![
+            batch_ids[0].clone(),
+            batch_ids[2].clone(),
+            batch_ids[3].clone(),
+        ];
+        expected_ids.sort();
 
         assert_eq!(
-            add_tracking_batch.unwrap_err().to_string(),
-            expected.to_string()
+            store
+                .find_duplicate_dcids("TEST")
+                .expect("Failed to find duplicate dcids"),
+            vec![("dcid:one".to_string(), expected_ids)]
         );
+        assert!(store
+            .find_duplicate_dcids("OTHER")
+            .expect("Failed to find duplicate dcids")
+            .is_empty());
     }
 
+    /// Verify that batches are counted by origin within a window:
+    ///
+    /// 1. Add batches from two origins and one without an origin
+    /// 2. Move one batch of each origin before the window
+    /// 3. Validate the origin is returned with the batch
+    /// 4. Validate only batches with an origin created in the window are
+    ///    counted
     #[test]
-    fn update_batch_status() {
+    fn test_count_batches_by_origin() {
         let pool = create_connection_pool_and_migrate();
 
-        let store = DieselBatchTrackingStore::new(pool);
+        let store = DieselBatchTrackingStore::new(pool.clone());
 
         let signer = new_signer();
 
-        let pair = get_transact_transaction(&*signer, NONCE);
+        let batches: Vec<TrackingBatch> = [
+            ("n1", Some("erp")),
+            ("n2", Some("erp")),
+            ("n3", Some("erp")),
+            ("n4", Some("mes")),
+            ("n5", Some("mes")),
+            ("n6", None),
+        ]
+        .iter()
+        .map(|(nonce, origin)| {
+            let builder = get_tracking_batch(
+                get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, nonce)]),
+                false,
+            );
+            match origin {
+                Some(origin) => builder.with_origin(origin.to_string()),
+                None => builder,
+            }
+            .build()
+            .expect("Failed to build batch")
+        })
+        .collect();
+        let batch_ids: Vec<String> = batches
+            .iter()
+            .map(|batch| batch.batch_header().to_string())
+            .collect();
+
+        store.add_batches(batches).expect("Failed to add batches");
+
+        diesel::update(schema::batches::table)
+            .set(schema::batches::created_at.eq(200))
+            .execute(&*pool.get().expect("Failed to get connection"))
+            .expect("Failed to update batches");
+        diesel::update(
+            schema::batches::table
+                .filter(schema::batches::batch_id.eq_any(vec![&batch_ids[0], &batch_ids[3]])),
+        )
+        .set(schema::batches::created_at.eq(100))
+        .execute(&*pool.get().expect("Failed to get connection"))
+        .expect("Failed to update batches");
 
-        let transaction_id = pair.header_signature().to_string();
+        assert_eq!(
+            store
+                .get_batch(&batch_ids[3], "TEST")
+                .expect("Failed to get batch")
+                .expect("Batch not found")
+                .origin(),
+            Some("mes")
+        );
 
-        let batch_1 = get_transact_batch(&*signer, vec![pair]);
+        let mut expected = HashMap::new();
+        expected.insert("erp".to_string(), 2);
+        expected.insert("mes".to_string(), 1);
+        assert_eq!(
+            store
+                .count_batches_by_origin("TEST", 150)
+                .expect("Failed to count batches"),
+            expected
+        );
 
-        let tracking_batch = get_tracking_batch(batch_1.clone(), false)
-            .build()
-            .expect("Failed to build batch");
+        assert!(store
+            .count_batches_by_origin("TEST", 300)
+            .expect("Failed to count batches")
+            .is_empty());
+    }
 
-        let id = tracking_batch.batch_header();
+    /// Verify that iter_id_status yields the ID and status of every batch in a
+    /// service exactly once, across several pages:
+    ///
+    /// 1. Add more batches than fit in two pages and give them statuses
+    /// 2. Validate each batch is yielded once, in order, with its status
+    #[test]
+    fn test_iter_id_status() {
+        let pool = create_connection_pool_and_migrate();
 
-        store
-            .add_batches(vec![tracking_batch.clone()])
-            .expect("Failed to add batch");
+        let store = DieselBatchTrackingStore::new(pool.clone());
 
-        store
-            .update_batch_status(
-                &tracking_batch.batch_header(),
-                "TEST",
-                Some(BatchStatus::Pending),
-                Vec::new(),
-                None,
-            )
-            .expect("Failed to update batch");
+        let signer = new_signer();
 
-        let batch_result_1 = store
-            .get_batch(&id, "TEST")
-            .expect("Failed to get batch")
-            .unwrap();
-        let batch_result_1_timestamp = batch_result_1.created_at();
+        let batch_count = 2 * ID_STATUS_PAGE_SIZE as usize + 1;
+        let batches: Vec<TrackingBatch> = (0..batch_count)
+            .map(|i| {
+                get_tracking_batch(
+                    get_transact_batch(
+                        &*signer,
+                        vec![get_transact_transaction(&*signer, &format!("n{}", i))],
+                    ),
+                    false,
+                )
+                .build()
+                .expect("Failed to build batch")
+            })
+            .collect();
+        let mut batch_ids: Vec<String> = batches
+            .iter()
+            .map(|batch| batch.batch_header().to_string())
+            .collect();
+        batch_ids.sort();
 
-        let expected_1 = get_tracking_batch(batch_1.clone(), true)
-            .with_created_at(batch_result_1_timestamp)
-            .with_batch_status(BatchStatus::Pending)
-            .build()
-            .expect("Failed to build batch");
+        store.add_batches(batches).expect("Failed to add batches");
 
-        assert_eq!(batch_result_1, expected_1);
+        let conn = pool.get().expect("Failed to get connection");
+        diesel::sql_query(
+            "INSERT INTO batch_statuses (service_id, batch_id, dlt_status, created_at, updated_at)
+            SELECT service_id, batch_id, 'Pending', 0, 0 FROM batches",
+        )
+        .execute(&*conn)
+        .expect("Failed to insert statuses");
+        diesel::update(
+            schema::batch_statuses::table
+                .filter(schema::batch_statuses::batch_id.eq_any(&batch_ids[..10])),
+        )
+        .set(schema::batch_statuses::dlt_status.eq("Delayed"))
+        .execute(&*conn)
+        .expect("Failed to update statuses");
+        drop(conn);
 
-        let submission_error = SubmissionErrorBuilder::default()
-            .with_error_type("test".to_string())
-            .with_error_message("test message".to_string())
-            .build()
-            .expect("Failed to build error");
+        let id_statuses = store
+            .iter_id_status("TEST")
+            .expect("Failed to iterate batches")
+            .collect::<Result<Vec<(String, BatchStatusName)>, _>>()
+            .expect("Failed to read batch");
 
-        let receipt_1 = TransactionReceiptBuilder::default()
-            .with_transaction_id(transaction_id.to_string())
-            .with_result_valid(false)
-            .with_error_message("test".to_string())
-            .with_error_data(BYTES2.to_vec())
-            .with_serialized_receipt(
-                std::str::from_utf8(&BYTES2)
-                    .expect("Failed to build string")
-                    .to_string(),
-            )
-            .build()
-            .expect("Failed to build receipt");
+        assert_eq!(
+            id_statuses
+                .iter()
+                .map(|(batch_id, _)| batch_id.clone())
+                .collect::<Vec<String>>(),
+            batch_ids
+        );
+        for (i, (_, status)) in id_statuses.iter().enumerate() {
+            if i < 10 {
+                assert_eq!(status, &BatchStatusName::Delayed);
+            } else {
+                assert_eq!(status, &BatchStatusName::Pending);
+            }
+        }
 
-        let invalid_transactions = vec![InvalidTransactionBuilder::default()
-            .with_transaction_id(transaction_id.to_string())
-            .with_error_message("test".to_string())
-            .with_error_data(BYTES2.to_vec())
-            .build()
-            .expect("Failed to build invalid transaction")];
+        assert_eq!(
+            store
+                .iter_id_status("OTHER")
+                .expect("Failed to iterate batches")
+                .count(),
+            0
+        );
+    }
 
-        store
-            .update_batch_status(
-                &tracking_batch.batch_header(),
-                "TEST",
-                Some(BatchStatus::Invalid(invalid_transactions.to_vec())),
-                vec![receipt_1],
-                Some(submission_error.clone()),
-            )
-            .expect("Failed to update batch");
+    /// Verify that `list_batches_depending_on` returns the batches with a
+    /// transaction that lists the given transaction as a dependency, and that
+    /// the dependencies are returned with the batch
+    ///
+    /// 1. Add a batch and a second batch whose transaction depends on the
+    ///    first batch's transaction
+    /// 2. Check that only the second batch is listed as depending on the
+    ///    first transaction, with the dependency intact, and that the first
+    ///    batch is listed as depending on its own dependency
+    /// 3. Check that no batches are listed for an unknown transaction ID
+    #[test]
+    fn test_list_batches_depending_on() {
+        let pool = create_connection_pool_and_migrate();
 
-        let batch_result_2 = store
-            .get_batch(&id, "TEST")
-            .expect("Failed to get batch")
-            .unwrap();
-        let batch_result_2_timestamp = batch_result_2.created_at();
+        let store = DieselBatchTrackingStore::new(pool);
 
-        let expected_2 = get_tracking_batch(batch_1.clone(), true)
-            .with_created_at(batch_result_2_timestamp)
-            .with_batch_status(BatchStatus::Invalid(invalid_transactions))
-            .with_submission_error(submission_error)
+        let signer = new_signer();
+
+        let first_txn = get_transact_transaction(&*signer, NONCE);
+        let first_txn_id = first_txn.header_signature().to_string();
+        let dependent_txn = TransactionBuilder::new()
+            .with_batcher_public_key(hex::parse_hex(KEY1).unwrap())
+            .with_dependencies(vec![first_txn_id.clone()])
+            .with_family_name(FAMILY_NAME.to_string())
+            .with_family_version(FAMILY_VERSION.to_string())
+            .with_inputs(vec![hex::parse_hex(KEY4).unwrap()])
+            .with_nonce(NONCE2.to_string().into_bytes())
+            .with_outputs(vec![hex::parse_hex(KEY6).unwrap()])
+            .with_payload_hash_method(HashMethod::Sha512)
+            .with_payload(BYTES2.to_vec())
+            .build(&*signer)
+            .expect("Failed to build transaction");
+
+        let first = get_tracking_batch(get_transact_batch(&*signer, vec![first_txn]), false)
             .build()
             .expect("Failed to build batch");
+        let dependent =
+            get_tracking_batch(get_transact_batch(&*signer, vec![dependent_txn]), false)
+                .build()
+                .expect("Failed to build batch");
+        let dependent_id = dependent.batch_header().to_string();
 
-        assert_eq!(batch_result_2, expected_2);
+        store
+            .add_batches(vec![first, dependent])
+            .expect("Failed to add batches");
+
+        let batches = store
+            .list_batches_depending_on(&first_txn_id, "TEST")
+            .expect("Failed to list batches")
+            .batches;
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].batch_header(), dependent_id);
+        assert_eq!(batches[0].transactions()[0].dependencies(), &[first_txn_id]);
+
+        // The first batch's transaction depends on KEY2
+        let batches = store
+            .list_batches_depending_on(KEY2, "TEST")
+            .expect("Failed to list batches")
+            .batches;
+        assert_eq!(batches.len(), 1);
+        assert_ne!(batches[0].batch_header(), dependent_id);
+
+        assert!(store
+            .list_batches_depending_on("unknown", "TEST")
+            .expect("Failed to list batches")
+            .batches
+            .is_empty());
     }
 
+    /// Verify that `tombstone_batches` tombstones every given ID, counting
+    /// only the IDs that were not already tombstoned, and that none of the
+    /// batches can be added afterwards
     #[test]
-    fn update_batch_status_dcid() {
+    fn test_tombstone_batches() {
         let pool = create_connection_pool_and_migrate();
 
         let store = DieselBatchTrackingStore::new(pool);
 
         let signer = new_signer();
 
-        let pair = get_transact_transaction(&*signer, NONCE);
-
-        let transaction_id = pair.header_signature().to_string();
-
-        let batch_1 = get_transact_batch(&*signer, vec![pair]);
-
-        let dcid = "dcid:data_change".to_string();
-
-        let tracking_batch = get_tracking_batch(batch_1.clone(), false)
-            .with_data_change_id(dcid.clone())
-            .build()
-            .expect("Failed to build batch");
+        let tracking_batches: Vec<TrackingBatch> = [NONCE, NONCE2, "zz9kdf"]
+            .iter()
+            .map(|nonce| {
+                get_tracking_batch(
+                    get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, nonce)]),
+                    false,
+                )
+                .build()
+                .expect("Failed to build batch")
+            })
+            .collect();
+        let ids: Vec<String> = tracking_batches
+            .iter()
+            .map(|batch| batch.batch_header().to_string())
+            .collect();
 
         store
-            .add_batches(vec![tracking_batch.clone()])
-            .expect("Failed to add batch");
+            .tombstone_batch(&ids[0], "TEST")
+            .expect("Failed to tombstone batch");
 
-        store
-            .update_batch_status(&dcid, "TEST", Some(BatchStatus::Pending), Vec::new(), None)
-            .expect("Failed to update batch");
+        let id_refs: Vec<&str> = ids.iter().map(String::as_str).collect();
+        assert_eq!(
+            store
+                .tombstone_batches(&[id_refs[0], id_refs[1], id_refs[2], id_refs[2]], "TEST")
+                .expect("Failed to tombstone batches"),
+            2
+        );
+        assert_eq!(
+            store
+                .tombstone_batches(&id_refs, "TEST")
+                .expect("Failed to tombstone batches"),
+            0
+        );
 
-        let batch_result_1 = store
-            .get_batch(&dcid, "TEST")
-            .expect("Failed to get batch")
-            .unwrap();
-        let batch_result_1_timestamp = batch_result_1.created_at();
+        for (id, batch) in ids.iter().zip(tracking_batches) {
+            let res = store.add_batches(vec![batch]).unwrap_err();
+            assert_eq!(
+                res.to_string(),
+                BatchTrackingStoreError::Tombstoned(id.to_string()).to_string()
+            );
+            assert_eq!(
+                store.get_batch(id, "TEST").expect("Failed to get batch"),
+                None
+            );
+        }
 
-        let expected_1 = get_tracking_batch(batch_1.clone(), true)
-            .with_created_at(batch_result_1_timestamp)
-            .with_batch_status(BatchStatus::Pending)
-            .with_data_change_id(dcid.clone())
-            .build()
-            .expect("Failed to build batch");
+        assert_eq!(
+            store
+                .tombstone_batches(&[], "TEST")
+                .expect("Failed to tombstone batches"),
+            0
+        );
+    }
 
-        assert_eq!(batch_result_1, expected_1);
+    /// Verify that `submission_success_rate` divides the committed batches
+    /// created in the window by those that reached a terminal status, and
+    /// returns a `NotFoundError` when none did
+    #[test]
+    fn test_submission_success_rate() {
+        let pool = create_connection_pool_and_migrate();
 
-        let submission_error = SubmissionErrorBuilder::default()
-            .with_error_type("test".to_string())
-            .with_error_message("test message".to_string())
-            .build()
-            .expect("Failed to build error");
+        let store = DieselBatchTrackingStore::new(pool.clone());
 
-        let receipt_1 = TransactionReceiptBuilder::default()
-            .with_transaction_id(transaction_id.to_string())
-            .with_result_valid(false)
-            .with_error_message("test".to_string())
-            .with_error_data(BYTES2.to_vec())
-            .with_serialized_receipt(
-                std::str::from_utf8(&BYTES2)
-                    .expect("Failed to build string")
-                    .to_string(),
+        let signer = new_signer();
+
+        let statuses_and_times = [
+            (BatchStatus::Committed(Vec::new()), 100),
+            (BatchStatus::Committed(Vec::new()), 120),
+            (BatchStatus::Committed(Vec::new()), 140),
+            (BatchStatus::Invalid(Vec::new()), 160),
+            (BatchStatus::Pending, 180),
+            // Outside of the window
+            (BatchStatus::Invalid(Vec::new()), 200),
+            (BatchStatus::Invalid(Vec::new()), 99),
+        ];
+
+        for (i, (status, created_at)) in statuses_and_times.iter().enumerate() {
+            let batch = get_transact_batch(
+                &*signer,
+                vec![get_transact_transaction(&*signer, &format!("n{}", i))],
+            );
+            let tracking_batch = get_tracking_batch(batch, false)
+                .build()
+                .expect("Failed to build batch");
+            let id = tracking_batch.batch_header().to_string();
+            store
+                .add_batches(vec![tracking_batch])
+                .expect("Failed to add batch");
+            store
+                .update_batch_status(&id, "TEST", Some(status.clone()), Vec::new(), None)
+                .expect("Failed to update batch");
+
+            diesel::update(
+                schema::batches::table.filter(
+                    schema::batches::batch_id
+                        .eq(&id)
+                        .and(schema::batches::service_id.eq("TEST")),
+                ),
             )
-            .build()
-            .expect("Failed to build receipt");
+            .set(schema::batches::created_at.eq(created_at))
+            .execute(&*pool.get().expect("Failed to get connection"))
+            .expect("Failed to set created_at");
+        }
 
-        let invalid_transactions = vec![InvalidTransactionBuilder::default()
-            .with_transaction_id(transaction_id.to_string())
-            .with_error_message("test".to_string())
-            .with_error_data(BYTES2.to_vec())
-            .build()
-            .expect("Failed to build invalid transaction")];
+        assert_eq!(
+            store
+                .submission_success_rate("TEST", 100, 200)
+                .expect("Failed to get success rate"),
+            0.75
+        );
 
+        // Dead-lettered batches count as failed
+        let batch = get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, "dead")]);
+        let tracking_batch = get_tracking_batch(batch, false)
+            .build()
+            .expect("Failed to build batch");
+        let id = tracking_batch.batch_header().to_string();
+        store
+            .add_batches(vec![tracking_batch])
+            .expect("Failed to add batch");
         store
             .update_batch_status(
-                &dcid,
+                &id,
                 "TEST",
-                Some(BatchStatus::Invalid(invalid_transactions.to_vec())),
-                vec![receipt_1],
-                Some(submission_error.clone()),
+                Some(BatchStatus::DeadLettered),
+                Vec::new(),
+                None,
             )
             .expect("Failed to update batch");
+        diesel::update(schema::batches::table.filter(schema::batches::batch_id.eq(&id)))
+            .set(schema::batches::created_at.eq(170))
+            .execute(&*pool.get().expect("Failed to get connection"))
+            .expect("Failed to set created_at");
 
-        let batch_result_2 = store
-            .get_batch(&dcid, "TEST")
-            .expect("Failed to get batch")
-            .unwrap();
-        let batch_result_2_timestamp = batch_result_2.created_at();
-
-        let expected_2 = get_tracking_batch(batch_1.clone(), true)
-            .with_created_at(batch_result_2_timestamp)
-            .with_batch_status(BatchStatus::Invalid(invalid_transactions))
-            .with_submission_error(submission_error)
-            .with_data_change_id(dcid.clone())
-            .build()
-            .expect("Failed to build batch");
+        assert_eq!(
+            store
+                .submission_success_rate("TEST", 100, 200)
+                .expect("Failed to get success rate"),
+            0.6
+        );
 
-        assert_eq!(batch_result_2, expected_2);
+        // Only a pending batch was created in this window
+        assert!(matches!(
+            store.submission_success_rate("TEST", 180, 181),
+            Err(BatchTrackingStoreError::NotFoundError(_))
+        ));
+        assert!(matches!(
+            store.submission_success_rate("OTHER", 0, i64::MAX),
+            Err(BatchTrackingStoreError::NotFoundError(_))
+        ));
     }
 
+    /// Verify that `find_batches_without_transactions` flags a batch stored
+    /// without any transactions, but not batches added with theirs
     #[test]
-    fn change_batch_to_submitted() {
+    fn test_find_batches_without_transactions() {
+        use super::models::NewBatchModel;
+
         let pool = create_connection_pool_and_migrate();
 
-        let store = DieselBatchTrackingStore::new(pool);
+        let store = DieselBatchTrackingStore::new(pool.clone());
 
         let signer = new_signer();
 
-        let pair = get_transact_transaction(&*signer, NONCE);
+        store
+            .add_batches(vec![
+                get_tracking_batch(
+                    get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE)]),
+                    false,
+                )
+                .build()
+                .expect("Failed to build batch"),
+                get_tracking_batch(
+                    get_transact_batch(
+                        &*signer,
+                        vec![
+                            get_transact_transaction(&*signer, NONCE2),
+                            get_transact_transaction(&*signer, "zz9kdf"),
+                        ],
+                    ),
+                    false,
+                )
+                .build()
+                .expect("Failed to build batch"),
+            ])
+            .expect("Failed to add batches");
 
-        let transaction_header = pair.header_signature().to_string();
+        assert!(store
+            .find_batches_without_transactions("TEST")
+            .expect("Failed to find batches")
+            .is_empty());
+
+        // A batch can only be stored without transactions directly
+        diesel::insert_into(schema::batches::table)
+            .values(NewBatchModel {
+                service_id: "TEST".to_string(),
+                batch_id: "empty".to_string(),
+                data_change_id: None,
+                signer_public_key: KEY1.to_string(),
+                trace: false,
+                serialized_batch: Vec::new(),
+                submitted: false,
+                created_at: 0,
+                notes: None,
+                byte_size: 0,
+                batch_kind: None,
+                origin: None,
+                metadata: None,
+            })
+            .execute(&*pool.get().expect("Failed to get connection"))
+            .expect("Failed to insert batch");
 
-        let batch_1 = get_transact_batch(&*signer, vec![pair]);
+        assert_eq!(
+            store
+                .find_batches_without_transactions("TEST")
+                .expect("Failed to find batches"),
+            vec!["empty".to_string()]
+        );
+        assert!(store
+            .find_batches_without_transactions("OTHER")
+            .expect("Failed to find batches")
+            .is_empty());
+    }
 
-        let tracking_batch = get_tracking_batch(batch_1.clone(), false)
-            .build()
-            .expect("Failed to build batch");
+    /// Verify that `claim_unsubmitted_batches` leases disjoint blocks of the
+    /// oldest unsubmitted batches to each worker, and that batches can only be
+    /// claimed again once their claim has expired
+    ///
+    /// 1. Add five batches, created in order
+    /// 2. Claim two batches for each of two workers, and check that each
+    ///    worker gets the oldest batches not claimed by the other
+    /// 3. Check that only the one remaining batch can then be claimed
+    /// 4. Expire the first worker's first claim, and check that its batches
+    ///    can be claimed by the second worker
+    #[test]
+    fn test_claim_unsubmitted_batches() {
+        let pool = create_connection_pool_and_migrate();
 
-        let id = tracking_batch.batch_header();
+        let store = DieselBatchTrackingStore::new(pool.clone());
 
-        let txn_receipts = vec![TransactionReceiptBuilder::default()
-            .with_transaction_id(transaction_header)
-            .with_result_valid(true)
-            .with_serialized_receipt(
-                std::str::from_utf8(&BYTES2)
-                    .expect("Failed to build string")
-                    .to_string(),
-            )
-            .build()
-            .expect("Failed to build receipt")];
+        let signer = new_signer();
 
-        let submission_error = SubmissionErrorBuilder::default()
-            .with_error_type("test".to_string())
-            .with_error_message("test message".to_string())
-            .build()
-            .expect("Failed to build error");
+        let mut ids = Vec::new();
+        for (created_at, nonce) in ["n1", "n2", "n3", "n4", "n5"].iter().enumerate() {
+            let batch =
+                get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, nonce)]);
+            let tracking_batch = get_tracking_batch(batch, false)
+                .build()
+                .expect("Failed to build batch");
+            let id = tracking_batch.batch_header().to_string();
+            store
+                .add_batches(vec![tracking_batch])
+                .expect("Failed to add batch");
+
+            diesel::update(schema::batches::table.filter(schema::batches::batch_id.eq(&id)))
+                .set(schema::batches::created_at.eq(created_at as i64))
+                .execute(&*pool.get().expect("Failed to get connection"))
+                .expect("Failed to set created_at");
+            ids.push(id);
+        }
 
-        store
-            .add_batches(vec![tracking_batch.clone()])
-            .expect("Failed to add batch");
+        let claim = |claimant: &str, n: i64| -> Vec<String> {
+            store
+                .claim_unsubmitted_batches("TEST", claimant, n, 3600)
+                .expect("Failed to claim batches")
+                .batches
+                .iter()
+                .map(|batch| batch.batch_header().to_string())
+                .collect()
+        };
 
-        store
-            .change_batch_to_submitted(
-                &id,
-                "TEST",
-                txn_receipts,
-                Some("Pending"),
-                Some(submission_error),
-            )
-            .expect("Failed to change batch to submitted");
+        let before_claim = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("Failed to get time")
+            .as_secs() as i64;
+
+        assert_eq!(claim("worker-a", 2), ids[0..2].to_vec());
+        assert_eq!(claim("worker-b", 2), ids[2..4].to_vec());
+
+        let claims: Vec<(String, Option<String>, Option<i64>)> = schema::batches::table
+            .select((
+                schema::batches::batch_id,
+                schema::batches::claimed_by,
+                schema::batches::claim_expires_at,
+            ))
+            .order(schema::batches::created_at.asc())
+            .load(&*pool.get().expect("Failed to get connection"))
+            .expect("Failed to load claims");
+        for (i, (_, claimed_by, claim_expires_at)) in claims.iter().enumerate() {
+            match i {
+                0 | 1 => assert_eq!(claimed_by.as_deref(), Some("worker-a")),
+                2 | 3 => assert_eq!(claimed_by.as_deref(), Some("worker-b")),
+                _ => {
+                    assert_eq!(claimed_by, &None);
+                    continue;
+                }
+            }
+            assert!(claim_expires_at.expect("Claim has no expiry") >= before_claim + 3600);
+        }
 
-        let batch = store
-            .get_batch(&id, "TEST")
-            .expect("Failed to get batch")
-            .unwrap();
+        assert_eq!(claim("worker-a", 2), ids[4..5].to_vec());
+        assert!(claim("worker-b", 2).is_empty());
 
-        assert_eq!(batch.submitted(), true)
+        diesel::update(schema::batches::table.filter(schema::batches::batch_id.eq_any(&ids[0..2])))
+            .set(schema::batches::claim_expires_at.eq(before_claim - 1))
+            .execute(&*pool.get().expect("Failed to get connection"))
+            .expect("Failed to expire claims");
+
+        assert_eq!(claim("worker-b", 5), ids[0..2].to_vec());
+        assert!(store
+            .claim_unsubmitted_batches("OTHER", "worker-a", 5, 3600)
+            .expect("Failed to claim batches")
+            .batches
+            .is_empty());
     }
 
+    /// Verify that `health_snapshot` counts a service's failed, stuck pending
+    /// and unsubmitted batches, along with the total
     #[test]
-    fn change_batch_to_submitted_dcid() {
+    fn test_health_snapshot() {
         let pool = create_connection_pool_and_migrate();
 
-        let store = DieselBatchTrackingStore::new(pool);
+        let store = DieselBatchTrackingStore::new(pool.clone());
 
         let signer = new_signer();
 
-        let pair = get_transact_transaction(&*signer, NONCE);
+        assert_eq!(
+            store
+                .health_snapshot("TEST", 100)
+                .expect("Failed to get health snapshot"),
+            HealthSnapshot::new(0, 0, 0, 0)
+        );
 
-        let transaction_header = pair.header_signature().to_string();
+        let add_batch = |nonce: &str, submitted: bool| -> String {
+            let batch =
+                get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, nonce)]);
+            let tracking_batch = get_tracking_batch(batch, submitted)
+                .build()
+                .expect("Failed to build batch");
+            let id = tracking_batch.batch_header().to_string();
+            store
+                .add_batches(vec![tracking_batch])
+                .expect("Failed to add batch");
+            id
+        };
 
-        let batch_1 = get_transact_batch(&*signer, vec![pair]);
+        let committed = add_batch("n1", true);
+        let invalid = add_batch("n2", true);
+        let dead_lettered = add_batch("n3", false);
+        let stuck = add_batch("n4", true);
+        let recent = add_batch("n5", true);
+        // Unsubmitted batches, one of them pending resubmission
+        add_batch("n6", false);
+        let unsubmitted = add_batch("n7", false);
+
+        for (id, status) in &[
+            (&committed, BatchStatus::Committed(Vec::new())),
+            (&invalid, BatchStatus::Invalid(Vec::new())),
+            (&stuck, BatchStatus::Pending),
+            (&recent, BatchStatus::Pending),
+            (&unsubmitted, BatchStatus::Delayed),
+        ] {
+            store
+                .update_batch_status(id, "TEST", Some(status.clone()), Vec::new(), None)
+                .expect("Failed to update batch");
+        }
+        store
+            .dead_letter_batch(&dead_lettered, "TEST", "Out of retries")
+            .expect("Failed to dead-letter batch");
 
-        let dcid = "dcid:data_change".to_string();
+        for (id, updated_at) in &[(&stuck, 50), (&recent, 150)] {
+            diesel::update(
+                schema::batch_statuses::table.filter(schema::batch_statuses::batch_id.eq(id)),
+            )
+            .set(schema::batch_statuses::updated_at.eq(updated_at))
+            .execute(&*pool.get().expect("Failed to get connection"))
+            .expect("Failed to set updated_at");
+        }
 
-        let tracking_batch = get_tracking_batch(batch_1.clone(), false)
-            .with_data_change_id(dcid.clone())
-            .build()
-            .expect("Failed to build batch");
+        let snapshot = store
+            .health_snapshot("TEST", 100)
+            .expect("Failed to get health snapshot");
+        assert_eq!(snapshot.total(), 7);
+        assert_eq!(snapshot.failed(), 2);
+        assert_eq!(snapshot.stuck_pending(), 1);
+        assert_eq!(snapshot.unsubmitted(), 2);
 
-        let txn_receipts = vec![TransactionReceiptBuilder::default()
-            .with_transaction_id(transaction_header)
-            .with_result_valid(true)
-            .with_serialized_receipt(
-                std::str::from_utf8(&BYTES2)
-                    .expect("Failed to build string")
-                    .to_string(),
-            )
-            .build()
-            .expect("Failed to build receipt")];
+        assert_eq!(
+            store
+                .health_snapshot("TEST", 200)
+                .expect("Failed to get health snapshot")
+                .stuck_pending(),
+            2
+        );
+        assert_eq!(
+            store
+                .health_snapshot("OTHER", 100)
+                .expect("Failed to get health snapshot"),
+            HealthSnapshot::new(0, 0, 0, 0)
+        );
+    }
 
-        let submission_error = SubmissionErrorBuilder::default()
-            .with_error_type("test".to_string())
-            .with_error_message("test message".to_string())
-            .build()
-            .expect("Failed to build error");
+    /// A cipher that XORs each byte with a key, for testing payload
+    /// encryption
+    struct XorCipher(u8);
 
-        store
-            .add_batches(vec![tracking_batch.clone()])
+    impl PayloadCipher for XorCipher {
+        fn encrypt(&self, payload: &[u8]) -> Result<Vec<u8>, InternalError> {
+            Ok(payload.iter().map(|byte| byte ^ self.0).collect())
+        }
+
+        fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>, InternalError> {
+            self.encrypt(ciphertext)
+        }
+    }
+
+    /// Verify that a store with a payload cipher stores encrypted payloads,
+    /// returns the original payloads when reading, and still reads payloads
+    /// stored before the cipher was configured
+    ///
+    /// 1. Add a batch with a store that has no cipher
+    /// 2. Add a batch with a store that has an XOR cipher, and check that the
+    ///    stored payload differs from the original and is flagged encrypted
+    /// 3. Check that the store with the cipher returns the original payloads
+    ///    of both batches
+    /// 4. Check that the store without the cipher fails to read the encrypted
+    ///    batch
+    #[test]
+    fn test_payload_cipher() {
+        let pool = create_connection_pool_and_migrate();
+
+        let plain_store = DieselBatchTrackingStore::new(pool.clone());
+        let store = DieselBatchTrackingStore::new(pool.clone())
+            .with_payload_cipher(Arc::new(XorCipher(0x5a)));
+
+        let signer = new_signer();
+
+        let legacy = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE)]),
+            false,
+        )
+        .build()
+        .expect("Failed to build batch");
+        let legacy_id = legacy.batch_header().to_string();
+        plain_store
+            .add_batches(vec![legacy])
             .expect("Failed to add batch");
 
+        let encrypted = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE2)]),
+            false,
+        )
+        .build()
+        .expect("Failed to build batch");
+        let encrypted_id = encrypted.batch_header().to_string();
+        let encrypted_txn_id = encrypted.transactions()[0].transaction_header().to_string();
         store
-            .change_batch_to_submitted(
-                &dcid,
-                "TEST",
-                txn_receipts,
-                Some("Pending"),
-                Some(submission_error),
-            )
-            .expect("Failed to change batch to submitted");
+            .add_batches(vec![encrypted])
+            .expect("Failed to add batch");
 
-        let batch = store
-            .get_batch(&dcid, "TEST")
-            .expect("Failed to get batch")
-            .unwrap();
+        let stored: Vec<(Vec<u8>, bool)> = schema::transactions::table
+            .filter(schema::transactions::transaction_id.eq(&encrypted_txn_id))
+            .select((
+                schema::transactions::payload,
+                schema::transactions::payload_encrypted,
+            ))
+            .load(&*pool.get().expect("Failed to get connection"))
+            .expect("Failed to load payload");
+        assert_eq!(stored.len(), 1);
+        assert_ne!(stored[0].0, BYTES2.to_vec());
+        assert!(stored[0].1);
+
+        for id in &[&legacy_id, &encrypted_id] {
+            let batch = store
+                .get_batch(id, "TEST")
+                .expect("Failed to get batch")
+                .expect("Batch not found");
+            assert_eq!(batch.transactions()[0].payload(), BYTES2);
+        }
+        assert!(store
+            .list_batches("TEST")
+            .expect("Failed to list batches")
+            .batches
+            .iter()
+            .all(|batch| batch.transactions()[0].payload() == BYTES2));
 
-        assert_eq!(batch.submitted(), true)
+        assert!(plain_store.get_batch(&encrypted_id, "TEST").is_err());
     }
 
+    /// Verify that a store with a batch ID validator adds batches whose IDs
+    /// the validator accepts and rejects the others with `InvalidBatchId`
     #[test]
-    fn change_batch_to_submitted_no_batch() {
+    fn test_batch_id_validator() {
         let pool = create_connection_pool_and_migrate();
 
-        let store = DieselBatchTrackingStore::new(pool);
+        let store = DieselBatchTrackingStore::new(pool).with_batch_id_validator(|batch_id| {
+            if batch_id.chars().all(|c| c.is_ascii_hexdigit()) {
+                Ok(())
+            } else {
+                Err("Batch ID must be hex".to_string())
+            }
+        });
 
-        let res = store
-            .change_batch_to_submitted("id", "TEST", Vec::new(), Some("Pending"), None)
-            .unwrap_err();
+        let signer = new_signer();
 
+        let valid = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE)]),
+            false,
+        )
+        .build()
+        .expect("Failed to build batch");
+        let valid_id = valid.batch_header().to_string();
+
+        let mut malformed = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE2)]),
+            false,
+        )
+        .build()
+        .expect("Failed to build batch");
+        malformed.batch_header = "not+hex/base64==".to_string();
+
+        match store.add_batches(vec![valid.clone(), malformed]) {
+            Err(BatchTrackingStoreError::InvalidBatchId { batch_id, reason }) => {
+                assert_eq!(batch_id, "not+hex/base64==");
+                assert_eq!(reason, "Batch ID must be hex");
+            }
+            res => panic!("Expected InvalidBatchId, got {:?}", res),
+        }
+        // The whole call fails, so the valid batch is not added either
         assert_eq!(
-            res.to_string(),
-            BatchTrackingStoreError::NotFoundError("Could not find batch with ID id".to_string())
-                .to_string()
+            store
+                .get_batch(&valid_id, "TEST")
+                .expect("Failed to get batch"),
+            None
         );
+
+        store
+            .add_batches(vec![valid])
+            .expect("Failed to add valid batch");
+        assert!(store
+            .get_batch(&valid_id, "TEST")
+            .expect("Failed to get batch")
+            .is_some());
     }
 
+    /// Verify that `hourly_batch_counts` counts the batches created in each
+    /// hour of the window, omitting hours with no batches
     #[test]
-    fn get_unsubmitted_batches() {
+    fn test_hourly_batch_counts() {
         let pool = create_connection_pool_and_migrate();
 
-        let store = DieselBatchTrackingStore::new(pool);
+        let store = DieselBatchTrackingStore::new(pool.clone());
 
         let signer = new_signer();
 
-        let pair_1 = get_transact_transaction(&*signer, NONCE);
+        let created_ats = [
+            // Before the window
+            3599, 3600, 3601, 7199, // No batches in the hour starting at 7200
+            10800, 14399, // After the window
+            14400,
+        ];
+
+        for (i, created_at) in created_ats.iter().enumerate() {
+            let batch = get_transact_batch(
+                &*signer,
+                vec![get_transact_transaction(&*signer, &format!("n{}", i))],
+            );
+            let tracking_batch = get_tracking_batch(batch, false)
+                .build()
+                .expect("Failed to build batch");
+            let id = tracking_batch.batch_header().to_string();
+            store
+                .add_batches(vec![tracking_batch])
+                .expect("Failed to add batch");
 
-        let pair_2 = get_transact_transaction(&*signer, NONCE2);
+            diesel::update(schema::batches::table.filter(schema::batches::batch_id.eq(&id)))
+                .set(schema::batches::created_at.eq(created_at))
+                .execute(&*pool.get().expect("Failed to get connection"))
+                .expect("Failed to set created_at");
+        }
 
-        let batch_1 = get_transact_batch(&*signer, vec![pair_1]);
+        assert_eq!(
+            store
+                .hourly_batch_counts("TEST", 3600, 14400)
+                .expect("Failed to count batches"),
+            vec![(3600, 3), (10800, 2)]
+        );
 
-        let batch_2 = get_transact_batch(&*signer, vec![pair_2]);
+        // Hours are aligned to the epoch rather than to the start of the
+        // window
+        assert_eq!(
+            store
+                .hourly_batch_counts("TEST", 3601, 10801)
+                .expect("Failed to count batches"),
+            vec![(3600, 2), (10800, 1)]
+        );
 
-        let tracking_batch_1 = get_tracking_batch(batch_1.clone(), false)
-            .build()
-            .expect("Failed to build batch");
+        assert!(store
+            .hourly_batch_counts("OTHER", 0, i64::MAX)
+            .expect("Failed to count batches")
+            .is_empty());
+    }
 
-        let id = tracking_batch_1.batch_header();
+    #[test]
+    /// Test that a status change queues an event in the outbox, and that
+    /// events are fetched oldest first and removed once acknowledged
+    fn test_outbox() {
+        let pool = create_connection_pool_and_migrate();
 
-        let tracking_batch_2 = get_tracking_batch(batch_2.clone(), true)
-            .build()
-            .expect("Failed to build batch");
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let signer = new_signer();
+
+        let batch = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE)]),
+            false,
+        )
+        .build()
+        .expect("Failed to build batch");
+        let id = batch.batch_header().to_string();
 
         store
-            .add_batches(vec![tracking_batch_1.clone(), tracking_batch_2])
+            .add_batches(vec![batch])
             .expect("Failed to add batches");
 
-        let batch_result = store
-            .get_batch(&id, "TEST")
-            .expect("Failed to get batch")
-            .unwrap();
-        let batch_result_timestamp = batch_result.created_at();
+        assert!(store
+            .fetch_outbox(10)
+            .expect("Failed to fetch outbox")
+            .is_empty());
 
-        let expected_batch = get_tracking_batch(batch_1.clone(), false)
-            .with_created_at(batch_result_timestamp)
-            .build()
-            .expect("Failed to build batch");
+        store
+            .with_correlation_id("request-1")
+            .update_batch_status(&id, "TEST", Some(BatchStatus::Pending), Vec::new(), None)
+            .expect("Failed to update batch status");
+        store
+            .update_batch_status(&id, "TEST", Some(BatchStatus::Delayed), Vec::new(), None)
+            .expect("Failed to update batch status");
+
+        let events = store.fetch_outbox(1).expect("Failed to fetch outbox");
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event().batch_id(), id);
+        assert_eq!(events[0].event().service_id(), "TEST");
+        assert_eq!(events[0].event().status(), BatchStatusName::Pending);
+        assert_eq!(events[0].event().correlation_id(), Some("request-1"));
+        let first_id = events[0].id();
+
+        // An event stays in the outbox until it is acknowledged
+        assert_eq!(
+            store.fetch_outbox(1).expect("Failed to fetch outbox"),
+            events
+        );
 
-        let expected = TrackingBatchList {
-            batches: vec![expected_batch],
-        };
+        assert_eq!(
+            store.ack_outbox(&[first_id]).expect("Failed to ack outbox"),
+            1
+        );
+
+        let events = store.fetch_outbox(10).expect("Failed to fetch outbox");
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event().status(), BatchStatusName::Delayed);
+        assert_eq!(events[0].event().correlation_id(), None);
+
+        // Acknowledging an event twice is ignored
+        assert_eq!(
+            store
+                .ack_outbox(&[first_id, events[0].id()])
+                .expect("Failed to ack outbox"),
+            1
+        );
+        assert!(store
+            .fetch_outbox(10)
+            .expect("Failed to fetch outbox")
+            .is_empty());
+    }
+
+    #[test]
+    /// Test that a set of batches can be dead-lettered together with a shared
+    /// reason, and that nothing is dead-lettered if a batch is missing
+    fn test_dead_letter_batches() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+
+        let signer = new_signer();
 
+        let batches: Vec<TrackingBatch> = [NONCE, NONCE2, "k9fzzd"]
+            .iter()
+            .map(|nonce| {
+                get_tracking_batch(
+                    get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, nonce)]),
+                    false,
+                )
+                .build()
+                .expect("Failed to build batch")
+            })
+            .collect();
+        let dead_ids: Vec<String> = batches[..2]
+            .iter()
+            .map(|batch| batch.batch_header().to_string())
+            .collect();
+        let live_id = batches[2].batch_header().to_string();
+
+        store.add_batches(batches).expect("Failed to add batches");
+
+        let result =
+            store.dead_letter_batches(&[&dead_ids[0], "missing"], "TEST", "retries exhausted");
+        assert!(matches!(
+            result,
+            Err(BatchTrackingStoreError::NotFoundError(_))
+        ));
+        assert!(store
+            .get_dead_lettered_batches("TEST")
+            .expect("Failed to get dead-lettered batches")
+            .batches
+            .is_empty());
+
+        assert_eq!(
+            store
+                .dead_letter_batches(
+                    &[&dead_ids[0], &dead_ids[1], &dead_ids[0]],
+                    "TEST",
+                    "retries exhausted"
+                )
+                .expect("Failed to dead-letter batches"),
+            2
+        );
+
+        let unsubmitted: Vec<String> = store
+            .get_unsubmitted_batches()
+            .expect("Failed to get unsubmitted batches")
+            .batches
+            .iter()
+            .map(|b| b.batch_header().to_string())
+            .collect();
+        assert_eq!(unsubmitted, vec![live_id]);
+
+        let mut dead_lettered = store
+            .get_dead_lettered_batches("TEST")
+            .expect("Failed to get dead-lettered batches")
+            .batches;
+        dead_lettered.sort_by(|a, b| a.batch_header().cmp(b.batch_header()));
+        let mut expected = dead_ids.clone();
+        expected.sort();
         assert_eq!(
-            store
-                .get_unsubmitted_batches()
-                .expect("Failed to get batch"),
+            dead_lettered
+                .iter()
+                .map(|b| b.batch_header().to_string())
+                .collect::<Vec<_>>(),
             expected
         );
+        for batch in &dead_lettered {
+            assert_eq!(batch.dead_letter_reason(), Some("retries exhausted"));
+            assert_eq!(batch.batch_status(), Some(&BatchStatus::DeadLettered));
+        }
     }
 
     #[test]
-    fn test_list_pending_batches() {
+    /// Test that only the batches whose labels match every predicate are
+    /// listed, and that non-string metadata values are not treated as labels
+    fn test_list_batches_by_labels() {
         let pool = create_connection_pool_and_migrate();
 
         let store = DieselBatchTrackingStore::new(pool);
 
         let signer = new_signer();
 
-        let pair = get_transact_transaction(&*signer, NONCE);
-
-        let batch_1 = get_transact_batch(&*signer, vec![pair]);
+        let label_sets = vec![
+            Some(serde_json::json!({"tenant": "acme", "priority": "high"})),
+            Some(serde_json::json!({"tenant": "acme", "priority": "low"})),
+            Some(serde_json::json!({"tenant": "globex", "priority": "high"})),
+            Some(serde_json::json!({"tenant": "acme", "priority": "high", "region": "eu"})),
+            Some(serde_json::json!({"tenant": "acme", "priority": 1})),
+            None,
+        ];
+        let mut ids = Vec::new();
+        let mut batches = Vec::new();
+        for (i, labels) in label_sets.into_iter().enumerate() {
+            let mut builder = get_tracking_batch(
+                get_transact_batch(
+                    &*signer,
+                    vec![get_transact_transaction(&*signer, &format!("nonce{}", i))],
+                ),
+                false,
+            );
+            if let Some(labels) = labels {
+                builder = builder.with_metadata(labels);
+            }
+            let batch = builder.build().expect("Failed to build batch");
+            ids.push(batch.batch_header().to_string());
+            batches.push(batch);
+        }
 
-        let tracking_batch = get_tracking_batch(batch_1.clone(), false)
-            .build()
-            .expect("Failed to build batch");
+        store.add_batches(batches).expect("Failed to add batches");
 
-        let id = tracking_batch.batch_header();
+        let list = |predicates: &[(&str, &str)]| {
+            let predicates: Vec<(String, String)> = predicates
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect();
+            let mut listed: Vec<String> = store
+                .list_batches_by_labels(&predicates, "TEST")
+                .expect("Failed to list batches by labels")
+                .batches
+                .iter()
+                .map(|b| b.batch_header().to_string())
+                .collect();
+            listed.sort();
+            listed
+        };
+        let expected = |indexes: &[usize]| {
+            let mut expected: Vec<String> = indexes.iter().map(|i| ids[*i].clone()).collect();
+            expected.sort();
+            expected
+        };
 
-        store
-            .add_batches(vec![tracking_batch.clone()])
-            .expect("Failed to add batch");
+        assert_eq!(
+            list(&[("tenant", "acme"), ("priority", "high")]),
+            expected(&[0, 3])
+        );
+        assert_eq!(list(&[("tenant", "acme")]), expected(&[0, 1, 3, 4]));
+        assert_eq!(list(&[("priority", "1")]), Vec::<String>::new());
+        assert_eq!(
+            list(&[("tenant", "acme"), ("region", "us")]),
+            Vec::<String>::new()
+        );
+        assert_eq!(list(&[]), expected(&[0, 1, 2, 3, 4, 5]));
+    }
 
-        let batch_result = store
-            .get_batch(&id, "TEST")
-            .expect("Failed to get batch")
-            .unwrap();
-        let batch_result_timestamp = batch_result.created_at();
+    #[test]
+    /// Test that the error from a batch's latest failed submission is
+    /// returned, and that a batch without submission errors has none
+    fn test_get_latest_submission_error() {
+        let pool = create_connection_pool_and_migrate();
 
-        store
-            .update_batch_status(&id, "TEST", Some(BatchStatus::Pending), Vec::new(), None)
-            .expect("Failed to update batch");
+        let store = DieselBatchTrackingStore::new(pool);
 
-        let expected = get_tracking_batch(batch_1.clone(), true)
-            .with_created_at(batch_result_timestamp)
-            .with_batch_status(BatchStatus::Pending)
-            .build()
-            .expect("Failed to build batch");
+        let signer = new_signer();
 
-        assert_eq!(
+        let batches: Vec<TrackingBatch> = [NONCE, NONCE2]
+            .iter()
+            .map(|nonce| {
+                get_tracking_batch(
+                    get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, nonce)]),
+                    false,
+                )
+                .build()
+                .expect("Failed to build batch")
+            })
+            .collect();
+        let failed_id = batches[0].batch_header().to_string();
+        let clean_id = batches[1].batch_header().to_string();
+
+        store.add_batches(batches).expect("Failed to add batches");
+
+        for message in &["connection refused", "service unavailable"] {
+            let submission_error = SubmissionErrorBuilder::default()
+                .with_error_type("Unknown".to_string())
+                .with_error_message(message.to_string())
+                .build()
+                .expect("Failed to build error");
             store
-                .list_batches_by_status(BatchStatus::Pending)
-                .expect("Failed to get batch"),
-            TrackingBatchList {
-                batches: vec![expected]
-            }
-        );
+                .update_batch_status(
+                    &failed_id,
+                    "TEST",
+                    Some(BatchStatus::Unknown),
+                    Vec::new(),
+                    Some(submission_error),
+                )
+                .expect("Failed to update batch status");
+        }
 
-        store
-            .update_batch_status(&id, "TEST", Some(BatchStatus::Unknown), Vec::new(), None)
-            .expect("Failed to update batch");
+        let latest = store
+            .get_latest_submission_error(&failed_id, "TEST")
+            .expect("Failed to get latest submission error")
+            .expect("Submission error not found");
+        assert_eq!(latest.error_type(), "Unknown");
+        assert_eq!(latest.error_message(), "service unavailable");
 
         assert_eq!(
             store
-                .list_batches_by_status(BatchStatus::Pending)
-                .expect("Failed to get batch"),
-            TrackingBatchList {
-                batches: Vec::new()
-            }
+                .get_latest_submission_error(&clean_id, "TEST")
+                .expect("Failed to get latest submission error"),
+            None
         );
     }
 
     #[test]
-    fn test_get_failed_batches() {
+    /// Test that a set of status transitions is applied together with a
+    /// status event for each, and that an illegal transition rolls back
+    /// every transition in the call
+    fn test_transition_batches() {
         let pool = create_connection_pool_and_migrate();
 
         let store = DieselBatchTrackingStore::new(pool);
 
         let signer = new_signer();
 
-        let pair = get_transact_transaction(&*signer, NONCE);
+        let batches: Vec<TrackingBatch> = [NONCE, NONCE2, "k9fzzd"]
+            .iter()
+            .map(|nonce| {
+                get_tracking_batch(
+                    get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, nonce)]),
+                    false,
+                )
+                .build()
+                .expect("Failed to build batch")
+            })
+            .collect();
+        let new_id = batches[0].batch_header().to_string();
+        let pending_id = batches[1].batch_header().to_string();
+        let dead_id = batches[2].batch_header().to_string();
+
+        store.add_batches(batches).expect("Failed to add batches");
 
-        let transaction_id = pair.header_signature().to_string();
+        store
+            .update_batch_status(
+                &pending_id,
+                "TEST",
+                Some(BatchStatus::Pending),
+                Vec::new(),
+                None,
+            )
+            .expect("Failed to update batch status");
+        store
+            .dead_letter_batch(&dead_id, "TEST", "retries exhausted")
+            .expect("Failed to dead-letter batch");
 
-        let batch_1 = get_transact_batch(&*signer, vec![pair]);
+        let status_of = |id: &str| {
+            store
+                .get_batch_status(id, "TEST")
+                .expect("Failed to get batch status")
+        };
+        let event_count = |id: &str| {
+            store
+                .list_batch_status_events(id, "TEST")
+                .expect("Failed to list status events")
+                .len()
+        };
 
-        let tracking_batch = get_tracking_batch(batch_1.clone(), false)
-            .build()
-            .expect("Failed to build batch");
+        match store.transition_batches(
+            vec![
+                (new_id.clone(), BatchStatus::Pending),
+                (pending_id.clone(), BatchStatus::Delayed),
+                (dead_id.clone(), BatchStatus::Pending),
+            ],
+            "TEST",
+        ) {
+            Err(BatchTrackingStoreError::InvalidStatusTransition { batch_id, from, to }) => {
+                assert_eq!(batch_id, dead_id);
+                assert_eq!(from, Some(BatchStatusName::DeadLettered));
+                assert_eq!(to, BatchStatusName::Pending);
+            }
+            res => panic!("Expected InvalidStatusTransition, got {:?}", res),
+        }
 
-        let id = tracking_batch.batch_header();
+        assert_eq!(status_of(&new_id), None);
+        assert_eq!(status_of(&pending_id), Some(BatchStatus::Pending));
+        assert_eq!(status_of(&dead_id), Some(BatchStatus::DeadLettered));
+        assert_eq!(event_count(&new_id), 0);
+        assert_eq!(event_count(&pending_id), 1);
+
+        // A later transition of the same batch is checked against the status
+        // set by the earlier one
+        assert!(matches!(
+            store.transition_batches(
+                vec![
+                    (new_id.clone(), BatchStatus::DeadLettered),
+                    (new_id.clone(), BatchStatus::Pending),
+                ],
+                "TEST",
+            ),
+            Err(BatchTrackingStoreError::InvalidStatusTransition { .. })
+        ));
+        assert_eq!(status_of(&new_id), None);
 
         store
-            .add_batches(vec![tracking_batch.clone()])
-            .expect("Failed to add batch");
+            .transition_batches(
+                vec![
+                    (new_id.clone(), BatchStatus::Pending),
+                    (pending_id.clone(), BatchStatus::Delayed),
+                ],
+                "TEST",
+            )
+            .expect("Failed to transition batches");
 
-        let batch_result = store
-            .get_batch(&id, "TEST")
-            .expect("Failed to get batch")
-            .unwrap();
-        let batch_result_timestamp = batch_result.created_at();
+        assert_eq!(status_of(&new_id), Some(BatchStatus::Pending));
+        assert_eq!(status_of(&pending_id), Some(BatchStatus::Delayed));
+        assert_eq!(event_count(&new_id), 1);
+        assert_eq!(event_count(&pending_id), 2);
+    }
 
-        let submission_error = SubmissionErrorBuilder::default()
-            .with_error_type("test".to_string())
-            .with_error_message("test message".to_string())
+    #[test]
+    /// Test that a service backed up from one store and restored into a fresh
+    /// store has the same rows, that other services are not backed up, and
+    /// that a backup can't be restored over the rows it contains
+    fn test_backup_and_restore_service() {
+        let store = DieselBatchTrackingStore::new(create_connection_pool_and_migrate());
+
+        let signer = new_signer();
+
+        let txn = get_transact_transaction(&*signer, NONCE);
+        let transaction_id = txn.header_signature().to_string();
+        let invalid_batch = get_tracking_batch(get_transact_batch(&*signer, vec![txn]), false)
+            .with_metadata(serde_json::json!({"tenant": "acme"}))
             .build()
-            .expect("Failed to build error");
+            .expect("Failed to build batch");
+        let invalid_id = invalid_batch.batch_header().to_string();
+        let delayed_batch = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE2)]),
+            false,
+        )
+        .build()
+        .expect("Failed to build batch");
+        let delayed_id = delayed_batch.batch_header().to_string();
+        let other_batch = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, "k9fzzd")]),
+            false,
+        )
+        .with_service_id("OTHER".to_string())
+        .build()
+        .expect("Failed to build batch");
+        let other_id = other_batch.batch_header().to_string();
 
-        let receipt_1 = TransactionReceiptBuilder::default()
-            .with_transaction_id(transaction_id.to_string())
+        store
+            .add_batches(vec![invalid_batch, delayed_batch, other_batch])
+            .expect("Failed to add batches");
+
+        let receipt = TransactionReceiptBuilder::default()
+            .with_transaction_id(transaction_id.clone())
             .with_result_valid(false)
             .with_error_message("test".to_string())
             .with_error_data(BYTES2.to_vec())
-            .with_serialized_receipt(
-                std::str::from_utf8(&BYTES2)
-                    .expect("Failed to build string")
-                    .to_string(),
-            )
+            .with_serialized_receipt("receipt".to_string())
             .build()
             .expect("Failed to build receipt");
-
         let invalid_transactions = vec![InvalidTransactionBuilder::default()
-            .with_transaction_id(transaction_id.to_string())
+            .with_transaction_id(transaction_id)
             .with_error_message("test".to_string())
             .with_error_data(BYTES2.to_vec())
             .build()
-            .expect("Failed to build transaction")];
-
+            .expect("Failed to build invalid transaction")];
+        let submission_error = SubmissionErrorBuilder::default()
+            .with_error_type("test".to_string())
+            .with_error_message("test message".to_string())
+            .build()
+            .expect("Failed to build error");
         store
             .update_batch_status(
-                &id,
+                &invalid_id,
                 "TEST",
-                Some(BatchStatus::Invalid(invalid_transactions.to_vec())),
-                vec![receipt_1],
-                Some(submission_error.clone()),
+                Some(BatchStatus::Invalid(invalid_transactions)),
+                vec![receipt],
+                Some(submission_error),
             )
-            .expect("Failed to update batch");
+            .expect("Failed to update batch status");
+        for status in [BatchStatus::Pending, BatchStatus::Delayed] {
+            store
+                .update_batch_status(&delayed_id, "TEST", Some(status), Vec::new(), None)
+                .expect("Failed to update batch status");
+        }
+        store
+            .tombstone_batches(&["removed"], "TEST")
+            .expect("Failed to tombstone batch");
 
-        let expected = get_tracking_batch(batch_1.clone(), true)
-            .with_created_at(batch_result_timestamp)
-            .with_batch_status(BatchStatus::Invalid(invalid_transactions))
-            .with_submission_error(submission_error)
-            .build()
-            .expect("Failed to build batch");
+        let mut backup = Vec::new();
+        store
+            .backup_service("TEST", &mut backup)
+            .expect("Failed to back up service");
+
+        let restored = DieselBatchTrackingStore::new(create_connection_pool_and_migrate());
+        restored
+            .restore_service(&backup[..])
+            .expect("Failed to restore service");
+
+        for id in &[&invalid_id, &delayed_id] {
+            assert_eq!(
+                restored.get_batch(id, "TEST").expect("Failed to get batch"),
+                store.get_batch(id, "TEST").expect("Failed to get batch")
+            );
+            assert_eq!(
+                restored
+                    .list_batch_status_events(id, "TEST")
+                    .expect("Failed to list status events"),
+                store
+                    .list_batch_status_events(id, "TEST")
+                    .expect("Failed to list status events")
+            );
+        }
+        assert_eq!(
+            restored
+                .get_batch(&other_id, "OTHER")
+                .expect("Failed to get batch"),
+            None
+        );
+        assert_eq!(
+            restored
+                .tombstone_batches(&["removed"], "TEST")
+                .expect("Failed to tombstone batch"),
+            0
+        );
 
+        // Backing up the restored service writes the same rows
+        let mut restored_backup = Vec::new();
+        restored
+            .backup_service("TEST", &mut restored_backup)
+            .expect("Failed to back up service");
         assert_eq!(
-            store.get_failed_batches().expect("Failed to get batch"),
-            TrackingBatchList {
-                batches: vec![expected]
-            }
+            String::from_utf8(restored_backup).expect("Backup is not UTF-8"),
+            String::from_utf8(backup.clone()).expect("Backup is not UTF-8")
         );
 
-        store
-            .update_batch_status(&id, "TEST", Some(BatchStatus::Pending), Vec::new(), None)
-            .expect("Failed to update batch");
+        assert!(matches!(
+            restored.restore_service(&backup[..]),
+            Err(BatchTrackingStoreError::ConstraintViolationError(_))
+        ));
+        assert!(matches!(
+            restored.restore_service(&b"{\"record\":\"batch_tombstone\"}\n"[..]),
+            Err(BatchTrackingStoreError::InvalidArgumentError(_))
+        ));
+    }
 
-        assert_eq!(
-            store.get_failed_batches().expect("Failed to get batch"),
-            TrackingBatchList {
-                batches: Vec::new()
+    #[test]
+    /// Test that looking up a batch by a data change ID that matches more
+    /// than one batch returns an ambiguity error, for reads and writes alike,
+    /// while other lookups still work
+    fn test_get_batch_ambiguous_dcid() {
+        fn assert_ambiguous<T: std::fmt::Debug>(res: Result<T, BatchTrackingStoreError>) {
+            match res {
+                Err(BatchTrackingStoreError::AmbiguousDataChangeId { dcid, count }) => {
+                    assert_eq!(dcid, "dcid:one");
+                    assert_eq!(count, 2);
+                }
+                res => panic!("Expected AmbiguousDataChangeId, got {:?}", res),
             }
+        }
+
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool.clone());
+
+        let signer = new_signer();
+
+        // The data change ID's unique constraint is dropped so that the
+        // duplicate can be written directly
+        for statement in &[
+            "CREATE TABLE batches_copy AS SELECT * FROM batches",
+            "DROP TABLE batches",
+            "ALTER TABLE batches_copy RENAME TO batches",
+        ] {
+            diesel::sql_query(*statement)
+                .execute(&*pool.get().expect("Failed to get connection"))
+                .expect("Failed to rebuild batches table");
+        }
+
+        let batches: Vec<TrackingBatch> = [("n1", "dcid:one"), ("n2", "dcid:two")]
+            .iter()
+            .map(|(nonce, dcid)| {
+                get_tracking_batch(
+                    get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, nonce)]),
+                    false,
+                )
+                .with_data_change_id(dcid.to_string())
+                .build()
+                .expect("Failed to build batch")
+            })
+            .collect();
+        let batch_ids: Vec<String> = batches
+            .iter()
+            .map(|batch| batch.batch_header().to_string())
+            .collect();
+
+        store.add_batches(batches).expect("Failed to add batches");
+
+        assert!(store
+            .get_batch("dcid:one", "TEST")
+            .expect("Failed to get batch")
+            .is_some());
+
+        diesel::update(schema::batches::table.filter(schema::batches::batch_id.eq(&batch_ids[1])))
+            .set(schema::batches::data_change_id.eq("dcid:one"))
+            .execute(&*pool.get().expect("Failed to get connection"))
+            .expect("Failed to update data change ID");
+
+        assert_ambiguous(store.get_batch("dcid:one", "TEST"));
+        assert_ambiguous(store.get_latest_submission_error("dcid:one", "TEST"));
+        assert_ambiguous(store.get_batches_by_data_change_ids(&["dcid:one"], "TEST"));
+        assert_ambiguous(store.record_submission_attempt("dcid:one", "TEST"));
+        assert_ambiguous(store.set_batch_notes("dcid:one", "TEST", Some("notes")));
+        assert_ambiguous(
+            store.transition_batches(vec![("dcid:one".to_string(), BatchStatus::Pending)], "TEST"),
         );
+
+        // Nothing was written through the ambiguous data change ID
+        let notes: Vec<Option<String>> = schema::batches::table
+            .select(schema::batches::notes)
+            .load(&*pool.get().expect("Failed to get connection"))
+            .expect("Failed to load notes");
+        assert!(notes.iter().all(Option::is_none));
+
+        for batch_id in &batch_ids {
+            assert!(store
+                .get_batch(batch_id, "TEST")
+                .expect("Failed to get batch")
+                .is_some());
+        }
+        assert!(store
+            .get_batch("dcid:one", "OTHER")
+            .expect("Failed to get batch")
+            .is_none());
     }
 
     #[test]
-    fn test_clean_stale_records() {
+    /// Test that senders blocked on a full channel all return once the
+    /// receiver is dropped, when more than one is sending at once
+    fn test_watch_block_receiver_dropped_concurrent_senders() {
+        let watchers = Arc::new(UnsubmittedWatchers::new(1, WatchBackpressure::Block));
+        let receiver = watchers.subscribe("TEST");
+
+        let signer = new_signer();
+
+        let batch = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE)]),
+            false,
+        )
+        .build()
+        .expect("Failed to build batch");
+
+        // Fill the channel so that the next sends block
+        watchers.send(vec![batch.clone()]);
+
+        let (done_tx, done_rx) = std::sync::mpsc::channel();
+        let senders: Vec<_> = (0..2)
+            .map(|_| {
+                let watchers = watchers.clone();
+                let batch = batch.clone();
+                let done_tx = done_tx.clone();
+                std::thread::spawn(move || {
+                    watchers.send(vec![batch]);
+                    done_tx.send(()).expect("Failed to signal send");
+                })
+            })
+            .collect();
+
+        std::thread::sleep(Duration::from_millis(200));
+        assert!(done_rx.try_recv().is_err());
+
+        drop(receiver);
+
+        for _ in 0..2 {
+            done_rx
+                .recv_timeout(Duration::from_secs(5))
+                .expect("Sender did not return after the receiver was dropped");
+        }
+        for sender in senders {
+            sender.join().expect("Sender panicked");
+        }
+    }
+
+    /// Verify that get_recent_failures returns the addresses of the failed
+    /// batches' transactions while making no more than three queries
+    #[test]
+    fn test_get_recent_failures_query_count() {
         let pool = create_connection_pool_and_migrate();
 
-        let store = DieselBatchTrackingStore::new(pool);
+        let store = DieselBatchTrackingStore::new(pool.clone());
 
         let signer = new_signer();
 
-        let pair = get_transact_transaction(&*signer, NONCE);
+        let mut tracking_batches = Vec::new();
+        for nonce in &[NONCE, NONCE2] {
+            let batch =
+                get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, nonce)]);
+            tracking_batches.push(
+                get_tracking_batch(batch, false)
+                    .build()
+                    .expect("Failed to build batch"),
+            );
+        }
 
-        let batch_1 = get_transact_batch(&*signer, vec![pair]);
+        store
+            .add_batches(tracking_batches.clone())
+            .expect("Failed to add batches");
 
-        let tracking_batch = get_tracking_batch(batch_1.clone(), false)
-            .build()
-            .expect("Failed to build batch");
+        for batch in &tracking_batches {
+            store
+                .update_batch_status(
+                    batch.batch_header(),
+                    "TEST",
+                    Some(BatchStatus::Unknown),
+                    Vec::new(),
+                    None,
+                )
+                .expect("Failed to update batch");
+        }
 
-        let id = tracking_batch.batch_header();
+        let conn = pool.get().expect("Failed to get connection");
+        let operations = BatchTrackingStoreOperations::new(&*conn);
 
-        store
-            .add_batches(vec![tracking_batch.clone()])
-            .expect("Failed to add batch");
+        let failures = operations
+            .get_recent_failures("TEST", 10)
+            .expect("Failed to get recent failures");
 
-        let batch_result = store
-            .get_batch(&id, "TEST")
-            .expect("Failed to get batch")
-            .unwrap();
-        let batch_timestamp = batch_result.created_at();
+        assert_eq!(failures.len(), 2);
+        for failure in &failures {
+            let expected = tracking_batches
+                .iter()
+                .find(|b| b.batch_header() == failure.batch().batch_header())
+                .expect("Unexpected batch");
+            assert_eq!(failure.batch().transactions(), expected.transactions());
+            assert!(!failure.batch().transactions()[0].inputs().is_empty());
+        }
+
+        assert!(operations.query_count() <= 3);
+    }
+
+    #[test]
+    /// Test that stores sharing watchers send the batches added through any of
+    /// them to every subscriber
+    fn test_watch_unsubmitted_shared_watchers() {
+        let pool = create_connection_pool_and_migrate();
+
+        let watchers = Arc::new(UnsubmittedWatchers::default());
+        let store =
+            DieselBatchTrackingStore::new(pool.clone()).with_unsubmitted_watchers(watchers.clone());
+        let other_store = DieselBatchTrackingStore::new(pool).with_unsubmitted_watchers(watchers);
+
+        let receiver = store.watch_unsubmitted("TEST");
+
+        let signer = new_signer();
+
+        let batch = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE)]),
+            false,
+        )
+        .build()
+        .expect("Failed to build batch");
+
+        other_store
+            .add_batches(vec![batch.clone()])
+            .expect("Failed to add batches");
+
+        let received = receiver
+            .recv_timeout(Duration::from_secs(1))
+            .expect("Failed to receive batch");
+        assert_eq!(received.batch_header(), batch.batch_header());
+    }
+
+    #[test]
+    /// Test that a receiver gets the batches already sent to it, and then
+    /// `None` rather than waiting forever, once the store is dropped
+    fn test_watch_unsubmitted_store_dropped() {
+        let pool = create_connection_pool_and_migrate();
+
+        let store = DieselBatchTrackingStore::new(pool);
+        let receiver = store.watch_unsubmitted("TEST");
+
+        let signer = new_signer();
+
+        let batch = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE)]),
+            false,
+        )
+        .build()
+        .expect("Failed to build batch");
 
         store
-            .clean_stale_records(batch_timestamp + 1)
-            .expect("Failed to clean records");
+            .add_batches(vec![batch.clone()])
+            .expect("Failed to add batches");
 
-        assert_eq!(
-            store.get_batch(&id, "TEST").expect("Failed to get batch"),
-            None
+        drop(store);
+
+        let (done_tx, done_rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let received = receiver
+                .recv()
+                .map(|batch| batch.batch_header().to_string());
+            let after_drop = receiver.recv().is_none();
+            done_tx
+                .send((received, after_drop))
+                .expect("Failed to signal receive");
+        });
+
+        let (received, after_drop) = done_rx
+            .recv_timeout(Duration::from_secs(5))
+            .expect("Receiver did not return after the store was dropped");
+        assert_eq!(received.as_deref(), Some(batch.batch_header()));
+        assert!(after_drop);
+    }
+
+    #[test]
+    /// Test that a connection store only sends the batches added outside of a
+    /// transaction opened by the caller
+    fn test_connection_store_watch_unsubmitted_in_transaction() {
+        let pool = create_connection_pool_and_migrate();
+        let conn = pool.get().expect("Failed to get connection");
+
+        let store = DieselConnectionBatchTrackingStore::new(&*conn);
+        let receiver = store.watch_unsubmitted("TEST");
+
+        let signer = new_signer();
+
+        let in_transaction = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE)]),
+            false,
+        )
+        .build()
+        .expect("Failed to build batch");
+        let outside_transaction = get_tracking_batch(
+            get_transact_batch(&*signer, vec![get_transact_transaction(&*signer, NONCE2)]),
+            false,
         )
+        .build()
+        .expect("Failed to build batch");
+
+        conn.transaction::<_, BatchTrackingStoreError, _>(|| {
+            store.add_batches(vec![in_transaction])
+        })
+        .expect("Failed to add batches");
+        assert!(receiver.try_recv().is_none());
+
+        store
+            .add_batches(vec![outside_transaction.clone()])
+            .expect("Failed to add batches");
+        let received = receiver
+            .recv_timeout(Duration::from_secs(1))
+            .expect("Failed to receive batch");
+        assert_eq!(received.batch_header(), outside_transaction.batch_header());
     }
 
     /// Creates a connection pool for an in-memory SQLite database with only a single connection
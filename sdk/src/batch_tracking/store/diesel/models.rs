@@ -13,20 +13,81 @@
 // limitations under the License.
 
 use core::convert::TryFrom;
+use std::io::Write;
+
+#[cfg(feature = "postgres")]
+use diesel::pg::Pg;
+#[cfg(feature = "postgres")]
+use diesel::sql_types::Jsonb;
+#[cfg(feature = "sqlite")]
+use diesel::sql_types::Text;
+#[cfg(feature = "sqlite")]
+use diesel::{backend::Backend, sqlite::Sqlite};
+use diesel::{
+    deserialize::{self, FromSql},
+    serialize::{self, IsNull, Output, ToSql},
+};
 use regex::Regex;
+use serde::{Deserialize, Serialize};
+use transact::protocol::transaction::TransactionHeader;
+use transact::protos::FromBytes;
 
 use crate::batch_tracking::store::diesel::schema::*;
 use crate::batch_tracking::store::NON_SPLINTER_SERVICE_ID_DEFAULT;
 use crate::error::InternalError;
 
 use super::{
-    BatchStatus, InvalidTransaction, SubmissionError, TrackingBatch, TrackingBatchList,
-    TrackingTransaction, TransactionReceipt, ValidTransaction,
+    BatchStatus, BatchStatusEvent, BatchStatusName, BatchSubmissionInfo, InvalidTransaction,
+    OutboxEvent, SubmissionError, TrackingBatch, TrackingBatchList, TrackingTransaction,
+    TransactionReceipt, ValidTransaction,
 };
 use crate::batch_tracking::store::error::BatchTrackingStoreError;
 
 pub const DCID_FORMAT: &str = "^dcid:[\\w\\-\\+=/~!@#\\$%\\^&\\*{}|\\[\\]<>\\?]+$";
 
+/// A JSON object, stored as `JSONB` in postgres and as text in sqlite
+#[derive(SqlType, QueryId)]
+#[postgres(oid = "3802", array_oid = "3807")]
+#[sqlite_type = "Text"]
+pub struct JsonObject;
+
+#[derive(AsExpression, FromSqlRow, PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
+#[sql_type = "JsonObject"]
+pub struct JsonObjectModel(pub serde_json::Value);
+
+#[cfg(feature = "postgres")]
+impl ToSql<JsonObject, Pg> for JsonObjectModel {
+    fn to_sql<W: Write>(&self, out: &mut Output<W, Pg>) -> serialize::Result {
+        ToSql::<Jsonb, Pg>::to_sql(&self.0, out)
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl FromSql<JsonObject, Pg> for JsonObjectModel {
+    fn from_sql(bytes: Option<&[u8]>) -> deserialize::Result<Self> {
+        FromSql::<Jsonb, Pg>::from_sql(bytes).map(JsonObjectModel)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl ToSql<JsonObject, Sqlite> for JsonObjectModel {
+    fn to_sql<W: Write>(&self, out: &mut Output<W, Sqlite>) -> serialize::Result {
+        serde_json::to_writer(out, &self.0)
+            .map(|_| IsNull::No)
+            .map_err(Into::into)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl FromSql<JsonObject, Sqlite> for JsonObjectModel {
+    fn from_sql(value: Option<&<Sqlite as Backend>::RawValue>) -> deserialize::Result<Self> {
+        let text = <String as FromSql<Text, Sqlite>>::from_sql(value)?;
+        serde_json::from_str(&text)
+            .map(JsonObjectModel)
+            .map_err(Into::into)
+    }
+}
+
 #[derive(Identifiable, Insertable, Queryable, PartialEq, Eq, Debug, Clone)]
 #[table_name = "batches"]
 #[primary_key(service_id, batch_id)]
@@ -38,9 +99,17 @@ pub struct NewBatchModel {
     pub trace: bool,
     pub serialized_batch: Vec<u8>,
     pub submitted: bool,
+    pub created_at: i64,
+    pub notes: Option<String>,
+    pub byte_size: i64,
+    pub batch_kind: Option<String>,
+    pub metadata: Option<JsonObjectModel>,
+    pub origin: Option<String>,
 }
 
-#[derive(Identifiable, Insertable, Queryable, PartialEq, Eq, Debug, Clone)]
+#[derive(
+    Identifiable, Insertable, Queryable, PartialEq, Eq, Debug, Clone, Serialize, Deserialize,
+)]
 #[table_name = "batches"]
 #[primary_key(service_id, batch_id)]
 pub struct BatchModel {
@@ -52,9 +121,31 @@ pub struct BatchModel {
     pub serialized_batch: Vec<u8>,
     pub submitted: bool,
     pub created_at: i64,
+    pub submission_latency_ms: Option<i64>,
+    pub notes: Option<String>,
+    pub submission_round: Option<i64>,
+    pub byte_size: i64,
+    pub network_id: Option<String>,
+    pub alias: Option<String>,
+    pub batch_kind: Option<String>,
+    pub metadata: Option<JsonObjectModel>,
+    pub dead_letter_reason: Option<String>,
+    pub origin: Option<String>,
+    pub claimed_by: Option<String>,
+    pub claim_expires_at: Option<i64>,
 }
 
-#[derive(Identifiable, Insertable, Queryable, PartialEq, Eq, Debug, QueryableByName)]
+#[derive(
+    Identifiable,
+    Insertable,
+    Queryable,
+    PartialEq,
+    Eq,
+    Debug,
+    QueryableByName,
+    Serialize,
+    Deserialize,
+)]
 #[table_name = "transactions"]
 #[primary_key(service_id, transaction_id)]
 pub struct TransactionModel {
@@ -65,10 +156,56 @@ pub struct TransactionModel {
     pub family_name: String,
     pub family_version: String,
     pub signer_public_key: String,
+    pub serialized_header: Option<Vec<u8>>,
+    pub payload_encrypted: bool,
+}
+
+#[derive(
+    Identifiable,
+    Insertable,
+    Queryable,
+    PartialEq,
+    Eq,
+    Debug,
+    Clone,
+    QueryableByName,
+    Serialize,
+    Deserialize,
+)]
+#[table_name = "transaction_addresses"]
+#[primary_key(service_id, transaction_id, is_input, position)]
+pub struct TransactionAddressModel {
+    pub service_id: String,
+    pub transaction_id: String,
+    pub is_input: bool,
+    pub position: i32,
+    pub address: String,
+}
+
+#[derive(
+    Identifiable, Insertable, Queryable, PartialEq, Eq, Debug, Clone, Serialize, Deserialize,
+)]
+#[table_name = "transaction_dependencies"]
+#[primary_key(service_id, transaction_id, position)]
+pub struct TransactionDependencyModel {
+    pub service_id: String,
+    pub transaction_id: String,
+    pub position: i32,
+    pub dependency_id: String,
 }
 
 #[derive(
-    Identifiable, Insertable, Queryable, PartialEq, Eq, Debug, AsChangeset, Clone, QueryableByName,
+    Identifiable,
+    Insertable,
+    Queryable,
+    PartialEq,
+    Eq,
+    Debug,
+    AsChangeset,
+    Clone,
+    QueryableByName,
+    Serialize,
+    Deserialize,
 )]
 #[table_name = "transaction_receipts"]
 #[primary_key(service_id, transaction_id)]
@@ -79,7 +216,7 @@ pub struct TransactionReceiptModel {
     pub result_valid: bool,
     pub error_message: Option<String>,
     pub error_data: Option<Vec<u8>>,
-    pub serialized_receipt: Vec<u8>,
+    pub serialized_receipt: Option<Vec<u8>>,
     pub external_status: Option<String>,
     pub external_error_message: Option<String>,
 }
@@ -92,7 +229,9 @@ pub struct NewBatchStatusModel {
     pub dlt_status: String,
 }
 
-#[derive(Identifiable, Insertable, Queryable, PartialEq, Eq, Debug, Clone)]
+#[derive(
+    Identifiable, Insertable, Queryable, PartialEq, Eq, Debug, Clone, Serialize, Deserialize,
+)]
 #[table_name = "batch_statuses"]
 #[primary_key(service_id, batch_id)]
 pub struct BatchStatusModel {
@@ -113,7 +252,17 @@ pub struct NewSubmissionModel {
     pub error_message: Option<String>,
 }
 
-#[derive(Identifiable, Insertable, Queryable, PartialEq, Eq, Debug, QueryableByName)]
+#[derive(
+    Identifiable,
+    Insertable,
+    Queryable,
+    PartialEq,
+    Eq,
+    Debug,
+    QueryableByName,
+    Serialize,
+    Deserialize,
+)]
 #[table_name = "submissions"]
 #[primary_key(service_id, batch_id)]
 pub struct SubmissionModel {
@@ -125,6 +274,62 @@ pub struct SubmissionModel {
     pub error_message: Option<String>,
     pub created_at: i64,
     pub updated_at: i64,
+    pub submitter_response: Option<Vec<u8>>,
+    pub submit_headers: Option<JsonObjectModel>,
+    pub submit_url: Option<String>,
+    /// Backups written before attempts were recorded restore with none
+    #[serde(default)]
+    pub attempts: i64,
+}
+
+#[derive(Insertable, PartialEq, Eq, Debug, Queryable, Serialize, Deserialize)]
+#[table_name = "batch_tombstones"]
+pub struct NewBatchTombstoneModel {
+    pub service_id: String,
+    pub batch_id: String,
+    pub created_at: i64,
+}
+
+#[derive(Insertable, PartialEq, Eq, Debug)]
+#[table_name = "batch_status_events"]
+pub struct NewBatchStatusEventModel {
+    pub service_id: String,
+    pub batch_id: String,
+    pub dlt_status: String,
+    pub correlation_id: Option<String>,
+    pub created_at: i64,
+}
+
+#[derive(Identifiable, Queryable, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[table_name = "batch_status_events"]
+pub struct BatchStatusEventModel {
+    pub id: i64,
+    pub service_id: String,
+    pub batch_id: String,
+    pub dlt_status: String,
+    pub correlation_id: Option<String>,
+    pub created_at: i64,
+}
+
+#[derive(Insertable, PartialEq, Eq, Debug)]
+#[table_name = "outbox"]
+pub struct NewOutboxModel {
+    pub service_id: String,
+    pub batch_id: String,
+    pub dlt_status: String,
+    pub correlation_id: Option<String>,
+    pub created_at: i64,
+}
+
+#[derive(Identifiable, Queryable, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[table_name = "outbox"]
+pub struct OutboxModel {
+    pub id: i64,
+    pub service_id: String,
+    pub batch_id: String,
+    pub dlt_status: String,
+    pub correlation_id: Option<String>,
+    pub created_at: i64,
 }
 
 impl
@@ -157,6 +362,16 @@ impl
             serialized_batch: batch.serialized_batch.to_vec(),
             submitted: batch.submitted,
             created_at: batch.created_at,
+            submission_latency_ms: batch.submission_latency_ms,
+            notes: batch.notes,
+            submission_round: batch.submission_round,
+            byte_size: batch.byte_size,
+            network_id: batch.network_id,
+            alias: batch.alias,
+            batch_kind: batch.batch_kind,
+            origin: batch.origin,
+            metadata: batch.metadata.map(|metadata| metadata.0),
+            dead_letter_reason: batch.dead_letter_reason,
             transactions,
             batch_status,
             submission_error,
@@ -164,8 +379,20 @@ impl
     }
 }
 
-impl From<TransactionModel> for TrackingTransaction {
-    fn from(transaction: TransactionModel) -> Self {
+impl From<(&TransactionModel, &[TransactionAddressModel])> for TrackingTransaction {
+    fn from((transaction, addresses): (&TransactionModel, &[TransactionAddressModel])) -> Self {
+        let mut txn_addresses: Vec<&TransactionAddressModel> = addresses
+            .iter()
+            .filter(|a| {
+                a.service_id == transaction.service_id
+                    && a.transaction_id == transaction.transaction_id
+            })
+            .collect();
+        txn_addresses.sort_by_key(|a| a.position);
+
+        let (inputs, outputs): (Vec<&TransactionAddressModel>, Vec<&TransactionAddressModel>) =
+            txn_addresses.into_iter().partition(|a| a.is_input);
+
         Self {
             family_name: transaction.family_name.to_string(),
             family_version: transaction.family_version.to_string(),
@@ -173,21 +400,25 @@ impl From<TransactionModel> for TrackingTransaction {
             payload: transaction.payload.to_vec(),
             signer_public_key: transaction.signer_public_key.to_string(),
             service_id: transaction.service_id.clone(),
+            serialized_header: transaction.serialized_header.clone(),
+            inputs: inputs.iter().map(|a| a.address.to_string()).collect(),
+            outputs: outputs.iter().map(|a| a.address.to_string()).collect(),
+            dependencies: transaction_dependencies(transaction),
         }
     }
 }
 
-impl From<&TransactionModel> for TrackingTransaction {
-    fn from(transaction: &TransactionModel) -> Self {
-        Self {
-            family_name: transaction.family_name.to_string(),
-            family_version: transaction.family_version.to_string(),
-            transaction_header: transaction.transaction_id.to_string(),
-            payload: transaction.payload.to_vec(),
-            signer_public_key: transaction.signer_public_key.to_string(),
-            service_id: transaction.service_id.clone(),
-        }
-    }
+/// Returns the dependencies listed in a stored transaction's header
+///
+/// Transactions stored before their header was kept are returned with no
+/// dependencies.
+fn transaction_dependencies(transaction: &TransactionModel) -> Vec<String> {
+    transaction
+        .serialized_header
+        .as_deref()
+        .and_then(|header| TransactionHeader::from_bytes(header).ok())
+        .map(|header| header.dependencies().to_vec())
+        .unwrap_or_default()
 }
 
 impl From<TransactionReceiptModel> for TransactionReceipt {
@@ -197,7 +428,11 @@ impl From<TransactionReceiptModel> for TransactionReceipt {
             result_valid: receipt.result_valid,
             error_message: receipt.error_message,
             error_data: receipt.error_data,
-            serialized_receipt: format!("{:?}", receipt.serialized_receipt),
+            serialized_receipt: receipt
+                .serialized_receipt
+                .as_ref()
+                .map(|serialized_receipt| format!("{:?}", serialized_receipt))
+                .unwrap_or_default(),
             external_status: receipt.external_status,
             external_error_message: receipt.external_error_message,
         }
@@ -211,7 +446,11 @@ impl From<&TransactionReceiptModel> for TransactionReceipt {
             result_valid: receipt.result_valid,
             error_message: receipt.error_message.clone(),
             error_data: receipt.error_data.clone(),
-            serialized_receipt: format!("{:?}", receipt.serialized_receipt),
+            serialized_receipt: receipt
+                .serialized_receipt
+                .as_ref()
+                .map(|serialized_receipt| format!("{:?}", serialized_receipt))
+                .unwrap_or_default(),
             external_status: receipt.external_status.clone(),
             external_error_message: receipt.external_error_message.clone(),
         }
@@ -226,7 +465,7 @@ impl From<(&TransactionReceipt, &str)> for TransactionReceiptModel {
             result_valid: receipt.result_valid(),
             error_message: receipt.error_message().map(String::from),
             error_data: receipt.error_data().map(Vec::from),
-            serialized_receipt: receipt.serialized_receipt().as_bytes().to_vec(),
+            serialized_receipt: Some(receipt.serialized_receipt().as_bytes().to_vec()),
             external_status: receipt.external_status().map(String::from),
             external_error_message: receipt.external_error_message().map(String::from),
         }
@@ -253,6 +492,7 @@ impl
             "Unknown" => Ok(BatchStatus::Unknown),
             "Pending" => Ok(BatchStatus::Pending),
             "Delayed" => Ok(BatchStatus::Delayed),
+            "DeadLettered" => Ok(BatchStatus::DeadLettered),
             "Invalid" => {
                 if invalid_transactions.is_empty() {
                     return Err(BatchTrackingStoreError::InternalError(
@@ -426,11 +666,91 @@ impl TryFrom<&SubmissionModel> for SubmissionError {
     }
 }
 
+impl TryFrom<SubmissionModel> for BatchSubmissionInfo {
+    type Error = BatchTrackingStoreError;
+
+    fn try_from(submission: SubmissionModel) -> Result<Self, Self::Error> {
+        let submission_error =
+            if submission.error_type.is_some() && submission.error_message.is_some() {
+                Some(SubmissionError::try_from(&submission)?)
+            } else {
+                None
+            };
+
+        Ok(Self {
+            last_checked: submission.last_checked,
+            times_checked: submission.times_checked,
+            submission_error,
+            submitter_response: submission.submitter_response,
+            submit_headers: submission
+                .submit_headers
+                .map(|submit_headers| submit_headers.0),
+            submit_url: submission.submit_url,
+        })
+    }
+}
+
+impl From<BatchStatusEventModel> for NewBatchStatusEventModel {
+    fn from(event: BatchStatusEventModel) -> Self {
+        NewBatchStatusEventModel {
+            service_id: event.service_id,
+            batch_id: event.batch_id,
+            dlt_status: event.dlt_status,
+            correlation_id: event.correlation_id,
+            created_at: event.created_at,
+        }
+    }
+}
+
+impl From<OutboxModel> for NewOutboxModel {
+    fn from(event: OutboxModel) -> Self {
+        NewOutboxModel {
+            service_id: event.service_id,
+            batch_id: event.batch_id,
+            dlt_status: event.dlt_status,
+            correlation_id: event.correlation_id,
+            created_at: event.created_at,
+        }
+    }
+}
+
+impl TryFrom<BatchStatusEventModel> for BatchStatusEvent {
+    type Error = BatchTrackingStoreError;
+
+    fn try_from(event: BatchStatusEventModel) -> Result<Self, Self::Error> {
+        Ok(Self {
+            service_id: event.service_id,
+            batch_id: event.batch_id,
+            status: BatchStatusName::try_from_string(&event.dlt_status)?,
+            correlation_id: event.correlation_id,
+            created_at: event.created_at,
+        })
+    }
+}
+
+impl TryFrom<OutboxModel> for OutboxEvent {
+    type Error = BatchTrackingStoreError;
+
+    fn try_from(model: OutboxModel) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: model.id,
+            event: BatchStatusEvent {
+                service_id: model.service_id,
+                batch_id: model.batch_id,
+                status: BatchStatusName::try_from_string(&model.dlt_status)?,
+                correlation_id: model.correlation_id,
+                created_at: model.created_at,
+            },
+        })
+    }
+}
+
 impl
     TryFrom<(
         Vec<BatchModel>,
         Vec<BatchStatusModel>,
         Vec<TransactionModel>,
+        Vec<TransactionAddressModel>,
         Vec<TransactionReceiptModel>,
         Vec<SubmissionModel>,
     )> for TrackingBatchList
@@ -438,10 +758,11 @@ impl
     type Error = BatchTrackingStoreError;
 
     fn try_from(
-        (batches, statuses, transactions, receipts, submissions): (
+        (batches, statuses, transactions, addresses, receipts, submissions): (
             Vec<BatchModel>,
             Vec<BatchStatusModel>,
             Vec<TransactionModel>,
+            Vec<TransactionAddressModel>,
             Vec<TransactionReceiptModel>,
             Vec<SubmissionModel>,
         ),
@@ -502,7 +823,9 @@ impl
 
             tbs.push(TrackingBatch::from((
                 batch,
-                txns.iter().map(|t| TrackingTransaction::from(*t)).collect(),
+                txns.iter()
+                    .map(|t| TrackingTransaction::from((*t, addresses.as_slice())))
+                    .collect(),
                 status,
                 sub_err,
             )))
@@ -512,7 +835,7 @@ impl
     }
 }
 
-pub fn make_new_batch_models(batches: &[TrackingBatch]) -> Vec<NewBatchModel> {
+pub fn make_new_batch_models(batches: &[TrackingBatch], created_at: i64) -> Vec<NewBatchModel> {
     let mut models = Vec::new();
     for batch in batches {
         let serv_id = batch
@@ -527,6 +850,12 @@ pub fn make_new_batch_models(batches: &[TrackingBatch]) -> Vec<NewBatchModel> {
             trace: batch.trace(),
             serialized_batch: batch.serialized_batch().to_vec(),
             submitted: batch.submitted(),
+            created_at,
+            notes: batch.notes().map(String::from),
+            byte_size: batch.byte_size(),
+            batch_kind: batch.batch_kind().map(String::from),
+            origin: batch.origin().map(String::from),
+            metadata: batch.metadata().cloned().map(JsonObjectModel),
         };
 
         models.push(model)
@@ -547,6 +876,8 @@ pub fn make_transaction_models(batches: &[TrackingBatch]) -> Vec<TransactionMode
                 family_name: transaction.family_name().to_string(),
                 family_version: transaction.family_version().to_string(),
                 signer_public_key: transaction.signer_public_key().to_string(),
+                serialized_header: transaction.serialized_header().map(<[u8]>::to_vec),
+                payload_encrypted: false,
             };
 
             models.push(model)
@@ -556,6 +887,52 @@ pub fn make_transaction_models(batches: &[TrackingBatch]) -> Vec<TransactionMode
     models
 }
 
+pub fn make_transaction_address_models(batches: &[TrackingBatch]) -> Vec<TransactionAddressModel> {
+    let mut models = Vec::new();
+    for batch in batches {
+        for transaction in batch.transactions() {
+            let inputs = transaction.inputs().iter().map(|a| (true, a));
+            let outputs = transaction.outputs().iter().map(|a| (false, a));
+
+            for (position, (is_input, address)) in inputs
+                .enumerate()
+                .chain(outputs.enumerate())
+                .map(|(i, a)| (i as i32, a))
+            {
+                models.push(TransactionAddressModel {
+                    service_id: transaction.service_id().to_string(),
+                    transaction_id: transaction.transaction_header().to_string(),
+                    is_input,
+                    position,
+                    address: address.to_string(),
+                })
+            }
+        }
+    }
+
+    models
+}
+
+pub fn make_transaction_dependency_models(
+    batches: &[TrackingBatch],
+) -> Vec<TransactionDependencyModel> {
+    let mut models = Vec::new();
+    for batch in batches {
+        for transaction in batch.transactions() {
+            for (position, dependency_id) in transaction.dependencies().iter().enumerate() {
+                models.push(TransactionDependencyModel {
+                    service_id: transaction.service_id().to_string(),
+                    transaction_id: transaction.transaction_header().to_string(),
+                    position: position as i32,
+                    dependency_id: dependency_id.to_string(),
+                })
+            }
+        }
+    }
+
+    models
+}
+
 pub fn is_data_change_id(id: &str) -> Result<bool, BatchTrackingStoreError> {
     let dcid_format = Regex::new(DCID_FORMAT).map_err(|err| {
         BatchTrackingStoreError::InternalError(InternalError::from_source(Box::new(err)))
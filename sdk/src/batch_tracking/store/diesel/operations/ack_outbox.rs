@@ -0,0 +1,44 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::{diesel::schema::outbox, BatchTrackingStoreError};
+use diesel::{delete, prelude::*};
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreAckOutboxOperation {
+    fn ack_outbox(&self, ids: &[i64]) -> Result<usize, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreAckOutboxOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn ack_outbox(&self, ids: &[i64]) -> Result<usize, BatchTrackingStoreError> {
+        self.retrying_transaction("ack_outbox", || {
+            Ok(delete(outbox::table.filter(outbox::id.eq_any(ids))).execute(self.conn)?)
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreAckOutboxOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn ack_outbox(&self, ids: &[i64]) -> Result<usize, BatchTrackingStoreError> {
+        self.transaction("ack_outbox", || {
+            Ok(delete(outbox::table.filter(outbox::id.eq_any(ids))).execute(self.conn)?)
+        })
+    }
+}
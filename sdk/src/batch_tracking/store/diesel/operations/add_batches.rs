@@ -15,37 +15,138 @@
 use super::BatchTrackingStoreOperations;
 use crate::batch_tracking::store::{
     diesel::{
-        models::{make_new_batch_models, make_transaction_models},
-        schema::{batches, transactions},
+        models::{
+            make_new_batch_models, make_transaction_address_models,
+            make_transaction_dependency_models, make_transaction_models, NewBatchModel,
+        },
+        schema::{
+            batch_tombstones, batches, transaction_addresses, transaction_dependencies,
+            transactions,
+        },
     },
     BatchTrackingStoreError, TrackingBatch,
 };
 
+#[cfg(feature = "sqlite")]
+use diesel::dsl::insert_or_ignore_into;
 use diesel::{dsl::insert_into, prelude::*};
 
 pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreAddBatchesOperation {
-    fn add_batches(&self, batches: Vec<TrackingBatch>) -> Result<(), BatchTrackingStoreError>;
+    fn add_batches(
+        &self,
+        batches: Vec<TrackingBatch>,
+        ignore_duplicates: bool,
+    ) -> Result<(), BatchTrackingStoreError>;
 }
 
 #[cfg(feature = "postgres")]
 impl<'a> BatchTrackingStoreAddBatchesOperation
     for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
 {
-    fn add_batches(&self, batches: Vec<TrackingBatch>) -> Result<(), BatchTrackingStoreError> {
-        let batch_models = make_new_batch_models(&batches);
-        let transaction_models = make_transaction_models(&batches);
-        self.conn.transaction::<_, BatchTrackingStoreError, _>(|| {
-            insert_into(batches::table)
-                .values(batch_models)
-                .execute(self.conn)
-                .map(|_| ())
-                .map_err(BatchTrackingStoreError::from)?;
-
-            insert_into(transactions::table)
-                .values(transaction_models)
-                .execute(self.conn)
-                .map(|_| ())
-                .map_err(BatchTrackingStoreError::from)?;
+    fn add_batches(
+        &self,
+        batches: Vec<TrackingBatch>,
+        ignore_duplicates: bool,
+    ) -> Result<(), BatchTrackingStoreError> {
+        self.retrying_transaction("add_batches", || {
+            let batch_models = make_new_batch_models(&batches, self.now()?);
+            let transaction_models = self.encrypt_payloads(make_transaction_models(&batches))?;
+            let address_models = make_transaction_address_models(&batches);
+            let dependency_models = make_transaction_dependency_models(&batches);
+
+            // Batches that have been tombstoned must not be re-created
+            let batch_ids: Vec<String> = batch_models
+                .iter()
+                .map(|b| b.batch_id.to_string())
+                .collect();
+
+            let tombstones: Vec<(String, String)> = batch_tombstones::table
+                .select((batch_tombstones::service_id, batch_tombstones::batch_id))
+                .filter(batch_tombstones::batch_id.eq_any(&batch_ids))
+                .load::<(String, String)>(self.conn)?;
+
+            if let Some(tombstoned) = batch_models.iter().find(|b| {
+                tombstones.iter().any(|(service_id, batch_id)| {
+                    service_id == &b.service_id && batch_id == &b.batch_id
+                })
+            }) {
+                return Err(BatchTrackingStoreError::Tombstoned(
+                    tombstoned.batch_id.to_string(),
+                ));
+            }
+
+            // A data change ID that is also a batch ID would make lookups by
+            // either ambiguous, so they must not collide within a service
+            let data_change_ids: Vec<&str> = batch_models
+                .iter()
+                .filter_map(|b| b.data_change_id.as_deref())
+                .collect();
+
+            let batch_ids_matching_dcids: Vec<(String, String)> = batches::table
+                .select((batches::service_id, batches::batch_id))
+                .filter(batches::batch_id.eq_any(&data_change_ids))
+                .load(self.conn)?;
+
+            let dcids_matching_batch_ids: Vec<(String, Option<String>)> = batches::table
+                .select((batches::service_id, batches::data_change_id))
+                .filter(batches::data_change_id.eq_any(&batch_ids))
+                .load(self.conn)?;
+
+            if let Some(collision) = find_id_collision(
+                &batch_models,
+                &batch_ids_matching_dcids,
+                &dcids_matching_batch_ids,
+            ) {
+                return Err(BatchTrackingStoreError::IdCollision(collision));
+            }
+
+            if ignore_duplicates {
+                // Batches that are already present, such as those added
+                // concurrently by another caller, are left untouched
+                insert_into(batches::table)
+                    .values(batch_models)
+                    .on_conflict_do_nothing()
+                    .execute(self.conn)?;
+
+                insert_into(transactions::table)
+                    .values(transaction_models)
+                    .on_conflict_do_nothing()
+                    .execute(self.conn)?;
+
+                insert_into(transaction_addresses::table)
+                    .values(address_models)
+                    .on_conflict_do_nothing()
+                    .execute(self.conn)?;
+
+                insert_into(transaction_dependencies::table)
+                    .values(dependency_models)
+                    .on_conflict_do_nothing()
+                    .execute(self.conn)?;
+            } else {
+                insert_into(batches::table)
+                    .values(batch_models)
+                    .execute(self.conn)
+                    .map(|_| ())
+                    .map_err(BatchTrackingStoreError::from)?;
+
+                insert_into(transactions::table)
+                    .values(transaction_models)
+                    .execute(self.conn)
+                    .map(|_| ())
+                    .map_err(BatchTrackingStoreError::from)?;
+
+                insert_into(transaction_addresses::table)
+                    .values(address_models)
+                    .execute(self.conn)
+                    .map(|_| ())
+                    .map_err(BatchTrackingStoreError::from)?;
+
+                insert_into(transaction_dependencies::table)
+                    .values(dependency_models)
+                    .execute(self.conn)
+                    .map(|_| ())
+                    .map_err(BatchTrackingStoreError::from)?;
+            }
 
             Ok(())
         })
@@ -56,23 +157,142 @@ impl<'a> BatchTrackingStoreAddBatchesOperation
 impl<'a> BatchTrackingStoreAddBatchesOperation
     for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
 {
-    fn add_batches(&self, batches: Vec<TrackingBatch>) -> Result<(), BatchTrackingStoreError> {
-        let batch_models = make_new_batch_models(&batches);
-        let transaction_models = make_transaction_models(&batches);
-        self.conn.transaction::<_, BatchTrackingStoreError, _>(|| {
-            insert_into(batches::table)
-                .values(batch_models)
-                .execute(self.conn)
-                .map(|_| ())
-                .map_err(BatchTrackingStoreError::from)?;
-
-            insert_into(transactions::table)
-                .values(transaction_models)
-                .execute(self.conn)
-                .map(|_| ())
-                .map_err(BatchTrackingStoreError::from)?;
+    fn add_batches(
+        &self,
+        batches: Vec<TrackingBatch>,
+        ignore_duplicates: bool,
+    ) -> Result<(), BatchTrackingStoreError> {
+        self.transaction("add_batches", || {
+            let batch_models = make_new_batch_models(&batches, self.now()?);
+            let transaction_models = self.encrypt_payloads(make_transaction_models(&batches))?;
+            let address_models = make_transaction_address_models(&batches);
+            let dependency_models = make_transaction_dependency_models(&batches);
+
+            // Batches that have been tombstoned must not be re-created
+            let batch_ids: Vec<String> = batch_models
+                .iter()
+                .map(|b| b.batch_id.to_string())
+                .collect();
+
+            let tombstones: Vec<(String, String)> = batch_tombstones::table
+                .select((batch_tombstones::service_id, batch_tombstones::batch_id))
+                .filter(batch_tombstones::batch_id.eq_any(&batch_ids))
+                .load::<(String, String)>(self.conn)?;
+
+            if let Some(tombstoned) = batch_models.iter().find(|b| {
+                tombstones.iter().any(|(service_id, batch_id)| {
+                    service_id == &b.service_id && batch_id == &b.batch_id
+                })
+            }) {
+                return Err(BatchTrackingStoreError::Tombstoned(
+                    tombstoned.batch_id.to_string(),
+                ));
+            }
+
+            // A data change ID that is also a batch ID would make lookups by
+            // either ambiguous, so they must not collide within a service
+            let data_change_ids: Vec<&str> = batch_models
+                .iter()
+                .filter_map(|b| b.data_change_id.as_deref())
+                .collect();
+
+            let batch_ids_matching_dcids: Vec<(String, String)> = batches::table
+                .select((batches::service_id, batches::batch_id))
+                .filter(batches::batch_id.eq_any(&data_change_ids))
+                .load(self.conn)?;
+
+            let dcids_matching_batch_ids: Vec<(String, Option<String>)> = batches::table
+                .select((batches::service_id, batches::data_change_id))
+                .filter(batches::data_change_id.eq_any(&batch_ids))
+                .load(self.conn)?;
+
+            if let Some(collision) = find_id_collision(
+                &batch_models,
+                &batch_ids_matching_dcids,
+                &dcids_matching_batch_ids,
+            ) {
+                return Err(BatchTrackingStoreError::IdCollision(collision));
+            }
+
+            if ignore_duplicates {
+                // Batches that are already present, such as those added
+                // concurrently by another caller, are left untouched
+                insert_or_ignore_into(batches::table)
+                    .values(batch_models)
+                    .execute(self.conn)?;
+
+                insert_or_ignore_into(transactions::table)
+                    .values(transaction_models)
+                    .execute(self.conn)?;
+
+                insert_or_ignore_into(transaction_addresses::table)
+                    .values(address_models)
+                    .execute(self.conn)?;
+
+                insert_or_ignore_into(transaction_dependencies::table)
+                    .values(dependency_models)
+                    .execute(self.conn)?;
+            } else {
+                insert_into(batches::table)
+                    .values(batch_models)
+                    .execute(self.conn)
+                    .map(|_| ())
+                    .map_err(BatchTrackingStoreError::from)?;
+
+                insert_into(transactions::table)
+                    .values(transaction_models)
+                    .execute(self.conn)
+                    .map(|_| ())
+                    .map_err(BatchTrackingStoreError::from)?;
+
+                insert_into(transaction_addresses::table)
+                    .values(address_models)
+                    .execute(self.conn)
+                    .map(|_| ())
+                    .map_err(BatchTrackingStoreError::from)?;
+
+                insert_into(transaction_dependencies::table)
+                    .values(dependency_models)
+                    .execute(self.conn)
+                    .map(|_| ())
+                    .map_err(BatchTrackingStoreError::from)?;
+            }
 
             Ok(())
         })
     }
 }
+
+/// Returns the ID of the first batch in `batch_models` whose data change ID
+/// is a batch ID, or whose batch ID is a data change ID, in its service
+///
+/// `batch_ids` and `data_change_ids` are the (service ID, batch ID) and
+/// (service ID, data change ID) pairs of stored batches that may collide
+/// with the new batches.
+fn find_id_collision(
+    batch_models: &[NewBatchModel],
+    batch_ids: &[(String, String)],
+    data_change_ids: &[(String, Option<String>)],
+) -> Option<String> {
+    batch_models
+        .iter()
+        .find(|b| {
+            let dcid_is_batch_id = match &b.data_change_id {
+                Some(dcid) => {
+                    batch_ids.iter().any(|(service_id, batch_id)| {
+                        service_id == &b.service_id && batch_id == dcid
+                    }) || batch_models
+                        .iter()
+                        .any(|other| other.service_id == b.service_id && &other.batch_id == dcid)
+                }
+                None => false,
+            };
+
+            let batch_id_is_dcid = data_change_ids.iter().any(|(service_id, dcid)| {
+                service_id == &b.service_id && dcid.as_ref() == Some(&b.batch_id)
+            });
+
+            dcid_is_batch_id || batch_id_is_dcid
+        })
+        .map(|b| b.batch_id.to_string())
+}
@@ -0,0 +1,93 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{add_batches::BatchTrackingStoreAddBatchesOperation, BatchTrackingStoreOperations};
+
+use transact::protocol::batch::{Batch, BatchHeader};
+use transact::protos::FromBytes;
+
+use crate::batch_tracking::store::{BatchTrackingStoreError, TrackingBatch, TrackingBatchBuilder};
+use crate::error::InvalidArgumentError;
+use crate::hex::to_hex;
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreAddTransactBatchesOperation {
+    fn add_transact_batches(
+        &self,
+        batches: Vec<Batch>,
+        service_id: &str,
+        ignore_duplicates: bool,
+    ) -> Result<(), BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreAddTransactBatchesOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn add_transact_batches(
+        &self,
+        batches: Vec<Batch>,
+        service_id: &str,
+        ignore_duplicates: bool,
+    ) -> Result<(), BatchTrackingStoreError> {
+        let tracking_batches = make_tracking_batches(batches, service_id)?;
+        self.add_batches(tracking_batches, ignore_duplicates)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreAddTransactBatchesOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn add_transact_batches(
+        &self,
+        batches: Vec<Batch>,
+        service_id: &str,
+        ignore_duplicates: bool,
+    ) -> Result<(), BatchTrackingStoreError> {
+        let tracking_batches = make_tracking_batches(batches, service_id)?;
+        self.add_batches(tracking_batches, ignore_duplicates)
+    }
+}
+
+/// Builds unsubmitted tracking batches, taking each batch's signer from its
+/// header
+fn make_tracking_batches(
+    batches: Vec<Batch>,
+    service_id: &str,
+) -> Result<Vec<TrackingBatch>, BatchTrackingStoreError> {
+    batches
+        .into_iter()
+        .map(|batch| {
+            let header = BatchHeader::from_bytes(batch.header()).map_err(|err| {
+                BatchTrackingStoreError::InvalidArgumentError(InvalidArgumentError::new(
+                    "batches".to_string(),
+                    format!("could not read batch header: {}", err),
+                ))
+            })?;
+
+            TrackingBatchBuilder::default()
+                .with_batch(batch)
+                .with_service_id(service_id.to_string())
+                .with_signer_public_key(to_hex(header.signer_public_key()))
+                .with_submitted(false)
+                .build()
+                .map_err(|err| {
+                    BatchTrackingStoreError::InvalidArgumentError(InvalidArgumentError::new(
+                        "batches".to_string(),
+                        err.to_string(),
+                    ))
+                })
+        })
+        .collect()
+}
@@ -0,0 +1,120 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::{
+    diesel::schema::batch_statuses, BatchStatusName, BatchTrackingStoreError,
+};
+
+use diesel::prelude::*;
+
+/// The number of statuses read from the database at a time
+const STATUS_CHUNK_SIZE: i64 = 1000;
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreAllStatusesForServiceOperation
+{
+    fn all_statuses_for_service(
+        &self,
+        service_id: &str,
+    ) -> Result<HashMap<String, BatchStatusName>, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreAllStatusesForServiceOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn all_statuses_for_service(
+        &self,
+        service_id: &str,
+    ) -> Result<HashMap<String, BatchStatusName>, BatchTrackingStoreError> {
+        self.transaction("all_statuses_for_service", || {
+            let mut statuses = HashMap::new();
+            let mut last_batch_id: Option<String> = None;
+
+            // Statuses are read in chunks ordered by batch ID so that a large
+            // service is never loaded as a single result set
+            loop {
+                let mut query = batch_statuses::table
+                    .select((batch_statuses::batch_id, batch_statuses::dlt_status))
+                    .filter(batch_statuses::service_id.eq(service_id))
+                    .order(batch_statuses::batch_id.asc())
+                    .limit(STATUS_CHUNK_SIZE)
+                    .into_boxed();
+
+                if let Some(last_batch_id) = &last_batch_id {
+                    query = query.filter(batch_statuses::batch_id.gt(last_batch_id.clone()));
+                }
+
+                let chunk: Vec<(String, String)> = query.load(self.conn)?;
+                let chunk_len = chunk.len() as i64;
+
+                for (batch_id, status) in chunk {
+                    let status = BatchStatusName::try_from_string(&status)?;
+                    last_batch_id = Some(batch_id.clone());
+                    statuses.insert(batch_id, status);
+                }
+
+                if chunk_len < STATUS_CHUNK_SIZE {
+                    return Ok(statuses);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreAllStatusesForServiceOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn all_statuses_for_service(
+        &self,
+        service_id: &str,
+    ) -> Result<HashMap<String, BatchStatusName>, BatchTrackingStoreError> {
+        self.transaction("all_statuses_for_service", || {
+            let mut statuses = HashMap::new();
+            let mut last_batch_id: Option<String> = None;
+
+            // Statuses are read in chunks ordered by batch ID so that a large
+            // service is never loaded as a single result set
+            loop {
+                let mut query = batch_statuses::table
+                    .select((batch_statuses::batch_id, batch_statuses::dlt_status))
+                    .filter(batch_statuses::service_id.eq(service_id))
+                    .order(batch_statuses::batch_id.asc())
+                    .limit(STATUS_CHUNK_SIZE)
+                    .into_boxed();
+
+                if let Some(last_batch_id) = &last_batch_id {
+                    query = query.filter(batch_statuses::batch_id.gt(last_batch_id.clone()));
+                }
+
+                let chunk: Vec<(String, String)> = query.load(self.conn)?;
+                let chunk_len = chunk.len() as i64;
+
+                for (batch_id, status) in chunk {
+                    let status = BatchStatusName::try_from_string(&status)?;
+                    last_batch_id = Some(batch_id.clone());
+                    statuses.insert(batch_id, status);
+                }
+
+                if chunk_len < STATUS_CHUNK_SIZE {
+                    return Ok(statuses);
+                }
+            }
+        })
+    }
+}
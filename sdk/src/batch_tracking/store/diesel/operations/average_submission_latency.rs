@@ -0,0 +1,83 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::{diesel::schema::batches, BatchTrackingStoreError};
+use diesel::prelude::*;
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreAverageSubmissionLatencyOperation
+{
+    fn average_submission_latency(
+        &self,
+        service_id: &str,
+        since: i64,
+    ) -> Result<Option<i64>, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreAverageSubmissionLatencyOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn average_submission_latency(
+        &self,
+        service_id: &str,
+        since: i64,
+    ) -> Result<Option<i64>, BatchTrackingStoreError> {
+        self.transaction("average_submission_latency", || {
+            let latencies: Vec<Option<i64>> = batches::table
+                .select(batches::submission_latency_ms)
+                .filter(batches::service_id.eq(service_id))
+                .filter(batches::created_at.ge(since))
+                .filter(batches::submission_latency_ms.is_not_null())
+                .load(self.conn)?;
+
+            let latencies: Vec<i64> = latencies.into_iter().flatten().collect();
+
+            if latencies.is_empty() {
+                return Ok(None);
+            }
+
+            Ok(Some(latencies.iter().sum::<i64>() / latencies.len() as i64))
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreAverageSubmissionLatencyOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn average_submission_latency(
+        &self,
+        service_id: &str,
+        since: i64,
+    ) -> Result<Option<i64>, BatchTrackingStoreError> {
+        self.transaction("average_submission_latency", || {
+            let latencies: Vec<Option<i64>> = batches::table
+                .select(batches::submission_latency_ms)
+                .filter(batches::service_id.eq(service_id))
+                .filter(batches::created_at.ge(since))
+                .filter(batches::submission_latency_ms.is_not_null())
+                .load(self.conn)?;
+
+            let latencies: Vec<i64> = latencies.into_iter().flatten().collect();
+
+            if latencies.is_empty() {
+                return Ok(None);
+            }
+
+            Ok(Some(latencies.iter().sum::<i64>() / latencies.len() as i64))
+        })
+    }
+}
@@ -0,0 +1,475 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::Write;
+
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::{
+    diesel::{
+        models::{
+            BatchModel, BatchStatusEventModel, BatchStatusModel, NewBatchTombstoneModel,
+            OutboxModel, SubmissionModel, TransactionAddressModel, TransactionDependencyModel,
+            TransactionModel, TransactionReceiptModel,
+        },
+        schema::{
+            batch_status_events, batch_statuses, batch_tombstones, batches, outbox, submissions,
+            transaction_addresses, transaction_dependencies, transaction_receipts, transactions,
+        },
+    },
+    BatchTrackingStoreError,
+};
+use crate::error::InternalError;
+
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The version of the backup format, written in the backup's header
+pub(super) const BACKUP_FORMAT_VERSION: u32 = 1;
+
+/// The number of batches, or of status or outbox events, read from the
+/// store at a time when writing a backup
+const BACKUP_PAGE_SIZE: i64 = 100;
+
+/// A line of a service backup
+///
+/// A backup starts with a header, followed by the rows of each table in an
+/// order that lets them be inserted as they are read.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "record", content = "data", rename_all = "snake_case")]
+pub(super) enum BackupRecord {
+    Header { version: u32, service_id: String },
+    Batch(BatchModel),
+    Transaction(TransactionModel),
+    TransactionAddress(TransactionAddressModel),
+    TransactionDependency(TransactionDependencyModel),
+    TransactionReceipt(TransactionReceiptModel),
+    BatchStatus(BatchStatusModel),
+    Submission(SubmissionModel),
+    BatchStatusEvent(BatchStatusEventModel),
+    BatchTombstone(NewBatchTombstoneModel),
+    Outbox(OutboxModel),
+}
+
+impl BackupRecord {
+    /// Returns the service ID of the header or row
+    pub(super) fn service_id(&self) -> &str {
+        match self {
+            BackupRecord::Header { service_id, .. } => service_id,
+            BackupRecord::Batch(row) => &row.service_id,
+            BackupRecord::Transaction(row) => &row.service_id,
+            BackupRecord::TransactionAddress(row) => &row.service_id,
+            BackupRecord::TransactionDependency(row) => &row.service_id,
+            BackupRecord::TransactionReceipt(row) => &row.service_id,
+            BackupRecord::BatchStatus(row) => &row.service_id,
+            BackupRecord::Submission(row) => &row.service_id,
+            BackupRecord::BatchStatusEvent(row) => &row.service_id,
+            BackupRecord::BatchTombstone(row) => &row.service_id,
+            BackupRecord::Outbox(row) => &row.service_id,
+        }
+    }
+}
+
+/// Writes each record to the writer as a line of JSON
+fn write_records<I>(writer: &mut dyn Write, records: I) -> Result<(), BatchTrackingStoreError>
+where
+    I: IntoIterator<Item = BackupRecord>,
+{
+    for record in records {
+        serde_json::to_writer(&mut *writer, &record).map_err(|err| {
+            BatchTrackingStoreError::InternalError(InternalError::from_source(Box::new(err)))
+        })?;
+        writer.write_all(b"\n").map_err(|err| {
+            BatchTrackingStoreError::InternalError(InternalError::from_source(Box::new(err)))
+        })?;
+    }
+
+    Ok(())
+}
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreBackupServiceOperation {
+    fn backup_service(
+        &self,
+        service_id: &str,
+        writer: &mut dyn Write,
+    ) -> Result<(), BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreBackupServiceOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn backup_service(
+        &self,
+        service_id: &str,
+        writer: &mut dyn Write,
+    ) -> Result<(), BatchTrackingStoreError> {
+        self.transaction("backup_service", || {
+            write_records(
+                writer,
+                vec![BackupRecord::Header {
+                    version: BACKUP_FORMAT_VERSION,
+                    service_id: service_id.to_string(),
+                }],
+            )?;
+
+            // Each page of batches is followed by the rows that reference
+            // them, so that a restore never inserts a row before its batch
+            let mut after: Option<String> = None;
+            loop {
+                let mut query = batches::table
+                    .into_boxed()
+                    .filter(batches::service_id.eq(service_id));
+                if let Some(after) = &after {
+                    query = query.filter(batches::batch_id.gt(after));
+                }
+                let batch_models: Vec<BatchModel> = query
+                    .order(batches::batch_id.asc())
+                    .limit(BACKUP_PAGE_SIZE)
+                    .load(self.conn)?;
+                let batch_ids: Vec<String> =
+                    batch_models.iter().map(|b| b.batch_id.clone()).collect();
+
+                let txn_models: Vec<TransactionModel> = transactions::table
+                    .filter(transactions::service_id.eq(service_id))
+                    .filter(transactions::batch_id.eq_any(&batch_ids))
+                    .order(transactions::transaction_id.asc())
+                    .load(self.conn)?;
+                let txn_ids: Vec<String> = txn_models
+                    .iter()
+                    .map(|t| t.transaction_id.clone())
+                    .collect();
+
+                let address_models: Vec<TransactionAddressModel> = transaction_addresses::table
+                    .filter(transaction_addresses::service_id.eq(service_id))
+                    .filter(transaction_addresses::transaction_id.eq_any(&txn_ids))
+                    .order((
+                        transaction_addresses::transaction_id.asc(),
+                        transaction_addresses::is_input.asc(),
+                        transaction_addresses::position.asc(),
+                    ))
+                    .load(self.conn)?;
+
+                let dependency_models: Vec<TransactionDependencyModel> =
+                    transaction_dependencies::table
+                        .filter(transaction_dependencies::service_id.eq(service_id))
+                        .filter(transaction_dependencies::transaction_id.eq_any(&txn_ids))
+                        .order((
+                            transaction_dependencies::transaction_id.asc(),
+                            transaction_dependencies::position.asc(),
+                        ))
+                        .load(self.conn)?;
+
+                let receipt_models: Vec<TransactionReceiptModel> = transaction_receipts::table
+                    .filter(transaction_receipts::service_id.eq(service_id))
+                    .filter(transaction_receipts::transaction_id.eq_any(&txn_ids))
+                    .order(transaction_receipts::transaction_id.asc())
+                    .load(self.conn)?;
+
+                let status_models: Vec<BatchStatusModel> = batch_statuses::table
+                    .filter(batch_statuses::service_id.eq(service_id))
+                    .filter(batch_statuses::batch_id.eq_any(&batch_ids))
+                    .order(batch_statuses::batch_id.asc())
+                    .load(self.conn)?;
+
+                let submission_models: Vec<SubmissionModel> = submissions::table
+                    .filter(submissions::service_id.eq(service_id))
+                    .filter(submissions::batch_id.eq_any(&batch_ids))
+                    .order(submissions::batch_id.asc())
+                    .load(self.conn)?;
+
+                let last_page = (batch_models.len() as i64) < BACKUP_PAGE_SIZE;
+                after = batch_ids.last().cloned();
+
+                write_records(writer, batch_models.into_iter().map(BackupRecord::Batch))?;
+                write_records(
+                    writer,
+                    txn_models.into_iter().map(BackupRecord::Transaction),
+                )?;
+                write_records(
+                    writer,
+                    address_models
+                        .into_iter()
+                        .map(BackupRecord::TransactionAddress),
+                )?;
+                write_records(
+                    writer,
+                    dependency_models
+                        .into_iter()
+                        .map(BackupRecord::TransactionDependency),
+                )?;
+                write_records(
+                    writer,
+                    receipt_models
+                        .into_iter()
+                        .map(BackupRecord::TransactionReceipt),
+                )?;
+                write_records(
+                    writer,
+                    status_models.into_iter().map(BackupRecord::BatchStatus),
+                )?;
+                write_records(
+                    writer,
+                    submission_models.into_iter().map(BackupRecord::Submission),
+                )?;
+
+                if last_page {
+                    break;
+                }
+            }
+
+            // Events are written in the order they were recorded, so that
+            // they are recorded in the same order when restored
+            let mut after_id = 0;
+            loop {
+                let event_models: Vec<BatchStatusEventModel> = batch_status_events::table
+                    .filter(batch_status_events::service_id.eq(service_id))
+                    .filter(batch_status_events::id.gt(after_id))
+                    .order(batch_status_events::id.asc())
+                    .limit(BACKUP_PAGE_SIZE)
+                    .load(self.conn)?;
+
+                let last_page = (event_models.len() as i64) < BACKUP_PAGE_SIZE;
+                after_id = event_models.last().map(|e| e.id).unwrap_or(after_id);
+
+                write_records(
+                    writer,
+                    event_models.into_iter().map(BackupRecord::BatchStatusEvent),
+                )?;
+
+                if last_page {
+                    break;
+                }
+            }
+
+            let tombstone_models: Vec<NewBatchTombstoneModel> = batch_tombstones::table
+                .filter(batch_tombstones::service_id.eq(service_id))
+                .order(batch_tombstones::batch_id.asc())
+                .load(self.conn)?;
+            write_records(
+                writer,
+                tombstone_models
+                    .into_iter()
+                    .map(BackupRecord::BatchTombstone),
+            )?;
+
+            let mut after_id = 0;
+            loop {
+                let outbox_models: Vec<OutboxModel> = outbox::table
+                    .filter(outbox::service_id.eq(service_id))
+                    .filter(outbox::id.gt(after_id))
+                    .order(outbox::id.asc())
+                    .limit(BACKUP_PAGE_SIZE)
+                    .load(self.conn)?;
+
+                let last_page = (outbox_models.len() as i64) < BACKUP_PAGE_SIZE;
+                after_id = outbox_models.last().map(|e| e.id).unwrap_or(after_id);
+
+                write_records(writer, outbox_models.into_iter().map(BackupRecord::Outbox))?;
+
+                if last_page {
+                    break;
+                }
+            }
+
+            writer.flush().map_err(|err| {
+                BatchTrackingStoreError::InternalError(InternalError::from_source(Box::new(err)))
+            })
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreBackupServiceOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn backup_service(
+        &self,
+        service_id: &str,
+        writer: &mut dyn Write,
+    ) -> Result<(), BatchTrackingStoreError> {
+        self.transaction("backup_service", || {
+            write_records(
+                writer,
+                vec![BackupRecord::Header {
+                    version: BACKUP_FORMAT_VERSION,
+                    service_id: service_id.to_string(),
+                }],
+            )?;
+
+            // Each page of batches is followed by the rows that reference
+            // them, so that a restore never inserts a row before its batch
+            let mut after: Option<String> = None;
+            loop {
+                let mut query = batches::table
+                    .into_boxed()
+                    .filter(batches::service_id.eq(service_id));
+                if let Some(after) = &after {
+                    query = query.filter(batches::batch_id.gt(after));
+                }
+                let batch_models: Vec<BatchModel> = query
+                    .order(batches::batch_id.asc())
+                    .limit(BACKUP_PAGE_SIZE)
+                    .load(self.conn)?;
+                let batch_ids: Vec<String> =
+                    batch_models.iter().map(|b| b.batch_id.clone()).collect();
+
+                let txn_models: Vec<TransactionModel> = transactions::table
+                    .filter(transactions::service_id.eq(service_id))
+                    .filter(transactions::batch_id.eq_any(&batch_ids))
+                    .order(transactions::transaction_id.asc())
+                    .load(self.conn)?;
+                let txn_ids: Vec<String> = txn_models
+                    .iter()
+                    .map(|t| t.transaction_id.clone())
+                    .collect();
+
+                let address_models: Vec<TransactionAddressModel> = transaction_addresses::table
+                    .filter(transaction_addresses::service_id.eq(service_id))
+                    .filter(transaction_addresses::transaction_id.eq_any(&txn_ids))
+                    .order((
+                        transaction_addresses::transaction_id.asc(),
+                        transaction_addresses::is_input.asc(),
+                        transaction_addresses::position.asc(),
+                    ))
+                    .load(self.conn)?;
+
+                let dependency_models: Vec<TransactionDependencyModel> =
+                    transaction_dependencies::table
+                        .filter(transaction_dependencies::service_id.eq(service_id))
+                        .filter(transaction_dependencies::transaction_id.eq_any(&txn_ids))
+                        .order((
+                            transaction_dependencies::transaction_id.asc(),
+                            transaction_dependencies::position.asc(),
+                        ))
+                        .load(self.conn)?;
+
+                let receipt_models: Vec<TransactionReceiptModel> = transaction_receipts::table
+                    .filter(transaction_receipts::service_id.eq(service_id))
+                    .filter(transaction_receipts::transaction_id.eq_any(&txn_ids))
+                    .order(transaction_receipts::transaction_id.asc())
+                    .load(self.conn)?;
+
+                let status_models: Vec<BatchStatusModel> = batch_statuses::table
+                    .filter(batch_statuses::service_id.eq(service_id))
+                    .filter(batch_statuses::batch_id.eq_any(&batch_ids))
+                    .order(batch_statuses::batch_id.asc())
+                    .load(self.conn)?;
+
+                let submission_models: Vec<SubmissionModel> = submissions::table
+                    .filter(submissions::service_id.eq(service_id))
+                    .filter(submissions::batch_id.eq_any(&batch_ids))
+                    .order(submissions::batch_id.asc())
+                    .load(self.conn)?;
+
+                let last_page = (batch_models.len() as i64) < BACKUP_PAGE_SIZE;
+                after = batch_ids.last().cloned();
+
+                write_records(writer, batch_models.into_iter().map(BackupRecord::Batch))?;
+                write_records(
+                    writer,
+                    txn_models.into_iter().map(BackupRecord::Transaction),
+                )?;
+                write_records(
+                    writer,
+                    address_models
+                        .into_iter()
+                        .map(BackupRecord::TransactionAddress),
+                )?;
+                write_records(
+                    writer,
+                    dependency_models
+                        .into_iter()
+                        .map(BackupRecord::TransactionDependency),
+                )?;
+                write_records(
+                    writer,
+                    receipt_models
+                        .into_iter()
+                        .map(BackupRecord::TransactionReceipt),
+                )?;
+                write_records(
+                    writer,
+                    status_models.into_iter().map(BackupRecord::BatchStatus),
+                )?;
+                write_records(
+                    writer,
+                    submission_models.into_iter().map(BackupRecord::Submission),
+                )?;
+
+                if last_page {
+                    break;
+                }
+            }
+
+            // Events are written in the order they were recorded, so that
+            // they are recorded in the same order when restored
+            let mut after_id = 0;
+            loop {
+                let event_models: Vec<BatchStatusEventModel> = batch_status_events::table
+                    .filter(batch_status_events::service_id.eq(service_id))
+                    .filter(batch_status_events::id.gt(after_id))
+                    .order(batch_status_events::id.asc())
+                    .limit(BACKUP_PAGE_SIZE)
+                    .load(self.conn)?;
+
+                let last_page = (event_models.len() as i64) < BACKUP_PAGE_SIZE;
+                after_id = event_models.last().map(|e| e.id).unwrap_or(after_id);
+
+                write_records(
+                    writer,
+                    event_models.into_iter().map(BackupRecord::BatchStatusEvent),
+                )?;
+
+                if last_page {
+                    break;
+                }
+            }
+
+            let tombstone_models: Vec<NewBatchTombstoneModel> = batch_tombstones::table
+                .filter(batch_tombstones::service_id.eq(service_id))
+                .order(batch_tombstones::batch_id.asc())
+                .load(self.conn)?;
+            write_records(
+                writer,
+                tombstone_models
+                    .into_iter()
+                    .map(BackupRecord::BatchTombstone),
+            )?;
+
+            let mut after_id = 0;
+            loop {
+                let outbox_models: Vec<OutboxModel> = outbox::table
+                    .filter(outbox::service_id.eq(service_id))
+                    .filter(outbox::id.gt(after_id))
+                    .order(outbox::id.asc())
+                    .limit(BACKUP_PAGE_SIZE)
+                    .load(self.conn)?;
+
+                let last_page = (outbox_models.len() as i64) < BACKUP_PAGE_SIZE;
+                after_id = outbox_models.last().map(|e| e.id).unwrap_or(after_id);
+
+                write_records(writer, outbox_models.into_iter().map(BackupRecord::Outbox))?;
+
+                if last_page {
+                    break;
+                }
+            }
+
+            writer.flush().map_err(|err| {
+                BatchTrackingStoreError::InternalError(InternalError::from_source(Box::new(err)))
+            })
+        })
+    }
+}
@@ -12,14 +12,18 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use super::BatchTrackingStoreOperations;
-use crate::error::InternalError;
+use super::{
+    record_status_event::BatchTrackingStoreRecordStatusEventOperation,
+    resolve_data_change_id::BatchTrackingStoreResolveDataChangeIdOperation,
+    BatchTrackingStoreOperations,
+};
+use crate::error::{InternalError, InvalidArgumentError};
 
 use crate::batch_tracking::store::{
     diesel::{
         models::{
-            is_data_change_id, NewBatchStatusModel, NewSubmissionModel, TransactionModel,
-            TransactionReceiptModel,
+            is_data_change_id, JsonObjectModel, NewBatchStatusModel, NewSubmissionModel,
+            TransactionModel, TransactionReceiptModel,
         },
         schema::{batch_statuses, batches, submissions, transaction_receipts, transactions},
     },
@@ -33,6 +37,7 @@ use diesel::{
 
 pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreChangeBatchToSubmittedOperation
 {
+    #[allow(clippy::too_many_arguments)]
     fn change_batch_to_submitted(
         &self,
         id: &str,
@@ -40,6 +45,11 @@ pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreChangeBatch
         txn_receipts: Vec<TransactionReceiptModel>,
         status: Option<NewBatchStatusModel>,
         submission: NewSubmissionModel,
+        submitter_response: Option<&[u8]>,
+        submit_headers: Option<&serde_json::Value>,
+        submit_url: Option<&str>,
+        submission_round: Option<i64>,
+        network_id: Option<&str>,
     ) -> Result<(), BatchTrackingStoreError>;
 }
 
@@ -54,19 +64,23 @@ impl<'a> BatchTrackingStoreChangeBatchToSubmittedOperation
         txn_receipts: Vec<TransactionReceiptModel>,
         status: Option<NewBatchStatusModel>,
         submission: NewSubmissionModel,
+        submitter_response: Option<&[u8]>,
+        submit_headers: Option<&serde_json::Value>,
+        submit_url: Option<&str>,
+        submission_round: Option<i64>,
+        network_id: Option<&str>,
     ) -> Result<(), BatchTrackingStoreError> {
-        self.conn.transaction::<_, BatchTrackingStoreError, _>(|| {
+        let submit_headers = submit_headers.map(validate_submit_headers).transpose()?;
+
+        self.transaction("change_batch_to_submitted", || {
+            let now = self.now()?;
+
             let mut batch_id = id.to_string();
             let is_dcid = is_data_change_id(id)?;
             if is_dcid {
-                batch_id = batches::table
-                    .select(batches::batch_id)
-                    .filter(
-                        batches::data_change_id
-                            .eq(&id)
-                            .and(batches::service_id.eq(&service_id)),
-                    )
-                    .first::<String>(self.conn)?;
+                batch_id = self
+                    .resolve_data_change_id(id, service_id)?
+                    .ok_or(diesel::result::Error::NotFound)?;
             }
 
             let batch_exists: bool = select(exists(
@@ -118,14 +132,25 @@ impl<'a> BatchTrackingStoreChangeBatchToSubmittedOperation
                                         batch_statuses::service_id.eq(&batch_status.service_id),
                                     ),
                                 )
-                                .set(&batch_status)
+                                .set((&batch_status, batch_statuses::updated_at.eq(now)))
                                 .execute(self.conn)?;
                         } else {
                             insert_into(batch_statuses::table)
-                                .values(&batch_status)
+                                .values((
+                                    &batch_status,
+                                    batch_statuses::created_at.eq(now),
+                                    batch_statuses::updated_at.eq(now),
+                                ))
                                 .execute(self.conn)?;
                         }
 
+                        self.record_status_event(
+                            &batch_id,
+                            service_id,
+                            &batch_status.dlt_status,
+                            now,
+                        )?;
+
                         if txns.len() != txn_receipts.len()
                             && batch_status.dlt_status != BatchStatus::Pending.to_string()
                         {
@@ -135,6 +160,35 @@ impl<'a> BatchTrackingStoreChangeBatchToSubmittedOperation
                                 ),
                             ));
                         }
+
+                        if matches!(
+                            status_string,
+                            BatchStatusName::Invalid | BatchStatusName::Committed
+                        ) {
+                            // The latency is measured to the first time the batch reaches a
+                            // terminal status
+                            let created_at: i64 = batches::table
+                                .select(batches::created_at)
+                                .filter(
+                                    batches::batch_id
+                                        .eq(&batch_id)
+                                        .and(batches::service_id.eq(&service_id)),
+                                )
+                                .first(self.conn)?;
+
+                            update(batches::table)
+                                .filter(
+                                    batches::batch_id
+                                        .eq(&batch_id)
+                                        .and(batches::service_id.eq(&service_id))
+                                        .and(batches::submission_latency_ms.is_null()),
+                                )
+                                .set(
+                                    batches::submission_latency_ms
+                                        .eq(self.to_millis(now - created_at)),
+                                )
+                                .execute(self.conn)?;
+                        }
                     }
                     _ => {
                         return Err(BatchTrackingStoreError::NotFoundError(format!(
@@ -187,11 +241,26 @@ impl<'a> BatchTrackingStoreChangeBatchToSubmittedOperation
                             .eq(&submission.batch_id)
                             .and(submissions::service_id.eq(&submission.service_id)),
                     )
-                    .set(&submission)
+                    .set((
+                        &submission,
+                        submissions::updated_at.eq(now),
+                        submissions::last_checked.eq(now),
+                        submissions::submitter_response.eq(submitter_response),
+                        submissions::submit_headers.eq(&submit_headers),
+                        submissions::submit_url.eq(submit_url),
+                    ))
                     .execute(self.conn)?;
             } else {
                 insert_into(submissions::table)
-                    .values(&submission)
+                    .values((
+                        &submission,
+                        submissions::created_at.eq(now),
+                        submissions::updated_at.eq(now),
+                        submissions::last_checked.eq(now),
+                        submissions::submitter_response.eq(submitter_response),
+                        submissions::submit_headers.eq(&submit_headers),
+                        submissions::submit_url.eq(submit_url),
+                    ))
                     .execute(self.conn)?;
             }
 
@@ -204,6 +273,28 @@ impl<'a> BatchTrackingStoreChangeBatchToSubmittedOperation
                 .set(batches::submitted.eq(true))
                 .execute(self.conn)?;
 
+            if let Some(round) = submission_round {
+                update(batches::table)
+                    .filter(
+                        batches::batch_id
+                            .eq(&batch_id)
+                            .and(batches::service_id.eq(&service_id)),
+                    )
+                    .set(batches::submission_round.eq(round))
+                    .execute(self.conn)?;
+            }
+
+            if let Some(network_id) = network_id {
+                update(batches::table)
+                    .filter(
+                        batches::batch_id
+                            .eq(&batch_id)
+                            .and(batches::service_id.eq(&service_id)),
+                    )
+                    .set(batches::network_id.eq(network_id))
+                    .execute(self.conn)?;
+            }
+
             Ok(())
         })
     }
@@ -220,19 +311,23 @@ impl<'a> BatchTrackingStoreChangeBatchToSubmittedOperation
         txn_receipts: Vec<TransactionReceiptModel>,
         status: Option<NewBatchStatusModel>,
         submission: NewSubmissionModel,
+        submitter_response: Option<&[u8]>,
+        submit_headers: Option<&serde_json::Value>,
+        submit_url: Option<&str>,
+        submission_round: Option<i64>,
+        network_id: Option<&str>,
     ) -> Result<(), BatchTrackingStoreError> {
-        self.conn.transaction::<_, BatchTrackingStoreError, _>(|| {
+        let submit_headers = submit_headers.map(validate_submit_headers).transpose()?;
+
+        self.transaction("change_batch_to_submitted", || {
+            let now = self.now()?;
+
             let mut batch_id = id.to_string();
             let is_dcid = is_data_change_id(id)?;
             if is_dcid {
-                batch_id = batches::table
-                    .select(batches::batch_id)
-                    .filter(
-                        batches::data_change_id
-                            .eq(&id)
-                            .and(batches::service_id.eq(&service_id)),
-                    )
-                    .first::<String>(self.conn)?;
+                batch_id = self
+                    .resolve_data_change_id(id, service_id)?
+                    .ok_or(diesel::result::Error::NotFound)?;
             }
             let batch_exists: bool = select(exists(
                 batches::table.filter(
@@ -283,14 +378,25 @@ impl<'a> BatchTrackingStoreChangeBatchToSubmittedOperation
                                         batch_statuses::service_id.eq(&batch_status.service_id),
                                     ),
                                 )
-                                .set(&batch_status)
+                                .set((&batch_status, batch_statuses::updated_at.eq(now)))
                                 .execute(self.conn)?;
                         } else {
                             insert_into(batch_statuses::table)
-                                .values(&batch_status)
+                                .values((
+                                    &batch_status,
+                                    batch_statuses::created_at.eq(now),
+                                    batch_statuses::updated_at.eq(now),
+                                ))
                                 .execute(self.conn)?;
                         }
 
+                        self.record_status_event(
+                            &batch_id,
+                            service_id,
+                            &batch_status.dlt_status,
+                            now,
+                        )?;
+
                         if txns.len() != txn_receipts.len()
                             && batch_status.dlt_status != BatchStatus::Pending.to_string()
                         {
@@ -300,6 +406,35 @@ impl<'a> BatchTrackingStoreChangeBatchToSubmittedOperation
                                 ),
                             ));
                         }
+
+                        if matches!(
+                            status_string,
+                            BatchStatusName::Invalid | BatchStatusName::Committed
+                        ) {
+                            // The latency is measured to the first time the batch reaches a
+                            // terminal status
+                            let created_at: i64 = batches::table
+                                .select(batches::created_at)
+                                .filter(
+                                    batches::batch_id
+                                        .eq(&batch_id)
+                                        .and(batches::service_id.eq(&service_id)),
+                                )
+                                .first(self.conn)?;
+
+                            update(batches::table)
+                                .filter(
+                                    batches::batch_id
+                                        .eq(&batch_id)
+                                        .and(batches::service_id.eq(&service_id))
+                                        .and(batches::submission_latency_ms.is_null()),
+                                )
+                                .set(
+                                    batches::submission_latency_ms
+                                        .eq(self.to_millis(now - created_at)),
+                                )
+                                .execute(self.conn)?;
+                        }
                     }
                     _ => {
                         return Err(BatchTrackingStoreError::NotFoundError(format!(
@@ -352,11 +487,26 @@ impl<'a> BatchTrackingStoreChangeBatchToSubmittedOperation
                             .eq(&submission.batch_id)
                             .and(submissions::service_id.eq(&submission.service_id)),
                     )
-                    .set(&submission)
+                    .set((
+                        &submission,
+                        submissions::updated_at.eq(now),
+                        submissions::last_checked.eq(now),
+                        submissions::submitter_response.eq(submitter_response),
+                        submissions::submit_headers.eq(&submit_headers),
+                        submissions::submit_url.eq(submit_url),
+                    ))
                     .execute(self.conn)?;
             } else {
                 insert_into(submissions::table)
-                    .values(&submission)
+                    .values((
+                        &submission,
+                        submissions::created_at.eq(now),
+                        submissions::updated_at.eq(now),
+                        submissions::last_checked.eq(now),
+                        submissions::submitter_response.eq(submitter_response),
+                        submissions::submit_headers.eq(&submit_headers),
+                        submissions::submit_url.eq(submit_url),
+                    ))
                     .execute(self.conn)?;
             }
 
@@ -369,7 +519,48 @@ impl<'a> BatchTrackingStoreChangeBatchToSubmittedOperation
                 .set(batches::submitted.eq(true))
                 .execute(self.conn)?;
 
+            if let Some(round) = submission_round {
+                update(batches::table)
+                    .filter(
+                        batches::batch_id
+                            .eq(&batch_id)
+                            .and(batches::service_id.eq(&service_id)),
+                    )
+                    .set(batches::submission_round.eq(round))
+                    .execute(self.conn)?;
+            }
+
+            if let Some(network_id) = network_id {
+                update(batches::table)
+                    .filter(
+                        batches::batch_id
+                            .eq(&batch_id)
+                            .and(batches::service_id.eq(&service_id)),
+                    )
+                    .set(batches::network_id.eq(network_id))
+                    .execute(self.conn)?;
+            }
+
             Ok(())
         })
     }
 }
+
+/// Checks that the submit request headers are a JSON object of header names
+fn validate_submit_headers(
+    submit_headers: &serde_json::Value,
+) -> Result<JsonObjectModel, BatchTrackingStoreError> {
+    if !submit_headers.is_object() {
+        return Err(BatchTrackingStoreError::InvalidArgumentError(
+            InvalidArgumentError::new(
+                "submit_headers".to_string(),
+                format!(
+                    "submit_headers must be a JSON object, found {}",
+                    submit_headers
+                ),
+            ),
+        ));
+    }
+
+    Ok(JsonObjectModel(submit_headers.clone()))
+}
@@ -0,0 +1,242 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::convert::TryFrom;
+use std::time::Duration;
+
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::diesel::{
+    models::{
+        BatchModel, BatchStatusModel, SubmissionModel, TransactionAddressModel, TransactionModel,
+        TransactionReceiptModel,
+    },
+    schema::{
+        batch_statuses, batches, submissions, transaction_addresses, transaction_receipts,
+        transactions,
+    },
+    TrackingBatchList,
+};
+
+use crate::batch_tracking::store::BatchTrackingStoreError;
+use diesel::{dsl::update, prelude::*};
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreClaimUnsubmittedBatchesOperation
+{
+    fn claim_unsubmitted_batches(
+        &self,
+        service_id: &str,
+        claimant: &str,
+        n: i64,
+        lease_secs: u64,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreClaimUnsubmittedBatchesOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn claim_unsubmitted_batches(
+        &self,
+        service_id: &str,
+        claimant: &str,
+        n: i64,
+        lease_secs: u64,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        self.retrying_transaction("claim_unsubmitted_batches", || {
+            let claimed_at = self.now()?;
+            let claim_expires_at = claimed_at + self.in_precision(Duration::from_secs(lease_secs));
+
+            // Batches claimed by another worker can only be claimed once the
+            // claim has expired
+            let batch_ids: Vec<String> = batches::table
+                .filter(batches::service_id.eq(service_id))
+                .filter(batches::submitted.eq(false))
+                .filter(batches::dead_letter_reason.is_null())
+                .filter(
+                    batches::claim_expires_at
+                        .is_null()
+                        .or(batches::claim_expires_at.le(claimed_at)),
+                )
+                .order((batches::created_at.asc(), batches::batch_id.asc()))
+                .select(batches::batch_id)
+                .limit(n)
+                // Rows locked by a concurrent claim are skipped rather than
+                // waited on, so concurrent workers claim disjoint batches
+                .for_update()
+                .skip_locked()
+                .load(self.conn)?;
+
+            if batch_ids.is_empty() {
+                return Ok(TrackingBatchList {
+                    batches: Vec::new(),
+                });
+            }
+
+            update(
+                batches::table
+                    .filter(batches::service_id.eq(service_id))
+                    .filter(batches::batch_id.eq_any(&batch_ids)),
+            )
+            .set((
+                batches::claimed_by.eq(claimant),
+                batches::claim_expires_at.eq(claim_expires_at),
+            ))
+            .execute(self.conn)?;
+
+            let batch_models: Vec<BatchModel> = batches::table
+                .filter(batches::service_id.eq(service_id))
+                .filter(batches::batch_id.eq_any(&batch_ids))
+                .order((batches::created_at.asc(), batches::batch_id.asc()))
+                .load(self.conn)?;
+
+            let batch_status_models: Vec<BatchStatusModel> = batch_statuses::table
+                .filter(batch_statuses::service_id.eq(service_id))
+                .filter(batch_statuses::batch_id.eq_any(&batch_ids))
+                .load(self.conn)?;
+
+            let submission_models: Vec<SubmissionModel> = submissions::table
+                .filter(submissions::service_id.eq(service_id))
+                .filter(submissions::batch_id.eq_any(&batch_ids))
+                .load(self.conn)?;
+
+            let txn_models: Vec<TransactionModel> = transactions::table
+                .filter(transactions::service_id.eq(service_id))
+                .filter(transactions::batch_id.eq_any(&batch_ids))
+                .load(self.conn)?;
+            let txn_models = self.decrypt_payloads(txn_models)?;
+
+            let txn_ids: Vec<&str> = txn_models
+                .iter()
+                .map(|t| t.transaction_id.as_str())
+                .collect();
+
+            let address_models: Vec<TransactionAddressModel> = transaction_addresses::table
+                .filter(transaction_addresses::service_id.eq(service_id))
+                .filter(transaction_addresses::transaction_id.eq_any(&txn_ids))
+                .load(self.conn)?;
+
+            let receipt_models: Vec<TransactionReceiptModel> = transaction_receipts::table
+                .filter(transaction_receipts::service_id.eq(service_id))
+                .filter(transaction_receipts::transaction_id.eq_any(&txn_ids))
+                .load(self.conn)?;
+
+            TrackingBatchList::try_from((
+                batch_models,
+                batch_status_models,
+                txn_models,
+                address_models,
+                receipt_models,
+                submission_models,
+            ))
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreClaimUnsubmittedBatchesOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn claim_unsubmitted_batches(
+        &self,
+        service_id: &str,
+        claimant: &str,
+        n: i64,
+        lease_secs: u64,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        self.transaction("claim_unsubmitted_batches", || {
+            let claimed_at = self.now()?;
+            let claim_expires_at = claimed_at + self.in_precision(Duration::from_secs(lease_secs));
+
+            // Batches claimed by another worker can only be claimed once the
+            // claim has expired
+            let batch_ids: Vec<String> = batches::table
+                .filter(batches::service_id.eq(service_id))
+                .filter(batches::submitted.eq(false))
+                .filter(batches::dead_letter_reason.is_null())
+                .filter(
+                    batches::claim_expires_at
+                        .is_null()
+                        .or(batches::claim_expires_at.le(claimed_at)),
+                )
+                .order((batches::created_at.asc(), batches::batch_id.asc()))
+                .select(batches::batch_id)
+                .limit(n)
+                .load(self.conn)?;
+
+            if batch_ids.is_empty() {
+                return Ok(TrackingBatchList {
+                    batches: Vec::new(),
+                });
+            }
+
+            update(
+                batches::table
+                    .filter(batches::service_id.eq(service_id))
+                    .filter(batches::batch_id.eq_any(&batch_ids)),
+            )
+            .set((
+                batches::claimed_by.eq(claimant),
+                batches::claim_expires_at.eq(claim_expires_at),
+            ))
+            .execute(self.conn)?;
+
+            let batch_models: Vec<BatchModel> = batches::table
+                .filter(batches::service_id.eq(service_id))
+                .filter(batches::batch_id.eq_any(&batch_ids))
+                .order((batches::created_at.asc(), batches::batch_id.asc()))
+                .load(self.conn)?;
+
+            let batch_status_models: Vec<BatchStatusModel> = batch_statuses::table
+                .filter(batch_statuses::service_id.eq(service_id))
+                .filter(batch_statuses::batch_id.eq_any(&batch_ids))
+                .load(self.conn)?;
+
+            let submission_models: Vec<SubmissionModel> = submissions::table
+                .filter(submissions::service_id.eq(service_id))
+                .filter(submissions::batch_id.eq_any(&batch_ids))
+                .load(self.conn)?;
+
+            let txn_models: Vec<TransactionModel> = transactions::table
+                .filter(transactions::service_id.eq(service_id))
+                .filter(transactions::batch_id.eq_any(&batch_ids))
+                .load(self.conn)?;
+            let txn_models = self.decrypt_payloads(txn_models)?;
+
+            let txn_ids: Vec<&str> = txn_models
+                .iter()
+                .map(|t| t.transaction_id.as_str())
+                .collect();
+
+            let address_models: Vec<TransactionAddressModel> = transaction_addresses::table
+                .filter(transaction_addresses::service_id.eq(service_id))
+                .filter(transaction_addresses::transaction_id.eq_any(&txn_ids))
+                .load(self.conn)?;
+
+            let receipt_models: Vec<TransactionReceiptModel> = transaction_receipts::table
+                .filter(transaction_receipts::service_id.eq(service_id))
+                .filter(transaction_receipts::transaction_id.eq_any(&txn_ids))
+                .load(self.conn)?;
+
+            TrackingBatchList::try_from((
+                batch_models,
+                batch_status_models,
+                txn_models,
+                address_models,
+                receipt_models,
+                submission_models,
+            ))
+        })
+    }
+}
@@ -28,7 +28,7 @@ impl<'a> BatchTrackingCleanStaleRecordsOperation
     for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
 {
     fn clean_stale_records(&self, submitted_by: i64) -> Result<(), BatchTrackingStoreError> {
-        self.conn.transaction::<_, BatchTrackingStoreError, _>(|| {
+        self.retrying_transaction("clean_stale_records", || {
             delete(batches::table.filter(batches::created_at.lt(&submitted_by)))
                 .execute(self.conn)?;
 
@@ -42,7 +42,7 @@ impl<'a> BatchTrackingCleanStaleRecordsOperation
     for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
 {
     fn clean_stale_records(&self, submitted_by: i64) -> Result<(), BatchTrackingStoreError> {
-        self.conn.transaction::<_, BatchTrackingStoreError, _>(|| {
+        self.transaction("clean_stale_records", || {
             delete(batches::table.filter(batches::created_at.lt(&submitted_by)))
                 .execute(self.conn)?;
 
@@ -0,0 +1,131 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{
+    resolve_data_change_id::BatchTrackingStoreResolveDataChangeIdOperation,
+    update_batch_status::BatchTrackingStoreUpdateBatchStatusOperation,
+    BatchTrackingStoreOperations,
+};
+
+use crate::batch_tracking::store::{
+    diesel::{
+        models::{is_data_change_id, TransactionReceiptModel},
+        schema::batches,
+    },
+    BatchStatusName, BatchTrackingStoreError,
+};
+use diesel::{dsl::exists, prelude::*, select};
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreCommitBatchOperation {
+    fn commit_batch(
+        &self,
+        id: &str,
+        service_id: &str,
+        txn_receipts: Vec<TransactionReceiptModel>,
+    ) -> Result<(), BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreCommitBatchOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn commit_batch(
+        &self,
+        id: &str,
+        service_id: &str,
+        txn_receipts: Vec<TransactionReceiptModel>,
+    ) -> Result<(), BatchTrackingStoreError> {
+        self.transaction("commit_batch", || {
+            let mut batch_id = id.to_string();
+            if is_data_change_id(id)? {
+                batch_id = self
+                    .resolve_data_change_id(id, service_id)?
+                    .unwrap_or(batch_id);
+            }
+
+            let batch_exists: bool = select(exists(
+                batches::table.filter(
+                    batches::batch_id
+                        .eq(&batch_id)
+                        .and(batches::service_id.eq(&service_id)),
+                ),
+            ))
+            .get_result(self.conn)?;
+
+            if !batch_exists {
+                return Err(BatchTrackingStoreError::NotFoundError(format!(
+                    "Could not find batch with ID {}",
+                    id
+                )));
+            }
+
+            // Setting a terminal status also records the batch's submission
+            // latency
+            self.update_batch_status(
+                &batch_id,
+                service_id,
+                Some(&BatchStatusName::Committed.to_string()),
+                txn_receipts,
+                None,
+            )
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreCommitBatchOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn commit_batch(
+        &self,
+        id: &str,
+        service_id: &str,
+        txn_receipts: Vec<TransactionReceiptModel>,
+    ) -> Result<(), BatchTrackingStoreError> {
+        self.transaction("commit_batch", || {
+            let mut batch_id = id.to_string();
+            if is_data_change_id(id)? {
+                batch_id = self
+                    .resolve_data_change_id(id, service_id)?
+                    .unwrap_or(batch_id);
+            }
+
+            let batch_exists: bool = select(exists(
+                batches::table.filter(
+                    batches::batch_id
+                        .eq(&batch_id)
+                        .and(batches::service_id.eq(&service_id)),
+                ),
+            ))
+            .get_result(self.conn)?;
+
+            if !batch_exists {
+                return Err(BatchTrackingStoreError::NotFoundError(format!(
+                    "Could not find batch with ID {}",
+                    id
+                )));
+            }
+
+            // Setting a terminal status also records the batch's submission
+            // latency
+            self.update_batch_status(
+                &batch_id,
+                service_id,
+                Some(&BatchStatusName::Committed.to_string()),
+                txn_receipts,
+                None,
+            )
+        })
+    }
+}
@@ -0,0 +1,70 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::BatchTrackingStoreError;
+use diesel::{prelude::*, sql_query};
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreCompactOperation {
+    fn compact(&self) -> Result<(), BatchTrackingStoreError>;
+}
+
+// VACUUM cannot be run inside of a transaction block on either backend, so
+// these operations are executed directly on the connection.
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreCompactOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn compact(&self) -> Result<(), BatchTrackingStoreError> {
+        // Only the batch tracking tables are vacuumed, as the database may be
+        // shared with other stores. The search path can't be set for this
+        // statement, so the tables are qualified with the configured schema.
+        let tables = [
+            "batches",
+            "batch_statuses",
+            "batch_tombstones",
+            "outbox",
+            "submissions",
+            "transactions",
+            "transaction_addresses",
+            "transaction_dependencies",
+            "transaction_receipts",
+        ]
+        .iter()
+        .map(|table| self.qualified_table_name(table))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+        sql_query(format!("VACUUM (ANALYZE) {}", tables))
+            .execute(self.conn)
+            .map_err(|err| BatchTrackingStoreError::from(err).with_operation("compact"))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreCompactOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn compact(&self) -> Result<(), BatchTrackingStoreError> {
+        sql_query("VACUUM")
+            .execute(self.conn)
+            .map_err(|err| BatchTrackingStoreError::from(err).with_operation("compact"))?;
+
+        Ok(())
+    }
+}
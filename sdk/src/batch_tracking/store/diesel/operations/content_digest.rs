@@ -0,0 +1,87 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::{
+    diesel::schema::{batch_statuses, batches},
+    BatchTrackingStoreError,
+};
+
+use crypto::digest::Digest;
+use crypto::sha2::Sha256;
+use diesel::prelude::*;
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreContentDigestOperation {
+    fn content_digest(&self, service_id: &str) -> Result<String, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreContentDigestOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn content_digest(&self, service_id: &str) -> Result<String, BatchTrackingStoreError> {
+        self.transaction("content_digest", || {
+            let contents: Vec<(String, Option<String>)> = batches::table
+                .left_join(
+                    batch_statuses::table.on(batches::batch_id
+                        .eq(batch_statuses::batch_id)
+                        .and(batches::service_id.eq(batch_statuses::service_id))),
+                )
+                .filter(batches::service_id.eq(service_id))
+                .order(batches::batch_id.asc())
+                .select((batches::batch_id, batch_statuses::dlt_status.nullable()))
+                .load(self.conn)?;
+
+            Ok(digest(&contents))
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreContentDigestOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn content_digest(&self, service_id: &str) -> Result<String, BatchTrackingStoreError> {
+        self.transaction("content_digest", || {
+            let contents: Vec<(String, Option<String>)> = batches::table
+                .left_join(
+                    batch_statuses::table.on(batches::batch_id
+                        .eq(batch_statuses::batch_id)
+                        .and(batches::service_id.eq(batch_statuses::service_id))),
+                )
+                .filter(batches::service_id.eq(service_id))
+                .order(batches::batch_id.asc())
+                .select((batches::batch_id, batch_statuses::dlt_status.nullable()))
+                .load(self.conn)?;
+
+            Ok(digest(&contents))
+        })
+    }
+}
+
+/// Returns the hex-encoded SHA-256 digest of the given batch IDs and statuses
+///
+/// Each batch is hashed as a line of its ID and status, separated by a tab,
+/// with an empty status for a batch that has none.
+fn digest(contents: &[(String, Option<String>)]) -> String {
+    let mut sha = Sha256::new();
+    for (batch_id, status) in contents {
+        sha.input_str(batch_id);
+        sha.input_str("\t");
+        sha.input_str(status.as_deref().unwrap_or(""));
+        sha.input_str("\n");
+    }
+    sha.result_str()
+}
@@ -0,0 +1,85 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::{diesel::schema::batches, BatchTrackingStoreError};
+
+use diesel::{dsl::sql, prelude::*, sql_types::BigInt};
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreCountBatchesByOriginOperation {
+    fn count_batches_by_origin(
+        &self,
+        service_id: &str,
+        since: i64,
+    ) -> Result<HashMap<String, i64>, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreCountBatchesByOriginOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn count_batches_by_origin(
+        &self,
+        service_id: &str,
+        since: i64,
+    ) -> Result<HashMap<String, i64>, BatchTrackingStoreError> {
+        self.transaction("count_batches_by_origin", || {
+            // Diesel does not allow mixing aggregate and non-aggregate
+            // expressions in a select, so the count is written as raw SQL
+            let counts: Vec<(Option<String>, i64)> = batches::table
+                .filter(batches::service_id.eq(service_id))
+                .filter(batches::created_at.ge(since))
+                .filter(batches::origin.is_not_null())
+                .group_by(batches::origin)
+                .select((batches::origin, sql::<BigInt>("COUNT(*)")))
+                .load(self.conn)?;
+
+            Ok(counts
+                .into_iter()
+                .filter_map(|(origin, count)| origin.map(|origin| (origin, count)))
+                .collect())
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreCountBatchesByOriginOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn count_batches_by_origin(
+        &self,
+        service_id: &str,
+        since: i64,
+    ) -> Result<HashMap<String, i64>, BatchTrackingStoreError> {
+        self.transaction("count_batches_by_origin", || {
+            // Diesel does not allow mixing aggregate and non-aggregate
+            // expressions in a select, so the count is written as raw SQL
+            let counts: Vec<(Option<String>, i64)> = batches::table
+                .filter(batches::service_id.eq(service_id))
+                .filter(batches::created_at.ge(since))
+                .filter(batches::origin.is_not_null())
+                .group_by(batches::origin)
+                .select((batches::origin, sql::<BigInt>("COUNT(*)")))
+                .load(self.conn)?;
+
+            Ok(counts
+                .into_iter()
+                .filter_map(|(origin, count)| origin.map(|origin| (origin, count)))
+                .collect())
+        })
+    }
+}
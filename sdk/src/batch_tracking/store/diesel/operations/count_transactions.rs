@@ -0,0 +1,50 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::{diesel::schema::transactions, BatchTrackingStoreError};
+use diesel::prelude::*;
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreCountTransactionsOperation {
+    fn count_transactions(&self, service_id: &str) -> Result<i64, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreCountTransactionsOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn count_transactions(&self, service_id: &str) -> Result<i64, BatchTrackingStoreError> {
+        self.transaction("count_transactions", || {
+            Ok(transactions::table
+                .filter(transactions::service_id.eq(service_id))
+                .count()
+                .get_result(self.conn)?)
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreCountTransactionsOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn count_transactions(&self, service_id: &str) -> Result<i64, BatchTrackingStoreError> {
+        self.transaction("count_transactions", || {
+            Ok(transactions::table
+                .filter(transactions::service_id.eq(service_id))
+                .count()
+                .get_result(self.conn)?)
+        })
+    }
+}
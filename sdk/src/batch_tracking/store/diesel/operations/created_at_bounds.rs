@@ -0,0 +1,83 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::{diesel::schema::batches, BatchTrackingStoreError};
+use diesel::{
+    dsl::sql,
+    prelude::*,
+    sql_types::{BigInt, Nullable},
+};
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreCreatedAtBoundsOperation {
+    fn created_at_bounds(
+        &self,
+        service_id: &str,
+    ) -> Result<Option<(i64, i64)>, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreCreatedAtBoundsOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn created_at_bounds(
+        &self,
+        service_id: &str,
+    ) -> Result<Option<(i64, i64)>, BatchTrackingStoreError> {
+        self.transaction("created_at_bounds", || {
+            // Diesel can't select more than one aggregate at a time, so the
+            // bounds are selected together as SQL
+            let bounds: (Option<i64>, Option<i64>) = batches::table
+                .select(sql::<(Nullable<BigInt>, Nullable<BigInt>)>(
+                    "MIN(created_at), MAX(created_at)",
+                ))
+                .filter(batches::service_id.eq(service_id))
+                .get_result(self.conn)?;
+
+            // Both bounds are NULL when the service has no batches
+            match bounds {
+                (Some(earliest), Some(latest)) => Ok(Some((earliest, latest))),
+                _ => Ok(None),
+            }
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreCreatedAtBoundsOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn created_at_bounds(
+        &self,
+        service_id: &str,
+    ) -> Result<Option<(i64, i64)>, BatchTrackingStoreError> {
+        self.transaction("created_at_bounds", || {
+            // Diesel can't select more than one aggregate at a time, so the
+            // bounds are selected together as SQL
+            let bounds: (Option<i64>, Option<i64>) = batches::table
+                .select(sql::<(Nullable<BigInt>, Nullable<BigInt>)>(
+                    "MIN(created_at), MAX(created_at)",
+                ))
+                .filter(batches::service_id.eq(service_id))
+                .get_result(self.conn)?;
+
+            // Both bounds are NULL when the service has no batches
+            match bounds {
+                (Some(earliest), Some(latest)) => Ok(Some((earliest, latest))),
+                _ => Ok(None),
+            }
+        })
+    }
+}
@@ -0,0 +1,125 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{
+    resolve_data_change_id::BatchTrackingStoreResolveDataChangeIdOperation,
+    update_batch_status::BatchTrackingStoreUpdateBatchStatusOperation,
+    BatchTrackingStoreOperations,
+};
+
+use crate::batch_tracking::store::{
+    diesel::{models::is_data_change_id, schema::batches},
+    BatchStatusName, BatchTrackingStoreError,
+};
+
+use diesel::{dsl::update, prelude::*};
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreDeadLetterBatchOperation {
+    fn dead_letter_batch(
+        &self,
+        id: &str,
+        service_id: &str,
+        reason: &str,
+    ) -> Result<(), BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreDeadLetterBatchOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn dead_letter_batch(
+        &self,
+        id: &str,
+        service_id: &str,
+        reason: &str,
+    ) -> Result<(), BatchTrackingStoreError> {
+        self.transaction("dead_letter_batch", || {
+            let mut batch_id = id.to_string();
+            if is_data_change_id(id)? {
+                batch_id = self
+                    .resolve_data_change_id(id, service_id)?
+                    .unwrap_or(batch_id);
+            }
+
+            let updated = update(batches::table)
+                .filter(
+                    batches::batch_id
+                        .eq(&batch_id)
+                        .and(batches::service_id.eq(&service_id)),
+                )
+                .set(batches::dead_letter_reason.eq(reason))
+                .execute(self.conn)?;
+
+            if updated == 0 {
+                return Err(BatchTrackingStoreError::NotFoundError(format!(
+                    "Could not find batch with ID {}",
+                    id
+                )));
+            }
+
+            self.update_batch_status(
+                &batch_id,
+                service_id,
+                Some(&BatchStatusName::DeadLettered.to_string()),
+                Vec::new(),
+                None,
+            )
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreDeadLetterBatchOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn dead_letter_batch(
+        &self,
+        id: &str,
+        service_id: &str,
+        reason: &str,
+    ) -> Result<(), BatchTrackingStoreError> {
+        self.transaction("dead_letter_batch", || {
+            let mut batch_id = id.to_string();
+            if is_data_change_id(id)? {
+                batch_id = self
+                    .resolve_data_change_id(id, service_id)?
+                    .unwrap_or(batch_id);
+            }
+
+            let updated = update(batches::table)
+                .filter(
+                    batches::batch_id
+                        .eq(&batch_id)
+                        .and(batches::service_id.eq(&service_id)),
+                )
+                .set(batches::dead_letter_reason.eq(reason))
+                .execute(self.conn)?;
+
+            if updated == 0 {
+                return Err(BatchTrackingStoreError::NotFoundError(format!(
+                    "Could not find batch with ID {}",
+                    id
+                )));
+            }
+
+            self.update_batch_status(
+                &batch_id,
+                service_id,
+                Some(&BatchStatusName::DeadLettered.to_string()),
+                Vec::new(),
+                None,
+            )
+        })
+    }
+}
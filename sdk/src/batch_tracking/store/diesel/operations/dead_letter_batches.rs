@@ -0,0 +1,76 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeSet;
+
+use super::{
+    dead_letter_batch::BatchTrackingStoreDeadLetterBatchOperation, BatchTrackingStoreOperations,
+};
+
+use crate::batch_tracking::store::BatchTrackingStoreError;
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreDeadLetterBatchesOperation {
+    fn dead_letter_batches(
+        &self,
+        ids: &[&str],
+        service_id: &str,
+        reason: &str,
+    ) -> Result<usize, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreDeadLetterBatchesOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn dead_letter_batches(
+        &self,
+        ids: &[&str],
+        service_id: &str,
+        reason: &str,
+    ) -> Result<usize, BatchTrackingStoreError> {
+        self.retrying_transaction("dead_letter_batches", || {
+            // Repeated IDs are only dead-lettered once; if any batch can't be
+            // found, none of the batches are dead-lettered
+            let ids: BTreeSet<&str> = ids.iter().copied().collect();
+            for id in &ids {
+                self.dead_letter_batch(id, service_id, reason)?;
+            }
+
+            Ok(ids.len())
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreDeadLetterBatchesOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn dead_letter_batches(
+        &self,
+        ids: &[&str],
+        service_id: &str,
+        reason: &str,
+    ) -> Result<usize, BatchTrackingStoreError> {
+        self.transaction("dead_letter_batches", || {
+            // Repeated IDs are only dead-lettered once; if any batch can't be
+            // found, none of the batches are dead-lettered
+            let ids: BTreeSet<&str> = ids.iter().copied().collect();
+            for id in &ids {
+                self.dead_letter_batch(id, service_id, reason)?;
+            }
+
+            Ok(ids.len())
+        })
+    }
+}
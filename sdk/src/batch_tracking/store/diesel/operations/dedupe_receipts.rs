@@ -0,0 +1,149 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::resolve_data_change_id::BatchTrackingStoreResolveDataChangeIdOperation;
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::{
+    diesel::{models::is_data_change_id, schema::batches},
+    BatchTrackingStoreError,
+};
+
+use diesel::{dsl::exists, prelude::*, select, sql_query, sql_types::Text};
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreDedupeReceiptsOperation {
+    fn dedupe_receipts(&self, id: &str, service_id: &str)
+        -> Result<usize, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreDedupeReceiptsOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn dedupe_receipts(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<usize, BatchTrackingStoreError> {
+        self.transaction("dedupe_receipts", || {
+            let mut batch_id = id.to_string();
+            if is_data_change_id(id)? {
+                batch_id = self
+                    .resolve_data_change_id(id, service_id)?
+                    .ok_or_else(|| {
+                        BatchTrackingStoreError::NotFoundError(format!(
+                            "Could not find batch with data change ID {}",
+                            id
+                        ))
+                    })?;
+            }
+
+            let batch_exists: bool = select(exists(
+                batches::table.filter(
+                    batches::batch_id
+                        .eq(&batch_id)
+                        .and(batches::service_id.eq(&service_id)),
+                ),
+            ))
+            .get_result(self.conn)?;
+
+            if !batch_exists {
+                return Err(BatchTrackingStoreError::NotFoundError(format!(
+                    "Could not find batch with ID {}",
+                    batch_id
+                )));
+            }
+
+            // Only the first receipt recorded for each transaction is kept
+            sql_query(
+                "DELETE FROM transaction_receipts r
+                USING transaction_receipts kept
+                WHERE r.service_id = $1
+                AND kept.service_id = r.service_id
+                AND kept.transaction_id = r.transaction_id
+                AND kept.ctid < r.ctid
+                AND r.transaction_id IN (
+                    SELECT transaction_id FROM transactions
+                    WHERE service_id = $1 AND batch_id = $2
+                )",
+            )
+            .bind::<Text, _>(service_id)
+            .bind::<Text, _>(&batch_id)
+            .execute(self.conn)
+            .map_err(BatchTrackingStoreError::from)
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreDedupeReceiptsOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn dedupe_receipts(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<usize, BatchTrackingStoreError> {
+        self.transaction("dedupe_receipts", || {
+            let mut batch_id = id.to_string();
+            if is_data_change_id(id)? {
+                batch_id = self
+                    .resolve_data_change_id(id, service_id)?
+                    .ok_or_else(|| {
+                        BatchTrackingStoreError::NotFoundError(format!(
+                            "Could not find batch with data change ID {}",
+                            id
+                        ))
+                    })?;
+            }
+
+            let batch_exists: bool = select(exists(
+                batches::table.filter(
+                    batches::batch_id
+                        .eq(&batch_id)
+                        .and(batches::service_id.eq(&service_id)),
+                ),
+            ))
+            .get_result(self.conn)?;
+
+            if !batch_exists {
+                return Err(BatchTrackingStoreError::NotFoundError(format!(
+                    "Could not find batch with ID {}",
+                    batch_id
+                )));
+            }
+
+            // Only the first receipt recorded for each transaction is kept
+            sql_query(
+                "DELETE FROM transaction_receipts
+                WHERE service_id = ?
+                AND transaction_id IN (
+                    SELECT transaction_id FROM transactions
+                    WHERE service_id = ? AND batch_id = ?
+                )
+                AND rowid NOT IN (
+                    SELECT MIN(rowid) FROM transaction_receipts
+                    WHERE service_id = ?
+                    GROUP BY transaction_id
+                )",
+            )
+            .bind::<Text, _>(service_id)
+            .bind::<Text, _>(service_id)
+            .bind::<Text, _>(&batch_id)
+            .bind::<Text, _>(service_id)
+            .execute(self.conn)
+            .map_err(BatchTrackingStoreError::from)
+        })
+    }
+}
@@ -0,0 +1,273 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::diesel::{
+    models::{
+        BatchModel, BatchStatusModel, SubmissionModel, TransactionAddressModel, TransactionModel,
+        TransactionReceiptModel,
+    },
+    schema::{
+        batch_statuses, batches, submissions, transaction_addresses, transaction_receipts,
+        transactions,
+    },
+    BatchStatusName, TrackingBatchList,
+};
+
+use crate::batch_tracking::store::BatchTrackingStoreError;
+use crate::error::InvalidArgumentError;
+use diesel::prelude::*;
+use std::convert::TryFrom;
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreFailedBatchesCursorOperation {
+    fn failed_batches_cursor(
+        &self,
+        service_id: &str,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreFailedBatchesCursorOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn failed_batches_cursor(
+        &self,
+        service_id: &str,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        self.transaction("failed_batches_cursor", || {
+            let failed_statuses: Vec<String> = vec![
+                BatchStatusName::Unknown.to_string(),
+                BatchStatusName::Invalid.to_string(),
+            ];
+
+            let mut query = batches::table
+                .inner_join(
+                    batch_statuses::table.on(batches::batch_id
+                        .eq(batch_statuses::batch_id)
+                        .and(batches::service_id.eq(batch_statuses::service_id))),
+                )
+                .left_join(
+                    submissions::table.on(batches::batch_id
+                        .eq(submissions::batch_id)
+                        .and(batches::service_id.eq(submissions::service_id))),
+                )
+                .filter(batches::service_id.eq(service_id))
+                .filter(batch_statuses::dlt_status.eq_any(failed_statuses))
+                .select((
+                    batches::all_columns,
+                    batch_statuses::all_columns,
+                    submissions::all_columns.nullable(),
+                ))
+                .into_boxed();
+
+            // Batches are paged on (created_at, batch_id), so the cursor's
+            // position is found from the created_at of the cursor batch
+            if let Some(after) = after {
+                let after_created_at: i64 = batches::table
+                    .select(batches::created_at)
+                    .filter(
+                        batches::batch_id
+                            .eq(after)
+                            .and(batches::service_id.eq(service_id)),
+                    )
+                    .first::<i64>(self.conn)
+                    .optional()?
+                    .ok_or_else(|| {
+                        BatchTrackingStoreError::InvalidArgumentError(InvalidArgumentError::new(
+                            "after".to_string(),
+                            format!("Could not find batch with ID {}", after),
+                        ))
+                    })?;
+
+                query = query.filter(
+                    batches::created_at
+                        .gt(after_created_at)
+                        .or(batches::created_at
+                            .eq(after_created_at)
+                            .and(batches::batch_id.gt(after))),
+                );
+            }
+
+            let batch_results: Vec<(BatchModel, BatchStatusModel, Option<SubmissionModel>)> = query
+                .order((batches::created_at.asc(), batches::batch_id.asc()))
+                .limit(limit)
+                .load(self.conn)?;
+
+            let mut batch_models = Vec::new();
+            let mut batch_status_models = Vec::new();
+            let mut submission_models = Vec::new();
+
+            for (batch, status, submission) in batch_results {
+                batch_models.push(batch);
+                batch_status_models.push(status);
+                if let Some(submission) = submission {
+                    submission_models.push(submission);
+                }
+            }
+
+            let batch_ids: Vec<&str> = batch_models.iter().map(|b| b.batch_id.as_str()).collect();
+
+            let txn_models: Vec<TransactionModel> = transactions::table
+                .filter(transactions::batch_id.eq_any(&batch_ids))
+                .filter(transactions::service_id.eq(service_id))
+                .load(self.conn)?;
+            let txn_models = self.decrypt_payloads(txn_models)?;
+
+            let txn_ids: Vec<&str> = txn_models
+                .iter()
+                .map(|t| t.transaction_id.as_str())
+                .collect();
+
+            let receipt_models: Vec<TransactionReceiptModel> = transaction_receipts::table
+                .filter(transaction_receipts::transaction_id.eq_any(&txn_ids))
+                .filter(transaction_receipts::service_id.eq(service_id))
+                .load(self.conn)?;
+
+            let address_models: Vec<TransactionAddressModel> = transaction_addresses::table
+                .filter(transaction_addresses::transaction_id.eq_any(&txn_ids))
+                .filter(transaction_addresses::service_id.eq(service_id))
+                .load(self.conn)?;
+
+            TrackingBatchList::try_from((
+                batch_models,
+                batch_status_models,
+                txn_models,
+                address_models,
+                receipt_models,
+                submission_models,
+            ))
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreFailedBatchesCursorOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn failed_batches_cursor(
+        &self,
+        service_id: &str,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        self.transaction("failed_batches_cursor", || {
+            let failed_statuses: Vec<String> = vec![
+                BatchStatusName::Unknown.to_string(),
+                BatchStatusName::Invalid.to_string(),
+            ];
+
+            let mut query = batches::table
+                .inner_join(
+                    batch_statuses::table.on(batches::batch_id
+                        .eq(batch_statuses::batch_id)
+                        .and(batches::service_id.eq(batch_statuses::service_id))),
+                )
+                .left_join(
+                    submissions::table.on(batches::batch_id
+                        .eq(submissions::batch_id)
+                        .and(batches::service_id.eq(submissions::service_id))),
+                )
+                .filter(batches::service_id.eq(service_id))
+                .filter(batch_statuses::dlt_status.eq_any(failed_statuses))
+                .select((
+                    batches::all_columns,
+                    batch_statuses::all_columns,
+                    submissions::all_columns.nullable(),
+                ))
+                .into_boxed();
+
+            // Batches are paged on (created_at, batch_id), so the cursor's
+            // position is found from the created_at of the cursor batch
+            if let Some(after) = after {
+                let after_created_at: i64 = batches::table
+                    .select(batches::created_at)
+                    .filter(
+                        batches::batch_id
+                            .eq(after)
+                            .and(batches::service_id.eq(service_id)),
+                    )
+                    .first::<i64>(self.conn)
+                    .optional()?
+                    .ok_or_else(|| {
+                        BatchTrackingStoreError::InvalidArgumentError(InvalidArgumentError::new(
+                            "after".to_string(),
+                            format!("Could not find batch with ID {}", after),
+                        ))
+                    })?;
+
+                query = query.filter(
+                    batches::created_at
+                        .gt(after_created_at)
+                        .or(batches::created_at
+                            .eq(after_created_at)
+                            .and(batches::batch_id.gt(after))),
+                );
+            }
+
+            let batch_results: Vec<(BatchModel, BatchStatusModel, Option<SubmissionModel>)> = query
+                .order((batches::created_at.asc(), batches::batch_id.asc()))
+                .limit(limit)
+                .load(self.conn)?;
+
+            let mut batch_models = Vec::new();
+            let mut batch_status_models = Vec::new();
+            let mut submission_models = Vec::new();
+
+            for (batch, status, submission) in batch_results {
+                batch_models.push(batch);
+                batch_status_models.push(status);
+                if let Some(submission) = submission {
+                    submission_models.push(submission);
+                }
+            }
+
+            let batch_ids: Vec<&str> = batch_models.iter().map(|b| b.batch_id.as_str()).collect();
+
+            let txn_models: Vec<TransactionModel> = transactions::table
+                .filter(transactions::batch_id.eq_any(&batch_ids))
+                .filter(transactions::service_id.eq(service_id))
+                .load(self.conn)?;
+            let txn_models = self.decrypt_payloads(txn_models)?;
+
+            let txn_ids: Vec<&str> = txn_models
+                .iter()
+                .map(|t| t.transaction_id.as_str())
+                .collect();
+
+            let receipt_models: Vec<TransactionReceiptModel> = transaction_receipts::table
+                .filter(transaction_receipts::transaction_id.eq_any(&txn_ids))
+                .filter(transaction_receipts::service_id.eq(service_id))
+                .load(self.conn)?;
+
+            let address_models: Vec<TransactionAddressModel> = transaction_addresses::table
+                .filter(transaction_addresses::transaction_id.eq_any(&txn_ids))
+                .filter(transaction_addresses::service_id.eq(service_id))
+                .load(self.conn)?;
+
+            TrackingBatchList::try_from((
+                batch_models,
+                batch_status_models,
+                txn_models,
+                address_models,
+                receipt_models,
+                submission_models,
+            ))
+        })
+    }
+}
@@ -0,0 +1,60 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::{
+    diesel::{models::OutboxModel, schema::outbox},
+    BatchTrackingStoreError, OutboxEvent,
+};
+use diesel::prelude::*;
+use std::convert::TryFrom;
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreFetchOutboxOperation {
+    fn fetch_outbox(&self, limit: i64) -> Result<Vec<OutboxEvent>, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreFetchOutboxOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn fetch_outbox(&self, limit: i64) -> Result<Vec<OutboxEvent>, BatchTrackingStoreError> {
+        self.transaction("fetch_outbox", || {
+            outbox::table
+                .order(outbox::id.asc())
+                .limit(limit)
+                .load::<OutboxModel>(self.conn)?
+                .into_iter()
+                .map(OutboxEvent::try_from)
+                .collect()
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreFetchOutboxOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn fetch_outbox(&self, limit: i64) -> Result<Vec<OutboxEvent>, BatchTrackingStoreError> {
+        self.transaction("fetch_outbox", || {
+            outbox::table
+                .order(outbox::id.asc())
+                .limit(limit)
+                .load::<OutboxModel>(self.conn)?
+                .into_iter()
+                .map(OutboxEvent::try_from)
+                .collect()
+        })
+    }
+}
@@ -0,0 +1,122 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{BTreeMap, HashMap};
+
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::{
+    diesel::schema::{transaction_receipts, transactions},
+    BatchTrackingStoreError,
+};
+
+use diesel::prelude::*;
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreFindBatchesWithExcessReceiptsOperation
+{
+    fn find_batches_with_excess_receipts(
+        &self,
+        service_id: &str,
+    ) -> Result<Vec<String>, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreFindBatchesWithExcessReceiptsOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn find_batches_with_excess_receipts(
+        &self,
+        service_id: &str,
+    ) -> Result<Vec<String>, BatchTrackingStoreError> {
+        self.transaction("find_batches_with_excess_receipts", || {
+            let txns: Vec<(String, String)> = transactions::table
+                .select((transactions::batch_id, transactions::transaction_id))
+                .filter(transactions::service_id.eq(&service_id))
+                .load(self.conn)?;
+
+            let receipt_txn_ids: Vec<String> = transaction_receipts::table
+                .select(transaction_receipts::transaction_id)
+                .filter(transaction_receipts::service_id.eq(&service_id))
+                .load(self.conn)?;
+
+            let batch_ids: HashMap<&str, &str> = txns
+                .iter()
+                .map(|(batch_id, txn_id)| (txn_id.as_str(), batch_id.as_str()))
+                .collect();
+
+            // Compare the number of transactions in each batch to the number
+            // of receipts recorded for those transactions
+            let mut counts: BTreeMap<&str, (usize, usize)> = BTreeMap::new();
+            for (batch_id, _) in &txns {
+                counts.entry(batch_id).or_insert((0, 0)).0 += 1;
+            }
+            for txn_id in &receipt_txn_ids {
+                if let Some(batch_id) = batch_ids.get(txn_id.as_str()) {
+                    counts.entry(batch_id).or_insert((0, 0)).1 += 1;
+                }
+            }
+
+            Ok(counts
+                .into_iter()
+                .filter(|(_, (txn_count, receipt_count))| receipt_count > txn_count)
+                .map(|(batch_id, _)| batch_id.to_string())
+                .collect())
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreFindBatchesWithExcessReceiptsOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn find_batches_with_excess_receipts(
+        &self,
+        service_id: &str,
+    ) -> Result<Vec<String>, BatchTrackingStoreError> {
+        self.transaction("find_batches_with_excess_receipts", || {
+            let txns: Vec<(String, String)> = transactions::table
+                .select((transactions::batch_id, transactions::transaction_id))
+                .filter(transactions::service_id.eq(&service_id))
+                .load(self.conn)?;
+
+            let receipt_txn_ids: Vec<String> = transaction_receipts::table
+                .select(transaction_receipts::transaction_id)
+                .filter(transaction_receipts::service_id.eq(&service_id))
+                .load(self.conn)?;
+
+            let batch_ids: HashMap<&str, &str> = txns
+                .iter()
+                .map(|(batch_id, txn_id)| (txn_id.as_str(), batch_id.as_str()))
+                .collect();
+
+            // Compare the number of transactions in each batch to the number
+            // of receipts recorded for those transactions
+            let mut counts: BTreeMap<&str, (usize, usize)> = BTreeMap::new();
+            for (batch_id, _) in &txns {
+                counts.entry(batch_id).or_insert((0, 0)).0 += 1;
+            }
+            for txn_id in &receipt_txn_ids {
+                if let Some(batch_id) = batch_ids.get(txn_id.as_str()) {
+                    counts.entry(batch_id).or_insert((0, 0)).1 += 1;
+                }
+            }
+
+            Ok(counts
+                .into_iter()
+                .filter(|(_, (txn_count, receipt_count))| receipt_count > txn_count)
+                .map(|(batch_id, _)| batch_id.to_string())
+                .collect())
+        })
+    }
+}
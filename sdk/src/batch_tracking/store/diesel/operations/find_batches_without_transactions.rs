@@ -0,0 +1,80 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::{
+    diesel::schema::{batches, transactions},
+    BatchTrackingStoreError,
+};
+
+use diesel::prelude::*;
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreFindBatchesWithoutTransactionsOperation
+{
+    fn find_batches_without_transactions(
+        &self,
+        service_id: &str,
+    ) -> Result<Vec<String>, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreFindBatchesWithoutTransactionsOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn find_batches_without_transactions(
+        &self,
+        service_id: &str,
+    ) -> Result<Vec<String>, BatchTrackingStoreError> {
+        self.transaction("find_batches_without_transactions", || {
+            batches::table
+                .left_join(
+                    transactions::table.on(batches::batch_id
+                        .eq(transactions::batch_id)
+                        .and(batches::service_id.eq(transactions::service_id))),
+                )
+                .filter(batches::service_id.eq(service_id))
+                .filter(transactions::transaction_id.is_null())
+                .order(batches::batch_id.asc())
+                .select(batches::batch_id)
+                .load(self.conn)
+                .map_err(BatchTrackingStoreError::from)
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreFindBatchesWithoutTransactionsOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn find_batches_without_transactions(
+        &self,
+        service_id: &str,
+    ) -> Result<Vec<String>, BatchTrackingStoreError> {
+        self.transaction("find_batches_without_transactions", || {
+            batches::table
+                .left_join(
+                    transactions::table.on(batches::batch_id
+                        .eq(transactions::batch_id)
+                        .and(batches::service_id.eq(transactions::service_id))),
+                )
+                .filter(batches::service_id.eq(service_id))
+                .filter(transactions::transaction_id.is_null())
+                .order(batches::batch_id.asc())
+                .select(batches::batch_id)
+                .load(self.conn)
+                .map_err(BatchTrackingStoreError::from)
+        })
+    }
+}
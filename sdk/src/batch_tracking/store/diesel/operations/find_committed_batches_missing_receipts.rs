@@ -0,0 +1,170 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{HashMap, HashSet};
+
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::{
+    diesel::schema::{batch_statuses, transaction_receipts, transactions},
+    BatchStatusName, BatchTrackingStoreError,
+};
+
+use diesel::prelude::*;
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreFindCommittedBatchesMissingReceiptsOperation
+{
+    fn find_committed_batches_missing_receipts(
+        &self,
+        service_id: &str,
+    ) -> Result<Vec<String>, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreFindCommittedBatchesMissingReceiptsOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn find_committed_batches_missing_receipts(
+        &self,
+        service_id: &str,
+    ) -> Result<Vec<String>, BatchTrackingStoreError> {
+        self.transaction("find_committed_batches_missing_receipts", || {
+            let committed_ids: Vec<String> = batch_statuses::table
+                .select(batch_statuses::batch_id)
+                .filter(
+                    batch_statuses::service_id
+                        .eq(&service_id)
+                        .and(batch_statuses::dlt_status.eq(BatchStatusName::Committed.to_string())),
+                )
+                .order(batch_statuses::batch_id)
+                .load(self.conn)?;
+
+            if committed_ids.is_empty() {
+                return Ok(Vec::new());
+            }
+
+            let txns: Vec<(String, String)> = transactions::table
+                .select((transactions::batch_id, transactions::transaction_id))
+                .filter(
+                    transactions::service_id
+                        .eq(&service_id)
+                        .and(transactions::batch_id.eq_any(&committed_ids)),
+                )
+                .load(self.conn)?;
+
+            let receipt_ids: HashSet<String> = transaction_receipts::table
+                .select(transaction_receipts::transaction_id)
+                .filter(
+                    transaction_receipts::service_id.eq(&service_id).and(
+                        transaction_receipts::transaction_id
+                            .eq_any(txns.iter().map(|(_, txn_id)| txn_id)),
+                    ),
+                )
+                .load::<String>(self.conn)?
+                .into_iter()
+                .collect();
+
+            // Compare the number of transactions in each committed batch to
+            // the number of those transactions that have a receipt
+            let mut counts: HashMap<&str, (usize, usize)> = HashMap::new();
+            for (batch_id, txn_id) in &txns {
+                let (txn_count, receipt_count) = counts.entry(batch_id).or_insert((0, 0));
+                *txn_count += 1;
+                if receipt_ids.contains(txn_id) {
+                    *receipt_count += 1;
+                }
+            }
+
+            Ok(committed_ids
+                .iter()
+                .filter(|id| {
+                    counts
+                        .get(id.as_str())
+                        .map(|(txn_count, receipt_count)| txn_count != receipt_count)
+                        .unwrap_or(false)
+                })
+                .cloned()
+                .collect())
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreFindCommittedBatchesMissingReceiptsOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn find_committed_batches_missing_receipts(
+        &self,
+        service_id: &str,
+    ) -> Result<Vec<String>, BatchTrackingStoreError> {
+        self.transaction("find_committed_batches_missing_receipts", || {
+            let committed_ids: Vec<String> = batch_statuses::table
+                .select(batch_statuses::batch_id)
+                .filter(
+                    batch_statuses::service_id
+                        .eq(&service_id)
+                        .and(batch_statuses::dlt_status.eq(BatchStatusName::Committed.to_string())),
+                )
+                .order(batch_statuses::batch_id)
+                .load(self.conn)?;
+
+            if committed_ids.is_empty() {
+                return Ok(Vec::new());
+            }
+
+            let txns: Vec<(String, String)> = transactions::table
+                .select((transactions::batch_id, transactions::transaction_id))
+                .filter(
+                    transactions::service_id
+                        .eq(&service_id)
+                        .and(transactions::batch_id.eq_any(&committed_ids)),
+                )
+                .load(self.conn)?;
+
+            let receipt_ids: HashSet<String> = transaction_receipts::table
+                .select(transaction_receipts::transaction_id)
+                .filter(
+                    transaction_receipts::service_id.eq(&service_id).and(
+                        transaction_receipts::transaction_id
+                            .eq_any(txns.iter().map(|(_, txn_id)| txn_id)),
+                    ),
+                )
+                .load::<String>(self.conn)?
+                .into_iter()
+                .collect();
+
+            // Compare the number of transactions in each committed batch to
+            // the number of those transactions that have a receipt
+            let mut counts: HashMap<&str, (usize, usize)> = HashMap::new();
+            for (batch_id, txn_id) in &txns {
+                let (txn_count, receipt_count) = counts.entry(batch_id).or_insert((0, 0));
+                *txn_count += 1;
+                if receipt_ids.contains(txn_id) {
+                    *receipt_count += 1;
+                }
+            }
+
+            Ok(committed_ids
+                .iter()
+                .filter(|id| {
+                    counts
+                        .get(id.as_str())
+                        .map(|(txn_count, receipt_count)| txn_count != receipt_count)
+                        .unwrap_or(false)
+                })
+                .cloned()
+                .collect())
+        })
+    }
+}
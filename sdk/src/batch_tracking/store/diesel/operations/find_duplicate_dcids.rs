@@ -0,0 +1,96 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::{diesel::schema::batches, BatchTrackingStoreError};
+
+use diesel::prelude::*;
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreFindDuplicateDcidsOperation {
+    fn find_duplicate_dcids(
+        &self,
+        service_id: &str,
+    ) -> Result<Vec<(String, Vec<String>)>, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreFindDuplicateDcidsOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn find_duplicate_dcids(
+        &self,
+        service_id: &str,
+    ) -> Result<Vec<(String, Vec<String>)>, BatchTrackingStoreError> {
+        self.transaction("find_duplicate_dcids", || {
+            let pairs: Vec<(Option<String>, String)> = batches::table
+                .select((batches::data_change_id, batches::batch_id))
+                .filter(batches::service_id.eq(&service_id))
+                .filter(batches::data_change_id.is_not_null())
+                .load(self.conn)?;
+
+            let mut batch_ids: BTreeMap<String, Vec<String>> = BTreeMap::new();
+            for (dcid, batch_id) in pairs {
+                if let Some(dcid) = dcid {
+                    batch_ids.entry(dcid).or_default().push(batch_id);
+                }
+            }
+
+            Ok(batch_ids
+                .into_iter()
+                .filter(|(_, batch_ids)| batch_ids.len() > 1)
+                .map(|(dcid, mut batch_ids)| {
+                    batch_ids.sort();
+                    (dcid, batch_ids)
+                })
+                .collect())
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreFindDuplicateDcidsOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn find_duplicate_dcids(
+        &self,
+        service_id: &str,
+    ) -> Result<Vec<(String, Vec<String>)>, BatchTrackingStoreError> {
+        self.transaction("find_duplicate_dcids", || {
+            let pairs: Vec<(Option<String>, String)> = batches::table
+                .select((batches::data_change_id, batches::batch_id))
+                .filter(batches::service_id.eq(&service_id))
+                .filter(batches::data_change_id.is_not_null())
+                .load(self.conn)?;
+
+            let mut batch_ids: BTreeMap<String, Vec<String>> = BTreeMap::new();
+            for (dcid, batch_id) in pairs {
+                if let Some(dcid) = dcid {
+                    batch_ids.entry(dcid).or_default().push(batch_id);
+                }
+            }
+
+            Ok(batch_ids
+                .into_iter()
+                .filter(|(_, batch_ids)| batch_ids.len() > 1)
+                .map(|(dcid, mut batch_ids)| {
+                    batch_ids.sort();
+                    (dcid, batch_ids)
+                })
+                .collect())
+        })
+    }
+}
@@ -0,0 +1,109 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::BatchTrackingStoreOperations;
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::batch_tracking::store::{diesel::schema::batch_status_events, BatchTrackingStoreError};
+use diesel::prelude::*;
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreFindFlappingBatchesOperation {
+    fn find_flapping_batches(
+        &self,
+        min_transitions: i64,
+        since: i64,
+    ) -> Result<Vec<String>, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreFindFlappingBatchesOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn find_flapping_batches(
+        &self,
+        min_transitions: i64,
+        since: i64,
+    ) -> Result<Vec<String>, BatchTrackingStoreError> {
+        self.transaction("find_flapping_batches", || {
+            let events: Vec<(String, String, String)> = batch_status_events::table
+                .select((
+                    batch_status_events::service_id,
+                    batch_status_events::batch_id,
+                    batch_status_events::dlt_status,
+                ))
+                .filter(batch_status_events::created_at.ge(since))
+                .order(batch_status_events::id.asc())
+                .load(self.conn)?;
+
+            Ok(count_flapping_batches(events, min_transitions))
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreFindFlappingBatchesOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn find_flapping_batches(
+        &self,
+        min_transitions: i64,
+        since: i64,
+    ) -> Result<Vec<String>, BatchTrackingStoreError> {
+        self.transaction("find_flapping_batches", || {
+            let events: Vec<(String, String, String)> = batch_status_events::table
+                .select((
+                    batch_status_events::service_id,
+                    batch_status_events::batch_id,
+                    batch_status_events::dlt_status,
+                ))
+                .filter(batch_status_events::created_at.ge(since))
+                .order(batch_status_events::id.asc())
+                .load(self.conn)?;
+
+            Ok(count_flapping_batches(events, min_transitions))
+        })
+    }
+}
+
+/// Returns the IDs of the batches whose status changed more than
+/// `min_transitions` times in a list of (service ID, batch ID, status)
+/// events ordered from oldest to newest
+///
+/// A status being set to the value it already had is not a change.
+fn count_flapping_batches(
+    events: Vec<(String, String, String)>,
+    min_transitions: i64,
+) -> Vec<String> {
+    let mut last_status: BTreeMap<(String, String), String> = BTreeMap::new();
+    let mut transitions: BTreeMap<(String, String), i64> = BTreeMap::new();
+
+    for (service_id, batch_id, status) in events {
+        let key = (service_id, batch_id);
+        if let Some(last) = last_status.get(&key) {
+            if *last != status {
+                *transitions.entry(key.clone()).or_insert(0) += 1;
+            }
+        }
+        last_status.insert(key, status);
+    }
+
+    transitions
+        .into_iter()
+        .filter(|(_, count)| *count > min_transitions)
+        .map(|((_, batch_id), _)| batch_id)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
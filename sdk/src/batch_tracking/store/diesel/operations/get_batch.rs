@@ -12,15 +12,19 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use super::resolve_data_change_id::BatchTrackingStoreResolveDataChangeIdOperation;
 use super::BatchTrackingStoreOperations;
 use crate::error::InternalError;
 
 use crate::batch_tracking::store::diesel::{
     models::{
-        is_data_change_id, BatchModel, BatchStatusModel, SubmissionModel, TransactionModel,
-        TransactionReceiptModel,
+        is_data_change_id, BatchModel, BatchStatusModel, SubmissionModel, TransactionAddressModel,
+        TransactionModel, TransactionReceiptModel,
+    },
+    schema::{
+        batch_statuses, batches, submissions, transaction_addresses, transaction_receipts,
+        transactions,
     },
-    schema::{batch_statuses, batches, submissions, transaction_receipts, transactions},
     BatchStatus, InvalidTransaction, SubmissionError, TrackingBatch, TrackingTransaction,
     TransactionReceipt, ValidTransaction,
 };
@@ -46,11 +50,19 @@ impl<'a> BatchTrackingStoreGetBatchOperation
         id: &str,
         service_id: &str,
     ) -> Result<Option<TrackingBatch>, BatchTrackingStoreError> {
-        self.conn.transaction::<_, BatchTrackingStoreError, _>(|| {
+        self.transaction("get_batch", || {
+            let mut batch_id = id.to_string();
+            if is_data_change_id(id)? {
+                match self.resolve_data_change_id(id, service_id)? {
+                    Some(found) => batch_id = found,
+                    None => return Ok(None),
+                }
+            }
+
             // This performs a query to select all columns from the batches,
             // batch_statuses, and submissions tables joined on the batch_id
             // column. These rows are then filtered on the batch_id.
-            let mut query = batches::table
+            let query = batches::table
                 .into_boxed()
                 .left_join(
                     batch_statuses::table.on(batches::batch_id
@@ -66,22 +78,12 @@ impl<'a> BatchTrackingStoreGetBatchOperation
                     batches::all_columns,
                     batch_statuses::all_columns.nullable(),
                     submissions::all_columns.nullable(),
-                ));
-
-            let is_dcid = is_data_change_id(id)?;
-            if is_dcid {
-                query = query.filter(
-                    batches::data_change_id
-                        .eq(&id)
-                        .and(batches::service_id.eq(&service_id)),
-                );
-            } else {
-                query = query.filter(
+                ))
+                .filter(
                     batches::batch_id
-                        .eq(&id)
+                        .eq(&batch_id)
                         .and(batches::service_id.eq(&service_id)),
                 );
-            }
 
             // Diesel will deserialize the joined results into the respective
             // models for the tables in the join.
@@ -123,6 +125,16 @@ impl<'a> BatchTrackingStoreGetBatchOperation
                             Box::new(err),
                         ))
                     })?;
+                let txn_models = self.decrypt_payloads(txn_models)?;
+
+                let address_models: Vec<TransactionAddressModel> = transaction_addresses::table
+                    .filter(
+                        transaction_addresses::service_id.eq(&service_id).and(
+                            transaction_addresses::transaction_id
+                                .eq_any(txn_models.iter().map(|t| t.transaction_id.as_str())),
+                        ),
+                    )
+                    .load(self.conn)?;
 
                 let mut txns = Vec::new();
                 let mut txn_ids = Vec::new();
@@ -130,7 +142,7 @@ impl<'a> BatchTrackingStoreGetBatchOperation
                 let mut invalid_txns = Vec::new();
 
                 for t in txn_models {
-                    txns.push(TrackingTransaction::from(&t));
+                    txns.push(TrackingTransaction::from((&t, address_models.as_slice())));
                     txn_ids.push(t.transaction_id.to_string());
                 }
 
@@ -194,11 +206,19 @@ impl<'a> BatchTrackingStoreGetBatchOperation
         id: &str,
         service_id: &str,
     ) -> Result<Option<TrackingBatch>, BatchTrackingStoreError> {
-        self.conn.transaction::<_, BatchTrackingStoreError, _>(|| {
+        self.transaction("get_batch", || {
+            let mut batch_id = id.to_string();
+            if is_data_change_id(id)? {
+                match self.resolve_data_change_id(id, service_id)? {
+                    Some(found) => batch_id = found,
+                    None => return Ok(None),
+                }
+            }
+
             // This performs a query to select all columns from the batches,
             // batch_statuses, and submissions tables joined on the batch_id
             // column. These rows are then filtered on the batch_id.
-            let mut query = batches::table
+            let query = batches::table
                 .into_boxed()
                 .left_join(
                     batch_statuses::table.on(batches::batch_id
@@ -214,22 +234,12 @@ impl<'a> BatchTrackingStoreGetBatchOperation
                     batches::all_columns,
                     batch_statuses::all_columns.nullable(),
                     submissions::all_columns.nullable(),
-                ));
-
-            let is_dcid = is_data_change_id(id)?;
-            if is_dcid {
-                query = query.filter(
-                    batches::data_change_id
-                        .eq(&id)
-                        .and(batches::service_id.eq(&service_id)),
-                );
-            } else {
-                query = query.filter(
+                ))
+                .filter(
                     batches::batch_id
-                        .eq(&id)
+                        .eq(&batch_id)
                         .and(batches::service_id.eq(&service_id)),
                 );
-            }
 
             // Diesel will deserialize the joined results into the respective
             // models for the tables in the join.
@@ -271,6 +281,16 @@ impl<'a> BatchTrackingStoreGetBatchOperation
                             Box::new(err),
                         ))
                     })?;
+                let txn_models = self.decrypt_payloads(txn_models)?;
+
+                let address_models: Vec<TransactionAddressModel> = transaction_addresses::table
+                    .filter(
+                        transaction_addresses::service_id.eq(&service_id).and(
+                            transaction_addresses::transaction_id
+                                .eq_any(txn_models.iter().map(|t| t.transaction_id.as_str())),
+                        ),
+                    )
+                    .load(self.conn)?;
 
                 let mut txns = Vec::new();
                 let mut txn_ids = Vec::new();
@@ -278,7 +298,7 @@ impl<'a> BatchTrackingStoreGetBatchOperation
                 let mut invalid_txns = Vec::new();
 
                 for t in txn_models {
-                    txns.push(TrackingTransaction::from(&t));
+                    txns.push(TrackingTransaction::from((&t, address_models.as_slice())));
                     txn_ids.push(t.transaction_id.to_string());
                 }
 
@@ -0,0 +1,96 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::get_batch::BatchTrackingStoreGetBatchOperation;
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::{
+    diesel::{schema::transactions, TrackingBatch},
+    BatchTrackingStoreError,
+};
+
+use diesel::prelude::*;
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreGetBatchByTransactionIdOperation
+{
+    fn get_batch_by_transaction_id(
+        &self,
+        transaction_id: &str,
+        service_id: &str,
+    ) -> Result<Vec<TrackingBatch>, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreGetBatchByTransactionIdOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn get_batch_by_transaction_id(
+        &self,
+        transaction_id: &str,
+        service_id: &str,
+    ) -> Result<Vec<TrackingBatch>, BatchTrackingStoreError> {
+        self.transaction("get_batch_by_transaction_id", || {
+            let batch_ids: Vec<String> = transactions::table
+                .select(transactions::batch_id)
+                .filter(
+                    transactions::transaction_id
+                        .eq(&transaction_id)
+                        .and(transactions::service_id.eq(&service_id)),
+                )
+                .order(transactions::batch_id.asc())
+                .load(self.conn)?;
+
+            let mut batches = Vec::new();
+            for batch_id in batch_ids {
+                if let Some(batch) = self.get_batch(&batch_id, service_id)? {
+                    batches.push(batch);
+                }
+            }
+
+            Ok(batches)
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreGetBatchByTransactionIdOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn get_batch_by_transaction_id(
+        &self,
+        transaction_id: &str,
+        service_id: &str,
+    ) -> Result<Vec<TrackingBatch>, BatchTrackingStoreError> {
+        self.transaction("get_batch_by_transaction_id", || {
+            let batch_ids: Vec<String> = transactions::table
+                .select(transactions::batch_id)
+                .filter(
+                    transactions::transaction_id
+                        .eq(&transaction_id)
+                        .and(transactions::service_id.eq(&service_id)),
+                )
+                .order(transactions::batch_id.asc())
+                .load(self.conn)?;
+
+            let mut batches = Vec::new();
+            for batch_id in batch_ids {
+                if let Some(batch) = self.get_batch(&batch_id, service_id)? {
+                    batches.push(batch);
+                }
+            }
+
+            Ok(batches)
+        })
+    }
+}
@@ -0,0 +1,334 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::resolve_data_change_id::BatchTrackingStoreResolveDataChangeIdOperation;
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::diesel::{
+    models::{
+        is_data_change_id, BatchModel, BatchStatusModel, SubmissionModel, TransactionAddressModel,
+        TransactionModel, TransactionReceiptModel,
+    },
+    schema::{
+        batch_statuses, batches, submissions, transaction_addresses, transaction_receipts,
+        transactions,
+    },
+    BatchStatus, InvalidTransaction, SubmissionError, TrackingBatch, TrackingTransaction,
+    TransactionReceipt, ValidTransaction,
+};
+
+use crate::batch_tracking::store::{BatchProjection, BatchStatusName, BatchTrackingStoreError};
+use diesel::prelude::*;
+use std::convert::TryFrom;
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreGetBatchProjectedOperation {
+    fn get_batch_projected(
+        &self,
+        id: &str,
+        service_id: &str,
+        projection: BatchProjection,
+    ) -> Result<Option<TrackingBatch>, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreGetBatchProjectedOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn get_batch_projected(
+        &self,
+        id: &str,
+        service_id: &str,
+        projection: BatchProjection,
+    ) -> Result<Option<TrackingBatch>, BatchTrackingStoreError> {
+        self.transaction("get_batch_projected", || {
+            let mut batch_id = id.to_string();
+            if is_data_change_id(id)? {
+                match self.resolve_data_change_id(id, service_id)? {
+                    Some(found) => batch_id = found,
+                    None => return Ok(None),
+                }
+            }
+
+            let batch: BatchModel = match batches::table
+                .filter(batches::service_id.eq(service_id))
+                .filter(batches::batch_id.eq(&batch_id))
+                .first(self.conn)
+                .optional()?
+            {
+                Some(batch) => batch,
+                None => return Ok(None),
+            };
+
+            let txns = if projection.include_transactions() {
+                let txn_models: Vec<TransactionModel> = transactions::table
+                    .filter(
+                        transactions::batch_id
+                            .eq(&batch.batch_id)
+                            .and(transactions::service_id.eq(service_id)),
+                    )
+                    .load(self.conn)?;
+                let txn_models = self.decrypt_payloads(txn_models)?;
+
+                let address_models: Vec<TransactionAddressModel> = transaction_addresses::table
+                    .filter(
+                        transaction_addresses::service_id.eq(service_id).and(
+                            transaction_addresses::transaction_id
+                                .eq_any(txn_models.iter().map(|t| t.transaction_id.as_str())),
+                        ),
+                    )
+                    .load(self.conn)?;
+
+                txn_models
+                    .iter()
+                    .map(|t| TrackingTransaction::from((t, address_models.as_slice())))
+                    .collect()
+            } else {
+                Vec::new()
+            };
+
+            let status = if projection.include_status() {
+                let status_model: Option<BatchStatusModel> = batch_statuses::table
+                    .filter(
+                        batch_statuses::batch_id
+                            .eq(&batch.batch_id)
+                            .and(batch_statuses::service_id.eq(service_id)),
+                    )
+                    .first(self.conn)
+                    .optional()?;
+
+                match status_model {
+                    Some(status_model) if projection.include_receipts() => {
+                        let txn_ids: Vec<String> = transactions::table
+                            .select(transactions::transaction_id)
+                            .filter(
+                                transactions::batch_id
+                                    .eq(&batch.batch_id)
+                                    .and(transactions::service_id.eq(service_id)),
+                            )
+                            .load(self.conn)?;
+
+                        let receipt_models: Vec<TransactionReceiptModel> =
+                            transaction_receipts::table
+                                .filter(
+                                    transaction_receipts::service_id
+                                        .eq(service_id)
+                                        .and(transaction_receipts::transaction_id.eq_any(txn_ids)),
+                                )
+                                .load(self.conn)?;
+
+                        let mut valid_txns = Vec::new();
+                        let mut invalid_txns = Vec::new();
+
+                        for rcpt in receipt_models {
+                            if rcpt.result_valid {
+                                valid_txns.push(ValidTransaction::try_from(
+                                    TransactionReceipt::from(rcpt),
+                                )?);
+                            } else {
+                                invalid_txns.push(InvalidTransaction::try_from(
+                                    TransactionReceipt::from(rcpt),
+                                )?);
+                            }
+                        }
+
+                        Some(BatchStatus::try_from((
+                            status_model,
+                            invalid_txns,
+                            valid_txns,
+                        ))?)
+                    }
+                    Some(status_model) => Some(status_without_receipts(&status_model.dlt_status)?),
+                    None => None,
+                }
+            } else {
+                None
+            };
+
+            let sub_err = if projection.include_submission_error() {
+                let submission: Option<SubmissionModel> = submissions::table
+                    .filter(
+                        submissions::batch_id
+                            .eq(&batch.batch_id)
+                            .and(submissions::service_id.eq(service_id)),
+                    )
+                    .first(self.conn)
+                    .optional()?;
+
+                match submission {
+                    Some(sub) if sub.error_type.is_some() && sub.error_message.is_some() => {
+                        Some(SubmissionError::try_from(&sub)?)
+                    }
+                    _ => None,
+                }
+            } else {
+                None
+            };
+
+            Ok(Some(TrackingBatch::from((batch, txns, status, sub_err))))
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreGetBatchProjectedOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn get_batch_projected(
+        &self,
+        id: &str,
+        service_id: &str,
+        projection: BatchProjection,
+    ) -> Result<Option<TrackingBatch>, BatchTrackingStoreError> {
+        self.transaction("get_batch_projected", || {
+            let mut batch_id = id.to_string();
+            if is_data_change_id(id)? {
+                match self.resolve_data_change_id(id, service_id)? {
+                    Some(found) => batch_id = found,
+                    None => return Ok(None),
+                }
+            }
+
+            let batch: BatchModel = match batches::table
+                .filter(batches::service_id.eq(service_id))
+                .filter(batches::batch_id.eq(&batch_id))
+                .first(self.conn)
+                .optional()?
+            {
+                Some(batch) => batch,
+                None => return Ok(None),
+            };
+
+            let txns = if projection.include_transactions() {
+                let txn_models: Vec<TransactionModel> = transactions::table
+                    .filter(
+                        transactions::batch_id
+                            .eq(&batch.batch_id)
+                            .and(transactions::service_id.eq(service_id)),
+                    )
+                    .load(self.conn)?;
+                let txn_models = self.decrypt_payloads(txn_models)?;
+
+                let address_models: Vec<TransactionAddressModel> = transaction_addresses::table
+                    .filter(
+                        transaction_addresses::service_id.eq(service_id).and(
+                            transaction_addresses::transaction_id
+                                .eq_any(txn_models.iter().map(|t| t.transaction_id.as_str())),
+                        ),
+                    )
+                    .load(self.conn)?;
+
+                txn_models
+                    .iter()
+                    .map(|t| TrackingTransaction::from((t, address_models.as_slice())))
+                    .collect()
+            } else {
+                Vec::new()
+            };
+
+            let status = if projection.include_status() {
+                let status_model: Option<BatchStatusModel> = batch_statuses::table
+                    .filter(
+                        batch_statuses::batch_id
+                            .eq(&batch.batch_id)
+                            .and(batch_statuses::service_id.eq(service_id)),
+                    )
+                    .first(self.conn)
+                    .optional()?;
+
+                match status_model {
+                    Some(status_model) if projection.include_receipts() => {
+                        let txn_ids: Vec<String> = transactions::table
+                            .select(transactions::transaction_id)
+                            .filter(
+                                transactions::batch_id
+                                    .eq(&batch.batch_id)
+                                    .and(transactions::service_id.eq(service_id)),
+                            )
+                            .load(self.conn)?;
+
+                        let receipt_models: Vec<TransactionReceiptModel> =
+                            transaction_receipts::table
+                                .filter(
+                                    transaction_receipts::service_id
+                                        .eq(service_id)
+                                        .and(transaction_receipts::transaction_id.eq_any(txn_ids)),
+                                )
+                                .load(self.conn)?;
+
+                        let mut valid_txns = Vec::new();
+                        let mut invalid_txns = Vec::new();
+
+                        for rcpt in receipt_models {
+                            if rcpt.result_valid {
+                                valid_txns.push(ValidTransaction::try_from(
+                                    TransactionReceipt::from(rcpt),
+                                )?);
+                            } else {
+                                invalid_txns.push(InvalidTransaction::try_from(
+                                    TransactionReceipt::from(rcpt),
+                                )?);
+                            }
+                        }
+
+                        Some(BatchStatus::try_from((
+                            status_model,
+                            invalid_txns,
+                            valid_txns,
+                        ))?)
+                    }
+                    Some(status_model) => Some(status_without_receipts(&status_model.dlt_status)?),
+                    None => None,
+                }
+            } else {
+                None
+            };
+
+            let sub_err = if projection.include_submission_error() {
+                let submission: Option<SubmissionModel> = submissions::table
+                    .filter(
+                        submissions::batch_id
+                            .eq(&batch.batch_id)
+                            .and(submissions::service_id.eq(service_id)),
+                    )
+                    .first(self.conn)
+                    .optional()?;
+
+                match submission {
+                    Some(sub) if sub.error_type.is_some() && sub.error_message.is_some() => {
+                        Some(SubmissionError::try_from(&sub)?)
+                    }
+                    _ => None,
+                }
+            } else {
+                None
+            };
+
+            Ok(Some(TrackingBatch::from((batch, txns, status, sub_err))))
+        })
+    }
+}
+
+/// Returns the status with the given name, without the transactions that
+/// would be built from the batch's receipts
+fn status_without_receipts(dlt_status: &str) -> Result<BatchStatus, BatchTrackingStoreError> {
+    Ok(match BatchStatusName::try_from_string(dlt_status)? {
+        BatchStatusName::Unknown => BatchStatus::Unknown,
+        BatchStatusName::Pending => BatchStatus::Pending,
+        BatchStatusName::Delayed => BatchStatus::Delayed,
+        BatchStatusName::Invalid => BatchStatus::Invalid(Vec::new()),
+        BatchStatusName::Valid => BatchStatus::Valid(Vec::new()),
+        BatchStatusName::Committed => BatchStatus::Committed(Vec::new()),
+        BatchStatusName::DeadLettered => BatchStatus::DeadLettered,
+    })
+}
@@ -12,12 +12,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use super::resolve_data_change_id::BatchTrackingStoreResolveDataChangeIdOperation;
 use super::BatchTrackingStoreOperations;
 use crate::error::InternalError;
 
 use crate::batch_tracking::store::diesel::{
     models::{is_data_change_id, BatchStatusModel, TransactionReceiptModel},
-    schema::{batch_statuses, batches, transaction_receipts, transactions},
+    schema::{batch_statuses, transaction_receipts, transactions},
     BatchStatus, InvalidTransaction, TransactionReceipt, ValidTransaction,
 };
 
@@ -42,18 +43,13 @@ impl<'a> BatchTrackingStoreGetBatchStatusOperation
         id: &str,
         service_id: &str,
     ) -> Result<Option<BatchStatus>, BatchTrackingStoreError> {
-        self.conn.transaction::<_, BatchTrackingStoreError, _>(|| {
+        self.transaction("get_batch_status", || {
             let mut batch_id = id.to_string();
             let is_dcid = is_data_change_id(id)?;
             if is_dcid {
-                batch_id = batches::table
-                    .select(batches::batch_id)
-                    .filter(
-                        batches::data_change_id
-                            .eq(&id)
-                            .and(batches::service_id.eq(&service_id)),
-                    )
-                    .first::<String>(self.conn)?;
+                batch_id = self
+                    .resolve_data_change_id(id, service_id)?
+                    .ok_or(diesel::result::Error::NotFound)?;
             }
 
             // This query fetches the batch status for the batch with the given
@@ -154,18 +150,13 @@ impl<'a> BatchTrackingStoreGetBatchStatusOperation
         id: &str,
         service_id: &str,
     ) -> Result<Option<BatchStatus>, BatchTrackingStoreError> {
-        self.conn.transaction::<_, BatchTrackingStoreError, _>(|| {
+        self.transaction("get_batch_status", || {
             let mut batch_id = id.to_string();
             let is_dcid = is_data_change_id(id)?;
             if is_dcid {
-                batch_id = batches::table
-                    .select(batches::batch_id)
-                    .filter(
-                        batches::data_change_id
-                            .eq(&id)
-                            .and(batches::service_id.eq(&service_id)),
-                    )
-                    .first::<String>(self.conn)?;
+                batch_id = self
+                    .resolve_data_change_id(id, service_id)?
+                    .ok_or(diesel::result::Error::NotFound)?;
             }
 
             // This query fetches the batch status for the batch with the given
@@ -0,0 +1,111 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::resolve_data_change_id::BatchTrackingStoreResolveDataChangeIdOperation;
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::{
+    diesel::{models::is_data_change_id, schema::batch_status_events},
+    BatchStatusName, BatchTrackingStoreError,
+};
+use diesel::prelude::*;
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreGetBatchStatusAtOperation {
+    fn get_batch_status_at(
+        &self,
+        id: &str,
+        service_id: &str,
+        at: i64,
+    ) -> Result<Option<BatchStatusName>, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreGetBatchStatusAtOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn get_batch_status_at(
+        &self,
+        id: &str,
+        service_id: &str,
+        at: i64,
+    ) -> Result<Option<BatchStatusName>, BatchTrackingStoreError> {
+        self.transaction("get_batch_status_at", || {
+            let mut batch_id = id.to_string();
+            if is_data_change_id(id)? {
+                batch_id = self
+                    .resolve_data_change_id(id, service_id)?
+                    .unwrap_or(batch_id);
+            }
+
+            // The status in effect is the one set by the last event at or
+            // before the given time
+            batch_status_events::table
+                .select(batch_status_events::dlt_status)
+                .filter(
+                    batch_status_events::batch_id
+                        .eq(&batch_id)
+                        .and(batch_status_events::service_id.eq(&service_id))
+                        .and(batch_status_events::created_at.le(at)),
+                )
+                .order((
+                    batch_status_events::created_at.desc(),
+                    batch_status_events::id.desc(),
+                ))
+                .first::<String>(self.conn)
+                .optional()?
+                .map(|status| BatchStatusName::try_from_string(&status))
+                .transpose()
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreGetBatchStatusAtOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn get_batch_status_at(
+        &self,
+        id: &str,
+        service_id: &str,
+        at: i64,
+    ) -> Result<Option<BatchStatusName>, BatchTrackingStoreError> {
+        self.transaction("get_batch_status_at", || {
+            let mut batch_id = id.to_string();
+            if is_data_change_id(id)? {
+                batch_id = self
+                    .resolve_data_change_id(id, service_id)?
+                    .unwrap_or(batch_id);
+            }
+
+            // The status in effect is the one set by the last event at or
+            // before the given time
+            batch_status_events::table
+                .select(batch_status_events::dlt_status)
+                .filter(
+                    batch_status_events::batch_id
+                        .eq(&batch_id)
+                        .and(batch_status_events::service_id.eq(&service_id))
+                        .and(batch_status_events::created_at.le(at)),
+                )
+                .order((
+                    batch_status_events::created_at.desc(),
+                    batch_status_events::id.desc(),
+                ))
+                .first::<String>(self.conn)
+                .optional()?
+                .map(|status| BatchStatusName::try_from_string(&status))
+                .transpose()
+        })
+    }
+}
@@ -0,0 +1,99 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::resolve_data_change_id::BatchTrackingStoreResolveDataChangeIdOperation;
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::diesel::{
+    models::{is_data_change_id, SubmissionModel},
+    schema::submissions,
+    BatchSubmissionInfo,
+};
+
+use crate::batch_tracking::store::BatchTrackingStoreError;
+use diesel::prelude::*;
+use std::convert::TryFrom;
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreGetBatchSubmissionInfoOperation
+{
+    fn get_batch_submission_info(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<Option<BatchSubmissionInfo>, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreGetBatchSubmissionInfoOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn get_batch_submission_info(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<Option<BatchSubmissionInfo>, BatchTrackingStoreError> {
+        self.transaction("get_batch_submission_info", || {
+            let mut batch_id = id.to_string();
+            if is_data_change_id(id)? {
+                match self.resolve_data_change_id(id, service_id)? {
+                    Some(found) => batch_id = found,
+                    None => return Ok(None),
+                }
+            }
+
+            submissions::table
+                .filter(
+                    submissions::batch_id
+                        .eq(&batch_id)
+                        .and(submissions::service_id.eq(&service_id)),
+                )
+                .first::<SubmissionModel>(self.conn)
+                .optional()?
+                .map(BatchSubmissionInfo::try_from)
+                .transpose()
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreGetBatchSubmissionInfoOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn get_batch_submission_info(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<Option<BatchSubmissionInfo>, BatchTrackingStoreError> {
+        self.transaction("get_batch_submission_info", || {
+            let mut batch_id = id.to_string();
+            if is_data_change_id(id)? {
+                match self.resolve_data_change_id(id, service_id)? {
+                    Some(found) => batch_id = found,
+                    None => return Ok(None),
+                }
+            }
+
+            submissions::table
+                .filter(
+                    submissions::batch_id
+                        .eq(&batch_id)
+                        .and(submissions::service_id.eq(&service_id)),
+                )
+                .first::<SubmissionModel>(self.conn)
+                .optional()?
+                .map(BatchSubmissionInfo::try_from)
+                .transpose()
+        })
+    }
+}
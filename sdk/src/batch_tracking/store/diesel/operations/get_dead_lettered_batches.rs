@@ -0,0 +1,195 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::diesel::{
+    models::{
+        BatchModel, BatchStatusModel, SubmissionModel, TransactionAddressModel, TransactionModel,
+        TransactionReceiptModel,
+    },
+    schema::{
+        batch_statuses, batches, submissions, transaction_addresses, transaction_receipts,
+        transactions,
+    },
+    BatchStatus, TrackingBatchList,
+};
+
+use crate::batch_tracking::store::BatchTrackingStoreError;
+use diesel::prelude::*;
+use std::convert::TryFrom;
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreGetDeadLetteredBatchesOperation
+{
+    fn get_dead_lettered_batches(
+        &self,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreGetDeadLetteredBatchesOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn get_dead_lettered_batches(
+        &self,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        self.transaction("get_dead_lettered_batches", || {
+            let batch_results: Vec<(BatchModel, BatchStatusModel, Option<SubmissionModel>)> =
+                batches::table
+                    .inner_join(
+                        batch_statuses::table.on(batches::batch_id
+                            .eq(batch_statuses::batch_id)
+                            .and(batches::service_id.eq(batch_statuses::service_id))),
+                    )
+                    .left_join(
+                        submissions::table.on(batches::batch_id
+                            .eq(submissions::batch_id)
+                            .and(batches::service_id.eq(submissions::service_id))),
+                    )
+                    .filter(batches::service_id.eq(service_id))
+                    .filter(batch_statuses::dlt_status.eq(BatchStatus::DeadLettered.to_string()))
+                    .order((batch_statuses::updated_at.asc(), batches::batch_id.asc()))
+                    .select((
+                        batches::all_columns,
+                        batch_statuses::all_columns,
+                        submissions::all_columns.nullable(),
+                    ))
+                    .load(self.conn)?;
+
+            let mut batch_models = Vec::new();
+            let mut batch_status_models = Vec::new();
+            let mut submission_models = Vec::new();
+
+            for (batch, status, submission) in batch_results {
+                batch_models.push(batch);
+                batch_status_models.push(status);
+                if let Some(submission) = submission {
+                    submission_models.push(submission);
+                }
+            }
+
+            let batch_ids: Vec<&str> = batch_models.iter().map(|b| b.batch_id.as_str()).collect();
+
+            let txn_models: Vec<TransactionModel> = transactions::table
+                .filter(transactions::batch_id.eq_any(&batch_ids))
+                .filter(transactions::service_id.eq(service_id))
+                .load(self.conn)?;
+            let txn_models = self.decrypt_payloads(txn_models)?;
+
+            let txn_ids: Vec<&str> = txn_models
+                .iter()
+                .map(|t| t.transaction_id.as_str())
+                .collect();
+
+            let receipt_models: Vec<TransactionReceiptModel> = transaction_receipts::table
+                .filter(transaction_receipts::transaction_id.eq_any(&txn_ids))
+                .filter(transaction_receipts::service_id.eq(service_id))
+                .load(self.conn)?;
+
+            let address_models: Vec<TransactionAddressModel> = transaction_addresses::table
+                .filter(transaction_addresses::transaction_id.eq_any(&txn_ids))
+                .filter(transaction_addresses::service_id.eq(service_id))
+                .load(self.conn)?;
+
+            TrackingBatchList::try_from((
+                batch_models,
+                batch_status_models,
+                txn_models,
+                address_models,
+                receipt_models,
+                submission_models,
+            ))
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreGetDeadLetteredBatchesOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn get_dead_lettered_batches(
+        &self,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        self.transaction("get_dead_lettered_batches", || {
+            let batch_results: Vec<(BatchModel, BatchStatusModel, Option<SubmissionModel>)> =
+                batches::table
+                    .inner_join(
+                        batch_statuses::table.on(batches::batch_id
+                            .eq(batch_statuses::batch_id)
+                            .and(batches::service_id.eq(batch_statuses::service_id))),
+                    )
+                    .left_join(
+                        submissions::table.on(batches::batch_id
+                            .eq(submissions::batch_id)
+                            .and(batches::service_id.eq(submissions::service_id))),
+                    )
+                    .filter(batches::service_id.eq(service_id))
+                    .filter(batch_statuses::dlt_status.eq(BatchStatus::DeadLettered.to_string()))
+                    .order((batch_statuses::updated_at.asc(), batches::batch_id.asc()))
+                    .select((
+                        batches::all_columns,
+                        batch_statuses::all_columns,
+                        submissions::all_columns.nullable(),
+                    ))
+                    .load(self.conn)?;
+
+            let mut batch_models = Vec::new();
+            let mut batch_status_models = Vec::new();
+            let mut submission_models = Vec::new();
+
+            for (batch, status, submission) in batch_results {
+                batch_models.push(batch);
+                batch_status_models.push(status);
+                if let Some(submission) = submission {
+                    submission_models.push(submission);
+                }
+            }
+
+            let batch_ids: Vec<&str> = batch_models.iter().map(|b| b.batch_id.as_str()).collect();
+
+            let txn_models: Vec<TransactionModel> = transactions::table
+                .filter(transactions::batch_id.eq_any(&batch_ids))
+                .filter(transactions::service_id.eq(service_id))
+                .load(self.conn)?;
+            let txn_models = self.decrypt_payloads(txn_models)?;
+
+            let txn_ids: Vec<&str> = txn_models
+                .iter()
+                .map(|t| t.transaction_id.as_str())
+                .collect();
+
+            let receipt_models: Vec<TransactionReceiptModel> = transaction_receipts::table
+                .filter(transaction_receipts::transaction_id.eq_any(&txn_ids))
+                .filter(transaction_receipts::service_id.eq(service_id))
+                .load(self.conn)?;
+
+            let address_models: Vec<TransactionAddressModel> = transaction_addresses::table
+                .filter(transaction_addresses::transaction_id.eq_any(&txn_ids))
+                .filter(transaction_addresses::service_id.eq(service_id))
+                .load(self.conn)?;
+
+            TrackingBatchList::try_from((
+                batch_models,
+                batch_status_models,
+                txn_models,
+                address_models,
+                receipt_models,
+                submission_models,
+            ))
+        })
+    }
+}
@@ -16,7 +16,8 @@ use super::BatchTrackingStoreOperations;
 
 use crate::batch_tracking::store::diesel::{
     models::{
-        BatchModel, BatchStatusModel, SubmissionModel, TransactionModel, TransactionReceiptModel,
+        BatchModel, BatchStatusModel, SubmissionModel, TransactionAddressModel, TransactionModel,
+        TransactionReceiptModel,
     },
     schema::{batch_statuses, batches},
     BatchStatusName, TrackingBatchList,
@@ -35,85 +36,103 @@ impl<'a> BatchTrackingStoreGetFailedBatchesOperation
     for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
 {
     fn get_failed_batches(&self) -> Result<TrackingBatchList, BatchTrackingStoreError> {
-        let failed_statuses: Vec<String> = vec![
-            BatchStatusName::Unknown.to_string(),
-            BatchStatusName::Invalid.to_string(),
-        ];
-
-        let batches_and_statuses: Vec<(BatchModel, Option<BatchStatusModel>)> = batches::table
-            .into_boxed()
-            .left_join(
-                batch_statuses::table.on(batches::batch_id
-                    .eq(batch_statuses::batch_id)
-                    .and(batches::service_id.eq(batch_statuses::service_id))),
-            )
-            .filter(batch_statuses::dlt_status.eq_any(failed_statuses))
-            .select((batches::all_columns, batch_statuses::all_columns.nullable()))
-            .load::<(BatchModel, Option<BatchStatusModel>)>(self.conn)?;
-
-        if batches_and_statuses.is_empty() {
-            return Ok(TrackingBatchList {
-                batches: Vec::new(),
-            });
-        }
+        self.transaction("get_failed_batches", || {
+            let failed_statuses: Vec<String> = vec![
+                BatchStatusName::Unknown.to_string(),
+                BatchStatusName::Invalid.to_string(),
+            ];
+
+            let batches_and_statuses: Vec<(BatchModel, Option<BatchStatusModel>)> = batches::table
+                .into_boxed()
+                .left_join(
+                    batch_statuses::table.on(batches::batch_id
+                        .eq(batch_statuses::batch_id)
+                        .and(batches::service_id.eq(batch_statuses::service_id))),
+                )
+                .filter(batch_statuses::dlt_status.eq_any(failed_statuses))
+                .select((batches::all_columns, batch_statuses::all_columns.nullable()))
+                .load::<(BatchModel, Option<BatchStatusModel>)>(self.conn)?;
+
+            if batches_and_statuses.is_empty() {
+                return Ok(TrackingBatchList {
+                    batches: Vec::new(),
+                });
+            }
 
-        let (batch_models, batch_status_model_options): (
-            Vec<BatchModel>,
-            Vec<Option<BatchStatusModel>>,
-        ) = batches_and_statuses.iter().cloned().unzip();
+            let (batch_models, batch_status_model_options): (
+                Vec<BatchModel>,
+                Vec<Option<BatchStatusModel>>,
+            ) = batches_and_statuses.iter().cloned().unzip();
 
-        let mut batch_status_models: Vec<BatchStatusModel> = Vec::new();
+            let mut batch_status_models: Vec<BatchStatusModel> = Vec::new();
 
-        batch_status_model_options.iter().for_each(|m| {
-            if let Some(model) = m {
-                batch_status_models.push(model.clone());
-            }
-        });
+            batch_status_model_options.iter().for_each(|m| {
+                if let Some(model) = m {
+                    batch_status_models.push(model.clone());
+                }
+            });
 
-        let submission_models: Vec<SubmissionModel> = sql_query(
-            "WITH bbs AS (
-                SELECT b.batch_id, b.service_id FROM batches b
-                LEFT JOIN batch_statuses bs ON bs.batch_id = b.batch_id AND bs.service_id = b.service_id
-                WHERE bs.dlt_status = 'Invalid' OR bs.dlt_status = 'Unknown'
+            let submission_models: Vec<SubmissionModel> = sql_query(
+                "WITH bbs AS (
+                    SELECT b.batch_id, b.service_id FROM batches b
+                    LEFT JOIN batch_statuses bs ON bs.batch_id = b.batch_id AND bs.service_id = b.service_id
+                    WHERE bs.dlt_status = 'Invalid' OR bs.dlt_status = 'Unknown'
+                )
+                SELECT * FROM submissions s
+                WHERE (s.service_id, s.batch_id) IN (SELECT service_id, batch_id FROM bbs);"
+            )
+            .load(self.conn)?;
+
+            let txn_models: Vec<TransactionModel> = sql_query(
+                "WITH bbs AS (
+                    SELECT b.batch_id, b.service_id FROM batches b
+                    LEFT JOIN batch_statuses bs ON bs.batch_id = b.batch_id AND bs.service_id = b.service_id
+                    WHERE bs.dlt_status = 'Invalid' OR bs.dlt_status = 'Unknown'
+                )
+                SELECT * FROM transactions t
+                WHERE (t.service_id, t.batch_id) IN (SELECT service_id, batch_id FROM bbs);"
             )
-            SELECT * FROM submissions s
-            WHERE (s.service_id, s.batch_id) IN (SELECT service_id, batch_id FROM bbs);"
-        )
-        .load(self.conn)?;
-
-        let txn_models: Vec<TransactionModel> = sql_query(
-            "WITH bbs AS (
-                SELECT b.batch_id, b.service_id FROM batches b
-                LEFT JOIN batch_statuses bs ON bs.batch_id = b.batch_id AND bs.service_id = b.service_id
-                WHERE bs.dlt_status = 'Invalid' OR bs.dlt_status = 'Unknown'
+            .load(self.conn)?;
+            let txn_models = self.decrypt_payloads(txn_models)?;
+
+            let receipt_models: Vec<TransactionReceiptModel> = sql_query(
+                "WITH bbs AS (
+                    SELECT b.batch_id, b.service_id FROM batches b
+                    LEFT JOIN batch_statuses bs ON bs.batch_id = b.batch_id AND bs.service_id = b.service_id
+                    WHERE bs.dlt_status = 'Invalid' OR bs.dlt_status = 'Unknown'
+                ), txn_models AS (
+                    SELECT t.transaction_id, t.service_id FROM transactions t
+                    WHERE (t.service_id, t.batch_id) IN (SELECT service_id, batch_id FROM bbs)
+                )
+                SELECT * FROM transaction_receipts tr
+                WHERE (tr.service_id, tr.transaction_id) IN (SELECT service_id, transaction_id FROM txn_models);"
             )
-            SELECT * FROM transactions t
-            WHERE (t.service_id, t.batch_id) IN (SELECT service_id, batch_id FROM bbs);"
-        )
-        .load(self.conn)?;
-
-        let receipt_models: Vec<TransactionReceiptModel> = sql_query(
-            "WITH bbs AS (
-                SELECT b.batch_id, b.service_id FROM batches b
-                LEFT JOIN batch_statuses bs ON bs.batch_id = b.batch_id AND bs.service_id = b.service_id
-                WHERE bs.dlt_status = 'Invalid' OR bs.dlt_status = 'Unknown'
-            ), txn_models AS (
-                SELECT t.transaction_id, t.service_id FROM transactions t
-                WHERE (t.service_id, t.batch_id) IN (SELECT service_id, batch_id FROM bbs)
+            .load(self.conn)?;
+
+            let address_models: Vec<TransactionAddressModel> = sql_query(
+                "WITH bbs AS (
+                    SELECT b.batch_id, b.service_id FROM batches b
+                    LEFT JOIN batch_statuses bs ON bs.batch_id = b.batch_id AND bs.service_id = b.service_id
+                    WHERE bs.dlt_status = 'Invalid' OR bs.dlt_status = 'Unknown'
+                ), txn_models AS (
+                    SELECT t.transaction_id, t.service_id FROM transactions t
+                    WHERE (t.service_id, t.batch_id) IN (SELECT service_id, batch_id FROM bbs)
+                )
+                SELECT * FROM transaction_addresses ta
+                WHERE (ta.service_id, ta.transaction_id) IN (SELECT service_id, transaction_id FROM txn_models);"
             )
-            SELECT * FROM transaction_receipts tr
-            WHERE (tr.service_id, tr.transaction_id) IN (SELECT service_id, transaction_id FROM txn_models);"
-        )
-        .load(self.conn)?;
-
-        let batches = TrackingBatchList::try_from((
-            batch_models,
-            batch_status_models,
-            txn_models,
-            receipt_models,
-            submission_models,
-        ))?;
-        Ok(batches)
+            .load(self.conn)?;
+
+            let batches = TrackingBatchList::try_from((
+                batch_models,
+                batch_status_models,
+                txn_models,
+                address_models,
+                receipt_models,
+                submission_models,
+            ))?;
+            Ok(batches)
+        })
     }
 }
 
@@ -122,84 +141,102 @@ impl<'a> BatchTrackingStoreGetFailedBatchesOperation
     for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
 {
     fn get_failed_batches(&self) -> Result<TrackingBatchList, BatchTrackingStoreError> {
-        let failed_statuses: Vec<String> = vec![
-            BatchStatusName::Unknown.to_string(),
-            BatchStatusName::Invalid.to_string(),
-        ];
-
-        let batches_and_statuses: Vec<(BatchModel, Option<BatchStatusModel>)> = batches::table
-            .into_boxed()
-            .left_join(
-                batch_statuses::table.on(batches::batch_id
-                    .eq(batch_statuses::batch_id)
-                    .and(batches::service_id.eq(batch_statuses::service_id))),
-            )
-            .filter(batch_statuses::dlt_status.eq_any(failed_statuses))
-            .select((batches::all_columns, batch_statuses::all_columns.nullable()))
-            .load::<(BatchModel, Option<BatchStatusModel>)>(self.conn)?;
-
-        if batches_and_statuses.is_empty() {
-            return Ok(TrackingBatchList {
-                batches: Vec::new(),
-            });
-        }
+        self.transaction("get_failed_batches", || {
+            let failed_statuses: Vec<String> = vec![
+                BatchStatusName::Unknown.to_string(),
+                BatchStatusName::Invalid.to_string(),
+            ];
+
+            let batches_and_statuses: Vec<(BatchModel, Option<BatchStatusModel>)> = batches::table
+                .into_boxed()
+                .left_join(
+                    batch_statuses::table.on(batches::batch_id
+                        .eq(batch_statuses::batch_id)
+                        .and(batches::service_id.eq(batch_statuses::service_id))),
+                )
+                .filter(batch_statuses::dlt_status.eq_any(failed_statuses))
+                .select((batches::all_columns, batch_statuses::all_columns.nullable()))
+                .load::<(BatchModel, Option<BatchStatusModel>)>(self.conn)?;
+
+            if batches_and_statuses.is_empty() {
+                return Ok(TrackingBatchList {
+                    batches: Vec::new(),
+                });
+            }
 
-        let (batch_models, batch_status_model_options): (
-            Vec<BatchModel>,
-            Vec<Option<BatchStatusModel>>,
-        ) = batches_and_statuses.iter().cloned().unzip();
+            let (batch_models, batch_status_model_options): (
+                Vec<BatchModel>,
+                Vec<Option<BatchStatusModel>>,
+            ) = batches_and_statuses.iter().cloned().unzip();
 
-        let mut batch_status_models: Vec<BatchStatusModel> = Vec::new();
+            let mut batch_status_models: Vec<BatchStatusModel> = Vec::new();
 
-        batch_status_model_options.iter().for_each(|m| {
-            if let Some(model) = m {
-                batch_status_models.push(model.clone());
-            }
-        });
+            batch_status_model_options.iter().for_each(|m| {
+                if let Some(model) = m {
+                    batch_status_models.push(model.clone());
+                }
+            });
 
-        let submission_models: Vec<SubmissionModel> = sql_query(
-            "WITH bbs AS (
-                SELECT b.batch_id, b.service_id FROM batches b
-                LEFT JOIN batch_statuses bs ON bs.batch_id = b.batch_id AND bs.service_id = b.service_id
-                WHERE bs.dlt_status = 'Invalid' OR bs.dlt_status = 'Unknown'
+            let submission_models: Vec<SubmissionModel> = sql_query(
+                "WITH bbs AS (
+                    SELECT b.batch_id, b.service_id FROM batches b
+                    LEFT JOIN batch_statuses bs ON bs.batch_id = b.batch_id AND bs.service_id = b.service_id
+                    WHERE bs.dlt_status = 'Invalid' OR bs.dlt_status = 'Unknown'
+                )
+                SELECT * FROM submissions s
+                WHERE (s.service_id, s.batch_id) IN (SELECT service_id, batch_id FROM bbs);"
+            )
+            .load(self.conn)?;
+
+            let txn_models: Vec<TransactionModel> = sql_query(
+                "WITH bbs AS (
+                    SELECT b.batch_id, b.service_id FROM batches b
+                    LEFT JOIN batch_statuses bs ON bs.batch_id = b.batch_id AND bs.service_id = b.service_id
+                    WHERE bs.dlt_status = 'Invalid' OR bs.dlt_status = 'Unknown'
+                )
+                SELECT * FROM transactions t
+                WHERE (t.service_id, t.batch_id) IN (SELECT service_id, batch_id FROM bbs);"
             )
-            SELECT * FROM submissions s
-            WHERE (s.service_id, s.batch_id) IN (SELECT service_id, batch_id FROM bbs);"
-        )
-        .load(self.conn)?;
-
-        let txn_models: Vec<TransactionModel> = sql_query(
-            "WITH bbs AS (
-                SELECT b.batch_id, b.service_id FROM batches b
-                LEFT JOIN batch_statuses bs ON bs.batch_id = b.batch_id AND bs.service_id = b.service_id
-                WHERE bs.dlt_status = 'Invalid' OR bs.dlt_status = 'Unknown'
+            .load(self.conn)?;
+            let txn_models = self.decrypt_payloads(txn_models)?;
+
+            let receipt_models: Vec<TransactionReceiptModel> = sql_query(
+                "WITH bbs AS (
+                    SELECT b.batch_id, b.service_id FROM batches b
+                    LEFT JOIN batch_statuses bs ON bs.batch_id = b.batch_id AND bs.service_id = b.service_id
+                    WHERE bs.dlt_status = 'Invalid' OR bs.dlt_status = 'Unknown'
+                ), txn_models AS (
+                    SELECT t.transaction_id, t.service_id FROM transactions t
+                    WHERE (t.service_id, t.batch_id) IN (SELECT service_id, batch_id FROM bbs)
+                )
+                SELECT * FROM transaction_receipts tr
+                WHERE (tr.service_id, tr.transaction_id) IN (SELECT service_id, transaction_id FROM txn_models);"
             )
-            SELECT * FROM transactions t
-            WHERE (t.service_id, t.batch_id) IN (SELECT service_id, batch_id FROM bbs);"
-        )
-        .load(self.conn)?;
-
-        let receipt_models: Vec<TransactionReceiptModel> = sql_query(
-            "WITH bbs AS (
-                SELECT b.batch_id, b.service_id FROM batches b
-                LEFT JOIN batch_statuses bs ON bs.batch_id = b.batch_id AND bs.service_id = b.service_id
-                WHERE bs.dlt_status = 'Invalid' OR bs.dlt_status = 'Unknown'
-            ), txn_models AS (
-                SELECT t.transaction_id, t.service_id FROM transactions t
-                WHERE (t.service_id, t.batch_id) IN (SELECT service_id, batch_id FROM bbs)
+            .load(self.conn)?;
+
+            let address_models: Vec<TransactionAddressModel> = sql_query(
+                "WITH bbs AS (
+                    SELECT b.batch_id, b.service_id FROM batches b
+                    LEFT JOIN batch_statuses bs ON bs.batch_id = b.batch_id AND bs.service_id = b.service_id
+                    WHERE bs.dlt_status = 'Invalid' OR bs.dlt_status = 'Unknown'
+                ), txn_models AS (
+                    SELECT t.transaction_id, t.service_id FROM transactions t
+                    WHERE (t.service_id, t.batch_id) IN (SELECT service_id, batch_id FROM bbs)
+                )
+                SELECT * FROM transaction_addresses ta
+                WHERE (ta.service_id, ta.transaction_id) IN (SELECT service_id, transaction_id FROM txn_models);"
             )
-            SELECT * FROM transaction_receipts tr
-            WHERE (tr.service_id, tr.transaction_id) IN (SELECT service_id, transaction_id FROM txn_models);"
-        )
-        .load(self.conn)?;
-
-        let batches = TrackingBatchList::try_from((
-            batch_models,
-            batch_status_models,
-            txn_models,
-            receipt_models,
-            submission_models,
-        ))?;
-        Ok(batches)
+            .load(self.conn)?;
+
+            let batches = TrackingBatchList::try_from((
+                batch_models,
+                batch_status_models,
+                txn_models,
+                address_models,
+                receipt_models,
+                submission_models,
+            ))?;
+            Ok(batches)
+        })
     }
 }
@@ -0,0 +1,107 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::resolve_data_change_id::BatchTrackingStoreResolveDataChangeIdOperation;
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::diesel::{
+    models::{is_data_change_id, SubmissionModel},
+    schema::submissions,
+    SubmissionError,
+};
+
+use crate::batch_tracking::store::BatchTrackingStoreError;
+use diesel::prelude::*;
+use std::convert::TryFrom;
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreGetLatestSubmissionErrorOperation
+{
+    fn get_latest_submission_error(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<Option<SubmissionError>, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreGetLatestSubmissionErrorOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn get_latest_submission_error(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<Option<SubmissionError>, BatchTrackingStoreError> {
+        self.transaction("get_latest_submission_error", || {
+            let mut batch_id = id.to_string();
+            if is_data_change_id(id)? {
+                match self.resolve_data_change_id(id, service_id)? {
+                    Some(found) => batch_id = found,
+                    None => return Ok(None),
+                }
+            }
+
+            submissions::table
+                .filter(
+                    submissions::batch_id
+                        .eq(&batch_id)
+                        .and(submissions::service_id.eq(&service_id)),
+                )
+                .first::<SubmissionModel>(self.conn)
+                .optional()?
+                // The submission only holds an error if the batch's latest
+                // submission failed
+                .filter(|submission| submission.error_type.is_some())
+                .as_ref()
+                .map(SubmissionError::try_from)
+                .transpose()
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreGetLatestSubmissionErrorOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn get_latest_submission_error(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<Option<SubmissionError>, BatchTrackingStoreError> {
+        self.transaction("get_latest_submission_error", || {
+            let mut batch_id = id.to_string();
+            if is_data_change_id(id)? {
+                match self.resolve_data_change_id(id, service_id)? {
+                    Some(found) => batch_id = found,
+                    None => return Ok(None),
+                }
+            }
+
+            submissions::table
+                .filter(
+                    submissions::batch_id
+                        .eq(&batch_id)
+                        .and(submissions::service_id.eq(&service_id)),
+                )
+                .first::<SubmissionModel>(self.conn)
+                .optional()?
+                // The submission only holds an error if the batch's latest
+                // submission failed
+                .filter(|submission| submission.error_type.is_some())
+                .as_ref()
+                .map(SubmissionError::try_from)
+                .transpose()
+        })
+    }
+}
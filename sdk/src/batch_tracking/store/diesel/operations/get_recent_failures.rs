@@ -0,0 +1,278 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::diesel::{
+    models::{
+        BatchModel, BatchStatusModel, SubmissionModel, TransactionAddressModel, TransactionModel,
+        TransactionReceiptModel,
+    },
+    schema::{
+        batch_statuses, batches, submissions, transaction_addresses, transaction_receipts,
+        transactions,
+    },
+    BatchStatusName, FailedBatchDetail, TrackingBatchList,
+};
+
+use crate::batch_tracking::store::BatchTrackingStoreError;
+use diesel::prelude::*;
+use std::collections::HashSet;
+use std::convert::TryFrom;
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreGetRecentFailuresOperation {
+    fn get_recent_failures(
+        &self,
+        service_id: &str,
+        limit: i64,
+    ) -> Result<Vec<FailedBatchDetail>, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreGetRecentFailuresOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn get_recent_failures(
+        &self,
+        service_id: &str,
+        limit: i64,
+    ) -> Result<Vec<FailedBatchDetail>, BatchTrackingStoreError> {
+        self.transaction("get_recent_failures", || {
+            let failed_statuses: Vec<String> = vec![
+                BatchStatusName::Unknown.to_string(),
+                BatchStatusName::Invalid.to_string(),
+            ];
+
+            // Fetch the most recent failed batches along with their statuses
+            // and submissions in a single query
+            let batch_results: Vec<(BatchModel, BatchStatusModel, Option<SubmissionModel>)> = self
+                .load(
+                    batches::table
+                        .inner_join(
+                            batch_statuses::table.on(batches::batch_id
+                                .eq(batch_statuses::batch_id)
+                                .and(batches::service_id.eq(batch_statuses::service_id))),
+                        )
+                        .left_join(
+                            submissions::table.on(batches::batch_id
+                                .eq(submissions::batch_id)
+                                .and(batches::service_id.eq(submissions::service_id))),
+                        )
+                        .filter(batches::service_id.eq(service_id))
+                        .filter(batch_statuses::dlt_status.eq_any(failed_statuses))
+                        .order((batches::created_at.desc(), batches::batch_id.asc()))
+                        .limit(limit)
+                        .select((
+                            batches::all_columns,
+                            batch_statuses::all_columns,
+                            submissions::all_columns.nullable(),
+                        )),
+                )?;
+
+            if batch_results.is_empty() {
+                return Ok(Vec::new());
+            }
+
+            let mut batch_models = Vec::new();
+            let mut batch_status_models = Vec::new();
+            let mut submission_models = Vec::new();
+
+            for (batch, status, submission) in batch_results {
+                batch_models.push(batch);
+                batch_status_models.push(status);
+                if let Some(submission) = submission {
+                    submission_models.push(submission);
+                }
+            }
+
+            let batch_ids: Vec<&str> = batch_models.iter().map(|b| b.batch_id.as_str()).collect();
+
+            // The addresses are joined onto the transactions, so that the
+            // transactions and their addresses are fetched in one query
+            let txn_results: Vec<(TransactionModel, Option<TransactionAddressModel>)> = self.load(
+                transactions::table
+                    .left_join(
+                        transaction_addresses::table.on(transactions::transaction_id
+                            .eq(transaction_addresses::transaction_id)
+                            .and(transactions::service_id.eq(transaction_addresses::service_id))),
+                    )
+                    .filter(transactions::service_id.eq(service_id))
+                    .filter(transactions::batch_id.eq_any(&batch_ids))
+                    .select((
+                        transactions::all_columns,
+                        transaction_addresses::all_columns.nullable(),
+                    )),
+            )?;
+
+            let mut txn_models = Vec::new();
+            let mut address_models = Vec::new();
+            let mut seen_txn_ids = HashSet::new();
+
+            for (txn, address) in txn_results {
+                if let Some(address) = address {
+                    address_models.push(address);
+                }
+                if seen_txn_ids.insert(txn.transaction_id.clone()) {
+                    txn_models.push(txn);
+                }
+            }
+            let txn_models = self.decrypt_payloads(txn_models)?;
+
+            let txn_ids: Vec<&str> = txn_models
+                .iter()
+                .map(|t| t.transaction_id.as_str())
+                .collect();
+
+            let receipt_models: Vec<TransactionReceiptModel> = self.load(
+                transaction_receipts::table
+                    .filter(transaction_receipts::service_id.eq(service_id))
+                    .filter(transaction_receipts::transaction_id.eq_any(&txn_ids)),
+            )?;
+
+            let batch_list = TrackingBatchList::try_from((
+                batch_models,
+                batch_status_models,
+                txn_models,
+                address_models,
+                receipt_models,
+                submission_models,
+            ))?;
+
+            Ok(batch_list
+                .batches
+                .into_iter()
+                .map(FailedBatchDetail::from)
+                .collect())
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreGetRecentFailuresOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn get_recent_failures(
+        &self,
+        service_id: &str,
+        limit: i64,
+    ) -> Result<Vec<FailedBatchDetail>, BatchTrackingStoreError> {
+        self.transaction("get_recent_failures", || {
+            let failed_statuses: Vec<String> = vec![
+                BatchStatusName::Unknown.to_string(),
+                BatchStatusName::Invalid.to_string(),
+            ];
+
+            // Fetch the most recent failed batches along with their statuses
+            // and submissions in a single query
+            let batch_results: Vec<(BatchModel, BatchStatusModel, Option<SubmissionModel>)> = self
+                .load(
+                    batches::table
+                        .inner_join(
+                            batch_statuses::table.on(batches::batch_id
+                                .eq(batch_statuses::batch_id)
+                                .and(batches::service_id.eq(batch_statuses::service_id))),
+                        )
+                        .left_join(
+                            submissions::table.on(batches::batch_id
+                                .eq(submissions::batch_id)
+                                .and(batches::service_id.eq(submissions::service_id))),
+                        )
+                        .filter(batches::service_id.eq(service_id))
+                        .filter(batch_statuses::dlt_status.eq_any(failed_statuses))
+                        .order((batches::created_at.desc(), batches::batch_id.asc()))
+                        .limit(limit)
+                        .select((
+                            batches::all_columns,
+                            batch_statuses::all_columns,
+                            submissions::all_columns.nullable(),
+                        )),
+                )?;
+
+            if batch_results.is_empty() {
+                return Ok(Vec::new());
+            }
+
+            let mut batch_models = Vec::new();
+            let mut batch_status_models = Vec::new();
+            let mut submission_models = Vec::new();
+
+            for (batch, status, submission) in batch_results {
+                batch_models.push(batch);
+                batch_status_models.push(status);
+                if let Some(submission) = submission {
+                    submission_models.push(submission);
+                }
+            }
+
+            let batch_ids: Vec<&str> = batch_models.iter().map(|b| b.batch_id.as_str()).collect();
+
+            // The addresses are joined onto the transactions, so that the
+            // transactions and their addresses are fetched in one query
+            let txn_results: Vec<(TransactionModel, Option<TransactionAddressModel>)> = self.load(
+                transactions::table
+                    .left_join(
+                        transaction_addresses::table.on(transactions::transaction_id
+                            .eq(transaction_addresses::transaction_id)
+                            .and(transactions::service_id.eq(transaction_addresses::service_id))),
+                    )
+                    .filter(transactions::service_id.eq(service_id))
+                    .filter(transactions::batch_id.eq_any(&batch_ids))
+                    .select((
+                        transactions::all_columns,
+                        transaction_addresses::all_columns.nullable(),
+                    )),
+            )?;
+
+            let mut txn_models = Vec::new();
+            let mut address_models = Vec::new();
+            let mut seen_txn_ids = HashSet::new();
+
+            for (txn, address) in txn_results {
+                if let Some(address) = address {
+                    address_models.push(address);
+                }
+                if seen_txn_ids.insert(txn.transaction_id.clone()) {
+                    txn_models.push(txn);
+                }
+            }
+            let txn_models = self.decrypt_payloads(txn_models)?;
+
+            let txn_ids: Vec<&str> = txn_models
+                .iter()
+                .map(|t| t.transaction_id.as_str())
+                .collect();
+
+            let receipt_models: Vec<TransactionReceiptModel> = self.load(
+                transaction_receipts::table
+                    .filter(transaction_receipts::service_id.eq(service_id))
+                    .filter(transaction_receipts::transaction_id.eq_any(&txn_ids)),
+            )?;
+
+            let batch_list = TrackingBatchList::try_from((
+                batch_models,
+                batch_status_models,
+                txn_models,
+                address_models,
+                receipt_models,
+                submission_models,
+            ))?;
+
+            Ok(batch_list
+                .batches
+                .into_iter()
+                .map(FailedBatchDetail::from)
+                .collect())
+        })
+    }
+}
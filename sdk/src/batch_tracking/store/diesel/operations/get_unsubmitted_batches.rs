@@ -16,7 +16,8 @@ use super::BatchTrackingStoreOperations;
 
 use crate::batch_tracking::store::diesel::{
     models::{
-        BatchModel, BatchStatusModel, SubmissionModel, TransactionModel, TransactionReceiptModel,
+        BatchModel, BatchStatusModel, SubmissionModel, TransactionAddressModel, TransactionModel,
+        TransactionReceiptModel,
     },
     schema::{batch_statuses, batches},
     BatchStatus, TrackingBatchList,
@@ -36,7 +37,7 @@ impl<'a> BatchTrackingStoreGetUnsubmittedBatchesOperation
     for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
 {
     fn get_unsubmitted_batches(&self) -> Result<TrackingBatchList, BatchTrackingStoreError> {
-        self.conn.transaction::<_, BatchTrackingStoreError, _>(|| {
+        self.transaction("get_unsubmitted_batches", || {
             let unsubmitted_statuses: Vec<String> = vec![
                 BatchStatus::Unknown.to_string(),
                 BatchStatus::Delayed.to_string(),
@@ -51,6 +52,11 @@ impl<'a> BatchTrackingStoreGetUnsubmittedBatchesOperation
                 ))
                 .filter(batch_statuses::dlt_status.eq_any(unsubmitted_statuses))
                 .or_filter(batches::submitted.eq(false))
+                .filter(
+                    batch_statuses::dlt_status
+                        .is_null()
+                        .or(batch_statuses::dlt_status.ne(BatchStatus::DeadLettered.to_string())),
+                )
                 .select((batches::all_columns, batch_statuses::all_columns.nullable()))
                 .load::<(BatchModel, Option<BatchStatusModel>)>(self.conn)?;
 
@@ -75,7 +81,8 @@ impl<'a> BatchTrackingStoreGetUnsubmittedBatchesOperation
                 "WITH bbs AS (
                     SELECT b.batch_id, b.service_id FROM batches b
                     LEFT JOIN batch_statuses bs ON bs.batch_id = b.batch_id AND bs.service_id = b.service_id
-                    WHERE bs.dlt_status = 'Delayed' OR bs.dlt_status = 'Unknown' OR b.submitted = false
+                    WHERE (bs.dlt_status = 'Delayed' OR bs.dlt_status = 'Unknown' OR b.submitted = false)
+                    AND (bs.dlt_status IS NULL OR bs.dlt_status <> 'DeadLettered')
                 )
                 SELECT * FROM submissions s
                 WHERE (s.service_id, s.batch_id) IN (SELECT service_id, batch_id FROM bbs);"
@@ -86,18 +93,21 @@ impl<'a> BatchTrackingStoreGetUnsubmittedBatchesOperation
                 "WITH bbs AS (
                     SELECT b.batch_id, b.service_id FROM batches b
                     LEFT JOIN batch_statuses bs ON bs.batch_id = b.batch_id AND bs.service_id = b.service_id
-                    WHERE bs.dlt_status = 'Delayed' OR bs.dlt_status = 'Unknown' OR b.submitted = false
+                    WHERE (bs.dlt_status = 'Delayed' OR bs.dlt_status = 'Unknown' OR b.submitted = false)
+                    AND (bs.dlt_status IS NULL OR bs.dlt_status <> 'DeadLettered')
                 )
                 SELECT * FROM transactions t
                 WHERE (t.service_id, t.batch_id) IN (SELECT service_id, batch_id FROM bbs);"
             )
             .load(self.conn)?;
+            let txn_models = self.decrypt_payloads(txn_models)?;
 
             let receipt_models: Vec<TransactionReceiptModel> = sql_query(
                 "WITH bbs AS (
                     SELECT b.batch_id, b.service_id FROM batches b
                     LEFT JOIN batch_statuses bs ON bs.batch_id = b.batch_id AND bs.service_id = b.service_id
-                    WHERE bs.dlt_status = 'Delayed' OR bs.dlt_status = 'Unknown' OR b.submitted = false
+                    WHERE (bs.dlt_status = 'Delayed' OR bs.dlt_status = 'Unknown' OR b.submitted = false)
+                    AND (bs.dlt_status IS NULL OR bs.dlt_status <> 'DeadLettered')
                 ), txn_models AS (
                     SELECT t.transaction_id, t.service_id FROM transactions t
                     WHERE (t.service_id, t.batch_id) IN (SELECT service_id, batch_id FROM bbs)
@@ -107,7 +117,22 @@ impl<'a> BatchTrackingStoreGetUnsubmittedBatchesOperation
             )
             .load(self.conn)?;
 
-            let batches = TrackingBatchList::try_from((batch_models, batch_status_models, txn_models, receipt_models, submission_models))?;
+            let address_models: Vec<TransactionAddressModel> = sql_query(
+                "WITH bbs AS (
+                    SELECT b.batch_id, b.service_id FROM batches b
+                    LEFT JOIN batch_statuses bs ON bs.batch_id = b.batch_id AND bs.service_id = b.service_id
+                    WHERE (bs.dlt_status = 'Delayed' OR bs.dlt_status = 'Unknown' OR b.submitted = false)
+                    AND (bs.dlt_status IS NULL OR bs.dlt_status <> 'DeadLettered')
+                ), txn_models AS (
+                    SELECT t.transaction_id, t.service_id FROM transactions t
+                    WHERE (t.service_id, t.batch_id) IN (SELECT service_id, batch_id FROM bbs)
+                )
+                SELECT * FROM transaction_addresses ta
+                WHERE (ta.service_id, ta.transaction_id) IN (SELECT service_id, transaction_id FROM txn_models);"
+            )
+            .load(self.conn)?;
+
+            let batches = TrackingBatchList::try_from((batch_models, batch_status_models, txn_models, address_models, receipt_models, submission_models))?;
             Ok(batches)
         })
     }
@@ -118,7 +143,7 @@ impl<'a> BatchTrackingStoreGetUnsubmittedBatchesOperation
     for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
 {
     fn get_unsubmitted_batches(&self) -> Result<TrackingBatchList, BatchTrackingStoreError> {
-        self.conn.transaction::<_, BatchTrackingStoreError, _>(|| {
+        self.transaction("get_unsubmitted_batches", || {
             let unsubmitted_statuses: Vec<String> = vec![
                 BatchStatus::Unknown.to_string(),
                 BatchStatus::Delayed.to_string(),
@@ -133,6 +158,11 @@ impl<'a> BatchTrackingStoreGetUnsubmittedBatchesOperation
                 ))
                 .filter(batch_statuses::dlt_status.eq_any(unsubmitted_statuses))
                 .or_filter(batches::submitted.eq(false))
+                .filter(
+                    batch_statuses::dlt_status
+                        .is_null()
+                        .or(batch_statuses::dlt_status.ne(BatchStatus::DeadLettered.to_string())),
+                )
                 .select((batches::all_columns, batch_statuses::all_columns.nullable()))
                 .load::<(BatchModel, Option<BatchStatusModel>)>(self.conn)?;
 
@@ -157,7 +187,8 @@ impl<'a> BatchTrackingStoreGetUnsubmittedBatchesOperation
                 "WITH bbs AS (
                     SELECT b.batch_id, b.service_id FROM batches b
                     LEFT JOIN batch_statuses bs ON bs.batch_id = b.batch_id AND bs.service_id = b.service_id
-                    WHERE bs.dlt_status = 'Delayed' OR bs.dlt_status = 'Unknown' OR b.submitted = false
+                    WHERE (bs.dlt_status = 'Delayed' OR bs.dlt_status = 'Unknown' OR b.submitted = false)
+                    AND (bs.dlt_status IS NULL OR bs.dlt_status <> 'DeadLettered')
                 )
                 SELECT * FROM submissions s
                 WHERE (s.service_id, s.batch_id) IN (SELECT service_id, batch_id FROM bbs);"
@@ -168,18 +199,21 @@ impl<'a> BatchTrackingStoreGetUnsubmittedBatchesOperation
                 "WITH bbs AS (
                     SELECT b.batch_id, b.service_id FROM batches b
                     LEFT JOIN batch_statuses bs ON bs.batch_id = b.batch_id AND bs.service_id = b.service_id
-                    WHERE bs.dlt_status = 'Delayed' OR bs.dlt_status = 'Unknown' OR b.submitted = false
+                    WHERE (bs.dlt_status = 'Delayed' OR bs.dlt_status = 'Unknown' OR b.submitted = false)
+                    AND (bs.dlt_status IS NULL OR bs.dlt_status <> 'DeadLettered')
                 )
                 SELECT * FROM transactions t
                 WHERE (t.service_id, t.batch_id) IN (SELECT service_id, batch_id FROM bbs);"
             )
             .load(self.conn)?;
+            let txn_models = self.decrypt_payloads(txn_models)?;
 
             let receipt_models: Vec<TransactionReceiptModel> = sql_query(
                 "WITH bbs AS (
                     SELECT b.batch_id, b.service_id FROM batches b
                     LEFT JOIN batch_statuses bs ON bs.batch_id = b.batch_id AND bs.service_id = b.service_id
-                    WHERE bs.dlt_status = 'Delayed' OR bs.dlt_status = 'Unknown' OR b.submitted = false
+                    WHERE (bs.dlt_status = 'Delayed' OR bs.dlt_status = 'Unknown' OR b.submitted = false)
+                    AND (bs.dlt_status IS NULL OR bs.dlt_status <> 'DeadLettered')
                 ), txn_models AS (
                     SELECT t.transaction_id, t.service_id FROM transactions t
                     WHERE (t.service_id, t.batch_id) IN (SELECT service_id, batch_id FROM bbs)
@@ -189,7 +223,22 @@ impl<'a> BatchTrackingStoreGetUnsubmittedBatchesOperation
             )
             .load(self.conn)?;
 
-            let batches = TrackingBatchList::try_from((batch_models, batch_status_models, txn_models, receipt_models, submission_models))?;
+            let address_models: Vec<TransactionAddressModel> = sql_query(
+                "WITH bbs AS (
+                    SELECT b.batch_id, b.service_id FROM batches b
+                    LEFT JOIN batch_statuses bs ON bs.batch_id = b.batch_id AND bs.service_id = b.service_id
+                    WHERE (bs.dlt_status = 'Delayed' OR bs.dlt_status = 'Unknown' OR b.submitted = false)
+                    AND (bs.dlt_status IS NULL OR bs.dlt_status <> 'DeadLettered')
+                ), txn_models AS (
+                    SELECT t.transaction_id, t.service_id FROM transactions t
+                    WHERE (t.service_id, t.batch_id) IN (SELECT service_id, batch_id FROM bbs)
+                )
+                SELECT * FROM transaction_addresses ta
+                WHERE (ta.service_id, ta.transaction_id) IN (SELECT service_id, transaction_id FROM txn_models);"
+            )
+            .load(self.conn)?;
+
+            let batches = TrackingBatchList::try_from((batch_models, batch_status_models, txn_models, address_models, receipt_models, submission_models))?;
             Ok(batches)
         })
     }
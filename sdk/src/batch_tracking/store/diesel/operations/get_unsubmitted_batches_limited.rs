@@ -0,0 +1,252 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::diesel::{
+    models::{
+        BatchModel, BatchStatusModel, SubmissionModel, TransactionAddressModel, TransactionModel,
+        TransactionReceiptModel,
+    },
+    schema::{
+        batch_statuses, batches, submissions, transaction_addresses, transaction_receipts,
+        transactions,
+    },
+    BatchStatus, TrackingBatchList,
+};
+
+use crate::batch_tracking::store::BatchTrackingStoreError;
+use diesel::prelude::*;
+use std::convert::TryFrom;
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreGetUnsubmittedBatchesLimitedOperation
+{
+    fn get_unsubmitted_batches_limited(
+        &self,
+        service_id: Option<&str>,
+        limit: i64,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreGetUnsubmittedBatchesLimitedOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn get_unsubmitted_batches_limited(
+        &self,
+        service_id: Option<&str>,
+        limit: i64,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        self.transaction("get_unsubmitted_batches_limited", || {
+            let unsubmitted_statuses = vec![
+                BatchStatus::Unknown.to_string(),
+                BatchStatus::Delayed.to_string(),
+            ];
+
+            let mut query = batches::table
+                .into_boxed()
+                .left_join(
+                    batch_statuses::table.on(batches::batch_id
+                        .eq(batch_statuses::batch_id)
+                        .and(batches::service_id.eq(batch_statuses::service_id))),
+                )
+                .left_join(
+                    submissions::table.on(batches::batch_id
+                        .eq(submissions::batch_id)
+                        .and(batches::service_id.eq(submissions::service_id))),
+                )
+                .filter(
+                    batch_statuses::dlt_status
+                        .eq_any(&unsubmitted_statuses)
+                        .or(batches::submitted.eq(false)),
+                )
+                .filter(
+                    batch_statuses::dlt_status
+                        .is_null()
+                        .or(batch_statuses::dlt_status.ne(BatchStatus::DeadLettered.to_string())),
+                )
+                .order((batches::created_at.asc(), batches::batch_id.asc()))
+                .select((
+                    batches::all_columns,
+                    batch_statuses::all_columns.nullable(),
+                    submissions::all_columns.nullable(),
+                ))
+                .limit(limit);
+
+            if let Some(service_id) = service_id {
+                query = query.filter(batches::service_id.eq(service_id));
+            }
+
+            let batch_results: Vec<(
+                BatchModel,
+                Option<BatchStatusModel>,
+                Option<SubmissionModel>,
+            )> = query.load(self.conn)?;
+
+            let mut batch_models = Vec::new();
+            let mut batch_status_models = Vec::new();
+            let mut submission_models = Vec::new();
+
+            for (batch, status, submission) in batch_results {
+                batch_models.push(batch);
+                if let Some(status) = status {
+                    batch_status_models.push(status);
+                }
+                if let Some(submission) = submission {
+                    submission_models.push(submission);
+                }
+            }
+
+            let batch_ids: Vec<&str> = batch_models.iter().map(|b| b.batch_id.as_str()).collect();
+            let service_ids: Vec<&str> =
+                batch_models.iter().map(|b| b.service_id.as_str()).collect();
+
+            let txn_models: Vec<TransactionModel> = transactions::table
+                .filter(transactions::batch_id.eq_any(&batch_ids))
+                .filter(transactions::service_id.eq_any(&service_ids))
+                .load(self.conn)?;
+            let txn_models = self.decrypt_payloads(txn_models)?;
+
+            let txn_ids: Vec<&str> = txn_models
+                .iter()
+                .map(|t| t.transaction_id.as_str())
+                .collect();
+
+            let receipt_models: Vec<TransactionReceiptModel> = transaction_receipts::table
+                .filter(transaction_receipts::transaction_id.eq_any(&txn_ids))
+                .filter(transaction_receipts::service_id.eq_any(&service_ids))
+                .load(self.conn)?;
+
+            let address_models: Vec<TransactionAddressModel> = transaction_addresses::table
+                .filter(transaction_addresses::transaction_id.eq_any(&txn_ids))
+                .filter(transaction_addresses::service_id.eq_any(&service_ids))
+                .load(self.conn)?;
+
+            TrackingBatchList::try_from((
+                batch_models,
+                batch_status_models,
+                txn_models,
+                address_models,
+                receipt_models,
+                submission_models,
+            ))
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreGetUnsubmittedBatchesLimitedOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn get_unsubmitted_batches_limited(
+        &self,
+        service_id: Option<&str>,
+        limit: i64,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        self.transaction("get_unsubmitted_batches_limited", || {
+            let unsubmitted_statuses = vec![
+                BatchStatus::Unknown.to_string(),
+                BatchStatus::Delayed.to_string(),
+            ];
+
+            let mut query = batches::table
+                .into_boxed()
+                .left_join(
+                    batch_statuses::table.on(batches::batch_id
+                        .eq(batch_statuses::batch_id)
+                        .and(batches::service_id.eq(batch_statuses::service_id))),
+                )
+                .left_join(
+                    submissions::table.on(batches::batch_id
+                        .eq(submissions::batch_id)
+                        .and(batches::service_id.eq(submissions::service_id))),
+                )
+                .filter(
+                    batch_statuses::dlt_status
+                        .eq_any(&unsubmitted_statuses)
+                        .or(batches::submitted.eq(false)),
+                )
+                .filter(
+                    batch_statuses::dlt_status
+                        .is_null()
+                        .or(batch_statuses::dlt_status.ne(BatchStatus::DeadLettered.to_string())),
+                )
+                .order((batches::created_at.asc(), batches::batch_id.asc()))
+                .select((
+                    batches::all_columns,
+                    batch_statuses::all_columns.nullable(),
+                    submissions::all_columns.nullable(),
+                ))
+                .limit(limit);
+
+            if let Some(service_id) = service_id {
+                query = query.filter(batches::service_id.eq(service_id));
+            }
+
+            let batch_results: Vec<(
+                BatchModel,
+                Option<BatchStatusModel>,
+                Option<SubmissionModel>,
+            )> = query.load(self.conn)?;
+
+            let mut batch_models = Vec::new();
+            let mut batch_status_models = Vec::new();
+            let mut submission_models = Vec::new();
+
+            for (batch, status, submission) in batch_results {
+                batch_models.push(batch);
+                if let Some(status) = status {
+                    batch_status_models.push(status);
+                }
+                if let Some(submission) = submission {
+                    submission_models.push(submission);
+                }
+            }
+
+            let batch_ids: Vec<&str> = batch_models.iter().map(|b| b.batch_id.as_str()).collect();
+            let service_ids: Vec<&str> =
+                batch_models.iter().map(|b| b.service_id.as_str()).collect();
+
+            let txn_models: Vec<TransactionModel> = transactions::table
+                .filter(transactions::batch_id.eq_any(&batch_ids))
+                .filter(transactions::service_id.eq_any(&service_ids))
+                .load(self.conn)?;
+            let txn_models = self.decrypt_payloads(txn_models)?;
+
+            let txn_ids: Vec<&str> = txn_models
+                .iter()
+                .map(|t| t.transaction_id.as_str())
+                .collect();
+
+            let receipt_models: Vec<TransactionReceiptModel> = transaction_receipts::table
+                .filter(transaction_receipts::transaction_id.eq_any(&txn_ids))
+                .filter(transaction_receipts::service_id.eq_any(&service_ids))
+                .load(self.conn)?;
+
+            let address_models: Vec<TransactionAddressModel> = transaction_addresses::table
+                .filter(transaction_addresses::transaction_id.eq_any(&txn_ids))
+                .filter(transaction_addresses::service_id.eq_any(&service_ids))
+                .load(self.conn)?;
+
+            TrackingBatchList::try_from((
+                batch_models,
+                batch_status_models,
+                txn_models,
+                address_models,
+                receipt_models,
+                submission_models,
+            ))
+        })
+    }
+}
@@ -0,0 +1,105 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::{
+    diesel::schema::{batch_statuses, batches},
+    BatchStatusName, BatchTrackingStoreError,
+};
+
+use diesel::{dsl::exists, prelude::*, select};
+
+const UNSUBMITTED_STATUSES: [BatchStatusName; 2] =
+    [BatchStatusName::Delayed, BatchStatusName::Unknown];
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreHasUnsubmittedBatchesOperation
+{
+    fn has_unsubmitted_batches(
+        &self,
+        service_id: Option<&str>,
+    ) -> Result<bool, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreHasUnsubmittedBatchesOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn has_unsubmitted_batches(
+        &self,
+        service_id: Option<&str>,
+    ) -> Result<bool, BatchTrackingStoreError> {
+        self.transaction("has_unsubmitted_batches", || {
+            // Matches the batches returned by get_unsubmitted_batches
+            let mut query =
+                batches::table
+                    .left_join(
+                        batch_statuses::table.on(batches::batch_id
+                            .eq(batch_statuses::batch_id)
+                            .and(batches::service_id.eq(batch_statuses::service_id))),
+                    )
+                    .filter(
+                        batches::submitted.eq(false).or(batch_statuses::dlt_status
+                            .eq_any(UNSUBMITTED_STATUSES.iter().map(ToString::to_string))),
+                    )
+                    .filter(batch_statuses::dlt_status.is_null().or(
+                        batch_statuses::dlt_status.ne(BatchStatusName::DeadLettered.to_string()),
+                    ))
+                    .select(batches::batch_id)
+                    .into_boxed();
+
+            if let Some(service_id) = service_id {
+                query = query.filter(batches::service_id.eq(service_id));
+            }
+
+            Ok(select(exists(query)).get_result(self.conn)?)
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreHasUnsubmittedBatchesOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn has_unsubmitted_batches(
+        &self,
+        service_id: Option<&str>,
+    ) -> Result<bool, BatchTrackingStoreError> {
+        self.transaction("has_unsubmitted_batches", || {
+            // Matches the batches returned by get_unsubmitted_batches
+            let mut query =
+                batches::table
+                    .left_join(
+                        batch_statuses::table.on(batches::batch_id
+                            .eq(batch_statuses::batch_id)
+                            .and(batches::service_id.eq(batch_statuses::service_id))),
+                    )
+                    .filter(
+                        batches::submitted.eq(false).or(batch_statuses::dlt_status
+                            .eq_any(UNSUBMITTED_STATUSES.iter().map(ToString::to_string))),
+                    )
+                    .filter(batch_statuses::dlt_status.is_null().or(
+                        batch_statuses::dlt_status.ne(BatchStatusName::DeadLettered.to_string()),
+                    ))
+                    .select(batches::batch_id)
+                    .into_boxed();
+
+            if let Some(service_id) = service_id {
+                query = query.filter(batches::service_id.eq(service_id));
+            }
+
+            Ok(select(exists(query)).get_result(self.conn)?)
+        })
+    }
+}
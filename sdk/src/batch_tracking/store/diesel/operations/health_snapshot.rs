@@ -0,0 +1,134 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::{
+    diesel::schema::{batch_statuses, batches},
+    BatchStatusName, BatchTrackingStoreError, HealthSnapshot,
+};
+
+use diesel::{dsl::sql, prelude::*, sql_types::BigInt};
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreHealthSnapshotOperation {
+    fn health_snapshot(
+        &self,
+        service_id: &str,
+        stuck_threshold: i64,
+    ) -> Result<HealthSnapshot, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreHealthSnapshotOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn health_snapshot(
+        &self,
+        service_id: &str,
+        stuck_threshold: i64,
+    ) -> Result<HealthSnapshot, BatchTrackingStoreError> {
+        self.transaction("health_snapshot", || {
+            // The counts are taken in a single query by summing a condition
+            // for each; diesel has no conditional aggregates, so they are
+            // written as raw SQL
+            let (total, failed, stuck_pending, unsubmitted): (i64, i64, i64, i64) = batches::table
+                .left_join(
+                    batch_statuses::table.on(batches::batch_id
+                        .eq(batch_statuses::batch_id)
+                        .and(batches::service_id.eq(batch_statuses::service_id))),
+                )
+                .filter(batches::service_id.eq(service_id))
+                .select((
+                    sql::<BigInt>("COUNT(*)"),
+                    sql::<BigInt>(&format!(
+                        "COALESCE(SUM(CASE WHEN batch_statuses.dlt_status IN ('{}', '{}') \
+                             THEN 1 ELSE 0 END), 0)",
+                        BatchStatusName::Invalid,
+                        BatchStatusName::DeadLettered,
+                    )),
+                    sql::<BigInt>(&format!(
+                        "COALESCE(SUM(CASE WHEN batch_statuses.dlt_status = '{}' \
+                             AND batch_statuses.updated_at < ",
+                        BatchStatusName::Pending,
+                    ))
+                    .bind::<BigInt, _>(stuck_threshold)
+                    .sql(" THEN 1 ELSE 0 END), 0)"),
+                    sql::<BigInt>(
+                        "COALESCE(SUM(CASE WHEN NOT batches.submitted \
+                             AND batches.dead_letter_reason IS NULL THEN 1 ELSE 0 END), 0)",
+                    ),
+                ))
+                .get_result(self.conn)?;
+
+            Ok(HealthSnapshot::new(
+                total,
+                failed,
+                stuck_pending,
+                unsubmitted,
+            ))
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreHealthSnapshotOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn health_snapshot(
+        &self,
+        service_id: &str,
+        stuck_threshold: i64,
+    ) -> Result<HealthSnapshot, BatchTrackingStoreError> {
+        self.transaction("health_snapshot", || {
+            // The counts are taken in a single query by summing a condition
+            // for each; diesel has no conditional aggregates, so they are
+            // written as raw SQL
+            let (total, failed, stuck_pending, unsubmitted): (i64, i64, i64, i64) = batches::table
+                .left_join(
+                    batch_statuses::table.on(batches::batch_id
+                        .eq(batch_statuses::batch_id)
+                        .and(batches::service_id.eq(batch_statuses::service_id))),
+                )
+                .filter(batches::service_id.eq(service_id))
+                .select((
+                    sql::<BigInt>("COUNT(*)"),
+                    sql::<BigInt>(&format!(
+                        "COALESCE(SUM(CASE WHEN batch_statuses.dlt_status IN ('{}', '{}') \
+                             THEN 1 ELSE 0 END), 0)",
+                        BatchStatusName::Invalid,
+                        BatchStatusName::DeadLettered,
+                    )),
+                    sql::<BigInt>(&format!(
+                        "COALESCE(SUM(CASE WHEN batch_statuses.dlt_status = '{}' \
+                             AND batch_statuses.updated_at < ",
+                        BatchStatusName::Pending,
+                    ))
+                    .bind::<BigInt, _>(stuck_threshold)
+                    .sql(" THEN 1 ELSE 0 END), 0)"),
+                    sql::<BigInt>(
+                        "COALESCE(SUM(CASE WHEN NOT batches.submitted \
+                             AND batches.dead_letter_reason IS NULL THEN 1 ELSE 0 END), 0)",
+                    ),
+                ))
+                .get_result(self.conn)?;
+
+            Ok(HealthSnapshot::new(
+                total,
+                failed,
+                stuck_pending,
+                unsubmitted,
+            ))
+        })
+    }
+}
@@ -0,0 +1,90 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::Duration;
+
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::{diesel::schema::batches, BatchTrackingStoreError};
+
+use diesel::{dsl::sql, prelude::*, sql_types::BigInt};
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreHourlyBatchCountsOperation {
+    fn hourly_batch_counts(
+        &self,
+        service_id: &str,
+        start: i64,
+        end: i64,
+    ) -> Result<Vec<(i64, i64)>, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreHourlyBatchCountsOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn hourly_batch_counts(
+        &self,
+        service_id: &str,
+        start: i64,
+        end: i64,
+    ) -> Result<Vec<(i64, i64)>, BatchTrackingStoreError> {
+        self.transaction("hourly_batch_counts", || {
+            // Each batch is bucketed by rounding its creation time down to
+            // the hour; diesel has no arithmetic on columns in a group by, so
+            // the bucket is written as raw SQL
+            let hour = self.in_precision(Duration::from_secs(3600));
+            let bucket = format!("batches.created_at - batches.created_at % {}", hour);
+
+            batches::table
+                .filter(batches::service_id.eq(service_id))
+                .filter(batches::created_at.ge(start))
+                .filter(batches::created_at.lt(end))
+                .group_by(sql::<BigInt>(&bucket))
+                .order(sql::<BigInt>(&bucket).asc())
+                .select((sql::<BigInt>(&bucket), sql::<BigInt>("COUNT(*)")))
+                .load(self.conn)
+                .map_err(BatchTrackingStoreError::from)
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreHourlyBatchCountsOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn hourly_batch_counts(
+        &self,
+        service_id: &str,
+        start: i64,
+        end: i64,
+    ) -> Result<Vec<(i64, i64)>, BatchTrackingStoreError> {
+        self.transaction("hourly_batch_counts", || {
+            // Each batch is bucketed by rounding its creation time down to
+            // the hour; diesel has no arithmetic on columns in a group by, so
+            // the bucket is written as raw SQL
+            let hour = self.in_precision(Duration::from_secs(3600));
+            let bucket = format!("batches.created_at - batches.created_at % {}", hour);
+
+            batches::table
+                .filter(batches::service_id.eq(service_id))
+                .filter(batches::created_at.ge(start))
+                .filter(batches::created_at.lt(end))
+                .group_by(sql::<BigInt>(&bucket))
+                .order(sql::<BigInt>(&bucket).asc())
+                .select((sql::<BigInt>(&bucket), sql::<BigInt>("COUNT(*)")))
+                .load(self.conn)
+                .map_err(BatchTrackingStoreError::from)
+        })
+    }
+}
@@ -0,0 +1,96 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::{
+    diesel::schema::batch_statuses, BatchStatusName, BatchTrackingStoreError,
+};
+
+use diesel::prelude::*;
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreIdStatusPageOperation {
+    fn id_status_page(
+        &self,
+        service_id: &str,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<(String, BatchStatusName)>, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreIdStatusPageOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn id_status_page(
+        &self,
+        service_id: &str,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<(String, BatchStatusName)>, BatchTrackingStoreError> {
+        self.transaction("id_status_page", || {
+            let mut query = batch_statuses::table
+                .filter(batch_statuses::service_id.eq(service_id))
+                .select((batch_statuses::batch_id, batch_statuses::dlt_status))
+                .order(batch_statuses::batch_id.asc())
+                .limit(limit)
+                .into_boxed();
+
+            if let Some(after) = after {
+                query = query.filter(batch_statuses::batch_id.gt(after));
+            }
+
+            let rows: Vec<(String, String)> = query.load(self.conn)?;
+
+            rows.into_iter()
+                .map(|(batch_id, status)| {
+                    Ok((batch_id, BatchStatusName::try_from_string(&status)?))
+                })
+                .collect()
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreIdStatusPageOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn id_status_page(
+        &self,
+        service_id: &str,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<(String, BatchStatusName)>, BatchTrackingStoreError> {
+        self.transaction("id_status_page", || {
+            let mut query = batch_statuses::table
+                .filter(batch_statuses::service_id.eq(service_id))
+                .select((batch_statuses::batch_id, batch_statuses::dlt_status))
+                .order(batch_statuses::batch_id.asc())
+                .limit(limit)
+                .into_boxed();
+
+            if let Some(after) = after {
+                query = query.filter(batch_statuses::batch_id.gt(after));
+            }
+
+            let rows: Vec<(String, String)> = query.load(self.conn)?;
+
+            rows.into_iter()
+                .map(|(batch_id, status)| {
+                    Ok((batch_id, BatchStatusName::try_from_string(&status)?))
+                })
+                .collect()
+        })
+    }
+}
@@ -0,0 +1,99 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::resolve_data_change_id::BatchTrackingStoreResolveDataChangeIdOperation;
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::{
+    diesel::{
+        models::{is_data_change_id, BatchStatusEventModel},
+        schema::batch_status_events,
+    },
+    BatchStatusEvent, BatchTrackingStoreError,
+};
+use diesel::prelude::*;
+use std::convert::TryFrom;
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreListBatchStatusEventsOperation
+{
+    fn list_batch_status_events(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<Vec<BatchStatusEvent>, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreListBatchStatusEventsOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn list_batch_status_events(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<Vec<BatchStatusEvent>, BatchTrackingStoreError> {
+        self.transaction("list_batch_status_events", || {
+            let mut batch_id = id.to_string();
+            if is_data_change_id(id)? {
+                batch_id = self
+                    .resolve_data_change_id(id, service_id)?
+                    .unwrap_or(batch_id);
+            }
+
+            batch_status_events::table
+                .filter(
+                    batch_status_events::batch_id
+                        .eq(&batch_id)
+                        .and(batch_status_events::service_id.eq(&service_id)),
+                )
+                .order(batch_status_events::id.asc())
+                .load::<BatchStatusEventModel>(self.conn)?
+                .into_iter()
+                .map(BatchStatusEvent::try_from)
+                .collect()
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreListBatchStatusEventsOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn list_batch_status_events(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<Vec<BatchStatusEvent>, BatchTrackingStoreError> {
+        self.transaction("list_batch_status_events", || {
+            let mut batch_id = id.to_string();
+            if is_data_change_id(id)? {
+                batch_id = self
+                    .resolve_data_change_id(id, service_id)?
+                    .unwrap_or(batch_id);
+            }
+
+            batch_status_events::table
+                .filter(
+                    batch_status_events::batch_id
+                        .eq(&batch_id)
+                        .and(batch_status_events::service_id.eq(&service_id)),
+                )
+                .order(batch_status_events::id.asc())
+                .load::<BatchStatusEventModel>(self.conn)?
+                .into_iter()
+                .map(BatchStatusEvent::try_from)
+                .collect()
+        })
+    }
+}
@@ -0,0 +1,210 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::diesel::{
+    models::{
+        BatchModel, BatchStatusModel, JsonObjectModel, SubmissionModel, TransactionAddressModel,
+        TransactionModel, TransactionReceiptModel,
+    },
+    schema::{
+        batch_statuses, batches, submissions, transaction_addresses, transaction_receipts,
+        transactions,
+    },
+    TrackingBatchList,
+};
+
+use crate::batch_tracking::store::BatchTrackingStoreError;
+use diesel::prelude::*;
+use std::convert::TryFrom;
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreListBatchesByLabelsOperation {
+    fn list_batches_by_labels(
+        &self,
+        predicates: &[(String, String)],
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError>;
+}
+
+/// Returns true if the metadata has a string value for each predicate's key
+/// equal to the predicate's value
+fn labels_match(metadata: Option<&JsonObjectModel>, predicates: &[(String, String)]) -> bool {
+    predicates.iter().all(|(key, value)| {
+        metadata
+            .and_then(|metadata| metadata.0.get(key))
+            .and_then(|label| label.as_str())
+            == Some(value.as_str())
+    })
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreListBatchesByLabelsOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn list_batches_by_labels(
+        &self,
+        predicates: &[(String, String)],
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        self.transaction("list_batches_by_labels", || {
+            // Labels are the string values of the batch metadata, which is
+            // stored differently by each backend, so the predicates are
+            // matched here rather than in the query
+            let labelled: Vec<(String, Option<JsonObjectModel>)> = batches::table
+                .filter(batches::service_id.eq(service_id))
+                .select((batches::batch_id, batches::metadata))
+                .load(self.conn)?;
+            let matching_ids: Vec<String> = labelled
+                .into_iter()
+                .filter(|(_, metadata)| labels_match(metadata.as_ref(), predicates))
+                .map(|(batch_id, _)| batch_id)
+                .collect();
+
+            let batch_models: Vec<BatchModel> = batches::table
+                .filter(batches::service_id.eq(service_id))
+                .filter(batches::batch_id.eq_any(&matching_ids))
+                .order((batches::created_at.asc(), batches::batch_id.asc()))
+                .load(self.conn)?;
+
+            if batch_models.is_empty() {
+                return Ok(TrackingBatchList {
+                    batches: Vec::new(),
+                });
+            }
+
+            let batch_ids: Vec<&str> = batch_models.iter().map(|b| b.batch_id.as_str()).collect();
+
+            let batch_status_models: Vec<BatchStatusModel> = batch_statuses::table
+                .filter(batch_statuses::service_id.eq(service_id))
+                .filter(batch_statuses::batch_id.eq_any(&batch_ids))
+                .load(self.conn)?;
+
+            let submission_models: Vec<SubmissionModel> = submissions::table
+                .filter(submissions::service_id.eq(service_id))
+                .filter(submissions::batch_id.eq_any(&batch_ids))
+                .load(self.conn)?;
+
+            let txn_models: Vec<TransactionModel> = transactions::table
+                .filter(transactions::service_id.eq(service_id))
+                .filter(transactions::batch_id.eq_any(&batch_ids))
+                .load(self.conn)?;
+            let txn_models = self.decrypt_payloads(txn_models)?;
+
+            let txn_ids: Vec<&str> = txn_models
+                .iter()
+                .map(|t| t.transaction_id.as_str())
+                .collect();
+
+            let address_models: Vec<TransactionAddressModel> = transaction_addresses::table
+                .filter(transaction_addresses::service_id.eq(service_id))
+                .filter(transaction_addresses::transaction_id.eq_any(&txn_ids))
+                .load(self.conn)?;
+
+            let receipt_models: Vec<TransactionReceiptModel> = transaction_receipts::table
+                .filter(transaction_receipts::service_id.eq(service_id))
+                .filter(transaction_receipts::transaction_id.eq_any(&txn_ids))
+                .load(self.conn)?;
+
+            TrackingBatchList::try_from((
+                batch_models,
+                batch_status_models,
+                txn_models,
+                address_models,
+                receipt_models,
+                submission_models,
+            ))
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreListBatchesByLabelsOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn list_batches_by_labels(
+        &self,
+        predicates: &[(String, String)],
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        self.transaction("list_batches_by_labels", || {
+            // Labels are the string values of the batch metadata, which is
+            // stored differently by each backend, so the predicates are
+            // matched here rather than in the query
+            let labelled: Vec<(String, Option<JsonObjectModel>)> = batches::table
+                .filter(batches::service_id.eq(service_id))
+                .select((batches::batch_id, batches::metadata))
+                .load(self.conn)?;
+            let matching_ids: Vec<String> = labelled
+                .into_iter()
+                .filter(|(_, metadata)| labels_match(metadata.as_ref(), predicates))
+                .map(|(batch_id, _)| batch_id)
+                .collect();
+
+            let batch_models: Vec<BatchModel> = batches::table
+                .filter(batches::service_id.eq(service_id))
+                .filter(batches::batch_id.eq_any(&matching_ids))
+                .order((batches::created_at.asc(), batches::batch_id.asc()))
+                .load(self.conn)?;
+
+            if batch_models.is_empty() {
+                return Ok(TrackingBatchList {
+                    batches: Vec::new(),
+                });
+            }
+
+            let batch_ids: Vec<&str> = batch_models.iter().map(|b| b.batch_id.as_str()).collect();
+
+            let batch_status_models: Vec<BatchStatusModel> = batch_statuses::table
+                .filter(batch_statuses::service_id.eq(service_id))
+                .filter(batch_statuses::batch_id.eq_any(&batch_ids))
+                .load(self.conn)?;
+
+            let submission_models: Vec<SubmissionModel> = submissions::table
+                .filter(submissions::service_id.eq(service_id))
+                .filter(submissions::batch_id.eq_any(&batch_ids))
+                .load(self.conn)?;
+
+            let txn_models: Vec<TransactionModel> = transactions::table
+                .filter(transactions::service_id.eq(service_id))
+                .filter(transactions::batch_id.eq_any(&batch_ids))
+                .load(self.conn)?;
+            let txn_models = self.decrypt_payloads(txn_models)?;
+
+            let txn_ids: Vec<&str> = txn_models
+                .iter()
+                .map(|t| t.transaction_id.as_str())
+                .collect();
+
+            let address_models: Vec<TransactionAddressModel> = transaction_addresses::table
+                .filter(transaction_addresses::service_id.eq(service_id))
+                .filter(transaction_addresses::transaction_id.eq_any(&txn_ids))
+                .load(self.conn)?;
+
+            let receipt_models: Vec<TransactionReceiptModel> = transaction_receipts::table
+                .filter(transaction_receipts::service_id.eq(service_id))
+                .filter(transaction_receipts::transaction_id.eq_any(&txn_ids))
+                .load(self.conn)?;
+
+            TrackingBatchList::try_from((
+                batch_models,
+                batch_status_models,
+                txn_models,
+                address_models,
+                receipt_models,
+                submission_models,
+            ))
+        })
+    }
+}
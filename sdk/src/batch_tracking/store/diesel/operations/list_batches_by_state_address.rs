@@ -0,0 +1,208 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::diesel::{
+    models::{
+        BatchModel, BatchStatusModel, SubmissionModel, TransactionAddressModel, TransactionModel,
+        TransactionReceiptModel,
+    },
+    schema::{
+        batch_statuses, batches, submissions, transaction_addresses, transaction_receipts,
+        transactions,
+    },
+    TrackingBatchList,
+};
+
+use crate::batch_tracking::store::BatchTrackingStoreError;
+use diesel::prelude::*;
+use std::convert::TryFrom;
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreListBatchesByStateAddressOperation
+{
+    fn list_batches_by_state_address(
+        &self,
+        address: &str,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreListBatchesByStateAddressOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn list_batches_by_state_address(
+        &self,
+        address: &str,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        self.transaction("list_batches_by_state_address", || {
+            // Find the batches containing a transaction that reads from or
+            // writes to the given address
+            let batch_models: Vec<BatchModel> = batches::table
+                .filter(batches::service_id.eq(service_id))
+                .filter(
+                    batches::batch_id.eq_any(
+                        transactions::table
+                            .inner_join(
+                                transaction_addresses::table.on(transactions::transaction_id
+                                    .eq(transaction_addresses::transaction_id)
+                                    .and(
+                                        transactions::service_id
+                                            .eq(transaction_addresses::service_id),
+                                    )),
+                            )
+                            .filter(transaction_addresses::service_id.eq(service_id))
+                            .filter(transaction_addresses::address.eq(address))
+                            .select(transactions::batch_id),
+                    ),
+                )
+                .order((batches::created_at.asc(), batches::batch_id.asc()))
+                .load(self.conn)?;
+
+            if batch_models.is_empty() {
+                return Ok(TrackingBatchList {
+                    batches: Vec::new(),
+                });
+            }
+
+            let batch_ids: Vec<&str> = batch_models.iter().map(|b| b.batch_id.as_str()).collect();
+
+            let batch_status_models: Vec<BatchStatusModel> = batch_statuses::table
+                .filter(batch_statuses::service_id.eq(service_id))
+                .filter(batch_statuses::batch_id.eq_any(&batch_ids))
+                .load(self.conn)?;
+
+            let submission_models: Vec<SubmissionModel> = submissions::table
+                .filter(submissions::service_id.eq(service_id))
+                .filter(submissions::batch_id.eq_any(&batch_ids))
+                .load(self.conn)?;
+
+            let txn_models: Vec<TransactionModel> = transactions::table
+                .filter(transactions::service_id.eq(service_id))
+                .filter(transactions::batch_id.eq_any(&batch_ids))
+                .load(self.conn)?;
+            let txn_models = self.decrypt_payloads(txn_models)?;
+
+            let txn_ids: Vec<&str> = txn_models
+                .iter()
+                .map(|t| t.transaction_id.as_str())
+                .collect();
+
+            let address_models: Vec<TransactionAddressModel> = transaction_addresses::table
+                .filter(transaction_addresses::service_id.eq(service_id))
+                .filter(transaction_addresses::transaction_id.eq_any(&txn_ids))
+                .load(self.conn)?;
+
+            let receipt_models: Vec<TransactionReceiptModel> = transaction_receipts::table
+                .filter(transaction_receipts::service_id.eq(service_id))
+                .filter(transaction_receipts::transaction_id.eq_any(&txn_ids))
+                .load(self.conn)?;
+
+            TrackingBatchList::try_from((
+                batch_models,
+                batch_status_models,
+                txn_models,
+                address_models,
+                receipt_models,
+                submission_models,
+            ))
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreListBatchesByStateAddressOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn list_batches_by_state_address(
+        &self,
+        address: &str,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        self.transaction("list_batches_by_state_address", || {
+            // Find the batches containing a transaction that reads from or
+            // writes to the given address
+            let batch_models: Vec<BatchModel> = batches::table
+                .filter(batches::service_id.eq(service_id))
+                .filter(
+                    batches::batch_id.eq_any(
+                        transactions::table
+                            .inner_join(
+                                transaction_addresses::table.on(transactions::transaction_id
+                                    .eq(transaction_addresses::transaction_id)
+                                    .and(
+                                        transactions::service_id
+                                            .eq(transaction_addresses::service_id),
+                                    )),
+                            )
+                            .filter(transaction_addresses::service_id.eq(service_id))
+                            .filter(transaction_addresses::address.eq(address))
+                            .select(transactions::batch_id),
+                    ),
+                )
+                .order((batches::created_at.asc(), batches::batch_id.asc()))
+                .load(self.conn)?;
+
+            if batch_models.is_empty() {
+                return Ok(TrackingBatchList {
+                    batches: Vec::new(),
+                });
+            }
+
+            let batch_ids: Vec<&str> = batch_models.iter().map(|b| b.batch_id.as_str()).collect();
+
+            let batch_status_models: Vec<BatchStatusModel> = batch_statuses::table
+                .filter(batch_statuses::service_id.eq(service_id))
+                .filter(batch_statuses::batch_id.eq_any(&batch_ids))
+                .load(self.conn)?;
+
+            let submission_models: Vec<SubmissionModel> = submissions::table
+                .filter(submissions::service_id.eq(service_id))
+                .filter(submissions::batch_id.eq_any(&batch_ids))
+                .load(self.conn)?;
+
+            let txn_models: Vec<TransactionModel> = transactions::table
+                .filter(transactions::service_id.eq(service_id))
+                .filter(transactions::batch_id.eq_any(&batch_ids))
+                .load(self.conn)?;
+            let txn_models = self.decrypt_payloads(txn_models)?;
+
+            let txn_ids: Vec<&str> = txn_models
+                .iter()
+                .map(|t| t.transaction_id.as_str())
+                .collect();
+
+            let address_models: Vec<TransactionAddressModel> = transaction_addresses::table
+                .filter(transaction_addresses::service_id.eq(service_id))
+                .filter(transaction_addresses::transaction_id.eq_any(&txn_ids))
+                .load(self.conn)?;
+
+            let receipt_models: Vec<TransactionReceiptModel> = transaction_receipts::table
+                .filter(transaction_receipts::service_id.eq(service_id))
+                .filter(transaction_receipts::transaction_id.eq_any(&txn_ids))
+                .load(self.conn)?;
+
+            TrackingBatchList::try_from((
+                batch_models,
+                batch_status_models,
+                txn_models,
+                address_models,
+                receipt_models,
+                submission_models,
+            ))
+        })
+    }
+}
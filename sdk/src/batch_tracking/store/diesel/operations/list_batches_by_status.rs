@@ -16,7 +16,8 @@ use super::BatchTrackingStoreOperations;
 
 use crate::batch_tracking::store::diesel::{
     models::{
-        BatchModel, BatchStatusModel, SubmissionModel, TransactionModel, TransactionReceiptModel,
+        BatchModel, BatchStatusModel, SubmissionModel, TransactionAddressModel, TransactionModel,
+        TransactionReceiptModel,
     },
     schema::{batch_statuses, batches},
     TrackingBatchList,
@@ -41,7 +42,7 @@ impl<'a> BatchTrackingStoreListBatchesByStatusOperation
         &self,
         status: &str,
     ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
-        self.conn.transaction::<_, BatchTrackingStoreError, _>(|| {
+        self.transaction("list_batches_by_status", || {
             let batches_and_statuses: Vec<(BatchModel, Option<BatchStatusModel>)> = batches::table
                 .into_boxed()
                 .left_join(batch_statuses::table.on(
@@ -93,6 +94,7 @@ impl<'a> BatchTrackingStoreListBatchesByStatusOperation
                 &status
             ))
             .load(self.conn)?;
+            let txn_models = self.decrypt_payloads(txn_models)?;
 
             let receipt_models: Vec<TransactionReceiptModel> = sql_query(format!(
                 "WITH bbs AS (
@@ -109,7 +111,22 @@ impl<'a> BatchTrackingStoreListBatchesByStatusOperation
             ))
             .load(self.conn)?;
 
-            let batches = TrackingBatchList::try_from((batch_models, batch_status_models, txn_models, receipt_models, submission_models))?;
+            let address_models: Vec<TransactionAddressModel> = sql_query(format!(
+                "WITH bbs AS (
+                    SELECT b.batch_id, b.service_id FROM batches b
+                    LEFT JOIN batch_statuses bs ON bs.batch_id = b.batch_id AND bs.service_id = b.service_id
+                    WHERE bs.dlt_status = '{}'
+                ), txn_models AS (
+                    SELECT t.transaction_id, t.service_id FROM transactions t
+                    WHERE (t.service_id, t.batch_id) IN (SELECT service_id, batch_id FROM bbs)
+                )
+                SELECT * FROM transaction_addresses ta
+                WHERE (ta.service_id, ta.transaction_id) IN (SELECT service_id, transaction_id FROM txn_models);",
+                &status
+            ))
+            .load(self.conn)?;
+
+            let batches = TrackingBatchList::try_from((batch_models, batch_status_models, txn_models, address_models, receipt_models, submission_models))?;
             Ok(batches)
         })
     }
@@ -123,7 +140,7 @@ impl<'a> BatchTrackingStoreListBatchesByStatusOperation
         &self,
         status: &str,
     ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
-        self.conn.transaction::<_, BatchTrackingStoreError, _>(|| {
+        self.transaction("list_batches_by_status", || {
             let batches_and_statuses: Vec<(BatchModel, Option<BatchStatusModel>)> = batches::table
                 .into_boxed()
                 .left_join(batch_statuses::table.on(
@@ -175,6 +192,7 @@ impl<'a> BatchTrackingStoreListBatchesByStatusOperation
                 &status
             ))
             .load(self.conn)?;
+            let txn_models = self.decrypt_payloads(txn_models)?;
 
             let receipt_models: Vec<TransactionReceiptModel> = sql_query(format!(
                 "WITH bbs AS (
@@ -191,7 +209,22 @@ impl<'a> BatchTrackingStoreListBatchesByStatusOperation
             ))
             .load(self.conn)?;
 
-            let batches = TrackingBatchList::try_from((batch_models, batch_status_models, txn_models, receipt_models, submission_models))?;
+            let address_models: Vec<TransactionAddressModel> = sql_query(format!(
+                "WITH bbs AS (
+                    SELECT b.batch_id, b.service_id FROM batches b
+                    LEFT JOIN batch_statuses bs ON bs.batch_id = b.batch_id AND bs.service_id = b.service_id
+                    WHERE bs.dlt_status = '{}'
+                ), txn_models AS (
+                    SELECT t.transaction_id, t.service_id FROM transactions t
+                    WHERE (t.service_id, t.batch_id) IN (SELECT service_id, batch_id FROM bbs)
+                )
+                SELECT * FROM transaction_addresses ta
+                WHERE (ta.service_id, ta.transaction_id) IN (SELECT service_id, transaction_id FROM txn_models);",
+                &status
+            ))
+            .load(self.conn)?;
+
+            let batches = TrackingBatchList::try_from((batch_models, batch_status_models, txn_models, address_models, receipt_models, submission_models))?;
             Ok(batches)
         })
     }
@@ -0,0 +1,123 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::diesel::schema::{batch_statuses, batches, submissions};
+use crate::batch_tracking::store::{BatchStatusName, BatchTrackingStoreError, FailureSummary};
+use diesel::prelude::*;
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreListFailureSummariesOperation {
+    fn list_failure_summaries(
+        &self,
+        service_id: &str,
+        limit: i64,
+    ) -> Result<Vec<FailureSummary>, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreListFailureSummariesOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn list_failure_summaries(
+        &self,
+        service_id: &str,
+        limit: i64,
+    ) -> Result<Vec<FailureSummary>, BatchTrackingStoreError> {
+        self.transaction("list_failure_summaries", || {
+            let failed_statuses: Vec<String> = vec![
+                BatchStatusName::Unknown.to_string(),
+                BatchStatusName::Invalid.to_string(),
+            ];
+
+            let summaries: Vec<(String, Option<String>, Option<String>, i64)> = batches::table
+                .inner_join(
+                    batch_statuses::table.on(batches::batch_id
+                        .eq(batch_statuses::batch_id)
+                        .and(batches::service_id.eq(batch_statuses::service_id))),
+                )
+                .left_join(
+                    submissions::table.on(batches::batch_id
+                        .eq(submissions::batch_id)
+                        .and(batches::service_id.eq(submissions::service_id))),
+                )
+                .filter(batches::service_id.eq(service_id))
+                .filter(batch_statuses::dlt_status.eq_any(failed_statuses))
+                .order((batch_statuses::updated_at.desc(), batches::batch_id.asc()))
+                .limit(limit)
+                .select((
+                    batches::batch_id,
+                    submissions::error_type.nullable(),
+                    submissions::error_message.nullable(),
+                    batch_statuses::updated_at,
+                ))
+                .load(self.conn)?;
+
+            Ok(summaries
+                .into_iter()
+                .map(|(batch_id, error_type, error_message, failed_at)| {
+                    FailureSummary::new(batch_id, error_type, error_message, failed_at)
+                })
+                .collect())
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreListFailureSummariesOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn list_failure_summaries(
+        &self,
+        service_id: &str,
+        limit: i64,
+    ) -> Result<Vec<FailureSummary>, BatchTrackingStoreError> {
+        self.transaction("list_failure_summaries", || {
+            let failed_statuses: Vec<String> = vec![
+                BatchStatusName::Unknown.to_string(),
+                BatchStatusName::Invalid.to_string(),
+            ];
+
+            let summaries: Vec<(String, Option<String>, Option<String>, i64)> = batches::table
+                .inner_join(
+                    batch_statuses::table.on(batches::batch_id
+                        .eq(batch_statuses::batch_id)
+                        .and(batches::service_id.eq(batch_statuses::service_id))),
+                )
+                .left_join(
+                    submissions::table.on(batches::batch_id
+                        .eq(submissions::batch_id)
+                        .and(batches::service_id.eq(submissions::service_id))),
+                )
+                .filter(batches::service_id.eq(service_id))
+                .filter(batch_statuses::dlt_status.eq_any(failed_statuses))
+                .order((batch_statuses::updated_at.desc(), batches::batch_id.asc()))
+                .limit(limit)
+                .select((
+                    batches::batch_id,
+                    submissions::error_type.nullable(),
+                    submissions::error_message.nullable(),
+                    batch_statuses::updated_at,
+                ))
+                .load(self.conn)?;
+
+            Ok(summaries
+                .into_iter()
+                .map(|(batch_id, error_type, error_message, failed_at)| {
+                    FailureSummary::new(batch_id, error_type, error_message, failed_at)
+                })
+                .collect())
+        })
+    }
+}
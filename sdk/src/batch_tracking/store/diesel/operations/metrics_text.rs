@@ -0,0 +1,199 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::{
+    diesel::schema::{batch_statuses, batches},
+    BatchStatusName, BatchTrackingStoreError,
+};
+
+use diesel::{dsl::sql, prelude::*, sql_types::BigInt};
+
+const STATUSES: [BatchStatusName; 7] = [
+    BatchStatusName::Unknown,
+    BatchStatusName::Pending,
+    BatchStatusName::Delayed,
+    BatchStatusName::Invalid,
+    BatchStatusName::Valid,
+    BatchStatusName::Committed,
+    BatchStatusName::DeadLettered,
+];
+
+const UNSUBMITTED_STATUSES: [BatchStatusName; 2] =
+    [BatchStatusName::Delayed, BatchStatusName::Unknown];
+
+const FAILED_STATUSES: [BatchStatusName; 2] = [BatchStatusName::Unknown, BatchStatusName::Invalid];
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreMetricsTextOperation {
+    fn metrics_text(&self) -> Result<String, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreMetricsTextOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn metrics_text(&self) -> Result<String, BatchTrackingStoreError> {
+        self.transaction("metrics_text", || {
+            let total: i64 = batches::table.count().get_result(self.conn)?;
+
+            let status_counts: Vec<(String, i64)> = batches::table
+                .inner_join(
+                    batch_statuses::table.on(batches::batch_id
+                        .eq(batch_statuses::batch_id)
+                        .and(batches::service_id.eq(batch_statuses::service_id))),
+                )
+                .group_by(batch_statuses::dlt_status)
+                .select((batch_statuses::dlt_status, sql::<BigInt>("COUNT(*)")))
+                .load(self.conn)?;
+
+            let by_status = status_counts
+                .into_iter()
+                .map(|(status, count)| Ok((BatchStatusName::try_from_string(&status)?, count)))
+                .collect::<Result<HashMap<_, _>, BatchTrackingStoreError>>()?;
+
+            // Matches the batches returned by get_unsubmitted_batches
+            let unsubmitted: i64 =
+                batches::table
+                    .left_join(
+                        batch_statuses::table.on(batches::batch_id
+                            .eq(batch_statuses::batch_id)
+                            .and(batches::service_id.eq(batch_statuses::service_id))),
+                    )
+                    .filter(
+                        batches::submitted.eq(false).or(batch_statuses::dlt_status
+                            .eq_any(UNSUBMITTED_STATUSES.iter().map(ToString::to_string))),
+                    )
+                    .filter(batch_statuses::dlt_status.is_null().or(
+                        batch_statuses::dlt_status.ne(BatchStatusName::DeadLettered.to_string()),
+                    ))
+                    .count()
+                    .get_result(self.conn)?;
+
+            // Matches the batches returned by get_failed_batches
+            let failed: i64 = FAILED_STATUSES
+                .iter()
+                .map(|status| by_status.get(status).copied().unwrap_or(0))
+                .sum();
+
+            Ok(format_metrics(total, &by_status, unsubmitted, failed))
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreMetricsTextOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn metrics_text(&self) -> Result<String, BatchTrackingStoreError> {
+        self.transaction("metrics_text", || {
+            let total: i64 = batches::table.count().get_result(self.conn)?;
+
+            let status_counts: Vec<(String, i64)> = batches::table
+                .inner_join(
+                    batch_statuses::table.on(batches::batch_id
+                        .eq(batch_statuses::batch_id)
+                        .and(batches::service_id.eq(batch_statuses::service_id))),
+                )
+                .group_by(batch_statuses::dlt_status)
+                .select((batch_statuses::dlt_status, sql::<BigInt>("COUNT(*)")))
+                .load(self.conn)?;
+
+            let by_status = status_counts
+                .into_iter()
+                .map(|(status, count)| Ok((BatchStatusName::try_from_string(&status)?, count)))
+                .collect::<Result<HashMap<_, _>, BatchTrackingStoreError>>()?;
+
+            // Matches the batches returned by get_unsubmitted_batches
+            let unsubmitted: i64 =
+                batches::table
+                    .left_join(
+                        batch_statuses::table.on(batches::batch_id
+                            .eq(batch_statuses::batch_id)
+                            .and(batches::service_id.eq(batch_statuses::service_id))),
+                    )
+                    .filter(
+                        batches::submitted.eq(false).or(batch_statuses::dlt_status
+                            .eq_any(UNSUBMITTED_STATUSES.iter().map(ToString::to_string))),
+                    )
+                    .filter(batch_statuses::dlt_status.is_null().or(
+                        batch_statuses::dlt_status.ne(BatchStatusName::DeadLettered.to_string()),
+                    ))
+                    .count()
+                    .get_result(self.conn)?;
+
+            // Matches the batches returned by get_failed_batches
+            let failed: i64 = FAILED_STATUSES
+                .iter()
+                .map(|status| by_status.get(status).copied().unwrap_or(0))
+                .sum();
+
+            Ok(format_metrics(total, &by_status, unsubmitted, failed))
+        })
+    }
+}
+
+/// Formats the batch counts in the Prometheus text exposition format
+fn format_metrics(
+    total: i64,
+    by_status: &HashMap<BatchStatusName, i64>,
+    unsubmitted: i64,
+    failed: i64,
+) -> String {
+    let mut text = String::new();
+
+    // Writing to a String cannot fail
+    let _ = writeln!(
+        text,
+        "# HELP grid_batch_tracking_batches Number of batches in the store\n\
+         # TYPE grid_batch_tracking_batches gauge\n\
+         grid_batch_tracking_batches {}",
+        total
+    );
+
+    let _ = writeln!(
+        text,
+        "# HELP grid_batch_tracking_batches_by_status Number of batches with each status\n\
+         # TYPE grid_batch_tracking_batches_by_status gauge"
+    );
+    for status in STATUSES.iter() {
+        let _ = writeln!(
+            text,
+            "grid_batch_tracking_batches_by_status{{status=\"{}\"}} {}",
+            status,
+            by_status.get(status).copied().unwrap_or(0)
+        );
+    }
+
+    let _ = writeln!(
+        text,
+        "# HELP grid_batch_tracking_unsubmitted_batches Number of batches waiting to be submitted\n\
+         # TYPE grid_batch_tracking_unsubmitted_batches gauge\n\
+         grid_batch_tracking_unsubmitted_batches {}",
+        unsubmitted
+    );
+
+    let _ = writeln!(
+        text,
+        "# HELP grid_batch_tracking_failed_batches Number of batches that failed\n\
+         # TYPE grid_batch_tracking_failed_batches gauge\n\
+         grid_batch_tracking_failed_batches {}",
+        failed
+    );
+
+    text
+}
@@ -12,18 +12,122 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub(super) mod ack_outbox;
 pub(super) mod add_batches;
+pub(super) mod add_transact_batches;
+pub(super) mod all_statuses_for_service;
+pub(super) mod average_submission_latency;
+pub(super) mod backup_service;
 pub(super) mod change_batch_to_submitted;
+pub(super) mod claim_unsubmitted_batches;
 pub(super) mod clean_stale_records;
+pub(super) mod commit_batch;
+pub(super) mod compact;
+pub(super) mod content_digest;
+pub(super) mod count_batches_by_origin;
+pub(super) mod count_transactions;
+pub(super) mod created_at_bounds;
+pub(super) mod dead_letter_batch;
+pub(super) mod dead_letter_batches;
+pub(super) mod dedupe_receipts;
+pub(super) mod failed_batches_cursor;
+pub(super) mod fetch_outbox;
+pub(super) mod find_batches_by_transaction_prefix;
+pub(super) mod find_batches_with_excess_receipts;
+pub(super) mod find_batches_without_transactions;
+pub(super) mod find_committed_batches_missing_receipts;
+pub(super) mod find_duplicate_dcids;
+pub(super) mod find_flapping_batches;
 pub(super) mod get_batch;
+pub(super) mod get_batch_by_alias;
+pub(super) mod get_batch_by_transaction_id;
+pub(super) mod get_batch_projected;
 pub(super) mod get_batch_status;
+pub(super) mod get_batch_status_at;
+pub(super) mod get_batch_submission_info;
+pub(super) mod get_batches_awaiting_receipts;
+pub(super) mod get_batches_by_data_change_ids;
+pub(super) mod get_dead_lettered_batches;
 pub(super) mod get_failed_batches;
+pub(super) mod get_latest_submission_error;
+pub(super) mod get_recent_failures;
 pub(super) mod get_unsubmitted_batches;
+pub(super) mod get_unsubmitted_batches_limited;
+pub(super) mod has_unsubmitted_batches;
+pub(super) mod health_snapshot;
+pub(super) mod hourly_batch_counts;
+pub(super) mod id_status_page;
+pub(super) mod list_batch_status_events;
+pub(super) mod list_batches;
+pub(super) mod list_batches_by_attempts;
+pub(super) mod list_batches_by_family;
+pub(super) mod list_batches_by_kind;
+pub(super) mod list_batches_by_labels;
+pub(super) mod list_batches_by_network;
+pub(super) mod list_batches_by_round;
+pub(super) mod list_batches_by_state_address;
 pub(super) mod list_batches_by_status;
+pub(super) mod list_batches_by_status_with_total;
+pub(super) mod list_batches_by_submit_url;
+pub(super) mod list_batches_depending_on;
+pub(super) mod list_batches_status_changed_between;
+pub(super) mod list_failed_batches_recent;
+pub(super) mod list_failure_summaries;
+pub(super) mod metrics_text;
+pub(super) mod normalize_status_values;
+pub(super) mod record_status_event;
+pub(super) mod record_submission_attempt;
+pub(super) mod remap_data_change_ids;
+pub(super) mod repair_missing_statuses;
+pub(super) mod resolve_data_change_id;
+pub(super) mod resolve_service_id;
+pub(super) mod restore_service;
+pub(super) mod scrub_receipts;
+pub(super) mod set_alias;
+pub(super) mod set_batch_notes;
+pub(super) mod status_distribution_between;
+pub(super) mod store_receipts_only;
+pub(super) mod submission_success_rate;
+pub(super) mod swap_batch_status;
+pub(super) mod sync_since;
+pub(super) mod tombstone_batch;
+pub(super) mod tombstone_batches;
+pub(super) mod total_bytes_by_service;
+pub(super) mod transition_batches;
+pub(super) mod try_add_batches;
 pub(super) mod update_batch_status;
 
+use std::cell::Cell;
+use std::error::Error;
+use std::panic::{self, AssertUnwindSafe};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use diesel::connection::TransactionManager;
+use diesel::query_dsl::{LoadQuery, RunQueryDsl};
+use diesel::result::{DatabaseErrorKind, QueryResult};
+
+use crate::batch_tracking::store::{BatchTrackingStoreError, PayloadCipher, TimestampPrecision};
+use crate::error::InternalError;
+
+use super::models::TransactionModel;
+
+/// The setting the search path is saved in while an operation has the
+/// configured schema on it
+const SAVED_SEARCH_PATH_SETTING: &str = "grid_batch_tracking.saved_search_path";
+
 pub(super) struct BatchTrackingStoreOperations<'a, C> {
     conn: &'a C,
+    timestamp_precision: TimestampPrecision,
+    status_event_debounce: Duration,
+    schema: Option<&'a str>,
+    correlation_id: Option<&'a str>,
+    serialization_retries: u32,
+    payload_cipher: Option<&'a dyn PayloadCipher>,
+    /// How many of the operation's transactions are open, so that only the
+    /// outermost one sets and restores the search path
+    transaction_depth: Cell<u32>,
+    #[cfg(test)]
+    queries: Cell<usize>,
 }
 
 impl<'a, C> BatchTrackingStoreOperations<'a, C>
@@ -31,6 +135,322 @@ where
     C: diesel::Connection,
 {
     pub fn new(conn: &'a C) -> Self {
-        BatchTrackingStoreOperations { conn }
+        BatchTrackingStoreOperations {
+            conn,
+            timestamp_precision: TimestampPrecision::Seconds,
+            status_event_debounce: Duration::from_secs(0),
+            schema: None,
+            correlation_id: None,
+            serialization_retries: 0,
+            payload_cipher: None,
+            transaction_depth: Cell::new(0),
+            #[cfg(test)]
+            queries: Cell::new(0),
+        }
+    }
+
+    pub fn with_timestamp_precision(mut self, timestamp_precision: TimestampPrecision) -> Self {
+        self.timestamp_precision = timestamp_precision;
+        self
+    }
+
+    /// Sets how long after a status event is recorded an event with the same
+    /// status for the same batch is skipped
+    pub fn with_status_event_debounce(mut self, status_event_debounce: Duration) -> Self {
+        self.status_event_debounce = status_event_debounce;
+        self
+    }
+
+    /// Sets the schema the batch tracking tables are in, if it is not on the
+    /// connection's search path
+    #[cfg(feature = "postgres")]
+    pub fn with_schema(mut self, schema: Option<&'a str>) -> Self {
+        self.schema = schema;
+        self
+    }
+
+    /// Sets how many times an operation run with `retrying_transaction` is
+    /// retried after failing because postgres could not serialize it with a
+    /// concurrent transaction
+    #[cfg(feature = "postgres")]
+    pub fn with_serialization_retries(mut self, serialization_retries: u32) -> Self {
+        self.serialization_retries = serialization_retries;
+        self
+    }
+
+    /// Sets the correlation ID to include in log messages and in the status
+    /// events the operation records
+    pub fn with_correlation_id(mut self, correlation_id: Option<&'a str>) -> Self {
+        self.correlation_id = correlation_id;
+        self
+    }
+
+    /// Sets the cipher used to encrypt the transaction payloads the operation
+    /// writes and decrypt those it reads
+    pub fn with_payload_cipher(mut self, payload_cipher: Option<&'a dyn PayloadCipher>) -> Self {
+        self.payload_cipher = payload_cipher;
+        self
+    }
+
+    /// Loads the results of a query
+    ///
+    /// Operations that promise to make a bounded number of queries load
+    /// through this, so that tests can check how many queries they make.
+    fn load<Q, U>(&self, query: Q) -> QueryResult<Vec<U>>
+    where
+        Q: RunQueryDsl<C> + LoadQuery<C, U>,
+    {
+        #[cfg(test)]
+        self.queries.set(self.queries.get() + 1);
+        query.load(self.conn)
+    }
+
+    /// Returns how many queries have been loaded through `load`
+    #[cfg(test)]
+    pub fn query_count(&self) -> usize {
+        self.queries.get()
+    }
+
+    /// Returns the current time in the configured timestamp precision
+    fn now(&self) -> Result<i64, BatchTrackingStoreError> {
+        let elapsed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|err| {
+                BatchTrackingStoreError::InternalError(InternalError::from_source(Box::new(err)))
+            })?;
+
+        match self.timestamp_precision {
+            TimestampPrecision::Seconds => Ok(elapsed.as_secs() as i64),
+            TimestampPrecision::Milliseconds => Ok(elapsed.as_millis() as i64),
+        }
+    }
+
+    /// Returns the status event debounce in the configured timestamp
+    /// precision
+    fn status_event_debounce(&self) -> i64 {
+        self.in_precision(self.status_event_debounce)
+    }
+
+    /// Converts a duration to the configured timestamp precision
+    fn in_precision(&self, duration: Duration) -> i64 {
+        match self.timestamp_precision {
+            TimestampPrecision::Seconds => duration.as_secs() as i64,
+            TimestampPrecision::Milliseconds => duration.as_millis() as i64,
+        }
+    }
+
+    /// Converts a duration in the configured timestamp precision to
+    /// milliseconds
+    fn to_millis(&self, duration: i64) -> i64 {
+        match self.timestamp_precision {
+            TimestampPrecision::Seconds => duration * 1000,
+            TimestampPrecision::Milliseconds => duration,
+        }
     }
+
+    /// Runs `f` in a database transaction, prefixing any internal error with
+    /// the name of the operation
+    ///
+    /// If `f` panics, the transaction is rolled back before the panic is
+    /// resumed. Otherwise the connection would be returned to its pool with
+    /// the transaction still open, and every later operation on it would run
+    /// in a savepoint that is never committed.
+    fn transaction<T, F>(&self, operation: &str, f: F) -> Result<T, BatchTrackingStoreError>
+    where
+        F: FnOnce() -> Result<T, BatchTrackingStoreError>,
+    {
+        #[cfg(feature = "log")]
+        if let Some(correlation_id) = self.correlation_id {
+            debug!("Running {} [correlation ID {}]", operation, correlation_id);
+        }
+
+        let mut panic_payload = None;
+
+        let result = self
+            .conn
+            .transaction::<_, BatchTrackingStoreError, _>(|| {
+                let depth = self.transaction_depth.get();
+                if depth == 0 {
+                    self.set_search_path()?;
+                }
+
+                self.transaction_depth.set(depth + 1);
+                let result = panic::catch_unwind(AssertUnwindSafe(f));
+                self.transaction_depth.set(depth);
+
+                match result {
+                    Ok(Ok(value)) => {
+                        if depth == 0 {
+                            self.restore_search_path()?;
+                        }
+                        Ok(value)
+                    }
+                    Ok(Err(err)) => Err(err),
+                    Err(payload) => {
+                        panic_payload = Some(payload);
+                        Err(BatchTrackingStoreError::InternalError(
+                            InternalError::with_message("Operation panicked".to_string()),
+                        ))
+                    }
+                }
+            })
+            .map_err(|err| err.with_operation(operation));
+
+        if let Some(payload) = panic_payload {
+            panic::resume_unwind(payload);
+        }
+
+        result
+    }
+
+    /// Runs `f` in a database transaction, as `transaction` does, retrying it
+    /// in a new transaction when it fails with a serialization failure
+    ///
+    /// A serialization failure aborts the whole transaction, so the operation
+    /// is only retried when it is not nested in a transaction opened by the
+    /// caller. It is retried up to the configured number of serialization
+    /// retries; any other error is returned as is.
+    fn retrying_transaction<T, F>(
+        &self,
+        operation: &str,
+        f: F,
+    ) -> Result<T, BatchTrackingStoreError>
+    where
+        F: Fn() -> Result<T, BatchTrackingStoreError>,
+    {
+        let mut retries = 0;
+        loop {
+            let nested = self.conn.transaction_manager().get_transaction_depth() > 0;
+            match self.transaction(operation, &f) {
+                Err(err)
+                    if !nested
+                        && retries < self.serialization_retries
+                        && is_serialization_failure(&err) =>
+                {
+                    retries += 1;
+                    #[cfg(feature = "log")]
+                    debug!(
+                        "Retrying {} after serialization failure ({} of {})",
+                        operation, retries, self.serialization_retries
+                    );
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Encrypts the payloads of transactions about to be written, if a
+    /// payload cipher is configured
+    fn encrypt_payloads(
+        &self,
+        mut transaction_models: Vec<TransactionModel>,
+    ) -> Result<Vec<TransactionModel>, BatchTrackingStoreError> {
+        if let Some(payload_cipher) = self.payload_cipher {
+            for model in transaction_models.iter_mut() {
+                model.payload = payload_cipher
+                    .encrypt(&model.payload)
+                    .map_err(BatchTrackingStoreError::InternalError)?;
+                model.payload_encrypted = true;
+            }
+        }
+
+        Ok(transaction_models)
+    }
+
+    /// Decrypts the payloads of transactions that were read, leaving those
+    /// that were not stored encrypted as they are
+    fn decrypt_payloads(
+        &self,
+        mut transaction_models: Vec<TransactionModel>,
+    ) -> Result<Vec<TransactionModel>, BatchTrackingStoreError> {
+        for model in transaction_models
+            .iter_mut()
+            .filter(|model| model.payload_encrypted)
+        {
+            let payload_cipher = self.payload_cipher.ok_or_else(|| {
+                BatchTrackingStoreError::InternalError(InternalError::with_message(format!(
+                    "Transaction {} has an encrypted payload but no payload cipher is configured",
+                    model.transaction_id
+                )))
+            })?;
+            model.payload = payload_cipher
+                .decrypt(&model.payload)
+                .map_err(BatchTrackingStoreError::InternalError)?;
+            model.payload_encrypted = false;
+        }
+
+        Ok(transaction_models)
+    }
+
+    /// Puts the configured schema on the search path, saving the current
+    /// search path so that `restore_search_path` can put it back
+    ///
+    /// When the operation runs in a transaction opened by the caller, its
+    /// transaction is only a savepoint, and a `SET LOCAL` would stay in effect
+    /// for the rest of the caller's transaction once the savepoint is
+    /// released. A schema is only configured for postgres, so this does
+    /// nothing on other backends.
+    fn set_search_path(&self) -> Result<(), BatchTrackingStoreError> {
+        if let Some(schema) = self.schema {
+            self.conn.execute(&format!(
+                "SELECT set_config('{}', current_setting('search_path'), true)",
+                SAVED_SEARCH_PATH_SETTING
+            ))?;
+            self.conn.execute(&format!(
+                "SET LOCAL search_path TO {}",
+                quote_identifier(schema)
+            ))?;
+        }
+
+        Ok(())
+    }
+
+    /// Puts back the search path saved by `set_search_path`
+    ///
+    /// This is only needed when the operation succeeds, as rolling back its
+    /// transaction or savepoint also undoes `set_search_path`.
+    fn restore_search_path(&self) -> Result<(), BatchTrackingStoreError> {
+        if self.schema.is_some() {
+            self.conn.execute(&format!(
+                "SELECT set_config('search_path', current_setting('{}'), true)",
+                SAVED_SEARCH_PATH_SETTING
+            ))?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the name of a batch tracking table, qualified with the
+    /// configured schema if there is one
+    #[cfg(feature = "postgres")]
+    fn qualified_table_name(&self, table: &str) -> String {
+        match self.schema {
+            Some(schema) => format!("{}.{}", quote_identifier(schema), table),
+            None => table.to_string(),
+        }
+    }
+}
+
+/// Returns whether an error was caused by the database failing to serialize a
+/// transaction with a concurrent one, in which case the transaction can be
+/// retried
+fn is_serialization_failure(err: &BatchTrackingStoreError) -> bool {
+    let mut source: Option<&(dyn Error + 'static)> = Some(err);
+    while let Some(err) = source {
+        if let Some(diesel::result::Error::DatabaseError(
+            DatabaseErrorKind::SerializationFailure,
+            _,
+        )) = err.downcast_ref::<diesel::result::Error>()
+        {
+            return true;
+        }
+        source = err.source();
+    }
+
+    false
+}
+
+/// Quotes a postgres identifier so it can be used in a query
+fn quote_identifier(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
 }
@@ -0,0 +1,81 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::{
+    diesel::schema::batch_statuses, BatchStatusName, BatchTrackingStoreError,
+};
+use diesel::{dsl::update, prelude::*, sql_types::Text};
+
+sql_function!(fn lower(x: Text) -> Text);
+
+const STATUSES: [BatchStatusName; 7] = [
+    BatchStatusName::Unknown,
+    BatchStatusName::Pending,
+    BatchStatusName::Delayed,
+    BatchStatusName::Invalid,
+    BatchStatusName::Valid,
+    BatchStatusName::Committed,
+    BatchStatusName::DeadLettered,
+];
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreNormalizeStatusValuesOperation
+{
+    fn normalize_status_values(&self) -> Result<usize, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreNormalizeStatusValuesOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn normalize_status_values(&self) -> Result<usize, BatchTrackingStoreError> {
+        self.retrying_transaction("normalize_status_values", || {
+            let mut normalized = 0;
+
+            for status in STATUSES.iter() {
+                let canonical = status.to_string();
+                normalized += update(batch_statuses::table)
+                    .filter(lower(batch_statuses::dlt_status).eq(canonical.to_lowercase()))
+                    .filter(batch_statuses::dlt_status.ne(&canonical))
+                    .set(batch_statuses::dlt_status.eq(&canonical))
+                    .execute(self.conn)?;
+            }
+
+            Ok(normalized)
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreNormalizeStatusValuesOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn normalize_status_values(&self) -> Result<usize, BatchTrackingStoreError> {
+        self.transaction("normalize_status_values", || {
+            let mut normalized = 0;
+
+            for status in STATUSES.iter() {
+                let canonical = status.to_string();
+                normalized += update(batch_statuses::table)
+                    .filter(lower(batch_statuses::dlt_status).eq(canonical.to_lowercase()))
+                    .filter(batch_statuses::dlt_status.ne(&canonical))
+                    .set(batch_statuses::dlt_status.eq(&canonical))
+                    .execute(self.conn)?;
+            }
+
+            Ok(normalized)
+        })
+    }
+}
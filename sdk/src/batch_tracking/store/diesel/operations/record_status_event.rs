@@ -0,0 +1,158 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::{
+    diesel::{
+        models::{NewBatchStatusEventModel, NewOutboxModel},
+        schema::{batch_status_events, outbox},
+    },
+    BatchTrackingStoreError,
+};
+use diesel::{dsl::insert_into, prelude::*};
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreRecordStatusEventOperation {
+    /// Records that a batch's status was set, tagged with the operation's
+    /// correlation ID, and queues the event in the outbox for relaying
+    ///
+    /// This is run as part of the operation that sets the status, so it
+    /// does not start its own transaction and the outbox row is committed
+    /// or rolled back along with the status change. If the batch's last event has the
+    /// same status and was recorded within the status event debounce, no
+    /// event is recorded.
+    fn record_status_event(
+        &self,
+        batch_id: &str,
+        service_id: &str,
+        dlt_status: &str,
+        created_at: i64,
+    ) -> Result<(), BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreRecordStatusEventOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn record_status_event(
+        &self,
+        batch_id: &str,
+        service_id: &str,
+        dlt_status: &str,
+        created_at: i64,
+    ) -> Result<(), BatchTrackingStoreError> {
+        if self.status_event_debounce() > 0 {
+            let last_event: Option<(String, i64)> = batch_status_events::table
+                .filter(batch_status_events::service_id.eq(service_id))
+                .filter(batch_status_events::batch_id.eq(batch_id))
+                .order(batch_status_events::id.desc())
+                .select((
+                    batch_status_events::dlt_status,
+                    batch_status_events::created_at,
+                ))
+                .first(self.conn)
+                .optional()?;
+
+            // A repeat of the last event within the debounce window is
+            // collapsed into it
+            if let Some((last_status, last_created_at)) = last_event {
+                if last_status == dlt_status
+                    && created_at - last_created_at < self.status_event_debounce()
+                {
+                    return Ok(());
+                }
+            }
+        }
+
+        insert_into(batch_status_events::table)
+            .values(NewBatchStatusEventModel {
+                service_id: service_id.to_string(),
+                batch_id: batch_id.to_string(),
+                dlt_status: dlt_status.to_string(),
+                correlation_id: self.correlation_id.map(String::from),
+                created_at,
+            })
+            .execute(self.conn)?;
+
+        insert_into(outbox::table)
+            .values(NewOutboxModel {
+                service_id: service_id.to_string(),
+                batch_id: batch_id.to_string(),
+                dlt_status: dlt_status.to_string(),
+                correlation_id: self.correlation_id.map(String::from),
+                created_at,
+            })
+            .execute(self.conn)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreRecordStatusEventOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn record_status_event(
+        &self,
+        batch_id: &str,
+        service_id: &str,
+        dlt_status: &str,
+        created_at: i64,
+    ) -> Result<(), BatchTrackingStoreError> {
+        if self.status_event_debounce() > 0 {
+            let last_event: Option<(String, i64)> = batch_status_events::table
+                .filter(batch_status_events::service_id.eq(service_id))
+                .filter(batch_status_events::batch_id.eq(batch_id))
+                .order(batch_status_events::id.desc())
+                .select((
+                    batch_status_events::dlt_status,
+                    batch_status_events::created_at,
+                ))
+                .first(self.conn)
+                .optional()?;
+
+            // A repeat of the last event within the debounce window is
+            // collapsed into it
+            if let Some((last_status, last_created_at)) = last_event {
+                if last_status == dlt_status
+                    && created_at - last_created_at < self.status_event_debounce()
+                {
+                    return Ok(());
+                }
+            }
+        }
+
+        insert_into(batch_status_events::table)
+            .values(NewBatchStatusEventModel {
+                service_id: service_id.to_string(),
+                batch_id: batch_id.to_string(),
+                dlt_status: dlt_status.to_string(),
+                correlation_id: self.correlation_id.map(String::from),
+                created_at,
+            })
+            .execute(self.conn)?;
+
+        insert_into(outbox::table)
+            .values(NewOutboxModel {
+                service_id: service_id.to_string(),
+                batch_id: batch_id.to_string(),
+                dlt_status: dlt_status.to_string(),
+                correlation_id: self.correlation_id.map(String::from),
+                created_at,
+            })
+            .execute(self.conn)?;
+
+        Ok(())
+    }
+}
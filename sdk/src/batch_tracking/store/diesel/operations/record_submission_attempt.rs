@@ -0,0 +1,141 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::resolve_data_change_id::BatchTrackingStoreResolveDataChangeIdOperation;
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::diesel::{models::is_data_change_id, schema::submissions};
+
+use crate::batch_tracking::store::BatchTrackingStoreError;
+use diesel::{prelude::*, update};
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreRecordSubmissionAttemptOperation
+{
+    fn record_submission_attempt(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<i64, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreRecordSubmissionAttemptOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn record_submission_attempt(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<i64, BatchTrackingStoreError> {
+        self.transaction("record_submission_attempt", || {
+            let mut batch_id = id.to_string();
+            if is_data_change_id(id)? {
+                match self.resolve_data_change_id(id, service_id)? {
+                    Some(found) => batch_id = found,
+                    None => {
+                        return Err(BatchTrackingStoreError::NotFoundError(format!(
+                            "Could not find batch with data change ID {}",
+                            id
+                        )))
+                    }
+                }
+            }
+
+            let now = self.now()?;
+
+            // The counter is incremented and read in a single statement, so
+            // concurrent attempts are counted once each
+            update(submissions::table)
+                .filter(
+                    submissions::batch_id
+                        .eq(&batch_id)
+                        .and(submissions::service_id.eq(&service_id)),
+                )
+                .set((
+                    submissions::attempts.eq(submissions::attempts + 1),
+                    submissions::updated_at.eq(now),
+                    submissions::last_checked.eq(now),
+                ))
+                .returning(submissions::attempts)
+                .get_result::<i64>(self.conn)
+                .optional()?
+                .ok_or_else(|| {
+                    BatchTrackingStoreError::NotFoundError(format!(
+                        "Could not find submission for batch {}",
+                        id
+                    ))
+                })
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreRecordSubmissionAttemptOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn record_submission_attempt(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<i64, BatchTrackingStoreError> {
+        self.transaction("record_submission_attempt", || {
+            let mut batch_id = id.to_string();
+            if is_data_change_id(id)? {
+                match self.resolve_data_change_id(id, service_id)? {
+                    Some(found) => batch_id = found,
+                    None => {
+                        return Err(BatchTrackingStoreError::NotFoundError(format!(
+                            "Could not find batch with data change ID {}",
+                            id
+                        )))
+                    }
+                }
+            }
+
+            let now = self.now()?;
+
+            // The update holds the database's write lock until the
+            // transaction ends, so concurrent attempts are counted once each
+            let updated = update(submissions::table)
+                .filter(
+                    submissions::batch_id
+                        .eq(&batch_id)
+                        .and(submissions::service_id.eq(&service_id)),
+                )
+                .set((
+                    submissions::attempts.eq(submissions::attempts + 1),
+                    submissions::updated_at.eq(now),
+                    submissions::last_checked.eq(now),
+                ))
+                .execute(self.conn)?;
+
+            if updated == 0 {
+                return Err(BatchTrackingStoreError::NotFoundError(format!(
+                    "Could not find submission for batch {}",
+                    id
+                )));
+            }
+
+            submissions::table
+                .select(submissions::attempts)
+                .filter(
+                    submissions::batch_id
+                        .eq(&batch_id)
+                        .and(submissions::service_id.eq(&service_id)),
+                )
+                .first::<i64>(self.conn)
+                .map_err(BatchTrackingStoreError::from)
+        })
+    }
+}
@@ -0,0 +1,280 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{HashMap, HashSet};
+
+use super::{
+    resolve_data_change_id::check_data_change_ids_unambiguous, BatchTrackingStoreOperations,
+};
+
+use crate::batch_tracking::store::{
+    diesel::{models::is_data_change_id, schema::batches},
+    BatchTrackingStoreError,
+};
+use crate::error::{
+    ConstraintViolationError, ConstraintViolationType, InternalError, InvalidArgumentError,
+};
+
+use diesel::{dsl::update, prelude::*};
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreRemapDataChangeIdsOperation {
+    fn remap_data_change_ids(
+        &self,
+        mapping: &HashMap<String, String>,
+        service_id: &str,
+    ) -> Result<usize, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreRemapDataChangeIdsOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn remap_data_change_ids(
+        &self,
+        mapping: &HashMap<String, String>,
+        service_id: &str,
+    ) -> Result<usize, BatchTrackingStoreError> {
+        self.retrying_transaction("remap_data_change_ids", || {
+            for (old_dcid, new_dcid) in mapping {
+                for dcid in &[old_dcid, new_dcid] {
+                    if !is_data_change_id(dcid)? {
+                        return Err(BatchTrackingStoreError::InvalidArgumentError(
+                            InvalidArgumentError::new(
+                                "mapping".to_string(),
+                                format!("{} is not a valid data change ID", dcid),
+                            ),
+                        ));
+                    }
+                }
+            }
+
+            let mut targets = HashSet::new();
+            if let Some(duplicate) = mapping.values().find(|dcid| !targets.insert(*dcid)) {
+                return Err(unique_violation(format!(
+                    "Data change ID {} is mapped to more than once",
+                    duplicate
+                )));
+            }
+
+            let remapped: Vec<(String, Option<String>)> = batches::table
+                .select((batches::batch_id, batches::data_change_id))
+                .filter(
+                    batches::service_id
+                        .eq(service_id)
+                        .and(batches::data_change_id.eq_any(mapping.keys())),
+                )
+                .load(self.conn)?;
+
+            if remapped.is_empty() {
+                return Ok(0);
+            }
+
+            check_data_change_ids_unambiguous(
+                remapped.iter().filter_map(|(_, dcid)| dcid.as_deref()),
+            )?;
+
+            let remapped_ids: Vec<&str> = remapped
+                .iter()
+                .map(|(batch_id, _)| batch_id.as_str())
+                .collect();
+
+            // Data change IDs are unique across services, so a new ID can
+            // only be taken from a batch that is itself being remapped
+            let taken: Option<String> = batches::table
+                .select(batches::data_change_id)
+                .filter(
+                    batches::data_change_id.eq_any(mapping.values()).and(
+                        batches::service_id
+                            .ne(service_id)
+                            .or(batches::batch_id.ne_all(&remapped_ids)),
+                    ),
+                )
+                .first::<Option<String>>(self.conn)
+                .optional()?
+                .flatten();
+
+            if let Some(taken) = taken {
+                return Err(unique_violation(format!(
+                    "Data change ID {} is already used by another batch",
+                    taken
+                )));
+            }
+
+            let colliding_batch_id: Option<String> = batches::table
+                .select(batches::batch_id)
+                .filter(
+                    batches::service_id
+                        .eq(service_id)
+                        .and(batches::batch_id.eq_any(mapping.values())),
+                )
+                .first(self.conn)
+                .optional()?;
+
+            if let Some(batch_id) = colliding_batch_id {
+                return Err(BatchTrackingStoreError::IdCollision(batch_id));
+            }
+
+            // The old IDs are cleared first so that IDs can be swapped
+            // between batches without tripping the unique constraint
+            update(batches::table)
+                .filter(
+                    batches::service_id
+                        .eq(service_id)
+                        .and(batches::batch_id.eq_any(&remapped_ids)),
+                )
+                .set(batches::data_change_id.eq(None::<String>))
+                .execute(self.conn)?;
+
+            for (batch_id, old_dcid) in &remapped {
+                let new_dcid = old_dcid.as_ref().and_then(|dcid| mapping.get(dcid));
+                update(batches::table)
+                    .filter(
+                        batches::service_id
+                            .eq(service_id)
+                            .and(batches::batch_id.eq(batch_id)),
+                    )
+                    .set(batches::data_change_id.eq(new_dcid))
+                    .execute(self.conn)?;
+            }
+
+            Ok(remapped.len())
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreRemapDataChangeIdsOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn remap_data_change_ids(
+        &self,
+        mapping: &HashMap<String, String>,
+        service_id: &str,
+    ) -> Result<usize, BatchTrackingStoreError> {
+        self.transaction("remap_data_change_ids", || {
+            for (old_dcid, new_dcid) in mapping {
+                for dcid in &[old_dcid, new_dcid] {
+                    if !is_data_change_id(dcid)? {
+                        return Err(BatchTrackingStoreError::InvalidArgumentError(
+                            InvalidArgumentError::new(
+                                "mapping".to_string(),
+                                format!("{} is not a valid data change ID", dcid),
+                            ),
+                        ));
+                    }
+                }
+            }
+
+            let mut targets = HashSet::new();
+            if let Some(duplicate) = mapping.values().find(|dcid| !targets.insert(*dcid)) {
+                return Err(unique_violation(format!(
+                    "Data change ID {} is mapped to more than once",
+                    duplicate
+                )));
+            }
+
+            let remapped: Vec<(String, Option<String>)> = batches::table
+                .select((batches::batch_id, batches::data_change_id))
+                .filter(
+                    batches::service_id
+                        .eq(service_id)
+                        .and(batches::data_change_id.eq_any(mapping.keys())),
+                )
+                .load(self.conn)?;
+
+            if remapped.is_empty() {
+                return Ok(0);
+            }
+
+            check_data_change_ids_unambiguous(
+                remapped.iter().filter_map(|(_, dcid)| dcid.as_deref()),
+            )?;
+
+            let remapped_ids: Vec<&str> = remapped
+                .iter()
+                .map(|(batch_id, _)| batch_id.as_str())
+                .collect();
+
+            // Data change IDs are unique across services, so a new ID can
+            // only be taken from a batch that is itself being remapped
+            let taken: Option<String> = batches::table
+                .select(batches::data_change_id)
+                .filter(
+                    batches::data_change_id.eq_any(mapping.values()).and(
+                        batches::service_id
+                            .ne(service_id)
+                            .or(batches::batch_id.ne_all(&remapped_ids)),
+                    ),
+                )
+                .first::<Option<String>>(self.conn)
+                .optional()?
+                .flatten();
+
+            if let Some(taken) = taken {
+                return Err(unique_violation(format!(
+                    "Data change ID {} is already used by another batch",
+                    taken
+                )));
+            }
+
+            let colliding_batch_id: Option<String> = batches::table
+                .select(batches::batch_id)
+                .filter(
+                    batches::service_id
+                        .eq(service_id)
+                        .and(batches::batch_id.eq_any(mapping.values())),
+                )
+                .first(self.conn)
+                .optional()?;
+
+            if let Some(batch_id) = colliding_batch_id {
+                return Err(BatchTrackingStoreError::IdCollision(batch_id));
+            }
+
+            // The old IDs are cleared first so that IDs can be swapped
+            // between batches without tripping the unique constraint
+            update(batches::table)
+                .filter(
+                    batches::service_id
+                        .eq(service_id)
+                        .and(batches::batch_id.eq_any(&remapped_ids)),
+                )
+                .set(batches::data_change_id.eq(None::<String>))
+                .execute(self.conn)?;
+
+            for (batch_id, old_dcid) in &remapped {
+                let new_dcid = old_dcid.as_ref().and_then(|dcid| mapping.get(dcid));
+                update(batches::table)
+                    .filter(
+                        batches::service_id
+                            .eq(service_id)
+                            .and(batches::batch_id.eq(batch_id)),
+                    )
+                    .set(batches::data_change_id.eq(new_dcid))
+                    .execute(self.conn)?;
+            }
+
+            Ok(remapped.len())
+        })
+    }
+}
+
+fn unique_violation(message: String) -> BatchTrackingStoreError {
+    BatchTrackingStoreError::ConstraintViolationError(
+        ConstraintViolationError::from_source_with_violation_type(
+            ConstraintViolationType::Unique,
+            Box::new(InternalError::with_message(message)),
+        ),
+    )
+}
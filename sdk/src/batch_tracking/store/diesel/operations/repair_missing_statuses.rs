@@ -0,0 +1,116 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{
+    record_status_event::BatchTrackingStoreRecordStatusEventOperation, BatchTrackingStoreOperations,
+};
+
+use crate::batch_tracking::store::{
+    diesel::{
+        models::NewBatchStatusModel,
+        schema::{batch_statuses, batches},
+    },
+    BatchStatusName, BatchTrackingStoreError,
+};
+
+use diesel::{dsl::insert_into, prelude::*};
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreRepairMissingStatusesOperation
+{
+    fn repair_missing_statuses(&self) -> Result<usize, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreRepairMissingStatusesOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn repair_missing_statuses(&self) -> Result<usize, BatchTrackingStoreError> {
+        self.retrying_transaction("repair_missing_statuses", || {
+            let now = self.now()?;
+
+            let missing: Vec<(String, String)> = batches::table
+                .left_join(
+                    batch_statuses::table.on(batches::batch_id
+                        .eq(batch_statuses::batch_id)
+                        .and(batches::service_id.eq(batch_statuses::service_id))),
+                )
+                .filter(batches::submitted.eq(true))
+                .filter(batch_statuses::batch_id.is_null())
+                .select((batches::service_id, batches::batch_id))
+                .load(self.conn)?;
+
+            let status = BatchStatusName::Unknown.to_string();
+
+            for (service_id, batch_id) in &missing {
+                insert_into(batch_statuses::table)
+                    .values((
+                        NewBatchStatusModel {
+                            batch_id: batch_id.to_string(),
+                            service_id: service_id.to_string(),
+                            dlt_status: status.clone(),
+                        },
+                        batch_statuses::created_at.eq(now),
+                        batch_statuses::updated_at.eq(now),
+                    ))
+                    .execute(self.conn)?;
+
+                self.record_status_event(batch_id, service_id, &status, now)?;
+            }
+
+            Ok(missing.len())
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreRepairMissingStatusesOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn repair_missing_statuses(&self) -> Result<usize, BatchTrackingStoreError> {
+        self.transaction("repair_missing_statuses", || {
+            let now = self.now()?;
+
+            let missing: Vec<(String, String)> = batches::table
+                .left_join(
+                    batch_statuses::table.on(batches::batch_id
+                        .eq(batch_statuses::batch_id)
+                        .and(batches::service_id.eq(batch_statuses::service_id))),
+                )
+                .filter(batches::submitted.eq(true))
+                .filter(batch_statuses::batch_id.is_null())
+                .select((batches::service_id, batches::batch_id))
+                .load(self.conn)?;
+
+            let status = BatchStatusName::Unknown.to_string();
+
+            for (service_id, batch_id) in &missing {
+                insert_into(batch_statuses::table)
+                    .values((
+                        NewBatchStatusModel {
+                            batch_id: batch_id.to_string(),
+                            service_id: service_id.to_string(),
+                            dlt_status: status.clone(),
+                        },
+                        batch_statuses::created_at.eq(now),
+                        batch_statuses::updated_at.eq(now),
+                    ))
+                    .execute(self.conn)?;
+
+                self.record_status_event(batch_id, service_id, &status, now)?;
+            }
+
+            Ok(missing.len())
+        })
+    }
+}
@@ -0,0 +1,117 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::{diesel::schema::batches, BatchTrackingStoreError};
+use diesel::prelude::*;
+use std::collections::BTreeMap;
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreResolveDataChangeIdOperation {
+    /// Returns the ID of the service's batch that has the given data change
+    /// ID, or `None` if no batch has it
+    ///
+    /// If more than one batch has the data change ID, an
+    /// `AmbiguousDataChangeId` error is returned rather than picking one.
+    fn resolve_data_change_id(
+        &self,
+        dcid: &str,
+        service_id: &str,
+    ) -> Result<Option<String>, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreResolveDataChangeIdOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn resolve_data_change_id(
+        &self,
+        dcid: &str,
+        service_id: &str,
+    ) -> Result<Option<String>, BatchTrackingStoreError> {
+        self.transaction("resolve_data_change_id", || {
+            let batch_ids: Vec<String> = batches::table
+                .select(batches::batch_id)
+                .filter(
+                    batches::data_change_id
+                        .eq(dcid)
+                        .and(batches::service_id.eq(service_id)),
+                )
+                .load(self.conn)?;
+
+            unique_batch_id(dcid, batch_ids)
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreResolveDataChangeIdOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn resolve_data_change_id(
+        &self,
+        dcid: &str,
+        service_id: &str,
+    ) -> Result<Option<String>, BatchTrackingStoreError> {
+        self.transaction("resolve_data_change_id", || {
+            let batch_ids: Vec<String> = batches::table
+                .select(batches::batch_id)
+                .filter(
+                    batches::data_change_id
+                        .eq(dcid)
+                        .and(batches::service_id.eq(service_id)),
+                )
+                .load(self.conn)?;
+
+            unique_batch_id(dcid, batch_ids)
+        })
+    }
+}
+
+/// Returns the only batch ID a data change ID matched, if it matched any
+fn unique_batch_id(
+    dcid: &str,
+    mut batch_ids: Vec<String>,
+) -> Result<Option<String>, BatchTrackingStoreError> {
+    if batch_ids.len() > 1 {
+        return Err(BatchTrackingStoreError::AmbiguousDataChangeId {
+            dcid: dcid.to_string(),
+            count: batch_ids.len() as i64,
+        });
+    }
+
+    Ok(batch_ids.pop())
+}
+
+/// Returns an `AmbiguousDataChangeId` error if any data change ID appears more
+/// than once in the data change IDs of a set of batches
+///
+/// This is used by the operations that look up several batches by their data
+/// change IDs at once, which can't use `resolve_data_change_id`.
+pub(in crate::batch_tracking::store::diesel) fn check_data_change_ids_unambiguous<'b>(
+    dcids: impl Iterator<Item = &'b str>,
+) -> Result<(), BatchTrackingStoreError> {
+    let mut counts: BTreeMap<&str, i64> = BTreeMap::new();
+    for dcid in dcids {
+        *counts.entry(dcid).or_insert(0) += 1;
+    }
+
+    match counts.into_iter().find(|(_, count)| *count > 1) {
+        Some((dcid, count)) => Err(BatchTrackingStoreError::AmbiguousDataChangeId {
+            dcid: dcid.to_string(),
+            count,
+        }),
+        None => Ok(()),
+    }
+}
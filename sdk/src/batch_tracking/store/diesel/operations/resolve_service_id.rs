@@ -0,0 +1,64 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::{diesel::schema::batches, BatchTrackingStoreError};
+use diesel::{prelude::*, sql_types::Text};
+
+sql_function!(fn lower(x: Text) -> Text);
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreResolveServiceIdOperation {
+    /// Returns the stored service ID that matches the given service ID when
+    /// compared case-insensitively, or the given service ID if none match
+    ///
+    /// If several stored service IDs match, the first in sort order is used.
+    fn resolve_service_id(&self, service_id: &str) -> Result<String, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreResolveServiceIdOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn resolve_service_id(&self, service_id: &str) -> Result<String, BatchTrackingStoreError> {
+        self.transaction("resolve_service_id", || {
+            let stored: Option<String> = batches::table
+                .select(batches::service_id)
+                .filter(lower(batches::service_id).eq(lower(service_id)))
+                .order(batches::service_id.asc())
+                .first(self.conn)
+                .optional()?;
+
+            Ok(stored.unwrap_or_else(|| service_id.to_string()))
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreResolveServiceIdOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn resolve_service_id(&self, service_id: &str) -> Result<String, BatchTrackingStoreError> {
+        self.transaction("resolve_service_id", || {
+            let stored: Option<String> = batches::table
+                .select(batches::service_id)
+                .filter(lower(batches::service_id).eq(lower(service_id)))
+                .order(batches::service_id.asc())
+                .first(self.conn)
+                .optional()?;
+
+            Ok(stored.unwrap_or_else(|| service_id.to_string()))
+        })
+    }
+}
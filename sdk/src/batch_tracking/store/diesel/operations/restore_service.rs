@@ -0,0 +1,261 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashSet;
+use std::io::{BufRead, BufReader, Read};
+
+use super::{
+    backup_service::{BackupRecord, BACKUP_FORMAT_VERSION},
+    BatchTrackingStoreOperations,
+};
+
+use crate::batch_tracking::store::{
+    diesel::{
+        check_service_allowed,
+        models::{NewBatchStatusEventModel, NewOutboxModel},
+        schema::{
+            batch_status_events, batch_statuses, batch_tombstones, batches, outbox, submissions,
+            transaction_addresses, transaction_dependencies, transaction_receipts, transactions,
+        },
+    },
+    BatchTrackingStoreError,
+};
+use crate::error::{InternalError, InvalidArgumentError};
+
+use diesel::{dsl::insert_into, prelude::*};
+
+/// Reads the next record from a backup, or `None` at the end of the backup
+fn next_record(
+    lines: &mut dyn Iterator<Item = std::io::Result<String>>,
+) -> Result<Option<BackupRecord>, BatchTrackingStoreError> {
+    lines
+        .next()
+        .transpose()
+        .map_err(|err| {
+            BatchTrackingStoreError::InternalError(InternalError::from_source(Box::new(err)))
+        })?
+        .map(|line| {
+            serde_json::from_str(&line).map_err(|err| {
+                BatchTrackingStoreError::InvalidArgumentError(InvalidArgumentError::new(
+                    "reader".to_string(),
+                    format!("backup contains an invalid record: {}", err),
+                ))
+            })
+        })
+        .transpose()
+}
+
+/// Returns an error for a backup that can't be restored
+fn invalid_backup(reason: String) -> BatchTrackingStoreError {
+    BatchTrackingStoreError::InvalidArgumentError(InvalidArgumentError::new(
+        "reader".to_string(),
+        reason,
+    ))
+}
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreRestoreServiceOperation {
+    fn restore_service(
+        &self,
+        reader: &mut dyn Read,
+        allowed_service_ids: Option<&HashSet<String>>,
+    ) -> Result<(), BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreRestoreServiceOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn restore_service(
+        &self,
+        reader: &mut dyn Read,
+        allowed_service_ids: Option<&HashSet<String>>,
+    ) -> Result<(), BatchTrackingStoreError> {
+        self.transaction("restore_service", || {
+            let mut lines = BufReader::new(reader).lines();
+
+            let service_id = match next_record(&mut lines)? {
+                Some(BackupRecord::Header {
+                    version,
+                    service_id,
+                }) => {
+                    if version != BACKUP_FORMAT_VERSION {
+                        return Err(invalid_backup(format!(
+                            "backup format version {} is not supported",
+                            version
+                        )));
+                    }
+                    service_id
+                }
+                _ => {
+                    return Err(invalid_backup(
+                        "backup does not start with a header".to_string(),
+                    ))
+                }
+            };
+            check_service_allowed(allowed_service_ids, &service_id)?;
+
+            // Event IDs are assigned by the store, so events are inserted
+            // without them, in the order they were recorded
+            while let Some(record) = next_record(&mut lines)? {
+                if record.service_id() != service_id {
+                    return Err(invalid_backup(format!(
+                        "backup of service {} contains a row for service {}",
+                        service_id,
+                        record.service_id()
+                    )));
+                }
+
+                match record {
+                    BackupRecord::Header { .. } => {
+                        return Err(invalid_backup(
+                            "backup contains more than one header".to_string(),
+                        ))
+                    }
+                    BackupRecord::Batch(row) => insert_into(batches::table)
+                        .values(&row)
+                        .execute(self.conn)?,
+                    BackupRecord::Transaction(row) => insert_into(transactions::table)
+                        .values(&row)
+                        .execute(self.conn)?,
+                    BackupRecord::TransactionAddress(row) => {
+                        insert_into(transaction_addresses::table)
+                            .values(&row)
+                            .execute(self.conn)?
+                    }
+                    BackupRecord::TransactionDependency(row) => {
+                        insert_into(transaction_dependencies::table)
+                            .values(&row)
+                            .execute(self.conn)?
+                    }
+                    BackupRecord::TransactionReceipt(row) => {
+                        insert_into(transaction_receipts::table)
+                            .values(&row)
+                            .execute(self.conn)?
+                    }
+                    BackupRecord::BatchStatus(row) => insert_into(batch_statuses::table)
+                        .values(&row)
+                        .execute(self.conn)?,
+                    BackupRecord::Submission(row) => insert_into(submissions::table)
+                        .values(&row)
+                        .execute(self.conn)?,
+                    BackupRecord::BatchStatusEvent(row) => insert_into(batch_status_events::table)
+                        .values(NewBatchStatusEventModel::from(row))
+                        .execute(self.conn)?,
+                    BackupRecord::BatchTombstone(row) => insert_into(batch_tombstones::table)
+                        .values(&row)
+                        .execute(self.conn)?,
+                    BackupRecord::Outbox(row) => insert_into(outbox::table)
+                        .values(NewOutboxModel::from(row))
+                        .execute(self.conn)?,
+                };
+            }
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreRestoreServiceOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn restore_service(
+        &self,
+        reader: &mut dyn Read,
+        allowed_service_ids: Option<&HashSet<String>>,
+    ) -> Result<(), BatchTrackingStoreError> {
+        self.transaction("restore_service", || {
+            let mut lines = BufReader::new(reader).lines();
+
+            let service_id = match next_record(&mut lines)? {
+                Some(BackupRecord::Header {
+                    version,
+                    service_id,
+                }) => {
+                    if version != BACKUP_FORMAT_VERSION {
+                        return Err(invalid_backup(format!(
+                            "backup format version {} is not supported",
+                            version
+                        )));
+                    }
+                    service_id
+                }
+                _ => {
+                    return Err(invalid_backup(
+                        "backup does not start with a header".to_string(),
+                    ))
+                }
+            };
+            check_service_allowed(allowed_service_ids, &service_id)?;
+
+            // Event IDs are assigned by the store, so events are inserted
+            // without them, in the order they were recorded
+            while let Some(record) = next_record(&mut lines)? {
+                if record.service_id() != service_id {
+                    return Err(invalid_backup(format!(
+                        "backup of service {} contains a row for service {}",
+                        service_id,
+                        record.service_id()
+                    )));
+                }
+
+                match record {
+                    BackupRecord::Header { .. } => {
+                        return Err(invalid_backup(
+                            "backup contains more than one header".to_string(),
+                        ))
+                    }
+                    BackupRecord::Batch(row) => insert_into(batches::table)
+                        .values(&row)
+                        .execute(self.conn)?,
+                    BackupRecord::Transaction(row) => insert_into(transactions::table)
+                        .values(&row)
+                        .execute(self.conn)?,
+                    BackupRecord::TransactionAddress(row) => {
+                        insert_into(transaction_addresses::table)
+                            .values(&row)
+                            .execute(self.conn)?
+                    }
+                    BackupRecord::TransactionDependency(row) => {
+                        insert_into(transaction_dependencies::table)
+                            .values(&row)
+                            .execute(self.conn)?
+                    }
+                    BackupRecord::TransactionReceipt(row) => {
+                        insert_into(transaction_receipts::table)
+                            .values(&row)
+                            .execute(self.conn)?
+                    }
+                    BackupRecord::BatchStatus(row) => insert_into(batch_statuses::table)
+                        .values(&row)
+                        .execute(self.conn)?,
+                    BackupRecord::Submission(row) => insert_into(submissions::table)
+                        .values(&row)
+                        .execute(self.conn)?,
+                    BackupRecord::BatchStatusEvent(row) => insert_into(batch_status_events::table)
+                        .values(NewBatchStatusEventModel::from(row))
+                        .execute(self.conn)?,
+                    BackupRecord::BatchTombstone(row) => insert_into(batch_tombstones::table)
+                        .values(&row)
+                        .execute(self.conn)?,
+                    BackupRecord::Outbox(row) => insert_into(outbox::table)
+                        .values(NewOutboxModel::from(row))
+                        .execute(self.conn)?,
+                };
+            }
+
+            Ok(())
+        })
+    }
+}
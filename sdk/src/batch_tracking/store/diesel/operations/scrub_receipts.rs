@@ -0,0 +1,158 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::resolve_data_change_id::BatchTrackingStoreResolveDataChangeIdOperation;
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::{
+    diesel::{
+        models::is_data_change_id,
+        schema::{batches, transaction_receipts, transactions},
+    },
+    BatchTrackingStoreError,
+};
+
+use diesel::{
+    dsl::{exists, update},
+    prelude::*,
+    select,
+};
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreScrubReceiptsOperation {
+    fn scrub_receipts(&self, id: &str, service_id: &str) -> Result<usize, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreScrubReceiptsOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn scrub_receipts(&self, id: &str, service_id: &str) -> Result<usize, BatchTrackingStoreError> {
+        self.transaction("scrub_receipts", || {
+            let mut batch_id = id.to_string();
+            if is_data_change_id(id)? {
+                batch_id = self
+                    .resolve_data_change_id(id, service_id)?
+                    .ok_or_else(|| {
+                        BatchTrackingStoreError::NotFoundError(format!(
+                            "Could not find batch with data change ID {}",
+                            id
+                        ))
+                    })?;
+            }
+
+            let batch_exists: bool = select(exists(
+                batches::table.filter(
+                    batches::batch_id
+                        .eq(&batch_id)
+                        .and(batches::service_id.eq(&service_id)),
+                ),
+            ))
+            .get_result(self.conn)?;
+
+            if !batch_exists {
+                return Err(BatchTrackingStoreError::NotFoundError(format!(
+                    "Could not find batch with ID {}",
+                    batch_id
+                )));
+            }
+
+            let txn_ids: Vec<String> = transactions::table
+                .select(transactions::transaction_id)
+                .filter(
+                    transactions::batch_id
+                        .eq(&batch_id)
+                        .and(transactions::service_id.eq(&service_id)),
+                )
+                .load(self.conn)?;
+
+            // The receipt rows are kept, as the batch's status is rebuilt
+            // from whether each transaction was valid, but their payloads are
+            // cleared
+            update(transaction_receipts::table)
+                .filter(
+                    transaction_receipts::transaction_id
+                        .eq_any(&txn_ids)
+                        .and(transaction_receipts::service_id.eq(&service_id)),
+                )
+                .set((
+                    transaction_receipts::serialized_receipt.eq(None::<Vec<u8>>),
+                    transaction_receipts::error_data.eq(None::<Vec<u8>>),
+                ))
+                .execute(self.conn)
+                .map_err(BatchTrackingStoreError::from)
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreScrubReceiptsOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn scrub_receipts(&self, id: &str, service_id: &str) -> Result<usize, BatchTrackingStoreError> {
+        self.transaction("scrub_receipts", || {
+            let mut batch_id = id.to_string();
+            if is_data_change_id(id)? {
+                batch_id = self
+                    .resolve_data_change_id(id, service_id)?
+                    .ok_or_else(|| {
+                        BatchTrackingStoreError::NotFoundError(format!(
+                            "Could not find batch with data change ID {}",
+                            id
+                        ))
+                    })?;
+            }
+
+            let batch_exists: bool = select(exists(
+                batches::table.filter(
+                    batches::batch_id
+                        .eq(&batch_id)
+                        .and(batches::service_id.eq(&service_id)),
+                ),
+            ))
+            .get_result(self.conn)?;
+
+            if !batch_exists {
+                return Err(BatchTrackingStoreError::NotFoundError(format!(
+                    "Could not find batch with ID {}",
+                    batch_id
+                )));
+            }
+
+            let txn_ids: Vec<String> = transactions::table
+                .select(transactions::transaction_id)
+                .filter(
+                    transactions::batch_id
+                        .eq(&batch_id)
+                        .and(transactions::service_id.eq(&service_id)),
+                )
+                .load(self.conn)?;
+
+            // The receipt rows are kept, as the batch's status is rebuilt
+            // from whether each transaction was valid, but their payloads are
+            // cleared
+            update(transaction_receipts::table)
+                .filter(
+                    transaction_receipts::transaction_id
+                        .eq_any(&txn_ids)
+                        .and(transaction_receipts::service_id.eq(&service_id)),
+                )
+                .set((
+                    transaction_receipts::serialized_receipt.eq(None::<Vec<u8>>),
+                    transaction_receipts::error_data.eq(None::<Vec<u8>>),
+                ))
+                .execute(self.conn)
+                .map_err(BatchTrackingStoreError::from)
+        })
+    }
+}
@@ -0,0 +1,188 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::resolve_data_change_id::BatchTrackingStoreResolveDataChangeIdOperation;
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::{
+    diesel::{models::is_data_change_id, schema::batches},
+    BatchTrackingStoreError,
+};
+use crate::error::{
+    ConstraintViolationError, ConstraintViolationType, InternalError, InvalidArgumentError,
+};
+
+use diesel::{dsl::update, prelude::*};
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreSetAliasOperation {
+    fn set_alias(
+        &self,
+        id: &str,
+        service_id: &str,
+        alias: Option<&str>,
+    ) -> Result<(), BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreSetAliasOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn set_alias(
+        &self,
+        id: &str,
+        service_id: &str,
+        alias: Option<&str>,
+    ) -> Result<(), BatchTrackingStoreError> {
+        self.transaction("set_alias", || {
+            let mut batch_id = id.to_string();
+            if is_data_change_id(id)? {
+                batch_id = self
+                    .resolve_data_change_id(id, service_id)?
+                    .unwrap_or(batch_id);
+            }
+
+            if let Some(alias) = alias {
+                validate_alias(alias)?;
+
+                let taken = batches::table
+                    .select(batches::batch_id)
+                    .filter(
+                        batches::alias
+                            .eq(alias)
+                            .and(batches::service_id.eq(&service_id))
+                            .and(batches::batch_id.ne(&batch_id)),
+                    )
+                    .first::<String>(self.conn)
+                    .optional()?;
+
+                if let Some(other_batch_id) = taken {
+                    return Err(BatchTrackingStoreError::ConstraintViolationError(
+                        ConstraintViolationError::from_source_with_violation_type(
+                            ConstraintViolationType::Unique,
+                            Box::new(InternalError::with_message(format!(
+                                "Alias {} is already used by batch {}",
+                                alias, other_batch_id
+                            ))),
+                        ),
+                    ));
+                }
+            }
+
+            let updated = update(batches::table)
+                .filter(
+                    batches::batch_id
+                        .eq(&batch_id)
+                        .and(batches::service_id.eq(&service_id)),
+                )
+                .set(batches::alias.eq(alias))
+                .execute(self.conn)?;
+
+            if updated == 0 {
+                return Err(BatchTrackingStoreError::NotFoundError(format!(
+                    "Could not find batch with ID {}",
+                    id
+                )));
+            }
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreSetAliasOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn set_alias(
+        &self,
+        id: &str,
+        service_id: &str,
+        alias: Option<&str>,
+    ) -> Result<(), BatchTrackingStoreError> {
+        self.transaction("set_alias", || {
+            let mut batch_id = id.to_string();
+            if is_data_change_id(id)? {
+                batch_id = self
+                    .resolve_data_change_id(id, service_id)?
+                    .unwrap_or(batch_id);
+            }
+
+            if let Some(alias) = alias {
+                validate_alias(alias)?;
+
+                let taken = batches::table
+                    .select(batches::batch_id)
+                    .filter(
+                        batches::alias
+                            .eq(alias)
+                            .and(batches::service_id.eq(&service_id))
+                            .and(batches::batch_id.ne(&batch_id)),
+                    )
+                    .first::<String>(self.conn)
+                    .optional()?;
+
+                if let Some(other_batch_id) = taken {
+                    return Err(BatchTrackingStoreError::ConstraintViolationError(
+                        ConstraintViolationError::from_source_with_violation_type(
+                            ConstraintViolationType::Unique,
+                            Box::new(InternalError::with_message(format!(
+                                "Alias {} is already used by batch {}",
+                                alias, other_batch_id
+                            ))),
+                        ),
+                    ));
+                }
+            }
+
+            let updated = update(batches::table)
+                .filter(
+                    batches::batch_id
+                        .eq(&batch_id)
+                        .and(batches::service_id.eq(&service_id)),
+                )
+                .set(batches::alias.eq(alias))
+                .execute(self.conn)?;
+
+            if updated == 0 {
+                return Err(BatchTrackingStoreError::NotFoundError(format!(
+                    "Could not find batch with ID {}",
+                    id
+                )));
+            }
+
+            Ok(())
+        })
+    }
+}
+
+/// Checks that an alias can be told apart from the IDs a batch can be
+/// referenced by
+fn validate_alias(alias: &str) -> Result<(), BatchTrackingStoreError> {
+    if alias.trim().is_empty() {
+        return Err(BatchTrackingStoreError::InvalidArgumentError(
+            InvalidArgumentError::new("alias".to_string(), "alias can not be empty".to_string()),
+        ));
+    }
+
+    if is_data_change_id(alias)? {
+        return Err(BatchTrackingStoreError::InvalidArgumentError(
+            InvalidArgumentError::new(
+                "alias".to_string(),
+                "alias can not be formatted as a data change ID".to_string(),
+            ),
+        ));
+    }
+
+    Ok(())
+}
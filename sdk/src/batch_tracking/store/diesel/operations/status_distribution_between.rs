@@ -0,0 +1,102 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::{
+    diesel::schema::{batch_statuses, batches},
+    BatchStatusName, BatchTrackingStoreError,
+};
+
+use diesel::{dsl::sql, prelude::*, sql_types::BigInt};
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreStatusDistributionBetweenOperation
+{
+    fn status_distribution_between(
+        &self,
+        service_id: &str,
+        start: i64,
+        end: i64,
+    ) -> Result<HashMap<BatchStatusName, i64>, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreStatusDistributionBetweenOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn status_distribution_between(
+        &self,
+        service_id: &str,
+        start: i64,
+        end: i64,
+    ) -> Result<HashMap<BatchStatusName, i64>, BatchTrackingStoreError> {
+        self.transaction("status_distribution_between", || {
+            // Diesel does not allow mixing aggregate and non-aggregate
+            // expressions in a select, so the count is written as raw SQL
+            let counts: Vec<(String, i64)> = batches::table
+                .inner_join(
+                    batch_statuses::table.on(batches::batch_id
+                        .eq(batch_statuses::batch_id)
+                        .and(batches::service_id.eq(batch_statuses::service_id))),
+                )
+                .filter(batches::service_id.eq(service_id))
+                .filter(batches::created_at.ge(start))
+                .filter(batches::created_at.lt(end))
+                .group_by(batch_statuses::dlt_status)
+                .select((batch_statuses::dlt_status, sql::<BigInt>("COUNT(*)")))
+                .load(self.conn)?;
+
+            counts
+                .into_iter()
+                .map(|(status, count)| Ok((BatchStatusName::try_from_string(&status)?, count)))
+                .collect()
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreStatusDistributionBetweenOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn status_distribution_between(
+        &self,
+        service_id: &str,
+        start: i64,
+        end: i64,
+    ) -> Result<HashMap<BatchStatusName, i64>, BatchTrackingStoreError> {
+        self.transaction("status_distribution_between", || {
+            // Diesel does not allow mixing aggregate and non-aggregate
+            // expressions in a select, so the count is written as raw SQL
+            let counts: Vec<(String, i64)> = batches::table
+                .inner_join(
+                    batch_statuses::table.on(batches::batch_id
+                        .eq(batch_statuses::batch_id)
+                        .and(batches::service_id.eq(batch_statuses::service_id))),
+                )
+                .filter(batches::service_id.eq(service_id))
+                .filter(batches::created_at.ge(start))
+                .filter(batches::created_at.lt(end))
+                .group_by(batch_statuses::dlt_status)
+                .select((batch_statuses::dlt_status, sql::<BigInt>("COUNT(*)")))
+                .load(self.conn)?;
+
+            counts
+                .into_iter()
+                .map(|(status, count)| Ok((BatchStatusName::try_from_string(&status)?, count)))
+                .collect()
+        })
+    }
+}
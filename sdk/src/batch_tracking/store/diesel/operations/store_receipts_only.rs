@@ -0,0 +1,230 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::resolve_data_change_id::BatchTrackingStoreResolveDataChangeIdOperation;
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::{
+    diesel::{
+        models::{is_data_change_id, TransactionReceiptModel},
+        schema::{batches, transaction_receipts, transactions},
+    },
+    BatchTrackingStoreError,
+};
+use crate::error::InvalidArgumentError;
+
+use diesel::{
+    dsl::{exists, insert_into, update},
+    prelude::*,
+    select,
+};
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreStoreReceiptsOnlyOperation {
+    fn store_receipts_only(
+        &self,
+        id: &str,
+        service_id: &str,
+        txn_receipts: Vec<TransactionReceiptModel>,
+    ) -> Result<(), BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreStoreReceiptsOnlyOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn store_receipts_only(
+        &self,
+        id: &str,
+        service_id: &str,
+        txn_receipts: Vec<TransactionReceiptModel>,
+    ) -> Result<(), BatchTrackingStoreError> {
+        self.transaction("store_receipts_only", || {
+            let mut batch_id = id.to_string();
+            let is_dcid = is_data_change_id(id)?;
+            if is_dcid {
+                batch_id = self
+                    .resolve_data_change_id(id, service_id)?
+                    .ok_or_else(|| {
+                        BatchTrackingStoreError::NotFoundError(format!(
+                            "Could not find batch with data change ID {}",
+                            id
+                        ))
+                    })?;
+            }
+
+            let batch_exists: bool = select(exists(
+                batches::table.filter(
+                    batches::batch_id
+                        .eq(&batch_id)
+                        .and(batches::service_id.eq(&service_id)),
+                ),
+            ))
+            .get_result(self.conn)?;
+
+            if !batch_exists {
+                return Err(BatchTrackingStoreError::NotFoundError(format!(
+                    "Could not find batch with ID {}",
+                    batch_id
+                )));
+            }
+
+            let txn_ids: Vec<String> = transactions::table
+                .select(transactions::transaction_id)
+                .filter(
+                    transactions::batch_id
+                        .eq(&batch_id)
+                        .and(transactions::service_id.eq(&service_id)),
+                )
+                .load(self.conn)?;
+
+            if let Some(rcpt) = txn_receipts
+                .iter()
+                .find(|r| !txn_ids.contains(&r.transaction_id))
+            {
+                return Err(BatchTrackingStoreError::InvalidArgumentError(
+                    InvalidArgumentError::new(
+                        "transaction_receipts".to_string(),
+                        format!(
+                            "transaction {} is not in batch {}",
+                            rcpt.transaction_id, batch_id
+                        ),
+                    ),
+                ));
+            }
+
+            let existing_rcpts: Vec<String> = transaction_receipts::table
+                .select(transaction_receipts::transaction_id)
+                .filter(
+                    transaction_receipts::transaction_id
+                        .eq_any(&txn_ids)
+                        .and(transaction_receipts::service_id.eq(&service_id)),
+                )
+                .load(self.conn)?;
+
+            for r in txn_receipts {
+                if existing_rcpts.contains(&r.transaction_id) {
+                    update(transaction_receipts::table)
+                        .filter(
+                            transaction_receipts::transaction_id
+                                .eq(&r.transaction_id)
+                                .and(transaction_receipts::service_id.eq(&service_id)),
+                        )
+                        .set(&r)
+                        .execute(self.conn)?;
+                } else {
+                    insert_into(transaction_receipts::table)
+                        .values(&r)
+                        .execute(self.conn)?;
+                }
+            }
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreStoreReceiptsOnlyOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn store_receipts_only(
+        &self,
+        id: &str,
+        service_id: &str,
+        txn_receipts: Vec<TransactionReceiptModel>,
+    ) -> Result<(), BatchTrackingStoreError> {
+        self.transaction("store_receipts_only", || {
+            let mut batch_id = id.to_string();
+            let is_dcid = is_data_change_id(id)?;
+            if is_dcid {
+                batch_id = self
+                    .resolve_data_change_id(id, service_id)?
+                    .ok_or_else(|| {
+                        BatchTrackingStoreError::NotFoundError(format!(
+                            "Could not find batch with data change ID {}",
+                            id
+                        ))
+                    })?;
+            }
+
+            let batch_exists: bool = select(exists(
+                batches::table.filter(
+                    batches::batch_id
+                        .eq(&batch_id)
+                        .and(batches::service_id.eq(&service_id)),
+                ),
+            ))
+            .get_result(self.conn)?;
+
+            if !batch_exists {
+                return Err(BatchTrackingStoreError::NotFoundError(format!(
+                    "Could not find batch with ID {}",
+                    batch_id
+                )));
+            }
+
+            let txn_ids: Vec<String> = transactions::table
+                .select(transactions::transaction_id)
+                .filter(
+                    transactions::batch_id
+                        .eq(&batch_id)
+                        .and(transactions::service_id.eq(&service_id)),
+                )
+                .load(self.conn)?;
+
+            if let Some(rcpt) = txn_receipts
+                .iter()
+                .find(|r| !txn_ids.contains(&r.transaction_id))
+            {
+                return Err(BatchTrackingStoreError::InvalidArgumentError(
+                    InvalidArgumentError::new(
+                        "transaction_receipts".to_string(),
+                        format!(
+                            "transaction {} is not in batch {}",
+                            rcpt.transaction_id, batch_id
+                        ),
+                    ),
+                ));
+            }
+
+            let existing_rcpts: Vec<String> = transaction_receipts::table
+                .select(transaction_receipts::transaction_id)
+                .filter(
+                    transaction_receipts::transaction_id
+                        .eq_any(&txn_ids)
+                        .and(transaction_receipts::service_id.eq(&service_id)),
+                )
+                .load(self.conn)?;
+
+            for r in txn_receipts {
+                if existing_rcpts.contains(&r.transaction_id) {
+                    update(transaction_receipts::table)
+                        .filter(
+                            transaction_receipts::transaction_id
+                                .eq(&r.transaction_id)
+                                .and(transaction_receipts::service_id.eq(&service_id)),
+                        )
+                        .set(&r)
+                        .execute(self.conn)?;
+                } else {
+                    insert_into(transaction_receipts::table)
+                        .values(&r)
+                        .execute(self.conn)?;
+                }
+            }
+
+            Ok(())
+        })
+    }
+}
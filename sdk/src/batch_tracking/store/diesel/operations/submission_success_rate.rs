@@ -0,0 +1,125 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::{
+    diesel::schema::{batch_statuses, batches},
+    BatchStatusName, BatchTrackingStoreError,
+};
+
+use diesel::{dsl::sql, prelude::*, sql_types::BigInt};
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreSubmissionSuccessRateOperation
+{
+    fn submission_success_rate(
+        &self,
+        service_id: &str,
+        start: i64,
+        end: i64,
+    ) -> Result<f64, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreSubmissionSuccessRateOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn submission_success_rate(
+        &self,
+        service_id: &str,
+        start: i64,
+        end: i64,
+    ) -> Result<f64, BatchTrackingStoreError> {
+        self.transaction("submission_success_rate", || {
+            let counts: Vec<(String, i64)> = batches::table
+                .inner_join(
+                    batch_statuses::table.on(batches::batch_id
+                        .eq(batch_statuses::batch_id)
+                        .and(batches::service_id.eq(batch_statuses::service_id))),
+                )
+                .filter(batches::service_id.eq(service_id))
+                .filter(batches::created_at.ge(start))
+                .filter(batches::created_at.lt(end))
+                .filter(batch_statuses::dlt_status.eq_any(&[
+                    BatchStatusName::Committed.to_string(),
+                    BatchStatusName::Invalid.to_string(),
+                    BatchStatusName::DeadLettered.to_string(),
+                ]))
+                .group_by(batch_statuses::dlt_status)
+                .select((batch_statuses::dlt_status, sql::<BigInt>("COUNT(*)")))
+                .load(self.conn)?;
+
+            success_rate(&counts, start, end)
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreSubmissionSuccessRateOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn submission_success_rate(
+        &self,
+        service_id: &str,
+        start: i64,
+        end: i64,
+    ) -> Result<f64, BatchTrackingStoreError> {
+        self.transaction("submission_success_rate", || {
+            let counts: Vec<(String, i64)> = batches::table
+                .inner_join(
+                    batch_statuses::table.on(batches::batch_id
+                        .eq(batch_statuses::batch_id)
+                        .and(batches::service_id.eq(batch_statuses::service_id))),
+                )
+                .filter(batches::service_id.eq(service_id))
+                .filter(batches::created_at.ge(start))
+                .filter(batches::created_at.lt(end))
+                .filter(batch_statuses::dlt_status.eq_any(&[
+                    BatchStatusName::Committed.to_string(),
+                    BatchStatusName::Invalid.to_string(),
+                    BatchStatusName::DeadLettered.to_string(),
+                ]))
+                .group_by(batch_statuses::dlt_status)
+                .select((batch_statuses::dlt_status, sql::<BigInt>("COUNT(*)")))
+                .load(self.conn)?;
+
+            success_rate(&counts, start, end)
+        })
+    }
+}
+
+/// Returns the fraction of the terminal batches counted by status in
+/// `counts` that were committed
+fn success_rate(
+    counts: &[(String, i64)],
+    start: i64,
+    end: i64,
+) -> Result<f64, BatchTrackingStoreError> {
+    let committed_status = BatchStatusName::Committed.to_string();
+    let committed: i64 = counts
+        .iter()
+        .filter(|(status, _)| status == &committed_status)
+        .map(|(_, count)| count)
+        .sum();
+    let terminal: i64 = counts.iter().map(|(_, count)| count).sum();
+
+    if terminal == 0 {
+        return Err(BatchTrackingStoreError::NotFoundError(format!(
+            "No batches created between {} and {} have reached a terminal status",
+            start, end
+        )));
+    }
+
+    Ok(committed as f64 / terminal as f64)
+}
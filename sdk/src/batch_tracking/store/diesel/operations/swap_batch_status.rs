@@ -0,0 +1,97 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{
+    get_batch_status::BatchTrackingStoreGetBatchStatusOperation,
+    update_batch_status::BatchTrackingStoreUpdateBatchStatusOperation,
+    BatchTrackingStoreOperations,
+};
+
+use crate::batch_tracking::store::{BatchStatus, BatchTrackingStoreError};
+
+#[cfg(feature = "postgres")]
+use super::resolve_data_change_id::BatchTrackingStoreResolveDataChangeIdOperation;
+#[cfg(feature = "postgres")]
+use crate::batch_tracking::store::diesel::{models::is_data_change_id, schema::batch_statuses};
+#[cfg(feature = "postgres")]
+use diesel::prelude::*;
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreSwapBatchStatusOperation {
+    fn swap_batch_status(
+        &self,
+        id: &str,
+        service_id: &str,
+        status: &str,
+    ) -> Result<Option<BatchStatus>, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreSwapBatchStatusOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn swap_batch_status(
+        &self,
+        id: &str,
+        service_id: &str,
+        status: &str,
+    ) -> Result<Option<BatchStatus>, BatchTrackingStoreError> {
+        self.transaction("swap_batch_status", || {
+            // The status row is locked so that a concurrent update can not
+            // land between reading and replacing the status
+            let mut batch_id = id.to_string();
+            if is_data_change_id(id)? {
+                batch_id = self
+                    .resolve_data_change_id(id, service_id)?
+                    .ok_or(diesel::result::Error::NotFound)?;
+            }
+
+            batch_statuses::table
+                .select(batch_statuses::batch_id)
+                .filter(
+                    batch_statuses::batch_id
+                        .eq(&batch_id)
+                        .and(batch_statuses::service_id.eq(&service_id)),
+                )
+                .for_update()
+                .first::<String>(self.conn)
+                .optional()?;
+
+            let previous = self.get_batch_status(id, service_id)?;
+
+            self.update_batch_status(id, service_id, Some(status), Vec::new(), None)?;
+
+            Ok(previous)
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreSwapBatchStatusOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn swap_batch_status(
+        &self,
+        id: &str,
+        service_id: &str,
+        status: &str,
+    ) -> Result<Option<BatchStatus>, BatchTrackingStoreError> {
+        self.transaction("swap_batch_status", || {
+            let previous = self.get_batch_status(id, service_id)?;
+
+            self.update_batch_status(id, service_id, Some(status), Vec::new(), None)?;
+
+            Ok(previous)
+        })
+    }
+}
@@ -0,0 +1,340 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::diesel::{
+    models::{
+        BatchModel, BatchStatusModel, SubmissionModel, TransactionAddressModel, TransactionModel,
+        TransactionReceiptModel,
+    },
+    schema::{
+        batch_statuses, batches, submissions, transaction_addresses, transaction_receipts,
+        transactions,
+    },
+    TrackingBatchList,
+};
+
+use crate::batch_tracking::store::{BatchTrackingStoreError, TrackingBatch};
+use diesel::{dsl::sql, prelude::*, sql_types::BigInt};
+use std::convert::TryFrom;
+
+/// The time a batch was last updated, which is the latest of the time it was
+/// added and the times its status and submission were last updated
+#[cfg(feature = "postgres")]
+const PG_UPDATED_AT: &str = "GREATEST(batches.created_at, \
+    COALESCE(batch_statuses.updated_at, 0), COALESCE(submissions.updated_at, 0))";
+
+/// The time a batch was last updated, which is the latest of the time it was
+/// added and the times its status and submission were last updated
+///
+/// With more than one argument, MAX is SQLite's scalar maximum rather than the
+/// aggregate.
+#[cfg(feature = "sqlite")]
+const SQLITE_UPDATED_AT: &str = "MAX(batches.created_at, \
+    COALESCE(batch_statuses.updated_at, 0), COALESCE(submissions.updated_at, 0))";
+
+type SyncRow = (
+    BatchModel,
+    Option<BatchStatusModel>,
+    Option<SubmissionModel>,
+    i64,
+);
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreSyncSinceOperation {
+    fn sync_since(
+        &self,
+        service_id: &str,
+        checkpoint: Option<i64>,
+        limit: i64,
+    ) -> Result<(Vec<TrackingBatch>, Option<i64>), BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreSyncSinceOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn sync_since(
+        &self,
+        service_id: &str,
+        checkpoint: Option<i64>,
+        limit: i64,
+    ) -> Result<(Vec<TrackingBatch>, Option<i64>), BatchTrackingStoreError> {
+        self.transaction("sync_since", || {
+            let mut query = batches::table
+                .left_join(
+                    batch_statuses::table.on(batches::batch_id
+                        .eq(batch_statuses::batch_id)
+                        .and(batches::service_id.eq(batch_statuses::service_id))),
+                )
+                .left_join(
+                    submissions::table.on(batches::batch_id
+                        .eq(submissions::batch_id)
+                        .and(batches::service_id.eq(submissions::service_id))),
+                )
+                .filter(batches::service_id.eq(service_id))
+                .select((
+                    batches::all_columns,
+                    batch_statuses::all_columns.nullable(),
+                    submissions::all_columns.nullable(),
+                    sql::<BigInt>(PG_UPDATED_AT),
+                ))
+                .order((sql::<BigInt>(PG_UPDATED_AT).asc(), batches::batch_id.asc()))
+                .into_boxed();
+
+            if let Some(checkpoint) = checkpoint {
+                query = query.filter(sql::<BigInt>(PG_UPDATED_AT).gt(checkpoint));
+            }
+
+            let mut batch_results: Vec<SyncRow> = query.limit(limit + 1).load(self.conn)?;
+
+            if let Some(boundary) = page_boundary(&mut batch_results, limit) {
+                // Every batch on the page was updated at the same time as the
+                // next batch, so the whole group is returned instead
+                let mut query = batches::table
+                    .left_join(
+                        batch_statuses::table.on(batches::batch_id
+                            .eq(batch_statuses::batch_id)
+                            .and(batches::service_id.eq(batch_statuses::service_id))),
+                    )
+                    .left_join(
+                        submissions::table.on(batches::batch_id
+                            .eq(submissions::batch_id)
+                            .and(batches::service_id.eq(submissions::service_id))),
+                    )
+                    .filter(batches::service_id.eq(service_id))
+                    .filter(sql::<BigInt>(PG_UPDATED_AT).eq(boundary))
+                    .select((
+                        batches::all_columns,
+                        batch_statuses::all_columns.nullable(),
+                        submissions::all_columns.nullable(),
+                        sql::<BigInt>(PG_UPDATED_AT),
+                    ))
+                    .order(batches::batch_id.asc())
+                    .into_boxed();
+
+                if let Some(checkpoint) = checkpoint {
+                    query = query.filter(sql::<BigInt>(PG_UPDATED_AT).gt(checkpoint));
+                }
+
+                batch_results = query.load(self.conn)?;
+            }
+
+            let next_checkpoint = batch_results
+                .last()
+                .map(|(_, _, _, updated_at)| *updated_at)
+                .or(checkpoint);
+
+            let mut batch_models = Vec::new();
+            let mut batch_status_models = Vec::new();
+            let mut submission_models = Vec::new();
+
+            for (batch, status, submission, _) in batch_results {
+                batch_models.push(batch);
+                if let Some(status) = status {
+                    batch_status_models.push(status);
+                }
+                if let Some(submission) = submission {
+                    submission_models.push(submission);
+                }
+            }
+
+            let batch_ids: Vec<&str> = batch_models.iter().map(|b| b.batch_id.as_str()).collect();
+
+            let txn_models: Vec<TransactionModel> = transactions::table
+                .filter(transactions::batch_id.eq_any(&batch_ids))
+                .filter(transactions::service_id.eq(service_id))
+                .load(self.conn)?;
+            let txn_models = self.decrypt_payloads(txn_models)?;
+
+            let txn_ids: Vec<&str> = txn_models
+                .iter()
+                .map(|t| t.transaction_id.as_str())
+                .collect();
+
+            let receipt_models: Vec<TransactionReceiptModel> = transaction_receipts::table
+                .filter(transaction_receipts::transaction_id.eq_any(&txn_ids))
+                .filter(transaction_receipts::service_id.eq(service_id))
+                .load(self.conn)?;
+
+            let address_models: Vec<TransactionAddressModel> = transaction_addresses::table
+                .filter(transaction_addresses::transaction_id.eq_any(&txn_ids))
+                .filter(transaction_addresses::service_id.eq(service_id))
+                .load(self.conn)?;
+
+            let batch_list = TrackingBatchList::try_from((
+                batch_models,
+                batch_status_models,
+                txn_models,
+                address_models,
+                receipt_models,
+                submission_models,
+            ))?;
+
+            Ok((batch_list.batches, next_checkpoint))
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreSyncSinceOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn sync_since(
+        &self,
+        service_id: &str,
+        checkpoint: Option<i64>,
+        limit: i64,
+    ) -> Result<(Vec<TrackingBatch>, Option<i64>), BatchTrackingStoreError> {
+        self.transaction("sync_since", || {
+            let mut query = batches::table
+                .left_join(
+                    batch_statuses::table.on(batches::batch_id
+                        .eq(batch_statuses::batch_id)
+                        .and(batches::service_id.eq(batch_statuses::service_id))),
+                )
+                .left_join(
+                    submissions::table.on(batches::batch_id
+                        .eq(submissions::batch_id)
+                        .and(batches::service_id.eq(submissions::service_id))),
+                )
+                .filter(batches::service_id.eq(service_id))
+                .select((
+                    batches::all_columns,
+                    batch_statuses::all_columns.nullable(),
+                    submissions::all_columns.nullable(),
+                    sql::<BigInt>(SQLITE_UPDATED_AT),
+                ))
+                .order((
+                    sql::<BigInt>(SQLITE_UPDATED_AT).asc(),
+                    batches::batch_id.asc(),
+                ))
+                .into_boxed();
+
+            if let Some(checkpoint) = checkpoint {
+                query = query.filter(sql::<BigInt>(SQLITE_UPDATED_AT).gt(checkpoint));
+            }
+
+            let mut batch_results: Vec<SyncRow> = query.limit(limit + 1).load(self.conn)?;
+
+            if let Some(boundary) = page_boundary(&mut batch_results, limit) {
+                // Every batch on the page was updated at the same time as the
+                // next batch, so the whole group is returned instead
+                let mut query = batches::table
+                    .left_join(
+                        batch_statuses::table.on(batches::batch_id
+                            .eq(batch_statuses::batch_id)
+                            .and(batches::service_id.eq(batch_statuses::service_id))),
+                    )
+                    .left_join(
+                        submissions::table.on(batches::batch_id
+                            .eq(submissions::batch_id)
+                            .and(batches::service_id.eq(submissions::service_id))),
+                    )
+                    .filter(batches::service_id.eq(service_id))
+                    .filter(sql::<BigInt>(SQLITE_UPDATED_AT).eq(boundary))
+                    .select((
+                        batches::all_columns,
+                        batch_statuses::all_columns.nullable(),
+                        submissions::all_columns.nullable(),
+                        sql::<BigInt>(SQLITE_UPDATED_AT),
+                    ))
+                    .order(batches::batch_id.asc())
+                    .into_boxed();
+
+                if let Some(checkpoint) = checkpoint {
+                    query = query.filter(sql::<BigInt>(SQLITE_UPDATED_AT).gt(checkpoint));
+                }
+
+                batch_results = query.load(self.conn)?;
+            }
+
+            let next_checkpoint = batch_results
+                .last()
+                .map(|(_, _, _, updated_at)| *updated_at)
+                .or(checkpoint);
+
+            let mut batch_models = Vec::new();
+            let mut batch_status_models = Vec::new();
+            let mut submission_models = Vec::new();
+
+            for (batch, status, submission, _) in batch_results {
+                batch_models.push(batch);
+                if let Some(status) = status {
+                    batch_status_models.push(status);
+                }
+                if let Some(submission) = submission {
+                    submission_models.push(submission);
+                }
+            }
+
+            let batch_ids: Vec<&str> = batch_models.iter().map(|b| b.batch_id.as_str()).collect();
+
+            let txn_models: Vec<TransactionModel> = transactions::table
+                .filter(transactions::batch_id.eq_any(&batch_ids))
+                .filter(transactions::service_id.eq(service_id))
+                .load(self.conn)?;
+            let txn_models = self.decrypt_payloads(txn_models)?;
+
+            let txn_ids: Vec<&str> = txn_models
+                .iter()
+                .map(|t| t.transaction_id.as_str())
+                .collect();
+
+            let receipt_models: Vec<TransactionReceiptModel> = transaction_receipts::table
+                .filter(transaction_receipts::transaction_id.eq_any(&txn_ids))
+                .filter(transaction_receipts::service_id.eq(service_id))
+                .load(self.conn)?;
+
+            let address_models: Vec<TransactionAddressModel> = transaction_addresses::table
+                .filter(transaction_addresses::transaction_id.eq_any(&txn_ids))
+                .filter(transaction_addresses::service_id.eq(service_id))
+                .load(self.conn)?;
+
+            let batch_list = TrackingBatchList::try_from((
+                batch_models,
+                batch_status_models,
+                txn_models,
+                address_models,
+                receipt_models,
+                submission_models,
+            ))?;
+
+            Ok((batch_list.batches, next_checkpoint))
+        })
+    }
+}
+
+/// Trims a page of up to `limit + 1` rows, ordered by update time, down to at
+/// most `limit` rows without splitting rows with the same update time across
+/// pages, as the next sync would skip the rest of them
+///
+/// Returns the update time of the group to load in full if every row on the
+/// page shares it.
+fn page_boundary(rows: &mut Vec<SyncRow>, limit: i64) -> Option<i64> {
+    let limit = limit.max(0) as usize;
+    if rows.len() <= limit {
+        return None;
+    }
+
+    let boundary = rows[limit].3;
+    rows.truncate(limit);
+    rows.retain(|(_, _, _, updated_at)| *updated_at < boundary);
+
+    if rows.is_empty() {
+        Some(boundary)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,90 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::{
+    diesel::{models::NewBatchTombstoneModel, schema::batch_tombstones},
+    BatchTrackingStoreError,
+};
+
+use diesel::{
+    dsl::{exists, insert_into},
+    prelude::*,
+    select,
+};
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreTombstoneBatchOperation {
+    fn tombstone_batch(&self, id: &str, service_id: &str) -> Result<(), BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreTombstoneBatchOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn tombstone_batch(&self, id: &str, service_id: &str) -> Result<(), BatchTrackingStoreError> {
+        self.transaction("tombstone_batch", || {
+            let tombstone_exists: bool = select(exists(
+                batch_tombstones::table.filter(
+                    batch_tombstones::batch_id
+                        .eq(&id)
+                        .and(batch_tombstones::service_id.eq(&service_id)),
+                ),
+            ))
+            .get_result(self.conn)?;
+
+            if !tombstone_exists {
+                insert_into(batch_tombstones::table)
+                    .values(NewBatchTombstoneModel {
+                        service_id: service_id.to_string(),
+                        batch_id: id.to_string(),
+                        created_at: self.now()?,
+                    })
+                    .execute(self.conn)?;
+            }
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreTombstoneBatchOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn tombstone_batch(&self, id: &str, service_id: &str) -> Result<(), BatchTrackingStoreError> {
+        self.transaction("tombstone_batch", || {
+            let tombstone_exists: bool = select(exists(
+                batch_tombstones::table.filter(
+                    batch_tombstones::batch_id
+                        .eq(&id)
+                        .and(batch_tombstones::service_id.eq(&service_id)),
+                ),
+            ))
+            .get_result(self.conn)?;
+
+            if !tombstone_exists {
+                insert_into(batch_tombstones::table)
+                    .values(NewBatchTombstoneModel {
+                        service_id: service_id.to_string(),
+                        batch_id: id.to_string(),
+                        created_at: self.now()?,
+                    })
+                    .execute(self.conn)?;
+            }
+
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,120 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeSet;
+
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::{
+    diesel::{models::NewBatchTombstoneModel, schema::batch_tombstones},
+    BatchTrackingStoreError,
+};
+
+use diesel::{dsl::insert_into, prelude::*};
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreTombstoneBatchesOperation {
+    fn tombstone_batches(
+        &self,
+        ids: &[&str],
+        service_id: &str,
+    ) -> Result<usize, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreTombstoneBatchesOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn tombstone_batches(
+        &self,
+        ids: &[&str],
+        service_id: &str,
+    ) -> Result<usize, BatchTrackingStoreError> {
+        self.retrying_transaction("tombstone_batches", || {
+            let tombstoned: Vec<String> = batch_tombstones::table
+                .filter(batch_tombstones::service_id.eq(&service_id))
+                .filter(batch_tombstones::batch_id.eq_any(ids))
+                .select(batch_tombstones::batch_id)
+                .load(self.conn)?;
+
+            // IDs that are already tombstoned, or repeated, are only counted
+            // once
+            let created_at = self.now()?;
+            let new_ids: BTreeSet<&str> = ids
+                .iter()
+                .copied()
+                .filter(|id| !tombstoned.iter().any(|tombstoned| tombstoned == id))
+                .collect();
+            let tombstone_models: Vec<NewBatchTombstoneModel> = new_ids
+                .into_iter()
+                .map(|id| NewBatchTombstoneModel {
+                    service_id: service_id.to_string(),
+                    batch_id: id.to_string(),
+                    created_at,
+                })
+                .collect();
+
+            if tombstone_models.is_empty() {
+                return Ok(0);
+            }
+
+            Ok(insert_into(batch_tombstones::table)
+                .values(tombstone_models)
+                .execute(self.conn)?)
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreTombstoneBatchesOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn tombstone_batches(
+        &self,
+        ids: &[&str],
+        service_id: &str,
+    ) -> Result<usize, BatchTrackingStoreError> {
+        self.transaction("tombstone_batches", || {
+            let tombstoned: Vec<String> = batch_tombstones::table
+                .filter(batch_tombstones::service_id.eq(&service_id))
+                .filter(batch_tombstones::batch_id.eq_any(ids))
+                .select(batch_tombstones::batch_id)
+                .load(self.conn)?;
+
+            // IDs that are already tombstoned, or repeated, are only counted
+            // once
+            let created_at = self.now()?;
+            let new_ids: BTreeSet<&str> = ids
+                .iter()
+                .copied()
+                .filter(|id| !tombstoned.iter().any(|tombstoned| tombstoned == id))
+                .collect();
+            let tombstone_models: Vec<NewBatchTombstoneModel> = new_ids
+                .into_iter()
+                .map(|id| NewBatchTombstoneModel {
+                    service_id: service_id.to_string(),
+                    batch_id: id.to_string(),
+                    created_at,
+                })
+                .collect();
+
+            if tombstone_models.is_empty() {
+                return Ok(0);
+            }
+
+            Ok(insert_into(batch_tombstones::table)
+                .values(tombstone_models)
+                .execute(self.conn)?)
+        })
+    }
+}
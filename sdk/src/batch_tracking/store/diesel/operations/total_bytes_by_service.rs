@@ -0,0 +1,52 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::BatchTrackingStoreOperations;
+
+use crate::batch_tracking::store::{diesel::schema::batches, BatchTrackingStoreError};
+use diesel::{dsl::sql, prelude::*, sql_types::BigInt};
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreTotalBytesByServiceOperation {
+    fn total_bytes_by_service(&self, service_id: &str) -> Result<i64, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreTotalBytesByServiceOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn total_bytes_by_service(&self, service_id: &str) -> Result<i64, BatchTrackingStoreError> {
+        self.transaction("total_bytes_by_service", || {
+            // SUM of a BIGINT is a NUMERIC on postgres, so it is cast back
+            Ok(batches::table
+                .select(sql::<BigInt>("CAST(COALESCE(SUM(byte_size), 0) AS BIGINT)"))
+                .filter(batches::service_id.eq(service_id))
+                .get_result(self.conn)?)
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreTotalBytesByServiceOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn total_bytes_by_service(&self, service_id: &str) -> Result<i64, BatchTrackingStoreError> {
+        self.transaction("total_bytes_by_service", || {
+            // SUM of a BIGINT is a NUMERIC on postgres, so it is cast back
+            Ok(batches::table
+                .select(sql::<BigInt>("CAST(COALESCE(SUM(byte_size), 0) AS BIGINT)"))
+                .filter(batches::service_id.eq(service_id))
+                .get_result(self.conn)?)
+        })
+    }
+}
@@ -0,0 +1,175 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{
+    resolve_data_change_id::BatchTrackingStoreResolveDataChangeIdOperation,
+    update_batch_status::BatchTrackingStoreUpdateBatchStatusOperation,
+    BatchTrackingStoreOperations,
+};
+
+use crate::batch_tracking::store::{
+    diesel::{
+        models::is_data_change_id,
+        schema::{batch_statuses, batches},
+    },
+    BatchStatus, BatchStatusName, BatchTrackingStoreError,
+};
+
+use diesel::prelude::*;
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreTransitionBatchesOperation {
+    fn transition_batches(
+        &self,
+        transitions: Vec<(String, BatchStatus)>,
+        service_id: &str,
+    ) -> Result<(), BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreTransitionBatchesOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn transition_batches(
+        &self,
+        transitions: Vec<(String, BatchStatus)>,
+        service_id: &str,
+    ) -> Result<(), BatchTrackingStoreError> {
+        self.retrying_transaction("transition_batches", || {
+            for (id, status) in &transitions {
+                let batch_id: Option<String> = if is_data_change_id(id)? {
+                    self.resolve_data_change_id(id, service_id)?
+                } else {
+                    batches::table
+                        .select(batches::batch_id)
+                        .filter(
+                            batches::batch_id
+                                .eq(&id)
+                                .and(batches::service_id.eq(&service_id)),
+                        )
+                        .first(self.conn)
+                        .optional()?
+                };
+                let batch_id = batch_id.ok_or_else(|| {
+                    BatchTrackingStoreError::NotFoundError(format!(
+                        "Could not find batch with ID {}",
+                        id
+                    ))
+                })?;
+
+                // The current status is read within the transaction, so a
+                // batch that appears more than once is checked against the
+                // status set by its previous transition
+                let from = batch_statuses::table
+                    .select(batch_statuses::dlt_status)
+                    .filter(
+                        batch_statuses::batch_id
+                            .eq(&batch_id)
+                            .and(batch_statuses::service_id.eq(&service_id)),
+                    )
+                    .first::<String>(self.conn)
+                    .optional()?
+                    .map(|from| BatchStatusName::try_from_string(&from))
+                    .transpose()?;
+                let to = BatchStatusName::try_from_string(&status.to_string())?;
+
+                if !from.map(|from| from.can_transition_to(to)).unwrap_or(true) {
+                    return Err(BatchTrackingStoreError::InvalidStatusTransition {
+                        batch_id,
+                        from,
+                        to,
+                    });
+                }
+
+                self.update_batch_status(
+                    &batch_id,
+                    service_id,
+                    Some(&to.to_string()),
+                    Vec::new(),
+                    None,
+                )?;
+            }
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreTransitionBatchesOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn transition_batches(
+        &self,
+        transitions: Vec<(String, BatchStatus)>,
+        service_id: &str,
+    ) -> Result<(), BatchTrackingStoreError> {
+        self.transaction("transition_batches", || {
+            for (id, status) in &transitions {
+                let batch_id: Option<String> = if is_data_change_id(id)? {
+                    self.resolve_data_change_id(id, service_id)?
+                } else {
+                    batches::table
+                        .select(batches::batch_id)
+                        .filter(
+                            batches::batch_id
+                                .eq(&id)
+                                .and(batches::service_id.eq(&service_id)),
+                        )
+                        .first(self.conn)
+                        .optional()?
+                };
+                let batch_id = batch_id.ok_or_else(|| {
+                    BatchTrackingStoreError::NotFoundError(format!(
+                        "Could not find batch with ID {}",
+                        id
+                    ))
+                })?;
+
+                // The current status is read within the transaction, so a
+                // batch that appears more than once is checked against the
+                // status set by its previous transition
+                let from = batch_statuses::table
+                    .select(batch_statuses::dlt_status)
+                    .filter(
+                        batch_statuses::batch_id
+                            .eq(&batch_id)
+                            .and(batch_statuses::service_id.eq(&service_id)),
+                    )
+                    .first::<String>(self.conn)
+                    .optional()?
+                    .map(|from| BatchStatusName::try_from_string(&from))
+                    .transpose()?;
+                let to = BatchStatusName::try_from_string(&status.to_string())?;
+
+                if !from.map(|from| from.can_transition_to(to)).unwrap_or(true) {
+                    return Err(BatchTrackingStoreError::InvalidStatusTransition {
+                        batch_id,
+                        from,
+                        to,
+                    });
+                }
+
+                self.update_batch_status(
+                    &batch_id,
+                    service_id,
+                    Some(&to.to_string()),
+                    Vec::new(),
+                    None,
+                )?;
+            }
+
+            Ok(())
+        })
+    }
+}
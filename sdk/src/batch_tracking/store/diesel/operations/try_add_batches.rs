@@ -0,0 +1,73 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{add_batches::BatchTrackingStoreAddBatchesOperation, BatchTrackingStoreOperations};
+
+use crate::batch_tracking::store::{BatchTrackingStoreError, TrackingBatch, TryAddBatchesOutcomes};
+
+pub(in crate::batch_tracking::store::diesel) trait BatchTrackingStoreTryAddBatchesOperation {
+    fn try_add_batches(
+        &self,
+        batches: Vec<TrackingBatch>,
+        ignore_duplicates: bool,
+    ) -> Result<TryAddBatchesOutcomes, BatchTrackingStoreError>;
+}
+
+#[cfg(feature = "postgres")]
+impl<'a> BatchTrackingStoreTryAddBatchesOperation
+    for BatchTrackingStoreOperations<'a, diesel::pg::PgConnection>
+{
+    fn try_add_batches(
+        &self,
+        batches: Vec<TrackingBatch>,
+        ignore_duplicates: bool,
+    ) -> Result<TryAddBatchesOutcomes, BatchTrackingStoreError> {
+        self.transaction("try_add_batches", || {
+            // Each batch is added in its own savepoint, so a batch that fails
+            // is rolled back without undoing the batches added before it
+            Ok(batches
+                .into_iter()
+                .map(|batch| {
+                    let batch_id = batch.batch_header().to_string();
+                    let outcome = self.add_batches(vec![batch], ignore_duplicates);
+                    (batch_id, outcome)
+                })
+                .collect())
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl<'a> BatchTrackingStoreTryAddBatchesOperation
+    for BatchTrackingStoreOperations<'a, diesel::sqlite::SqliteConnection>
+{
+    fn try_add_batches(
+        &self,
+        batches: Vec<TrackingBatch>,
+        ignore_duplicates: bool,
+    ) -> Result<TryAddBatchesOutcomes, BatchTrackingStoreError> {
+        self.transaction("try_add_batches", || {
+            // Each batch is added in its own savepoint, so a batch that fails
+            // is rolled back without undoing the batches added before it
+            Ok(batches
+                .into_iter()
+                .map(|batch| {
+                    let batch_id = batch.batch_header().to_string();
+                    let outcome = self.add_batches(vec![batch], ignore_duplicates);
+                    (batch_id, outcome)
+                })
+                .collect())
+        })
+    }
+}
@@ -12,7 +12,11 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use super::BatchTrackingStoreOperations;
+use super::{
+    record_status_event::BatchTrackingStoreRecordStatusEventOperation,
+    resolve_data_change_id::BatchTrackingStoreResolveDataChangeIdOperation,
+    BatchTrackingStoreOperations,
+};
 
 use crate::batch_tracking::store::{
     diesel::{
@@ -24,6 +28,8 @@ use crate::batch_tracking::store::{
     BatchStatusName, BatchTrackingStoreError, SubmissionError,
 };
 
+use crate::error::InvalidArgumentError;
+
 use diesel::{
     dsl::{exists, insert_into, update},
     prelude::*,
@@ -53,18 +59,28 @@ impl<'a> BatchTrackingStoreUpdateBatchStatusOperation
         txn_receipts: Vec<TransactionReceiptModel>,
         submission_error: Option<SubmissionError>,
     ) -> Result<(), BatchTrackingStoreError> {
-        self.conn.transaction::<_, BatchTrackingStoreError, _>(|| {
+        // Receipts without a status are ambiguous; storing receipts alone
+        // must be done explicitly with `store_receipts_only`
+        if status.is_none() && !txn_receipts.is_empty() {
+            return Err(BatchTrackingStoreError::InvalidArgumentError(
+                InvalidArgumentError::new(
+                    "transaction_receipts".to_string(),
+                    "receipts can not be stored without a status, use \
+                    store_receipts_only instead"
+                        .to_string(),
+                ),
+            ));
+        }
+
+        self.transaction("update_batch_status", || {
+            let now = self.now()?;
+
             let mut batch_id = id.to_string();
             let is_dcid = is_data_change_id(id)?;
             if is_dcid {
-                batch_id = batches::table
-                    .select(batches::batch_id)
-                    .filter(
-                        batches::data_change_id
-                            .eq(&id)
-                            .and(batches::service_id.eq(&service_id)),
-                    )
-                    .first::<String>(self.conn)?;
+                batch_id = self
+                    .resolve_data_change_id(id, service_id)?
+                    .ok_or(diesel::result::Error::NotFound)?;
             }
 
             if let Some(batch_status) = status {
@@ -93,6 +109,35 @@ impl<'a> BatchTrackingStoreUpdateBatchStatusOperation
                             .set(batches::submitted.eq(false))
                             .execute(self.conn)?;
                     }
+                    // A dead-lettered batch is left as submitted or not, as
+                    // it is no longer sent to the DLT either way
+                    BatchStatusName::DeadLettered => (),
+                }
+
+                if matches!(
+                    status_string,
+                    BatchStatusName::Invalid | BatchStatusName::Committed
+                ) {
+                    // The latency is measured to the first time the batch reaches a
+                    // terminal status
+                    let created_at: i64 = batches::table
+                        .select(batches::created_at)
+                        .filter(
+                            batches::batch_id
+                                .eq(&batch_id)
+                                .and(batches::service_id.eq(&service_id)),
+                        )
+                        .first(self.conn)?;
+
+                    update(batches::table)
+                        .filter(
+                            batches::batch_id
+                                .eq(&batch_id)
+                                .and(batches::service_id.eq(&service_id))
+                                .and(batches::submission_latency_ms.is_null()),
+                        )
+                        .set(batches::submission_latency_ms.eq(self.to_millis(now - created_at)))
+                        .execute(self.conn)?;
                 }
 
                 let status_exists: bool = select(exists(
@@ -111,7 +156,10 @@ impl<'a> BatchTrackingStoreUpdateBatchStatusOperation
                                 .eq(&batch_id)
                                 .and(batch_statuses::service_id.eq(&service_id)),
                         )
-                        .set(batch_statuses::dlt_status.eq(&batch_status))
+                        .set((
+                            batch_statuses::dlt_status.eq(&batch_status),
+                            batch_statuses::updated_at.eq(now),
+                        ))
                         .execute(self.conn)?;
                 } else {
                     let model = NewBatchStatusModel {
@@ -121,9 +169,15 @@ impl<'a> BatchTrackingStoreUpdateBatchStatusOperation
                     };
 
                     insert_into(batch_statuses::table)
-                        .values(model)
+                        .values((
+                            model,
+                            batch_statuses::created_at.eq(now),
+                            batch_statuses::updated_at.eq(now),
+                        ))
                         .execute(self.conn)?;
                 };
+
+                self.record_status_event(&batch_id, service_id, batch_status, now)?;
             } else {
                 update(batches::table)
                     .filter(
@@ -186,11 +240,20 @@ impl<'a> BatchTrackingStoreUpdateBatchStatusOperation
                                 .eq(&model.batch_id)
                                 .and(submissions::service_id.eq(&model.service_id)),
                         )
-                        .set(&model)
+                        .set((
+                            &model,
+                            submissions::updated_at.eq(now),
+                            submissions::last_checked.eq(now),
+                        ))
                         .execute(self.conn)?;
                 } else {
                     insert_into(submissions::table)
-                        .values(&model)
+                        .values((
+                            &model,
+                            submissions::created_at.eq(now),
+                            submissions::updated_at.eq(now),
+                            submissions::last_checked.eq(now),
+                        ))
                         .execute(self.conn)?;
                 }
             }
@@ -212,18 +275,28 @@ impl<'a> BatchTrackingStoreUpdateBatchStatusOperation
         txn_receipts: Vec<TransactionReceiptModel>,
         submission_error: Option<SubmissionError>,
     ) -> Result<(), BatchTrackingStoreError> {
-        self.conn.transaction::<_, BatchTrackingStoreError, _>(|| {
+        // Receipts without a status are ambiguous; storing receipts alone
+        // must be done explicitly with `store_receipts_only`
+        if status.is_none() && !txn_receipts.is_empty() {
+            return Err(BatchTrackingStoreError::InvalidArgumentError(
+                InvalidArgumentError::new(
+                    "transaction_receipts".to_string(),
+                    "receipts can not be stored without a status, use \
+                    store_receipts_only instead"
+                        .to_string(),
+                ),
+            ));
+        }
+
+        self.transaction("update_batch_status", || {
+            let now = self.now()?;
+
             let mut batch_id = id.to_string();
             let is_dcid = is_data_change_id(id)?;
             if is_dcid {
-                batch_id = batches::table
-                    .select(batches::batch_id)
-                    .filter(
-                        batches::data_change_id
-                            .eq(&id)
-                            .and(batches::service_id.eq(&service_id)),
-                    )
-                    .first::<String>(self.conn)?;
+                batch_id = self
+                    .resolve_data_change_id(id, service_id)?
+                    .ok_or(diesel::result::Error::NotFound)?;
             }
 
             if let Some(batch_status) = status {
@@ -252,6 +325,35 @@ impl<'a> BatchTrackingStoreUpdateBatchStatusOperation
                             .set(batches::submitted.eq(false))
                             .execute(self.conn)?;
                     }
+                    // A dead-lettered batch is left as submitted or not, as
+                    // it is no longer sent to the DLT either way
+                    BatchStatusName::DeadLettered => (),
+                }
+
+                if matches!(
+                    status_string,
+                    BatchStatusName::Invalid | BatchStatusName::Committed
+                ) {
+                    // The latency is measured to the first time the batch reaches a
+                    // terminal status
+                    let created_at: i64 = batches::table
+                        .select(batches::created_at)
+                        .filter(
+                            batches::batch_id
+                                .eq(&batch_id)
+                                .and(batches::service_id.eq(&service_id)),
+                        )
+                        .first(self.conn)?;
+
+                    update(batches::table)
+                        .filter(
+                            batches::batch_id
+                                .eq(&batch_id)
+                                .and(batches::service_id.eq(&service_id))
+                                .and(batches::submission_latency_ms.is_null()),
+                        )
+                        .set(batches::submission_latency_ms.eq(self.to_millis(now - created_at)))
+                        .execute(self.conn)?;
                 }
 
                 let status_exists: bool = select(exists(
@@ -270,7 +372,10 @@ impl<'a> BatchTrackingStoreUpdateBatchStatusOperation
                                 .eq(&batch_id)
                                 .and(batch_statuses::service_id.eq(&service_id)),
                         )
-                        .set(batch_statuses::dlt_status.eq(&batch_status))
+                        .set((
+                            batch_statuses::dlt_status.eq(&batch_status),
+                            batch_statuses::updated_at.eq(now),
+                        ))
                         .execute(self.conn)?;
                 } else {
                     let model = NewBatchStatusModel {
@@ -280,9 +385,15 @@ impl<'a> BatchTrackingStoreUpdateBatchStatusOperation
                     };
 
                     insert_into(batch_statuses::table)
-                        .values(model)
+                        .values((
+                            model,
+                            batch_statuses::created_at.eq(now),
+                            batch_statuses::updated_at.eq(now),
+                        ))
                         .execute(self.conn)?;
                 };
+
+                self.record_status_event(&batch_id, service_id, batch_status, now)?;
             } else {
                 update(batches::table)
                     .filter(
@@ -345,11 +456,20 @@ impl<'a> BatchTrackingStoreUpdateBatchStatusOperation
                                 .eq(&model.batch_id)
                                 .and(submissions::service_id.eq(&model.service_id)),
                         )
-                        .set(&model)
+                        .set((
+                            &model,
+                            submissions::updated_at.eq(now),
+                            submissions::last_checked.eq(now),
+                        ))
                         .execute(self.conn)?;
                 } else {
                     insert_into(submissions::table)
-                        .values(&model)
+                        .values((
+                            &model,
+                            submissions::created_at.eq(now),
+                            submissions::updated_at.eq(now),
+                            submissions::last_checked.eq(now),
+                        ))
                         .execute(self.conn)?;
                 }
             }
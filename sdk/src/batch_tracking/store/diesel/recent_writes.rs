@@ -0,0 +1,86 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tracking of recently written batches, so that reads of them can be sent to
+//! the primary database until a replica has caught up.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::batch_tracking::store::{TrackingBatch, NON_SPLINTER_SERVICE_ID_DEFAULT};
+
+/// The batches written within a window, keyed by service ID and by batch ID
+/// or data change ID
+pub(super) struct RecentWrites {
+    window: Duration,
+    written: Mutex<HashMap<(String, String), Instant>>,
+}
+
+impl RecentWrites {
+    pub fn new(window: Duration) -> Self {
+        RecentWrites {
+            window,
+            written: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Records that the batches with the given service IDs and IDs were
+    /// written just now
+    pub fn record(&self, ids: impl IntoIterator<Item = (String, String)>) {
+        let mut written = match self.written.lock() {
+            Ok(written) => written,
+            Err(_) => return,
+        };
+
+        let now = Instant::now();
+        // Expired entries are dropped here so the map only holds the batches
+        // written within the window
+        written.retain(|_, written_at| now.duration_since(*written_at) < self.window);
+
+        for id in ids {
+            written.insert(id, now);
+        }
+    }
+
+    /// Returns true if the batch was written within the window
+    pub fn contains(&self, service_id: &str, id: &str) -> bool {
+        let written = match self.written.lock() {
+            Ok(written) => written,
+            // Reading from the primary is always consistent
+            Err(_) => return true,
+        };
+
+        written
+            .get(&(service_id.to_string(), id.to_string()))
+            .map(|written_at| written_at.elapsed() < self.window)
+            .unwrap_or(false)
+    }
+}
+
+/// Returns the service IDs and IDs a batch can be read by, for each of the
+/// given batches
+pub(super) fn written_batch_ids(batches: &[TrackingBatch]) -> Vec<(String, String)> {
+    let mut ids = Vec::new();
+    for batch in batches {
+        let service_id = batch
+            .service_id()
+            .unwrap_or(NON_SPLINTER_SERVICE_ID_DEFAULT);
+        ids.push((service_id.to_string(), batch.batch_header().to_string()));
+        if let Some(data_change_id) = batch.data_change_id() {
+            ids.push((service_id.to_string(), data_change_id.to_string()));
+        }
+    }
+    ids
+}
@@ -23,6 +23,28 @@ table! {
 }
 
 table! {
+    batch_status_events (id) {
+        id -> Int8,
+        service_id -> Text,
+        batch_id -> Text,
+        dlt_status -> Text,
+        correlation_id -> Nullable<Text>,
+        created_at -> Int8,
+    }
+}
+
+table! {
+    batch_tombstones (service_id, batch_id) {
+        service_id -> Text,
+        batch_id -> Text,
+        created_at -> Int8,
+    }
+}
+
+table! {
+    use diesel::sql_types::*;
+    use crate::batch_tracking::store::diesel::models::JsonObject;
+
     batches (service_id, batch_id) {
         service_id -> Text,
         batch_id -> Text,
@@ -32,10 +54,36 @@ table! {
         serialized_batch -> Binary,
         submitted -> Bool,
         created_at -> Int8,
+        submission_latency_ms -> Nullable<Int8>,
+        notes -> Nullable<Text>,
+        submission_round -> Nullable<Int8>,
+        byte_size -> Int8,
+        network_id -> Nullable<Text>,
+        alias -> Nullable<Text>,
+        batch_kind -> Nullable<Text>,
+        metadata -> Nullable<JsonObject>,
+        dead_letter_reason -> Nullable<Text>,
+        origin -> Nullable<Text>,
+        claimed_by -> Nullable<Text>,
+        claim_expires_at -> Nullable<Int8>,
     }
 }
 
 table! {
+    outbox (id) {
+        id -> Int8,
+        service_id -> Text,
+        batch_id -> Text,
+        dlt_status -> Text,
+        correlation_id -> Nullable<Text>,
+        created_at -> Int8,
+    }
+}
+
+table! {
+    use diesel::sql_types::*;
+    use crate::batch_tracking::store::diesel::models::JsonObject;
+
     submissions (service_id, batch_id) {
         service_id -> Text,
         batch_id -> Text,
@@ -45,6 +93,29 @@ table! {
         error_message -> Nullable<Text>,
         created_at -> Int8,
         updated_at -> Int8,
+        submitter_response -> Nullable<Binary>,
+        submit_headers -> Nullable<JsonObject>,
+        submit_url -> Nullable<Text>,
+        attempts -> Int8,
+    }
+}
+
+table! {
+    transaction_addresses (service_id, transaction_id, is_input, position) {
+        service_id -> Text,
+        transaction_id -> Text,
+        is_input -> Bool,
+        position -> Integer,
+        address -> Text,
+    }
+}
+
+table! {
+    transaction_dependencies (service_id, transaction_id, position) {
+        service_id -> Text,
+        transaction_id -> Text,
+        position -> Integer,
+        dependency_id -> Text,
     }
 }
 
@@ -55,7 +126,7 @@ table! {
         result_valid -> Bool,
         error_message -> Nullable<Text>,
         error_data -> Nullable<Binary>,
-        serialized_receipt -> Binary,
+        serialized_receipt -> Nullable<Binary>,
         external_status -> Nullable<Text>,
         external_error_message -> Nullable<Text>,
     }
@@ -70,13 +141,19 @@ table! {
         family_name -> Text,
         family_version -> Text,
         signer_public_key -> Text,
+        serialized_header -> Nullable<Binary>,
+        payload_encrypted -> Bool,
     }
 }
 
 allow_tables_to_appear_in_same_query!(
+    batch_status_events,
     batch_statuses,
+    batch_tombstones,
     batches,
     submissions,
+    transaction_addresses,
+    transaction_dependencies,
     transaction_receipts,
     transactions,
 );
@@ -17,15 +17,62 @@ use std::fmt;
 
 #[cfg(feature = "diesel")]
 use crate::error::ConstraintViolationType;
-use crate::error::{ConstraintViolationError, InternalError, ResourceTemporarilyUnavailableError};
+use crate::error::{
+    ConstraintViolationError, InternalError, InvalidArgumentError,
+    ResourceTemporarilyUnavailableError,
+};
+
+use super::{BatchMismatch, BatchStatusName};
 
 /// Represents Store errors
 #[derive(Debug)]
 pub enum BatchTrackingStoreError {
     InternalError(InternalError),
     ConstraintViolationError(ConstraintViolationError),
+    InvalidArgumentError(InvalidArgumentError),
     ResourceTemporarilyUnavailableError(ResourceTemporarilyUnavailableError),
     NotFoundError(String),
+    Tombstoned(String),
+    /// A batch's data change ID is the ID of another batch in the same
+    /// service, or its ID is another batch's data change ID
+    IdCollision(String),
+    /// A write was made to a service that is not on the store's list of
+    /// allowed service IDs
+    ServiceNotAllowed {
+        service_id: String,
+    },
+    /// A batch's ID was rejected by the store's batch ID validator
+    InvalidBatchId {
+        batch_id: String,
+        reason: String,
+    },
+    /// A batch's status can't be moved from its current status, if it has
+    /// one, to the requested status
+    InvalidStatusTransition {
+        batch_id: String,
+        from: Option<BatchStatusName>,
+        to: BatchStatusName,
+    },
+    /// A data change ID used to look up a batch matches more than one batch
+    AmbiguousDataChangeId {
+        dcid: String,
+        count: i64,
+    },
+}
+
+impl BatchTrackingStoreError {
+    /// Prefixes an internal error with the name of the store operation that
+    /// raised it, so that it reads like "add_batches: <source>"
+    ///
+    /// Errors other than `InternalError` are returned unchanged.
+    pub(crate) fn with_operation(self, operation: &str) -> Self {
+        match self {
+            BatchTrackingStoreError::InternalError(err) => BatchTrackingStoreError::InternalError(
+                InternalError::from_source_with_prefix(Box::new(err), operation.to_string()),
+            ),
+            err => err,
+        }
+    }
 }
 
 impl Error for BatchTrackingStoreError {
@@ -33,8 +80,15 @@ impl Error for BatchTrackingStoreError {
         match self {
             BatchTrackingStoreError::InternalError(err) => Some(err),
             BatchTrackingStoreError::ConstraintViolationError(err) => Some(err),
+            BatchTrackingStoreError::InvalidArgumentError(err) => Some(err),
             BatchTrackingStoreError::ResourceTemporarilyUnavailableError(err) => Some(err),
             BatchTrackingStoreError::NotFoundError(_) => None,
+            BatchTrackingStoreError::Tombstoned(_) => None,
+            BatchTrackingStoreError::IdCollision(_) => None,
+            BatchTrackingStoreError::ServiceNotAllowed { .. } => None,
+            BatchTrackingStoreError::InvalidBatchId { .. } => None,
+            BatchTrackingStoreError::InvalidStatusTransition { .. } => None,
+            BatchTrackingStoreError::AmbiguousDataChangeId { .. } => None,
         }
     }
 }
@@ -44,8 +98,38 @@ impl fmt::Display for BatchTrackingStoreError {
         match self {
             BatchTrackingStoreError::InternalError(err) => err.fmt(f),
             BatchTrackingStoreError::ConstraintViolationError(err) => err.fmt(f),
+            BatchTrackingStoreError::InvalidArgumentError(err) => err.fmt(f),
             BatchTrackingStoreError::ResourceTemporarilyUnavailableError(err) => err.fmt(f),
             BatchTrackingStoreError::NotFoundError(ref s) => write!(f, "Element not found: {}", s),
+            BatchTrackingStoreError::Tombstoned(ref s) => {
+                write!(f, "Batch has been tombstoned: {}", s)
+            }
+            BatchTrackingStoreError::IdCollision(ref s) => {
+                write!(f, "Batch ID collides with a data change ID: {}", s)
+            }
+            BatchTrackingStoreError::ServiceNotAllowed { ref service_id } => {
+                write!(f, "Writes to service are not allowed: {}", service_id)
+            }
+            BatchTrackingStoreError::InvalidBatchId {
+                ref batch_id,
+                ref reason,
+            } => write!(f, "Invalid batch ID {}: {}", batch_id, reason),
+            BatchTrackingStoreError::InvalidStatusTransition {
+                ref batch_id,
+                ref from,
+                ref to,
+            } => match from {
+                Some(from) => write!(
+                    f,
+                    "Batch {} can not move from status {} to {}",
+                    batch_id, from, to
+                ),
+                None => write!(f, "Batch {} can not move to status {}", batch_id, to),
+            },
+            BatchTrackingStoreError::AmbiguousDataChangeId {
+                ref dcid,
+                ref count,
+            } => write!(f, "Data change ID {} matches {} batches", dcid, count),
         }
     }
 }
@@ -91,6 +175,8 @@ impl From<diesel::r2d2::PoolError> for BatchTrackingStoreError {
 pub enum BatchBuilderError {
     /// Returned when a required field was not set
     MissingRequiredField(String),
+    /// Returned when a field was set to a malformed value
+    InvalidField(String),
     /// Returned when an error occurs building the PO
     BuildError(Box<dyn Error>),
 }
@@ -99,6 +185,7 @@ impl Error for BatchBuilderError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
             BatchBuilderError::MissingRequiredField(_) => None,
+            BatchBuilderError::InvalidField(_) => None,
             BatchBuilderError::BuildError(err) => Some(&**err),
         }
     }
@@ -110,9 +197,84 @@ impl fmt::Display for BatchBuilderError {
             BatchBuilderError::MissingRequiredField(ref s) => {
                 write!(f, "Missing required field: {}", s)
             }
+            BatchBuilderError::InvalidField(ref s) => write!(f, "Invalid field: {}", s),
             BatchBuilderError::BuildError(ref s) => {
                 write!(f, "Failed to build purchase order object: {}", s)
             }
         }
     }
 }
+
+/// Returned when a `TrackingBatch` does not match the transact batch it is
+/// validated against
+#[derive(Debug, PartialEq, Eq)]
+pub struct ValidationError {
+    mismatches: Vec<BatchMismatch>,
+}
+
+impl ValidationError {
+    pub fn new(mismatches: Vec<BatchMismatch>) -> Self {
+        ValidationError { mismatches }
+    }
+
+    /// Every way in which the batches differ
+    pub fn mismatches(&self) -> &[BatchMismatch] {
+        &self.mismatches
+    }
+}
+
+impl Error for ValidationError {}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Tracking batch does not match batch: ")?;
+        for (i, mismatch) in self.mismatches.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{}", mismatch)?;
+        }
+        Ok(())
+    }
+}
+
+/// Represents errors raised while converting a `TrackingBatch` to or from
+/// its binary representation
+#[cfg(feature = "bincode")]
+#[derive(Debug)]
+pub enum TrackingBatchSerializationError {
+    /// Returned when there are no bytes to read a version header from
+    MissingVersion,
+    /// Returned when the version header is not a supported format version
+    UnsupportedVersion(u8),
+    /// Returned when the batch could not be encoded or decoded
+    BincodeError(bincode::Error),
+}
+
+#[cfg(feature = "bincode")]
+impl Error for TrackingBatchSerializationError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TrackingBatchSerializationError::MissingVersion => None,
+            TrackingBatchSerializationError::UnsupportedVersion(_) => None,
+            TrackingBatchSerializationError::BincodeError(err) => Some(err),
+        }
+    }
+}
+
+#[cfg(feature = "bincode")]
+impl fmt::Display for TrackingBatchSerializationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TrackingBatchSerializationError::MissingVersion => {
+                write!(f, "Serialized batch is missing a version header")
+            }
+            TrackingBatchSerializationError::UnsupportedVersion(ref version) => {
+                write!(f, "Unsupported serialized batch version: {}", version)
+            }
+            TrackingBatchSerializationError::BincodeError(ref err) => {
+                write!(f, "Failed to serialize batch: {}", err)
+            }
+        }
+    }
+}
@@ -0,0 +1,50 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Serde support for the JSON metadata of a `TrackingBatch`.
+//!
+//! Binary formats such as bincode can not deserialize an arbitrary JSON
+//! value, because they do not describe the types of the values they hold.
+//! For those formats the metadata is encoded as a JSON string instead.
+
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+
+pub(super) fn serialize<S>(metadata: &Option<Value>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    if serializer.is_human_readable() {
+        metadata.serialize(serializer)
+    } else {
+        metadata
+            .as_ref()
+            .map(Value::to_string)
+            .serialize(serializer)
+    }
+}
+
+pub(super) fn deserialize<'de, D>(deserializer: D) -> Result<Option<Value>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    if deserializer.is_human_readable() {
+        Option::<Value>::deserialize(deserializer)
+    } else {
+        Option::<String>::deserialize(deserializer)?
+            .map(|metadata| serde_json::from_str(&metadata).map_err(D::Error::custom))
+            .transpose()
+    }
+}
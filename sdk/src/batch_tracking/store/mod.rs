@@ -12,28 +12,62 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::fmt;
+use std::io::{Read, Write};
 
 use transact::protocol::{
-    batch::Batch,
+    batch::{Batch, BatchHeader},
     transaction::{Transaction, TransactionHeader},
 };
 use transact::protos::FromBytes;
 
 use crate::batch_tracking::store::diesel::models::is_data_change_id;
 use crate::error::{InternalError, InvalidArgumentError};
+use crate::hex::to_hex;
+use crate::paging::Paging;
 use crate::scope_id::{GlobalScopeId, ServiceScopeId};
 
+mod cipher;
 #[cfg(feature = "diesel")]
 pub(crate) mod diesel;
 mod error;
+mod metadata;
+mod retention;
+#[cfg(feature = "bincode")]
+mod serialization;
+mod verify;
+mod watch;
 
-pub use error::{BatchBuilderError, BatchTrackingStoreError};
+#[cfg(feature = "diesel")]
+pub use self::diesel::{
+    BatchIdValidator, DieselBatchTrackingStore, DieselConnectionBatchTrackingStore,
+};
+pub use cipher::PayloadCipher;
+#[cfg(feature = "bincode")]
+pub use error::TrackingBatchSerializationError;
+pub use error::{BatchBuilderError, BatchTrackingStoreError, ValidationError};
+pub use retention::{spawn_retention_task, RetentionPolicy, RetentionTaskHandle};
+pub use verify::{
+    validate_against_batch, verify_migration, BatchDifference, BatchMismatch, MigrationReport,
+};
+pub(crate) use watch::UnsubmittedWatchers;
+pub use watch::{UnsubmittedBatchReceiver, WatchBackpressure};
 
 const NON_SPLINTER_SERVICE_ID_DEFAULT: &str = "----";
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+/// The length of a hex-encoded compressed secp256k1 public key
+const SIGNER_PUBLIC_KEY_HEX_LENGTH: usize = 66;
+
+/// The number of batches read from the store at a time when exporting
+const NDJSON_EXPORT_PAGE_SIZE: i64 = 100;
+
+/// The number of batch IDs and statuses read from the store at a time by
+/// `IdStatusIter`
+const ID_STATUS_PAGE_SIZE: i64 = 500;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum BatchStatus {
     Unknown,
     Pending,
@@ -41,6 +75,9 @@ pub enum BatchStatus {
     Invalid(Vec<InvalidTransaction>),
     Valid(Vec<ValidTransaction>),
     Committed(Vec<ValidTransaction>),
+    /// The batch will not be retried, such as after it has exhausted its
+    /// retries
+    DeadLettered,
 }
 
 impl fmt::Display for BatchStatus {
@@ -52,10 +89,12 @@ impl fmt::Display for BatchStatus {
             BatchStatus::Invalid(_) => write!(f, "Invalid"),
             BatchStatus::Valid(_) => write!(f, "Valid"),
             BatchStatus::Committed(_) => write!(f, "Committed"),
+            BatchStatus::DeadLettered => write!(f, "DeadLettered"),
         }
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum BatchStatusName {
     Unknown,
     Pending,
@@ -63,6 +102,7 @@ pub enum BatchStatusName {
     Invalid,
     Valid,
     Committed,
+    DeadLettered,
 }
 
 impl BatchStatusName {
@@ -74,11 +114,29 @@ impl BatchStatusName {
             "Invalid" => Ok(BatchStatusName::Invalid),
             "Valid" => Ok(BatchStatusName::Valid),
             "Committed" => Ok(BatchStatusName::Committed),
+            "DeadLettered" => Ok(BatchStatusName::DeadLettered),
             _ => Err(BatchTrackingStoreError::InternalError(
                 InternalError::with_message(format!("Status {} is not valid", value)),
             )),
         }
     }
+
+    /// Returns true if no further status changes are expected for a batch
+    /// with this status
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            BatchStatusName::Invalid | BatchStatusName::Committed | BatchStatusName::DeadLettered
+        )
+    }
+
+    /// Returns true if a batch with this status may be moved to `next`
+    ///
+    /// A batch with a terminal status can only have that status set again;
+    /// any other status may be followed by any status.
+    pub fn can_transition_to(&self, next: BatchStatusName) -> bool {
+        !self.is_terminal() || *self == next
+    }
 }
 
 impl fmt::Display for BatchStatusName {
@@ -90,11 +148,12 @@ impl fmt::Display for BatchStatusName {
             BatchStatusName::Invalid => write!(f, "Invalid"),
             BatchStatusName::Valid => write!(f, "Valid"),
             BatchStatusName::Committed => write!(f, "Committed"),
+            BatchStatusName::DeadLettered => write!(f, "DeadLettered"),
         }
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct InvalidTransaction {
     transaction_id: String,
     // These are for errors from the DLT itself
@@ -128,7 +187,7 @@ impl InvalidTransaction {
     }
 }
 
-#[derive(Default)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct InvalidTransactionBuilder {
     transaction_id: String,
     error_message: Option<String>,
@@ -212,7 +271,7 @@ impl InvalidTransactionBuilder {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ValidTransaction {
     transaction_id: String,
 }
@@ -223,6 +282,7 @@ impl ValidTransaction {
     }
 }
 
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct ValidTransactionBuilder {
     transaction_id: String,
 }
@@ -246,7 +306,7 @@ impl ValidTransactionBuilder {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct SubmissionError {
     error_type: String,
     error_message: String,
@@ -262,7 +322,7 @@ impl SubmissionError {
     }
 }
 
-#[derive(Default)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct SubmissionErrorBuilder {
     error_type: String,
     error_message: String,
@@ -304,7 +364,7 @@ impl SubmissionErrorBuilder {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct TrackingBatch {
     service_id: Option<String>,
     batch_header: String,
@@ -314,6 +374,17 @@ pub struct TrackingBatch {
     serialized_batch: Vec<u8>,
     submitted: bool,
     created_at: i64,
+    submission_latency_ms: Option<i64>,
+    notes: Option<String>,
+    submission_round: Option<i64>,
+    byte_size: i64,
+    network_id: Option<String>,
+    alias: Option<String>,
+    batch_kind: Option<String>,
+    origin: Option<String>,
+    #[serde(with = "metadata")]
+    metadata: Option<serde_json::Value>,
+    dead_letter_reason: Option<String>,
     transactions: Vec<TrackingTransaction>,
     batch_status: Option<BatchStatus>,
     submission_error: Option<SubmissionError>,
@@ -348,10 +419,66 @@ impl TrackingBatch {
         self.submitted
     }
 
+    /// Returns the time the batch was added to the store, measured from the
+    /// Unix epoch in the store's `TimestampPrecision` (seconds by default)
     pub fn created_at(&self) -> i64 {
         self.created_at
     }
 
+    /// Returns the time in milliseconds between the batch being added to the
+    /// store and reaching a terminal status, if it has reached one
+    pub fn submission_latency_ms(&self) -> Option<i64> {
+        self.submission_latency_ms
+    }
+
+    /// Returns the note left on the batch by an operator, if any
+    pub fn notes(&self) -> Option<&str> {
+        self.notes.as_deref()
+    }
+
+    /// Returns the submission round in which the batch was submitted, if any
+    pub fn submission_round(&self) -> Option<i64> {
+        self.submission_round
+    }
+
+    /// Returns the size of the serialized batch in bytes
+    pub fn byte_size(&self) -> i64 {
+        self.byte_size
+    }
+
+    /// Returns the identifier of the DLT network the batch was submitted to,
+    /// if any
+    pub fn network_id(&self) -> Option<&str> {
+        self.network_id.as_deref()
+    }
+
+    /// Returns the human-readable alias an operator assigned to the batch, if
+    /// any
+    pub fn alias(&self) -> Option<&str> {
+        self.alias.as_deref()
+    }
+
+    /// Returns the kind of change the batch makes, such as a product update,
+    /// if one was given
+    pub fn batch_kind(&self) -> Option<&str> {
+        self.batch_kind.as_deref()
+    }
+
+    /// Returns the upstream system the batch came from, if one was given
+    pub fn origin(&self) -> Option<&str> {
+        self.origin.as_deref()
+    }
+
+    /// Returns the JSON object of metadata attached to the batch, if any
+    pub fn metadata(&self) -> Option<&serde_json::Value> {
+        self.metadata.as_ref()
+    }
+
+    /// Returns why the batch was dead-lettered, if it has been
+    pub fn dead_letter_reason(&self) -> Option<&str> {
+        self.dead_letter_reason.as_deref()
+    }
+
     pub fn transactions(&self) -> &[TrackingTransaction] {
         &self.transactions
     }
@@ -363,9 +490,63 @@ impl TrackingBatch {
     pub fn submission_error(&self) -> Option<&SubmissionError> {
         self.submission_error.as_ref()
     }
+
+    /// Reconstructs the transact batch the tracking batch was built from
+    ///
+    /// Returns an error if the serialized header of any of the batch's
+    /// transactions was not stored, which is the case for transactions added
+    /// before the headers were kept, or if any of the transactions listed in
+    /// the batch header are missing.
+    pub fn to_transact_batch(&self) -> Result<Batch, InternalError> {
+        let header = BatchHeader::from_bytes(&self.serialized_batch).map_err(|err| {
+            InternalError::with_message(format!(
+                "Could not convert batch header from bytes: {}",
+                err
+            ))
+        })?;
+
+        // The transactions are put back in the order listed in the header, as
+        // the order they were loaded from the store in is not guaranteed
+        let transactions = header
+            .transaction_ids()
+            .iter()
+            .map(|transaction_id| {
+                let transaction = self
+                    .transactions
+                    .iter()
+                    .find(|transaction| transaction.transaction_header() == transaction_id)
+                    .ok_or_else(|| {
+                        InternalError::with_message(format!(
+                            "Transaction {} of batch {} was not found",
+                            transaction_id, self.batch_header
+                        ))
+                    })?;
+
+                let header = transaction.serialized_header().ok_or_else(|| {
+                    InternalError::with_message(format!(
+                        "The header of transaction {} was not stored",
+                        transaction_id
+                    ))
+                })?;
+
+                Ok(Transaction::new(
+                    header.to_vec(),
+                    transaction_id.to_string(),
+                    transaction.payload().to_vec(),
+                ))
+            })
+            .collect::<Result<Vec<Transaction>, InternalError>>()?;
+
+        Ok(Batch::new(
+            self.serialized_batch.clone(),
+            self.batch_header.clone(),
+            transactions,
+            self.trace,
+        ))
+    }
 }
 
-#[derive(Default, Clone)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct TrackingBatchBuilder {
     service_id: String,
     batch: Option<Batch>,
@@ -373,6 +554,13 @@ pub struct TrackingBatchBuilder {
     signer_public_key: String,
     submitted: bool,
     created_at: i64,
+    submission_latency_ms: Option<i64>,
+    notes: Option<String>,
+    submission_round: Option<i64>,
+    network_id: Option<String>,
+    batch_kind: Option<String>,
+    origin: Option<String>,
+    metadata: Option<serde_json::Value>,
     batch_status: Option<BatchStatus>,
     submission_error: Option<SubmissionError>,
 }
@@ -408,6 +596,41 @@ impl TrackingBatchBuilder {
         self
     }
 
+    pub fn with_submission_latency_ms(mut self, submission_latency_ms: i64) -> Self {
+        self.submission_latency_ms = Some(submission_latency_ms);
+        self
+    }
+
+    pub fn with_notes(mut self, notes: String) -> Self {
+        self.notes = Some(notes);
+        self
+    }
+
+    pub fn with_submission_round(mut self, submission_round: i64) -> Self {
+        self.submission_round = Some(submission_round);
+        self
+    }
+
+    pub fn with_network_id(mut self, network_id: String) -> Self {
+        self.network_id = Some(network_id);
+        self
+    }
+
+    pub fn with_batch_kind(mut self, batch_kind: String) -> Self {
+        self.batch_kind = Some(batch_kind);
+        self
+    }
+
+    pub fn with_origin(mut self, origin: String) -> Self {
+        self.origin = Some(origin);
+        self
+    }
+
+    pub fn with_metadata(mut self, metadata: serde_json::Value) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
     pub fn with_batch_status(mut self, status: BatchStatus) -> Self {
         self.batch_status = Some(status);
         self
@@ -426,6 +649,13 @@ impl TrackingBatchBuilder {
             signer_public_key,
             submitted,
             created_at,
+            submission_latency_ms,
+            notes,
+            submission_round,
+            network_id,
+            batch_kind,
+            origin,
+            metadata,
             batch_status,
             submission_error,
         } = self;
@@ -488,6 +718,8 @@ impl TrackingBatchBuilder {
             ));
         };
 
+        validate_signer_public_key(&signer_public_key)?;
+
         if serialized_batch.is_empty() {
             return Err(BatchBuilderError::MissingRequiredField(
                 "serialized_batch".to_string(),
@@ -506,6 +738,17 @@ impl TrackingBatchBuilder {
             ));
         };
 
+        if let Some(metadata) = &metadata {
+            if !metadata.is_object() {
+                return Err(BatchBuilderError::InvalidField(format!(
+                    "metadata must be a JSON object, found {}",
+                    metadata
+                )));
+            }
+        }
+
+        let byte_size = serialized_batch.len() as i64;
+
         Ok(TrackingBatch {
             service_id: Some(serv_id),
             batch_header,
@@ -515,6 +758,16 @@ impl TrackingBatchBuilder {
             serialized_batch,
             submitted,
             created_at,
+            submission_latency_ms,
+            notes,
+            submission_round,
+            byte_size,
+            network_id,
+            alias: None,
+            batch_kind,
+            origin,
+            metadata,
+            dead_letter_reason: None,
             transactions,
             batch_status,
             submission_error,
@@ -522,12 +775,423 @@ impl TrackingBatchBuilder {
     }
 }
 
+/// Checks that a signer public key is a hex-encoded public key, so that
+/// consumers can parse it
+fn validate_signer_public_key(signer_public_key: &str) -> Result<(), BatchBuilderError> {
+    if !signer_public_key.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(BatchBuilderError::InvalidField(format!(
+            "signer_public_key contains non-hex characters: {}",
+            signer_public_key
+        )));
+    }
+
+    if signer_public_key.len() % 2 == 1 {
+        return Err(BatchBuilderError::InvalidField(format!(
+            "signer_public_key has an odd number of hex digits: {}",
+            signer_public_key
+        )));
+    }
+
+    if signer_public_key.len() != SIGNER_PUBLIC_KEY_HEX_LENGTH {
+        return Err(BatchBuilderError::InvalidField(format!(
+            "signer_public_key must be {} hex digits, found {}",
+            SIGNER_PUBLIC_KEY_HEX_LENGTH,
+            signer_public_key.len()
+        )));
+    }
+
+    Ok(())
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct TrackingBatchList {
     pub batches: Vec<TrackingBatch>,
 }
 
+impl TrackingBatchList {
+    /// Returns the batches as CSV, with a header row and one row per batch
+    ///
+    /// The columns are the batch ID, service ID, status, whether the batch
+    /// was submitted, its creation time and its data change ID. A batch with
+    /// no status or data change ID has an empty value in that column.
+    pub fn to_csv(&self) -> String {
+        let mut csv =
+            String::from("batch_id,service_id,status,submitted,created_at,data_change_id\n");
+
+        for batch in &self.batches {
+            let status = batch
+                .batch_status()
+                .map(|status| status.to_string())
+                .unwrap_or_default();
+
+            let row = [
+                batch.batch_header().to_string(),
+                batch.service_id().unwrap_or_default().to_string(),
+                status,
+                batch.submitted().to_string(),
+                batch.created_at().to_string(),
+                batch.data_change_id().unwrap_or_default().to_string(),
+            ];
+
+            csv.push_str(
+                &row.iter()
+                    .map(|field| escape_csv_field(field))
+                    .collect::<Vec<_>>()
+                    .join(","),
+            );
+            csv.push('\n');
+        }
+
+        csv
+    }
+}
+
+/// Quotes a CSV field if it contains a comma, quote or line break, doubling
+/// any quotes in it
+fn escape_csv_field(field: &str) -> String {
+    if field.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// A page of batches along with the paging information for the full list
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TrackingBatchPage {
+    pub batches: Vec<TrackingBatch>,
+    pub paging: Paging,
+}
+
+impl TrackingBatchPage {
+    pub fn new(batches: Vec<TrackingBatch>, paging: Paging) -> Self {
+        Self { batches, paging }
+    }
+}
+
+/// The state of a store's database connection pool
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PoolState {
+    connections: u32,
+    idle_connections: u32,
+    max_size: u32,
+}
+
+impl PoolState {
+    pub fn new(connections: u32, idle_connections: u32, max_size: u32) -> Self {
+        PoolState {
+            connections,
+            idle_connections,
+            max_size,
+        }
+    }
+
+    /// The number of connections currently open, both idle and in use
+    pub fn connections(&self) -> u32 {
+        self.connections
+    }
+
+    /// The number of open connections that are not in use
+    pub fn idle_connections(&self) -> u32 {
+        self.idle_connections
+    }
+
+    /// The maximum number of connections the pool will open
+    pub fn max_size(&self) -> u32 {
+        self.max_size
+    }
+}
+
+/// Counts of a service's batches used to judge the health of its submissions
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HealthSnapshot {
+    total: i64,
+    failed: i64,
+    stuck_pending: i64,
+    unsubmitted: i64,
+}
+
+impl HealthSnapshot {
+    pub fn new(total: i64, failed: i64, stuck_pending: i64, unsubmitted: i64) -> Self {
+        HealthSnapshot {
+            total,
+            failed,
+            stuck_pending,
+            unsubmitted,
+        }
+    }
+
+    /// The number of batches the service has
+    pub fn total(&self) -> i64 {
+        self.total
+    }
+
+    /// The number of batches whose status is `Invalid` or `DeadLettered`
+    ///
+    /// This is not the set `get_failed_batches` returns, which is `Unknown`
+    /// and `Invalid`. Batches with an `Unknown` status are resubmitted, so
+    /// they are not counted as failed here; dead-lettered batches will never
+    /// be resubmitted, so they are.
+    pub fn failed(&self) -> i64 {
+        self.failed
+    }
+
+    /// The number of batches that have been pending since before the stuck
+    /// threshold
+    pub fn stuck_pending(&self) -> i64 {
+        self.stuck_pending
+    }
+
+    /// The number of batches that have not been submitted and have not been
+    /// dead-lettered
+    pub fn unsubmitted(&self) -> i64 {
+        self.unsubmitted
+    }
+}
+
+/// Selects the parts of a batch that `get_batch_projected` loads
+///
+/// Parts that are not selected are left empty in the returned batch. The
+/// receipts of a batch's transactions are returned in its status, so they
+/// are only loaded when the status is also selected.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BatchProjection {
+    include_transactions: bool,
+    include_receipts: bool,
+    include_status: bool,
+    include_submission_error: bool,
+}
+
+impl BatchProjection {
+    /// Creates a projection that selects only the batch itself
+    pub fn new() -> Self {
+        BatchProjection::default()
+    }
+
+    /// Creates a projection that selects every part of the batch
+    pub fn all() -> Self {
+        BatchProjection {
+            include_transactions: true,
+            include_receipts: true,
+            include_status: true,
+            include_submission_error: true,
+        }
+    }
+
+    pub fn with_transactions(mut self, include_transactions: bool) -> Self {
+        self.include_transactions = include_transactions;
+        self
+    }
+
+    pub fn with_receipts(mut self, include_receipts: bool) -> Self {
+        self.include_receipts = include_receipts;
+        self
+    }
+
+    pub fn with_status(mut self, include_status: bool) -> Self {
+        self.include_status = include_status;
+        self
+    }
+
+    pub fn with_submission_error(mut self, include_submission_error: bool) -> Self {
+        self.include_submission_error = include_submission_error;
+        self
+    }
+
+    pub fn include_transactions(&self) -> bool {
+        self.include_transactions
+    }
+
+    pub fn include_receipts(&self) -> bool {
+        self.include_receipts
+    }
+
+    pub fn include_status(&self) -> bool {
+        self.include_status
+    }
+
+    pub fn include_submission_error(&self) -> bool {
+        self.include_submission_error
+    }
+}
+
+/// The submission record for a batch
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct BatchSubmissionInfo {
+    last_checked: i64,
+    times_checked: i64,
+    submission_error: Option<SubmissionError>,
+    submitter_response: Option<Vec<u8>>,
+    submit_headers: Option<serde_json::Value>,
+    submit_url: Option<String>,
+}
+
+impl BatchSubmissionInfo {
+    pub fn last_checked(&self) -> i64 {
+        self.last_checked
+    }
+
+    pub fn times_checked(&self) -> i64 {
+        self.times_checked
+    }
+
+    pub fn submission_error(&self) -> Option<&SubmissionError> {
+        self.submission_error.as_ref()
+    }
+
+    pub fn submitter_response(&self) -> Option<&[u8]> {
+        self.submitter_response.as_deref()
+    }
+
+    /// Returns the headers sent with the submission, if they were retained
+    pub fn submit_headers(&self) -> Option<&serde_json::Value> {
+        self.submit_headers.as_ref()
+    }
+
+    /// Returns the URL the batch was submitted to, if it was recorded
+    pub fn submit_url(&self) -> Option<&str> {
+        self.submit_url.as_deref()
+    }
+}
+
+/// A record of a batch's status being set
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct BatchStatusEvent {
+    service_id: String,
+    batch_id: String,
+    status: BatchStatusName,
+    correlation_id: Option<String>,
+    created_at: i64,
+}
+
+impl BatchStatusEvent {
+    pub fn service_id(&self) -> &str {
+        &self.service_id
+    }
+
+    pub fn batch_id(&self) -> &str {
+        &self.batch_id
+    }
+
+    pub fn status(&self) -> BatchStatusName {
+        self.status
+    }
+
+    /// Returns the correlation ID bound to the store call that set the
+    /// status, if any
+    pub fn correlation_id(&self) -> Option<&str> {
+        self.correlation_id.as_deref()
+    }
+
+    /// Returns the time the status was set, in the store's
+    /// `TimestampPrecision`
+    pub fn created_at(&self) -> i64 {
+        self.created_at
+    }
+}
+
+/// A status event waiting in the outbox to be relayed
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct OutboxEvent {
+    id: i64,
+    event: BatchStatusEvent,
+}
+
+impl OutboxEvent {
+    /// Returns the ID used to acknowledge the event
+    pub fn id(&self) -> i64 {
+        self.id
+    }
+
+    pub fn event(&self) -> &BatchStatusEvent {
+        &self.event
+    }
+}
+
+/// A failed batch bundled with the errors that caused it to fail
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FailedBatchDetail {
+    batch: TrackingBatch,
+    submission_error: Option<SubmissionError>,
+    invalid_transactions: Vec<InvalidTransaction>,
+}
+
+impl FailedBatchDetail {
+    pub fn batch(&self) -> &TrackingBatch {
+        &self.batch
+    }
+
+    pub fn submission_error(&self) -> Option<&SubmissionError> {
+        self.submission_error.as_ref()
+    }
+
+    pub fn invalid_transactions(&self) -> &[InvalidTransaction] {
+        &self.invalid_transactions
+    }
+}
+
+impl From<TrackingBatch> for FailedBatchDetail {
+    fn from(batch: TrackingBatch) -> Self {
+        let submission_error = batch.submission_error.clone();
+        let invalid_transactions = match &batch.batch_status {
+            Some(BatchStatus::Invalid(txns)) => txns.clone(),
+            _ => Vec::new(),
+        };
+
+        Self {
+            batch,
+            submission_error,
+            invalid_transactions,
+        }
+    }
+}
+
+/// The ID and submission error of a failed batch
 #[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FailureSummary {
+    batch_id: String,
+    error_type: Option<String>,
+    error_message: Option<String>,
+    failed_at: i64,
+}
+
+impl FailureSummary {
+    pub fn new(
+        batch_id: String,
+        error_type: Option<String>,
+        error_message: Option<String>,
+        failed_at: i64,
+    ) -> Self {
+        FailureSummary {
+            batch_id,
+            error_type,
+            error_message,
+            failed_at,
+        }
+    }
+
+    pub fn batch_id(&self) -> &str {
+        &self.batch_id
+    }
+
+    /// Returns the type of the batch's submission error, if it has one
+    pub fn error_type(&self) -> Option<&str> {
+        self.error_type.as_deref()
+    }
+
+    /// Returns the message of the batch's submission error, if it has one
+    pub fn error_message(&self) -> Option<&str> {
+        self.error_message.as_deref()
+    }
+
+    /// Returns the time the batch's status was last updated, in the store's
+    /// `TimestampPrecision`
+    pub fn failed_at(&self) -> i64 {
+        self.failed_at
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct TrackingTransaction {
     family_name: String,
     family_version: String,
@@ -535,6 +1199,10 @@ pub struct TrackingTransaction {
     payload: Vec<u8>,
     signer_public_key: String,
     service_id: String,
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+    dependencies: Vec<String>,
+    serialized_header: Option<Vec<u8>>,
 }
 
 impl TrackingTransaction {
@@ -561,9 +1229,30 @@ impl TrackingTransaction {
     pub fn service_id(&self) -> &str {
         &self.service_id
     }
+
+    /// Returns the hex-encoded state addresses the transaction reads from
+    pub fn inputs(&self) -> &[String] {
+        &self.inputs
+    }
+
+    /// Returns the hex-encoded state addresses the transaction writes to
+    pub fn outputs(&self) -> &[String] {
+        &self.outputs
+    }
+
+    /// Returns the IDs of the transactions this transaction depends on
+    pub fn dependencies(&self) -> &[String] {
+        &self.dependencies
+    }
+
+    /// Returns the transaction's serialized header, if it was stored with the
+    /// transaction
+    pub fn serialized_header(&self) -> Option<&[u8]> {
+        self.serialized_header.as_deref()
+    }
 }
 
-#[derive(Default, Clone)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct TrackingTransactionBuilder {
     transaction: Option<Transaction>,
     service_id: String,
@@ -613,6 +1302,18 @@ impl TrackingTransactionBuilder {
         let signer_public_key = format!("{:?}", txn_header.signer_public_key());
         let transaction_header = transact_transaction.header_signature().to_string();
         let payload = transact_transaction.payload().to_vec();
+        let dependencies = txn_header.dependencies().to_vec();
+        let serialized_header = transact_transaction.header().to_vec();
+        let inputs = txn_header
+            .inputs()
+            .iter()
+            .map(|input| to_hex(input))
+            .collect();
+        let outputs = txn_header
+            .outputs()
+            .iter()
+            .map(|output| to_hex(output))
+            .collect();
 
         if family_name.is_empty() {
             return Err(BatchBuilderError::MissingRequiredField(
@@ -651,10 +1352,15 @@ impl TrackingTransactionBuilder {
             payload,
             signer_public_key,
             service_id: serv_id,
+            inputs,
+            outputs,
+            dependencies,
+            serialized_header: Some(serialized_header),
         })
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct TransactionReceipt {
     transaction_id: String,
     result_valid: bool,
@@ -695,7 +1401,7 @@ impl TransactionReceipt {
     }
 }
 
-#[derive(Default, Clone)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct TransactionReceiptBuilder {
     transaction_id: String,
     result_valid: bool,
@@ -955,178 +1661,2012 @@ impl TryFrom<TrackingBatch> for GlobalTrackingBatch {
     }
 }
 
-pub trait BatchTrackingStore {
-    /// Gets the status of a batch from the underlying storage
-    ///
-    /// # Arguments
-    ///
-    ///  * `id` - The ID or data change ID of the batch with the status to
-    ///    fetch
+/// The unit of the timestamps recorded by a `BatchTrackingStore`
+///
+/// All timestamps, such as a batch's `created_at`, are measured from the Unix
+/// epoch in this unit, as is the `submitted_by` argument of
+/// `clean_stale_records`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimestampPrecision {
+    Seconds,
+    Milliseconds,
+}
+
+/// The ID of each batch passed to `try_add_batches` along with the outcome of
+/// adding it
+pub type TryAddBatchesOutcomes = Vec<(String, Result<(), BatchTrackingStoreError>)>;
+
+/// An iterator over the IDs and statuses of a service's batches, returned by
+/// `BatchTrackingStore::iter_id_status`
+pub struct IdStatusIter<'a, BS> {
+    store: &'a BS,
+    service_id: String,
+    after: Option<String>,
+    exhausted: bool,
+    page: std::vec::IntoIter<(String, BatchStatusName)>,
+}
+
+impl<'a, BS: BatchTrackingStore> Iterator for IdStatusIter<'a, BS> {
+    type Item = Result<(String, BatchStatusName), BatchTrackingStoreError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(id_status) = self.page.next() {
+            return Some(Ok(id_status));
+        }
+
+        if self.exhausted {
+            return None;
+        }
+
+        match self.store.id_status_page(
+            &self.service_id,
+            self.after.as_deref(),
+            ID_STATUS_PAGE_SIZE,
+        ) {
+            Ok(page) => {
+                self.exhausted = (page.len() as i64) < ID_STATUS_PAGE_SIZE;
+                self.after = page.last().map(|(batch_id, _)| batch_id.clone());
+                self.page = page.into_iter();
+                self.page.next().map(Ok)
+            }
+            Err(err) => {
+                self.exhausted = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+pub trait BatchTrackingStore {
+    /// Gets the status of a batch from the underlying storage
+    ///
+    /// Returns `None` if no status has been recorded for the batch. This is
+    /// the case for a submitted batch whose status has not been reported,
+    /// which should not be assumed to be committed; see
+    /// `repair_missing_statuses`.
+    ///
+    /// # Arguments
+    ///
+    ///  * `id` - The ID or data change ID of the batch with the status to
+    ///    fetch
+    ///  * `service_id` - The service ID
+    fn get_batch_status(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<Option<BatchStatus>, BatchTrackingStoreError>;
+
+    /// Updates the status of a batch in the underlying storage
+    ///
+    /// # Arguments
+    ///
+    ///  * `id` - The ID or data change ID of the batch with the status to
+    ///    update
+    ///  * `service_id` - The service ID
+    ///  * `status` - The new status for the batch
+    ///  * `transaction_receipts` - A list of transaction receipts for the
+    ///    transactions in the batch. Receipts may only be provided along
+    ///    with a status; use `store_receipts_only` to store receipts without
+    ///    changing the batch's status.
+    ///  * `submission_error` - A submission error for the batch if it exists
+    fn update_batch_status(
+        &self,
+        id: &str,
+        service_id: &str,
+        status: Option<BatchStatus>,
+        transaction_receipts: Vec<TransactionReceipt>,
+        submission_error: Option<SubmissionError>,
+    ) -> Result<(), BatchTrackingStoreError>;
+
+    /// Adds batches to the underlying storage
+    ///
+    /// The batches are added atomically; if any batch can not be added, none
+    /// of them are. A batch whose data change ID is the ID of another batch in
+    /// the same service, or whose ID is another batch's data change ID, can
+    /// not be added and an `IdCollision` error is returned.
+    ///
+    /// # Arguments
+    ///
+    ///  * `batches` - The batches to be added
+    fn add_batches(&self, batches: Vec<TrackingBatch>) -> Result<(), BatchTrackingStoreError>;
+
+    /// Reconstructs the transact batch that was added to the store from the
+    /// stored batch header and transactions
+    ///
+    /// # Arguments
+    ///
+    ///  * `id` - The batch ID
+    ///  * `service_id` - The service ID
+    fn get_transact_batch(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<Option<Batch>, BatchTrackingStoreError> {
+        self.get_batch(id, service_id)?
+            .map(|batch| batch.to_transact_batch())
+            .transpose()
+            .map_err(BatchTrackingStoreError::InternalError)
+    }
+
+    /// Adds batches to the underlying storage, each on its own
+    ///
+    /// Unlike `add_batches`, a batch that can not be added does not prevent
+    /// the others from being added. Returns the ID of each batch along with
+    /// the outcome of adding it, in the order the batches were given.
+    ///
+    /// # Arguments
+    ///
+    ///  * `batches` - The batches to be added
+    fn try_add_batches(
+        &self,
+        batches: Vec<TrackingBatch>,
+    ) -> Result<TryAddBatchesOutcomes, BatchTrackingStoreError>;
+
+    /// Updates a batch's status to a submitted state
+    ///
+    /// # Arguments
+    ///
+    ///  * `batch_id` - The ID or data change ID of the batch to update
+    ///  * `service_id` - The service ID
+    ///  * `transaction_receipts` - A list of transaction receipts for the
+    ///    transactions in the batch
+    ///  * `dlt_status` - The new status for the batch
+    ///  * `submission_error` - A submission error for the batch if it exists
+    ///  * `submitter_response` - The raw response received when submitting
+    ///    the batch, if it should be retained
+    ///  * `submit_headers` - A JSON object of the headers sent with the
+    ///    submission, if they should be retained for audit
+    ///  * `submit_url` - The URL the batch was submitted to, if the service
+    ///    submits to more than one endpoint
+    ///  * `submission_round` - The submission round the batch was sent in, if
+    ///    the submitter groups its submissions into rounds
+    ///  * `network_id` - The identifier of the DLT network the batch was
+    ///    submitted to, if the service submits to more than one
+    #[allow(clippy::too_many_arguments)]
+    fn change_batch_to_submitted(
+        &self,
+        batch_id: &str,
+        service_id: &str,
+        transaction_receipts: Vec<TransactionReceipt>,
+        dlt_status: Option<&str>,
+        submission_error: Option<SubmissionError>,
+        submitter_response: Option<&[u8]>,
+        submit_headers: Option<&serde_json::Value>,
+        submit_url: Option<&str>,
+        submission_round: Option<i64>,
+        network_id: Option<&str>,
+    ) -> Result<(), BatchTrackingStoreError>;
+
+    /// Gets a batch from the underlying storage
+    ///
+    /// Returns an `AmbiguousDataChangeId` error if `id` is a data change ID
+    /// that matches more than one batch for the service.
+    ///
+    /// # Arguments
+    ///
+    ///  * `id` - The ID or data change ID of the batch to fetch
+    ///  * `service_id` - The service ID
+    fn get_batch(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<Option<TrackingBatch>, BatchTrackingStoreError>;
+
+    /// Lists batches with a given status from the underlying storage
+    ///
+    /// # Arguments
+    ///
+    ///  * `status` - The status to fetch batches for
+    fn list_batches_by_status(
+        &self,
+        status: BatchStatus,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError>;
+
+    /// Removes records for batches and batch submissions before a given time
+    ///
+    /// # Arguments
+    ///
+    ///  * `submitted_by` - The timestamp for which to delete records submitted
+    ///    before, in the store's `TimestampPrecision`
+    fn clean_stale_records(&self, submitted_by: i64) -> Result<(), BatchTrackingStoreError>;
+
+    /// Gets batches that have not yet been submitted from the underlying storage
+    fn get_unsubmitted_batches(&self) -> Result<TrackingBatchList, BatchTrackingStoreError>;
+
+    /// Gets batches that failed either due to validation or submission errors
+    /// from the underlying storage
+    fn get_failed_batches(&self) -> Result<TrackingBatchList, BatchTrackingStoreError>;
+
+    /// Tombstones a batch ID so that the batch can not be added again
+    ///
+    /// # Arguments
+    ///
+    ///  * `id` - The ID of the batch to tombstone
+    ///  * `service_id` - The service ID
+    fn tombstone_batch(&self, id: &str, service_id: &str) -> Result<(), BatchTrackingStoreError>;
+
+    /// Gets the most recently created failed batches for a service, each
+    /// bundled with its submission error and invalid transactions
+    ///
+    /// # Arguments
+    ///
+    ///  * `service_id` - The service ID
+    ///  * `limit` - The maximum number of failed batches to return
+    fn get_recent_failures(
+        &self,
+        service_id: &str,
+        limit: i64,
+    ) -> Result<Vec<FailedBatchDetail>, BatchTrackingStoreError>;
+
+    /// Reclaims unused space in the underlying storage, such as space left
+    /// behind by `clean_stale_records`
+    ///
+    /// This must not be called while a transaction is in progress on the
+    /// underlying connection.
+    fn compact(&self) -> Result<(), BatchTrackingStoreError>;
+
+    /// Counts the batches for a service created within a time window, grouped
+    /// by status. Batches that do not yet have a status are not counted.
+    ///
+    /// # Arguments
+    ///
+    ///  * `service_id` - The service ID
+    ///  * `start` - The inclusive start of the window
+    ///  * `end` - The exclusive end of the window
+    fn status_distribution_between(
+        &self,
+        service_id: &str,
+        start: i64,
+        end: i64,
+    ) -> Result<HashMap<BatchStatusName, i64>, BatchTrackingStoreError>;
+
+    /// Gets the submission record for a batch, including the raw response
+    /// retained when the batch was submitted
+    ///
+    /// # Arguments
+    ///
+    ///  * `id` - The ID or data change ID of the batch
+    ///  * `service_id` - The service ID
+    fn get_batch_submission_info(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<Option<BatchSubmissionInfo>, BatchTrackingStoreError>;
+
+    /// Stores transaction receipts for a batch without changing the batch's
+    /// status
+    ///
+    /// # Arguments
+    ///
+    ///  * `id` - The ID or data change ID of the batch the receipts are for
+    ///  * `service_id` - The service ID
+    ///  * `transaction_receipts` - A list of transaction receipts for
+    ///    transactions in the batch
+    fn store_receipts_only(
+        &self,
+        id: &str,
+        service_id: &str,
+        transaction_receipts: Vec<TransactionReceipt>,
+    ) -> Result<(), BatchTrackingStoreError>;
+
+    /// Finds the IDs of committed batches for a service that do not have a
+    /// receipt for every transaction in the batch
+    ///
+    /// # Arguments
+    ///
+    ///  * `service_id` - The service ID
+    fn find_committed_batches_missing_receipts(
+        &self,
+        service_id: &str,
+    ) -> Result<Vec<String>, BatchTrackingStoreError>;
+
+    /// Lists the batches for a service that contain a transaction with the
+    /// given state address in its inputs or outputs
+    ///
+    /// # Arguments
+    ///
+    ///  * `address` - The hex-encoded state address
+    ///  * `service_id` - The service ID
+    fn list_batches_by_state_address(
+        &self,
+        address: &str,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError>;
+
+    /// Returns the average time in milliseconds taken by a service's batches to
+    /// reach a terminal status, or `None` if no batches have reached one
+    ///
+    /// # Arguments
+    ///
+    ///  * `service_id` - The service ID
+    ///  * `since` - Only batches created at or after this time are included,
+    ///    in the store's timestamp precision
+    fn average_submission_latency(
+        &self,
+        service_id: &str,
+        since: i64,
+    ) -> Result<Option<i64>, BatchTrackingStoreError>;
+
+    /// Adds transact batches to the store as unsubmitted batches, taking the
+    /// signer of each batch from its header
+    ///
+    /// # Arguments
+    ///
+    ///  * `batches` - The transact batches to be added
+    ///  * `service_id` - The service ID the batches are added under
+    fn add_transact_batches(
+        &self,
+        batches: Vec<Batch>,
+        service_id: &str,
+    ) -> Result<(), BatchTrackingStoreError>;
+
+    /// Subscribes to the unsubmitted batches added to the store for a service
+    ///
+    /// Batches passed to `add_batches` that have not been submitted are sent to
+    /// the returned receiver once they have been committed to the store. The
+    /// subscription ends when the receiver is dropped.
+    ///
+    /// Batches added within a transaction opened by the caller, such as one
+    /// begun with `TransactionalStoreFactory::begin_transaction`, are not
+    /// sent, since the store can't tell whether the transaction will be
+    /// committed.
+    ///
+    /// # Arguments
+    ///
+    ///  * `service_id` - The service ID to receive unsubmitted batches for
+    fn watch_unsubmitted(&self, service_id: &str) -> UnsubmittedBatchReceiver;
+
+    /// Records an attempt to submit a batch, returning the number of attempts
+    /// recorded so far
+    ///
+    /// The submission's attempt counter is incremented and read in a single
+    /// transaction, so concurrent callers each see a distinct count. The
+    /// counter is separate from `times_checked`, which counts every update to
+    /// the submission.
+    /// Returns a `NotFoundError` if the batch has no submission record.
+    ///
+    /// # Arguments
+    ///
+    ///  * `id` - The ID or data change ID of the batch
+    ///  * `service_id` - The service ID
+    fn record_submission_attempt(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<i64, BatchTrackingStoreError>;
+
+    /// Gets a page of the batches with a given status along with the total
+    /// number of batches with that status
+    ///
+    /// The total and the page are read in a single transaction, so the total
+    /// is consistent with the returned page. Batches are ordered by the time
+    /// they were added.
+    ///
+    /// # Arguments
+    ///
+    ///  * `status` - The status to fetch batches for
+    ///  * `offset` - The index of the first batch to return
+    ///  * `limit` - The maximum number of batches to return
+    fn list_batches_by_status_with_total(
+        &self,
+        status: BatchStatus,
+        offset: i64,
+        limit: i64,
+    ) -> Result<TrackingBatchPage, BatchTrackingStoreError>;
+
+    /// Removes the receipt payloads for a batch's transactions, returning the
+    /// number of receipts scrubbed
+    ///
+    /// The serialized receipts and error data are cleared. The batch, its
+    /// status and whether each transaction was valid are kept.
+    ///
+    /// # Arguments
+    ///
+    ///  * `id` - The ID or data change ID of the batch
+    ///  * `service_id` - The service ID
+    fn scrub_receipts(&self, id: &str, service_id: &str) -> Result<usize, BatchTrackingStoreError>;
+
+    /// Gets all of the batches for a service, ordered by the time they were
+    /// added
+    ///
+    /// # Arguments
+    ///
+    ///  * `service_id` - The service ID
+    fn list_batches(&self, service_id: &str) -> Result<TrackingBatchList, BatchTrackingStoreError>;
+
+    /// Returns the state of the store's database connection pool
+    ///
+    /// For a store backed by a pool, this reports the pool used for writes. A
+    /// store backed by a single connection reports that connection as in use.
+    fn pool_state(&self) -> PoolState;
+
+    /// Sets or clears the operator note on a batch
+    ///
+    /// # Arguments
+    ///
+    ///  * `id` - The ID or data change ID of the batch
+    ///  * `service_id` - The service ID
+    ///  * `notes` - The note to leave on the batch, or `None` to clear it
+    fn set_batch_notes(
+        &self,
+        id: &str,
+        service_id: &str,
+        notes: Option<&str>,
+    ) -> Result<(), BatchTrackingStoreError>;
+
+    /// Gets the batches for a service that contain a transaction
+    ///
+    /// All matching batches are returned rather than an arbitrary one. A
+    /// transaction ID can only be stored once per service, as adding a batch
+    /// that repeats a stored transaction ID fails with a
+    /// `ConstraintViolationError`, so at most one batch is returned.
+    ///
+    /// # Arguments
+    ///
+    ///  * `transaction_id` - The ID of the transaction
+    ///  * `service_id` - The service ID
+    fn get_batch_by_transaction_id(
+        &self,
+        transaction_id: &str,
+        service_id: &str,
+    ) -> Result<Vec<TrackingBatch>, BatchTrackingStoreError>;
+
+    /// Returns the number of batches in the store, in total, by status,
+    /// waiting to be submitted and failed, in the Prometheus text exposition
+    /// format
+    fn metrics_text(&self) -> Result<String, BatchTrackingStoreError>;
+
+    /// Lists the batches that were submitted in a given submission round
+    ///
+    /// # Arguments
+    ///
+    ///  * `round` - The submission round to fetch batches for
+    ///  * `service_id` - The service ID
+    fn list_batches_by_round(
+        &self,
+        round: i64,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError>;
+
+    /// Returns whether there are any batches that have not yet been submitted
+    ///
+    /// This checks for the same batches returned by `get_unsubmitted_batches`
+    /// without loading them.
+    ///
+    /// # Arguments
+    ///
+    ///  * `service_id` - The service ID to check, or `None` to check all
+    ///    services
+    fn has_unsubmitted_batches(
+        &self,
+        service_id: Option<&str>,
+    ) -> Result<bool, BatchTrackingStoreError>;
+
+    /// Rewrites stored batch statuses that differ from a known status only by
+    /// case to the status's canonical form, returning the number of statuses
+    /// rewritten
+    ///
+    /// Statuses that don't match a known status are left unchanged.
+    fn normalize_status_values(&self) -> Result<usize, BatchTrackingStoreError>;
+
+    /// Lists the status events recorded for a batch, oldest first
+    ///
+    /// An event is recorded each time the batch's status is set, along with
+    /// the correlation ID of the store that set it.
+    ///
+    /// # Arguments
+    ///
+    ///  * `id` - The ID or data change ID of the batch
+    ///  * `service_id` - The service ID
+    fn list_batch_status_events(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<Vec<BatchStatusEvent>, BatchTrackingStoreError>;
+
+    /// Gets the batches with the given data change IDs
+    ///
+    /// Data change IDs that don't match a batch are ignored. Returns an
+    /// `AmbiguousDataChangeId` error if one matches more than one batch.
+    ///
+    /// # Arguments
+    ///
+    ///  * `dcids` - The data change IDs of the batches to fetch
+    ///  * `service_id` - The service ID
+    fn get_batches_by_data_change_ids(
+        &self,
+        dcids: &[&str],
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError>;
+
+    /// Returns the total size in bytes of the serialized batches stored for a
+    /// service
+    ///
+    /// # Arguments
+    ///
+    ///  * `service_id` - The service ID
+    fn total_bytes_by_service(&self, service_id: &str) -> Result<i64, BatchTrackingStoreError>;
+
+    /// Lists the batches for a service whose status was set within a time
+    /// window
+    ///
+    /// Each batch is listed once, however many times its status was set in the
+    /// window.
+    ///
+    /// # Arguments
+    ///
+    ///  * `service_id` - The service ID
+    ///  * `start` - The inclusive start of the window
+    ///  * `end` - The exclusive end of the window
+    fn list_batches_status_changed_between(
+        &self,
+        service_id: &str,
+        start: i64,
+        end: i64,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError>;
+
+    /// Marks a batch as committed and stores the receipts for its
+    /// transactions in a single transaction
+    ///
+    /// As with any terminal status, the batch's submission latency is recorded
+    /// the first time it is committed.
+    ///
+    /// # Arguments
+    ///
+    ///  * `id` - The ID or data change ID of the batch
+    ///  * `service_id` - The service ID
+    ///  * `transaction_receipts` - The receipts for the transactions in the batch
+    fn commit_batch(
+        &self,
+        id: &str,
+        service_id: &str,
+        transaction_receipts: Vec<TransactionReceipt>,
+    ) -> Result<(), BatchTrackingStoreError>;
+
+    /// Returns the earliest and latest `created_at` of the batches stored for
+    /// a service, or `None` if the service has no batches
+    ///
+    /// # Arguments
+    ///
+    ///  * `service_id` - The service ID
+    fn created_at_bounds(
+        &self,
+        service_id: &str,
+    ) -> Result<Option<(i64, i64)>, BatchTrackingStoreError>;
+
+    /// Lists the batches that were submitted to a given DLT network
+    ///
+    /// # Arguments
+    ///
+    ///  * `network_id` - The identifier of the network to fetch batches for
+    ///  * `service_id` - The service ID
+    fn list_batches_by_network(
+        &self,
+        network_id: &str,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError>;
+
+    /// Returns the IDs of batches whose status changed more than
+    /// `min_transitions` times since a given time, such as a batch that keeps
+    /// moving between pending and delayed
+    ///
+    /// # Arguments
+    ///
+    ///  * `min_transitions` - The number of status changes a batch must exceed
+    ///    to be reported
+    ///  * `since` - The timestamp to count status changes from, in the store's
+    ///    `TimestampPrecision`
+    fn find_flapping_batches(
+        &self,
+        min_transitions: i64,
+        since: i64,
+    ) -> Result<Vec<String>, BatchTrackingStoreError>;
+
+    /// Returns a service's batches that were updated after a checkpoint, along
+    /// with the checkpoint to pass to the next call
+    ///
+    /// A batch is updated when it is added and whenever its status or
+    /// submission changes. Batches are returned oldest update first. At most
+    /// `limit` batches are returned, unless more than `limit` batches were
+    /// updated at the same time, in which case all of them are returned so that
+    /// none are skipped by the next call. The returned checkpoint is the latest
+    /// update time of the returned batches, or the given checkpoint if there
+    /// are none.
+    ///
+    /// # Arguments
+    ///
+    ///  * `service_id` - The service ID
+    ///  * `checkpoint` - The checkpoint returned by the previous call, or
+    ///    `None` to start from the first batch
+    ///  * `limit` - The maximum number of batches to return
+    fn sync_since(
+        &self,
+        service_id: &str,
+        checkpoint: Option<i64>,
+        limit: i64,
+    ) -> Result<(Vec<TrackingBatch>, Option<i64>), BatchTrackingStoreError>;
+
+    /// Sets or clears the human-readable alias of a batch
+    ///
+    /// Aliases are unique within a service. Setting an alias that is already
+    /// used by another batch in the service returns a
+    /// `ConstraintViolationError`.
+    ///
+    /// # Arguments
+    ///
+    ///  * `id` - The ID or data change ID of the batch
+    ///  * `service_id` - The service ID
+    ///  * `alias` - The alias to give the batch, or `None` to clear it
+    fn set_alias(
+        &self,
+        id: &str,
+        service_id: &str,
+        alias: Option<&str>,
+    ) -> Result<(), BatchTrackingStoreError>;
+
+    /// Gets a batch by its alias from the underlying storage
+    ///
+    /// # Arguments
+    ///
+    ///  * `alias` - The alias of the batch
+    ///  * `service_id` - The service ID
+    fn get_batch_by_alias(
+        &self,
+        alias: &str,
+        service_id: &str,
+    ) -> Result<Option<TrackingBatch>, BatchTrackingStoreError>;
+
+    /// Returns the status of every batch in a service that has one, keyed by
+    /// batch ID
+    ///
+    /// # Arguments
+    ///
+    ///  * `service_id` - The service ID
+    fn all_statuses_for_service(
+        &self,
+        service_id: &str,
+    ) -> Result<HashMap<String, BatchStatusName>, BatchTrackingStoreError>;
+
+    /// Gives submitted batches that have no status the `Unknown` status
+    ///
+    /// A submitted batch should always have a status, but one can be missing
+    /// if the status was never reported or was removed. Such a batch has no
+    /// status in `get_batch_status`, so it can not be mistaken for a
+    /// committed batch. Returns the number of batches repaired.
+    fn repair_missing_statuses(&self) -> Result<usize, BatchTrackingStoreError>;
+
+    /// Lists the batches that have been attempted the most, most attempts
+    /// first
+    ///
+    /// Attempts are counted by the submission's `times_checked` counter, so
+    /// batches without a submission record are not listed.
+    ///
+    /// # Arguments
+    ///
+    ///  * `service_id` - The service ID
+    ///  * `limit` - The maximum number of batches to list
+    fn list_batches_by_attempts(
+        &self,
+        service_id: &str,
+        limit: i64,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError>;
+
+    /// Returns the number of transactions in all of a service's batches
+    ///
+    /// # Arguments
+    ///
+    ///  * `service_id` - The service ID
+    fn count_transactions(&self, service_id: &str) -> Result<i64, BatchTrackingStoreError>;
+
+    /// Gets a page of the failed batches for a service, most recently failed
+    /// first
+    ///
+    /// Batches are ordered by the time their status was last updated, which is
+    /// the time they failed.
+    ///
+    /// # Arguments
+    ///
+    ///  * `service_id` - The service ID
+    ///  * `offset` - The index of the first batch to return
+    ///  * `limit` - The maximum number of batches to return
+    fn list_failed_batches_recent(
+        &self,
+        service_id: &str,
+        offset: i64,
+        limit: i64,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError>;
+
+    /// Lists the batches of a given kind
+    ///
+    /// # Arguments
+    ///
+    ///  * `kind` - The kind of batch to fetch
+    ///  * `service_id` - The service ID
+    fn list_batches_by_kind(
+        &self,
+        kind: &str,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError>;
+
+    /// Gets a batch with only the parts selected by a projection
+    ///
+    /// Only the tables needed for the selected parts are queried, so this is
+    /// cheaper than `get_batch` when not every part of the batch is needed.
+    ///
+    /// # Arguments
+    ///
+    ///  * `id` - The ID or data change ID of the batch to fetch
+    ///  * `service_id` - The service ID
+    ///  * `projection` - The parts of the batch to load
+    fn get_batch_projected(
+        &self,
+        id: &str,
+        service_id: &str,
+        projection: BatchProjection,
+    ) -> Result<Option<TrackingBatch>, BatchTrackingStoreError>;
+
+    /// Finds the batches containing a transaction whose ID starts with a given
+    /// prefix
+    ///
+    /// `%` and `_` in the prefix are matched literally.
+    ///
+    /// # Arguments
+    ///
+    ///  * `prefix` - The start of the transaction ID
+    ///  * `service_id` - The service ID
+    fn find_batches_by_transaction_prefix(
+        &self,
+        prefix: &str,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError>;
+
+    /// Lists the IDs and submission errors of the most recently failed batches
+    /// for a service, most recently failed first
+    ///
+    /// # Arguments
+    ///
+    ///  * `service_id` - The service ID
+    ///  * `limit` - The maximum number of failures to list
+    fn list_failure_summaries(
+        &self,
+        service_id: &str,
+        limit: i64,
+    ) -> Result<Vec<FailureSummary>, BatchTrackingStoreError>;
+
+    /// Sets the status of a batch and returns the status it had before, in one
+    /// transaction
+    ///
+    /// # Arguments
+    ///
+    ///  * `id` - A batch ID or data change ID
+    ///  * `service_id` - The service ID
+    ///  * `status` - The new status of the batch
+    fn swap_batch_status(
+        &self,
+        id: &str,
+        service_id: &str,
+        status: BatchStatus,
+    ) -> Result<Option<BatchStatus>, BatchTrackingStoreError>;
+
+    /// Gets at most `limit` of the batches that have not yet been submitted,
+    /// oldest first
+    ///
+    /// This returns the same batches as `get_unsubmitted_batches`, so a
+    /// submitter can work through them a fixed number at a time.
+    ///
+    /// # Arguments
+    ///
+    ///  * `service_id` - The service ID to get batches for, or `None` to get
+    ///    batches for all services
+    ///  * `limit` - The maximum number of batches to return
+    fn get_unsubmitted_batches_limited(
+        &self,
+        service_id: Option<&str>,
+        limit: i64,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError>;
+
+    /// Moves a batch to the terminal `DeadLettered` status, recording why
+    ///
+    /// Dead-lettered batches are not returned as unsubmitted, so they are not
+    /// retried. They can be listed with `get_dead_lettered_batches`.
+    ///
+    /// # Arguments
+    ///
+    ///  * `id` - A batch ID or data change ID
+    ///  * `service_id` - The service ID
+    ///  * `reason` - Why the batch will not be retried
+    fn dead_letter_batch(
+        &self,
+        id: &str,
+        service_id: &str,
+        reason: &str,
+    ) -> Result<(), BatchTrackingStoreError>;
+
+    /// Gets the batches for a service that have been dead-lettered, in the
+    /// order they were dead-lettered
+    ///
+    /// # Arguments
+    ///
+    ///  * `service_id` - The service ID
+    fn get_dead_lettered_batches(
+        &self,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError>;
+
+    /// Returns a digest of the IDs and statuses of a service's batches
+    ///
+    /// Stores holding the same batches with the same statuses for a service
+    /// return the same digest, so comparing digests detects drift between
+    /// replicas without comparing every batch.
+    ///
+    /// # Arguments
+    ///
+    ///  * `service_id` - The service ID
+    fn content_digest(&self, service_id: &str) -> Result<String, BatchTrackingStoreError>;
+
+    /// Gets the submitted batches for a service that are still waiting on
+    /// receipts from the DLT and were submitted before a given time
+    ///
+    /// A batch is waiting on receipts while it has no status or a pending
+    /// status. Batches are returned oldest submission first.
+    ///
+    /// # Arguments
+    ///
+    ///  * `older_than` - The timestamp to return batches submitted before, in
+    ///    the store's `TimestampPrecision`
+    ///  * `service_id` - The service ID
+    fn get_batches_awaiting_receipts(
+        &self,
+        older_than: i64,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError>;
+
+    /// Gets a page of the failed batches for a service, ordered by creation
+    /// time, that come after a cursor batch
+    ///
+    /// Pages are keyed on the creation time and ID of the batch rather than an
+    /// offset, so passing the ID of the last batch of a page as `after`
+    /// resumes from the next page even if earlier batches have changed.
+    ///
+    /// # Arguments
+    ///
+    ///  * `service_id` - The service ID
+    ///  * `after` - The ID of the batch to return batches after, or `None` to
+    ///    start from the first failed batch
+    ///  * `limit` - The maximum number of batches to return
+    fn failed_batches_cursor(
+        &self,
+        service_id: &str,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError>;
+
+    /// Replaces the data change IDs of a service's batches
+    ///
+    /// All of the remaps are applied in one transaction, so either every batch
+    /// is remapped or none are. Data change IDs in the mapping that do not
+    /// belong to a batch are ignored, and an `AmbiguousDataChangeId` error is
+    /// returned if one belongs to more than one batch. Returns the number of
+    /// batches remapped.
+    ///
+    /// # Arguments
+    ///
+    ///  * `mapping` - A map of the current data change IDs to their
+    ///    replacements
+    ///  * `service_id` - The service ID
+    fn remap_data_change_ids(
+        &self,
+        mapping: HashMap<String, String>,
+        service_id: &str,
+    ) -> Result<usize, BatchTrackingStoreError>;
+
+    /// Lists the batches for a service that contain a transaction of the given
+    /// transaction family, ordered by creation time
+    ///
+    /// # Arguments
+    ///
+    ///  * `family_name` - The transaction family name
+    ///  * `service_id` - The service ID
+    fn list_batches_by_family(
+        &self,
+        family_name: &str,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError>;
+
+    /// Writes the batches for a service to a writer as newline-delimited
+    /// JSON, one batch per line
+    ///
+    /// Batches are read from the store a page at a time, oldest update first,
+    /// so the whole set is never held in memory. Returns the number of
+    /// batches written.
+    ///
+    /// # Arguments
+    ///
+    ///  * `service_id` - The service ID
+    ///  * `writer` - The writer the batches are written to
+    fn export_service_ndjson<W: Write>(
+        &self,
+        service_id: &str,
+        mut writer: W,
+    ) -> Result<usize, BatchTrackingStoreError>
+    where
+        Self: Sized,
+    {
+        let write_error = |err: std::io::Error| {
+            BatchTrackingStoreError::InternalError(InternalError::from_source(Box::new(err)))
+        };
+
+        let mut checkpoint = None;
+        let mut written = 0;
+        loop {
+            let (batches, next_checkpoint) =
+                self.sync_since(service_id, checkpoint, NDJSON_EXPORT_PAGE_SIZE)?;
+            if batches.is_empty() {
+                break;
+            }
+
+            for batch in &batches {
+                serde_json::to_writer(&mut writer, batch).map_err(|err| {
+                    BatchTrackingStoreError::InternalError(InternalError::from_source(Box::new(
+                        err,
+                    )))
+                })?;
+                writer.write_all(b"\n").map_err(write_error)?;
+            }
+
+            written += batches.len();
+            checkpoint = next_checkpoint;
+        }
+
+        writer.flush().map_err(write_error)?;
+
+        Ok(written)
+    }
+
+    /// Finds the IDs of batches for a service that have more receipts recorded
+    /// than they have transactions
+    ///
+    /// Such batches have a transaction with more than one receipt, which can be
+    /// removed with `dedupe_receipts`.
+    ///
+    /// # Arguments
+    ///
+    ///  * `service_id` - The service ID
+    fn find_batches_with_excess_receipts(
+        &self,
+        service_id: &str,
+    ) -> Result<Vec<String>, BatchTrackingStoreError>;
+
+    /// Removes all but the first receipt recorded for each transaction in a
+    /// batch, returning the number of receipts removed
+    ///
+    /// # Arguments
+    ///
+    ///  * `id` - The ID or data change ID of the batch
+    ///  * `service_id` - The service ID
+    fn dedupe_receipts(&self, id: &str, service_id: &str)
+        -> Result<usize, BatchTrackingStoreError>;
+
+    /// Gets the status a batch had at a given time, rebuilt from its status
+    /// events
+    ///
+    /// Returns `None` if the batch had no status at that time.
+    ///
+    /// # Arguments
+    ///
+    ///  * `id` - The ID or data change ID of the batch
+    ///  * `service_id` - The service ID
+    ///  * `at` - The timestamp to get the status at, in the store's
+    ///    `TimestampPrecision`
+    fn get_batch_status_at(
+        &self,
+        id: &str,
+        service_id: &str,
+        at: i64,
+    ) -> Result<Option<BatchStatusName>, BatchTrackingStoreError>;
+
+    /// Lists the batches for a service that were submitted to the given URL,
+    /// ordered by creation time
+    ///
+    /// # Arguments
+    ///
+    ///  * `submit_url` - The URL the batches were submitted to
+    ///  * `service_id` - The service ID
+    fn list_batches_by_submit_url(
+        &self,
+        submit_url: &str,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError>;
+
+    /// Finds the data change IDs held by more than one batch in a service,
+    /// such as after merging two databases
+    ///
+    /// Returns each duplicated data change ID, in order, along with the sorted
+    /// IDs of the batches holding it, so the collisions can be resolved before
+    /// the data change IDs are made unique.
+    ///
+    /// # Arguments
+    ///
+    ///  * `service_id` - The service ID
+    fn find_duplicate_dcids(
+        &self,
+        service_id: &str,
+    ) -> Result<Vec<(String, Vec<String>)>, BatchTrackingStoreError>;
+
+    /// Counts the batches for a service created since a given time, grouped by
+    /// the upstream system they came from. Batches without an origin are not
+    /// counted.
+    ///
+    /// # Arguments
+    ///
+    ///  * `service_id` - The service ID
+    ///  * `since` - Only batches created at or after this time are counted, in
+    ///    the store's `TimestampPrecision`
+    fn count_batches_by_origin(
+        &self,
+        service_id: &str,
+        since: i64,
+    ) -> Result<HashMap<String, i64>, BatchTrackingStoreError>;
+
+    /// Returns a page of the IDs and statuses of a service's batches, ordered
+    /// by batch ID. Batches that do not yet have a status are not included.
+    ///
+    /// # Arguments
+    ///
+    ///  * `service_id` - The service ID
+    ///  * `after` - The last batch ID of the previous page, or `None` for the
+    ///    first page
+    ///  * `limit` - The maximum number of batches to return
+    fn id_status_page(
+        &self,
+        service_id: &str,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<(String, BatchStatusName)>, BatchTrackingStoreError>;
+
+    /// Returns an iterator over the IDs and statuses of a service's batches,
+    /// ordered by batch ID
+    ///
+    /// The iterator reads the store a page at a time with `id_status_page`,
+    /// so the whole set is never held in memory. The first page is read
+    /// before returning, so an error reading it is returned here rather than
+    /// from the iterator.
+    ///
+    /// # Arguments
+    ///
+    ///  * `service_id` - The service ID
+    fn iter_id_status(
+        &self,
+        service_id: &str,
+    ) -> Result<IdStatusIter<'_, Self>, BatchTrackingStoreError>
+    where
+        Self: Sized,
+    {
+        let page = self.id_status_page(service_id, None, ID_STATUS_PAGE_SIZE)?;
+
+        Ok(IdStatusIter {
+            store: self,
+            service_id: service_id.to_string(),
+            after: page.last().map(|(batch_id, _)| batch_id.clone()),
+            exhausted: (page.len() as i64) < ID_STATUS_PAGE_SIZE,
+            page: page.into_iter(),
+        })
+    }
+
+    /// Lists the batches for a service that contain a transaction listing the
+    /// given transaction as a dependency, ordered by creation time
+    ///
+    /// # Arguments
+    ///
+    ///  * `transaction_id` - The ID of the transaction depended on
+    ///  * `service_id` - The service ID
+    fn list_batches_depending_on(
+        &self,
+        transaction_id: &str,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError>;
+
+    /// Tombstones a set of batch IDs in a single transaction so that none of
+    /// the batches can be added again, returning the number of IDs that were
+    /// not already tombstoned
+    ///
+    /// # Arguments
+    ///
+    ///  * `ids` - The IDs of the batches to tombstone
+    ///  * `service_id` - The service ID
+    fn tombstone_batches(
+        &self,
+        ids: &[&str],
+        service_id: &str,
+    ) -> Result<usize, BatchTrackingStoreError>;
+
+    /// Returns the fraction of a service's batches created within a time
+    /// window that were committed, out of those that reached a terminal status
+    ///
+    /// Batches that are invalid or dead-lettered count as failed; batches that
+    /// have not yet reached a terminal status are not counted. Returns a
+    /// `NotFoundError` if no batches in the window reached a terminal status.
+    ///
+    /// # Arguments
+    ///
+    ///  * `service_id` - The service ID
+    ///  * `start` - The inclusive start of the window
+    ///  * `end` - The exclusive end of the window
+    fn submission_success_rate(
+        &self,
+        service_id: &str,
+        start: i64,
+        end: i64,
+    ) -> Result<f64, BatchTrackingStoreError>;
+
+    /// Returns the IDs of a service's batches that have no stored
+    /// transactions, ordered by batch ID
+    ///
+    /// Every batch is stored with its transactions, so any batch returned
+    /// indicates that the stored data is incomplete.
+    ///
+    /// # Arguments
+    ///
+    ///  * `service_id` - The service ID
+    fn find_batches_without_transactions(
+        &self,
+        service_id: &str,
+    ) -> Result<Vec<String>, BatchTrackingStoreError>;
+
+    /// Claims up to `n` of a service's oldest unsubmitted batches for a
+    /// worker in a single transaction, leasing them to the claimant for
+    /// `lease_secs` seconds
+    ///
+    /// Dead-lettered batches and batches with an unexpired claim, including
+    /// one held by the same claimant, are not claimed. Returns the claimed
+    /// batches, ordered by creation time.
+    ///
+    /// # Arguments
+    ///
+    ///  * `service_id` - The service ID
+    ///  * `claimant` - An identifier for the worker claiming the batches
+    ///  * `n` - The maximum number of batches to claim
+    ///  * `lease_secs` - How long the claim lasts, in seconds
+    fn claim_unsubmitted_batches(
+        &self,
+        service_id: &str,
+        claimant: &str,
+        n: i64,
+        lease_secs: u64,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError>;
+
+    /// Returns counts of a service's batches for judging the health of its
+    /// submissions: the total number of batches, the number that failed, the
+    /// number stuck pending, and the unsubmitted backlog
+    ///
+    /// Batches count as failed when they are invalid or dead-lettered; see
+    /// `HealthSnapshot::failed`.
+    ///
+    /// # Arguments
+    ///
+    ///  * `service_id` - The service ID
+    ///  * `stuck_threshold` - Pending batches whose status was last updated
+    ///    before this time are counted as stuck, in the store's
+    ///    `TimestampPrecision`
+    fn health_snapshot(
+        &self,
+        service_id: &str,
+        stuck_threshold: i64,
+    ) -> Result<HealthSnapshot, BatchTrackingStoreError>;
+
+    /// Counts the batches for a service created within a time window, grouped
+    /// by the hour they were created in
+    ///
+    /// Returns the start of each hour and the number of batches created in it,
+    /// ordered by hour. Hours are aligned to the epoch, and are in the store's
+    /// `TimestampPrecision`. Hours in which no batches were created are
+    /// omitted rather than returned with a count of zero.
+    ///
+    /// # Arguments
+    ///
+    ///  * `service_id` - The service ID
+    ///  * `start` - The inclusive start of the window
+    ///  * `end` - The exclusive end of the window
+    fn hourly_batch_counts(
+        &self,
+        service_id: &str,
+        start: i64,
+        end: i64,
+    ) -> Result<Vec<(i64, i64)>, BatchTrackingStoreError>;
+
+    /// Returns up to `limit` events from the outbox, oldest first
+    ///
+    /// An event is queued in the outbox in the same transaction as the status
+    /// change it records, so a relay that fetches events and acknowledges them
+    /// with `ack_outbox` once they are published will see every committed
+    /// status change at least once. Events remain in the outbox until they are
+    /// acknowledged.
+    ///
+    /// # Arguments
+    ///
+    ///  * `limit` - The maximum number of events to return
+    fn fetch_outbox(&self, limit: i64) -> Result<Vec<OutboxEvent>, BatchTrackingStoreError>;
+
+    /// Removes acknowledged events from the outbox, returning the number of
+    /// events removed
+    ///
+    /// IDs that are not in the outbox, such as events that were already
+    /// acknowledged, are ignored.
+    ///
+    /// # Arguments
+    ///
+    ///  * `ids` - The IDs of the events returned by `fetch_outbox` to remove
+    fn ack_outbox(&self, ids: &[i64]) -> Result<usize, BatchTrackingStoreError>;
+
+    /// Moves a set of batches to the terminal `DeadLettered` status in a
+    /// single transaction, recording the same reason for each, and returns the
+    /// number of batches dead-lettered
+    ///
+    /// If any of the batches can't be found, none of them are dead-lettered.
+    ///
+    /// # Arguments
+    ///
+    ///  * `ids` - The batch IDs or data change IDs of the batches
+    ///  * `service_id` - The service ID
+    ///  * `reason` - Why the batches will not be retried
+    fn dead_letter_batches(
+        &self,
+        ids: &[&str],
+        service_id: &str,
+        reason: &str,
+    ) -> Result<usize, BatchTrackingStoreError>;
+
+    /// Lists the batches whose labels match every predicate, oldest first
+    ///
+    /// A batch's labels are the string values of its metadata, so a predicate
+    /// `(key, value)` matches a batch whose metadata has the string `value`
+    /// under `key`. With no predicates, every batch for the service is
+    /// returned.
+    ///
+    /// # Arguments
+    ///
+    ///  * `predicates` - The label keys and values that must all match
+    ///  * `service_id` - The service ID
+    fn list_batches_by_labels(
+        &self,
+        predicates: &[(String, String)],
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError>;
+
+    /// Gets the error from a batch's latest submission without loading the
+    /// batch
+    ///
+    /// Returns `None` if the batch can't be found or its latest submission
+    /// did not fail.
+    ///
+    /// # Arguments
+    ///
+    ///  * `id` - The ID or data change ID of the batch
+    ///  * `service_id` - The service ID
+    fn get_latest_submission_error(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<Option<SubmissionError>, BatchTrackingStoreError>;
+
+    /// Moves a set of batches to new statuses in a single transaction,
+    /// recording a status event for each
+    ///
+    /// Each transition is checked with `BatchStatusName::can_transition_to`
+    /// against the batch's current status, including any status set by an
+    /// earlier transition in the call. If any transition is not allowed, an
+    /// `InvalidStatusTransition` error is returned and none of the statuses
+    /// are changed. Returns a `NotFoundError` if a batch can't be found.
+    ///
+    /// # Arguments
+    ///
+    ///  * `transitions` - The batch IDs or data change IDs and the statuses to
+    ///    move them to, applied in order
+    ///  * `service_id` - The service ID
+    fn transition_batches(
+        &self,
+        transitions: Vec<(String, BatchStatus)>,
+        service_id: &str,
+    ) -> Result<(), BatchTrackingStoreError>;
+
+    /// Writes every row stored for a service to a writer, in a format that
+    /// can be restored with `restore_service`
+    ///
+    /// The backup is newline-delimited JSON: a header with the format version
+    /// and service ID, followed by one line per row of each table. It is read
+    /// in a single transaction, a page of batches at a time, so it is
+    /// consistent without the whole service being held in memory.
+    /// Transaction payloads are written as they are stored, so payloads
+    /// encrypted with a `PayloadCipher` can only be read after restoring if
+    /// the restoring store has the same cipher.
+    ///
+    /// # Arguments
+    ///
+    ///  * `service_id` - The service ID
+    ///  * `writer` - The writer the backup is written to
+    fn backup_service<W: Write>(
+        &self,
+        service_id: &str,
+        mut writer: W,
+    ) -> Result<(), BatchTrackingStoreError>
+    where
+        Self: Sized,
+    {
+        self.write_service_backup(service_id, &mut writer)
+    }
+
+    /// Restores a backup written by `backup_service`, recreating every row
+    /// of the backed up service
+    ///
+    /// The backup is restored in a single transaction, so if any row can't be
+    /// inserted, such as a batch that is already in the store, nothing is
+    /// restored. Status and outbox events are recorded in their original
+    /// order, but are given new IDs.
+    ///
+    /// # Arguments
+    ///
+    ///  * `reader` - The reader the backup is read from
+    fn restore_service<R: Read>(&self, mut reader: R) -> Result<(), BatchTrackingStoreError>
+    where
+        Self: Sized,
+    {
+        self.read_service_backup(&mut reader)
+    }
+
+    /// Writes a backup of a service to a writer
+    ///
+    /// This is the object-safe form of `backup_service`, which should be
+    /// preferred where the store's type is known.
+    ///
+    /// # Arguments
+    ///
     ///  * `service_id` - The service ID
+    ///  * `writer` - The writer the backup is written to
+    fn write_service_backup(
+        &self,
+        service_id: &str,
+        writer: &mut dyn Write,
+    ) -> Result<(), BatchTrackingStoreError>;
+
+    /// Restores a backup of a service from a reader
+    ///
+    /// This is the object-safe form of `restore_service`, which should be
+    /// preferred where the store's type is known.
+    ///
+    /// # Arguments
+    ///
+    ///  * `reader` - The reader the backup is read from
+    fn read_service_backup(&self, reader: &mut dyn Read) -> Result<(), BatchTrackingStoreError>;
+}
+
+impl<BS> BatchTrackingStore for Box<BS>
+where
+    BS: BatchTrackingStore + ?Sized,
+{
     fn get_batch_status(
         &self,
-        id: &str,
+        id: &str,
+        service_id: &str,
+    ) -> Result<Option<BatchStatus>, BatchTrackingStoreError> {
+        (**self).get_batch_status(id, service_id)
+    }
+
+    fn update_batch_status(
+        &self,
+        id: &str,
+        service_id: &str,
+        status: Option<BatchStatus>,
+        transaction_receipts: Vec<TransactionReceipt>,
+        submission_error: Option<SubmissionError>,
+    ) -> Result<(), BatchTrackingStoreError> {
+        (**self).update_batch_status(
+            id,
+            service_id,
+            status,
+            transaction_receipts,
+            submission_error,
+        )
+    }
+
+    fn add_batches(&self, batches: Vec<TrackingBatch>) -> Result<(), BatchTrackingStoreError> {
+        (**self).add_batches(batches)
+    }
+
+    fn try_add_batches(
+        &self,
+        batches: Vec<TrackingBatch>,
+    ) -> Result<TryAddBatchesOutcomes, BatchTrackingStoreError> {
+        (**self).try_add_batches(batches)
+    }
+
+    fn change_batch_to_submitted(
+        &self,
+        batch_id: &str,
+        service_id: &str,
+        transaction_receipts: Vec<TransactionReceipt>,
+        dlt_status: Option<&str>,
+        submission_error: Option<SubmissionError>,
+        submitter_response: Option<&[u8]>,
+        submit_headers: Option<&serde_json::Value>,
+        submit_url: Option<&str>,
+        submission_round: Option<i64>,
+        network_id: Option<&str>,
+    ) -> Result<(), BatchTrackingStoreError> {
+        (**self).change_batch_to_submitted(
+            batch_id,
+            service_id,
+            transaction_receipts,
+            dlt_status,
+            submission_error,
+            submitter_response,
+            submit_headers,
+            submit_url,
+            submission_round,
+            network_id,
+        )
+    }
+
+    fn get_batch(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<Option<TrackingBatch>, BatchTrackingStoreError> {
+        (**self).get_batch(id, service_id)
+    }
+
+    fn list_batches_by_status(
+        &self,
+        status: BatchStatus,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        (**self).list_batches_by_status(status)
+    }
+
+    fn clean_stale_records(&self, submitted_by: i64) -> Result<(), BatchTrackingStoreError> {
+        (**self).clean_stale_records(submitted_by)
+    }
+
+    fn get_unsubmitted_batches(&self) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        (**self).get_unsubmitted_batches()
+    }
+
+    fn get_failed_batches(&self) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        (**self).get_failed_batches()
+    }
+
+    fn tombstone_batch(&self, id: &str, service_id: &str) -> Result<(), BatchTrackingStoreError> {
+        (**self).tombstone_batch(id, service_id)
+    }
+
+    fn get_recent_failures(
+        &self,
+        service_id: &str,
+        limit: i64,
+    ) -> Result<Vec<FailedBatchDetail>, BatchTrackingStoreError> {
+        (**self).get_recent_failures(service_id, limit)
+    }
+
+    fn compact(&self) -> Result<(), BatchTrackingStoreError> {
+        (**self).compact()
+    }
+
+    fn status_distribution_between(
+        &self,
+        service_id: &str,
+        start: i64,
+        end: i64,
+    ) -> Result<HashMap<BatchStatusName, i64>, BatchTrackingStoreError> {
+        (**self).status_distribution_between(service_id, start, end)
+    }
+
+    fn get_batch_submission_info(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<Option<BatchSubmissionInfo>, BatchTrackingStoreError> {
+        (**self).get_batch_submission_info(id, service_id)
+    }
+
+    fn store_receipts_only(
+        &self,
+        id: &str,
+        service_id: &str,
+        transaction_receipts: Vec<TransactionReceipt>,
+    ) -> Result<(), BatchTrackingStoreError> {
+        (**self).store_receipts_only(id, service_id, transaction_receipts)
+    }
+
+    fn find_committed_batches_missing_receipts(
+        &self,
+        service_id: &str,
+    ) -> Result<Vec<String>, BatchTrackingStoreError> {
+        (**self).find_committed_batches_missing_receipts(service_id)
+    }
+
+    fn list_batches_by_state_address(
+        &self,
+        address: &str,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        (**self).list_batches_by_state_address(address, service_id)
+    }
+
+    fn average_submission_latency(
+        &self,
+        service_id: &str,
+        since: i64,
+    ) -> Result<Option<i64>, BatchTrackingStoreError> {
+        (**self).average_submission_latency(service_id, since)
+    }
+
+    fn add_transact_batches(
+        &self,
+        batches: Vec<Batch>,
+        service_id: &str,
+    ) -> Result<(), BatchTrackingStoreError> {
+        (**self).add_transact_batches(batches, service_id)
+    }
+
+    fn watch_unsubmitted(&self, service_id: &str) -> UnsubmittedBatchReceiver {
+        (**self).watch_unsubmitted(service_id)
+    }
+
+    fn record_submission_attempt(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<i64, BatchTrackingStoreError> {
+        (**self).record_submission_attempt(id, service_id)
+    }
+
+    fn list_batches_by_status_with_total(
+        &self,
+        status: BatchStatus,
+        offset: i64,
+        limit: i64,
+    ) -> Result<TrackingBatchPage, BatchTrackingStoreError> {
+        (**self).list_batches_by_status_with_total(status, offset, limit)
+    }
+
+    fn scrub_receipts(&self, id: &str, service_id: &str) -> Result<usize, BatchTrackingStoreError> {
+        (**self).scrub_receipts(id, service_id)
+    }
+
+    fn list_batches(&self, service_id: &str) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        (**self).list_batches(service_id)
+    }
+
+    fn pool_state(&self) -> PoolState {
+        (**self).pool_state()
+    }
+
+    fn set_batch_notes(
+        &self,
+        id: &str,
+        service_id: &str,
+        notes: Option<&str>,
+    ) -> Result<(), BatchTrackingStoreError> {
+        (**self).set_batch_notes(id, service_id, notes)
+    }
+
+    fn get_batch_by_transaction_id(
+        &self,
+        transaction_id: &str,
+        service_id: &str,
+    ) -> Result<Vec<TrackingBatch>, BatchTrackingStoreError> {
+        (**self).get_batch_by_transaction_id(transaction_id, service_id)
+    }
+
+    fn metrics_text(&self) -> Result<String, BatchTrackingStoreError> {
+        (**self).metrics_text()
+    }
+
+    fn list_batches_by_round(
+        &self,
+        round: i64,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        (**self).list_batches_by_round(round, service_id)
+    }
+
+    fn has_unsubmitted_batches(
+        &self,
+        service_id: Option<&str>,
+    ) -> Result<bool, BatchTrackingStoreError> {
+        (**self).has_unsubmitted_batches(service_id)
+    }
+
+    fn normalize_status_values(&self) -> Result<usize, BatchTrackingStoreError> {
+        (**self).normalize_status_values()
+    }
+
+    fn list_batch_status_events(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<Vec<BatchStatusEvent>, BatchTrackingStoreError> {
+        (**self).list_batch_status_events(id, service_id)
+    }
+
+    fn get_batches_by_data_change_ids(
+        &self,
+        dcids: &[&str],
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        (**self).get_batches_by_data_change_ids(dcids, service_id)
+    }
+
+    fn total_bytes_by_service(&self, service_id: &str) -> Result<i64, BatchTrackingStoreError> {
+        (**self).total_bytes_by_service(service_id)
+    }
+
+    fn list_batches_status_changed_between(
+        &self,
+        service_id: &str,
+        start: i64,
+        end: i64,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        (**self).list_batches_status_changed_between(service_id, start, end)
+    }
+
+    fn commit_batch(
+        &self,
+        id: &str,
+        service_id: &str,
+        transaction_receipts: Vec<TransactionReceipt>,
+    ) -> Result<(), BatchTrackingStoreError> {
+        (**self).commit_batch(id, service_id, transaction_receipts)
+    }
+
+    fn created_at_bounds(
+        &self,
+        service_id: &str,
+    ) -> Result<Option<(i64, i64)>, BatchTrackingStoreError> {
+        (**self).created_at_bounds(service_id)
+    }
+
+    fn list_batches_by_network(
+        &self,
+        network_id: &str,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        (**self).list_batches_by_network(network_id, service_id)
+    }
+
+    fn find_flapping_batches(
+        &self,
+        min_transitions: i64,
+        since: i64,
+    ) -> Result<Vec<String>, BatchTrackingStoreError> {
+        (**self).find_flapping_batches(min_transitions, since)
+    }
+
+    fn sync_since(
+        &self,
+        service_id: &str,
+        checkpoint: Option<i64>,
+        limit: i64,
+    ) -> Result<(Vec<TrackingBatch>, Option<i64>), BatchTrackingStoreError> {
+        (**self).sync_since(service_id, checkpoint, limit)
+    }
+
+    fn set_alias(
+        &self,
+        id: &str,
+        service_id: &str,
+        alias: Option<&str>,
+    ) -> Result<(), BatchTrackingStoreError> {
+        (**self).set_alias(id, service_id, alias)
+    }
+
+    fn get_batch_by_alias(
+        &self,
+        alias: &str,
+        service_id: &str,
+    ) -> Result<Option<TrackingBatch>, BatchTrackingStoreError> {
+        (**self).get_batch_by_alias(alias, service_id)
+    }
+
+    fn all_statuses_for_service(
+        &self,
+        service_id: &str,
+    ) -> Result<HashMap<String, BatchStatusName>, BatchTrackingStoreError> {
+        (**self).all_statuses_for_service(service_id)
+    }
+
+    fn repair_missing_statuses(&self) -> Result<usize, BatchTrackingStoreError> {
+        (**self).repair_missing_statuses()
+    }
+
+    fn list_batches_by_attempts(
+        &self,
         service_id: &str,
-    ) -> Result<Option<BatchStatus>, BatchTrackingStoreError>;
+        limit: i64,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        (**self).list_batches_by_attempts(service_id, limit)
+    }
 
-    /// Updates the status of a batch in the underlying storage
-    ///
-    /// # Arguments
-    ///
-    ///  * `id` - The ID or data change ID of the batch with the status to
-    ///    update
-    ///  * `service_id` - The service ID
-    ///  * `status` - The new status for the batch
-    ///  * `transaction_receipts` - A list of transaction receipts for the
-    ///    transactions in the batch
-    ///  * `submission_error` - A submission error for the batch if it exists
-    fn update_batch_status(
+    fn count_transactions(&self, service_id: &str) -> Result<i64, BatchTrackingStoreError> {
+        (**self).count_transactions(service_id)
+    }
+
+    fn list_failed_batches_recent(
+        &self,
+        service_id: &str,
+        offset: i64,
+        limit: i64,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        (**self).list_failed_batches_recent(service_id, offset, limit)
+    }
+
+    fn list_batches_by_kind(
+        &self,
+        kind: &str,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        (**self).list_batches_by_kind(kind, service_id)
+    }
+
+    fn get_batch_projected(
         &self,
         id: &str,
         service_id: &str,
-        status: Option<BatchStatus>,
-        transaction_receipts: Vec<TransactionReceipt>,
-        submission_error: Option<SubmissionError>,
-    ) -> Result<(), BatchTrackingStoreError>;
+        projection: BatchProjection,
+    ) -> Result<Option<TrackingBatch>, BatchTrackingStoreError> {
+        (**self).get_batch_projected(id, service_id, projection)
+    }
 
-    /// Adds batches to the underlying storage
-    ///
-    /// # Arguments
-    ///
-    ///  * `batches` - The batches to be added
-    fn add_batches(&self, batches: Vec<TrackingBatch>) -> Result<(), BatchTrackingStoreError>;
+    fn find_batches_by_transaction_prefix(
+        &self,
+        prefix: &str,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        (**self).find_batches_by_transaction_prefix(prefix, service_id)
+    }
 
-    /// Updates a batch's status to a submitted state
-    ///
-    /// # Arguments
-    ///
-    ///  * `batch_id` - The ID or data change ID of the batch to update
-    ///  * `service_id` - The service ID
-    ///  * `transaction_receipts` - A list of transaction receipts for the
-    ///    transactions in the batch
-    ///  * `dlt_status` - The new status for the batch
-    ///  * `submission_error` - A submission error for the batch if it exists
-    fn change_batch_to_submitted(
+    fn list_failure_summaries(
         &self,
-        batch_id: &str,
         service_id: &str,
-        transaction_receipts: Vec<TransactionReceipt>,
-        dlt_status: Option<&str>,
-        submission_error: Option<SubmissionError>,
-    ) -> Result<(), BatchTrackingStoreError>;
+        limit: i64,
+    ) -> Result<Vec<FailureSummary>, BatchTrackingStoreError> {
+        (**self).list_failure_summaries(service_id, limit)
+    }
 
-    /// Gets a batch from the underlying storage
-    ///
-    /// # Arguments
-    ///
-    ///  * `id` - The ID or data change ID of the batch to fetch
-    ///  * `service_id` - The service ID
-    fn get_batch(
+    fn swap_batch_status(
         &self,
         id: &str,
         service_id: &str,
-    ) -> Result<Option<TrackingBatch>, BatchTrackingStoreError>;
+        status: BatchStatus,
+    ) -> Result<Option<BatchStatus>, BatchTrackingStoreError> {
+        (**self).swap_batch_status(id, service_id, status)
+    }
 
-    /// Lists batches with a given status from the underlying storage
-    ///
-    /// # Arguments
-    ///
-    ///  * `status` - The status to fetch batches for
-    fn list_batches_by_status(
+    fn get_unsubmitted_batches_limited(
         &self,
-        status: BatchStatus,
-    ) -> Result<TrackingBatchList, BatchTrackingStoreError>;
+        service_id: Option<&str>,
+        limit: i64,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        (**self).get_unsubmitted_batches_limited(service_id, limit)
+    }
 
-    /// Removes records for batches and batch submissions before a given time
-    ///
-    /// # Arguments
-    ///
-    ///  * `submitted_by` - The timestamp for which to delete records submitted before
-    fn clean_stale_records(&self, submitted_by: i64) -> Result<(), BatchTrackingStoreError>;
+    fn dead_letter_batch(
+        &self,
+        id: &str,
+        service_id: &str,
+        reason: &str,
+    ) -> Result<(), BatchTrackingStoreError> {
+        (**self).dead_letter_batch(id, service_id, reason)
+    }
 
-    /// Gets batches that have not yet been submitted from the underlying storage
-    fn get_unsubmitted_batches(&self) -> Result<TrackingBatchList, BatchTrackingStoreError>;
+    fn get_dead_lettered_batches(
+        &self,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        (**self).get_dead_lettered_batches(service_id)
+    }
 
-    /// Gets batches that failed either due to validation or submission errors
-    /// from the underlying storage
-    fn get_failed_batches(&self) -> Result<TrackingBatchList, BatchTrackingStoreError>;
-}
+    fn content_digest(&self, service_id: &str) -> Result<String, BatchTrackingStoreError> {
+        (**self).content_digest(service_id)
+    }
 
-impl<BS> BatchTrackingStore for Box<BS>
-where
-    BS: BatchTrackingStore + ?Sized,
-{
-    fn get_batch_status(
+    fn get_batches_awaiting_receipts(
+        &self,
+        older_than: i64,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        (**self).get_batches_awaiting_receipts(older_than, service_id)
+    }
+
+    fn failed_batches_cursor(
+        &self,
+        service_id: &str,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        (**self).failed_batches_cursor(service_id, after, limit)
+    }
+
+    fn remap_data_change_ids(
+        &self,
+        mapping: HashMap<String, String>,
+        service_id: &str,
+    ) -> Result<usize, BatchTrackingStoreError> {
+        (**self).remap_data_change_ids(mapping, service_id)
+    }
+
+    fn list_batches_by_family(
+        &self,
+        family_name: &str,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        (**self).list_batches_by_family(family_name, service_id)
+    }
+
+    fn find_batches_with_excess_receipts(
+        &self,
+        service_id: &str,
+    ) -> Result<Vec<String>, BatchTrackingStoreError> {
+        (**self).find_batches_with_excess_receipts(service_id)
+    }
+
+    fn dedupe_receipts(
         &self,
         id: &str,
         service_id: &str,
-    ) -> Result<Option<BatchStatus>, BatchTrackingStoreError> {
-        (**self).get_batch_status(id, service_id)
+    ) -> Result<usize, BatchTrackingStoreError> {
+        (**self).dedupe_receipts(id, service_id)
     }
 
-    fn update_batch_status(
+    fn get_batch_status_at(
         &self,
         id: &str,
         service_id: &str,
-        status: Option<BatchStatus>,
-        transaction_receipts: Vec<TransactionReceipt>,
-        submission_error: Option<SubmissionError>,
-    ) -> Result<(), BatchTrackingStoreError> {
-        (**self).update_batch_status(
-            id,
-            service_id,
-            status,
-            transaction_receipts,
-            submission_error,
-        )
+        at: i64,
+    ) -> Result<Option<BatchStatusName>, BatchTrackingStoreError> {
+        (**self).get_batch_status_at(id, service_id, at)
     }
 
-    fn add_batches(&self, batches: Vec<TrackingBatch>) -> Result<(), BatchTrackingStoreError> {
-        (**self).add_batches(batches)
+    fn list_batches_by_submit_url(
+        &self,
+        submit_url: &str,
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        (**self).list_batches_by_submit_url(submit_url, service_id)
     }
 
-    fn change_batch_to_submitted(
+    fn find_duplicate_dcids(
         &self,
-        batch_id: &str,
         service_id: &str,
-        transaction_receipts: Vec<TransactionReceipt>,
-        dlt_status: Option<&str>,
-        submission_error: Option<SubmissionError>,
-    ) -> Result<(), BatchTrackingStoreError> {
-        (**self).change_batch_to_submitted(
-            batch_id,
-            service_id,
-            transaction_receipts,
-            dlt_status,
-            submission_error,
-        )
+    ) -> Result<Vec<(String, Vec<String>)>, BatchTrackingStoreError> {
+        (**self).find_duplicate_dcids(service_id)
     }
 
-    fn get_batch(
+    fn count_batches_by_origin(
         &self,
-        id: &str,
         service_id: &str,
-    ) -> Result<Option<TrackingBatch>, BatchTrackingStoreError> {
-        (**self).get_batch(id, service_id)
+        since: i64,
+    ) -> Result<HashMap<String, i64>, BatchTrackingStoreError> {
+        (**self).count_batches_by_origin(service_id, since)
     }
 
-    fn list_batches_by_status(
+    fn id_status_page(
         &self,
-        status: BatchStatus,
+        service_id: &str,
+        after: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<(String, BatchStatusName)>, BatchTrackingStoreError> {
+        (**self).id_status_page(service_id, after, limit)
+    }
+
+    fn list_batches_depending_on(
+        &self,
+        transaction_id: &str,
+        service_id: &str,
     ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
-        (**self).list_batches_by_status(status)
+        (**self).list_batches_depending_on(transaction_id, service_id)
     }
 
-    fn clean_stale_records(&self, submitted_by: i64) -> Result<(), BatchTrackingStoreError> {
-        (**self).clean_stale_records(submitted_by)
+    fn tombstone_batches(
+        &self,
+        ids: &[&str],
+        service_id: &str,
+    ) -> Result<usize, BatchTrackingStoreError> {
+        (**self).tombstone_batches(ids, service_id)
     }
 
-    fn get_unsubmitted_batches(&self) -> Result<TrackingBatchList, BatchTrackingStoreError> {
-        (**self).get_unsubmitted_batches()
+    fn submission_success_rate(
+        &self,
+        service_id: &str,
+        start: i64,
+        end: i64,
+    ) -> Result<f64, BatchTrackingStoreError> {
+        (**self).submission_success_rate(service_id, start, end)
     }
 
-    fn get_failed_batches(&self) -> Result<TrackingBatchList, BatchTrackingStoreError> {
-        (**self).get_failed_batches()
+    fn find_batches_without_transactions(
+        &self,
+        service_id: &str,
+    ) -> Result<Vec<String>, BatchTrackingStoreError> {
+        (**self).find_batches_without_transactions(service_id)
+    }
+
+    fn claim_unsubmitted_batches(
+        &self,
+        service_id: &str,
+        claimant: &str,
+        n: i64,
+        lease_secs: u64,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        (**self).claim_unsubmitted_batches(service_id, claimant, n, lease_secs)
+    }
+
+    fn health_snapshot(
+        &self,
+        service_id: &str,
+        stuck_threshold: i64,
+    ) -> Result<HealthSnapshot, BatchTrackingStoreError> {
+        (**self).health_snapshot(service_id, stuck_threshold)
+    }
+
+    fn hourly_batch_counts(
+        &self,
+        service_id: &str,
+        start: i64,
+        end: i64,
+    ) -> Result<Vec<(i64, i64)>, BatchTrackingStoreError> {
+        (**self).hourly_batch_counts(service_id, start, end)
+    }
+
+    fn fetch_outbox(&self, limit: i64) -> Result<Vec<OutboxEvent>, BatchTrackingStoreError> {
+        (**self).fetch_outbox(limit)
+    }
+
+    fn ack_outbox(&self, ids: &[i64]) -> Result<usize, BatchTrackingStoreError> {
+        (**self).ack_outbox(ids)
+    }
+
+    fn dead_letter_batches(
+        &self,
+        ids: &[&str],
+        service_id: &str,
+        reason: &str,
+    ) -> Result<usize, BatchTrackingStoreError> {
+        (**self).dead_letter_batches(ids, service_id, reason)
+    }
+
+    fn list_batches_by_labels(
+        &self,
+        predicates: &[(String, String)],
+        service_id: &str,
+    ) -> Result<TrackingBatchList, BatchTrackingStoreError> {
+        (**self).list_batches_by_labels(predicates, service_id)
+    }
+
+    fn get_latest_submission_error(
+        &self,
+        id: &str,
+        service_id: &str,
+    ) -> Result<Option<SubmissionError>, BatchTrackingStoreError> {
+        (**self).get_latest_submission_error(id, service_id)
+    }
+
+    fn transition_batches(
+        &self,
+        transitions: Vec<(String, BatchStatus)>,
+        service_id: &str,
+    ) -> Result<(), BatchTrackingStoreError> {
+        (**self).transition_batches(transitions, service_id)
+    }
+
+    fn write_service_backup(
+        &self,
+        service_id: &str,
+        writer: &mut dyn Write,
+    ) -> Result<(), BatchTrackingStoreError> {
+        (**self).write_service_backup(service_id, writer)
+    }
+
+    fn read_service_backup(&self, reader: &mut dyn Read) -> Result<(), BatchTrackingStoreError> {
+        (**self).read_service_backup(reader)
     }
 }
 
@@ -1146,6 +3686,16 @@ mod tests {
             serialized_batch: Vec::new(),
             submitted: false,
             created_at: 000,
+            submission_latency_ms: None,
+            notes: None,
+            submission_round: None,
+            byte_size: 0,
+            network_id: None,
+            alias: None,
+            batch_kind: None,
+            origin: None,
+            metadata: None,
+            dead_letter_reason: None,
             transactions: Vec::new(),
             batch_status: None,
             submission_error: None,
@@ -1160,6 +3710,16 @@ mod tests {
             serialized_batch: Vec::new(),
             submitted: false,
             created_at: 000,
+            submission_latency_ms: None,
+            notes: None,
+            submission_round: None,
+            byte_size: 0,
+            network_id: None,
+            alias: None,
+            batch_kind: None,
+            origin: None,
+            metadata: None,
+            dead_letter_reason: None,
             transactions: Vec::new(),
             batch_status: None,
             submission_error: None,
@@ -1196,6 +3756,16 @@ mod tests {
             serialized_batch: Vec::new(),
             submitted: false,
             created_at: 000,
+            submission_latency_ms: None,
+            notes: None,
+            submission_round: None,
+            byte_size: 0,
+            network_id: None,
+            alias: None,
+            batch_kind: None,
+            origin: None,
+            metadata: None,
+            dead_letter_reason: None,
             transactions: Vec::new(),
             batch_status: None,
             submission_error: None,
@@ -1210,6 +3780,16 @@ mod tests {
             serialized_batch: Vec::new(),
             submitted: false,
             created_at: 000,
+            submission_latency_ms: None,
+            notes: None,
+            submission_round: None,
+            byte_size: 0,
+            network_id: None,
+            alias: None,
+            batch_kind: None,
+            origin: None,
+            metadata: None,
+            dead_letter_reason: None,
             transactions: Vec::new(),
             batch_status: None,
             submission_error: None,
@@ -1234,4 +3814,101 @@ mod tests {
         assert_eq!(test_batch, expected);
         assert!(GlobalTrackingBatch::try_from(tracking_batch_w_service).is_err());
     }
+
+    /// Verify that the `Delayed` status converts to and from its string form
+    #[test]
+    fn test_delayed_status_string_round_trip() {
+        assert_eq!(BatchStatus::Delayed.to_string(), "Delayed");
+        assert_eq!(BatchStatusName::Delayed.to_string(), "Delayed");
+        assert_eq!(
+            BatchStatusName::try_from_string(&BatchStatus::Delayed.to_string())
+                .expect("Failed to parse status"),
+            BatchStatusName::Delayed
+        );
+    }
+
+    /// Verify that receipts built from identical builders compare equal and
+    /// that the builders themselves can be cloned and compared
+    #[test]
+    fn test_identically_built_receipts_are_equal() {
+        let builder = TransactionReceiptBuilder::default()
+            .with_transaction_id("txn_id".to_string())
+            .with_result_valid(true)
+            .with_serialized_receipt("receipt".to_string());
+        let cloned_builder = builder.clone();
+
+        assert_eq!(builder, cloned_builder);
+
+        let receipt = builder.build().expect("Failed to build receipt");
+        let other_receipt = cloned_builder.build().expect("Failed to build receipt");
+
+        assert_eq!(receipt, other_receipt);
+        assert_ne!(
+            receipt,
+            TransactionReceiptBuilder::default()
+                .with_transaction_id("other_txn_id".to_string())
+                .with_result_valid(true)
+                .with_serialized_receipt("receipt".to_string())
+                .build()
+                .expect("Failed to build receipt")
+        );
+    }
+
+    #[test]
+    /// Test that a batch list is written as CSV with a header row, and that a
+    /// field containing a comma or quote is quoted
+    fn test_tracking_batch_list_to_csv() {
+        let batch = TrackingBatch {
+            service_id: Some("12345-67890::abcd".to_string()),
+            batch_header: "abc123".to_string(),
+            data_change_id: Some("dcid:order,1".to_string()),
+            signer_public_key: "xxx".to_string(),
+            trace: false,
+            serialized_batch: Vec::new(),
+            submitted: true,
+            created_at: 100,
+            submission_latency_ms: None,
+            notes: None,
+            submission_round: None,
+            byte_size: 0,
+            network_id: None,
+            alias: None,
+            batch_kind: None,
+            origin: None,
+            metadata: None,
+            dead_letter_reason: None,
+            transactions: Vec::new(),
+            batch_status: Some(BatchStatus::Pending),
+            submission_error: None,
+        };
+
+        let other_batch = TrackingBatch {
+            batch_header: "def456".to_string(),
+            data_change_id: Some("dcid:\"quoted\"".to_string()),
+            submitted: false,
+            created_at: 200,
+            batch_status: None,
+            ..batch.clone()
+        };
+
+        let unquoted_batch = TrackingBatch {
+            batch_header: "ghi789".to_string(),
+            data_change_id: None,
+            created_at: 300,
+            batch_status: Some(BatchStatus::Delayed),
+            ..batch.clone()
+        };
+
+        let list = TrackingBatchList {
+            batches: vec![batch, other_batch, unquoted_batch],
+        };
+
+        assert_eq!(
+            list.to_csv(),
+            "batch_id,service_id,status,submitted,created_at,data_change_id\n\
+             abc123,12345-67890::abcd,Pending,true,100,\"dcid:order,1\"\n\
+             def456,12345-67890::abcd,,false,200,\"dcid:\"\"quoted\"\"\"\n\
+             ghi789,12345-67890::abcd,Delayed,true,300,\n"
+        );
+    }
 }
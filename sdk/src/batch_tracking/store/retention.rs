@@ -0,0 +1,151 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A background task that periodically removes stale records from a store.
+
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::error::InternalError;
+
+use super::{BatchTrackingStore, BatchTrackingStoreError, TimestampPrecision};
+
+/// Determines which records a retention task removes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    max_age: Duration,
+    timestamp_precision: TimestampPrecision,
+}
+
+impl RetentionPolicy {
+    /// Creates a policy that removes records older than `max_age`
+    ///
+    /// # Arguments
+    ///
+    ///  * `max_age` - How long records are kept before they are removed
+    pub fn new(max_age: Duration) -> Self {
+        RetentionPolicy {
+            max_age,
+            timestamp_precision: TimestampPrecision::Seconds,
+        }
+    }
+
+    /// Sets the timestamp precision of the store the policy is applied to
+    ///
+    /// # Arguments
+    ///
+    ///  * `timestamp_precision`: the unit the store records timestamps in
+    pub fn with_timestamp_precision(mut self, timestamp_precision: TimestampPrecision) -> Self {
+        self.timestamp_precision = timestamp_precision;
+        self
+    }
+
+    pub fn max_age(&self) -> Duration {
+        self.max_age
+    }
+
+    pub fn timestamp_precision(&self) -> TimestampPrecision {
+        self.timestamp_precision
+    }
+
+    /// Returns the timestamp before which records are stale, in the policy's
+    /// timestamp precision
+    fn cutoff(&self) -> Result<i64, BatchTrackingStoreError> {
+        let cutoff = SystemTime::now()
+            .checked_sub(self.max_age)
+            .unwrap_or(UNIX_EPOCH)
+            .duration_since(UNIX_EPOCH)
+            .map_err(|err| {
+                BatchTrackingStoreError::InternalError(InternalError::from_source(Box::new(err)))
+            })?;
+
+        match self.timestamp_precision {
+            TimestampPrecision::Seconds => Ok(cutoff.as_secs() as i64),
+            TimestampPrecision::Milliseconds => Ok(cutoff.as_millis() as i64),
+        }
+    }
+}
+
+/// A handle to a running retention task
+pub struct RetentionTaskHandle {
+    sender: Sender<()>,
+    join_handle: thread::JoinHandle<()>,
+}
+
+impl RetentionTaskHandle {
+    /// Stops the retention task and waits for its thread to exit
+    ///
+    /// A clean that is in progress is finished before the task stops.
+    pub fn stop(self) -> Result<(), BatchTrackingStoreError> {
+        // An error means the thread has already exited, which join reports
+        let _ = self.sender.send(());
+
+        self.join_handle.join().map_err(|err| {
+            BatchTrackingStoreError::InternalError(InternalError::with_message(format!(
+                "Retention task thread did not shutdown correctly: {:?}",
+                err
+            )))
+        })
+    }
+}
+
+/// Starts a background thread that removes stale records from a store
+///
+/// Every `interval`, starting immediately, the task calls
+/// `clean_stale_records` with the cutoff given by `policy`. An error from a
+/// clean is passed to `on_error` and the task keeps running.
+///
+/// # Arguments
+///
+///  * `store` - The store to remove stale records from
+///  * `policy` - Determines which records are stale
+///  * `interval` - How long to wait between cleans
+///  * `on_error` - Called with each error raised while cleaning
+pub fn spawn_retention_task<S, F>(
+    store: S,
+    policy: RetentionPolicy,
+    interval: Duration,
+    on_error: F,
+) -> Result<RetentionTaskHandle, BatchTrackingStoreError>
+where
+    S: BatchTrackingStore + Send + 'static,
+    F: Fn(BatchTrackingStoreError) + Send + 'static,
+{
+    let (sender, receiver) = channel();
+
+    let join_handle = thread::Builder::new()
+        .name("Batch Tracking Retention".into())
+        .spawn(move || loop {
+            if let Err(err) = policy
+                .cutoff()
+                .and_then(|cutoff| store.clean_stale_records(cutoff))
+            {
+                on_error(err);
+            }
+
+            match receiver.recv_timeout(interval) {
+                Err(RecvTimeoutError::Timeout) => (),
+                Ok(()) | Err(RecvTimeoutError::Disconnected) => break,
+            }
+        })
+        .map_err(|err| {
+            BatchTrackingStoreError::InternalError(InternalError::from_source(Box::new(err)))
+        })?;
+
+    Ok(RetentionTaskHandle {
+        sender,
+        join_handle,
+    })
+}
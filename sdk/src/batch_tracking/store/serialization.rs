@@ -0,0 +1,144 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Compact binary representation of a `TrackingBatch`, suitable for caching.
+//!
+//! The encoded form is a single version byte followed by the bincode
+//! encoding of the batch. The version byte must be incremented whenever the
+//! layout of `TrackingBatch` changes, so stale cache entries are rejected
+//! rather than decoded incorrectly.
+
+use super::{TrackingBatch, TrackingBatchSerializationError};
+
+const FORMAT_VERSION: u8 = 14;
+
+impl TrackingBatch {
+    /// Serializes the batch to its versioned binary representation
+    pub fn to_bytes(&self) -> Result<Vec<u8>, TrackingBatchSerializationError> {
+        let mut bytes = vec![FORMAT_VERSION];
+        bincode::serialize_into(&mut bytes, self)
+            .map_err(TrackingBatchSerializationError::BincodeError)?;
+
+        Ok(bytes)
+    }
+
+    /// Deserializes a batch from bytes created by `to_bytes`
+    ///
+    /// # Arguments
+    ///
+    ///  * `bytes` - The versioned binary representation of the batch
+    pub fn from_bytes(bytes: &[u8]) -> Result<TrackingBatch, TrackingBatchSerializationError> {
+        let (version, body) = bytes
+            .split_first()
+            .ok_or(TrackingBatchSerializationError::MissingVersion)?;
+
+        if *version != FORMAT_VERSION {
+            return Err(TrackingBatchSerializationError::UnsupportedVersion(
+                *version,
+            ));
+        }
+
+        bincode::deserialize(body).map_err(TrackingBatchSerializationError::BincodeError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::batch_tracking::store::{BatchStatus, SubmissionError};
+
+    #[test]
+    fn test_tracking_batch_bytes_round_trip() {
+        let batch = TrackingBatch {
+            service_id: Some("12345-67890::abcd".to_string()),
+            batch_header: "abc123".to_string(),
+            data_change_id: Some("dcid:abc".to_string()),
+            signer_public_key: "xxx".to_string(),
+            trace: false,
+            serialized_batch: vec![1, 2, 3],
+            submitted: true,
+            created_at: 100,
+            submission_latency_ms: None,
+            notes: None,
+            submission_round: None,
+            byte_size: 0,
+            network_id: None,
+            alias: None,
+            batch_kind: None,
+            origin: None,
+            metadata: Some(serde_json::json!({ "origin": { "system": "erp", "ids": [1, 2] } })),
+            dead_letter_reason: None,
+            transactions: Vec::new(),
+            batch_status: Some(BatchStatus::Pending),
+            submission_error: Some(SubmissionError {
+                error_type: "test".to_string(),
+                error_message: "test message".to_string(),
+            }),
+        };
+
+        let bytes = batch.to_bytes().expect("Failed to serialize batch");
+
+        assert_eq!(bytes[0], FORMAT_VERSION);
+        assert_eq!(
+            TrackingBatch::from_bytes(&bytes).expect("Failed to deserialize batch"),
+            batch
+        );
+    }
+
+    #[test]
+    fn test_tracking_batch_bytes_version_mismatch() {
+        let batch = TrackingBatch {
+            service_id: Some("12345-67890::abcd".to_string()),
+            batch_header: "abc123".to_string(),
+            data_change_id: None,
+            signer_public_key: "xxx".to_string(),
+            trace: false,
+            serialized_batch: vec![1, 2, 3],
+            submitted: false,
+            created_at: 100,
+            submission_latency_ms: None,
+            notes: None,
+            submission_round: None,
+            byte_size: 0,
+            network_id: None,
+            alias: None,
+            batch_kind: None,
+            origin: None,
+            metadata: None,
+            dead_letter_reason: None,
+            transactions: Vec::new(),
+            batch_status: None,
+            submission_error: None,
+        };
+
+        let mut bytes = batch.to_bytes().expect("Failed to serialize batch");
+        bytes[0] = FORMAT_VERSION + 1;
+
+        let err = TrackingBatch::from_bytes(&bytes).unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "Unsupported serialized batch version: {}",
+                FORMAT_VERSION + 1
+            )
+        );
+
+        assert!(matches!(
+            TrackingBatch::from_bytes(&[]),
+            Err(TrackingBatchSerializationError::MissingVersion)
+        ));
+    }
+}
@@ -0,0 +1,320 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Comparison of the batches held by two stores, such as when copying batches
+//! from one database to another, and of a batch with the transact batch it
+//! was built from.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use transact::protocol::batch::{Batch, BatchHeader};
+use transact::protos::FromBytes;
+
+use crate::hex::to_hex;
+
+use super::{BatchTrackingStore, BatchTrackingStoreError, TrackingBatch, ValidationError};
+
+/// A batch that is present in both stores but differs between them
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BatchDifference {
+    batch_id: String,
+    fields: Vec<String>,
+}
+
+impl BatchDifference {
+    pub fn batch_id(&self) -> &str {
+        &self.batch_id
+    }
+
+    /// The names of the fields that differ
+    pub fn fields(&self) -> &[String] {
+        &self.fields
+    }
+}
+
+/// The result of comparing the batches for a service in two stores
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MigrationReport {
+    missing_from_destination: Vec<String>,
+    missing_from_source: Vec<String>,
+    differences: Vec<BatchDifference>,
+}
+
+impl MigrationReport {
+    /// IDs of the batches present only in the source store
+    pub fn missing_from_destination(&self) -> &[String] {
+        &self.missing_from_destination
+    }
+
+    /// IDs of the batches present only in the destination store
+    pub fn missing_from_source(&self) -> &[String] {
+        &self.missing_from_source
+    }
+
+    /// Batches present in both stores whose fields differ
+    pub fn differences(&self) -> &[BatchDifference] {
+        &self.differences
+    }
+
+    /// Returns true if both stores hold the same batches
+    pub fn is_complete(&self) -> bool {
+        self.missing_from_destination.is_empty()
+            && self.missing_from_source.is_empty()
+            && self.differences.is_empty()
+    }
+}
+
+/// Compares the batches for a service in two stores
+///
+/// Batches are matched by ID. Creation times and submission latencies are
+/// not compared, as they are recorded by the store a batch is added to.
+///
+/// # Arguments
+///
+///  * `src` - The store batches were copied from
+///  * `dst` - The store batches were copied to
+///  * `service_id` - The service ID of the batches to compare
+pub fn verify_migration(
+    src: &dyn BatchTrackingStore,
+    dst: &dyn BatchTrackingStore,
+    service_id: &str,
+) -> Result<MigrationReport, BatchTrackingStoreError> {
+    let src_batches = by_id(src.list_batches(service_id)?.batches);
+    let mut dst_batches = by_id(dst.list_batches(service_id)?.batches);
+
+    let mut report = MigrationReport::default();
+
+    for (batch_id, src_batch) in src_batches {
+        match dst_batches.remove(&batch_id) {
+            Some(dst_batch) => {
+                let fields = differing_fields(&src_batch, &dst_batch);
+                if !fields.is_empty() {
+                    report
+                        .differences
+                        .push(BatchDifference { batch_id, fields });
+                }
+            }
+            None => report.missing_from_destination.push(batch_id),
+        }
+    }
+
+    report.missing_from_source = dst_batches.into_keys().collect();
+
+    Ok(report)
+}
+
+fn by_id(batches: Vec<TrackingBatch>) -> BTreeMap<String, TrackingBatch> {
+    batches
+        .into_iter()
+        .map(|batch| (batch.batch_header().to_string(), batch))
+        .collect()
+}
+
+fn differing_fields(src: &TrackingBatch, dst: &TrackingBatch) -> Vec<String> {
+    let mut fields = Vec::new();
+
+    if src.data_change_id() != dst.data_change_id() {
+        fields.push("data_change_id".to_string());
+    }
+    if src.signer_public_key() != dst.signer_public_key() {
+        fields.push("signer_public_key".to_string());
+    }
+    if src.trace() != dst.trace() {
+        fields.push("trace".to_string());
+    }
+    if src.serialized_batch() != dst.serialized_batch() {
+        fields.push("serialized_batch".to_string());
+    }
+    if src.submitted() != dst.submitted() {
+        fields.push("submitted".to_string());
+    }
+    if src.transactions() != dst.transactions() {
+        fields.push("transactions".to_string());
+    }
+    if src.batch_status() != dst.batch_status() {
+        fields.push("batch_status".to_string());
+    }
+    if src.submission_error() != dst.submission_error() {
+        fields.push("submission_error".to_string());
+    }
+    if src.notes() != dst.notes() {
+        fields.push("notes".to_string());
+    }
+    if src.submission_round() != dst.submission_round() {
+        fields.push("submission_round".to_string());
+    }
+    if src.byte_size() != dst.byte_size() {
+        fields.push("byte_size".to_string());
+    }
+    if src.network_id() != dst.network_id() {
+        fields.push("network_id".to_string());
+    }
+    if src.alias() != dst.alias() {
+        fields.push("alias".to_string());
+    }
+    if src.batch_kind() != dst.batch_kind() {
+        fields.push("batch_kind".to_string());
+    }
+    if src.origin() != dst.origin() {
+        fields.push("origin".to_string());
+    }
+    if src.metadata() != dst.metadata() {
+        fields.push("metadata".to_string());
+    }
+    if src.dead_letter_reason() != dst.dead_letter_reason() {
+        fields.push("dead_letter_reason".to_string());
+    }
+
+    fields
+}
+
+/// A way in which a tracking batch differs from the transact batch it is
+/// validated against
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BatchMismatch {
+    /// The batch IDs differ
+    BatchId { expected: String, found: String },
+    /// The serialized batch headers differ
+    Header,
+    /// The transact batch's header could not be parsed, so its signer could
+    /// not be compared
+    UnreadableHeader(String),
+    /// The batches have different signers
+    SignerPublicKey { expected: String, found: String },
+    /// The batches have different trace flags
+    Trace { expected: bool, found: bool },
+    /// The batches have different numbers of transactions
+    TransactionCount { expected: usize, found: usize },
+    /// A transaction in the transact batch is missing from the tracking
+    /// batch
+    MissingTransaction(String),
+    /// A transaction in the tracking batch is not in the transact batch
+    UnexpectedTransaction(String),
+}
+
+impl fmt::Display for BatchMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BatchMismatch::BatchId { expected, found } => {
+                write!(f, "batch ID is {}, expected {}", found, expected)
+            }
+            BatchMismatch::Header => write!(f, "serialized batch header differs"),
+            BatchMismatch::UnreadableHeader(err) => {
+                write!(f, "batch header could not be read: {}", err)
+            }
+            BatchMismatch::SignerPublicKey { expected, found } => {
+                write!(f, "signer public key is {}, expected {}", found, expected)
+            }
+            BatchMismatch::Trace { expected, found } => {
+                write!(f, "trace is {}, expected {}", found, expected)
+            }
+            BatchMismatch::TransactionCount { expected, found } => {
+                write!(f, "batch has {} transactions, expected {}", found, expected)
+            }
+            BatchMismatch::MissingTransaction(id) => {
+                write!(f, "transaction {} is missing", id)
+            }
+            BatchMismatch::UnexpectedTransaction(id) => {
+                write!(f, "transaction {} is not in the batch", id)
+            }
+        }
+    }
+}
+
+/// Checks that a tracking batch matches the transact batch it was built from
+///
+/// The batch ID, serialized header, trace flag, signer, transaction count and
+/// transaction IDs are compared. The tracking batch's signer public key is
+/// expected to be the key that signed the transact batch. All mismatches are
+/// reported, not just the first.
+///
+/// # Arguments
+///
+///  * `tracking` - The tracking batch to validate
+///  * `batch` - The transact batch the tracking batch is expected to match
+pub fn validate_against_batch(
+    tracking: &TrackingBatch,
+    batch: &Batch,
+) -> Result<(), ValidationError> {
+    let mut mismatches = Vec::new();
+
+    if tracking.batch_header() != batch.header_signature() {
+        mismatches.push(BatchMismatch::BatchId {
+            expected: batch.header_signature().to_string(),
+            found: tracking.batch_header().to_string(),
+        });
+    }
+
+    if tracking.serialized_batch() != batch.header() {
+        mismatches.push(BatchMismatch::Header);
+    }
+
+    match BatchHeader::from_bytes(batch.header()) {
+        Ok(header) => {
+            let signer_public_key = to_hex(header.signer_public_key());
+            if tracking.signer_public_key() != signer_public_key {
+                mismatches.push(BatchMismatch::SignerPublicKey {
+                    expected: signer_public_key,
+                    found: tracking.signer_public_key().to_string(),
+                });
+            }
+        }
+        Err(err) => mismatches.push(BatchMismatch::UnreadableHeader(err.to_string())),
+    }
+
+    if tracking.trace() != batch.trace() {
+        mismatches.push(BatchMismatch::Trace {
+            expected: batch.trace(),
+            found: tracking.trace(),
+        });
+    }
+
+    if tracking.transactions().len() != batch.transactions().len() {
+        mismatches.push(BatchMismatch::TransactionCount {
+            expected: batch.transactions().len(),
+            found: tracking.transactions().len(),
+        });
+    }
+
+    // Stored transactions are not kept in batch order, so only the sets of
+    // transaction IDs are compared
+    let tracking_ids: BTreeSet<&str> = tracking
+        .transactions()
+        .iter()
+        .map(|txn| txn.transaction_header())
+        .collect();
+    let batch_ids: BTreeSet<&str> = batch
+        .transactions()
+        .iter()
+        .map(|txn| txn.header_signature())
+        .collect();
+
+    mismatches.extend(
+        batch_ids
+            .difference(&tracking_ids)
+            .map(|id| BatchMismatch::MissingTransaction(id.to_string())),
+    );
+    mismatches.extend(
+        tracking_ids
+            .difference(&batch_ids)
+            .map(|id| BatchMismatch::UnexpectedTransaction(id.to_string())),
+    );
+
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(ValidationError::new(mismatches))
+    }
+}
@@ -0,0 +1,261 @@
+// Copyright 2022 Cargill Incorporated
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Subscriptions to batches as they are added to the store.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+use super::TrackingBatch;
+
+/// The number of batches a subscriber's channel holds by default
+const DEFAULT_WATCH_CAPACITY: usize = 64;
+
+/// Determines what happens when a batch is sent to a subscriber whose
+/// channel is full
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WatchBackpressure {
+    /// The sender waits until the subscriber has received a batch
+    Block,
+    /// The oldest batch in the channel is discarded to make room
+    DropOldest,
+}
+
+struct Channel {
+    queue: Mutex<VecDeque<TrackingBatch>>,
+    not_empty: Condvar,
+    not_full: Condvar,
+    capacity: usize,
+    backpressure: WatchBackpressure,
+    /// Set when the receiver is dropped, so that senders stop waiting for it
+    closed: AtomicBool,
+    /// Set when the watchers are dropped, so that the receiver stops waiting
+    /// for batches
+    disconnected: AtomicBool,
+}
+
+impl Channel {
+    fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+
+    fn is_disconnected(&self) -> bool {
+        self.disconnected.load(Ordering::SeqCst)
+    }
+
+    fn send(&self, batch: TrackingBatch) {
+        let mut queue = match self.queue.lock() {
+            Ok(queue) => queue,
+            Err(_) => return,
+        };
+
+        while queue.len() >= self.capacity {
+            match self.backpressure {
+                WatchBackpressure::DropOldest => {
+                    queue.pop_front();
+                }
+                WatchBackpressure::Block => {
+                    // The flag is checked with the queue locked, and the
+                    // receiver takes the lock before notifying, so a receiver
+                    // dropped while waiting always wakes the sender
+                    if self.is_closed() {
+                        return;
+                    }
+                    queue = match self.not_full.wait(queue) {
+                        Ok(queue) => queue,
+                        Err(_) => return,
+                    };
+                }
+            }
+        }
+
+        queue.push_back(batch);
+        self.not_empty.notify_one();
+    }
+
+    fn pop(&self, queue: &mut VecDeque<TrackingBatch>) -> Option<TrackingBatch> {
+        let batch = queue.pop_front();
+        if batch.is_some() {
+            self.not_full.notify_one();
+        }
+        batch
+    }
+}
+
+/// Receives the unsubmitted batches added to the store for a service
+///
+/// Created by `BatchTrackingStore::watch_unsubmitted`. The subscription ends
+/// when the receiver is dropped, or when the store and every store sharing its
+/// watchers have been dropped.
+pub struct UnsubmittedBatchReceiver {
+    channel: Arc<Channel>,
+}
+
+impl UnsubmittedBatchReceiver {
+    /// Waits for the next batch
+    ///
+    /// Returns `None` once the store has been dropped and the batches already
+    /// sent have been received.
+    pub fn recv(&self) -> Option<TrackingBatch> {
+        let mut queue = self.channel.queue.lock().ok()?;
+        loop {
+            if let Some(batch) = self.channel.pop(&mut queue) {
+                return Some(batch);
+            }
+            if self.channel.is_disconnected() {
+                return None;
+            }
+            queue = self.channel.not_empty.wait(queue).ok()?;
+        }
+    }
+
+    /// Waits up to `timeout` for the next batch
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<TrackingBatch> {
+        let queue = self.channel.queue.lock().ok()?;
+        let (mut queue, _) = self
+            .channel
+            .not_empty
+            .wait_timeout_while(queue, timeout, |queue| {
+                queue.is_empty() && !self.channel.is_disconnected()
+            })
+            .ok()?;
+        self.channel.pop(&mut queue)
+    }
+
+    /// Returns the next batch if one is available, without waiting
+    pub fn try_recv(&self) -> Option<TrackingBatch> {
+        let mut queue = self.channel.queue.lock().ok()?;
+        self.channel.pop(&mut queue)
+    }
+}
+
+impl Drop for UnsubmittedBatchReceiver {
+    fn drop(&mut self) {
+        self.channel.closed.store(true, Ordering::SeqCst);
+        // Taking the lock ensures a sender that saw the channel open is
+        // waiting before it is notified
+        let _queue = self.channel.queue.lock();
+        self.channel.not_full.notify_all();
+    }
+}
+
+/// The subscribers to a store's unsubmitted batches
+pub(crate) struct UnsubmittedWatchers {
+    capacity: usize,
+    backpressure: WatchBackpressure,
+    subscribers: Mutex<Vec<(String, Arc<Channel>)>>,
+}
+
+impl UnsubmittedWatchers {
+    pub fn new(capacity: usize, backpressure: WatchBackpressure) -> Self {
+        UnsubmittedWatchers {
+            capacity: capacity.max(1),
+            backpressure,
+            subscribers: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Registers a subscriber for the given service's unsubmitted batches
+    pub fn subscribe(&self, service_id: &str) -> UnsubmittedBatchReceiver {
+        let channel = Arc::new(Channel {
+            queue: Mutex::new(VecDeque::new()),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+            capacity: self.capacity,
+            backpressure: self.backpressure,
+            closed: AtomicBool::new(false),
+            disconnected: AtomicBool::new(false),
+        });
+
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.push((service_id.to_string(), channel.clone()));
+        }
+
+        UnsubmittedBatchReceiver { channel }
+    }
+
+    /// Returns copies of the batches that have subscribers, so that they can
+    /// be sent once the batches have been committed
+    pub fn watched(&self, batches: &[TrackingBatch]) -> Vec<TrackingBatch> {
+        let subscribers = match self.subscribers.lock() {
+            Ok(subscribers) => subscribers,
+            Err(_) => return Vec::new(),
+        };
+
+        if subscribers.is_empty() {
+            return Vec::new();
+        }
+
+        batches
+            .iter()
+            .filter(|batch| {
+                !batch.submitted()
+                    && subscribers
+                        .iter()
+                        .any(|(service_id, _)| Some(service_id.as_str()) == batch.service_id())
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Sends the batches to their service's subscribers
+    pub fn send(&self, batches: Vec<TrackingBatch>) {
+        if batches.is_empty() {
+            return;
+        }
+
+        // Subscribers whose receiver has been dropped are removed, and the
+        // rest are copied so the lock is not held while sending
+        let subscribers: Vec<(String, Arc<Channel>)> = match self.subscribers.lock() {
+            Ok(mut subscribers) => {
+                subscribers.retain(|(_, channel)| !channel.is_closed());
+                subscribers.clone()
+            }
+            Err(_) => return,
+        };
+
+        for batch in batches {
+            for (service_id, channel) in &subscribers {
+                if Some(service_id.as_str()) == batch.service_id() {
+                    channel.send(batch.clone());
+                }
+            }
+        }
+    }
+}
+
+impl Drop for UnsubmittedWatchers {
+    fn drop(&mut self) {
+        let subscribers = match self.subscribers.get_mut() {
+            Ok(subscribers) => subscribers,
+            Err(_) => return,
+        };
+
+        for (_, channel) in subscribers.iter() {
+            channel.disconnected.store(true, Ordering::SeqCst);
+            // Taking the lock ensures a receiver that saw the channel
+            // connected is waiting before it is notified
+            let _queue = channel.queue.lock();
+            channel.not_empty.notify_all();
+        }
+    }
+}
+
+impl Default for UnsubmittedWatchers {
+    fn default() -> Self {
+        UnsubmittedWatchers::new(DEFAULT_WATCH_CAPACITY, WatchBackpressure::DropOldest)
+    }
+}
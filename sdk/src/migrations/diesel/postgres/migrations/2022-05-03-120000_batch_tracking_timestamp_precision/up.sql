@@ -0,0 +1,33 @@
+-- Copyright 2022 Cargill Incorporated
+--
+-- Licensed under the Apache License, Version 2.0 (the "License");
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+--     http://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an "AS IS" BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+-- limitations under the License.
+-- -----------------------------------------------------------------------------
+
+-- Timestamps may be recorded in milliseconds, which do not fit in an INTEGER
+ALTER TABLE batches ALTER COLUMN created_at TYPE BIGINT;
+ALTER TABLE batch_statuses ALTER COLUMN created_at TYPE BIGINT;
+ALTER TABLE batch_statuses ALTER COLUMN updated_at TYPE BIGINT;
+ALTER TABLE submissions ALTER COLUMN last_checked TYPE BIGINT;
+ALTER TABLE submissions ALTER COLUMN created_at TYPE BIGINT;
+ALTER TABLE submissions ALTER COLUMN updated_at TYPE BIGINT;
+ALTER TABLE batch_tombstones ALTER COLUMN created_at TYPE BIGINT;
+
+-- Timestamps are now supplied by the store so they can be recorded at the
+-- configured precision. The trigger only needs to count submission checks.
+CREATE OR REPLACE FUNCTION trigger_update_submission()
+RETURNS TRIGGER AS $$
+BEGIN
+  	NEW.times_checked = OLD.times_checked + 1;
+  	RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,52 @@
+-- Copyright 2022 Cargill Incorporated
+--
+-- Licensed under the Apache License, Version 2.0 (the "License");
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+--     http://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an "AS IS" BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+-- limitations under the License.
+-- -----------------------------------------------------------------------------
+
+ALTER TABLE batch_tombstones
+  ALTER COLUMN service_id TYPE VARCHAR(17),
+  ALTER COLUMN batch_id TYPE VARCHAR(128);
+
+ALTER TABLE batch_statuses
+  ALTER COLUMN service_id TYPE VARCHAR(17),
+  ALTER COLUMN batch_id TYPE VARCHAR(70),
+  ALTER COLUMN dlt_status TYPE VARCHAR(16);
+
+ALTER TABLE submissions
+  ALTER COLUMN service_id TYPE VARCHAR(17),
+  ALTER COLUMN batch_id TYPE VARCHAR(128),
+  ALTER COLUMN error_type TYPE VARCHAR(64);
+
+ALTER TABLE transaction_receipts
+  ALTER COLUMN service_id TYPE VARCHAR(17),
+  ALTER COLUMN transaction_id TYPE VARCHAR(128),
+  ALTER COLUMN external_status TYPE VARCHAR(16);
+
+ALTER TABLE transaction_addresses
+  ALTER COLUMN service_id TYPE VARCHAR(17),
+  ALTER COLUMN transaction_id TYPE VARCHAR(128),
+  ALTER COLUMN address TYPE VARCHAR(70);
+
+ALTER TABLE transactions
+  ALTER COLUMN service_id TYPE VARCHAR(17),
+  ALTER COLUMN transaction_id TYPE VARCHAR(128),
+  ALTER COLUMN batch_id TYPE VARCHAR(128),
+  ALTER COLUMN family_name TYPE VARCHAR(128),
+  ALTER COLUMN family_version TYPE VARCHAR(16),
+  ALTER COLUMN signer_public_key TYPE VARCHAR(70);
+
+ALTER TABLE batches
+  ALTER COLUMN service_id TYPE VARCHAR(17),
+  ALTER COLUMN batch_id TYPE VARCHAR(128),
+  ALTER COLUMN data_change_id TYPE VARCHAR(256),
+  ALTER COLUMN signer_public_key TYPE VARCHAR(70);
@@ -0,0 +1,55 @@
+-- Copyright 2022 Cargill Incorporated
+--
+-- Licensed under the Apache License, Version 2.0 (the "License");
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+--     http://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an "AS IS" BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+-- limitations under the License.
+-- -----------------------------------------------------------------------------
+
+-- Identifiers and names are stored as TEXT so that long values are stored
+-- as-is rather than rejected by a fixed column width. SQLite does not enforce
+-- VARCHAR widths, so no equivalent SQLite migration is required.
+ALTER TABLE batches
+  ALTER COLUMN service_id TYPE TEXT,
+  ALTER COLUMN batch_id TYPE TEXT,
+  ALTER COLUMN data_change_id TYPE TEXT,
+  ALTER COLUMN signer_public_key TYPE TEXT;
+
+ALTER TABLE transactions
+  ALTER COLUMN service_id TYPE TEXT,
+  ALTER COLUMN transaction_id TYPE TEXT,
+  ALTER COLUMN batch_id TYPE TEXT,
+  ALTER COLUMN family_name TYPE TEXT,
+  ALTER COLUMN family_version TYPE TEXT,
+  ALTER COLUMN signer_public_key TYPE TEXT;
+
+ALTER TABLE transaction_addresses
+  ALTER COLUMN service_id TYPE TEXT,
+  ALTER COLUMN transaction_id TYPE TEXT,
+  ALTER COLUMN address TYPE TEXT;
+
+ALTER TABLE transaction_receipts
+  ALTER COLUMN service_id TYPE TEXT,
+  ALTER COLUMN transaction_id TYPE TEXT,
+  ALTER COLUMN external_status TYPE TEXT;
+
+ALTER TABLE submissions
+  ALTER COLUMN service_id TYPE TEXT,
+  ALTER COLUMN batch_id TYPE TEXT,
+  ALTER COLUMN error_type TYPE TEXT;
+
+ALTER TABLE batch_statuses
+  ALTER COLUMN service_id TYPE TEXT,
+  ALTER COLUMN batch_id TYPE TEXT,
+  ALTER COLUMN dlt_status TYPE TEXT;
+
+ALTER TABLE batch_tombstones
+  ALTER COLUMN service_id TYPE TEXT,
+  ALTER COLUMN batch_id TYPE TEXT;
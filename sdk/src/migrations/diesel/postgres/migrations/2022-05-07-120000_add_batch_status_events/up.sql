@@ -0,0 +1,28 @@
+-- Copyright 2022 Cargill Incorporated
+--
+-- Licensed under the Apache License, Version 2.0 (the "License");
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+--     http://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an "AS IS" BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+-- limitations under the License.
+-- -----------------------------------------------------------------------------
+
+CREATE TABLE batch_status_events
+  (
+     id                BIGSERIAL PRIMARY KEY,
+     service_id        TEXT NOT NULL,
+     batch_id          TEXT NOT NULL,
+     dlt_status        TEXT NOT NULL,
+     correlation_id    TEXT,
+     created_at        BIGINT NOT NULL,
+     FOREIGN KEY (service_id, batch_id) REFERENCES batches(service_id, batch_id) ON DELETE CASCADE
+  );
+
+CREATE INDEX IF NOT EXISTS idx_batch_status_events_batch
+  ON batch_status_events (service_id, batch_id);
@@ -0,0 +1,27 @@
+-- Copyright 2022 Cargill Incorporated
+--
+-- Licensed under the Apache License, Version 2.0 (the "License");
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+--     http://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an "AS IS" BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+-- limitations under the License.
+-- -----------------------------------------------------------------------------
+
+CREATE TABLE transaction_dependencies
+  (
+     service_id         TEXT NOT NULL,
+     transaction_id     TEXT NOT NULL,
+     position           INTEGER NOT NULL,
+     dependency_id      TEXT NOT NULL,
+     FOREIGN KEY (service_id, transaction_id) REFERENCES transactions(service_id, transaction_id) ON DELETE CASCADE,
+     PRIMARY KEY (service_id, transaction_id, position)
+  );
+
+CREATE INDEX IF NOT EXISTS idx_transaction_dependencies_dependency_id
+  ON transaction_dependencies (service_id, dependency_id);
@@ -0,0 +1,27 @@
+-- Copyright 2022 Cargill Incorporated
+--
+-- Licensed under the Apache License, Version 2.0 (the "License");
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+--     http://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an "AS IS" BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+-- limitations under the License.
+-- -----------------------------------------------------------------------------
+
+DROP TRIGGER IF EXISTS set_submissions_times_checked;
+
+CREATE TRIGGER IF NOT EXISTS set_submissions_updated
+BEFORE UPDATE ON submissions
+FOR EACH ROW
+BEGIN
+    UPDATE submissions
+    SET updated_at = (cast(strftime('%s') as int)),
+        last_checked = (cast(strftime('%s') as int)),
+    	times_checked = times_checked + 1
+    WHERE rowid = NEW.rowid;
+END;
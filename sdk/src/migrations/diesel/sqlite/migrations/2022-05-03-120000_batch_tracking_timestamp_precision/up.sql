@@ -0,0 +1,27 @@
+-- Copyright 2022 Cargill Incorporated
+--
+-- Licensed under the Apache License, Version 2.0 (the "License");
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+--     http://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an "AS IS" BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+-- limitations under the License.
+-- -----------------------------------------------------------------------------
+
+-- Timestamps are now supplied by the store so they can be recorded at the
+-- configured precision. The trigger only needs to count submission checks.
+DROP TRIGGER IF EXISTS set_submissions_updated;
+
+CREATE TRIGGER IF NOT EXISTS set_submissions_times_checked
+AFTER UPDATE ON submissions
+FOR EACH ROW
+BEGIN
+    UPDATE submissions
+    SET times_checked = OLD.times_checked + 1
+    WHERE rowid = NEW.rowid;
+END;
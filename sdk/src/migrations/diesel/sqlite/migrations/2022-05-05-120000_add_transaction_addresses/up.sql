@@ -0,0 +1,28 @@
+-- Copyright 2022 Cargill Incorporated
+--
+-- Licensed under the Apache License, Version 2.0 (the "License");
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+--     http://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an "AS IS" BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+-- limitations under the License.
+-- -----------------------------------------------------------------------------
+
+CREATE TABLE transaction_addresses
+  (
+     service_id         VARCHAR(17) NOT NULL,
+     transaction_id     VARCHAR(128) NOT NULL,
+     is_input           BOOLEAN NOT NULL,
+     position           INTEGER NOT NULL,
+     address            VARCHAR(70) NOT NULL,
+     FOREIGN KEY (service_id, transaction_id) REFERENCES transactions(service_id, transaction_id) ON DELETE CASCADE,
+     PRIMARY KEY (service_id, transaction_id, is_input, position)
+  );
+
+CREATE INDEX IF NOT EXISTS idx_transaction_addresses_address
+  ON transaction_addresses (service_id, address);
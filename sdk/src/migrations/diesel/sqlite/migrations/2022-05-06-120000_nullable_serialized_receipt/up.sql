@@ -0,0 +1,54 @@
+-- Copyright 2022 Cargill Incorporated
+--
+-- Licensed under the Apache License, Version 2.0 (the "License");
+-- you may not use this file except in compliance with the License.
+-- You may obtain a copy of the License at
+--
+--     http://www.apache.org/licenses/LICENSE-2.0
+--
+-- Unless required by applicable law or agreed to in writing, software
+-- distributed under the License is distributed on an "AS IS" BASIS,
+-- WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+-- See the License for the specific language governing permissions and
+-- limitations under the License.
+-- -----------------------------------------------------------------------------
+
+-- Receipt payloads may be scrubbed while the receipt is kept. SQLite cannot
+-- drop a NOT NULL constraint, so the table is rebuilt.
+CREATE TABLE transaction_receipts_temp
+  (
+     service_id             VARCHAR(17) NOT NULL,
+     transaction_id         VARCHAR(128) NOT NULL,
+     result_valid           BOOLEAN NOT NULL,
+     error_message          TEXT,
+     error_data             BLOB,
+     serialized_receipt     BLOB,
+     external_status        VARCHAR(16),
+     external_error_message TEXT,
+     FOREIGN KEY (service_id, transaction_id) REFERENCES transactions(service_id, transaction_id) ON DELETE CASCADE,
+     PRIMARY KEY (service_id, transaction_id)
+  );
+
+INSERT INTO transaction_receipts_temp (
+    service_id,
+    transaction_id,
+    result_valid,
+    error_message,
+    error_data,
+    serialized_receipt,
+    external_status,
+    external_error_message
+) SELECT
+service_id,
+transaction_id,
+result_valid,
+error_message,
+error_data,
+serialized_receipt,
+external_status,
+external_error_message
+FROM transaction_receipts;
+
+DROP TABLE transaction_receipts;
+
+ALTER TABLE transaction_receipts_temp RENAME TO transaction_receipts;
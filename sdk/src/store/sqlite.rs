@@ -12,6 +12,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+#[cfg(feature = "batch-tracking")]
+use std::sync::Arc;
+
 use diesel::{
     connection::TransactionManager,
     r2d2::{ConnectionManager, Pool, PooledConnection},
@@ -21,8 +24,8 @@ use diesel::{
 
 #[cfg(feature = "batch-tracking")]
 use crate::batch_tracking::store::{
-    diesel::{DieselBatchTrackingStore, DieselConnectionBatchTrackingStore},
-    BatchTrackingStore,
+    BatchTrackingStore, DieselBatchTrackingStore, DieselConnectionBatchTrackingStore,
+    UnsubmittedWatchers,
 };
 #[cfg(feature = "batch-store")]
 use crate::batches::store::{BatchStore, DieselBatchStore, DieselConnectionBatchStore};
@@ -51,11 +54,19 @@ use super::{InContextStoreFactory, StoreFactory, TransactionalStoreFactory};
 #[derive(Clone)]
 pub struct SqliteStoreFactory {
     pool: Pool<ConnectionManager<SqliteConnection>>,
+    /// Shared by the batch tracking stores the factory creates, so that a
+    /// subscriber receives the batches added through any of them
+    #[cfg(feature = "batch-tracking")]
+    unsubmitted_watchers: Arc<UnsubmittedWatchers>,
 }
 
 impl<'a> SqliteStoreFactory {
     pub fn new(pool: Pool<ConnectionManager<SqliteConnection>>) -> Self {
-        Self { pool }
+        Self {
+            pool,
+            #[cfg(feature = "batch-tracking")]
+            unsubmitted_watchers: Arc::new(UnsubmittedWatchers::default()),
+        }
     }
 }
 
@@ -101,7 +112,10 @@ impl StoreFactory for SqliteStoreFactory {
 
     #[cfg(feature = "batch-tracking")]
     fn get_batch_tracking_store<'a>(&'a self) -> Box<dyn BatchTrackingStore + 'a> {
-        Box::new(DieselBatchTrackingStore::new(self.pool.clone()))
+        Box::new(
+            DieselBatchTrackingStore::new(self.pool.clone())
+                .with_unsubmitted_watchers(self.unsubmitted_watchers.clone()),
+        )
     }
 }
 
@@ -112,7 +126,11 @@ impl TransactionalStoreFactory for SqliteStoreFactory {
             .get()
             .map_err(|err| InternalError::from_source(Box::new(err)))?;
 
-        let store_factory = InContextSqliteStoreFactory::new(conn);
+        let store_factory = InContextSqliteStoreFactory::new(
+            conn,
+            #[cfg(feature = "batch-tracking")]
+            self.unsubmitted_watchers.clone(),
+        );
         store_factory
             .conn
             .transaction_manager()
@@ -128,11 +146,20 @@ impl TransactionalStoreFactory for SqliteStoreFactory {
 
 pub struct InContextSqliteStoreFactory {
     conn: PooledConnection<ConnectionManager<SqliteConnection>>,
+    #[cfg(feature = "batch-tracking")]
+    unsubmitted_watchers: Arc<UnsubmittedWatchers>,
 }
 
 impl InContextSqliteStoreFactory {
-    fn new(conn: PooledConnection<ConnectionManager<SqliteConnection>>) -> Self {
-        Self { conn }
+    fn new(
+        conn: PooledConnection<ConnectionManager<SqliteConnection>>,
+        #[cfg(feature = "batch-tracking")] unsubmitted_watchers: Arc<UnsubmittedWatchers>,
+    ) -> Self {
+        Self {
+            conn,
+            #[cfg(feature = "batch-tracking")]
+            unsubmitted_watchers,
+        }
     }
 }
 
@@ -178,7 +205,10 @@ impl StoreFactory for InContextSqliteStoreFactory {
 
     #[cfg(feature = "batch-tracking")]
     fn get_batch_tracking_store<'a>(&'a self) -> Box<dyn BatchTrackingStore + 'a> {
-        Box::new(DieselConnectionBatchTrackingStore::new(&*self.conn))
+        Box::new(
+            DieselConnectionBatchTrackingStore::new(&*self.conn)
+                .with_unsubmitted_watchers(self.unsubmitted_watchers.clone()),
+        )
     }
 }
 